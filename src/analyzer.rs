@@ -0,0 +1,489 @@
+//! Reconstructing EPP sessions from captures and frame logs
+//!
+//! After an incident, the question is usually what was sent when, what the registry answered
+//! and how long it took. A [`SessionAnalyzer`] is fed the traffic of one session, either as the
+//! decrypted byte streams of a packet capture ([`SessionAnalyzer::stream()`]) or as documents
+//! from a frame log ([`SessionAnalyzer::frame()`]). It pairs each command with its response
+//! and produces a [`SessionReport`]: a timeline of the exchanges, with per-command latencies,
+//! result codes and the extensions used.
+//!
+//! Requests are parsed with a model of the `<command>` envelope, reading the command verb,
+//! the object namespace, the extensions and the clTRID, so commands this crate has no types
+//! for are reported as well. Responses are parsed with [`ResponseStatus`].
+
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+
+use chrono::{DateTime, Duration, Utc};
+use instant_xml::{Deserializer, FromXml, Id, Kind};
+
+use crate::common::EPP_XMLNS;
+use crate::frame::{FrameDecoder, FrameError};
+use crate::response::{ResponseStatus, ResultCode};
+use crate::xml;
+
+/// Which peer sent some traffic
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Direction {
+    /// From the client to the registry
+    Request,
+    /// From the registry to the client
+    Response,
+}
+
+/// A command and its response, as seen on the wire
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Exchange {
+    /// The command verb, like `create` or `hello`
+    ///
+    /// `None` for frames the registry sent without a request: the greeting after connecting,
+    /// or responses to commands missing from the capture.
+    pub command: Option<String>,
+    /// The namespace of the object the command acts on, like
+    /// `urn:ietf:params:xml:ns:domain-1.0`
+    pub object: Option<String>,
+    /// The namespaces used in the `<extension>` of the command
+    pub extensions: Vec<String>,
+    pub client_tr_id: Option<String>,
+    pub server_tr_id: Option<String>,
+    /// When the command was sent
+    pub sent_at: Option<DateTime<Utc>>,
+    /// When the response was received, `None` if it is missing
+    pub received_at: Option<DateTime<Utc>>,
+    /// The primary result code, `None` for a greeting or a missing or unparsable response
+    pub code: Option<ResultCode>,
+}
+
+impl Exchange {
+    /// The time between sending the command and receiving the response
+    pub fn latency(&self) -> Option<Duration> {
+        Some(self.received_at? - self.sent_at?)
+    }
+}
+
+/// One line per exchange, like
+/// `2024-03-01T10:00:00.120Z create domain-1.0 [cltrid-1] 1000 in 120 ms`
+impl fmt::Display for Exchange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.sent_at.or(self.received_at) {
+            Some(at) => write!(
+                f,
+                "{}",
+                at.to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+            )?,
+            None => write!(f, "-")?,
+        }
+
+        write!(f, " {}", self.command.as_deref().unwrap_or("(unsolicited)"))?;
+        if let Some(object) = &self.object {
+            write!(f, " {}", short_namespace(object))?;
+        }
+        for extension in &self.extensions {
+            write!(f, " +{}", short_namespace(extension))?;
+        }
+        if let Some(id) = &self.client_tr_id {
+            write!(f, " [{id}]")?;
+        }
+
+        match (self.received_at, self.code) {
+            (None, _) => write!(f, " no response"),
+            (Some(_), Some(code)) => write!(f, " {}", code as u16),
+            (Some(_), None) => write!(f, " greeting"),
+        }?;
+
+        match self.latency() {
+            Some(latency) => write!(f, " in {} ms", latency.num_milliseconds()),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Rebuilds the exchanges of a single session from its traffic
+///
+/// Traffic has to be added in the order it was captured. Responses are paired with the
+/// oldest command still waiting for one, as EPP servers answer in order.
+#[derive(Debug, Default)]
+pub struct SessionAnalyzer {
+    requests: Stream,
+    responses: Stream,
+    pending: VecDeque<Exchange>,
+    exchanges: Vec<Exchange>,
+}
+
+#[derive(Debug, Default)]
+struct Stream {
+    decoder: FrameDecoder,
+    buf: Vec<u8>,
+}
+
+impl SessionAnalyzer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a chunk of the (decrypted) TCP payload sent in `direction` at `at`
+    ///
+    /// Frames are split out of the stream; a frame spanning several chunks gets the time of
+    /// the chunk completing it. After an error, the rest of that direction can't be decoded;
+    /// the frames completed before it are still added.
+    pub fn stream(
+        &mut self,
+        direction: Direction,
+        at: DateTime<Utc>,
+        bytes: &[u8],
+    ) -> Result<(), FrameError> {
+        let stream = match direction {
+            Direction::Request => &mut self.requests,
+            Direction::Response => &mut self.responses,
+        };
+
+        stream.buf.extend_from_slice(bytes);
+        let mut frames = Vec::new();
+        let result = loop {
+            match stream.decoder.decode(&mut stream.buf) {
+                Ok(Some(xml)) => frames.push(xml),
+                Ok(None) => break Ok(()),
+                Err(err) => break Err(err),
+            }
+        };
+
+        for xml in frames {
+            self.frame(direction, at, &xml);
+        }
+        result
+    }
+
+    /// Add a complete EPP document sent in `direction` at `at`
+    pub fn frame(&mut self, direction: Direction, at: DateTime<Utc>, xml: &str) {
+        match direction {
+            Direction::Request => self.pending.push_back(request(xml, at)),
+            Direction::Response => {
+                let mut exchange = self.pending.pop_front().unwrap_or_default();
+                exchange.received_at = Some(at);
+                if let Ok(status) = xml::deserialize::<ResponseStatus>(xml) {
+                    exchange.code = Some(status.result.primary().code);
+                    exchange.server_tr_id = Some(status.tr_ids.server_tr_id);
+                    if exchange.client_tr_id.is_none() {
+                        exchange.client_tr_id = status.tr_ids.client_tr_id;
+                    }
+                }
+                self.exchanges.push(exchange);
+            }
+        }
+    }
+
+    /// The exchanges in the order they were sent, followed by commands without a response
+    pub fn finish(mut self) -> SessionReport {
+        self.exchanges.extend(self.pending);
+        SessionReport {
+            exchanges: self.exchanges,
+        }
+    }
+}
+
+// The exchange for a request, with what can be read from it without knowing the command
+fn request(xml: &str, sent_at: DateTime<Utc>) -> Exchange {
+    let mut exchange = Exchange {
+        sent_at: Some(sent_at),
+        ..Exchange::default()
+    };
+
+    if let Ok(command) = xml::deserialize::<CommandRequest>(xml) {
+        exchange.command = Some(command.verb.name);
+        exchange.object = command.verb.object;
+        exchange.extensions = command.extension.map(|ext| ext.0).unwrap_or_default();
+        exchange.client_tr_id = command.client_tr_id.map(|id| id.trim().to_owned());
+    } else if xml::deserialize::<HelloRequest>(xml).is_ok() {
+        exchange.command = Some("hello".to_owned());
+    }
+
+    exchange
+}
+
+#[derive(Debug, FromXml)]
+#[xml(rename = "hello", ns(EPP_XMLNS))]
+struct HelloRequest;
+
+// The parts of a command the analyzer reads
+#[derive(Debug, FromXml)]
+#[xml(rename = "command", ns(EPP_XMLNS))]
+struct CommandRequest {
+    extension: Option<ExtensionNamespaces>,
+    #[xml(rename = "clTRID")]
+    client_tr_id: Option<String>,
+    verb: Verb,
+}
+
+// The element in `<command>` naming the command, like `<create>`
+#[derive(Debug)]
+struct Verb {
+    name: String,
+    // The namespace of its first child, unless that is an EPP element like the `<clID>` of a
+    // login
+    object: Option<String>,
+}
+
+impl<'xml> FromXml<'xml> for Verb {
+    fn matches(id: Id<'_>, _: Option<Id<'_>>) -> bool {
+        id.ns == EPP_XMLNS && id.name != "extension" && id.name != "clTRID"
+    }
+
+    fn deserialize<'cx>(
+        acc: &mut Self::Accumulator,
+        _: &'static str,
+        de: &mut Deserializer<'cx, 'xml>,
+    ) -> Result<(), instant_xml::Error> {
+        let mut verb = Self {
+            name: de.parent().name.to_owned(),
+            object: None,
+        };
+
+        while let Some(node) = de.next() {
+            if let instant_xml::de::Node::Open(element) = node? {
+                let id = de.element_id(&element)?;
+                if verb.object.is_none() && id.ns != EPP_XMLNS {
+                    verb.object = Some(id.ns.to_owned());
+                }
+                de.nested(element).ignore()?;
+            }
+        }
+
+        *acc = Some(verb);
+        Ok(())
+    }
+
+    type Accumulator = Option<Self>;
+    const KIND: Kind = Kind::Element;
+}
+
+// The namespaces of the elements in an `<extension>`
+#[derive(Debug)]
+struct ExtensionNamespaces(Vec<String>);
+
+impl<'xml> FromXml<'xml> for ExtensionNamespaces {
+    fn matches(id: Id<'_>, _: Option<Id<'_>>) -> bool {
+        id == Id {
+            ns: EPP_XMLNS,
+            name: "extension",
+        }
+    }
+
+    fn deserialize<'cx>(
+        acc: &mut Self::Accumulator,
+        _: &'static str,
+        de: &mut Deserializer<'cx, 'xml>,
+    ) -> Result<(), instant_xml::Error> {
+        let mut namespaces = Vec::new();
+        collect_namespaces(de, &mut namespaces)?;
+        *acc = Some(Self(namespaces));
+        Ok(())
+    }
+
+    type Accumulator = Option<Self>;
+    const KIND: Kind = Kind::Element;
+}
+
+fn collect_namespaces(
+    de: &mut Deserializer<'_, '_>,
+    namespaces: &mut Vec<String>,
+) -> Result<(), instant_xml::Error> {
+    while let Some(node) = de.next() {
+        if let instant_xml::de::Node::Open(element) = node? {
+            let ns = de.element_id(&element)?.ns;
+            if !namespaces.iter().any(|known| known == ns) {
+                namespaces.push(ns.to_owned());
+            }
+            collect_namespaces(&mut de.nested(element), namespaces)?;
+        }
+    }
+    Ok(())
+}
+
+// `domain-1.0` for `urn:ietf:params:xml:ns:domain-1.0`, other namespaces as they are
+fn short_namespace(uri: &str) -> &str {
+    uri.strip_prefix("urn:ietf:params:xml:ns:").unwrap_or(uri)
+}
+
+/// The exchanges of a session, see [`SessionAnalyzer`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SessionReport {
+    pub exchanges: Vec<Exchange>,
+}
+
+/// Statistics for a command verb in a [`SessionReport`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CommandStats {
+    pub count: usize,
+    /// Commands with an error result code
+    pub failed: usize,
+    /// Commands without a response
+    pub unanswered: usize,
+    pub median_latency: Option<Duration>,
+    pub max_latency: Option<Duration>,
+}
+
+impl SessionReport {
+    /// Statistics per command verb
+    pub fn commands(&self) -> BTreeMap<&str, CommandStats> {
+        let mut latencies = BTreeMap::<&str, Vec<Duration>>::new();
+        let mut stats = BTreeMap::<&str, CommandStats>::new();
+        for exchange in &self.exchanges {
+            let Some(command) = exchange.command.as_deref() else {
+                continue;
+            };
+
+            let entry = stats.entry(command).or_default();
+            entry.count += 1;
+            match exchange.code {
+                Some(code) if !code.is_success() => entry.failed += 1,
+                _ if exchange.received_at.is_none() => entry.unanswered += 1,
+                _ => {}
+            }
+            if let Some(latency) = exchange.latency() {
+                latencies.entry(command).or_default().push(latency);
+            }
+        }
+
+        for (command, mut samples) in latencies {
+            samples.sort();
+            let entry = stats.entry(command).or_default();
+            entry.median_latency = Some(samples[samples.len() / 2]);
+            entry.max_latency = samples.last().copied();
+        }
+        stats
+    }
+
+    /// The number of responses per primary result code
+    pub fn result_codes(&self) -> BTreeMap<u16, usize> {
+        let mut codes = BTreeMap::new();
+        for code in self.exchanges.iter().filter_map(|exchange| exchange.code) {
+            *codes.entry(code as u16).or_default() += 1;
+        }
+        codes
+    }
+
+    /// The number of commands using each extension namespace
+    pub fn extensions(&self) -> BTreeMap<&str, usize> {
+        let mut extensions = BTreeMap::new();
+        for exchange in &self.exchanges {
+            for uri in &exchange.extensions {
+                *extensions.entry(uri.as_str()).or_default() += 1;
+            }
+        }
+        extensions
+    }
+}
+
+/// The timeline, one exchange per line, followed by the statistics per command
+impl fmt::Display for SessionReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for exchange in &self.exchanges {
+            writeln!(f, "{exchange}")?;
+        }
+
+        for (command, stats) in self.commands() {
+            write!(
+                f,
+                "\n{command}: {} sent, {} failed",
+                stats.count, stats.failed
+            )?;
+            if stats.unanswered > 0 {
+                write!(f, ", {} unanswered", stats.unanswered)?;
+            }
+            if let (Some(median), Some(max)) = (stats.median_latency, stats.max_latency) {
+                write!(
+                    f,
+                    ", median {} ms, max {} ms",
+                    median.num_milliseconds(),
+                    max.num_milliseconds()
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+
+    use super::{Direction, SessionAnalyzer};
+    use crate::frame::encode;
+    use crate::response::ResultCode;
+    use crate::tests::get_xml;
+
+    #[test]
+    fn timeline() {
+        let start = Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap();
+        let at = |ms| start + Duration::milliseconds(ms);
+        let mut analyzer = SessionAnalyzer::new();
+
+        let mut server = Vec::new();
+        encode(&get_xml("response/greeting.xml").unwrap(), &mut server).unwrap();
+        analyzer
+            .stream(Direction::Response, at(0), &server)
+            .unwrap();
+
+        // The login frame arrives in two chunks
+        let mut client = Vec::new();
+        encode(&get_xml("request/login.xml").unwrap(), &mut client).unwrap();
+        let (first, second) = client.split_at(10);
+        analyzer.stream(Direction::Request, at(5), first).unwrap();
+        analyzer.stream(Direction::Request, at(10), second).unwrap();
+        let response = get_xml("response/login.xml").unwrap();
+        analyzer.frame(Direction::Response, at(130), &response);
+
+        let request = get_xml("request/extensions/consolidate_namestore.xml").unwrap();
+        analyzer.frame(Direction::Request, at(200), &request);
+        let response = get_xml("response/error.xml").unwrap();
+        analyzer.frame(Direction::Response, at(260), &response);
+
+        let request = get_xml("request/domain/check.xml").unwrap();
+        analyzer.frame(Direction::Request, at(300), &request);
+
+        let report = analyzer.finish();
+        assert_eq!(report.exchanges.len(), 4);
+        assert_eq!(report.exchanges[0].command, None);
+        assert_eq!(report.exchanges[0].code, None);
+
+        let login = &report.exchanges[1];
+        assert_eq!(login.command.as_deref(), Some("login"));
+        assert_eq!(login.code, Some(ResultCode::CommandCompletedSuccessfully));
+        assert_eq!(login.latency(), Some(Duration::milliseconds(120)));
+        assert_eq!(login.client_tr_id.as_deref(), Some("cltrid:1626454866"));
+        assert_eq!(login.object, None);
+
+        let update = &report.exchanges[2];
+        assert_eq!(update.command.as_deref(), Some("update"));
+        assert_eq!(
+            update.object.as_deref(),
+            Some("urn:ietf:params:xml:ns:domain-1.0")
+        );
+        assert_eq!(update.extensions.len(), 2);
+
+        let commands = report.commands();
+        assert_eq!(commands["update"].failed, 1);
+        assert_eq!(commands["check"].unanswered, 1);
+        assert_eq!(report.result_codes()[&1000], 1);
+        assert_eq!(
+            report.exchanges[3].to_string(),
+            "2024-03-01T10:00:00.300Z check domain-1.0 [cltrid:1626454866] no response"
+        );
+    }
+
+    #[test]
+    fn stream_error() {
+        let at = Utc.with_ymd_and_hms(2024, 3, 1, 10, 0, 0).unwrap();
+        let mut analyzer = SessionAnalyzer::new();
+
+        // A hello followed by garbage in the same chunk
+        let mut client = Vec::new();
+        encode(&get_xml("request/hello.xml").unwrap(), &mut client).unwrap();
+        client.extend_from_slice(&[0, 0, 0, 1]);
+        assert!(analyzer.stream(Direction::Request, at, &client).is_err());
+
+        let report = analyzer.finish();
+        assert_eq!(report.exchanges.len(), 1);
+        assert_eq!(report.exchanges[0].command.as_deref(), Some("hello"));
+    }
+}
@@ -6,6 +6,10 @@ use chrono::{DateTime, Utc};
 ///
 /// Implementations should return quickly. Applications that need durable persistence should
 /// forward events to their own queue or channel.
+///
+/// Frames are passed on as received, uncompressed. EPP XML compresses very well, so sinks
+/// archiving large volumes (like a drain of the poll queue) should store them compressed, like
+/// with [`EppTransactionAuditEvent::deflated_frames()`] (with the `compression` feature).
 pub trait EppTransactionAuditSink: Send + Sync {
     fn record(&self, event: EppTransactionAuditEvent);
 }
@@ -27,3 +31,28 @@ pub struct EppTransactionAuditEvent {
     pub raw_response: Option<String>,
     pub error: Option<String>,
 }
+
+#[cfg(feature = "compression")]
+impl EppTransactionAuditEvent {
+    /// The request and response frames, compressed for archiving
+    ///
+    /// Read them back with [`inflate()`](crate::compression::inflate).
+    pub fn deflated_frames(&self) -> DeflatedFrames {
+        DeflatedFrames {
+            request: crate::compression::deflate(&self.raw_request),
+            response: self
+                .raw_response
+                .as_deref()
+                .map(crate::compression::deflate),
+        }
+    }
+}
+
+/// The frames of an [`EppTransactionAuditEvent`], compressed with
+/// [`deflate()`](crate::compression::deflate)
+#[cfg(feature = "compression")]
+#[derive(Clone, Debug)]
+pub struct DeflatedFrames {
+    pub request: Vec<u8>,
+    pub response: Option<Vec<u8>>,
+}
@@ -0,0 +1,301 @@
+//! Tracking the registrar's account balance at the registry
+//!
+//! Registries supporting fee-0.7 or fee-0.23 report the account balance and credit limit in the
+//! responses to billable commands, and Verisign sends a low balance poll message when the
+//! available credit falls below the threshold set up for the account. An [`AccountBalance`] is
+//! a [`Middleware`] picking up both, so the latest known balance is available without querying
+//! the registry, and calls a callback when the balance runs low.
+//!
+//! Response extensions reporting a balance implement [`ReportsBalance`], which the client reads
+//! through [`Extension::balance()`](crate::request::Extension::balance), also for extensions
+//! combined in a [`Composite`](crate::extensions::composite::Composite).
+
+use std::any::Any;
+use std::sync::Mutex;
+
+use crate::common::NoExtension;
+use crate::extensions::low_balance::LowBalance;
+use crate::extensions::{fee023, fee07};
+use crate::middleware::{Middleware, Request};
+use crate::poll::PollData;
+use crate::response::Response;
+use crate::Error;
+
+type OnLowBalance = Box<dyn Fn(&Balance) + Send + Sync>;
+
+/// The account balance as last reported by the registry
+#[derive(Clone, Debug, PartialEq)]
+pub struct Balance {
+    /// The balance, which is negative when the account is drawing on its credit
+    pub balance: f64,
+    pub credit_limit: Option<f64>,
+    /// The currency of the amounts, if the registry stated one
+    pub currency: Option<String>,
+}
+
+impl Balance {
+    /// The amount that can still be spent: the balance plus the credit limit
+    pub fn available(&self) -> f64 {
+        self.balance + self.credit_limit.unwrap_or(0.0)
+    }
+}
+
+impl From<&LowBalance> for Balance {
+    /// Amounts that don't parse are taken as zero
+    fn from(poll: &LowBalance) -> Self {
+        let available = poll.available_credit.trim().parse::<f64>().unwrap_or(0.0);
+        let credit_limit = poll.credit_limit.trim().parse::<f64>().unwrap_or(0.0);
+        Self {
+            balance: available - credit_limit,
+            credit_limit: Some(credit_limit),
+            currency: None,
+        }
+    }
+}
+
+/// A response extension reporting the account balance
+pub trait ReportsBalance {
+    /// The balance, if the response included one
+    fn balance(&self) -> Option<Balance>;
+}
+
+macro_rules! reports_balance {
+    ($($ty:ty),*) => {
+        $(impl ReportsBalance for $ty {
+            fn balance(&self) -> Option<Balance> {
+                Some(Balance {
+                    balance: self.balance?,
+                    credit_limit: self.credit_limit,
+                    currency: self.currency.clone().into(),
+                })
+            }
+        })*
+    };
+}
+
+reports_balance!(
+    fee07::CreateData,
+    fee07::RenewData,
+    fee07::TransferData,
+    fee07::UpdateData,
+    fee07::DeleteData,
+    fee023::CreateData,
+    fee023::RenewData,
+    fee023::TransferData,
+    fee023::UpdateData,
+    fee023::DeleteData
+);
+
+/// A [`Middleware`] keeping the latest account balance reported by the registry
+///
+/// Share one tracker between the clients logged in to the same registrar account.
+pub struct AccountBalance {
+    threshold: Option<f64>,
+    on_low_balance: Option<OnLowBalance>,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    current: Option<Balance>,
+    // Whether the balance is low, so the callback is only called on a change
+    low: bool,
+}
+
+impl AccountBalance {
+    pub fn new() -> Self {
+        Self {
+            threshold: None,
+            on_low_balance: None,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// Consider the balance low once the available amount falls below `threshold`
+    ///
+    /// Without a threshold, the balance is considered low from the first low balance poll
+    /// message the registry sends.
+    pub fn low_balance(mut self, threshold: f64) -> Self {
+        self.threshold = Some(threshold);
+        self
+    }
+
+    /// Call `on_low_balance` when the balance becomes low
+    ///
+    /// The callback is called again only after the balance recovered in between. It runs
+    /// inline after a response, so it should not block.
+    pub fn on_low_balance(
+        mut self,
+        on_low_balance: impl Fn(&Balance) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_low_balance = Some(Box::new(on_low_balance));
+        self
+    }
+
+    /// The latest balance, if the registry reported one yet
+    pub fn current(&self) -> Option<Balance> {
+        let inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        inner.current.clone()
+    }
+
+    /// Whether the balance is currently low
+    pub fn is_low(&self) -> bool {
+        let inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        inner.low
+    }
+
+    /// Record a balance obtained elsewhere, like a registry-specific balance query
+    pub fn update(&self, balance: Balance) {
+        let low = self
+            .threshold
+            .map(|threshold| balance.available() < threshold);
+        self.record(balance, low);
+    }
+
+    /// Record the balance from a low balance poll message, which marks the balance as low
+    pub fn update_from_poll(&self, poll: &LowBalance) {
+        self.record(Balance::from(poll), Some(true));
+    }
+
+    // `low` is `None` if there is no threshold to compare with, keeping the current state
+    fn record(&self, balance: Balance, low: Option<bool>) {
+        let became_low = {
+            let mut inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+            let low = low.unwrap_or(inner.low);
+            let became_low = low && !inner.low;
+            inner.low = low;
+            inner.current = Some(balance.clone());
+            became_low
+        };
+
+        if became_low {
+            if let Some(on_low_balance) = &self.on_low_balance {
+                on_low_balance(&balance);
+            }
+        }
+    }
+}
+
+impl Default for AccountBalance {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Middleware for AccountBalance {
+    fn request(&self, _: &mut Request<'_>) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn response(&self, request: &Request<'_>, response: Result<&dyn Any, &Error>) {
+        let Ok(response) = response else {
+            return;
+        };
+
+        if let Some(rsp) = response.downcast_ref::<Response<PollData, NoExtension>>() {
+            if let Some(PollData::LowBalance(poll)) = rsp.res_data() {
+                self.update_from_poll(poll);
+            }
+            return;
+        }
+
+        if let Some(balance) = request.balance() {
+            self.update(balance.clone());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    use super::{AccountBalance, Balance};
+    use crate::domain::{DomainDelete, DomainUpdate};
+    use crate::extensions::composite::Composite;
+    use crate::extensions::fee07::DeleteExtension;
+    use crate::extensions::rgp::request::{RgpRestoreRequest, Update};
+    use crate::extensions::{fee023, rgp};
+    use crate::middleware::{Middleware, Request};
+    use crate::poll::Poll;
+    use crate::request::{Command, Extension};
+    use crate::tests::{response_from_file, response_from_file_with_ext};
+
+    #[test]
+    fn tracking() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let tracker = AccountBalance::new()
+            .low_balance(1000.0)
+            .on_low_balance(move |_| {
+                counter.fetch_add(1, Ordering::SeqCst);
+            });
+
+        let delete = DomainDelete::new("eppdev.com");
+        let mut req = Request::new(delete.view(), DomainDelete::COMMAND, "delete-1");
+        let rsp = response_from_file_with_ext::<DomainDelete, DeleteExtension>(
+            "response/extensions/fee07_delete.xml",
+        );
+        req.set_balance(
+            rsp.extension
+                .as_ref()
+                .and_then(|ext| DeleteExtension::balance(&ext.data)),
+        );
+        tracker.response(&req, Ok(&rsp));
+        assert_eq!(
+            tracker.current(),
+            Some(Balance {
+                balance: 1005.0,
+                credit_limit: None,
+                currency: Some("USD".into()),
+            })
+        );
+        assert!(!tracker.is_low());
+
+        let poll = Poll;
+        let req = Request::new(poll.view(), Poll::COMMAND, "poll-1");
+        let rsp = response_from_file::<Poll>("response/poll/poll_low_balance.xml");
+        tracker.response(&req, Ok(&rsp));
+        tracker.response(&req, Ok(&rsp));
+        assert!(tracker.is_low());
+        assert_eq!(tracker.current().unwrap().available(), 491.31);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        tracker.update(Balance {
+            balance: 2000.0,
+            credit_limit: Some(500.0),
+            currency: None,
+        });
+        assert!(!tracker.is_low());
+        tracker.update(Balance {
+            balance: -200.0,
+            credit_limit: Some(500.0),
+            currency: None,
+        });
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    fn composite() {
+        type Ext<'a> = Composite<(Update<RgpRestoreRequest<'a>>, fee023::Update<'a>)>;
+
+        let rsp = response_from_file_with_ext::<DomainUpdate, Ext>(
+            "response/extensions/fee023_rgp_update.xml",
+        );
+        let ext = &rsp.extension.as_ref().unwrap().data;
+        match ext.0 .0.as_ref() {
+            Some(rgp::request::RgpRequestResponse::Update(update)) => {
+                assert_eq!(update.rgp_status[0], rgp::RgpStatus::PendingRestore);
+            }
+            other => panic!("unexpected rgp response: {other:?}"),
+        }
+        assert_eq!(
+            Ext::balance(ext),
+            Some(Balance {
+                balance: -5.0,
+                credit_limit: Some(1000.0),
+                currency: Some("USD".into()),
+            })
+        );
+    }
+}
@@ -0,0 +1,427 @@
+//! Cost estimates and spending limits for billable commands
+//!
+//! Creates, renewals and transfer requests are charged to the registrar's account at the
+//! registry. A loop in a provisioning job can drain the account in minutes, so a
+//! [`BudgetGuard`] estimates the cost of each billable command from a [`PriceList`] before it
+//! is sent, and refuses commands above a per-command cap or a total budget, unless they were
+//! approved with [`BudgetGuard::approve()`].
+//!
+//! The price list holds the caller's standard prices per TLD; prices the registry quoted for
+//! specific names (premium names, usually) are added from fee check responses with
+//! [`PriceList::with_fee_check()`].
+
+use std::any::Any;
+use std::collections::{HashMap, HashSet};
+use std::error::Error as StdError;
+use std::fmt;
+use std::sync::Mutex;
+
+use crate::common::Amount;
+use crate::domain::{normalize_name, Period};
+use crate::extensions::fee::{self, PeriodUnit};
+use crate::middleware::{CommandView, Middleware, Request};
+use crate::Error;
+
+/// The billable commands
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum BillableCommand {
+    Create,
+    Renew,
+    Transfer,
+}
+
+impl BillableCommand {
+    /// The command and period of a billable command, if `command` is one
+    pub fn of<'a>(command: &CommandView<'a>) -> Option<(Self, &'a str, Option<Period>)> {
+        Some(match *command {
+            CommandView::DomainCreate(cmd) => (Self::Create, cmd.name, Some(cmd.period)),
+            CommandView::DomainRenew(cmd) => {
+                (Self::Renew, cmd.domain.name, Some(cmd.domain.period))
+            }
+            CommandView::DomainTransfer(cmd) if cmd.operation == "request" => {
+                (Self::Transfer, cmd.name(), cmd.period())
+            }
+            _ => return None,
+        })
+    }
+
+    fn parse(name: &str) -> Option<Self> {
+        match name {
+            "create" => Some(Self::Create),
+            "renew" => Some(Self::Renew),
+            "transfer" => Some(Self::Transfer),
+            _ => None,
+        }
+    }
+}
+
+/// Yearly prices of the billable commands, in a single currency
+#[derive(Clone, Debug, Default)]
+pub struct PriceList {
+    // Keyed by the TLD, as with the `with_tld()` methods of the domain policies
+    tlds: HashMap<(String, BillableCommand), Amount>,
+    // Keyed by the normalized domain name
+    names: HashMap<(String, BillableCommand), Amount>,
+}
+
+impl PriceList {
+    /// Charge `price` per year for `command` on names under `tld`
+    ///
+    /// A multi-label `tld` (like `co.uk`) takes precedence over its parent.
+    pub fn with_price(
+        mut self,
+        tld: &str,
+        command: BillableCommand,
+        price: impl Into<Amount>,
+    ) -> Self {
+        let tld = tld.trim_matches('.').to_ascii_lowercase();
+        self.tlds.insert((tld, command), price.into());
+        self
+    }
+
+    /// Charge `price` per year for `command` on the domain `name`
+    pub fn with_name_price(
+        mut self,
+        name: &str,
+        command: BillableCommand,
+        price: impl Into<Amount>,
+    ) -> Self {
+        self.names
+            .insert((normalize_name(name), command), price.into());
+        self
+    }
+
+    /// Add the prices quoted in a fee check response
+    ///
+    /// Quotes for several years are converted to yearly prices. Commands without fees (like
+    /// those the registry gave a reason for instead) are skipped.
+    pub fn with_fee_check(mut self, data: &fee::CheckData) -> Self {
+        for cd in &data.list {
+            for quote in &cd.commands {
+                let Some(command) = BillableCommand::parse(&quote.name) else {
+                    continue;
+                };
+                if quote.fees.is_empty() {
+                    continue;
+                }
+
+                let years = match &quote.period {
+                    Some(period) => match period.unit {
+                        PeriodUnit::Years => f64::from(period.value),
+                        PeriodUnit::Months => f64::from(period.value) / 12.0,
+                    },
+                    None => 1.0,
+                };
+                let key = (normalize_name(&cd.obj_id), command);
+                self.names.insert(key, Amount::new(quote.total() / years));
+            }
+        }
+        self
+    }
+
+    /// The estimated cost of `command`
+    pub fn estimate(&self, command: &CommandView<'_>) -> Estimate {
+        let Some((billable, name, period)) = BillableCommand::of(command) else {
+            return Estimate::Free;
+        };
+
+        let years = match period {
+            Some(Period::Years(length)) => f64::from(length.0),
+            Some(Period::Months(length)) => f64::from(length.0) / 12.0,
+            None => 1.0,
+        };
+
+        match self.yearly_price(name, billable) {
+            Some(price) => Estimate::Cost(Amount {
+                value: price.value * years,
+                decimals: price.decimals,
+            }),
+            None => Estimate::Unknown,
+        }
+    }
+
+    fn yearly_price(&self, name: &str, command: BillableCommand) -> Option<Amount> {
+        let name = normalize_name(name);
+        if let Some(price) = self.names.get(&(name.clone(), command)) {
+            return Some(*price);
+        }
+
+        let mut suffix = name.as_str();
+        while let Some((_, parent)) = suffix.split_once('.') {
+            if let Some(price) = self.tlds.get(&(parent.to_owned(), command)) {
+                return Some(*price);
+            }
+            suffix = parent;
+        }
+        None
+    }
+}
+
+/// The estimated cost of a command
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Estimate {
+    /// The command is not billable
+    Free,
+    Cost(Amount),
+    /// The command is billable, but the price list has no price for it
+    Unknown,
+}
+
+/// A [`Middleware`] refusing billable commands over budget
+///
+/// Commands the price list has no price for are refused as well, since their cost can't be
+/// bounded. The estimated cost of a command is reserved when it is sent, so that concurrent
+/// commands can't overrun the total budget together; it is deducted from the budget if the
+/// command succeeds and released otherwise.
+pub struct BudgetGuard {
+    prices: PriceList,
+    per_command: Option<Amount>,
+    total: Option<Amount>,
+    ledger: Mutex<Ledger>,
+}
+
+impl BudgetGuard {
+    pub fn new(prices: PriceList) -> Self {
+        Self {
+            prices,
+            per_command: None,
+            total: None,
+            ledger: Mutex::new(Ledger::default()),
+        }
+    }
+
+    /// Refuse single commands estimated to cost more than `cap`
+    pub fn per_command(mut self, cap: impl Into<Amount>) -> Self {
+        self.per_command = Some(cap.into());
+        self
+    }
+
+    /// Refuse commands once their cost would take the total spent above `budget`
+    pub fn total(mut self, budget: impl Into<Amount>) -> Self {
+        self.total = Some(budget.into());
+        self
+    }
+
+    /// Send the next `command` on the domain `name` even if it is over budget
+    ///
+    /// The approval is used up by that command. Its cost still counts towards the total.
+    pub fn approve(&self, command: BillableCommand, name: &str) {
+        let mut ledger = self.ledger.lock().unwrap_or_else(|e| e.into_inner());
+        ledger.approved.insert((normalize_name(name), command));
+    }
+
+    /// The estimated cost of the successful commands so far
+    pub fn spent(&self) -> Amount {
+        let ledger = self.ledger.lock().unwrap_or_else(|e| e.into_inner());
+        amount(ledger.spent)
+    }
+}
+
+impl Middleware for BudgetGuard {
+    fn request(&self, request: &mut Request<'_>) -> Result<(), Error> {
+        let refused = |reason| OverBudget {
+            command: request.name,
+            object: request.command.object().unwrap_or_default().to_owned(),
+            reason,
+        };
+
+        let Some((billable, name, _)) = BillableCommand::of(&request.command) else {
+            return Ok(());
+        };
+
+        let mut ledger = self.ledger.lock().unwrap_or_else(|e| e.into_inner());
+        let approved = ledger.approved.remove(&(normalize_name(name), billable));
+        let cost = match self.prices.estimate(&request.command) {
+            Estimate::Cost(cost) => cost,
+            // Approved commands without a price are sent, but can't be accounted for
+            _ if approved => return Ok(()),
+            _ => return Err(refused(BudgetReason::Unpriced).into()),
+        };
+
+        if !approved {
+            if self.per_command.is_some_and(|cap| units(cost) > units(cap)) {
+                return Err(refused(BudgetReason::CommandCap { cost }).into());
+            }
+
+            let committed = ledger.spent + ledger.reserved.values().sum::<i64>();
+            match self.total {
+                Some(budget) if committed + units(cost) > units(budget) => {
+                    let spent = amount(committed);
+                    return Err(refused(BudgetReason::Total { cost, spent }).into());
+                }
+                _ => {}
+            }
+        }
+
+        ledger.reserved.insert(request.key(), units(cost));
+        Ok(())
+    }
+
+    fn response(&self, request: &Request<'_>, response: Result<&dyn Any, &Error>) {
+        let mut ledger = self.ledger.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(cost) = ledger.reserved.remove(&request.key()) else {
+            return;
+        };
+
+        if response.is_ok() {
+            ledger.spent += cost;
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Ledger {
+    // Amounts in units of `SCALE`
+    spent: i64,
+    // The costs of the commands in flight, keyed by `Request::key()`
+    reserved: HashMap<u64, i64>,
+    // Commands allowed over budget, see `BudgetGuard::approve()`
+    approved: HashSet<(String, BillableCommand)>,
+}
+
+// Costs are added up in ten-thousandths, finer than the minor unit of any currency, so that
+// totals don't drift the way sums of floating-point amounts do
+const SCALE: f64 = 10_000.0;
+
+fn units(amount: Amount) -> i64 {
+    (amount.value * SCALE).round() as i64
+}
+
+fn amount(units: i64) -> Amount {
+    Amount::new(units as f64 / SCALE)
+}
+
+/// A command refused by a [`BudgetGuard`]
+///
+/// Returned as [`Error::Other`]; nothing was sent to the registry.
+#[derive(Debug)]
+pub struct OverBudget {
+    /// The command verb, like `create`
+    pub command: &'static str,
+    /// The name of the domain the command acts on
+    pub object: String,
+    pub reason: BudgetReason,
+}
+
+/// Why a [`BudgetGuard`] refused a command
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BudgetReason {
+    /// The command costs more than the per-command cap
+    CommandCap { cost: Amount },
+    /// The command would exceed the total budget, given what was spent or reserved so far
+    Total { cost: Amount, spent: Amount },
+    /// The price list has no price for the command
+    Unpriced,
+}
+
+impl fmt::Display for OverBudget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (command, object) = (self.command, &self.object);
+        match self.reason {
+            BudgetReason::CommandCap { cost } => {
+                write!(
+                    f,
+                    "{command} of {object} refused: cost {cost} exceeds the cap"
+                )
+            }
+            BudgetReason::Total { cost, spent } => write!(
+                f,
+                "{command} of {object} refused: cost {cost} exceeds the budget ({spent} spent)"
+            ),
+            BudgetReason::Unpriced => write!(f, "{command} of {object} refused: no price"),
+        }
+    }
+}
+
+impl StdError for OverBudget {}
+
+impl From<OverBudget> for crate::Error {
+    fn from(err: OverBudget) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BillableCommand, BudgetGuard, Estimate, PriceList};
+    use crate::common::Amount;
+    use crate::domain::{DomainCheck, DomainCreate, DomainRenew, ONE_YEAR, THREE_YEARS};
+    use crate::extensions::fee;
+    use crate::middleware::{Middleware, Request};
+    use crate::request::Command;
+    use crate::tests::response_from_file_with_ext;
+    use crate::Error;
+
+    #[test]
+    fn estimate() {
+        let rsp = response_from_file_with_ext::<DomainCheck, fee::Check>(
+            "response/extensions/fee_check.xml",
+        );
+        let prices = PriceList::default()
+            .with_price("com", BillableCommand::Create, 10.0)
+            .with_fee_check(rsp.extension().unwrap());
+
+        let create = DomainCreate::new("eppdev.com", THREE_YEARS, None, None, "pw", None);
+        assert_eq!(
+            prices.estimate(&create.view()),
+            Estimate::Cost(Amount::new(30.0))
+        );
+        let create = DomainCreate::new("Example.com", THREE_YEARS, None, None, "pw", None);
+        assert_eq!(
+            prices.estimate(&create.view()),
+            Estimate::Cost(Amount::new(300.0))
+        );
+        let expiry = chrono::NaiveDate::from_ymd_opt(2025, 1, 1).unwrap();
+        let renew = DomainRenew::new("eppdev.com", expiry, ONE_YEAR);
+        assert_eq!(prices.estimate(&renew.view()), Estimate::Unknown);
+        let check = DomainCheck {
+            domains: &["eppdev.com"],
+        };
+        assert_eq!(prices.estimate(&check.view()), Estimate::Free);
+    }
+
+    #[test]
+    fn guard() {
+        let prices = PriceList::default().with_price("com", BillableCommand::Create, 10.0);
+        let guard = BudgetGuard::new(prices).per_command(25.0).total(45.0);
+
+        let create = DomainCreate::new("eppdev.com", THREE_YEARS, None, None, "pw", None);
+        let mut req = Request::new(create.view(), DomainCreate::COMMAND, "job-1");
+        let err = guard.request(&mut req).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "error: create of eppdev.com refused: cost 30.00 exceeds the cap"
+        );
+
+        guard.approve(BillableCommand::Create, "EPPDEV.com");
+        assert!(guard.request(&mut req).is_ok());
+        guard.response(&req, Ok(&()));
+        assert_eq!(guard.spent(), Amount::new(30.0));
+        // The approval was used up
+        let mut req = Request::new(create.view(), DomainCreate::COMMAND, "job-1");
+        assert!(guard.request(&mut req).is_err());
+
+        // Commands in flight count towards the total
+        let create = DomainCreate::new("eppdev.com", ONE_YEAR, None, None, "pw", None);
+        let mut first = Request::new(create.view(), DomainCreate::COMMAND, "job-2");
+        assert!(guard.request(&mut first).is_ok());
+        let mut second = Request::new(create.view(), DomainCreate::COMMAND, "job-2");
+        let err = guard.request(&mut second).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "error: create of eppdev.com refused: cost 10.00 exceeds the budget (40.00 spent)"
+        );
+
+        // Failed commands release their reservation
+        let failed = Error::Timeout;
+        guard.response(&first, Err(&failed));
+        assert_eq!(guard.spent(), Amount::new(30.0));
+        assert!(guard.request(&mut second).is_ok());
+        guard.response(&second, Ok(&()));
+        assert_eq!(guard.spent(), Amount::new(40.0));
+
+        let create = DomainCreate::new("eppdev.net", ONE_YEAR, None, None, "pw", None);
+        let mut req = Request::new(create.view(), DomainCreate::COMMAND, "job-3");
+        assert!(guard.request(&mut req).is_err());
+    }
+}
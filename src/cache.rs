@@ -0,0 +1,277 @@
+//! Short-lived caching of check results
+//!
+//! Storefronts tend to check the same names over and over as users type and refine their
+//! searches. Sending every one of those checks to the registry wastes its rate limits on
+//! answers that rarely change within a minute. A [`CheckCache`] keeps recent results, keyed
+//! by normalized name, for a configurable time; keep one per registry to give each its own
+//! TTL. [`check_domains()`] answers what it can from the cache and only checks the rest, and
+//! [`quote()`] does the same for availability with prices from the fee extensions.
+
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::client::{Connector, EppClient};
+use crate::domain::check::CheckedDomain;
+use crate::domain::normalize_name;
+use crate::quote::Quote;
+use crate::registrar;
+use crate::Error;
+
+/// A bounded cache of check results that expire after a fixed time
+#[derive(Debug)]
+pub struct CheckCache<T> {
+    ttl: Duration,
+    capacity: usize,
+    entries: HashMap<String, Cached<T>>,
+    // Keys in insertion order, with the `seq` of the result they were inserted with
+    //
+    // Since all results live for the same time, this is also the order they expire in. Keys
+    // whose result was since replaced or dropped are skipped when they come up.
+    order: VecDeque<(String, u64)>,
+    // Insertion counter, to tell the current result for a key from earlier ones
+    seq: u64,
+    stats: CacheStats,
+}
+
+#[derive(Debug)]
+struct Cached<T> {
+    at: Instant,
+    seq: u64,
+    value: T,
+}
+
+impl<T: Clone> CheckCache<T> {
+    /// Create a cache keeping at most `capacity` results for `ttl` each
+    pub fn new(ttl: Duration, capacity: usize) -> Self {
+        Self {
+            ttl,
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+            seq: 0,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// The cached result for `name`, if it has not expired
+    pub fn get(&mut self, name: &str) -> Option<T> {
+        self.lookup(name, |_| true)
+    }
+
+    // The cached result for `name`, if it has not expired and is `usable`
+    fn lookup(&mut self, name: &str, usable: impl Fn(&T) -> bool) -> Option<T> {
+        let key = normalize_name(name);
+        match self.entries.get(&key) {
+            Some(cached) if cached.at.elapsed() < self.ttl && usable(&cached.value) => {
+                self.stats.hits += 1;
+                Some(cached.value.clone())
+            }
+            Some(cached) if cached.at.elapsed() < self.ttl => {
+                self.stats.misses += 1;
+                None
+            }
+            Some(_) => {
+                self.entries.remove(&key);
+                self.stats.misses += 1;
+                None
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Cache `value` as the result for `name`
+    ///
+    /// If the cache is full, expired results are dropped first, then the oldest ones.
+    pub fn insert(&mut self, name: &str, value: T) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let key = normalize_name(name);
+        self.seq += 1;
+        let cached = Cached {
+            at: Instant::now(),
+            seq: self.seq,
+            value,
+        };
+        self.entries.insert(key.clone(), cached);
+        self.order.push_back((key, self.seq));
+
+        while let Some((key, seq)) = self.order.front() {
+            let evict = match self.entries.get(key) {
+                Some(cached) if cached.seq == *seq => {
+                    self.entries.len() > self.capacity || cached.at.elapsed() >= self.ttl
+                }
+                // Replaced or dropped since
+                _ => {
+                    self.order.pop_front();
+                    continue;
+                }
+            };
+
+            if !evict {
+                break;
+            }
+            self.entries.remove(key);
+            self.order.pop_front();
+        }
+
+        // Results replaced while older ones are still cached pile up behind those
+        if self.order.len() > 2 * self.capacity {
+            let entries = &self.entries;
+            self.order
+                .retain(|(key, seq)| entries.get(key).is_some_and(|cached| cached.seq == *seq));
+        }
+    }
+
+    /// Drop the cached result for `name`, for example after registering it
+    pub fn invalidate(&mut self, name: &str) {
+        self.entries.remove(&normalize_name(name));
+    }
+
+    /// The number of results cached, including expired ones not yet dropped
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Hits and misses since the cache was created
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+}
+
+/// Lookup counters for a [`CheckCache`]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+}
+
+/// Check the availability of domains, answering from `cache` where possible
+///
+/// The names not found in the cache are checked with [`registrar::check_domains()`], and the
+/// results added to the cache. Results are keyed by normalized domain name.
+pub async fn check_domains<C: Connector>(
+    client: &mut EppClient<C>,
+    cache: &mut CheckCache<CheckedDomain>,
+    names: &[&str],
+    chunk_size: usize,
+    id: &str,
+) -> Result<HashMap<String, CheckedDomain>, Error> {
+    let mut checked = HashMap::with_capacity(names.len());
+    let mut missing = Vec::new();
+    for &name in names {
+        match cache.get(name) {
+            Some(result) => {
+                checked.insert(normalize_name(name), result);
+            }
+            None => missing.push(name),
+        }
+    }
+
+    if missing.is_empty() {
+        return Ok(checked);
+    }
+
+    for (name, result) in registrar::check_domains(client, &missing, chunk_size, id).await? {
+        cache.insert(&name, result.clone());
+        checked.insert(name, result);
+    }
+
+    Ok(checked)
+}
+
+/// Check the availability and price of `name` for `years`, answering from `cache` if possible
+///
+/// Otherwise this is [`EppClient::quote()`], whose result is added to the cache. The cache
+/// keeps one quote per name, along with the period it is for; a quote for another period is
+/// not used, and replaced by the new one.
+pub async fn quote<C: Connector>(
+    client: &mut EppClient<C>,
+    cache: &mut CheckCache<(u16, Quote)>,
+    name: &str,
+    years: u16,
+    id: &str,
+) -> Result<Quote, Error> {
+    if let Some((_, quote)) = cache.lookup(name, |(cached, _)| *cached == years) {
+        return Ok(quote);
+    }
+
+    let quote = client.quote(name, years, id).await?;
+    cache.insert(name, (years, quote.clone()));
+    Ok(quote)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{CacheStats, CheckCache};
+
+    #[test]
+    fn hits_and_misses() {
+        let mut cache = CheckCache::new(Duration::from_secs(60), 2);
+        assert_eq!(cache.get("example.com"), None);
+
+        cache.insert("Example.com", true);
+        cache.insert("example.net", false);
+        assert_eq!(cache.get("EXAMPLE.com"), Some(true));
+
+        // Evicts the oldest entry
+        cache.insert("example.org", true);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("example.com"), None);
+        assert_eq!(cache.get("example.net"), Some(false));
+
+        assert_eq!(cache.stats(), CacheStats { hits: 2, misses: 2 });
+    }
+
+    #[test]
+    fn replaced() {
+        let mut cache = CheckCache::new(Duration::from_secs(60), 2);
+        cache.insert("example.com", 1);
+        for value in 2..10 {
+            cache.insert("example.net", value);
+        }
+        assert!(cache.order.len() <= 4);
+
+        // The earlier results for example.net don't count against the later one
+        cache.insert("example.org", 10);
+        assert_eq!(cache.get("example.com"), None);
+        assert_eq!(cache.get("example.net"), Some(9));
+        assert_eq!(cache.get("example.org"), Some(10));
+
+        cache.invalidate("example.net");
+        cache.insert("example.com", 11);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get("example.org"), Some(10));
+    }
+
+    #[test]
+    fn quote_periods() {
+        let mut cache = CheckCache::new(Duration::from_secs(60), 10);
+        cache.insert("example.com", (1u16, "one year"));
+        assert_eq!(
+            cache.lookup("EXAMPLE.com", |(years, _)| *years == 1),
+            Some((1, "one year"))
+        );
+        assert_eq!(cache.lookup("example.com", |(years, _)| *years == 2), None);
+        assert_eq!(cache.stats().hits, 1);
+        assert_eq!(cache.stats().misses, 1);
+    }
+
+    #[test]
+    fn expiry() {
+        let mut cache = CheckCache::new(Duration::ZERO, 2);
+        cache.insert("example.com", true);
+        assert_eq!(cache.get("example.com"), None);
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,427 @@
+//! Running OT&E certification scripts
+//!
+//! Before granting production access, registries have new registrars run a fixed sequence of
+//! operations against their OT&E (operational test and evaluation) environment: check a few
+//! names, create a domain, update its statuses and name servers, renew it, transfer it and
+//! delete it again. A [`Script`] describes such a sequence, each step with the result the
+//! registry should return, and [`run()`] executes it and produces a [`Report`].
+
+use std::fmt;
+use std::net::IpAddr;
+
+use chrono::FixedOffset;
+
+use crate::client::{Connector, EppClient};
+use crate::domain::update::{DomainAdd, DomainRemove};
+use crate::domain::{
+    DomainCheck, DomainContact, DomainCreate, DomainDelete, DomainInfo, DomainRenew,
+    DomainTransfer, DomainUpdate, NameserverList, Period, PeriodLength, Status,
+};
+use crate::host::{HostCreate, HostDelete};
+use crate::response::ResultCode;
+use crate::Error;
+
+/// A sequence of operations with their expected results
+#[derive(Clone, Debug, Default)]
+pub struct Script {
+    pub steps: Vec<Step>,
+}
+
+impl Script {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a step to the end of the script
+    pub fn step(mut self, name: &str, operation: Operation, expect: Expect) -> Self {
+        self.steps.push(Step {
+            name: name.to_owned(),
+            operation,
+            expect,
+        });
+        self
+    }
+}
+
+/// A single operation of a [`Script`]
+#[derive(Clone, Debug)]
+pub struct Step {
+    /// A description of the step, used in the report
+    pub name: String,
+    pub operation: Operation,
+    pub expect: Expect,
+}
+
+/// The operations a certification script can contain
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub enum Operation {
+    DomainCheck {
+        names: Vec<String>,
+    },
+    DomainCreate {
+        name: String,
+        years: u8,
+        registrant: Option<String>,
+        /// Contacts as `(type, id)` pairs, like `("admin", "ABC-123")`
+        contacts: Vec<(String, String)>,
+        nameservers: Option<NameserverList>,
+        auth_password: String,
+    },
+    DomainInfo {
+        name: String,
+    },
+    /// Add and remove statuses, like `clientHold`
+    DomainUpdateStatuses {
+        name: String,
+        add: Vec<Status>,
+        remove: Vec<Status>,
+    },
+    DomainUpdateNameservers {
+        name: String,
+        add: Option<NameserverList>,
+        remove: Option<NameserverList>,
+    },
+    /// Renew a domain, reading its current expiry date with an info command first
+    ///
+    /// The expiry date is taken in UTC, which is what OT&E environments generally use.
+    DomainRenew {
+        name: String,
+        years: u8,
+    },
+    DomainTransfer {
+        name: String,
+        op: TransferOp,
+        /// Needed to request or query a transfer
+        auth_password: Option<String>,
+    },
+    DomainDelete {
+        name: String,
+    },
+    HostCreate {
+        name: String,
+        addresses: Vec<IpAddr>,
+    },
+    HostDelete {
+        name: String,
+    },
+}
+
+/// The `op` of a domain transfer command
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TransferOp {
+    Request,
+    Query,
+    Approve,
+    Reject,
+    Cancel,
+}
+
+/// The result a step is expected to have
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Expect {
+    /// Any successful result code
+    Success,
+    /// This exact result code
+    Code(ResultCode),
+    /// A successful check reporting the first name as available or not
+    Available(bool),
+}
+
+/// The outcome of running a [`Script`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Report {
+    pub steps: Vec<StepReport>,
+}
+
+impl Report {
+    /// Whether every step had its expected result
+    pub fn passed(&self) -> bool {
+        self.steps.iter().all(|step| step.passed)
+    }
+}
+
+/// One line per step, like `PASS create domain (1000)`
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, step) in self.steps.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{step}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The outcome of a single [`Step`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StepReport {
+    pub name: String,
+    pub passed: bool,
+    /// The result code returned by the registry, if a response was received
+    pub code: Option<ResultCode>,
+    /// What went wrong, for failed steps
+    pub detail: Option<String>,
+}
+
+impl fmt::Display for StepReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verdict = if self.passed { "PASS" } else { "FAIL" };
+        write!(f, "{verdict} {}", self.name)?;
+        if let Some(code) = self.code {
+            write!(f, " ({})", code as u16)?;
+        }
+        if let Some(detail) = &self.detail {
+            write!(f, ": {detail}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Run the steps of `script` in order, continuing after failed steps
+///
+/// Each step is sent with `id` suffixed by `:` and the step number as its client transaction
+/// ID. Registries usually require the whole script to pass in a single session, so a
+/// connection failure fails all remaining steps rather than reconnecting.
+pub async fn run<C: Connector>(client: &mut EppClient<C>, script: &Script, id: &str) -> Report {
+    let mut report = Report::default();
+    let mut connected = true;
+    for (seq, step) in script.steps.iter().enumerate() {
+        let result = if connected {
+            execute(client, &step.operation, &format!("{id}:{}", seq + 1)).await
+        } else {
+            Err(Error::Other("not run after connection failure".into()))
+        };
+
+        if matches!(
+            result,
+            Err(Error::Io(_) | Error::Closed(_) | Error::Timeout)
+        ) {
+            connected = false;
+        }
+
+        report.steps.push(evaluate(&step.name, step.expect, result));
+    }
+
+    report
+}
+
+// The result code and, for checks, the availability of the first name
+struct Outcome {
+    code: ResultCode,
+    available: Option<bool>,
+}
+
+fn evaluate(name: &str, expect: Expect, result: Result<Outcome, Error>) -> StepReport {
+    let outcome = match result {
+        Ok(outcome) => outcome,
+        Err(Error::Command(status)) => Outcome {
+            code: status.result.code,
+            available: None,
+        },
+        Err(err) => {
+            return StepReport {
+                name: name.to_owned(),
+                passed: false,
+                code: None,
+                detail: Some(err.to_string()),
+            }
+        }
+    };
+
+    let detail = match expect {
+        Expect::Success if !outcome.code.is_success() => Some("expected success".to_owned()),
+        Expect::Code(code) if code != outcome.code => {
+            Some(format!("expected result code {}", code as u16))
+        }
+        Expect::Available(available) if outcome.available != Some(available) => {
+            let expected = if available {
+                "available"
+            } else {
+                "unavailable"
+            };
+            Some(format!("expected the name to be {expected}"))
+        }
+        _ => None,
+    };
+
+    StepReport {
+        name: name.to_owned(),
+        passed: detail.is_none(),
+        code: Some(outcome.code),
+        detail,
+    }
+}
+
+async fn execute<C: Connector>(
+    client: &mut EppClient<C>,
+    operation: &Operation,
+    id: &str,
+) -> Result<Outcome, Error> {
+    let code = match operation {
+        Operation::DomainCheck { names } => {
+            let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+            let rsp = client
+                .transact(&DomainCheck { domains: &names }, id)
+                .await?;
+            let available = rsp
+                .res_data()
+                .and_then(|data| data.list.first())
+                .map(|checked| checked.inner.available);
+            return Ok(Outcome {
+                code: rsp.result.code,
+                available,
+            });
+        }
+        Operation::DomainCreate {
+            name,
+            years,
+            registrant,
+            contacts,
+            nameservers,
+            auth_password,
+        } => {
+            let contacts = contacts
+                .iter()
+                .map(|(contact_type, id)| DomainContact {
+                    contact_type: contact_type.into(),
+                    id: id.into(),
+                })
+                .collect::<Vec<_>>();
+            let mut create = DomainCreate::new(
+                name,
+                Period::Years(PeriodLength::new(*years)?),
+                None,
+                registrant.as_deref(),
+                auth_password,
+                Some(contacts.as_slice()).filter(|contacts| !contacts.is_empty()),
+            );
+            if let Some(ns) = nameservers {
+                create.nameservers(ns);
+            }
+            client.transact(&create, id).await?.result.code
+        }
+        Operation::DomainInfo { name } => {
+            let info = DomainInfo::new(name, None);
+            client.transact(&info, id).await?.result.code
+        }
+        Operation::DomainUpdateStatuses { name, add, remove } => {
+            let mut update = DomainUpdate::new(name);
+            if !add.is_empty() {
+                update.add(DomainAdd {
+                    ns: None,
+                    contacts: None,
+                    statuses: Some(add.as_slice()),
+                });
+            }
+            if !remove.is_empty() {
+                update.remove(DomainRemove {
+                    ns: None,
+                    contacts: None,
+                    statuses: Some(remove.as_slice()),
+                });
+            }
+            client.transact(&update, id).await?.result.code
+        }
+        Operation::DomainUpdateNameservers { name, add, remove } => {
+            let mut update = DomainUpdate::new(name);
+            if let Some(add) = add {
+                update.add_nameservers(add);
+            }
+            if let Some(remove) = remove {
+                update.remove_nameservers(remove);
+            }
+            client.transact(&update, id).await?.result.code
+        }
+        Operation::DomainRenew { name, years } => {
+            let info = DomainInfo::new(name, None);
+            let rsp = client.transact(&info, &format!("{id}:info")).await?;
+            let expiry = rsp
+                .data()?
+                .expiry(FixedOffset::east_opt(0).unwrap())
+                .ok_or_else(|| Error::Other(format!("no expiry date for {name}").into()))?;
+            let period = Period::Years(PeriodLength::new(*years)?);
+            let renew = DomainRenew::from_expiry(name, &expiry, period);
+            client.transact(&renew, id).await?.result.code
+        }
+        Operation::DomainTransfer {
+            name,
+            op,
+            auth_password,
+        } => {
+            let password = auth_password.as_deref().unwrap_or_default();
+            let transfer = match op {
+                TransferOp::Request => DomainTransfer::new(name, None, password),
+                TransferOp::Query => DomainTransfer::query(name, password),
+                TransferOp::Approve => DomainTransfer::approve(name),
+                TransferOp::Reject => DomainTransfer::reject(name),
+                TransferOp::Cancel => DomainTransfer::cancel(name),
+            };
+            client.transact(&transfer, id).await?.result.code
+        }
+        Operation::DomainDelete { name } => {
+            let delete = DomainDelete::new(name);
+            client.transact(&delete, id).await?.result.code
+        }
+        Operation::HostCreate { name, addresses } => {
+            let addresses = Some(addresses.as_slice()).filter(|addrs| !addrs.is_empty());
+            let create = HostCreate::new(name, addresses);
+            client.transact(&create, id).await?.result.code
+        }
+        Operation::HostDelete { name } => {
+            let delete = HostDelete::new(name);
+            client.transact(&delete, id).await?.result.code
+        }
+    };
+
+    Ok(Outcome {
+        code,
+        available: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, Expect, Outcome, Report};
+    use crate::response::ResultCode;
+    use crate::Error;
+
+    #[test]
+    fn report() {
+        let outcome = |code, available| {
+            Ok(Outcome {
+                code,
+                available: Some(available),
+            })
+        };
+
+        let report = Report {
+            steps: vec![
+                evaluate(
+                    "check available name",
+                    Expect::Available(true),
+                    outcome(ResultCode::CommandCompletedSuccessfully, true),
+                ),
+                evaluate(
+                    "check registered name",
+                    Expect::Available(false),
+                    outcome(ResultCode::CommandCompletedSuccessfully, true),
+                ),
+                evaluate(
+                    "create domain",
+                    Expect::Code(ResultCode::ObjectExists),
+                    Err(Error::Other("connection reset".into())),
+                ),
+            ],
+        };
+
+        assert!(!report.passed());
+        assert_eq!(
+            report.to_string(),
+            "PASS check available name (1000)\n\
+             FAIL check registered name (1000): expected the name to be unavailable\n\
+             FAIL create domain: error: connection reset"
+        );
+    }
+}
@@ -1,26 +1,47 @@
-use std::time::Duration;
+use std::any::Any;
+use std::error::Error as StdError;
+use std::fmt;
+use std::future::Future;
+use std::time::{Duration, Instant};
 
-#[cfg(feature = "transaction-audit")]
 use std::sync::Arc;
 
-#[cfg(feature = "transaction-audit")]
 use chrono::{DateTime, Utc};
+use tokio::sync::oneshot;
 #[cfg(feature = "__rustls")]
 use tokio_rustls::rustls::pki_types::{CertificateDer, PrivateKeyDer};
-use tracing::{debug, error};
+#[cfg(feature = "otel")]
+use tracing::Instrument;
+use tracing::{debug, error, warn};
 
 #[cfg(feature = "transaction-audit")]
 use crate::audit::{EppTransactionAuditEvent, EppTransactionAuditSink};
+use crate::cltrid::{ClTridCache, ClTridConflict, DuplicateTransaction};
 use crate::common::NoExtension;
-pub use crate::connection::Connector;
 use crate::connection::EppConnection;
+pub use crate::connection::{Connector, InvalidSessionState, SessionState};
+use crate::contact::{self, ContactInfo};
+use crate::domain::{self, DomainInfo};
 use crate::error::Error;
-use crate::hello::{Greeting, Hello};
-use crate::request::{Command, CommandWrapper, Extension, Transaction};
+use crate::hello::{
+    Greeting, GreetingExpectations, GreetingWatch, Hello, HelloHistory, HelloSample,
+};
+use crate::journal::{JournalEntry, JournalOutcome, TransactionJournal};
+use crate::logout::Logout;
+use crate::middleware::{CommandView, Middleware, Request};
+#[cfg(feature = "otel")]
+use crate::otel;
+use crate::pending::{LostResponse, LostResponseCheck, TransactionState, Verified};
+use crate::poll::Ack;
+use crate::profile::SessionProfile;
+use crate::quirks::Quirks;
+pub use crate::request::RequestData;
+use crate::request::{is_transform, Command, Extension, Transaction};
 #[cfg(feature = "transaction-audit")]
 use crate::response::{EppResult, ResponseTRID};
-use crate::response::{Response, ResponseStatus};
-use crate::xml;
+use crate::response::{RawResponse, Reply, Response, ResponseStatus, ResultCode};
+use crate::transport;
+use crate::xml::{self, SerializeOptions, XmlLimits};
 
 /// An `EppClient` provides an interface to sending EPP requests to a registry
 ///
@@ -75,6 +96,22 @@ use crate::xml;
 /// ```
 pub struct EppClient<C: Connector> {
     connection: EppConnection<C>,
+    quirks: Option<Arc<dyn Quirks>>,
+    middleware: Vec<Arc<dyn Middleware>>,
+    cltrid_cache: Option<ClTridCache>,
+    journal: Option<Arc<dyn TransactionJournal>>,
+    greeting_expectations: Option<GreetingExpectations>,
+    greeting_watch: Option<Arc<GreetingWatch>>,
+    lost_response_check: Option<LostResponseCheck<C>>,
+    profile: Option<SessionProfile>,
+    retain_raw: bool,
+    unchecked_raw: bool,
+    // The ID of the last poll message retrieved but not yet acknowledged
+    unacked_message: Option<String>,
+    // A command from `submit()` whose response is still to be read
+    submitted: Option<Submitted<C>>,
+    #[cfg(feature = "otel")]
+    trace_cltrid: bool,
     #[cfg(feature = "transaction-audit")]
     audit_sink: Option<Arc<dyn EppTransactionAuditSink>>,
 }
@@ -133,6 +170,20 @@ impl<C: Connector> EppClient<C> {
     pub async fn new(connector: C, registry: String, timeout: Duration) -> Result<Self, Error> {
         Ok(Self {
             connection: EppConnection::new(connector, registry, timeout).await?,
+            quirks: None,
+            middleware: Vec::new(),
+            cltrid_cache: None,
+            journal: None,
+            greeting_expectations: None,
+            greeting_watch: None,
+            lost_response_check: None,
+            profile: None,
+            retain_raw: false,
+            unchecked_raw: false,
+            unacked_message: None,
+            submitted: None,
+            #[cfg(feature = "otel")]
+            trace_cltrid: false,
             #[cfg(feature = "transaction-audit")]
             audit_sink: None,
         })
@@ -148,6 +199,20 @@ impl<C: Connector> EppClient<C> {
     ) -> Result<Self, Error> {
         Ok(Self {
             connection: EppConnection::new(connector, registry, timeout).await?,
+            quirks: None,
+            middleware: Vec::new(),
+            cltrid_cache: None,
+            journal: None,
+            greeting_expectations: None,
+            greeting_watch: None,
+            lost_response_check: None,
+            profile: None,
+            retain_raw: false,
+            unchecked_raw: false,
+            unacked_message: None,
+            submitted: None,
+            #[cfg(feature = "otel")]
+            trace_cltrid: false,
             audit_sink,
         })
     }
@@ -158,9 +223,121 @@ impl<C: Connector> EppClient<C> {
         self.audit_sink = audit_sink;
     }
 
+    /// Set the adjustments applied to requests and responses for this registry
+    pub fn set_quirks(&mut self, quirks: Option<Arc<dyn Quirks>>) {
+        self.quirks = quirks;
+    }
+
+    /// Add a [`Middleware`] to the chain that sees every command sent through
+    /// [`EppClient::transact()`]
+    pub fn add_middleware(&mut self, middleware: Arc<dyn Middleware>) {
+        self.middleware.push(middleware);
+    }
+
+    /// Remember the clTRIDs of recent transforms, to avoid sending a transform twice
+    ///
+    /// See the [`cltrid`](crate::cltrid) module for details.
+    pub fn set_cltrid_cache(&mut self, cache: Option<ClTridCache>) {
+        self.cltrid_cache = cache;
+    }
+
+    /// Record every transform in `journal` before it is sent and when its response arrives
+    pub fn set_journal(&mut self, journal: Option<Arc<dyn TransactionJournal>>) {
+        self.journal = journal;
+    }
+
+    /// Allow transforms through [`EppClient::transact_raw()`] and [`EppClient::transact_xml()`]
+    ///
+    /// Raw documents are sent as-is, so [`Middleware`] (like an
+    /// [`Interlock`](crate::interlock::Interlock)), the clTRID cache and the journal can't tell
+    /// what they do. Once any of these is set, raw documents with a transform verb (`create`,
+    /// `delete`, `renew`, `transfer` or `update`) or no recognizable verb are refused with
+    /// [`UncheckedCommand`], unless this is enabled.
+    pub fn set_unchecked_raw(&mut self, allow: bool) {
+        self.unchecked_raw = allow;
+    }
+
+    /// Check the server's greeting against `expectations`, now and after every reconnect
+    ///
+    /// Set this right after connecting, before logging in: if the greeting received on
+    /// connection does not match, this returns a
+    /// [`GreetingMismatch`](crate::hello::GreetingMismatch) error.
+    pub fn set_greeting_expectations(
+        &mut self,
+        expectations: Option<GreetingExpectations>,
+    ) -> Result<(), Error> {
+        if let Some(expectations) = &expectations {
+            expectations.check(&self.greeting()?)?;
+        }
+
+        self.greeting_expectations = expectations;
+        Ok(())
+    }
+
+    /// Report changes between the greetings received on reconnects (and `<hello>`) to `watch`
+    ///
+    /// The greeting of the current connection is observed right away.
+    pub fn set_greeting_watch(&mut self, watch: Option<Arc<GreetingWatch>>) -> Result<(), Error> {
+        if let Some(watch) = &watch {
+            watch.observe(self.greeting()?, Utc::now());
+        }
+
+        self.greeting_watch = watch;
+        Ok(())
+    }
+
+    /// Find out whether a domain or contact create or delete took effect when its response is
+    /// lost
+    ///
+    /// If the connection breaks or times out after such a command was sent, the client
+    /// reconnects, logs in with `check` and sends an `<info>` for the object (with `:login` and
+    /// `:info` appended to the client transaction ID) before returning a
+    /// [`LostResponse`] error. The session is left logged in.
+    ///
+    /// A create took effect if the object is sponsored by the registrar of `check` and was
+    /// created after the command was sent, going by the clock skew measured with
+    /// [`EppClient::hello()`] and allowing a second for dates truncated by the registry.
+    pub fn set_lost_response_check(&mut self, check: Option<LostResponseCheck<C>>) {
+        self.lost_response_check = check;
+    }
+
+    /// Refuse commands carrying extensions outside `profile`
+    ///
+    /// Use [`SessionProfile::login()`] to negotiate only the extensions of the profile when
+    /// logging in. Commands are checked as they are serialized, before anything is sent.
+    pub fn set_profile(&mut self, profile: Option<SessionProfile>) {
+        self.profile = profile;
+    }
+
+    /// Replace the limits every received document is checked against
+    ///
+    /// [`XmlLimits::default()`] applies unless changed; `None` disables the checks. A document
+    /// exceeding the limits is returned as [`Error::Xml`] without being parsed.
+    pub fn set_xml_limits(&mut self, limits: Option<XmlLimits>) {
+        self.connection.xml_limits = limits;
+    }
+
+    /// Keep the raw XML of every response, available through [`Response::raw_xml()`]
+    pub fn set_retain_raw(&mut self, retain: bool) {
+        self.retain_raw = retain;
+    }
+
+    /// Append the current OpenTelemetry trace ID to the client transaction ID of each command
+    ///
+    /// The ID is left unchanged if there is no active trace, or if the result would exceed
+    /// the 64 characters allowed for client transaction IDs.
+    #[cfg(feature = "otel")]
+    pub fn set_trace_cltrid(&mut self, enabled: bool) {
+        self.trace_cltrid = enabled;
+    }
+
     /// Executes an EPP Hello call and returns the response as a `Greeting`
+    ///
+    /// The round trip time and the server's date are kept in the [`EppClient::hello_history()`].
     pub async fn hello(&mut self) -> Result<Greeting, Error> {
+        self.flush().await;
         let xml = xml::serialize(Hello)?;
+        let (sent_at, started) = (Utc::now(), Instant::now());
 
         debug!("{}: hello: {}", self.connection.registry, &xml);
         #[cfg(feature = "transaction-audit")]
@@ -181,6 +358,7 @@ impl<C: Connector> EppClient<C> {
 
         #[cfg(not(feature = "transaction-audit"))]
         let response = self.connection.transact(&xml)?.await?;
+        let rtt = started.elapsed();
         debug!("{}: greeting: {}", self.connection.registry, &response);
 
         let greeting = match xml::deserialize::<Greeting>(&response) {
@@ -215,6 +393,15 @@ impl<C: Connector> EppClient<C> {
             error: None,
         });
 
+        self.connection.hellos.record(HelloSample {
+            sent_at,
+            rtt,
+            service_date: greeting.service_date,
+        });
+        if let Some(watch) = &self.greeting_watch {
+            watch.observe(greeting.clone(), Utc::now());
+        }
+
         Ok(greeting)
     }
 
@@ -223,43 +410,723 @@ impl<C: Connector> EppClient<C> {
         data: impl Into<RequestData<'c, 'e, Cmd, Ext>>,
         id: &str,
     ) -> Result<Response<Cmd::Response, Ext::Response>, Error>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+    {
+        self.transact_inner(data.into(), id, self.retain_raw).await
+    }
+
+    /// Send a batch of commands, collecting the result of each
+    ///
+    /// The commands are sent one after the other on this session, with `id` suffixed by `:`
+    /// and a sequence number as their client transaction IDs. A failing command does not stop
+    /// the batch; its error is returned in its place in the results. To run a batch over
+    /// several sessions, see `EppPool::transact_batch()` (with the `pool` feature).
+    pub async fn transact_batch<'c, 'e, Cmd, Ext, D>(
+        &mut self,
+        batch: impl IntoIterator<Item = D>,
+        id: &str,
+    ) -> Vec<Result<Response<Cmd::Response, Ext::Response>, Error>>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+        D: Into<RequestData<'c, 'e, Cmd, Ext>>,
+    {
+        let mut results = Vec::new();
+        for (seq, data) in batch.into_iter().enumerate() {
+            let id = format!("{id}:{}", seq + 1);
+            results.push(self.transact(data, &id).await);
+        }
+
+        results
+    }
+
+    /// Render the XML [`EppClient::transact()`] would send for a command, without sending it
+    ///
+    /// The XML goes through [`Quirks`] like it would when sending. Middleware is not run, so
+    /// a command a [`Middleware`] would reject or give another client transaction ID is
+    /// rendered as given.
+    pub fn render<'c, 'e, Cmd, Ext>(
+        &self,
+        data: impl Into<RequestData<'c, 'e, Cmd, Ext>>,
+        id: &str,
+    ) -> Result<RenderedCommand, Error>
     where
         Cmd: Transaction<Ext> + Command + 'c,
         Ext: Extension + 'e,
     {
         let data = data.into();
-        let document = CommandWrapper::new(data.command, data.extension, id);
-        let xml = xml::serialize(&document)?;
+        Ok(RenderedCommand {
+            id: id.to_owned(),
+            command: Cmd::COMMAND,
+            object: data.command.view().object().map(ToOwned::to_owned),
+            xml: self.request_xml(data, id)?,
+        })
+    }
+
+    /// Render a batch of commands as [`EppClient::transact_batch()`] would send them
+    ///
+    /// Use this to review the commands of a large batch before sending it; the client
+    /// transaction IDs match the ones `transact_batch()` uses for the same `id`.
+    pub fn render_batch<'c, 'e, Cmd, Ext, D>(
+        &self,
+        batch: impl IntoIterator<Item = D>,
+        id: &str,
+    ) -> Result<Vec<RenderedCommand>, Error>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+        D: Into<RequestData<'c, 'e, Cmd, Ext>>,
+    {
+        batch
+            .into_iter()
+            .enumerate()
+            .map(|(seq, data)| self.render(data, &format!("{id}:{}", seq + 1)))
+            .collect()
+    }
+
+    /// Like [`EppClient::transact()`], but keeps the raw XML of this response regardless of
+    /// [`EppClient::set_retain_raw()`]
+    pub async fn transact_retaining_raw<'c, 'e, Cmd, Ext>(
+        &mut self,
+        data: impl Into<RequestData<'c, 'e, Cmd, Ext>>,
+        id: &str,
+    ) -> Result<Response<Cmd::Response, Ext::Response>, Error>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+    {
+        self.transact_inner(data.into(), id, true).await
+    }
+
+    /// Take the transform sent with `id` off the journal's list of transforms in doubt
+    ///
+    /// Call this once the outcome of a transform listed by
+    /// [`TransactionJournal::in_doubt()`] has been established, like by looking up the object
+    /// at the registry. Does nothing without a journal.
+    pub fn resolve_transaction(&self, id: &str) -> Result<(), Error> {
+        match &self.journal {
+            Some(journal) => journal.resolve(id),
+            None => Ok(()),
+        }
+    }
+
+    /// What is known about the transform sent with `id`
+    ///
+    /// Only transforms are tracked, by the [`ClTridCache`] (which also survives a reconnect) and
+    /// the [`TransactionJournal`] (which also survives a restart), if set. Without either, the
+    /// state is always [`TransactionState::Unknown`].
+    pub fn transaction_state(&self, id: &str) -> Result<TransactionState, Error> {
+        if let Some(entry) = self.cltrid_cache.as_ref().and_then(|cache| cache.get(id)) {
+            return Ok(match entry.response {
+                Some(_) => TransactionState::Completed,
+                None => TransactionState::InDoubt,
+            });
+        }
+
+        if let Some(journal) = &self.journal {
+            if journal
+                .in_doubt()?
+                .iter()
+                .any(|entry| entry.client_tr_id == id)
+            {
+                return Ok(TransactionState::InDoubt);
+            }
+        }
+
+        Ok(TransactionState::Unknown)
+    }
+
+    /// The response to the transform sent with `id`, if it is in the clTRID cache
+    ///
+    /// A response with an error result code is returned as [`Error::Command`], as from
+    /// [`EppClient::transact()`].
+    pub fn completed<Cmd: Command, Ext: Extension>(
+        &self,
+        id: &str,
+    ) -> Option<TransactResult<Cmd, Ext>> {
+        let entry = self.cltrid_cache.as_ref()?.get(id)?;
+        let response = entry.response.clone()?;
+        if entry.command != Cmd::COMMAND {
+            return Some(Err(ClTridConflict {
+                id: id.to_owned(),
+                command: Cmd::COMMAND,
+                previous: entry.command,
+            }
+            .into()));
+        }
+
+        Some(self.replay(response, self.retain_raw))
+    }
+
+    #[cfg(feature = "otel")]
+    pub(crate) async fn transact_inner<'c, 'e, Cmd, Ext, R>(
+        &mut self,
+        data: RequestData<'c, 'e, Cmd, Ext>,
+        id: &str,
+        retain_raw: bool,
+    ) -> Result<R, Error>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+        R: Reply,
+    {
+        let view = data.command.view();
+        let span = otel::command_span(&self.connection.registry, &view, Cmd::COMMAND);
+        let id = if self.trace_cltrid {
+            otel::traced_id(&span, id)
+        } else {
+            id.into()
+        };
+
+        let result = self
+            .send_command(data, &id, retain_raw)
+            .instrument(span.clone())
+            .await;
+        otel::record_result(&span, &result, |rsp: &R| rsp.results().code);
+        result
+    }
+
+    #[cfg(not(feature = "otel"))]
+    pub(crate) async fn transact_inner<'c, 'e, Cmd, Ext, R>(
+        &mut self,
+        data: RequestData<'c, 'e, Cmd, Ext>,
+        id: &str,
+        retain_raw: bool,
+    ) -> Result<R, Error>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+        R: Reply,
+    {
+        self.send_command(data, id, retain_raw).await
+    }
+
+    async fn send_command<'c, 'e, Cmd, Ext, R>(
+        &mut self,
+        data: RequestData<'c, 'e, Cmd, Ext>,
+        id: &str,
+        retain_raw: bool,
+    ) -> Result<R, Error>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+        R: Reply,
+    {
+        self.flush().await;
+        if self.middleware.is_empty() {
+            return self.exchange_checked(data, id, retain_raw).await;
+        }
+
+        let middleware = self.middleware.clone();
+        let mut request = Request::new(data.command.view(), Cmd::COMMAND, id);
+        self.request_middleware(&middleware, &mut request)?;
+
+        let data = RequestData {
+            replacement: request.replacement(),
+            ..data
+        };
+        let result: Result<R, _> = self.exchange_checked(data, &request.id, retain_raw).await;
+        let response = match &result {
+            Ok(rsp) => {
+                let ext = rsp
+                    .extension()
+                    .and_then(|ext| ext.downcast_ref::<Ext::Response>());
+                request.set_balance(ext.and_then(Ext::balance));
+                Ok(rsp as &dyn Any)
+            }
+            Err(err) => Err(err),
+        };
+
+        for layer in middleware.iter().rev() {
+            layer.response(&request, response);
+        }
+
+        result
+    }
+
+    // Write a command for `submit()`, leaving its response to be read before the next command
+    //
+    // The response, or the error the command failed with, is passed to `sender`. Returns the
+    // clTRID the command is sent with, as middleware may change it.
+    pub(crate) async fn write_submitted<'c, 'e, Cmd, Ext>(
+        &mut self,
+        data: RequestData<'c, 'e, Cmd, Ext>,
+        id: &str,
+        sender: oneshot::Sender<TransactResult<Cmd, Ext>>,
+    ) -> Result<String, Error>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+        Cmd::Response: Send,
+        Ext::Response: Send,
+    {
+        self.flush().await;
+
+        #[cfg(feature = "otel")]
+        let span = otel::command_span(
+            &self.connection.registry,
+            &data.command.view(),
+            Cmd::COMMAND,
+        );
+        #[cfg(feature = "otel")]
+        let id = if self.trace_cltrid {
+            otel::traced_id(&span, id)
+        } else {
+            id.into()
+        };
+        #[cfg(feature = "otel")]
+        let id: &str = &id;
+
+        let middleware = self.middleware.clone();
+        let mut request = Request::new(data.command.view(), Cmd::COMMAND, id);
+        self.request_middleware(&middleware, &mut request)?;
+
+        let lost = LostTarget::of(data.command.view());
+        let data = RequestData {
+            replacement: request.replacement(),
+            ..data
+        };
+        let retain_raw = self.retain_raw;
+        let prepared = self.prepare(data, &request.id, retain_raw);
+        let request = request.detach();
+        let id = request.id.clone();
+        let finish = move |result: TransactResult<Cmd, Ext>| {
+            let mut request = request;
+            let response = match &result {
+                Ok(rsp) => {
+                    request.set_balance(rsp.extension().and_then(Ext::balance));
+                    Ok(rsp as &dyn Any)
+                }
+                Err(err) => Err(err),
+            };
+            for layer in middleware.iter().rev() {
+                layer.response(&request, response);
+            }
+
+            #[cfg(feature = "otel")]
+            otel::record_result(&span, &result, |rsp| rsp.result.code);
+            result
+        };
+
+        // Sending fails if the handle was dropped, which leaves nobody to take the response
+        let outgoing = match prepared {
+            Ok(Prepared::Send(outgoing)) => outgoing,
+            Ok(Prepared::Replayed(rsp)) => {
+                let _ = sender.send(finish(Ok(rsp)));
+                return Ok(id);
+            }
+            Err(err) => return finish(Err(err)).map(|_| id),
+        };
+
+        let written = self.connection.send(&outgoing.xml).await;
+        let submitted = Submitted {
+            outgoing,
+            lost,
+            deliver: Box::new(move |client: &mut Self, response| {
+                let result = match response {
+                    Ok((outgoing, response)) => {
+                        client.received(outgoing, Ok(response), true, retain_raw)
+                    }
+                    Err(err) => Err(err),
+                };
+                let _ = sender.send(finish(result));
+            }),
+        };
+
+        match written {
+            Ok(()) => self.submitted = Some(submitted),
+            Err(err) => self.finish_submitted(submitted, Err(err)).await,
+        }
+        Ok(id)
+    }
+
+    /// Read the response to the command from [`EppClient::submit()`], if one is outstanding
+    ///
+    /// This resolves its [`PendingTransaction`](crate::pending::PendingTransaction). The client
+    /// does this itself before sending the next command.
+    pub async fn flush(&mut self) {
+        if let Some(submitted) = self.submitted.take() {
+            let response = self.connection.receive().await;
+            self.finish_submitted(submitted, response).await;
+        }
+    }
+
+    async fn finish_submitted(&mut self, submitted: Submitted<C>, response: Result<String, Error>) {
+        let Submitted {
+            outgoing,
+            lost,
+            deliver,
+        } = submitted;
+
+        let response = match response {
+            Ok(response) => Ok((outgoing, response)),
+            Err(err) => {
+                self.failed(&outgoing, &err, self.connection.request_written());
+                match (err, lost) {
+                    // The command may have reached the registry before the connection broke
+                    (err @ (Error::Io(_) | Error::Timeout), Some(target))
+                        if self.lost_response_check.is_some() =>
+                    {
+                        Err(self.check_lost(target, &outgoing.id, err).await)
+                    }
+                    (err, _) => Err(err),
+                }
+            }
+        };
+
+        deliver(self, response);
+    }
+
+    // Run the request hooks of `middleware`, unwinding the ones that ran if one rejects
+    //
+    // Middleware may replace the command with one it serializes.
+    fn request_middleware(
+        &self,
+        middleware: &[Arc<dyn Middleware>],
+        request: &mut Request<'_>,
+    ) -> Result<(), Error> {
+        let rejected = xml::with_options(self.serialize_options(), || {
+            middleware
+                .iter()
+                .enumerate()
+                .find_map(|(i, layer)| layer.request(request).err().map(|err| (i, err)))
+        });
+        match rejected {
+            Some((i, err)) => {
+                for layer in middleware[..i].iter().rev() {
+                    layer.response(request, Err(&err));
+                }
+                Err(err)
+            }
+            None => Ok(()),
+        }
+    }
+
+    async fn exchange_checked<'c, 'e, Cmd, Ext, R>(
+        &mut self,
+        data: RequestData<'c, 'e, Cmd, Ext>,
+        id: &str,
+        retain_raw: bool,
+    ) -> Result<R, Error>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+        R: Reply,
+    {
+        let lost = match &self.lost_response_check {
+            Some(_) => LostTarget::of(data.command.view()),
+            None => None,
+        };
+
+        match (self.exchange(data, id, retain_raw).await, lost) {
+            // The command may have reached the registry before the connection broke
+            (Err(err @ (Error::Io(_) | Error::Timeout)), Some(target)) => {
+                Err(self.check_lost(target, id, err).await)
+            }
+            (result, _) => result,
+        }
+    }
+
+    // Look up the object of a create or delete whose response was lost
+    async fn check_lost(&mut self, target: LostTarget, id: &str, cause: Error) -> Error {
+        let LostTarget {
+            command,
+            object,
+            domain,
+            sent_at,
+        } = target;
+        let object = object.as_str();
+
+        let check = match &self.lost_response_check {
+            Some(check) => check.clone(),
+            None => return cause,
+        };
+
+        // The earliest creation date the registry could give an object created by the command
+        let skew = self.connection.hellos.skew().unwrap_or_default();
+        let earliest = sent_at + skew - chrono::Duration::seconds(1);
+        let verified = match self.lost_object(&check, object, domain, id).await {
+            Ok(Some(found)) => match command {
+                "create" if found.client_id != check.client_id() => Verified::NotApplied,
+                "create" => match found.created_at {
+                    Some(created_at) if created_at >= earliest => Verified::Applied,
+                    Some(_) => Verified::NotApplied,
+                    None => Verified::Unknown,
+                },
+                "delete" if found.pending_delete => Verified::Applied,
+                _ => Verified::NotApplied,
+            },
+            Ok(None) => match command {
+                "create" => Verified::NotApplied,
+                _ => Verified::Applied,
+            },
+            Err(err) => {
+                error!(
+                    "{}: failed to look up {object} after losing response to {id}: {err}",
+                    self.connection.registry
+                );
+                Verified::Unknown
+            }
+        };
+
+        LostResponse {
+            id: id.to_owned(),
+            command,
+            object: object.to_owned(),
+            verified,
+            cause,
+        }
+        .into()
+    }
+
+    // The object as found after logging in on a new connection; `None` if it does not exist
+    async fn lost_object(
+        &mut self,
+        check: &LostResponseCheck<C>,
+        object: &str,
+        domain: bool,
+        id: &str,
+    ) -> Result<Option<LostObject>, Error> {
+        self.reconnect_checked().await?;
+        check.relogin(self, &format!("{id}:login")).await?;
+
+        let info_id = format!("{id}:info");
+        let found = match domain {
+            true => {
+                let info = DomainInfo::new(object, None);
+                let found = self
+                    .exchange::<_, _, Response<domain::InfoData, NoExtension>>(
+                        (&info).into(),
+                        &info_id,
+                        false,
+                    )
+                    .await;
+                match found {
+                    Ok(rsp) => rsp.into_data().map(|info| {
+                        let statuses = info.statuses.unwrap_or_default();
+                        LostObject {
+                            client_id: info.client_id,
+                            created_at: info.created_at,
+                            pending_delete: statuses.contains(&domain::Status::PendingDelete),
+                        }
+                    }),
+                    Err(err) => Err(err),
+                }
+            }
+            false => {
+                // The sponsoring registrar can query its contacts without their password
+                let info = ContactInfo::sponsored(object);
+                let found = self
+                    .exchange::<_, _, Response<contact::info::InfoData, NoExtension>>(
+                        (&info).into(),
+                        &info_id,
+                        false,
+                    )
+                    .await;
+                match found {
+                    Ok(rsp) => rsp.into_data().map(|info| LostObject {
+                        pending_delete: info.statuses.contains(&contact::Status::PendingDelete),
+                        client_id: info.client_id,
+                        created_at: Some(info.created_at),
+                    }),
+                    Err(err) => Err(err),
+                }
+            }
+        };
+
+        match found {
+            Ok(found) => Ok(Some(found)),
+            Err(err)
+                if err
+                    .result()
+                    .is_some_and(|result| result.code == ResultCode::ObjectDoesNotExist) =>
+            {
+                Ok(None)
+            }
+            Err(err) => Err(err),
+        }
+    }
+
+    async fn exchange<'c, 'e, Cmd, Ext, R>(
+        &mut self,
+        data: RequestData<'c, 'e, Cmd, Ext>,
+        id: &str,
+        retain_raw: bool,
+    ) -> Result<R, Error>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+        R: Reply,
+    {
+        let outgoing = match self.prepare(data, id, retain_raw)? {
+            Prepared::Replayed(rsp) => return Ok(rsp),
+            Prepared::Send(outgoing) => outgoing,
+        };
+
+        let response = match self.connection.transact(&outgoing.xml) {
+            Ok(future) => future.await,
+            Err(err) => Err(err),
+        };
+        let written = self.connection.request_written();
+        self.received(outgoing, response, written, retain_raw)
+    }
+
+    // Check a command against the session state and the clTRID cache, and serialize it
+    //
+    // A transform repeated with the clTRID of one that was answered is answered from the cache.
+    fn prepare<'c, 'e, Cmd, Ext, R>(
+        &mut self,
+        data: RequestData<'c, 'e, Cmd, Ext>,
+        id: &str,
+        retain_raw: bool,
+    ) -> Result<Prepared<R>, Error>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+        R: Reply,
+    {
+        self.connection.state.check(Cmd::COMMAND)?;
+
+        let transform = is_transform(Cmd::COMMAND, &data.command.view());
+        let cached = match &self.cltrid_cache {
+            Some(cache) if transform => cache
+                .get(id)
+                .map(|entry| (entry.command, entry.response.clone())),
+            _ => None,
+        };
+
+        match cached {
+            Some((previous, _)) if previous != Cmd::COMMAND => {
+                return Err(ClTridConflict {
+                    id: id.to_owned(),
+                    command: Cmd::COMMAND,
+                    previous,
+                }
+                .into())
+            }
+            Some((_, Some(response))) => {
+                debug!("{}: answering {id} from cache", self.connection.registry);
+                return self.replay(response, retain_raw).map(Prepared::Replayed);
+            }
+            Some((command, None)) => {
+                return Err(DuplicateTransaction {
+                    id: id.to_owned(),
+                    command,
+                }
+                .into())
+            }
+            None => {}
+        }
+
+        let xml = self.request_xml(data, id)?;
+
+        let journal = match &self.journal {
+            Some(journal) if transform => {
+                journal.begin(&JournalEntry {
+                    registry: self.connection.registry.clone(),
+                    command: Cmd::COMMAND.to_owned(),
+                    object: data.command.view().object().map(ToOwned::to_owned),
+                    client_tr_id: id.to_owned(),
+                })?;
+                Some(journal.clone())
+            }
+            _ => None,
+        };
 
         debug!("{}: request: {}", self.connection.registry, &xml);
-        #[cfg(feature = "transaction-audit")]
-        let (response, request_at, response_at) = {
-            let audited = self
-                .send_raw_transaction_for_audit(
-                    &xml,
-                    Some(Cmd::COMMAND),
-                    Some(std::any::type_name::<Cmd>()),
-                )
-                .await?;
-            (
-                audited.raw_response,
-                audited.request_at,
-                audited.response_at,
-            )
+        // The clTRID is used once the request is handed to the connection; should the future
+        // sending it be dropped, the connection still writes it out
+        if let (Some(cache), true) = (&mut self.cltrid_cache, transform) {
+            cache.begin(id, Cmd::COMMAND);
+        }
+
+        Ok(Prepared::Send(Outgoing {
+            id: id.to_owned(),
+            command: Cmd::COMMAND,
+            #[cfg(feature = "transaction-audit")]
+            command_type: std::any::type_name::<Cmd>(),
+            xml,
+            journal,
+            #[cfg(feature = "transaction-audit")]
+            request_at: Utc::now(),
+        }))
+    }
+
+    // Handle the response to a request from `prepare()`
+    //
+    // `written` tells whether the request was written out in full, should sending it fail.
+    fn received<R: Reply>(
+        &mut self,
+        outgoing: Outgoing,
+        response: Result<String, Error>,
+        written: bool,
+        retain_raw: bool,
+    ) -> Result<R, Error> {
+        let response = match response {
+            Ok(response) => response,
+            Err(err) => {
+                self.failed(&outgoing, &err, written);
+                return Err(err);
+            }
         };
 
-        #[cfg(not(feature = "transaction-audit"))]
-        let response = self.connection.transact(&xml)?.await?;
+        let Outgoing {
+            id,
+            command,
+            #[cfg(feature = "transaction-audit")]
+            command_type,
+            #[cfg(feature = "transaction-audit")]
+            xml,
+            journal,
+            #[cfg(feature = "transaction-audit")]
+            request_at,
+            ..
+        } = outgoing;
+        let id = id.as_str();
+        #[cfg(feature = "transaction-audit")]
+        let response_at = Utc::now();
+
         debug!("{}: response: {}", self.connection.registry, &response);
+        if let Some(cache) = &mut self.cltrid_cache {
+            cache.complete(id, &response);
+        }
+
+        let result = self.parse::<R>(&response);
+
+        if let Some(journal) = journal {
+            // A response whose data can't be parsed still has a result code
+            let outcome = match &result {
+                Ok(rsp) => Some((rsp.results().code, rsp.tr_ids().server_tr_id.clone())),
+                Err(_) => self
+                    .response_status(&response)
+                    .map(|status| (status.result.code, status.tr_ids.server_tr_id)),
+            };
+
+            match outcome {
+                Some((code, server_tr_id)) => {
+                    let outcome = JournalOutcome { code, server_tr_id };
+                    self.journal_result(id, journal.complete(id, &outcome));
+                }
+                None => error!(
+                    "{}: response to {id} has no readable result, leaving it in doubt",
+                    self.connection.registry
+                ),
+            }
+        }
 
-        let rsp = match xml::deserialize::<Response<Cmd::Response, Ext::Response>>(&response) {
-            Ok(rsp) => rsp,
+        let mut rsp = match result {
+            Ok(rsp) => {
+                self.connection.observe_result(Some(command), rsp.results());
+                rsp
+            }
             Err(e) => {
                 error!(%response, "failed to deserialize response for transaction: {e}");
                 #[cfg(feature = "transaction-audit")]
                 self.record_audit_event(AuditRecord {
-                    command: Some(Cmd::COMMAND),
-                    command_type: Some(std::any::type_name::<Cmd>()),
+                    command: Some(command),
+                    command_type: Some(command_type),
                     request_at,
                     response_at,
                     raw_request: &xml,
@@ -272,33 +1139,45 @@ impl<C: Connector> EppClient<C> {
             }
         };
 
-        if rsp.result.code.is_success() {
+        if rsp.results().code.is_success() {
             #[cfg(feature = "transaction-audit")]
             self.record_audit_event(AuditRecord {
-                command: Some(Cmd::COMMAND),
-                command_type: Some(std::any::type_name::<Cmd>()),
+                command: Some(command),
+                command_type: Some(command_type),
                 request_at,
                 response_at,
                 raw_request: &xml,
                 raw_response: Some(&response),
-                metadata: audit_metadata_from_response_parts(&rsp.result, &rsp.tr_ids),
+                metadata: audit_metadata_from_response_parts(rsp.results(), rsp.tr_ids()),
                 succeeded: true,
                 error: None,
             });
+
+            if command == "poll" {
+                self.unacked_message = match rsp.results().code {
+                    ResultCode::CommandCompletedSuccessfullyAckToDequeue => rsp.message_id(),
+                    _ => None,
+                };
+            }
+
+            if retain_raw {
+                rsp.retain_raw(response);
+            }
             return Ok(rsp);
         }
 
         #[cfg(feature = "transaction-audit")]
-        let metadata = audit_metadata_from_response_parts(&rsp.result, &rsp.tr_ids);
-        let err = crate::error::Error::Command(Box::new(ResponseStatus {
-            result: rsp.result,
-            tr_ids: rsp.tr_ids,
-        }));
+        let metadata = audit_metadata_from_response_parts(rsp.results(), rsp.tr_ids());
+        // Error responses are the ones worth keeping for debugging
+        if retain_raw {
+            rsp.retain_raw(response.clone());
+        }
+        let err = crate::error::Error::Command(Box::new(rsp.into_status()));
 
         #[cfg(feature = "transaction-audit")]
         self.record_audit_event(AuditRecord {
-            command: Some(Cmd::COMMAND),
-            command_type: Some(std::any::type_name::<Cmd>()),
+            command: Some(command),
+            command_type: Some(command_type),
             request_at,
             response_at,
             raw_request: &xml,
@@ -311,9 +1190,199 @@ impl<C: Connector> EppClient<C> {
         Err(err)
     }
 
+    // Record a request from `prepare()` that got no response
+    fn failed(&mut self, outgoing: &Outgoing, err: &Error, written: bool) {
+        #[cfg(feature = "transaction-audit")]
+        self.record_audit_event(AuditRecord {
+            command: Some(outgoing.command),
+            command_type: Some(outgoing.command_type),
+            request_at: outgoing.request_at,
+            response_at: Utc::now(),
+            raw_request: &outgoing.xml,
+            raw_response: None,
+            metadata: AuditResponseMetadata::default(),
+            succeeded: false,
+            error: Some(err.to_string()),
+        });
+        #[cfg(not(feature = "transaction-audit"))]
+        let _ = err;
+
+        // A request that never fully reached the registry can safely be sent again
+        if !written {
+            if let Some(cache) = &mut self.cltrid_cache {
+                cache.abandon(&outgoing.id);
+            }
+            if let Some(journal) = &outgoing.journal {
+                self.journal_result(&outgoing.id, journal.resolve(&outgoing.id));
+            }
+        }
+    }
+
+    // Answer a repeated transform from the response received for its first attempt
+    fn replay<R: Reply>(&self, response: String, retain_raw: bool) -> Result<R, Error> {
+        let mut rsp = self.parse::<R>(&response)?;
+
+        if retain_raw {
+            rsp.retain_raw(response);
+        }
+        if !rsp.results().code.is_success() {
+            return Err(Error::Command(Box::new(rsp.into_status())));
+        }
+
+        Ok(rsp)
+    }
+
+    // Parse `response` with the quirks in effect, keeping the values only their parse options
+    // accepted with the response
+    fn parse<R: Reply>(&self, response: &str) -> Result<R, Error> {
+        let Some(quirks) = &self.quirks else {
+            return R::parse(response);
+        };
+
+        let options = quirks.parsing().copied().unwrap_or_default();
+        let (result, warnings) =
+            xml::with_parse_options(options, || R::parse(&quirks.response(response.into())));
+        for warning in &warnings {
+            warn!(
+                field = warning.field,
+                original = %warning.original,
+                normalized = %warning.normalized,
+                "{}: accepted non-conforming value in response",
+                self.connection.registry
+            );
+        }
+
+        let mut rsp = result?;
+        rsp.set_scalar_warnings(warnings);
+        Ok(rsp)
+    }
+
+    // The result and transaction IDs of a response that may not parse as a whole
+    fn response_status(&self, response: &str) -> Option<ResponseStatus> {
+        let status = match &self.quirks {
+            Some(quirks) => {
+                let options = quirks.parsing().copied().unwrap_or_default();
+                xml::with_parse_options(options, || {
+                    xml::deserialize(&quirks.response(response.into()))
+                })
+                .0
+            }
+            None => xml::deserialize(response),
+        };
+        status.ok()
+    }
+
+    fn journal_result(&self, id: &str, result: Result<(), Error>) {
+        if let Err(err) = result {
+            error!(
+                "{}: failed to update the journal for {id}: {err}",
+                self.connection.registry
+            );
+        }
+    }
+
+    fn serialize_options(&self) -> Option<&SerializeOptions> {
+        self.quirks
+            .as_deref()
+            .and_then(|quirks| quirks.serialization())
+    }
+
+    // Check a command against the session profile, serialize it and apply the request quirks
+    fn request_xml<'c, 'e, Cmd, Ext>(
+        &self,
+        data: RequestData<'c, 'e, Cmd, Ext>,
+        id: &str,
+    ) -> Result<String, Error>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+    {
+        if let (Some(profile), Some(extension)) = (&self.profile, data.extension) {
+            profile.check(extension)?;
+        }
+
+        let xml = xml::with_options(self.serialize_options(), || {
+            transport::serialize_command(data, id)
+        })?;
+
+        Ok(match &self.quirks {
+            Some(quirks) => quirks.request(xml.into()).into_owned(),
+            None => xml,
+        })
+    }
+
+    /// Sends a caller-provided EPP command and parses the status of the response
+    ///
+    /// Use this as a stopgap for commands or extensions that are not supported by the typed
+    /// API yet. `xml` has to be a complete `<epp>` document; it is framed and sent as-is, without
+    /// applying any [`Quirks`]. Unlike [`EppClient::transact()`], a response with an error result
+    /// code is returned as `Ok`, so the raw XML is available in either case; check
+    /// [`RawResponse::code()`] before using it. The [`SessionState`] follows the result like for
+    /// typed commands, with the verb taken from `xml`. Middleware, the clTRID cache and the
+    /// journal don't see the command, so transforms are refused while any of them is set, see
+    /// [`EppClient::set_unchecked_raw()`].
+    pub async fn transact_raw(&mut self, xml: &str) -> Result<RawResponse, Error> {
+        let command = self.check_raw(xml)?;
+        self.connection.state.check(command.unwrap_or("command"))?;
+
+        let response = self.send_xml(xml).await?;
+        let status = xml::deserialize::<ResponseStatus>(&response)?;
+        self.connection.observe_result(command, &status.result);
+        Ok(RawResponse {
+            xml: response,
+            status,
+        })
+    }
+
+    /// Sends a command and returns the response frame without parsing its data
+    ///
+    /// Parse the frame with [`RawResponse::parse()`] into response types that borrow from it,
+    /// like [`CheckDataRef`](crate::domain::check::CheckDataRef) or
+    /// [`PollDataRef`](crate::poll::PollDataRef), to save allocations when sending many checks
+    /// or polls. Otherwise the command is sent like with [`EppClient::transact()`]: it goes
+    /// through [`Quirks`], [`Middleware`] (whose response hook sees the [`RawResponse`]), the
+    /// clTRID cache and the journal, and a response with an error result code is returned as
+    /// [`Error::Command`].
+    pub async fn transact_frame<'c, 'e, Cmd, Ext>(
+        &mut self,
+        data: impl Into<RequestData<'c, 'e, Cmd, Ext>>,
+        id: &str,
+    ) -> Result<RawResponse, Error>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+    {
+        self.transact_inner(data.into(), id, self.retain_raw).await
+    }
+
     /// Accepts raw EPP XML and returns the raw EPP XML response to it.
     /// Not recommended for direct use but sometimes can be useful for debugging
+    ///
+    /// Transforms are refused while middleware, a clTRID cache or a journal is set, see
+    /// [`EppClient::set_unchecked_raw()`].
     pub async fn transact_xml(&mut self, xml: &str) -> Result<String, Error> {
+        self.check_raw(xml)?;
+        self.send_xml(xml).await
+    }
+
+    // The verb of a raw document, if the client's safeguards allow sending it
+    fn check_raw(&self, xml: &str) -> Result<Option<&'static str>, Error> {
+        let command = xml::command_verb(xml);
+        let guarded =
+            !self.middleware.is_empty() || self.cltrid_cache.is_some() || self.journal.is_some();
+        let transform = match command {
+            Some(command) => RAW_TRANSFORMS.contains(&command),
+            None => true,
+        };
+
+        match guarded && transform && !self.unchecked_raw {
+            true => Err(UncheckedCommand { command }.into()),
+            false => Ok(command),
+        }
+    }
+
+    async fn send_xml(&mut self, xml: &str) -> Result<String, Error> {
+        self.flush().await;
         #[cfg(feature = "transaction-audit")]
         {
             let audited = self.send_raw_transaction_for_audit(xml, None, None).await?;
@@ -415,17 +1484,204 @@ impl<C: Connector> EppClient<C> {
     }
 
     /// Returns the greeting received on establishment of the connection as an `Greeting`
+    ///
+    /// The greeting is read before limits can be set with [`EppClient::set_xml_limits()`], so it
+    /// is checked against the current limits again before it is parsed.
     pub fn greeting(&self) -> Result<Greeting, Error> {
-        xml::deserialize::<Greeting>(&self.connection.greeting)
+        xml::deserialize_within::<Greeting>(
+            &self.connection.greeting,
+            self.connection.xml_limits.as_ref(),
+        )
     }
 
+    /// Round trip times and server dates of the latest `<hello>` commands
+    ///
+    /// Use it to detect clock skew on the registry's side, and latency jitter.
+    pub fn hello_history(&self) -> &HelloHistory {
+        &self.connection.hellos
+    }
+
+    /// Returns the state of the EPP session
+    ///
+    /// Tracks whether the client has logged in, and after a logout (or when the server closes
+    /// the connection), whether the session ended cleanly or was closed by the server along
+    /// with its parting message.
+    pub fn session_state(&self) -> &SessionState {
+        &self.connection.state
+    }
+
+    /// The connector used to (re)connect to the registry
+    pub fn connector(&self) -> &C {
+        &self.connection.connector
+    }
+
+    /// Reconnect to the server, checking its greeting if expectations or a watch were set
+    ///
+    /// The response to a command from [`EppClient::submit()`] is read first.
     pub async fn reconnect(&mut self) -> Result<(), Error> {
-        self.connection.reconnect().await
+        self.flush().await;
+        self.reconnect_checked().await
+    }
+
+    async fn reconnect_checked(&mut self) -> Result<(), Error> {
+        self.connection.reconnect().await?;
+        if let Some(watch) = &self.greeting_watch {
+            watch.observe(self.greeting()?, Utc::now());
+        }
+        if let Some(expectations) = &self.greeting_expectations {
+            expectations.check(&self.greeting()?)?;
+        }
+
+        Ok(())
     }
 
     pub async fn shutdown(mut self) -> Result<(), Error> {
-        self.connection.shutdown().await
+        let timeout = self.connection.timeout;
+        self.connection.shutdown(timeout).await
     }
+
+    /// Ends the session in an orderly fashion, giving up at `deadline`
+    ///
+    /// Consuming the client stops it from accepting new commands. A request left in flight
+    /// by a dropped future is finished first. If a poll message was retrieved but not yet
+    /// acknowledged, `acknowledge` is called with its ID and awaited; it should resolve to
+    /// `true` once the message has been handled, and the message is then acknowledged (with
+    /// `{id}:ack` as the client transaction ID). Next a logout (with `id` as its client
+    /// transaction ID) is sent if the session is still open, and finally the connection is
+    /// closed. Every step is bounded by `deadline`; anything that could not be completed before
+    /// it is listed in the returned report.
+    pub async fn shutdown_gracefully<F>(
+        mut self,
+        deadline: Instant,
+        id: &str,
+        acknowledge: impl FnOnce(&str) -> F,
+    ) -> Result<ShutdownReport, Error>
+    where
+        F: Future<Output = bool>,
+    {
+        let mut report = ShutdownReport::default();
+        let remaining = || deadline.saturating_duration_since(Instant::now());
+        report.abandoned_in_flight = !matches!(
+            tokio::time::timeout(remaining(), async {
+                self.flush().await;
+                self.connection.drain().await
+            })
+            .await,
+            Ok(Ok(_))
+        );
+
+        report.unacked_message = self.unacked_message.take();
+        if let (Some(message_id), false) = (&report.unacked_message, report.abandoned_in_flight) {
+            let handled = tokio::time::timeout(remaining(), acknowledge(message_id)).await;
+            if handled == Ok(true) {
+                let ack = Ack { message_id };
+                let ack_id = format!("{id}:ack");
+                match tokio::time::timeout(remaining(), self.transact(&ack, &ack_id)).await {
+                    Ok(Ok(_)) => report.unacked_message = None,
+                    Err(_) | Ok(Err(_)) => debug!(
+                        "{}: acknowledging message {message_id} failed during shutdown",
+                        self.connection.registry
+                    ),
+                }
+            }
+        }
+
+        let expired = remaining().is_zero();
+        if !report.abandoned_in_flight && !expired && self.connection.state.is_open() {
+            let logout = tokio::time::timeout(remaining(), self.transact(&Logout, id)).await;
+            if let Err(_) | Ok(Err(_)) = logout {
+                debug!(
+                    "{}: logout failed during shutdown",
+                    self.connection.registry
+                );
+            }
+        }
+
+        report.logged_out = self.connection.state == SessionState::Ended;
+        self.connection.shutdown(remaining()).await?;
+        Ok(report)
+    }
+}
+
+/// What [`EppClient::shutdown_gracefully()`] could not complete
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ShutdownReport {
+    /// A request left in flight by a dropped future was not finished before the deadline
+    pub abandoned_in_flight: bool,
+    /// The ID of a poll message that was retrieved but not acknowledged
+    ///
+    /// The registry will deliver it again in the next session.
+    pub unacked_message: Option<String>,
+    /// Whether the session was ended by a logout
+    pub logged_out: bool,
+}
+
+// The outcome of a command, as returned from `EppClient::transact()`
+type TransactResult<Cmd, Ext> =
+    Result<Response<<Cmd as Command>::Response, <Ext as Extension>::Response>, Error>;
+
+// A command written by `EppClient::write_submitted()`, waiting for its response
+struct Submitted<C: Connector> {
+    outgoing: Outgoing,
+    lost: Option<LostTarget>,
+    // Hands the response, or the error the command failed with, to the handle
+    deliver: Deliver<C>,
+}
+
+type Deliver<C> = Box<dyn FnOnce(&mut EppClient<C>, Result<(Outgoing, String), Error>) + Send>;
+
+// The object of a create or delete, to look up should its response be lost
+struct LostTarget {
+    command: &'static str,
+    object: String,
+    domain: bool,
+    sent_at: DateTime<Utc>,
+}
+
+impl LostTarget {
+    fn of(view: CommandView<'_>) -> Option<Self> {
+        let (command, object, domain) = match view {
+            CommandView::DomainCreate(cmd) => ("create", cmd.name, true),
+            CommandView::DomainDelete(cmd) => ("delete", cmd.name(), true),
+            CommandView::ContactCreate(cmd) => ("create", cmd.id(), false),
+            CommandView::ContactDelete(cmd) => ("delete", cmd.id(), false),
+            _ => return None,
+        };
+
+        Some(Self {
+            command,
+            object: object.to_owned(),
+            domain,
+            sent_at: Utc::now(),
+        })
+    }
+}
+
+// An object looked up by `EppClient::check_lost()`
+struct LostObject {
+    // The sponsoring client
+    client_id: String,
+    created_at: Option<DateTime<Utc>>,
+    pending_delete: bool,
+}
+
+// A command checked and serialized by `EppClient::prepare()`
+enum Prepared<R> {
+    // A repeated transform, answered from the clTRID cache
+    Replayed(R),
+    Send(Outgoing),
+}
+
+// A serialized request, with what is needed to handle its response
+struct Outgoing {
+    id: String,
+    command: &'static str,
+    #[cfg(feature = "transaction-audit")]
+    command_type: &'static str,
+    xml: String,
+    journal: Option<Arc<dyn TransactionJournal>>,
+    #[cfg(feature = "transaction-audit")]
+    request_at: DateTime<Utc>,
 }
 
 #[cfg(feature = "transaction-audit")]
@@ -488,52 +1744,68 @@ fn audit_command_error_message(metadata: &AuditResponseMetadata) -> String {
     }
 }
 
+// The verbs of raw documents that may change objects at the registry
+const RAW_TRANSFORMS: [&str; 5] = ["create", "delete", "renew", "transfer", "update"];
+
+/// A raw document was refused because the client's safeguards can't see what it does
+///
+/// Returned as [`Error::Other`] from [`EppClient::transact_raw()`] and
+/// [`EppClient::transact_xml()`], see [`EppClient::set_unchecked_raw()`].
 #[derive(Debug)]
-pub struct RequestData<'c, 'e, C, E> {
-    pub(crate) command: &'c C,
-    pub(crate) extension: Option<&'e E>,
+pub struct UncheckedCommand {
+    /// The verb of the refused document, if it has a recognizable one
+    pub command: Option<&'static str>,
 }
 
-impl<'c, C: Command> From<&'c C> for RequestData<'c, 'static, C, NoExtension> {
-    fn from(command: &'c C) -> Self {
-        Self {
-            command,
-            extension: None,
+impl fmt::Display for UncheckedCommand {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.command {
+            Some(command) => write!(
+                f,
+                "refusing to send raw {command} past the client's safeguards"
+            ),
+            None => write!(
+                f,
+                "refusing to send raw command past the client's safeguards"
+            ),
         }
     }
 }
 
-impl<'c, 'e, C: Command, E: Extension> From<(&'c C, &'e E)> for RequestData<'c, 'e, C, E> {
-    fn from((command, extension): (&'c C, &'e E)) -> Self {
-        Self {
-            command,
-            extension: Some(extension),
-        }
-    }
-}
+impl StdError for UncheckedCommand {}
 
-// Manual impl because this does not depend on whether `C` and `E` are `Clone`
-impl<C, E> Clone for RequestData<'_, '_, C, E> {
-    fn clone(&self) -> Self {
-        *self
+impl From<UncheckedCommand> for Error {
+    fn from(err: UncheckedCommand) -> Self {
+        Self::Other(Box::new(err))
     }
 }
 
-// Manual impl because this does not depend on whether `C` and `E` are `Copy`
-impl<C, E> Copy for RequestData<'_, '_, C, E> {}
+/// A command rendered by [`EppClient::render()`] instead of being sent
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RenderedCommand {
+    /// The client transaction ID
+    pub id: String,
+    /// The command verb, like `create` or `update`
+    pub command: &'static str,
+    /// The name or ID of the object the command acts on, see [`CommandView::object()`]
+    ///
+    /// [`CommandView::object()`]: crate::middleware::CommandView::object
+    pub object: Option<String>,
+    /// The complete `<epp>` document
+    pub xml: String,
+}
 
 #[cfg(feature = "__rustls")]
 pub use rustls_connector::RustlsConnector;
 
 #[cfg(feature = "__rustls")]
 mod rustls_connector {
-    use std::io;
-    use std::sync::Arc;
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
     use std::time::Duration;
 
     use async_trait::async_trait;
     use rustls_platform_verifier::BuilderVerifierExt;
-    use tokio::net::lookup_host;
     use tokio::net::TcpStream;
     use tokio_rustls::client::TlsStream;
     use tokio_rustls::rustls::pki_types::InvalidDnsNameError;
@@ -549,6 +1821,7 @@ mod rustls_connector {
         inner: TlsConnector,
         server_name: ServerName<'static>,
         server: (String, u16),
+        endpoint: Mutex<Option<SocketAddr>>,
     }
 
     impl RustlsConnector {
@@ -562,6 +1835,13 @@ mod rustls_connector {
                 identity: None,
             })
         }
+
+        /// The address of the last successful connection, if any
+        ///
+        /// If the host name resolves to several addresses, this tells which one is in use.
+        pub fn endpoint(&self) -> Option<SocketAddr> {
+            *self.endpoint.lock().unwrap()
+        }
     }
 
     #[async_trait]
@@ -570,17 +1850,8 @@ mod rustls_connector {
 
         async fn connect(&self, timeout: Duration) -> Result<Self::Connection, Error> {
             info!("connecting to server: {}:{}", self.server.0, self.server.1);
-            let addr = match lookup_host(&self.server).await?.next() {
-                Some(addr) => addr,
-                None => {
-                    return Err(Error::Io(io::Error::new(
-                        io::ErrorKind::InvalidInput,
-                        format!("invalid host: {}", &self.server.0),
-                    )))
-                }
-            };
-
-            let stream = TcpStream::connect(addr).await?;
+            let (stream, addr) = connection::connect_tcp(&self.server).await?;
+            *self.endpoint.lock().unwrap() = Some(addr);
             let future = self.inner.connect(self.server_name.clone(), stream);
             connection::timeout(timeout, future).await
         }
@@ -619,6 +1890,7 @@ mod rustls_connector {
                 inner: TlsConnector::from(config),
                 server_name,
                 server,
+                endpoint: Mutex::new(None),
             }
         }
 
@@ -640,6 +1912,7 @@ mod rustls_connector {
                 inner: TlsConnector::from(Arc::new(config)),
                 server_name,
                 server,
+                endpoint: Mutex::new(None),
             })
         }
     }
@@ -0,0 +1,190 @@
+//! Detection of transform commands re-sent with the same client transaction ID
+//!
+//! An application that retries a create or renew after a timeout or a crash of its own
+//! request handling can easily send the same transform twice. With a [`ClTridCache`] attached
+//! through [`EppClient::set_cltrid_cache()`], the client remembers the clTRIDs of recent
+//! transforms: a command reusing a clTRID that already got a response is answered from the
+//! cached response instead of being sent again, and one reusing a clTRID whose outcome is
+//! unknown (the connection failed before the response arrived) fails with
+//! [`DuplicateTransaction`]. A clTRID only counts as used once its command was written to the
+//! connection, and reusing it for a command with a different verb fails with
+//! [`ClTridConflict`].
+//!
+//! [`EppClient::set_cltrid_cache()`]: crate::EppClient::set_cltrid_cache
+
+use std::collections::{HashMap, VecDeque};
+use std::error::Error as StdError;
+use std::fmt;
+
+/// A bounded cache of the clTRIDs of recently sent transform commands
+#[derive(Debug)]
+pub struct ClTridCache {
+    capacity: usize,
+    entries: HashMap<String, Entry>,
+    // Insertion order, to evict the oldest entries first
+    order: VecDeque<String>,
+}
+
+impl ClTridCache {
+    /// Create a cache remembering at most `capacity` clTRIDs
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+            order: VecDeque::new(),
+        }
+    }
+
+    /// Whether a transform with this clTRID was sent
+    pub fn contains(&self, id: &str) -> bool {
+        self.entries.contains_key(id)
+    }
+
+    /// The number of clTRIDs remembered
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Record that a transform is being sent with `id`
+    pub(crate) fn begin(&mut self, id: &str, command: &'static str) {
+        if self.capacity == 0 || self.entries.contains_key(id) {
+            return;
+        }
+
+        while self.entries.len() >= self.capacity {
+            match self.order.pop_front() {
+                Some(oldest) => self.entries.remove(&oldest),
+                None => break,
+            };
+        }
+
+        self.order.push_back(id.to_owned());
+        self.entries.insert(
+            id.to_owned(),
+            Entry {
+                command,
+                response: None,
+            },
+        );
+    }
+
+    /// Forget the transform with `id`, which was not written to the connection
+    pub(crate) fn abandon(&mut self, id: &str) {
+        if self.entries.remove(id).is_some() {
+            self.order.retain(|entry| entry != id);
+        }
+    }
+
+    /// The entry for `id`, if the clTRID is remembered
+    pub(crate) fn get(&self, id: &str) -> Option<&Entry> {
+        self.entries.get(id)
+    }
+
+    /// Record the raw response to the transform sent with `id`
+    pub(crate) fn complete(&mut self, id: &str, response: &str) {
+        if let Some(entry) = self.entries.get_mut(id) {
+            entry.response = Some(response.to_owned());
+        }
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Entry {
+    pub(crate) command: &'static str,
+    /// The raw response, if one was received
+    pub(crate) response: Option<String>,
+}
+
+/// A transform reused the clTRID of an earlier transform with a different verb
+///
+/// Returned as [`Error::Other`](crate::Error::Other). The response to the earlier command
+/// doesn't answer this one, so it is neither replayed nor is the command sent.
+#[derive(Debug)]
+pub struct ClTridConflict {
+    /// The reused client transaction ID
+    pub id: String,
+    /// The verb of the command being sent
+    pub command: &'static str,
+    /// The verb of the earlier command, like `create`
+    pub previous: &'static str,
+}
+
+impl fmt::Display for ClTridConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} reuses clTRID {} of an earlier {}",
+            self.command, self.id, self.previous
+        )
+    }
+}
+
+impl StdError for ClTridConflict {}
+
+impl From<ClTridConflict> for crate::Error {
+    fn from(err: ClTridConflict) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+/// A transform reused the clTRID of an earlier command whose outcome is unknown
+///
+/// Returned as [`Error::Other`](crate::Error::Other). The earlier command was written to the
+/// connection, but no response was received, so the registry may or may not have executed it.
+#[derive(Debug)]
+pub struct DuplicateTransaction {
+    /// The reused client transaction ID
+    pub id: String,
+    /// The verb of the earlier command, like `create`
+    pub command: &'static str,
+}
+
+impl fmt::Display for DuplicateTransaction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} with clTRID {} was already sent and its outcome is unknown",
+            self.command, self.id
+        )
+    }
+}
+
+impl StdError for DuplicateTransaction {}
+
+impl From<DuplicateTransaction> for crate::Error {
+    fn from(err: DuplicateTransaction) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ClTridCache;
+
+    #[test]
+    fn evicts_oldest() {
+        let mut cache = ClTridCache::new(2);
+        cache.begin("a", "create");
+        cache.complete("a", "<epp/>");
+        cache.begin("b", "renew");
+
+        let entry = cache.get("a").unwrap();
+        assert_eq!(entry.response.as_deref(), Some("<epp/>"));
+
+        cache.begin("c", "delete");
+        assert!(!cache.contains("a"));
+        assert!(cache.contains("b"));
+        assert!(cache.contains("c"));
+        assert_eq!(cache.len(), 2);
+
+        cache.abandon("b");
+        cache.begin("d", "update");
+        assert!(cache.contains("c"));
+        assert!(cache.contains("d"));
+        assert_eq!(cache.len(), 2);
+    }
+}
@@ -1,10 +1,17 @@
 //! Common data types included in EPP Requests and Responses
 
 use std::borrow::Cow;
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
 
-use instant_xml::{FromXml, ToXml};
+use chrono::{DateTime, SecondsFormat, Utc};
+use instant_xml::{from_xml_str, FromXml, ToXml};
 
 use crate::request::Extension;
+use crate::response::ResultCode;
+use crate::xml;
+use crate::Error;
 
 pub(crate) const EPP_XMLNS: &str = "urn:ietf:params:xml:ns:epp-1.0";
 
@@ -34,8 +41,76 @@ impl Extension for NoExtension {
     type Response = Self;
 }
 
+/// A Repository Object IDentifier, assigned by the registry to every domain, host and contact
+///
+/// Unlike names, ROIDs are never reused, which is why registry support and escrow deposits
+/// refer to objects by ROID. They have the form `<id>-<repository>`, like
+/// `125899511_DOMAIN_COM-VRSN`.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Roid(pub String);
+
+impl Roid {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The identifier of the repository, after the last `-`
+    pub fn repository(&self) -> Option<&str> {
+        self.0
+            .rsplit_once('-')
+            .map(|(_, repository)| repository)
+            .filter(|repository| !repository.is_empty())
+    }
+}
+
+impl<'xml> FromXml<'xml> for Roid {
+    fn matches(id: instant_xml::Id<'_>, field: Option<instant_xml::Id<'_>>) -> bool {
+        match field {
+            Some(field) => id == field,
+            None => false,
+        }
+    }
+
+    fn deserialize<'cx>(
+        into: &mut Self::Accumulator,
+        field: &'static str,
+        deserializer: &mut instant_xml::Deserializer<'cx, 'xml>,
+    ) -> Result<(), instant_xml::Error> {
+        from_xml_str(into, field, deserializer)
+    }
+
+    type Accumulator = Option<Self>;
+    const KIND: instant_xml::Kind = instant_xml::Kind::Scalar;
+}
+
+impl FromStr for Roid {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.to_owned()))
+    }
+}
+
+impl fmt::Display for Roid {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl PartialEq<str> for Roid {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Roid {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
 /// The `<option>` type in EPP XML login requests
-#[derive(Debug, Eq, FromXml, PartialEq, ToXml)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq, ToXml)]
 #[xml(rename = "options", ns(EPP_XMLNS))]
 pub struct Options<'a> {
     /// The EPP version being used
@@ -55,7 +130,7 @@ impl<'a> Options<'a> {
 }
 
 /// The `<svcExtension>` type in EPP XML
-#[derive(Debug, Eq, FromXml, PartialEq, ToXml)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq, ToXml)]
 #[xml(rename = "svcExtension", ns(EPP_XMLNS))]
 pub struct ServiceExtension<'a> {
     /// The service extension URIs being represented by `<extURI>` in EPP XML
@@ -64,7 +139,7 @@ pub struct ServiceExtension<'a> {
 }
 
 /// The `<svcs>` type in EPP XML
-#[derive(Debug, Eq, FromXml, PartialEq, ToXml)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq, ToXml)]
 #[xml(rename = "svcs", ns(EPP_XMLNS))]
 pub struct Services<'a> {
     /// The service URIs being used by this EPP session represented by `<objURI>` in EPP XML
@@ -74,3 +149,277 @@ pub struct Services<'a> {
     #[xml(rename = "svcExtension")]
     pub svc_ext: Option<ServiceExtension<'a>>,
 }
+
+/// The availability of an object in a `<check>` response
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Availability<'a> {
+    /// The object can be provisioned
+    Available,
+    /// The object can't be provisioned, for example because it already exists
+    Unavailable { reason: Option<CheckReason<'a>> },
+    /// The server rejected the identifier itself as invalid
+    ///
+    /// EPP has no per-object flag for this; a server rejects the whole `<check>` with a
+    /// parameter value syntax or range error instead. See [`Availability::from_error()`].
+    Invalid { reason: CheckReason<'a> },
+}
+
+impl<'a> Availability<'a> {
+    /// The availability from the `avail` flag and the reason of a `<cd>` element
+    ///
+    /// Falls back to the non-standard `reason` attribute some servers put on the identifier
+    /// when there is no `<reason>` element.
+    pub(crate) fn new(
+        available: bool,
+        reason: Option<CheckReason<'a>>,
+        attribute: Option<&'a str>,
+    ) -> Self {
+        if available {
+            return Self::Available;
+        }
+
+        Self::Unavailable {
+            reason: reason.or(attribute.map(|text| CheckReason { lang: None, text })),
+        }
+    }
+
+    /// The availability implied by an error response to a `<check>` command
+    ///
+    /// Returns [`Availability::Invalid`] if the server rejected the command with a parameter
+    /// value syntax or range error (2005 or 2004), which is how it reports malformed
+    /// identifiers. The reason is the `<extValue>` reason if given, or the result message.
+    pub fn from_error(error: &'a Error) -> Option<Self> {
+        let result = error.result()?;
+        match result.code {
+            ResultCode::ParameterValueSyntaxError | ResultCode::ParameterValueRangeError => {
+                let text = match &result.ext_value {
+                    Some(ext_value) => &ext_value.reason,
+                    None => &result.message,
+                };
+                Some(Self::Invalid {
+                    reason: CheckReason { lang: None, text },
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the object can be provisioned
+    pub fn is_available(&self) -> bool {
+        matches!(self, Self::Available)
+    }
+}
+
+impl fmt::Display for Availability<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Available => write!(f, "available"),
+            Self::Unavailable { reason: None } => write!(f, "unavailable"),
+            Self::Unavailable {
+                reason: Some(reason),
+            } => write!(f, "unavailable ({})", reason.text),
+            Self::Invalid { reason } => write!(f, "invalid ({})", reason.text),
+        }
+    }
+}
+
+/// The `<reason>` given by the server for an unavailable object in a `<check>` response
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct CheckReason<'a> {
+    /// The language of the reason, if not the default of "en"
+    pub lang: Option<&'a str>,
+    /// The reason text
+    pub text: &'a str,
+}
+
+/// A monetary amount sent to the registry, like an acknowledged fee or charge
+///
+/// Registries commonly compare amounts as strings and reject `10` when they expect `10.00`,
+/// so amounts are written with a fixed number of decimal places (two by default) rather
+/// than the shortest representation of the number.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Amount {
+    /// The numeric value
+    pub value: f64,
+    /// The number of decimal places to write
+    pub decimals: u8,
+}
+
+impl Amount {
+    /// An amount written with two decimal places
+    pub fn new(value: f64) -> Self {
+        Self { value, decimals: 2 }
+    }
+
+    /// Write the amount with `decimals` decimal places instead
+    pub fn with_decimals(mut self, decimals: u8) -> Self {
+        self.decimals = decimals;
+        self
+    }
+}
+
+impl From<f64> for Amount {
+    fn from(value: f64) -> Self {
+        Self::new(value)
+    }
+}
+
+impl fmt::Display for Amount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:.*}", usize::from(self.decimals), self.value)
+    }
+}
+
+impl ToXml for Amount {
+    fn serialize<W: fmt::Write + ?Sized>(
+        &self,
+        field: Option<instant_xml::Id<'_>>,
+        serializer: &mut instant_xml::Serializer<W>,
+    ) -> Result<(), instant_xml::Error> {
+        self.to_string().as_str().serialize(field, serializer)
+    }
+}
+
+/// A point in time sent to the registry
+///
+/// Written in RFC 3339 format in UTC, like `2024-01-02T03:04:05.678Z`, with fractional seconds
+/// only if there are any. They are left out for registries set up with
+/// [`SerializeOptions::whole_seconds`](crate::xml::SerializeOptions::whole_seconds).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Timestamp(pub DateTime<Utc>);
+
+impl From<DateTime<Utc>> for Timestamp {
+    fn from(value: DateTime<Utc>) -> Self {
+        Self(value)
+    }
+}
+
+impl ToXml for Timestamp {
+    fn serialize<W: fmt::Write + ?Sized>(
+        &self,
+        field: Option<instant_xml::Id<'_>>,
+        serializer: &mut instant_xml::Serializer<W>,
+    ) -> Result<(), instant_xml::Error> {
+        let format = match xml::options(|options| options.is_some_and(|o| o.whole_seconds)) {
+            true => SecondsFormat::Secs,
+            false => SecondsFormat::AutoSi,
+        };
+
+        self.0
+            .to_rfc3339_opts(format, true)
+            .as_str()
+            .serialize(field, serializer)
+    }
+}
+
+/// A boolean sent to the registry
+///
+/// Written as `true` or `false`, or as `1` and `0` for registries set up with
+/// [`SerializeOptions::numeric_booleans`](crate::xml::SerializeOptions::numeric_booleans).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Boolean(pub bool);
+
+impl From<bool> for Boolean {
+    fn from(value: bool) -> Self {
+        Self(value)
+    }
+}
+
+impl ToXml for Boolean {
+    fn serialize<W: fmt::Write + ?Sized>(
+        &self,
+        field: Option<instant_xml::Id<'_>>,
+        serializer: &mut instant_xml::Serializer<W>,
+    ) -> Result<(), instant_xml::Error> {
+        let numeric = xml::options(|options| options.is_some_and(|o| o.numeric_booleans));
+        let value = match (self.0, numeric) {
+            (true, false) => "true",
+            (false, false) => "false",
+            (true, true) => "1",
+            (false, true) => "0",
+        };
+
+        value.serialize(field, serializer)
+    }
+}
+
+/// Text in a response, borrowed from the response frame where possible
+///
+/// Unlike `Cow<'xml, str>`, which instant-xml always deserializes as an owned copy, this
+/// borrows the text unless it contains entity or character references that had to be
+/// replaced. Used by the response types ending in `Ref`, like
+/// [`CheckDataRef`](crate::domain::check::CheckDataRef).
+#[derive(Clone, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Text<'xml>(pub Cow<'xml, str>);
+
+impl Text<'_> {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the text points into the response frame
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.0, Cow::Borrowed(_))
+    }
+
+    pub fn into_owned(self) -> String {
+        self.0.into_owned()
+    }
+}
+
+impl std::ops::Deref for Text<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Text<'_> {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq<str> for Text<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.0 == other
+    }
+}
+
+impl PartialEq<&str> for Text<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.0 == *other
+    }
+}
+
+impl fmt::Display for Text<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<'xml> FromXml<'xml> for Text<'xml> {
+    fn matches(id: instant_xml::Id<'_>, field: Option<instant_xml::Id<'_>>) -> bool {
+        match field {
+            Some(field) => id == field,
+            None => false,
+        }
+    }
+
+    fn deserialize<'cx>(
+        into: &mut Self::Accumulator,
+        field: &'static str,
+        deserializer: &mut instant_xml::Deserializer<'cx, 'xml>,
+    ) -> Result<(), instant_xml::Error> {
+        if into.is_some() {
+            return Err(instant_xml::Error::DuplicateValue(field));
+        }
+
+        *into = Some(Self(deserializer.take_str()?.unwrap_or_default()));
+        Ok(())
+    }
+
+    type Accumulator = Option<Self>;
+    const KIND: instant_xml::Kind = instant_xml::Kind::Scalar;
+}
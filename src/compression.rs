@@ -0,0 +1,70 @@
+//! Compressing EPP frames for transport and archival
+//!
+//! EPP XML is verbose and repetitive, so it compresses very well: a drain of tens of thousands
+//! of poll messages shrinks to a fraction of its size. Over TLS, RFC 5734 has no way to
+//! negotiate compression, but HTTPS gateways (see [`transport`](crate::transport)) often accept
+//! gzip. A [`Transport`](crate::transport::Transport) posting to such a gateway can send the
+//! body from [`gzip()`] with `Content-Encoding: gzip`, and decode responses served with the same
+//! encoding with [`gunzip()`].
+//!
+//! Frames kept for the record, like the ones in
+//! [`EppTransactionAuditEvent`](crate::audit::EppTransactionAuditEvent), can be stored with
+//! [`deflate()`] and read back with [`inflate()`].
+
+use std::io::{Read, Write};
+
+use flate2::read::{DeflateDecoder, GzDecoder};
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Compression;
+
+use crate::Error;
+
+/// Compress `xml` as a gzip stream, as for a body sent with `Content-Encoding: gzip`
+pub fn gzip(xml: &str) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    // Writing to a `Vec` can't fail
+    encoder.write_all(xml.as_bytes()).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Decompress a gzip stream, like a response body with `Content-Encoding: gzip`
+pub fn gunzip(body: &[u8]) -> Result<String, Error> {
+    let mut xml = String::new();
+    GzDecoder::new(body).read_to_string(&mut xml)?;
+    Ok(xml)
+}
+
+/// Compress `xml` as a raw deflate stream, for archiving frames
+pub fn deflate(xml: &str) -> Vec<u8> {
+    let mut encoder = DeflateEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(xml.as_bytes()).unwrap();
+    encoder.finish().unwrap()
+}
+
+/// Decompress a frame compressed with [`deflate()`]
+pub fn inflate(data: &[u8]) -> Result<String, Error> {
+    let mut xml = String::new();
+    DeflateDecoder::new(data).read_to_string(&mut xml)?;
+    Ok(xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{deflate, gunzip, gzip, inflate};
+    use crate::tests::get_xml;
+
+    #[test]
+    fn round_trip() {
+        let xml = get_xml("response/poll/poll_domain_transfer.xml").unwrap();
+
+        let compressed = gzip(&xml);
+        assert!(compressed.len() < xml.len());
+        assert_eq!(gunzip(&compressed).unwrap(), xml);
+
+        let compressed = deflate(&xml);
+        assert!(compressed.len() < xml.len());
+        assert_eq!(inflate(&compressed).unwrap(), xml);
+
+        assert!(gunzip(xml.as_bytes()).is_err());
+    }
+}
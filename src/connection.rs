@@ -2,7 +2,10 @@
 //!
 //! See also [RFC 5734](https://tools.ietf.org/html/rfc5734).
 
-use std::future::Future;
+use std::error::Error as StdError;
+use std::fmt;
+use std::future::{poll_fn, Future};
+use std::net::SocketAddr;
 use std::pin::Pin;
 use std::task::{Context, Poll};
 use std::time::Duration;
@@ -10,17 +13,132 @@ use std::{io, str};
 
 use async_trait::async_trait;
 use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt, ReadBuf};
+use tokio::net::{lookup_host, TcpStream};
+use tokio::time::Instant;
 use tracing::{debug, info};
 
 use crate::error::Error;
+use crate::frame;
+use crate::hello::HelloHistory;
+use crate::response::{EppResult, ResultCode};
+use crate::xml::XmlLimits;
+
+/// The state of the EPP session carried by a connection
+///
+/// Transitions are driven by the result codes of the commands sent: a successful login moves
+/// from [`Greeted`](Self::Greeted) to [`LoggedIn`](Self::LoggedIn), 1500 ends the session and
+/// 2500, 2501 and 2502 close it. Commands are refused without touching the connection once the
+/// session has ended or was closed, and a login is refused while logged in.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SessionState {
+    /// Connected, but the greeting was not received yet
+    Connected,
+    /// The server sent its greeting, but the client has not logged in
+    ///
+    /// Most registries only accept hello, login and logout in this state.
+    Greeted,
+    /// Logged in; commands can be sent
+    LoggedIn,
+    /// Logged in, but the last command failed with 2400 "Command failed"
+    ///
+    /// This usually points at an internal problem at the registry. Commands can still be sent;
+    /// the next successful one returns the session to [`LoggedIn`](Self::LoggedIn).
+    Degraded,
+    /// The server ended the session with a 1500 result, usually in response to a logout
+    Ended,
+    /// The server closed the connection, with its parting result if one was received
+    Closed(Option<EppResult>),
+}
+
+impl SessionState {
+    // Moves to the state following the result of the command with the verb `command`
+    fn observe(&mut self, command: Option<&str>, result: &EppResult) {
+        use ResultCode::*;
+        match result.code {
+            CommandCompletedSuccessfullyEndingSession => *self = Self::Ended,
+            CommandFailedServerClosingConnection
+            | AuthenticationErrorServerClosingConnection
+            | SessionLimitExceededServerClosingConnection => {
+                *self = Self::Closed(Some(result.clone()))
+            }
+            CommandFailed if *self == Self::LoggedIn => *self = Self::Degraded,
+            code if code.is_success() => match (&*self, command) {
+                (Self::Greeted, Some("login")) | (Self::Degraded, _) => *self = Self::LoggedIn,
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+
+    /// Whether the connection is up and commands can be sent
+    pub fn is_open(&self) -> bool {
+        matches!(self, Self::Greeted | Self::LoggedIn | Self::Degraded)
+    }
+
+    /// Whether the client has logged in to the session
+    pub fn is_logged_in(&self) -> bool {
+        matches!(self, Self::LoggedIn | Self::Degraded)
+    }
+
+    // Refuses `command` if it can't be sent in this state: anything but hello, login and
+    // logout before logging in, and another login after
+    //
+    // Whether the connection is still open is checked when the command is queued.
+    pub(crate) fn check(&self, command: &'static str) -> Result<(), InvalidSessionState> {
+        let valid = match self {
+            Self::Greeted => matches!(command, "hello" | "login" | "logout"),
+            Self::LoggedIn | Self::Degraded => command != "login",
+            Self::Connected | Self::Ended | Self::Closed(_) => true,
+        };
+
+        match valid {
+            true => Ok(()),
+            false => Err(InvalidSessionState {
+                command,
+                state: self.clone(),
+            }),
+        }
+    }
+}
+
+/// A command was refused because it is not valid in the current [`SessionState`]
+///
+/// Returned as [`Error::Other`] from [`EppClient::transact()`](crate::EppClient::transact).
+#[derive(Debug)]
+pub struct InvalidSessionState {
+    /// The verb of the refused command
+    pub command: &'static str,
+    pub state: SessionState,
+}
+
+impl fmt::Display for InvalidSessionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "cannot send {} in session state {:?}",
+            self.command, self.state
+        )
+    }
+}
+
+impl StdError for InvalidSessionState {}
+
+impl From<InvalidSessionState> for Error {
+    fn from(err: InvalidSessionState) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
 
 /// EPP Connection struct with some metadata for the connection
 pub(crate) struct EppConnection<C: Connector> {
     pub(crate) registry: String,
-    connector: C,
+    pub(crate) connector: C,
     stream: C::Connection,
     pub(crate) greeting: String,
-    timeout: Duration,
+    pub(crate) state: SessionState,
+    // Round trips and server dates of recent `<hello>` commands
+    pub(crate) hellos: HelloHistory,
+    pub(crate) timeout: Duration,
     // A request that is currently in flight
     //
     // Because the code here currently depends on only one request being in flight at a time,
@@ -31,6 +149,17 @@ pub(crate) struct EppConnection<C: Connector> {
     // If we get a request while another request is in flight (because its future was dropped),
     // we will store it here until the current request is finished.
     next: Option<RequestState>,
+    // Set if writing a request failed because the peer went away
+    //
+    // The server may have pushed a final frame before disconnecting, so we try to read that
+    // before giving up; if that fails too, this is the error we report.
+    write_error: Option<io::Error>,
+    // The number of requests written out in full on this connection
+    written: u64,
+    // The value `written` reaches once the latest request has been written out in full
+    latest: u64,
+    // Checked on every received document before it is returned
+    pub(crate) xml_limits: Option<XmlLimits>,
 }
 
 impl<C: Connector> EppConnection<C> {
@@ -44,9 +173,15 @@ impl<C: Connector> EppConnection<C> {
             stream: connector.connect(timeout).await?,
             connector,
             greeting: String::new(),
+            state: SessionState::Connected,
+            hellos: HelloHistory::default(),
             timeout,
             current: None,
             next: None,
+            write_error: None,
+            written: 0,
+            latest: 0,
+            xml_limits: Some(XmlLimits::default()),
         };
 
         this.read_greeting().await?;
@@ -61,6 +196,7 @@ impl<C: Connector> EppConnection<C> {
         });
 
         self.greeting = RequestFuture { conn: self }.await?;
+        self.state = SessionState::Greeted;
         Ok(())
     }
 
@@ -68,15 +204,37 @@ impl<C: Connector> EppConnection<C> {
         debug!("{}: reconnecting", self.registry);
         let _ = self.current.take();
         let _ = self.next.take();
+        let _ = self.write_error.take();
         self.stream = self.connector.connect(self.timeout).await?;
+        self.state = SessionState::Connected;
         self.read_greeting().await?;
         Ok(())
     }
 
     /// Sends an EPP XML request to the registry and returns the response
     pub(crate) fn transact(&'_ mut self, command: &str) -> Result<RequestFuture<'_, C>, Error> {
+        self.queue(command)?;
+        Ok(RequestFuture { conn: self })
+    }
+
+    // Queue up `command` to be written after any request in flight
+    fn queue(&mut self, command: &str) -> Result<(), Error> {
+        self.latest = self.written + 1;
+        match &self.state {
+            SessionState::Greeted | SessionState::LoggedIn | SessionState::Degraded => {}
+            SessionState::Connected | SessionState::Ended => return Err(Error::Closed(None)),
+            SessionState::Closed(result) => {
+                return Err(Error::Closed(result.clone().map(Box::new)))
+            }
+        }
+
         let new = RequestState::new(command)?;
 
+        // A request in flight that is still being written is written out before this one
+        if let Some(RequestState::Writing { .. }) = self.current {
+            self.latest += 1;
+        }
+
         // If we have a request currently in flight, finish that first
         // If another request was queued up behind the one in flight, just replace it
         match self.current.is_some() {
@@ -90,21 +248,122 @@ impl<C: Connector> EppConnection<C> {
             false => self.current = Some(new),
         }
 
-        Ok(RequestFuture { conn: self })
+        Ok(())
     }
 
-    /// Closes the socket and shuts down the connection
-    pub(crate) async fn shutdown(&mut self) -> Result<(), Error> {
-        info!("{}: Closing connection", self.registry);
-        timeout(self.timeout, self.stream.shutdown()).await?;
+    /// Writes out an EPP XML request, leaving its response to be read with
+    /// [`EppConnection::receive()`]
+    pub(crate) async fn send(&mut self, command: &str) -> Result<(), Error> {
+        self.queue(command)?;
+        poll_fn(|cx| loop {
+            // If the peer went away, the frame it pushed before is read as the response
+            if self.request_written() || self.write_error.is_some() {
+                return Poll::Ready(Ok(()));
+            }
+
+            let state = self.current.take().unwrap();
+            match self.handle(state, cx) {
+                Ok(Transition::Next(next)) => self.current = Some(next),
+                Ok(Transition::Pending(state)) => {
+                    self.current = Some(state);
+                    return Poll::Pending;
+                }
+                // Responses to earlier requests are discarded while this one is queued, so
+                // nothing completes before it is written
+                Ok(Transition::Done(_)) => return Poll::Ready(Err(Error::Closed(None))),
+                Err(err) => {
+                    self.next = None;
+                    self.write_error = None;
+                    return Poll::Ready(Err(err));
+                }
+            }
+        })
+        .await
+    }
+
+    /// Reads the response to the request written with [`EppConnection::send()`]
+    pub(crate) async fn receive(&mut self) -> Result<String, Error> {
+        match self.current.is_some() {
+            true => RequestFuture { conn: self }.await,
+            false => Err(Error::Closed(None)),
+        }
+    }
+
+    /// Whether the latest request passed to [`EppConnection::transact()`] was written out in full
+    ///
+    /// After a failure, this tells whether the registry may have received the request.
+    pub(crate) fn request_written(&self) -> bool {
+        self.written >= self.latest
+    }
+
+    /// Finishes any request left in flight by a dropped future, discarding its response
+    pub(crate) async fn drain(&mut self) -> Result<(), Error> {
+        if self.current.is_some() {
+            debug!("{}: draining in-flight request", self.registry);
+            RequestFuture { conn: self }.await?;
+        }
+
         Ok(())
     }
 
+    /// Closes the socket and shuts down the connection, giving up after `limit`
+    ///
+    /// If the server already ended the session, errors caused by the peer having closed its
+    /// side of the connection are ignored.
+    pub(crate) async fn shutdown(&mut self, limit: Duration) -> Result<(), Error> {
+        info!("{}: Closing connection", self.registry);
+        match timeout(limit, self.stream.shutdown()).await {
+            Err(Error::Io(err)) if !self.state.is_open() && is_disconnect(&err) => {
+                debug!("{}: peer already closed connection: {err}", self.registry);
+                Ok(())
+            }
+            result => result,
+        }
+    }
+
+    /// Updates the session state based on the result of a command
+    ///
+    /// `command` is the verb of the command, if known.
+    pub(crate) fn observe_result(&mut self, command: Option<&str>, result: &EppResult) {
+        self.state.observe(command, result);
+    }
+
+    // Called when the peer went away while we were waiting for a response
+    fn closed(&mut self, err: io::Error) -> Error {
+        if self.state.is_open() || self.state == SessionState::Ended {
+            self.state = SessionState::Closed(None);
+        }
+
+        match self.write_error.take() {
+            Some(write_error) => write_error.into(),
+            None => err.into(),
+        }
+    }
+
+    // The peer went away while we were writing a request
+    //
+    // Some registries push a final error frame before disconnecting, so try to read it.
+    fn salvage(&mut self, err: io::Error) -> Transition {
+        debug!(
+            "{}: peer closed connection while writing, reading parting frame: {err}",
+            self.registry
+        );
+        self.write_error = Some(err);
+        self.next = None;
+        Transition::Next(RequestState::ReadLength {
+            read: 0,
+            buf: vec![0; 256],
+        })
+    }
+
     fn handle(&mut self, state: RequestState, cx: &mut Context<'_>) -> Result<Transition, Error> {
         match state {
             RequestState::Writing { mut start, buf } => {
                 let wrote = match Pin::new(&mut self.stream).poll_write(cx, &buf[start..]) {
                     Poll::Ready(Ok(wrote)) => wrote,
+                    Poll::Ready(Err(err)) if is_disconnect(&err) => {
+                        return Ok(self.salvage(err));
+                    }
                     Poll::Ready(Err(err)) => return Err(err.into()),
                     Poll::Pending => {
                         return Ok(Transition::Pending(RequestState::Writing { start, buf }))
@@ -112,11 +371,10 @@ impl<C: Connector> EppConnection<C> {
                 };
 
                 if wrote == 0 {
-                    return Err(io::Error::new(
+                    return Ok(self.salvage(io::Error::new(
                         io::ErrorKind::UnexpectedEof,
                         format!("{}: Unexpected EOF while writing", self.registry),
-                    )
-                    .into());
+                    )));
                 }
 
                 start += wrote;
@@ -134,6 +392,7 @@ impl<C: Connector> EppConnection<C> {
                 }
 
                 // Request fully written, start reading frame header
+                self.written += 1;
                 Ok(Transition::Next(RequestState::ReadLength {
                     read: 0,
                     buf: vec![0; 256],
@@ -143,6 +402,9 @@ impl<C: Connector> EppConnection<C> {
                 let mut read_buf = ReadBuf::new(&mut buf[read..]);
                 match Pin::new(&mut self.stream).poll_read(cx, &mut read_buf) {
                     Poll::Ready(Ok(())) => {}
+                    Poll::Ready(Err(err)) if self.write_error.is_some() => {
+                        return Err(self.closed(err));
+                    }
                     Poll::Ready(Err(err)) => return Err(err.into()),
                     Poll::Pending => {
                         return Ok(Transition::Pending(RequestState::ReadLength { read, buf }))
@@ -151,11 +413,10 @@ impl<C: Connector> EppConnection<C> {
 
                 let filled = read_buf.filled();
                 if filled.is_empty() {
-                    return Err(io::Error::new(
+                    return Err(self.closed(io::Error::new(
                         io::ErrorKind::UnexpectedEof,
                         format!("{}: Unexpected EOF while reading length", self.registry),
-                    )
-                    .into());
+                    )));
                 }
 
                 read += filled.len();
@@ -185,6 +446,9 @@ impl<C: Connector> EppConnection<C> {
                 let mut read_buf = ReadBuf::new(&mut buf[read..]);
                 match Pin::new(&mut self.stream).poll_read(cx, &mut read_buf) {
                     Poll::Ready(Ok(())) => {}
+                    Poll::Ready(Err(err)) if self.write_error.is_some() => {
+                        return Err(self.closed(err));
+                    }
                     Poll::Ready(Err(err)) => return Err(err.into()),
                     Poll::Pending => {
                         return Ok(Transition::Pending(RequestState::Reading {
@@ -197,11 +461,10 @@ impl<C: Connector> EppConnection<C> {
 
                 let filled = read_buf.filled();
                 if filled.is_empty() {
-                    return Err(io::Error::new(
+                    return Err(self.closed(io::Error::new(
                         io::ErrorKind::UnexpectedEof,
                         format!("{}: Unexpected EOF while reading", self.registry),
-                    )
-                    .into());
+                    )));
                 }
 
                 read += filled.len();
@@ -225,6 +488,9 @@ impl<C: Connector> EppConnection<C> {
                     Transition::Next(next)
                 } else {
                     // Done: strip frame header and yield XML as String
+                    //
+                    // If writing failed, this is a frame the server pushed before disconnecting.
+                    let _ = self.write_error.take();
                     buf.drain(..4);
                     Transition::Done(String::from_utf8(buf)?)
                 })
@@ -233,6 +499,17 @@ impl<C: Connector> EppConnection<C> {
     }
 }
 
+fn is_disconnect(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::BrokenPipe
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::UnexpectedEof
+    )
+}
+
 pub(crate) struct RequestFuture<'a, C: Connector> {
     conn: &'a mut EppConnection<C>,
 }
@@ -253,10 +530,17 @@ impl<C: Connector> Future for RequestFuture<'_, C> {
                     this.conn.current = Some(state);
                     return Poll::Pending;
                 }
-                Ok(Transition::Done(rsp)) => return Poll::Ready(Ok(rsp)),
+                Ok(Transition::Done(rsp)) => {
+                    // The whole frame was read, so the connection stays usable on failure
+                    if let Some(limits) = &this.conn.xml_limits {
+                        limits.check(&rsp)?;
+                    }
+                    return Poll::Ready(Ok(rsp));
+                }
                 Err(err) => {
                     // Assume the error means the connection can no longer be used
                     this.conn.next = None;
+                    this.conn.write_error = None;
                     return Poll::Ready(Err(err));
                 }
             }
@@ -303,16 +587,8 @@ enum RequestState {
 
 impl RequestState {
     fn new(command: &str) -> Result<Self, Error> {
-        let len = command.len();
-
-        let buf_size = len + 4;
-        let mut buf: Vec<u8> = vec![0u8; buf_size];
-
-        let len = len + 4;
-        let len_u32: [u8; 4] = u32::to_be_bytes(len.try_into()?);
-
-        buf[..4].clone_from_slice(&len_u32);
-        buf[4..].clone_from_slice(command.as_bytes());
+        let mut buf = Vec::new();
+        frame::encode(command, &mut buf)?;
         Ok(Self::Writing { start: 0, buf })
     }
 }
@@ -328,9 +604,172 @@ pub(crate) async fn timeout<T, E: Into<Error>>(
     }
 }
 
+/// Delay between starting connection attempts to successive addresses (RFC 8305, section 5)
+const CONNECTION_ATTEMPT_DELAY: Duration = Duration::from_millis(250);
+
+/// Open a TCP connection to `server`, trying all of its addresses
+///
+/// The host name is resolved to all of its A and AAAA records, which are tried in the
+/// Happy Eyeballs order from [RFC 8305](https://tools.ietf.org/html/rfc8305): address families
+/// are interleaved, and a new attempt is started every 250 milliseconds or as soon as the
+/// previous one fails, until one of them succeeds. Returns the stream along with the address
+/// it is connected to.
+pub async fn connect_tcp(server: &(String, u16)) -> Result<(TcpStream, SocketAddr), Error> {
+    let addrs = interleave(lookup_host(server).await?.collect());
+    if addrs.is_empty() {
+        return Err(Error::Io(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("invalid host: {}", &server.0),
+        )));
+    }
+
+    type Attempt = Pin<Box<dyn Future<Output = io::Result<TcpStream>> + Send>>;
+    let mut remaining = addrs.into_iter().peekable();
+    let mut attempts = Vec::<(SocketAddr, Attempt)>::new();
+    let mut delay = Box::pin(tokio::time::sleep(CONNECTION_ATTEMPT_DELAY));
+    let mut last_error = None;
+
+    let result = poll_fn(|cx| loop {
+        let mut i = 0;
+        while i < attempts.len() {
+            let addr = attempts[i].0;
+            match attempts[i].1.as_mut().poll(cx) {
+                Poll::Ready(Ok(stream)) => return Poll::Ready(Ok((stream, addr))),
+                Poll::Ready(Err(err)) => {
+                    debug!("failed to connect to {addr}: {err}");
+                    last_error = Some(err);
+                    drop(attempts.swap_remove(i));
+                }
+                Poll::Pending => i += 1,
+            }
+        }
+
+        let start_next = remaining.peek().is_some()
+            && (attempts.is_empty() || delay.as_mut().poll(cx).is_ready());
+        if start_next {
+            if let Some(addr) = remaining.next() {
+                debug!("connecting to {addr}");
+                attempts.push((addr, Box::pin(TcpStream::connect(addr))));
+                delay
+                    .as_mut()
+                    .reset(Instant::now() + CONNECTION_ATTEMPT_DELAY);
+            }
+            continue;
+        }
+
+        if attempts.is_empty() {
+            return Poll::Ready(Err(last_error.take().unwrap_or_else(|| {
+                io::Error::new(io::ErrorKind::NotConnected, "no address to connect to")
+            })));
+        }
+
+        return Poll::Pending;
+    })
+    .await?;
+
+    info!("connected to {}", result.1);
+    Ok(result)
+}
+
+// Alternate between address families, starting with the family of the first address
+fn interleave(addrs: Vec<SocketAddr>) -> Vec<SocketAddr> {
+    let Some(first) = addrs.first() else {
+        return addrs;
+    };
+
+    let preferred = first.is_ipv6();
+    let (mut primary, mut secondary): (Vec<_>, Vec<_>) = addrs
+        .into_iter()
+        .partition(|addr| addr.is_ipv6() == preferred);
+    let mut out = Vec::with_capacity(primary.len() + secondary.len());
+    primary.reverse();
+    secondary.reverse();
+    loop {
+        match (primary.pop(), secondary.pop()) {
+            (None, None) => return out,
+            (a, b) => out.extend(a.into_iter().chain(b)),
+        }
+    }
+}
+
 #[async_trait]
 pub trait Connector {
     type Connection: AsyncRead + AsyncWrite + Unpin;
 
     async fn connect(&self, timeout: Duration) -> Result<Self::Connection, Error>;
 }
+
+#[cfg(test)]
+mod tests {
+    use std::net::SocketAddr;
+
+    use super::{interleave, SessionState};
+    use crate::response::{EppResult, ResultCode};
+
+    #[test]
+    fn session_states() {
+        let result = |code| EppResult {
+            code,
+            message: String::new(),
+            ext_value: None,
+        };
+        let after = |mut state: SessionState, command, code| {
+            state.observe(command, &result(code));
+            state
+        };
+
+        use ResultCode::*;
+        use SessionState::*;
+        let success = CommandCompletedSuccessfully;
+        assert_eq!(after(Greeted, Some("login"), success), LoggedIn);
+        assert_eq!(after(Greeted, Some("hello"), success), Greeted);
+        assert_eq!(after(Greeted, None, success), Greeted);
+        assert_eq!(after(Greeted, Some("login"), AuthenticationError), Greeted);
+
+        assert_eq!(after(LoggedIn, Some("info"), CommandFailed), Degraded);
+        assert_eq!(after(LoggedIn, Some("info"), ObjectDoesNotExist), LoggedIn);
+        assert_eq!(after(Degraded, Some("info"), CommandFailed), Degraded);
+        assert_eq!(after(Degraded, None, success), LoggedIn);
+
+        let ending = CommandCompletedSuccessfullyEndingSession;
+        assert_eq!(after(LoggedIn, Some("logout"), ending), Ended);
+        assert_eq!(after(Greeted, None, ending), Ended);
+
+        assert!(Greeted.check("login").is_ok());
+        assert!(Greeted.check("logout").is_ok());
+        assert!(Greeted.check("hello").is_ok());
+        for command in ["create", "poll", "renew", "info"] {
+            let err = Greeted.check(command).unwrap_err();
+            assert_eq!((err.command, err.state), (command, Greeted));
+        }
+        assert!(LoggedIn.check("create").is_ok());
+        assert!(LoggedIn.check("login").is_err());
+        assert!(Degraded.check("login").is_err());
+
+        for code in [
+            CommandFailedServerClosingConnection,
+            AuthenticationErrorServerClosingConnection,
+            SessionLimitExceededServerClosingConnection,
+        ] {
+            let state = after(LoggedIn, Some("info"), code);
+            assert_eq!(state, Closed(Some(result(code))));
+            assert!(!state.is_open());
+        }
+    }
+
+    #[test]
+    fn interleave_families() {
+        let addrs = [
+            "[2001:db8::1]:700",
+            "[2001:db8::2]:700",
+            "192.0.2.1:700",
+            "192.0.2.2:700",
+        ]
+        .iter()
+        .map(|s| s.parse::<SocketAddr>().unwrap())
+        .collect::<Vec<_>>();
+
+        let ordered = interleave(addrs.clone());
+        assert_eq!(ordered, [addrs[0], addrs[2], addrs[1], addrs[3]]);
+    }
+}
@@ -1,11 +1,13 @@
 //! Types for EPP contact check request
 
+use std::collections::HashMap;
 use std::fmt::{self, Debug};
 
 use instant_xml::{FromXml, Serializer, ToXml};
 
 use super::XMLNS;
-use crate::common::{NoExtension, EPP_XMLNS};
+use crate::common::{Availability, CheckReason, NoExtension, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for ContactCheck<'_> {}
@@ -13,6 +15,10 @@ impl Transaction<NoExtension> for ContactCheck<'_> {}
 impl Command for ContactCheck<'_> {
     type Response = CheckData;
     const COMMAND: &'static str = "check";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::ContactCheck(self)
+    }
 }
 
 // Request
@@ -40,6 +46,22 @@ pub struct ContactCheck<'a> {
     pub contact_ids: &'a [&'a str],
 }
 
+impl<'a> ContactCheck<'a> {
+    /// Split `contact_ids` into several check commands of at most `size` contact IDs each
+    ///
+    /// Like [`DomainCheck::chunked()`](crate::domain::DomainCheck::chunked), for batching
+    /// large lists.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    pub fn chunked(contact_ids: &'a [&'a str], size: usize) -> impl Iterator<Item = Self> + 'a {
+        contact_ids
+            .chunks(size)
+            .map(|contact_ids| Self { contact_ids })
+    }
+}
+
 // Response
 
 #[derive(Debug, FromXml)]
@@ -58,6 +80,29 @@ pub struct Checked {
 pub struct CheckedContact {
     /// Data under the `<cd>` tag
     pub inner: Checked,
+    /// Why the object is unavailable, if given by the server
+    pub reason: Option<Reason>,
+}
+
+impl CheckedContact {
+    /// The availability of the object, together with the reason given by the server
+    pub fn availability(&self) -> Availability<'_> {
+        let reason = self.reason.as_ref().map(|reason| CheckReason {
+            lang: reason.lang.as_deref(),
+            text: &reason.text,
+        });
+        Availability::new(self.inner.available, reason, self.inner.reason.as_deref())
+    }
+}
+
+/// Type that represents the `<reason>` tag in a `<cd>` element
+#[derive(Debug, FromXml)]
+#[xml(rename = "reason", ns(XMLNS))]
+pub struct Reason {
+    #[xml(attribute)]
+    pub lang: Option<String>,
+    #[xml(direct)]
+    pub text: String,
 }
 
 /// Type that represents the `<chkData>` tag for host check response
@@ -67,9 +112,39 @@ pub struct CheckData {
     pub list: Vec<CheckedContact>,
 }
 
+impl fmt::Display for CheckedContact {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.inner.id, self.availability())
+    }
+}
+
+impl CheckData {
+    /// The checked contact IDs, keyed by their IDs
+    pub fn into_map(self) -> HashMap<String, CheckedContact> {
+        self.list
+            .into_iter()
+            .map(|checked| (checked.inner.id.clone(), checked))
+            .collect()
+    }
+}
+
+/// One line per checked object, like `example.com: unavailable (In use)`
+impl fmt::Display for CheckData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, checked) in self.list.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{checked}")?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::ContactCheck;
+    use crate::common::{Availability, CheckReason};
     use crate::response::ResultCode;
     use crate::tests::{assert_serialized, response_from_file, CLTRID, SUCCESS_MSG, SVTRID};
 
@@ -95,4 +170,42 @@ mod tests {
         assert_eq!(object.tr_ids.client_tr_id.unwrap(), CLTRID);
         assert_eq!(object.tr_ids.server_tr_id, SVTRID);
     }
+
+    #[test]
+    fn response_reason() {
+        let object = response_from_file::<ContactCheck>("response/contact/check_reason.xml");
+        let results = object.res_data().unwrap();
+
+        assert_eq!(
+            results.list[0].availability(),
+            Availability::Unavailable {
+                reason: Some(CheckReason {
+                    lang: None,
+                    text: "In use"
+                })
+            }
+        );
+        assert_eq!(results.list[1].availability(), Availability::Available);
+    }
+
+    #[test]
+    fn chunked() {
+        let chunks = ContactCheck::chunked(
+            &["eppdev-contact-1", "eppdev-contact-2", "eppdev-contact-3"],
+            2,
+        )
+        .collect::<Vec<_>>();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].contact_ids.len(), 2);
+        assert_eq!(chunks[1].contact_ids.len(), 1);
+    }
+
+    #[test]
+    fn response_map() {
+        let object = response_from_file::<ContactCheck>("response/contact/check.xml");
+        let map = object.into_data().unwrap().into_map();
+
+        assert_eq!(map.len(), 2);
+        assert!(!map["eppdev-contact-1"].availability().is_available());
+    }
 }
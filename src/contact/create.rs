@@ -5,6 +5,7 @@ use instant_xml::{FromXml, ToXml};
 
 use super::{ContactAuthInfo, Fax, PostalInfo, Voice, XMLNS};
 use crate::common::{NoExtension, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for ContactCreate<'_> {}
@@ -12,6 +13,10 @@ impl Transaction<NoExtension> for ContactCreate<'_> {}
 impl Command for ContactCreate<'_> {
     type Response = CreateData;
     const COMMAND: &'static str = "create";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::ContactCreate(self)
+    }
 }
 
 // Request
@@ -66,6 +71,11 @@ impl<'a> ContactCreate<'a> {
     pub fn set_fax(&mut self, fax: Fax<'a>) {
         self.contact.fax = Some(fax);
     }
+
+    /// The ID of the contact to create
+    pub fn id(&self) -> &'a str {
+        self.contact.id
+    }
 }
 
 // Response
@@ -4,6 +4,7 @@ use instant_xml::ToXml;
 
 use super::XMLNS;
 use crate::common::{NoExtension, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for ContactDelete<'_> {}
@@ -11,6 +12,10 @@ impl Transaction<NoExtension> for ContactDelete<'_> {}
 impl Command for ContactDelete<'_> {
     type Response = ();
     const COMMAND: &'static str = "delete";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::ContactDelete(self)
+    }
 }
 
 /// Type containing the data for the `<delete>` tag for contacts
@@ -35,6 +40,11 @@ impl<'a> ContactDelete<'a> {
             contact: ContactDeleteRequest { id },
         }
     }
+
+    /// The ID of the contact to delete
+    pub fn id(&self) -> &'a str {
+        self.contact.id
+    }
 }
 
 #[cfg(test)]
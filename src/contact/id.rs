@@ -0,0 +1,138 @@
+//! Generating and validating contact IDs
+
+use std::collections::hash_map::RandomState;
+use std::error::Error as StdError;
+use std::fmt;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// The minimum length of a contact ID (`clIDType` in RFC 5730)
+pub const MIN_ID_LEN: usize = 3;
+
+/// The maximum length of a contact ID (`clIDType` in RFC 5730)
+pub const MAX_ID_LEN: usize = 16;
+
+/// The minimum number of random characters in a generated ID
+const MIN_RANDOM_LEN: usize = 4;
+
+const ALPHABET: &[u8; 36] = b"abcdefghijklmnopqrstuvwxyz0123456789";
+
+/// Check that `id` is a contact ID that registries will accept
+///
+/// The schema allows any token of 3 to 16 characters, but registries commonly restrict the
+/// character set further. This only accepts ASCII letters, digits, `-` and `_`, which are
+/// safe everywhere.
+pub fn validate_id(id: &str) -> Result<(), InvalidContactId> {
+    if id.len() < MIN_ID_LEN {
+        return Err(InvalidContactId::TooShort(id.to_owned()));
+    }
+
+    if id.len() > MAX_ID_LEN {
+        return Err(InvalidContactId::TooLong(id.to_owned()));
+    }
+
+    if id
+        .bytes()
+        .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+    {
+        Ok(())
+    } else {
+        Err(InvalidContactId::InvalidCharacters(id.to_owned()))
+    }
+}
+
+/// Generate a random contact ID starting with `prefix`
+///
+/// The prefix is followed by random lowercase letters and digits up to the maximum length of
+/// 16 characters, so the prefix can be at most 12 characters long. The IDs are unpredictable
+/// enough to avoid collisions, but are not suitable as secrets.
+pub fn generate_id(prefix: &str) -> Result<String, InvalidContactId> {
+    if prefix.len() > MAX_ID_LEN - MIN_RANDOM_LEN {
+        return Err(InvalidContactId::TooLong(prefix.to_owned()));
+    }
+
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let mut id = String::with_capacity(MAX_ID_LEN);
+    id.push_str(prefix);
+    while id.len() < MAX_ID_LEN {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(COUNTER.fetch_add(1, Ordering::Relaxed));
+        let mut value = hasher.finish();
+
+        // A 64-bit value holds 12 base-36 digits
+        for _ in 0..12 {
+            if id.len() == MAX_ID_LEN {
+                break;
+            }
+
+            id.push(char::from(ALPHABET[(value % 36) as usize]));
+            value /= 36;
+        }
+    }
+
+    validate_id(&id)?;
+    Ok(id)
+}
+
+/// A contact ID rejected by [`validate_id()`] or [`generate_id()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum InvalidContactId {
+    /// The ID is shorter than 3 characters
+    TooShort(String),
+    /// The ID (or prefix) is too long
+    TooLong(String),
+    /// The ID contains characters other than ASCII letters, digits, `-` and `_`
+    InvalidCharacters(String),
+}
+
+impl fmt::Display for InvalidContactId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooShort(id) => write!(f, "contact ID {id:?} is too short"),
+            Self::TooLong(id) => write!(f, "contact ID {id:?} is too long"),
+            Self::InvalidCharacters(id) => {
+                write!(f, "contact ID {id:?} contains invalid characters")
+            }
+        }
+    }
+}
+
+impl StdError for InvalidContactId {}
+
+impl From<InvalidContactId> for crate::Error {
+    fn from(err: InvalidContactId) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{generate_id, validate_id, InvalidContactId};
+
+    #[test]
+    fn validate() {
+        assert_eq!(validate_id("eppdev-contact-1"), Ok(()));
+        assert_eq!(validate_id("C_123"), Ok(()));
+        assert_eq!(
+            validate_id("ab"),
+            Err(InvalidContactId::TooShort("ab".into()))
+        );
+        assert_eq!(
+            validate_id("eppdev-contact-12"),
+            Err(InvalidContactId::TooLong("eppdev-contact-12".into()))
+        );
+        assert_eq!(
+            validate_id("john doe"),
+            Err(InvalidContactId::InvalidCharacters("john doe".into()))
+        );
+    }
+
+    #[test]
+    fn generate() {
+        let first = generate_id("idc-").unwrap();
+        assert!(first.starts_with("idc-"));
+        assert_eq!(first.len(), 16);
+        assert_ne!(first, generate_id("idc-").unwrap());
+        assert!(generate_id("much-too-long").is_err());
+    }
+}
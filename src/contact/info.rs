@@ -3,8 +3,11 @@
 use chrono::{DateTime, Utc};
 use instant_xml::{FromXml, ToXml};
 
-use super::{ContactAuthInfo, Fax, PostalInfo, Status, Voice, XMLNS};
-use crate::common::{NoExtension, EPP_XMLNS};
+use super::{
+    ContactAuthInfo, Disclose, DiscloseField, Fax, InfoType, PostalInfo, Status, Voice, XMLNS,
+};
+use crate::common::{NoExtension, Roid, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for ContactInfo<'_> {}
@@ -12,6 +15,10 @@ impl Transaction<NoExtension> for ContactInfo<'_> {}
 impl Command for ContactInfo<'_> {
     type Response = InfoData;
     const COMMAND: &'static str = "info";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::ContactInfo(self)
+    }
 }
 
 // Request
@@ -23,7 +30,7 @@ pub struct ContactInfoRequest<'a> {
     /// The contact id for the info command
     id: &'a str,
     /// The `<authInfo>` data
-    auth_info: ContactAuthInfo<'a>,
+    auth_info: Option<ContactAuthInfo<'a>>,
 }
 
 /// Type for EPP XML `<info>` command for contacts
@@ -39,10 +46,25 @@ impl<'a> ContactInfo<'a> {
         Self {
             info: ContactInfoRequest {
                 id,
-                auth_info: ContactAuthInfo::new(auth_password),
+                auth_info: Some(ContactAuthInfo::new(auth_password)),
+            },
+        }
+    }
+
+    /// Query a contact without its password, as the registrar sponsoring it can
+    pub fn sponsored(id: &'a str) -> Self {
+        Self {
+            info: ContactInfoRequest {
+                id,
+                auth_info: None,
             },
         }
     }
+
+    /// The ID of the contact to query
+    pub fn id(&self) -> &'a str {
+        self.info.id
+    }
 }
 
 // Response
@@ -54,11 +76,11 @@ pub struct InfoData {
     /// The contact id
     pub id: String,
     /// The contact ROID
-    pub roid: String,
+    pub roid: Roid,
     /// The list of contact statuses
     pub statuses: Vec<Status>,
-    /// The postal info for the contact
-    pub postal_info: PostalInfo<'static>,
+    /// The postal info for the contact, in internationalized and/or localized form
+    pub postal_info: Vec<PostalInfo<'static>>,
     /// The voice data for the contact
     pub voice: Option<Voice<'static>>,
     /// The fax data for the contact
@@ -95,6 +117,22 @@ pub struct InfoData {
     /// The contact auth info
     #[xml(rename = "authInfo")]
     pub auth_info: Option<ContactAuthInfo<'static>>,
+    /// Exceptions from the server's disclosure policy
+    pub disclose: Option<Disclose>,
+}
+
+impl InfoData {
+    /// The postal info of the given type, if present
+    pub fn postal_info_by_type(&self, info_type: InfoType) -> Option<&PostalInfo<'static>> {
+        self.postal_info
+            .iter()
+            .find(|info| info.info_type == info_type)
+    }
+
+    /// Whether `field` is disclosed, or `None` if server policy applies to it
+    pub fn disclosed(&self, field: DiscloseField) -> Option<bool> {
+        self.disclose.as_ref()?.disclosed(field)
+    }
 }
 
 #[cfg(test)]
@@ -102,7 +140,7 @@ mod tests {
     use chrono::{TimeZone, Utc};
 
     use super::ContactInfo;
-    use crate::contact::{InfoType, Status};
+    use crate::contact::{DiscloseField, InfoType, Status};
     use crate::response::ResultCode;
     use crate::tests::{assert_serialized, response_from_file, CLTRID, SUCCESS_MSG, SVTRID};
 
@@ -110,6 +148,9 @@ mod tests {
     fn command() {
         let object = ContactInfo::new("eppdev-contact-3", "eppdev-387323");
         assert_serialized("request/contact/info.xml", &object);
+
+        let object = ContactInfo::sponsored("eppdev-contact-3");
+        assert_serialized("request/contact/info_sponsored.xml", &object);
     }
 
     #[test]
@@ -127,18 +168,21 @@ mod tests {
         assert_eq!(result.id, "eppdev-contact-3");
         assert_eq!(result.roid, "UNDEF-ROID");
         assert_eq!(result.statuses[0], Status::Ok);
-        assert_eq!(result.postal_info.info_type, InfoType::Local);
-        assert_eq!(result.postal_info.name, "John Doe");
-        assert_eq!(result.postal_info.organization, Some("Acme Widgets".into()));
-        assert_eq!(result.postal_info.address.street[0], "58");
-        assert_eq!(result.postal_info.address.street[1], "Orchid Road");
-        assert_eq!(result.postal_info.address.city, "Paris");
-        assert_eq!(result.postal_info.address.province, Some("Paris".into()));
+        assert_eq!(result.postal_info[0].info_type, InfoType::Local);
+        assert_eq!(result.postal_info[0].name, "John Doe");
+        assert_eq!(
+            result.postal_info[0].organization,
+            Some("Acme Widgets".into())
+        );
+        assert_eq!(result.postal_info[0].address.street[0], "58");
+        assert_eq!(result.postal_info[0].address.street[1], "Orchid Road");
+        assert_eq!(result.postal_info[0].address.city, "Paris");
+        assert_eq!(result.postal_info[0].address.province, Some("Paris".into()));
         assert_eq!(
-            result.postal_info.address.postal_code,
+            result.postal_info[0].address.postal_code,
             Some("392374".into())
         );
-        assert_eq!(result.postal_info.address.country.alpha2, "FR");
+        assert_eq!(result.postal_info[0].address.country.alpha2, "FR");
         assert_eq!(
             result.voice.as_ref().unwrap().number,
             "+33.47237942".to_string()
@@ -174,15 +218,15 @@ mod tests {
         assert_eq!(result.id, "eppdev-contact-3");
         assert_eq!(result.roid, "UNDEF-ROID");
         assert_eq!(result.statuses[0], Status::Ok);
-        assert_eq!(result.postal_info.info_type, InfoType::Local);
-        assert_eq!(result.postal_info.name, "John Doe");
-        assert_eq!(result.postal_info.organization, None);
-        assert_eq!(result.postal_info.address.street[0], "58");
-        assert_eq!(result.postal_info.address.street[1], "Orchid Road");
-        assert_eq!(result.postal_info.address.city, "Paris");
-        assert_eq!(result.postal_info.address.province, None);
-        assert_eq!(result.postal_info.address.postal_code, None);
-        assert_eq!(result.postal_info.address.country.alpha2, "FR");
+        assert_eq!(result.postal_info[0].info_type, InfoType::Local);
+        assert_eq!(result.postal_info[0].name, "John Doe");
+        assert_eq!(result.postal_info[0].organization, None);
+        assert_eq!(result.postal_info[0].address.street[0], "58");
+        assert_eq!(result.postal_info[0].address.street[1], "Orchid Road");
+        assert_eq!(result.postal_info[0].address.city, "Paris");
+        assert_eq!(result.postal_info[0].address.province, None);
+        assert_eq!(result.postal_info[0].address.postal_code, None);
+        assert_eq!(result.postal_info[0].address.country.alpha2, "FR");
         assert_eq!(result.voice, None);
         assert_eq!(result.fax, None);
         assert_eq!(result.email.as_deref(), Some("contact@eppdev.net"));
@@ -198,4 +242,38 @@ mod tests {
         assert_eq!(object.tr_ids.client_tr_id.unwrap(), CLTRID);
         assert_eq!(object.tr_ids.server_tr_id, SVTRID);
     }
+
+    #[test]
+    fn response_disclose() {
+        let object = response_from_file::<ContactInfo>("response/contact/info_disclose.xml");
+        let result = object.res_data().unwrap();
+
+        assert_eq!(result.postal_info.len(), 2);
+        let int = result.postal_info_by_type(InfoType::International).unwrap();
+        assert_eq!(int.name, "Taro Yamada");
+        let loc = result.postal_info_by_type(InfoType::Local).unwrap();
+        assert_eq!(loc.name, "山田太郎");
+        assert_eq!(loc.address.city, "東京");
+        assert_eq!(
+            result.voice.as_ref().unwrap().extension.as_deref(),
+            Some("1234")
+        );
+
+        let disclose = result.disclose.as_ref().unwrap();
+        assert!(!disclose.flag);
+        assert_eq!(
+            disclose.fields(),
+            [
+                DiscloseField::Name(InfoType::Local),
+                DiscloseField::Addr(InfoType::Local),
+                DiscloseField::Voice,
+                DiscloseField::Email,
+            ]
+        );
+        assert_eq!(result.disclosed(DiscloseField::Email), Some(false));
+        assert_eq!(
+            result.disclosed(DiscloseField::Name(InfoType::International)),
+            None
+        );
+    }
 }
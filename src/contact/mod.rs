@@ -8,6 +8,8 @@ use std::str::FromStr;
 
 use instant_xml::{display_to_xml, from_xml_str, Deserializer, FromXml, Serializer, ToXml};
 
+use crate::common::Boolean;
+
 pub mod check;
 pub use check::ContactCheck;
 
@@ -17,6 +19,9 @@ pub use create::ContactCreate;
 pub mod delete;
 pub use delete::ContactDelete;
 
+pub mod id;
+pub use id::{generate_id, validate_id};
+
 pub mod info;
 pub use info::ContactInfo;
 
@@ -66,6 +71,14 @@ impl FromStr for Country {
     }
 }
 
+impl PartialEq for Country {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.alpha2 == other.0.alpha2
+    }
+}
+
+impl Eq for Country {}
+
 impl std::ops::Deref for Country {
     type Target = celes::Country;
 
@@ -147,7 +160,7 @@ impl<'a> Fax<'a> {
 }
 
 /// The `<addr>` type on contact transactions
-#[derive(Clone, Debug, FromXml, ToXml)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq, ToXml)]
 #[xml(rename = "addr", ns(XMLNS))]
 pub struct Address<'a> {
     /// The `<street>` tags under `<addr>`
@@ -187,7 +200,7 @@ impl<'a> Address<'a> {
 }
 
 /// The `<postalInfo>` type on contact transactions
-#[derive(Clone, Debug, FromXml, ToXml)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq, ToXml)]
 #[xml(rename = "postalInfo", ns(XMLNS))]
 pub struct PostalInfo<'a> {
     /// The 'type' attr on `<postalInfo>`
@@ -219,7 +232,7 @@ impl<'a> PostalInfo<'a> {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, ToXml, FromXml)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ToXml, FromXml)]
 #[xml(scalar)]
 pub enum InfoType {
     #[xml(rename = "loc")]
@@ -228,6 +241,146 @@ pub enum InfoType {
     International,
 }
 
+/// A piece of contact data that can be listed in a `<disclose>` element
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DiscloseField {
+    Name(InfoType),
+    Org(InfoType),
+    Addr(InfoType),
+    Voice,
+    Fax,
+    Email,
+}
+
+/// The `<disclose>` element in a contact info response or update request
+///
+/// With `flag` set, the listed fields are disclosed although server policy would not do so;
+/// without it, the listed fields are withheld although server policy would disclose them.
+#[derive(Clone, Debug, FromXml)]
+#[xml(rename = "disclose", ns(XMLNS))]
+pub struct Disclose {
+    /// Whether the listed fields are disclosed (`true`) or withheld (`false`)
+    #[xml(attribute)]
+    pub flag: bool,
+    name: Vec<DiscloseName>,
+    org: Vec<DiscloseOrg>,
+    addr: Vec<DiscloseAddr>,
+    voice: Option<DiscloseVoice>,
+    fax: Option<DiscloseFax>,
+    email: Option<DiscloseEmail>,
+}
+
+impl Disclose {
+    /// Disclose (`flag` is `true`) or withhold (`flag` is `false`) the given fields
+    pub fn new(flag: bool, fields: &[DiscloseField]) -> Self {
+        let mut disclose = Self {
+            flag,
+            name: Vec::new(),
+            org: Vec::new(),
+            addr: Vec::new(),
+            voice: None,
+            fax: None,
+            email: None,
+        };
+
+        for field in fields {
+            match *field {
+                DiscloseField::Name(info_type) => disclose.name.push(DiscloseName { info_type }),
+                DiscloseField::Org(info_type) => disclose.org.push(DiscloseOrg { info_type }),
+                DiscloseField::Addr(info_type) => disclose.addr.push(DiscloseAddr { info_type }),
+                DiscloseField::Voice => disclose.voice = Some(DiscloseVoice),
+                DiscloseField::Fax => disclose.fax = Some(DiscloseFax),
+                DiscloseField::Email => disclose.email = Some(DiscloseEmail),
+            }
+        }
+
+        disclose
+    }
+
+    /// The fields this element applies to
+    pub fn fields(&self) -> Vec<DiscloseField> {
+        let mut fields = Vec::new();
+        fields.extend(self.name.iter().map(|f| DiscloseField::Name(f.info_type)));
+        fields.extend(self.org.iter().map(|f| DiscloseField::Org(f.info_type)));
+        fields.extend(self.addr.iter().map(|f| DiscloseField::Addr(f.info_type)));
+        fields.extend(self.voice.as_ref().map(|_| DiscloseField::Voice));
+        fields.extend(self.fax.as_ref().map(|_| DiscloseField::Fax));
+        fields.extend(self.email.as_ref().map(|_| DiscloseField::Email));
+        fields
+    }
+
+    /// Whether `field` is disclosed, or `None` if server policy applies to it
+    pub fn disclosed(&self, field: DiscloseField) -> Option<bool> {
+        self.fields().contains(&field).then_some(self.flag)
+    }
+}
+
+impl ToXml for Disclose {
+    fn serialize<W: fmt::Write + ?Sized>(
+        &self,
+        _: Option<instant_xml::Id<'_>>,
+        serializer: &mut Serializer<W>,
+    ) -> Result<(), instant_xml::Error> {
+        SerializeDisclose {
+            flag: Boolean(self.flag),
+            name: &self.name,
+            org: &self.org,
+            addr: &self.addr,
+            voice: self.voice.as_ref(),
+            fax: self.fax.as_ref(),
+            email: self.email.as_ref(),
+        }
+        .serialize(None, serializer)
+    }
+}
+
+// The `<disclose>` element as sent, with the flag in the configured boolean format
+#[derive(ToXml)]
+#[xml(rename = "disclose", ns(XMLNS))]
+struct SerializeDisclose<'a> {
+    #[xml(attribute)]
+    flag: Boolean,
+    name: &'a [DiscloseName],
+    org: &'a [DiscloseOrg],
+    addr: &'a [DiscloseAddr],
+    voice: Option<&'a DiscloseVoice>,
+    fax: Option<&'a DiscloseFax>,
+    email: Option<&'a DiscloseEmail>,
+}
+
+#[derive(Clone, Debug, FromXml, ToXml)]
+#[xml(rename = "name", ns(XMLNS))]
+struct DiscloseName {
+    #[xml(rename = "type", attribute)]
+    info_type: InfoType,
+}
+
+#[derive(Clone, Debug, FromXml, ToXml)]
+#[xml(rename = "org", ns(XMLNS))]
+struct DiscloseOrg {
+    #[xml(rename = "type", attribute)]
+    info_type: InfoType,
+}
+
+#[derive(Clone, Debug, FromXml, ToXml)]
+#[xml(rename = "addr", ns(XMLNS))]
+struct DiscloseAddr {
+    #[xml(rename = "type", attribute)]
+    info_type: InfoType,
+}
+
+#[derive(Clone, Debug, FromXml, ToXml)]
+#[xml(rename = "voice", ns(XMLNS))]
+struct DiscloseVoice;
+
+#[derive(Clone, Debug, FromXml, ToXml)]
+#[xml(rename = "fax", ns(XMLNS))]
+struct DiscloseFax;
+
+#[derive(Clone, Debug, FromXml, ToXml)]
+#[xml(rename = "email", ns(XMLNS))]
+struct DiscloseEmail;
+
 /// The `<status>` type on contact transactions
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Status {
@@ -2,8 +2,9 @@
 
 use instant_xml::ToXml;
 
-use super::{ContactAuthInfo, Fax, PostalInfo, Status, Voice, XMLNS};
+use super::{ContactAuthInfo, Disclose, Fax, InfoType, PostalInfo, Status, Voice, XMLNS};
 use crate::common::{NoExtension, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for ContactUpdate<'_> {}
@@ -11,6 +12,10 @@ impl Transaction<NoExtension> for ContactUpdate<'_> {}
 impl Command for ContactUpdate<'_> {
     type Response = ();
     const COMMAND: &'static str = "update";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::ContactUpdate(self)
+    }
 }
 
 impl<'a> ContactUpdate<'a> {
@@ -35,10 +40,11 @@ impl<'a> ContactUpdate<'a> {
     ) {
         self.contact.change_info = Some(ContactChangeInfo {
             email: Some(email),
-            postal_info: Some(postal_info),
+            postal_info: vec![postal_info],
             voice: Some(voice),
             auth_info: Some(ContactAuthInfo::new(auth_password)),
             fax: None,
+            disclose: None,
         });
     }
 
@@ -58,17 +64,152 @@ impl<'a> ContactUpdate<'a> {
     pub fn remove(&mut self, statuses: &'a [Status]) {
         self.contact.remove_statuses = Some(RemoveStatuses { statuses });
     }
+
+    /// The ID of the contact to update
+    pub fn id(&self) -> &'a str {
+        self.contact.id
+    }
+
+    /// Build an update changing only the given parts of the contact
+    pub fn builder(id: &'a str) -> UpdateBuilder<'a> {
+        UpdateBuilder {
+            id,
+            add: None,
+            remove: None,
+            postal_info: Vec::new(),
+            voice: None,
+            fax: None,
+            email: None,
+            auth_password: None,
+            disclose: None,
+        }
+    }
+}
+
+/// Builder for [`ContactUpdate`] that only sends the elements being changed
+///
+/// Postal info is kept per type, so a contact can get its internationalized (`int`) and
+/// localized (`loc`) forms changed in the same update. [`build()`](Self::build) rejects
+/// updates that change nothing, and statuses both added and removed.
+#[derive(Debug)]
+pub struct UpdateBuilder<'a> {
+    id: &'a str,
+    add: Option<&'a [Status]>,
+    remove: Option<&'a [Status]>,
+    postal_info: Vec<PostalInfo<'a>>,
+    voice: Option<Voice<'a>>,
+    fax: Option<Fax<'a>>,
+    email: Option<&'a str>,
+    auth_password: Option<&'a str>,
+    disclose: Option<Disclose>,
+}
+
+impl<'a> UpdateBuilder<'a> {
+    /// Add the given statuses
+    pub fn add_statuses(mut self, statuses: &'a [Status]) -> Self {
+        self.add = Some(statuses);
+        self
+    }
+
+    /// Remove the given statuses
+    pub fn remove_statuses(mut self, statuses: &'a [Status]) -> Self {
+        self.remove = Some(statuses);
+        self
+    }
+
+    /// Change the postal info of the type of `postal_info`, replacing earlier calls for it
+    pub fn postal_info(mut self, postal_info: PostalInfo<'a>) -> Self {
+        self.postal_info
+            .retain(|info| info.info_type != postal_info.info_type);
+        self.postal_info.push(postal_info);
+        self
+    }
+
+    /// Change the voice telephone number
+    pub fn voice(mut self, voice: Voice<'a>) -> Self {
+        self.voice = Some(voice);
+        self
+    }
+
+    /// Change the fax number
+    pub fn fax(mut self, fax: Fax<'a>) -> Self {
+        self.fax = Some(fax);
+        self
+    }
+
+    /// Change the email address
+    pub fn email(mut self, email: &'a str) -> Self {
+        self.email = Some(email);
+        self
+    }
+
+    /// Change the authorization password
+    pub fn auth_password(mut self, password: &'a str) -> Self {
+        self.auth_password = Some(password);
+        self
+    }
+
+    /// Change which fields are disclosed
+    pub fn disclose(mut self, disclose: Disclose) -> Self {
+        self.disclose = Some(disclose);
+        self
+    }
+
+    pub fn build(self) -> Result<ContactUpdate<'a>, crate::Error> {
+        let (add, remove) = (
+            self.add.unwrap_or_default(),
+            self.remove.unwrap_or_default(),
+        );
+        if let Some(status) = add.iter().find(|status| remove.contains(status)) {
+            return Err(crate::Error::Other(
+                format!("cannot both add and remove status {}", status.as_str()).into(),
+            ));
+        }
+
+        let change = !self.postal_info.is_empty()
+            || self.voice.is_some()
+            || self.fax.is_some()
+            || self.email.is_some()
+            || self.auth_password.is_some()
+            || self.disclose.is_some();
+
+        if add.is_empty() && remove.is_empty() && !change {
+            return Err(crate::Error::Other("empty contact update".into()));
+        }
+
+        // RFC 5733 requires `int` postal info to precede `loc`
+        let mut postal_info = self.postal_info;
+        postal_info.sort_by_key(|info| info.info_type != InfoType::International);
+
+        Ok(ContactUpdate {
+            contact: ContactUpdateRequest {
+                id: self.id,
+                add_statuses: (!add.is_empty()).then_some(AddStatuses { statuses: add }),
+                remove_statuses: (!remove.is_empty())
+                    .then_some(RemoveStatuses { statuses: remove }),
+                change_info: change.then_some(ContactChangeInfo {
+                    postal_info,
+                    voice: self.voice,
+                    fax: self.fax,
+                    email: self.email,
+                    auth_info: self.auth_password.map(ContactAuthInfo::new),
+                    disclose: self.disclose,
+                }),
+            },
+        })
+    }
 }
 
 /// Type for elements under the `<chg>` tag for contact update request
 #[derive(Debug, ToXml)]
 #[xml(rename = "chg", ns(XMLNS))]
 pub struct ContactChangeInfo<'a> {
-    postal_info: Option<PostalInfo<'a>>,
+    postal_info: Vec<PostalInfo<'a>>,
     voice: Option<Voice<'a>>,
     fax: Option<Fax<'a>>,
     email: Option<&'a str>,
     auth_info: Option<ContactAuthInfo<'a>>,
+    disclose: Option<Disclose>,
 }
 
 #[derive(Debug, ToXml)]
@@ -105,7 +246,7 @@ pub struct ContactUpdate<'a> {
 #[cfg(test)]
 mod tests {
     use super::{ContactUpdate, PostalInfo, Status, Voice};
-    use crate::contact::{Address, InfoType};
+    use crate::contact::{Address, Disclose, DiscloseField, InfoType};
     use crate::response::ResultCode;
     use crate::tests::{assert_serialized, response_from_file, CLTRID, SUCCESS_MSG, SVTRID};
 
@@ -132,6 +273,52 @@ mod tests {
         assert_serialized("request/contact/update.xml", &object);
     }
 
+    #[test]
+    fn builder() {
+        let address = |street| Address::new(&[street], "Paris", None, None, "FR".parse().unwrap());
+        let int = PostalInfo::new(
+            InfoType::International,
+            "John Doe",
+            None,
+            address("58 Orchid Road"),
+        );
+        let loc = PostalInfo::new(
+            InfoType::Local,
+            "Jean Dupont",
+            None,
+            address("58 rue des Orchidées"),
+        );
+        let disclose = Disclose::new(
+            false,
+            &[
+                DiscloseField::Email,
+                DiscloseField::Name(InfoType::Local),
+                DiscloseField::Voice,
+            ],
+        );
+
+        let object = ContactUpdate::builder("eppdev-contact-3")
+            .postal_info(loc.clone())
+            .postal_info(int)
+            .email("newemail@eppdev.net")
+            .disclose(disclose)
+            .remove_statuses(&[Status::ClientDeleteProhibited])
+            .build()
+            .unwrap();
+
+        assert_serialized("request/contact/update_builder.xml", &object);
+
+        let empty = ContactUpdate::builder("eppdev-contact-3").build();
+        assert!(empty.is_err());
+
+        let conflicting = ContactUpdate::builder("eppdev-contact-3")
+            .add_statuses(&[Status::ClientUpdateProhibited])
+            .remove_statuses(&[Status::ClientUpdateProhibited])
+            .postal_info(loc)
+            .build();
+        assert!(conflicting.is_err());
+    }
+
     #[test]
     fn contact_update() {
         let object = response_from_file::<ContactUpdate>("response/contact/update.xml");
@@ -1,9 +1,29 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use instant_xml::{de::Deserializer, Error, OptionAccumulator};
 
+/// Parse a timestamp from a registry response
+///
+/// Registries don't all stick to the `dateTime` format from XML Schema, so this accepts:
+///
+/// - any number of fractional second digits, or none at all
+/// - `Z`, `z` or a numeric offset, with or without a colon (`+01:00`, `+0100`)
+/// - no offset at all, in which case the time is taken to be UTC
+/// - a space instead of `T` between the date and the time
+///
+/// Anything else is rejected rather than guessed at.
 fn parse_datetime_utc(value: &str) -> Result<DateTime<Utc>, Error> {
-    DateTime::parse_from_rfc3339(value.trim())
-        .map(|dt| dt.with_timezone(&Utc))
+    let value = value.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    let value = value.replacen(' ', "T", 1);
+    if let Ok(dt) = DateTime::parse_from_str(&value, "%Y-%m-%dT%H:%M:%S%.f%z") {
+        return Ok(dt.with_timezone(&Utc));
+    }
+
+    NaiveDateTime::parse_from_str(&value, "%Y-%m-%dT%H:%M:%S%.f")
+        .map(|dt| dt.and_utc())
         .map_err(|_| Error::Other("invalid date/time".into()))
 }
 
@@ -56,4 +76,29 @@ mod tests {
 
         assert_eq!(object.dt, Utc.with_ymd_and_hms(2026, 3, 30, 1, 36, 20).unwrap());
     }
+
+    #[test]
+    fn lenient_formats() {
+        let expected = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        for value in [
+            "2024-01-02T03:04:05Z",
+            "2024-01-02T03:04:05.0Z",
+            "2024-01-02T03:04:05.000000z",
+            "2024-01-02T03:04:05+00:00",
+            "2024-01-02T04:04:05+0100",
+            "2024-01-02T03:04:05",
+            "2024-01-02T03:04:05.0",
+            "2024-01-02 03:04:05Z",
+            " 2024-01-02T03:04:05Z\n",
+        ] {
+            assert_eq!(
+                super::parse_datetime_utc(value).unwrap(),
+                expected,
+                "{value}"
+            );
+        }
+
+        assert!(super::parse_datetime_utc("2024-01-02").is_err());
+        assert!(super::parse_datetime_utc("02/01/2024 03:04:05").is_err());
+    }
 }
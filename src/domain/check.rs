@@ -1,11 +1,13 @@
 //! Types for EPP domain check request
 
+use std::collections::HashMap;
 use std::fmt;
 
 use instant_xml::{FromXml, Serializer, ToXml};
 
-use super::XMLNS;
-use crate::common::{NoExtension, EPP_XMLNS};
+use super::{normalize_name, XMLNS};
+use crate::common::{Availability, CheckReason, NoExtension, Text, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for DomainCheck<'_> {}
@@ -13,6 +15,10 @@ impl Transaction<NoExtension> for DomainCheck<'_> {}
 impl Command for DomainCheck<'_> {
     type Response = CheckData;
     const COMMAND: &'static str = "check";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::DomainCheck(self)
+    }
 }
 
 // Request
@@ -39,9 +45,23 @@ pub struct DomainCheck<'a> {
     pub domains: &'a [&'a str],
 }
 
+impl<'a> DomainCheck<'a> {
+    /// Split `domains` into several check commands of at most `size` domains each
+    ///
+    /// Registries limit the number of objects in a single check command, so large lists
+    /// have to be sent in batches.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    pub fn chunked(domains: &'a [&'a str], size: usize) -> impl Iterator<Item = Self> + 'a {
+        domains.chunks(size).map(|domains| Self { domains })
+    }
+}
+
 // Response
 
-#[derive(Debug, FromXml)]
+#[derive(Clone, Debug, FromXml)]
 #[xml(rename = "name", ns(XMLNS))]
 pub struct Checked {
     #[xml(attribute, rename = "avail")]
@@ -52,12 +72,35 @@ pub struct Checked {
     pub id: String,
 }
 
-#[derive(Debug, FromXml)]
+#[derive(Clone, Debug, FromXml)]
 #[xml(rename = "cd", ns(XMLNS))]
 pub struct CheckedDomain {
     /// Data under the `<cd>` tag
     #[xml(rename = "cd")]
     pub inner: Checked,
+    /// Why the object is unavailable, if given by the server
+    pub reason: Option<Reason>,
+}
+
+impl CheckedDomain {
+    /// The availability of the object, together with the reason given by the server
+    pub fn availability(&self) -> Availability<'_> {
+        let reason = self.reason.as_ref().map(|reason| CheckReason {
+            lang: reason.lang.as_deref(),
+            text: &reason.text,
+        });
+        Availability::new(self.inner.available, reason, self.inner.reason.as_deref())
+    }
+}
+
+/// Type that represents the `<reason>` tag in a `<cd>` element
+#[derive(Clone, Debug, FromXml)]
+#[xml(rename = "reason", ns(XMLNS))]
+pub struct Reason {
+    #[xml(attribute)]
+    pub lang: Option<String>,
+    #[xml(direct)]
+    pub text: String,
 }
 
 /// Type that represents the `<chkData>` tag for host check response
@@ -67,11 +110,140 @@ pub struct CheckData {
     pub list: Vec<CheckedDomain>,
 }
 
+impl fmt::Display for CheckedDomain {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.inner.id, self.availability())
+    }
+}
+
+impl CheckData {
+    /// The result for `name`, compared after [normalization](super::normalize_name)
+    pub fn get(&self, name: &str) -> Option<&CheckedDomain> {
+        let name = normalize_name(name);
+        self.list
+            .iter()
+            .find(|checked| normalize_name(&checked.inner.id) == name)
+    }
+
+    /// The names of the domains that are available, as returned by the server
+    pub fn available_names(&self) -> impl Iterator<Item = &str> {
+        self.list
+            .iter()
+            .filter(|checked| checked.inner.available)
+            .map(|checked| checked.inner.id.as_str())
+    }
+
+    /// The checked domains, keyed by their [normalized](super::normalize_name) names
+    pub fn into_map(self) -> HashMap<String, CheckedDomain> {
+        self.list
+            .into_iter()
+            .map(|checked| (normalize_name(&checked.inner.id), checked))
+            .collect()
+    }
+}
+
+/// A `<chkData>` element borrowing from the response frame
+///
+/// For loops checking many names, parsing into this type with [`RawResponse::parse()`] avoids
+/// allocating a `String` for every name and reason. Use [`into_owned()`](Self::into_owned)
+/// to keep the results beyond the lifetime of the frame.
+///
+/// [`RawResponse::parse()`]: crate::response::RawResponse::parse
+#[derive(Debug, FromXml)]
+#[xml(rename = "chkData", ns(XMLNS))]
+pub struct CheckDataRef<'a> {
+    #[xml(borrow)]
+    pub list: Vec<CheckedDomainRef<'a>>,
+}
+
+impl CheckDataRef<'_> {
+    /// The names of the domains that are available, as returned by the server
+    pub fn available_names(&self) -> impl Iterator<Item = &str> {
+        self.list
+            .iter()
+            .filter(|checked| checked.inner.available)
+            .map(|checked| checked.inner.id.as_str())
+    }
+
+    pub fn into_owned(self) -> CheckData {
+        CheckData {
+            list: self
+                .list
+                .into_iter()
+                .map(CheckedDomainRef::into_owned)
+                .collect(),
+        }
+    }
+}
+
+/// A `<cd>` element borrowing from the response frame
+#[derive(Debug, FromXml)]
+#[xml(rename = "cd", ns(XMLNS))]
+pub struct CheckedDomainRef<'a> {
+    #[xml(borrow)]
+    pub inner: CheckedRef<'a>,
+    #[xml(borrow)]
+    pub reason: Option<ReasonRef<'a>>,
+}
+
+impl CheckedDomainRef<'_> {
+    pub fn into_owned(self) -> CheckedDomain {
+        CheckedDomain {
+            inner: Checked {
+                available: self.inner.available,
+                reason: self.inner.reason.map(Text::into_owned),
+                id: self.inner.id.into_owned(),
+            },
+            reason: self.reason.map(|reason| Reason {
+                lang: reason.lang.map(Text::into_owned),
+                text: reason.text.into_owned(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, FromXml)]
+#[xml(rename = "name", ns(XMLNS))]
+pub struct CheckedRef<'a> {
+    #[xml(attribute, rename = "avail")]
+    pub available: bool,
+    #[xml(attribute, borrow)]
+    pub reason: Option<Text<'a>>,
+    #[xml(direct, borrow)]
+    pub id: Text<'a>,
+}
+
+#[derive(Debug, FromXml)]
+#[xml(rename = "reason", ns(XMLNS))]
+pub struct ReasonRef<'a> {
+    #[xml(attribute, borrow)]
+    pub lang: Option<Text<'a>>,
+    #[xml(direct, borrow)]
+    pub text: Text<'a>,
+}
+
+/// One line per checked object, like `example.com: unavailable (In use)`
+impl fmt::Display for CheckData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, checked) in self.list.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{checked}")?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::DomainCheck;
-    use crate::response::ResultCode;
-    use crate::tests::{assert_serialized, response_from_file, CLTRID, SUCCESS_MSG, SVTRID};
+    use super::{CheckDataRef, DomainCheck};
+    use crate::common::{Availability, CheckReason, NoExtension};
+    use crate::response::{Response, ResponseStatus, ResultCode};
+    use crate::tests::{
+        assert_serialized, get_xml, response_from_file, CLTRID, SUCCESS_MSG, SVTRID,
+    };
+    use crate::{xml, Error};
 
     #[test]
     fn command() {
@@ -95,4 +267,77 @@ mod tests {
         assert_eq!(object.tr_ids.client_tr_id.unwrap(), CLTRID);
         assert_eq!(object.tr_ids.server_tr_id, SVTRID);
     }
+
+    #[test]
+    fn response_reason() {
+        let object = response_from_file::<DomainCheck>("response/domain/check_reason.xml");
+        let result = object.res_data().unwrap();
+
+        assert_eq!(result.list[0].availability(), Availability::Available);
+        assert_eq!(
+            result.list[1].availability(),
+            Availability::Unavailable {
+                reason: Some(CheckReason {
+                    lang: Some("de"),
+                    text: "Bereits registriert",
+                })
+            }
+        );
+        assert_eq!(
+            result.list[2].to_string(),
+            "-eppdev.org: unavailable (Invalid domain name)"
+        );
+    }
+
+    #[test]
+    fn response_invalid() {
+        let xml = get_xml("response/domain/check_error.xml").unwrap();
+        let err = Error::Command(Box::new(xml::deserialize::<ResponseStatus>(&xml).unwrap()));
+        assert_eq!(
+            Availability::from_error(&err),
+            Some(Availability::Invalid {
+                reason: CheckReason {
+                    lang: None,
+                    text: "Invalid domain name",
+                }
+            })
+        );
+
+        let xml = get_xml("response/error.xml").unwrap();
+        let err = Error::Command(Box::new(xml::deserialize::<ResponseStatus>(&xml).unwrap()));
+        assert_eq!(Availability::from_error(&err), None);
+    }
+
+    #[test]
+    fn borrowed() {
+        let xml = get_xml("response/domain/check_reason.xml").unwrap();
+        let object =
+            xml::deserialize_borrowed::<Response<CheckDataRef<'_>, NoExtension>>(&xml).unwrap();
+        let result = object.res_data().unwrap();
+
+        assert_eq!(result.list[0].inner.id, "eppdev.com");
+        assert!(result.list[0].inner.id.is_borrowed());
+        assert_eq!(result.available_names().collect::<Vec<_>>(), ["eppdev.com"]);
+
+        let owned = object.res_data.unwrap().into_inner().into_owned();
+        assert_eq!(
+            owned.list[1].availability(),
+            Availability::Unavailable {
+                reason: Some(CheckReason {
+                    lang: Some("de"),
+                    text: "Bereits registriert",
+                })
+            }
+        );
+    }
+
+    #[test]
+    fn accessors() {
+        let object = response_from_file::<DomainCheck>("response/domain/check_reason.xml");
+        let result = object.res_data().unwrap();
+
+        assert!(result.get("EPPDEV.NET.").is_some());
+        assert!(result.get("eppdev.info").is_none());
+        assert_eq!(result.available_names().collect::<Vec<_>>(), ["eppdev.com"]);
+    }
 }
@@ -3,8 +3,12 @@
 use chrono::{DateTime, Utc};
 use instant_xml::{FromXml, ToXml};
 
-use super::{DomainAuthInfo, DomainContact, HostInfo, NameServers, Period, XMLNS};
+use super::{
+    DomainAuthInfo, DomainContact, HostInfo, NameServers, NameserverList, Period, TldPolicy,
+    TldPolicyError, XMLNS,
+};
 use crate::common::{NoExtension, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for DomainCreate<'_> {}
@@ -12,6 +16,10 @@ impl Transaction<NoExtension> for DomainCreate<'_> {}
 impl Command for DomainCreate<'_> {
     type Response = CreateData;
     const COMMAND: &'static str = "create";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::DomainCreate(self.into())
+    }
 }
 
 // Request
@@ -65,6 +73,21 @@ impl<'a> DomainCreate<'a> {
             },
         }
     }
+
+    /// Sets the name servers, in either model
+    pub fn nameservers(&mut self, ns: &'a NameserverList) {
+        self.domain.ns = Some(ns.into());
+    }
+
+    /// Check the command against the policy of its TLD before using it
+    ///
+    /// Registries tend to report contact mistakes with a generic parameter error, so this
+    /// is meant to be chained to [`DomainCreate::new()`]; see [`TldPolicy::check_create()`]
+    /// for what is checked.
+    pub fn validated(self, policy: &TldPolicy) -> Result<Self, TldPolicyError> {
+        policy.check_create(&self)?;
+        Ok(self)
+    }
 }
 
 // Response
@@ -0,0 +1,163 @@
+//! DNS consistency checks before delegation changes
+//!
+//! A registry accepts any syntactically valid name server, so a typo or a DNS provider that
+//! hasn't been set up yet results in a lame delegation: the domain stops resolving as soon as
+//! the update is processed. [`check_update()`] and [`check_create()`] ask a
+//! [`DelegationResolver`] whether each name server being added answers authoritatively for
+//! the domain, and return warnings for those that don't, before the command is sent.
+//!
+//! This crate doesn't ship a resolver; implement [`DelegationResolver`] with the DNS library
+//! the application already uses, typically by sending an `SOA` query for the domain to each
+//! name server and checking the `AA` flag of the answer.
+
+use std::fmt;
+use std::net::IpAddr;
+
+use async_trait::async_trait;
+
+use super::{DomainCreate, DomainUpdate, HostInfo, NameServers};
+use crate::Error;
+
+/// Asks name servers whether they serve a zone
+#[async_trait]
+pub trait DelegationResolver: Send + Sync {
+    /// Whether `nameserver` answers authoritatively for `domain`
+    ///
+    /// `glue` holds the addresses given in the command for host attributes (and is empty
+    /// otherwise), for name servers within `domain` that can't be resolved yet.
+    async fn is_authoritative(
+        &self,
+        nameserver: &str,
+        glue: &[IpAddr],
+        domain: &str,
+    ) -> Result<bool, Error>;
+}
+
+/// A name server that doesn't look ready to serve a domain
+#[derive(Debug)]
+pub struct DelegationWarning {
+    pub nameserver: String,
+    pub kind: DelegationWarningKind,
+}
+
+#[derive(Debug)]
+pub enum DelegationWarningKind {
+    /// The name server answered without authority for the domain
+    NotAuthoritative,
+    /// The name server couldn't be asked
+    Unreachable(Error),
+}
+
+impl fmt::Display for DelegationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            DelegationWarningKind::NotAuthoritative => {
+                write!(f, "{} is not authoritative", self.nameserver)
+            }
+            DelegationWarningKind::Unreachable(err) => {
+                write!(f, "{} could not be queried: {err}", self.nameserver)
+            }
+        }
+    }
+}
+
+/// Check the name servers added by a domain update command
+pub async fn check_update(
+    resolver: &dyn DelegationResolver,
+    update: &DomainUpdate<'_>,
+) -> Vec<DelegationWarning> {
+    match update.domain.add.as_ref().and_then(|add| add.ns.as_ref()) {
+        Some(ns) => check_nameservers(resolver, update.domain.name, ns).await,
+        None => Vec::new(),
+    }
+}
+
+/// Check the name servers of a domain create command
+pub async fn check_create(
+    resolver: &dyn DelegationResolver,
+    create: &DomainCreate<'_>,
+) -> Vec<DelegationWarning> {
+    match &create.domain.ns {
+        Some(ns) => check_nameservers(resolver, create.domain.name, ns).await,
+        None => Vec::new(),
+    }
+}
+
+/// Check that each of the name servers `ns` is authoritative for `domain`
+pub async fn check_nameservers(
+    resolver: &dyn DelegationResolver,
+    domain: &str,
+    ns: &NameServers<'_>,
+) -> Vec<DelegationWarning> {
+    let mut warnings = Vec::new();
+    for host in ns.ns.iter() {
+        let (name, glue) = match host {
+            HostInfo::Obj(obj) => (&*obj.name, &[][..]),
+            HostInfo::Attr(attr) => (&*attr.name, attr.addresses.as_deref().unwrap_or_default()),
+        };
+
+        let kind = match resolver.is_authoritative(name, glue, domain).await {
+            Ok(true) => continue,
+            Ok(false) => DelegationWarningKind::NotAuthoritative,
+            Err(err) => DelegationWarningKind::Unreachable(err),
+        };
+
+        warnings.push(DelegationWarning {
+            nameserver: name.to_owned(),
+            kind,
+        });
+    }
+
+    warnings
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use async_trait::async_trait;
+
+    use super::{check_update, DelegationResolver, DelegationWarningKind};
+    use crate::domain::{DomainUpdate, Nameserver, NameserverList};
+    use crate::Error;
+
+    struct Zones;
+
+    #[async_trait]
+    impl DelegationResolver for Zones {
+        async fn is_authoritative(
+            &self,
+            nameserver: &str,
+            glue: &[IpAddr],
+            _: &str,
+        ) -> Result<bool, Error> {
+            match nameserver {
+                "ns1.eppdev.net" => Ok(true),
+                "ns1.eppdev.com" => Ok(!glue.is_empty()),
+                _ => Err(Error::Other("timed out".into())),
+            }
+        }
+    }
+
+    #[test]
+    fn update() {
+        let ns = NameserverList::HostAttributes(vec![
+            Nameserver::new("ns1.eppdev.net"),
+            Nameserver::new("ns1.eppdev.com"),
+            Nameserver::new("ns2.eppdev.org"),
+        ]);
+        let mut update = DomainUpdate::new("eppdev.com");
+        update.add_nameservers(&ns);
+
+        let warnings = tokio_test::block_on(check_update(&Zones, &update));
+        assert_eq!(warnings.len(), 2);
+        assert_eq!(
+            warnings[0].to_string(),
+            "ns1.eppdev.com is not authoritative"
+        );
+        assert!(matches!(
+            warnings[1].kind,
+            DelegationWarningKind::Unreachable(_)
+        ));
+    }
+}
@@ -4,6 +4,7 @@ use instant_xml::ToXml;
 
 use super::XMLNS;
 use crate::common::{NoExtension, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for DomainDelete<'_> {}
@@ -11,6 +12,10 @@ impl Transaction<NoExtension> for DomainDelete<'_> {}
 impl Command for DomainDelete<'_> {
     type Response = ();
     const COMMAND: &'static str = "delete";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::DomainDelete(self)
+    }
 }
 
 impl<'a> DomainDelete<'a> {
@@ -19,6 +24,11 @@ impl<'a> DomainDelete<'a> {
             domain: DomainDeleteRequestData { name },
         }
     }
+
+    /// The name of the domain to delete
+    pub fn name(&self) -> &'a str {
+        self.domain.name
+    }
 }
 
 /// Type for `<name>` element under the domain `<delete>` tag
@@ -0,0 +1,309 @@
+//! Typed failures for domain commands
+//!
+//! Each error type converts from the generic [`Error`], classifying command failures by their
+//! result code. Failures that don't map to a variant (and errors that aren't command failures
+//! at all) are kept in the `Other` variant, so no information is lost:
+//!
+//! ```no_run
+//! # use instant_epp::domain::{DomainCreate, DomainCreateError};
+//! # #[cfg(feature = "client")]
+//! # async fn create<C: instant_epp::client::Connector>(client: &mut instant_epp::EppClient<C>, create: &DomainCreate<'_>) {
+//! match client.transact(create, "create-1").await.map_err(DomainCreateError::from) {
+//!     Ok(response) => println!("created {}", response.res_data().unwrap().name),
+//!     Err(DomainCreateError::NameTaken) => println!("name is taken"),
+//!     Err(e) => println!("failed: {e}"),
+//! }
+//! # }
+//! ```
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use crate::error::is_fee_result;
+use crate::response::{EppResult, ResultCode};
+use crate::Error;
+
+/// Failures of a domain `<create>` command
+#[derive(Debug)]
+pub enum DomainCreateError {
+    /// The name is already registered (`2302`)
+    NameTaken,
+    /// Authorization information, like a claims or allocation token, was rejected (`2202`)
+    InvalidAuthInfo,
+    /// The fee sent with the command does not match the registry's price (`2004` or `2306`
+    /// concerning the fee)
+    FeeMismatch(EppResult),
+    /// The account balance does not cover the registration (`2104`)
+    InsufficientFunds,
+    /// The name or a parameter violates registry policy (`2306` or `2308`), like a reserved
+    /// name or an unsupported period
+    PolicyViolation(EppResult),
+    /// Any other error
+    Other(Error),
+}
+
+impl From<Error> for DomainCreateError {
+    fn from(err: Error) -> Self {
+        let Some(result) = err.result() else {
+            return Self::Other(err);
+        };
+
+        match result.code {
+            ResultCode::ParameterValueRangeError | ResultCode::ParameterValuePolicyError
+                if is_fee_result(result) =>
+            {
+                Self::FeeMismatch(result.clone())
+            }
+            ResultCode::ObjectExists => Self::NameTaken,
+            ResultCode::InvalidAuthorizationInformation => Self::InvalidAuthInfo,
+            ResultCode::BillingFailure => Self::InsufficientFunds,
+            ResultCode::ParameterValuePolicyError | ResultCode::DataManagementPolicyViolation => {
+                Self::PolicyViolation(result.clone())
+            }
+            _ => Self::Other(err),
+        }
+    }
+}
+
+impl fmt::Display for DomainCreateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NameTaken => write!(f, "domain name is already registered"),
+            Self::InvalidAuthInfo => write!(f, "invalid authorization information"),
+            Self::FeeMismatch(result) => write!(f, "fee mismatch: {result}"),
+            Self::InsufficientFunds => write!(f, "insufficient funds"),
+            Self::PolicyViolation(result) => write!(f, "policy violation: {result}"),
+            Self::Other(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl StdError for DomainCreateError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Other(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Failures of a domain `<renew>` command
+#[derive(Debug)]
+pub enum DomainRenewError {
+    /// The domain does not exist, or is not sponsored by this client (`2303`)
+    NotFound,
+    /// The domain can't be renewed, for example because it would exceed the maximum
+    /// registration period (`2105`)
+    NotEligible,
+    /// A status like `clientRenewProhibited` prevents the renewal (`2304`)
+    StatusProhibited,
+    /// The fee sent with the command does not match the registry's price (`2004` or `2306`
+    /// concerning the fee)
+    FeeMismatch(EppResult),
+    /// The account balance does not cover the renewal (`2104`)
+    InsufficientFunds,
+    /// A parameter violates registry policy (`2306` or `2308`), like a `curExpDate` that does
+    /// not match the expiry date
+    PolicyViolation(EppResult),
+    /// Any other error
+    Other(Error),
+}
+
+impl From<Error> for DomainRenewError {
+    fn from(err: Error) -> Self {
+        let Some(result) = err.result() else {
+            return Self::Other(err);
+        };
+
+        match result.code {
+            ResultCode::ParameterValueRangeError | ResultCode::ParameterValuePolicyError
+                if is_fee_result(result) =>
+            {
+                Self::FeeMismatch(result.clone())
+            }
+            ResultCode::ObjectDoesNotExist => Self::NotFound,
+            ResultCode::ObjectIsNotEligibleForRenewal => Self::NotEligible,
+            ResultCode::ObjectStatusProhibitsOperation => Self::StatusProhibited,
+            ResultCode::BillingFailure => Self::InsufficientFunds,
+            ResultCode::ParameterValuePolicyError | ResultCode::DataManagementPolicyViolation => {
+                Self::PolicyViolation(result.clone())
+            }
+            _ => Self::Other(err),
+        }
+    }
+}
+
+impl fmt::Display for DomainRenewError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "domain does not exist"),
+            Self::NotEligible => write!(f, "domain is not eligible for renewal"),
+            Self::StatusProhibited => write!(f, "domain status prohibits renewal"),
+            Self::FeeMismatch(result) => write!(f, "fee mismatch: {result}"),
+            Self::InsufficientFunds => write!(f, "insufficient funds"),
+            Self::PolicyViolation(result) => write!(f, "policy violation: {result}"),
+            Self::Other(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl StdError for DomainRenewError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Other(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Failures of a domain `<transfer>` command
+#[derive(Debug)]
+pub enum DomainTransferError {
+    /// The domain does not exist (`2303`)
+    NotFound,
+    /// The authorization information does not match the domain's (`2202`)
+    InvalidAuthInfo,
+    /// The domain can't be transferred, for example within 60 days of registration (`2106`)
+    NotEligible,
+    /// A transfer is already pending (`2300`)
+    AlreadyPending,
+    /// No transfer is pending, so it can't be approved, rejected or cancelled (`2301`)
+    NotPending,
+    /// A status like `clientTransferProhibited` prevents the transfer (`2304`)
+    StatusProhibited,
+    /// The fee sent with the command does not match the registry's price (`2004` or `2306`
+    /// concerning the fee)
+    FeeMismatch(EppResult),
+    /// The account balance does not cover the transfer (`2104`)
+    InsufficientFunds,
+    /// Any other error
+    Other(Error),
+}
+
+impl From<Error> for DomainTransferError {
+    fn from(err: Error) -> Self {
+        let Some(result) = err.result() else {
+            return Self::Other(err);
+        };
+
+        match result.code {
+            ResultCode::ParameterValueRangeError | ResultCode::ParameterValuePolicyError
+                if is_fee_result(result) =>
+            {
+                Self::FeeMismatch(result.clone())
+            }
+            ResultCode::ObjectDoesNotExist => Self::NotFound,
+            ResultCode::InvalidAuthorizationInformation => Self::InvalidAuthInfo,
+            ResultCode::ObjectIsNotEligibleForTransfer => Self::NotEligible,
+            ResultCode::ObjectPendingTransfer => Self::AlreadyPending,
+            ResultCode::ObjectNotPendingTransfer => Self::NotPending,
+            ResultCode::ObjectStatusProhibitsOperation => Self::StatusProhibited,
+            ResultCode::BillingFailure => Self::InsufficientFunds,
+            _ => Self::Other(err),
+        }
+    }
+}
+
+impl fmt::Display for DomainTransferError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "domain does not exist"),
+            Self::InvalidAuthInfo => write!(f, "invalid authorization information"),
+            Self::NotEligible => write!(f, "domain is not eligible for transfer"),
+            Self::AlreadyPending => write!(f, "a transfer is already pending"),
+            Self::NotPending => write!(f, "no transfer is pending"),
+            Self::StatusProhibited => write!(f, "domain status prohibits transfer"),
+            Self::FeeMismatch(result) => write!(f, "fee mismatch: {result}"),
+            Self::InsufficientFunds => write!(f, "insufficient funds"),
+            Self::Other(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl StdError for DomainTransferError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Other(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+/// Failures of a domain `<delete>` command
+#[derive(Debug)]
+pub enum DomainDeleteError {
+    /// The domain does not exist, or is not sponsored by this client (`2303`)
+    NotFound,
+    /// A status like `clientDeleteProhibited` prevents the deletion (`2304`)
+    StatusProhibited,
+    /// Other objects, like subordinate hosts used by other domains, prevent the deletion
+    /// (`2305`)
+    Linked,
+    /// Any other error
+    Other(Error),
+}
+
+impl From<Error> for DomainDeleteError {
+    fn from(err: Error) -> Self {
+        match err.result().map(|result| result.code) {
+            Some(ResultCode::ObjectDoesNotExist) => Self::NotFound,
+            Some(ResultCode::ObjectStatusProhibitsOperation) => Self::StatusProhibited,
+            Some(ResultCode::ObjectAssociationProhibitsOperation) => Self::Linked,
+            _ => Self::Other(err),
+        }
+    }
+}
+
+impl fmt::Display for DomainDeleteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound => write!(f, "domain does not exist"),
+            Self::StatusProhibited => write!(f, "domain status prohibits deletion"),
+            Self::Linked => write!(f, "domain has linked objects"),
+            Self::Other(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl StdError for DomainDeleteError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Self::Other(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DomainCreateError, DomainDeleteError, DomainRenewError};
+    use crate::response::ResponseStatus;
+    use crate::tests::get_xml;
+    use crate::{xml, Error};
+
+    fn error(path: &str) -> Error {
+        let xml = get_xml(path).unwrap();
+        Error::Command(Box::new(xml::deserialize::<ResponseStatus>(&xml).unwrap()))
+    }
+
+    #[test]
+    fn classify() {
+        let err = DomainDeleteError::from(error("response/error.xml"));
+        assert!(matches!(err, DomainDeleteError::NotFound));
+
+        let err = DomainCreateError::from(error("response/error.xml"));
+        assert!(matches!(err, DomainCreateError::Other(Error::Command(_))));
+
+        let err = DomainCreateError::from(error("response/domain/create_fee_error.xml"));
+        let DomainCreateError::FeeMismatch(result) = &err else {
+            panic!("unexpected error: {err:?}");
+        };
+        assert_eq!(
+            result.ext_value.as_ref().unwrap().reason,
+            "Fee amount does not match the premium price"
+        );
+
+        let err = DomainRenewError::from(Error::Timeout);
+        assert!(matches!(err, DomainRenewError::Other(Error::Timeout)));
+    }
+}
@@ -1,10 +1,17 @@
 //! Types for EPP domain info request
 
+use std::fmt;
+use std::net::IpAddr;
+
 use chrono::{DateTime, Utc};
 use instant_xml::{FromXml, ToXml};
 
-use super::{DomainAuthInfo, DomainContact, HostAttr, NameServers, Status, XMLNS};
-use crate::common::{NoExtension, EPP_XMLNS};
+use super::{
+    DomainAuthInfo, DomainContact, HostAttr, HostInfo, NameServers, NameserverList,
+    RequestAuthInfo, Status, XMLNS,
+};
+use crate::common::{NoExtension, Roid, Text, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for DomainInfo<'_> {}
@@ -12,6 +19,10 @@ impl Transaction<NoExtension> for DomainInfo<'_> {}
 impl Command for DomainInfo<'_> {
     type Response = InfoData;
     const COMMAND: &'static str = "info";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::DomainInfo(self)
+    }
 }
 
 impl<'a> DomainInfo<'a> {
@@ -19,12 +30,25 @@ impl<'a> DomainInfo<'a> {
         Self {
             info: DomainInfoRequestData {
                 name: Domain { hosts: "all", name },
-                auth_info: auth_password.map(|password| DomainAuthInfo {
-                    password: password.into(),
-                }),
+                auth_info: auth_password.map(|password| RequestAuthInfo::new(password, None)),
             },
         }
     }
+
+    /// Query a domain with the password of one of its contacts, identified by its ROID
+    pub fn with_contact_auth(name: &'a str, auth_password: &'a str, roid: &'a Roid) -> Self {
+        Self {
+            info: DomainInfoRequestData {
+                name: Domain { hosts: "all", name },
+                auth_info: Some(RequestAuthInfo::new(auth_password, Some(roid))),
+            },
+        }
+    }
+
+    /// The name of the domain to query
+    pub fn name(&self) -> &'a str {
+        self.info.name.name
+    }
 }
 
 // Request
@@ -48,7 +72,7 @@ pub struct DomainInfoRequestData<'a> {
     /// The data for the domain to be queried
     name: Domain<'a>,
     /// The auth info for the domain
-    auth_info: Option<DomainAuthInfo<'a>>,
+    auth_info: Option<RequestAuthInfo<'a>>,
 }
 
 #[derive(Debug, ToXml)]
@@ -79,7 +103,7 @@ pub struct InfoData {
     /// The domain name
     pub name: String,
     /// The domain ROID
-    pub roid: String,
+    pub roid: Roid,
     /// The list of domain statuses
     #[xml(rename = "status")]
     pub statuses: Option<Vec<Status>>,
@@ -131,13 +155,143 @@ pub struct InfoData {
     pub auth_info: Option<DomainAuthInfo<'static>>,
 }
 
+impl InfoData {
+    /// Whether the domain has the given status
+    pub fn has_status(&self, status: Status) -> bool {
+        self.statuses
+            .as_ref()
+            .is_some_and(|statuses| statuses.contains(&status))
+    }
+
+    /// The ID of the first contact of the given type, like `admin` or `tech`
+    pub fn contact(&self, contact_type: &str) -> Option<&str> {
+        self.contacts
+            .as_ref()?
+            .iter()
+            .find(|contact| contact.contact_type == contact_type)
+            .map(|contact| contact.id.as_ref())
+    }
+
+    /// The names of the name servers, whether listed as `<hostObj>` or `<hostAttr>`
+    pub fn name_servers(&self) -> impl Iterator<Item = &str> {
+        self.ns
+            .iter()
+            .flat_map(|ns| ns.ns.iter())
+            .map(|host| match host {
+                HostInfo::Obj(obj) => obj.name.as_ref(),
+                HostInfo::Attr(attr) => attr.name.as_ref(),
+            })
+    }
+
+    /// The name servers in the model the registry listed them in
+    pub fn nameservers(&self) -> Option<NameserverList> {
+        self.ns.as_ref().map(NameserverList::from)
+    }
+
+    /// The glue addresses of name servers listed as `<hostAttr>`, for those that have any
+    pub fn glue(&self) -> impl Iterator<Item = (&str, &[IpAddr])> {
+        self.ns
+            .iter()
+            .flat_map(|ns| ns.ns.iter())
+            .filter_map(|host| match host {
+                HostInfo::Attr(attr) => match attr.addresses.as_deref() {
+                    Some(addrs) if !addrs.is_empty() => Some((attr.name.as_ref(), addrs)),
+                    _ => None,
+                },
+                HostInfo::Obj(_) => None,
+            })
+    }
+}
+
+/// A one-line summary with the name, statuses, registrant and expiry date
+impl fmt::Display for InfoData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.name, self.roid)?;
+        if let Some(statuses) = &self.statuses {
+            let statuses = statuses.iter().map(|s| s.as_str()).collect::<Vec<_>>();
+            write!(f, " [{}]", statuses.join(", "))?;
+        }
+        if let Some(registrant) = &self.registrant {
+            write!(f, " registrant {registrant}")?;
+        }
+        if let Some(expiring_at) = &self.expiring_at {
+            write!(f, " expires {}", expiring_at.format("%Y-%m-%d"))?;
+        }
+        Ok(())
+    }
+}
+
+/// An `<infData>` element borrowing from the response frame
+///
+/// Parse a frame from [`EppClient::transact_frame()`] into this type with
+/// [`RawResponse::parse()`] to look up many domains without allocating a `String` for every
+/// value. The contacts, name servers and authorization info are left out; parse the frame into
+/// [`InfoData`] when those are needed.
+///
+/// [`EppClient::transact_frame()`]: crate::EppClient::transact_frame
+/// [`RawResponse::parse()`]: crate::response::RawResponse::parse
+#[derive(Debug, FromXml)]
+#[xml(rename = "infData", ns(XMLNS))]
+pub struct InfoDataRef<'a> {
+    #[xml(borrow)]
+    pub name: Text<'a>,
+    #[xml(borrow)]
+    pub roid: Text<'a>,
+    #[xml(rename = "status")]
+    pub statuses: Option<Vec<Status>>,
+    #[xml(borrow)]
+    pub registrant: Option<Text<'a>>,
+    #[xml(rename = "host", borrow)]
+    pub hosts: Option<Vec<Text<'a>>>,
+    #[xml(rename = "clID", borrow)]
+    pub client_id: Text<'a>,
+    #[xml(rename = "crID", borrow)]
+    pub creator_id: Option<Text<'a>>,
+    #[xml(
+        rename = "crDate",
+        deserialize_with = "crate::datetime::deserialize_datetime_utc_option"
+    )]
+    pub created_at: Option<DateTime<Utc>>,
+    #[xml(
+        rename = "exDate",
+        deserialize_with = "crate::datetime::deserialize_datetime_utc_option"
+    )]
+    pub expiring_at: Option<DateTime<Utc>>,
+    #[xml(rename = "upID", borrow)]
+    pub updater_id: Option<Text<'a>>,
+    #[xml(
+        rename = "upDate",
+        deserialize_with = "crate::datetime::deserialize_datetime_utc_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+    #[xml(
+        rename = "trDate",
+        deserialize_with = "crate::datetime::deserialize_datetime_utc_option"
+    )]
+    pub transferred_at: Option<DateTime<Utc>>,
+}
+
+impl InfoDataRef<'_> {
+    /// Whether the domain has the given status
+    pub fn has_status(&self, status: Status) -> bool {
+        self.statuses
+            .as_ref()
+            .is_some_and(|statuses| statuses.contains(&status))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::DomainInfo;
+    use super::{DomainInfo, InfoDataRef};
+    use crate::common::{NoExtension, Roid};
     use crate::domain::{HostInfo, HostObj, Status};
-    use crate::response::ResultCode;
-    use crate::tests::{assert_serialized, response_from_file, CLTRID, SUCCESS_MSG, SVTRID};
+    use crate::response::{Response, ResultCode};
+    use crate::tests::{
+        assert_serialized, get_xml, response_from_file, CLTRID, SUCCESS_MSG, SVTRID,
+    };
+    use crate::xml;
     use chrono::{TimeZone, Utc};
+    use std::net::IpAddr;
 
     #[test]
     fn command() {
@@ -145,6 +299,13 @@ mod tests {
         assert_serialized("request/domain/info.xml", &object);
     }
 
+    #[test]
+    fn contact_auth() {
+        let roid = Roid("SH8013-REP".into());
+        let object = DomainInfo::with_contact_auth("eppdev.com", "2fooBAR", &roid);
+        assert_serialized("request/domain/info_roid.xml", &object);
+    }
+
     #[test]
     fn response() {
         let object = response_from_file::<DomainInfo>("response/domain/info.xml");
@@ -162,6 +323,7 @@ mod tests {
         assert_eq!(object.result.message, SUCCESS_MSG);
         assert_eq!(result.name, "eppdev-1.com");
         assert_eq!(result.roid, "125899511_DOMAIN_COM-VRSN");
+        assert_eq!(result.roid.repository(), Some("VRSN"));
         assert_eq!(statuses[0], Status::Ok);
         assert_eq!(statuses[1], Status::ClientTransferProhibited);
         assert_eq!(*registrant, "eppdev-contact-2");
@@ -205,8 +367,60 @@ mod tests {
         assert_eq!(object.tr_ids.server_tr_id, SVTRID);
     }
 
+    #[test]
+    fn response_host_attr() {
+        let object = response_from_file::<DomainInfo>("response/domain/info_host_attr.xml");
+        let result = object.res_data().unwrap();
+
+        assert!(result.has_status(Status::Ok));
+        assert_eq!(result.contact("admin"), Some("eppdev-contact-3"));
+        assert_eq!(result.contact("billing"), None);
+        assert_eq!(
+            result.name_servers().collect::<Vec<_>>(),
+            ["ns1.eppdev-2.com", "ns2.example.net"]
+        );
+
+        let glue = result.glue().collect::<Vec<_>>();
+        assert_eq!(glue.len(), 1);
+        assert_eq!(glue[0].0, "ns1.eppdev-2.com");
+        assert_eq!(
+            glue[0].1,
+            [
+                IpAddr::from([192, 0, 2, 2]),
+                IpAddr::from([0x1080, 0, 0, 0, 8, 0x800, 0x200c, 0x417a])
+            ]
+        );
+        assert_eq!(
+            result.transferred_at,
+            Utc.with_ymd_and_hms(2022, 1, 5, 10, 0, 0).single()
+        );
+    }
+
     #[test]
     fn response_alt() {
         response_from_file::<DomainInfo>("response/domain/info_alt.xml");
     }
+
+    #[test]
+    fn borrowed() {
+        let xml = get_xml("response/domain/info.xml").unwrap();
+        let object =
+            xml::deserialize_borrowed::<Response<InfoDataRef<'_>, NoExtension>>(&xml).unwrap();
+        let result = object.res_data().unwrap();
+
+        assert_eq!(result.name, "eppdev-1.com");
+        assert!(result.name.is_borrowed());
+        assert_eq!(result.roid, "125899511_DOMAIN_COM-VRSN");
+        assert!(result.has_status(Status::ClientTransferProhibited));
+        assert_eq!(result.registrant.as_ref().unwrap(), "eppdev-contact-2");
+        assert_eq!(
+            result.hosts.as_ref().unwrap()[1].as_str(),
+            "ns2.eppdev-1.com"
+        );
+        assert_eq!(result.client_id, "eppdev");
+        assert_eq!(
+            result.expiring_at,
+            Utc.with_ymd_and_hms(2023, 7, 23, 15, 31, 20).single()
+        );
+    }
 }
@@ -9,7 +9,9 @@ use std::str::FromStr;
 
 use instant_xml::OptionAccumulator;
 use instant_xml::{Accumulate, Deserializer, FromXml, Serializer, ToXml};
+use serde::{Deserialize, Serialize};
 
+use crate::common::Roid;
 use crate::Error;
 
 pub mod check;
@@ -18,17 +20,38 @@ pub use check::DomainCheck;
 pub mod create;
 pub use create::DomainCreate;
 
+pub mod delegation;
+pub use delegation::{DelegationResolver, DelegationWarning};
+
 pub mod delete;
 pub use delete::DomainDelete;
 
+pub mod errors;
+pub use errors::{DomainCreateError, DomainDeleteError, DomainRenewError, DomainTransferError};
+
 pub mod info;
-pub use info::{DomainInfo, InfoData};
+pub use info::{DomainInfo, InfoData, InfoDataRef};
+
+pub mod name;
+pub use name::{normalize_name, validate_name, InvalidName};
+
+pub mod nameservers;
+pub use nameservers::{
+    Nameserver, NameserverError, NameserverList, NameserverModel, NameserverPolicies,
+    NameserverPolicy,
+};
+
+pub mod policy;
+pub use policy::{PeriodError, PeriodPolicies, PeriodPolicy};
 
 pub mod renew;
-pub use renew::DomainRenew;
+pub use renew::{DomainRenew, Expiry};
+
+pub mod tld;
+pub use tld::{LaunchPhase, TldPolicies, TldPolicy, TldPolicyError};
 
 pub mod transfer;
-pub use transfer::DomainTransfer;
+pub use transfer::{DomainTransfer, TransferStatus, TransferUrgency};
 
 pub mod update;
 pub use update::DomainUpdate;
@@ -65,14 +88,25 @@ fn deserialize_host_addrs_option<'xml>(
 
     let into = into.get_mut();
     for addr in new {
-        match IpAddr::from_str(&addr.address) {
-            Ok(ip) => into.push(ip),
+        let ip = match IpAddr::from_str(addr.address.trim()) {
+            Ok(ip) => ip,
             Err(_) => {
                 return Err(instant_xml::Error::UnexpectedValue(format!(
                     "invalid IP address '{}'",
                     &addr.address
                 )))
             }
+        };
+
+        // The `ip` attribute defaults to `v4`, but some registries omit it for IPv6 as well
+        match (addr.ip_version.as_deref(), ip) {
+            (Some("v4"), IpAddr::V6(_)) | (Some("v6"), IpAddr::V4(_)) => {
+                return Err(instant_xml::Error::UnexpectedValue(format!(
+                    "address '{ip}' does not match ip=\"{}\"",
+                    addr.ip_version.as_deref().unwrap_or_default()
+                )))
+            }
+            _ => into.push(ip),
         }
     }
 
@@ -138,11 +172,13 @@ pub struct NameServers<'a> {
 }
 
 /// The `<contact>` type on domain creation and update requests
-#[derive(Debug, FromXml, ToXml)]
+#[derive(Clone, Debug, Deserialize, Eq, FromXml, PartialEq, Serialize, ToXml)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[xml(rename = "contact", ns(XMLNS))]
 pub struct DomainContact<'a> {
     /// The contact type attr (usually admin, billing, or tech in most registries)
     #[xml(attribute, rename = "type")]
+    #[serde(rename = "type")]
     pub contact_type: Cow<'a, str>,
     /// The contact id
     #[xml(direct)]
@@ -219,6 +255,36 @@ impl<'a> DomainAuthInfo<'a> {
     }
 }
 
+/// The `<authInfo>` of info and transfer requests
+///
+/// RFC 5731 allows these to authorize with the password of the registrant or another contact
+/// of the domain, identified by the ROID in the `roid` attribute.
+#[derive(Debug, ToXml)]
+#[xml(rename = "authInfo", ns(XMLNS))]
+pub(crate) struct RequestAuthInfo<'a> {
+    pw: RequestPassword<'a>,
+}
+
+impl<'a> RequestAuthInfo<'a> {
+    pub(crate) fn new(password: &'a str, roid: Option<&'a Roid>) -> Self {
+        Self {
+            pw: RequestPassword {
+                roid: roid.map(Roid::as_str),
+                password,
+            },
+        }
+    }
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "pw", ns(XMLNS))]
+struct RequestPassword<'a> {
+    #[xml(attribute)]
+    roid: Option<&'a str>,
+    #[xml(direct)]
+    password: &'a str,
+}
+
 /// The `<status>` type on contact transactions
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum Status {
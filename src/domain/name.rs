@@ -0,0 +1,223 @@
+//! Domain name normalization and validation
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use crate::punycode;
+
+/// The maximum length of a domain name, without the trailing dot
+const MAX_NAME_LEN: usize = 253;
+
+/// The maximum length of a single label
+const MAX_LABEL_LEN: usize = 63;
+
+/// Normalize a domain name for comparison
+///
+/// Labels are lowercased and converted to their A-label (`xn--`) form, and a trailing dot is
+/// removed. This does not apply the full IDNA mapping, so names that differ in more than case
+/// (like full-width characters) are not considered equal.
+pub fn normalize_name(name: &str) -> String {
+    let name = name.trim().trim_end_matches('.');
+    let labels = name.split('.').map(|label| {
+        let label = label.to_lowercase();
+        if label.is_ascii() {
+            return label;
+        }
+
+        match punycode::encode(&label) {
+            Some(encoded) => format!("xn--{encoded}"),
+            None => label,
+        }
+    });
+
+    labels.collect::<Vec<_>>().join(".")
+}
+
+/// Check that `name` is a syntactically valid domain name in A-label form
+///
+/// Registries reject names that break these rules anyway, but only after a round trip that
+/// counts against rate limits. The rules checked are:
+///
+/// - the name is at most 253 characters long (ignoring a trailing dot)
+/// - every label is 1 to 63 characters long
+/// - labels consist of ASCII letters, digits and hyphens, and don't start or end with a hyphen
+/// - labels with hyphens in the third and fourth position are well-formed `xn--` labels
+///
+/// Internationalized names have to be converted with [`normalize_name()`] first.
+pub fn validate_name(name: &str) -> Result<(), InvalidName> {
+    let trimmed = name.strip_suffix('.').unwrap_or(name);
+    let mut problems = Vec::new();
+    if trimmed.len() > MAX_NAME_LEN {
+        problems.push(NameProblem::TooLong(trimmed.len()));
+    }
+
+    for label in trimmed.split('.') {
+        if let Some(problem) = check_label(label) {
+            problems.push(problem);
+        }
+    }
+
+    if problems.is_empty() {
+        return Ok(());
+    }
+
+    Err(InvalidName {
+        name: name.to_owned(),
+        problems,
+    })
+}
+
+fn check_label(label: &str) -> Option<NameProblem> {
+    let problem = match label {
+        "" => NameProblem::EmptyLabel,
+        _ if label.len() > MAX_LABEL_LEN => NameProblem::LabelTooLong(label.to_owned()),
+        _ if !label
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'-') =>
+        {
+            NameProblem::InvalidCharacters(label.to_owned())
+        }
+        _ if label.starts_with('-') || label.ends_with('-') => {
+            NameProblem::Hyphen(label.to_owned())
+        }
+        _ if label.get(2..4) == Some("--") => match label.get(..4) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("xn--") => {
+                let encoded = label[4..].to_ascii_lowercase();
+                match punycode::decode(&encoded) {
+                    Some(decoded)
+                        if !decoded.is_ascii()
+                            && punycode::encode(&decoded).as_deref() == Some(&encoded) =>
+                    {
+                        return None
+                    }
+                    _ => NameProblem::InvalidPunycode(label.to_owned()),
+                }
+            }
+            _ => NameProblem::Hyphen(label.to_owned()),
+        },
+        _ => return None,
+    };
+
+    Some(problem)
+}
+
+/// A domain name rejected by [`validate_name()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct InvalidName {
+    /// The name as given
+    pub name: String,
+    /// Everything wrong with the name, in order
+    pub problems: Vec<NameProblem>,
+}
+
+impl fmt::Display for InvalidName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid domain name {:?}: ", self.name)?;
+        for (i, problem) in self.problems.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{problem}")?;
+        }
+        Ok(())
+    }
+}
+
+impl StdError for InvalidName {}
+
+impl From<InvalidName> for crate::Error {
+    fn from(err: InvalidName) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+/// A single reason for [`InvalidName`], naming the offending label where applicable
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NameProblem {
+    /// The name is longer than 253 characters
+    TooLong(usize),
+    /// The name contains an empty label, as in `example..com`
+    EmptyLabel,
+    /// The label is longer than 63 characters
+    LabelTooLong(String),
+    /// The label contains characters other than ASCII letters, digits and hyphens
+    InvalidCharacters(String),
+    /// The label starts or ends with a hyphen, or has hyphens in the third and fourth
+    /// position without being an `xn--` label
+    Hyphen(String),
+    /// The label starts with `xn--` but is not valid Punycode
+    InvalidPunycode(String),
+}
+
+impl fmt::Display for NameProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooLong(len) => write!(f, "name is {len} characters long"),
+            Self::EmptyLabel => write!(f, "empty label"),
+            Self::LabelTooLong(label) => write!(f, "label {label:?} is too long"),
+            Self::InvalidCharacters(label) => write!(f, "label {label:?} has invalid characters"),
+            Self::Hyphen(label) => write!(f, "label {label:?} has misplaced hyphens"),
+            Self::InvalidPunycode(label) => write!(f, "label {label:?} is not valid punycode"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize_name, validate_name, NameProblem};
+
+    #[test]
+    fn normalize() {
+        assert_eq!(normalize_name("Bücher.Example."), "xn--bcher-kva.example");
+        assert_eq!(
+            normalize_name("xn--bcher-kva.example"),
+            "xn--bcher-kva.example"
+        );
+    }
+
+    #[test]
+    fn valid() {
+        for name in [
+            "example.com",
+            "EXAMPLE.com.",
+            "a-b.c0m",
+            "xn--bcher-kva.example",
+        ] {
+            assert_eq!(validate_name(name), Ok(()), "{name}");
+        }
+    }
+
+    #[test]
+    fn invalid() {
+        let err = validate_name("-foo.ex_ample..ab--cd.xn--bcher-k!a.com").unwrap_err();
+        assert_eq!(
+            err.problems,
+            [
+                NameProblem::Hyphen("-foo".into()),
+                NameProblem::InvalidCharacters("ex_ample".into()),
+                NameProblem::EmptyLabel,
+                NameProblem::Hyphen("ab--cd".into()),
+                NameProblem::InvalidCharacters("xn--bcher-k!a".into()),
+            ]
+        );
+
+        let err = validate_name("xn--abc-.example").unwrap_err();
+        assert_eq!(err.problems, [NameProblem::Hyphen("xn--abc-".into())]);
+
+        let err = validate_name("xn--abc-d.com").unwrap_err();
+        assert_eq!(
+            err.problems,
+            [NameProblem::InvalidPunycode("xn--abc-d".into())]
+        );
+
+        let long = format!("{}.com", "a".repeat(64));
+        assert_eq!(
+            validate_name(&long).unwrap_err().problems,
+            [NameProblem::LabelTooLong("a".repeat(64))]
+        );
+        assert_eq!(
+            validate_name(&["abc"; 64].join(".")).unwrap_err().problems,
+            [NameProblem::TooLong(255)]
+        );
+    }
+}
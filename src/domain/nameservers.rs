@@ -0,0 +1,676 @@
+//! Name server lists and the delegation model each TLD requires
+//!
+//! RFC 5731 allows a domain's name servers to be given either as references to host objects
+//! (`<hostObj>`) or as host attributes carrying their own glue addresses (`<hostAttr>`), but
+//! each registry implements only one of the two. Sending the other one fails with a `2306`
+//! (parameter value policy) error, usually discovered while on-boarding a new TLD.
+//! [`NameserverList`] represents a list in either model, and [`NameserverPolicies`] records which
+//! model each TLD requires so create and update commands can be checked before they are sent.
+
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use serde::{Deserialize, Serialize};
+
+use super::policy::normalize_tld;
+use super::{normalize_name, DomainCreate, DomainUpdate, HostAttr, HostInfo, HostObj, NameServers};
+use crate::host::HostCreate;
+
+/// A list of name servers for a domain
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum NameserverList {
+    /// Host names of host objects, which must exist at the registry
+    HostObjects(Vec<String>),
+    /// Host names with their glue addresses, if any
+    HostAttributes(Vec<Nameserver>),
+}
+
+impl NameserverList {
+    /// The model this list uses
+    pub fn model(&self) -> NameserverModel {
+        match self {
+            Self::HostObjects(_) => NameserverModel::HostObj,
+            Self::HostAttributes(_) => NameserverModel::HostAttr,
+        }
+    }
+
+    /// The host names in the list
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        let (objects, attributes) = match self {
+            Self::HostObjects(names) => (names.as_slice(), [].as_slice()),
+            Self::HostAttributes(hosts) => ([].as_slice(), hosts.as_slice()),
+        };
+
+        objects
+            .iter()
+            .map(String::as_str)
+            .chain(attributes.iter().map(|host| host.name.as_str()))
+    }
+
+    pub fn len(&self) -> usize {
+        match self {
+            Self::HostObjects(names) => names.len(),
+            Self::HostAttributes(hosts) => hosts.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The list with normalized host names and glue, in a stable order
+    ///
+    /// Registries may return name servers in another order, case or address notation than
+    /// they were sent in, so compare normalized lists (or use [`NameserverList::same_as()`]) to
+    /// find out whether a delegation changed.
+    pub fn normalized(&self) -> Self {
+        match self {
+            Self::HostObjects(names) => {
+                let mut names = names
+                    .iter()
+                    .map(|name| normalize_name(name))
+                    .collect::<Vec<_>>();
+                names.sort();
+                names.dedup();
+                Self::HostObjects(names)
+            }
+            Self::HostAttributes(hosts) => {
+                let mut hosts = hosts.iter().map(Nameserver::normalized).collect::<Vec<_>>();
+                hosts.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.glue.cmp(&b.glue)));
+                hosts.dedup();
+                Self::HostAttributes(hosts)
+            }
+        }
+    }
+
+    /// Whether both lists delegate to the same name servers with the same glue
+    pub fn same_as(&self, other: &Self) -> bool {
+        self.normalized() == other.normalized()
+    }
+}
+
+impl<'a> From<&'a NameserverList> for NameServers<'a> {
+    fn from(ns: &'a NameserverList) -> Self {
+        let hosts = match ns {
+            NameserverList::HostObjects(names) => names
+                .iter()
+                .map(|name| {
+                    HostInfo::Obj(HostObj {
+                        name: name.as_str().into(),
+                    })
+                })
+                .collect::<Vec<_>>(),
+            NameserverList::HostAttributes(hosts) => hosts
+                .iter()
+                .map(|host| {
+                    HostInfo::Attr(HostAttr {
+                        name: host.name.as_str().into(),
+                        addresses: Some(host.glue.clone()).filter(|glue| !glue.is_empty()),
+                    })
+                })
+                .collect(),
+        };
+
+        NameServers { ns: hosts.into() }
+    }
+}
+
+/// Converts a list as found in an info response
+///
+/// RFC 5731 does not allow mixing the two models; if a registry does anyway, host objects
+/// are returned as host attributes without glue.
+impl From<&NameServers<'_>> for NameserverList {
+    fn from(ns: &NameServers<'_>) -> Self {
+        let objects = ns
+            .ns
+            .iter()
+            .map(|host| match host {
+                HostInfo::Obj(obj) => Some(obj.name.to_string()),
+                HostInfo::Attr(_) => None,
+            })
+            .collect::<Option<Vec<_>>>();
+
+        if let Some(names) = objects {
+            return Self::HostObjects(names);
+        }
+
+        Self::HostAttributes(
+            ns.ns
+                .iter()
+                .map(|host| match host {
+                    HostInfo::Obj(obj) => Nameserver::new(&obj.name),
+                    HostInfo::Attr(attr) => Nameserver {
+                        name: attr.name.to_string(),
+                        glue: attr.addresses.clone().unwrap_or_default(),
+                    },
+                })
+                .collect(),
+        )
+    }
+}
+
+/// A name server given as a host attribute
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Nameserver {
+    pub name: String,
+    /// Glue addresses, needed if the host is within the domain being delegated
+    #[serde(default)]
+    pub glue: Vec<IpAddr>,
+}
+
+impl Nameserver {
+    /// A name server without glue
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            glue: Vec::new(),
+        }
+    }
+
+    /// A name server with glue addresses
+    pub fn with_glue(name: &str, glue: impl Into<Vec<IpAddr>>) -> Self {
+        Self {
+            name: name.to_owned(),
+            glue: glue.into(),
+        }
+    }
+
+    /// The IPv4 glue addresses
+    pub fn ipv4(&self) -> impl Iterator<Item = Ipv4Addr> + '_ {
+        self.glue.iter().filter_map(|addr| match addr {
+            IpAddr::V4(addr) => Some(*addr),
+            IpAddr::V6(_) => None,
+        })
+    }
+
+    /// The IPv6 glue addresses
+    pub fn ipv6(&self) -> impl Iterator<Item = Ipv6Addr> + '_ {
+        self.glue.iter().filter_map(|addr| match addr {
+            IpAddr::V4(_) => None,
+            IpAddr::V6(addr) => Some(*addr),
+        })
+    }
+
+    /// The name server with a normalized name, and its glue sorted (IPv4 first) without
+    /// duplicates
+    pub fn normalized(&self) -> Self {
+        let mut glue = self.glue.clone();
+        glue.sort();
+        glue.dedup();
+        Self {
+            name: normalize_name(&self.name),
+            glue,
+        }
+    }
+}
+
+/// The way a registry expects name servers to be given
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum NameserverModel {
+    /// `<hostObj>` references, the model used by most gTLD registries
+    #[default]
+    HostObj,
+    /// `<hostAttr>` elements with inline glue
+    HostAttr,
+}
+
+/// The name server requirements of a TLD
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct NameserverPolicy {
+    pub model: NameserverModel,
+    /// Whether name servers within the delegated domain need glue addresses
+    pub require_glue: bool,
+    /// Whether glue addresses are accepted for name servers outside the delegated domain
+    pub foreign_glue: bool,
+}
+
+impl NameserverPolicy {
+    /// A policy requiring glue exactly for in-bailiwick name servers
+    pub fn new(model: NameserverModel) -> Self {
+        Self {
+            model,
+            require_glue: true,
+            foreign_glue: false,
+        }
+    }
+
+    /// Check the name servers of `domain` against this policy
+    ///
+    /// For host attributes, the glue addresses are checked as well. Host objects carry their
+    /// addresses themselves; see [`NameserverPolicies::check_host_create()`].
+    pub fn check(&self, domain: &str, ns: &NameServers<'_>) -> Result<(), NameserverError> {
+        self.check_model(ns)?;
+        for host in ns.ns.iter() {
+            if let HostInfo::Attr(attr) = host {
+                let glue = attr.addresses.as_deref().unwrap_or_default();
+                self.check_glue(&attr.name, in_bailiwick(&attr.name, domain), glue)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check that the name servers `ns` are given in the model of this policy
+    pub fn check_model(&self, ns: &NameServers<'_>) -> Result<(), NameserverError> {
+        for host in ns.ns.iter() {
+            let (host, model) = match host {
+                HostInfo::Obj(obj) => (&obj.name, NameserverModel::HostObj),
+                HostInfo::Attr(attr) => (&attr.name, NameserverModel::HostAttr),
+            };
+
+            if model != self.model {
+                return Err(NameserverError::WrongModel {
+                    host: host.to_string(),
+                    expected: self.model,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_glue(
+        &self,
+        host: &str,
+        internal: bool,
+        glue: &[IpAddr],
+    ) -> Result<(), NameserverError> {
+        if internal && self.require_glue && glue.is_empty() {
+            Err(NameserverError::MissingGlue {
+                host: host.to_owned(),
+            })
+        } else if !internal && !self.foreign_glue && !glue.is_empty() {
+            Err(NameserverError::UnexpectedGlue {
+                host: host.to_owned(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Default for NameserverPolicy {
+    fn default() -> Self {
+        Self::new(NameserverModel::default())
+    }
+}
+
+/// Name server policies for a set of TLDs
+#[derive(Clone, Debug, Default)]
+pub struct NameserverPolicies {
+    /// The policy for TLDs without a specific policy
+    pub default: NameserverPolicy,
+    tlds: HashMap<String, NameserverPolicy>,
+    // The TLDs operated by the registry, whose hosts are internal
+    internal: Vec<String>,
+}
+
+impl NameserverPolicies {
+    /// Use `policy` for names under `tld`
+    ///
+    /// Like with [`PeriodPolicies`](super::PeriodPolicies), a multi-label `tld` takes
+    /// precedence over a policy for its parent.
+    pub fn with_tld(mut self, tld: &str, policy: NameserverPolicy) -> Self {
+        self.tlds.insert(normalize_tld(tld), policy);
+        self
+    }
+
+    /// Treat hosts under `tld` as internal to the registry
+    ///
+    /// Add every TLD the registry operates, whether or not it has a policy of its own; hosts
+    /// under any other TLD are external. This only matters for
+    /// [`check_host_create()`](Self::check_host_create).
+    pub fn internal_tld(mut self, tld: &str) -> Self {
+        self.internal.push(normalize_tld(tld));
+        self
+    }
+
+    /// The policy applying to the domain `name`
+    pub fn for_name(&self, name: &str) -> &NameserverPolicy {
+        let name = normalize_tld(name);
+        let mut suffix = name.as_str();
+        loop {
+            if let Some(policy) = self.tlds.get(suffix) {
+                return policy;
+            }
+
+            match suffix.split_once('.') {
+                Some((_, parent)) => suffix = parent,
+                None => return &self.default,
+            }
+        }
+    }
+
+    /// Check the name servers of a domain create command against the policy for its TLD
+    pub fn check_create(&self, create: &DomainCreate<'_>) -> Result<(), NameserverError> {
+        match &create.domain.ns {
+            Some(ns) => {
+                let name = create.domain.name;
+                self.for_name(name).check(name, ns)
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Check the name servers added and removed by a domain update command
+    ///
+    /// Both need to be given in the model of the TLD, but glue is only checked for the name
+    /// servers added: the registry identifies those removed by name.
+    pub fn check_update(&self, update: &DomainUpdate<'_>) -> Result<(), NameserverError> {
+        let name = update.domain.name;
+        let policy = self.for_name(name);
+        if let Some(ns) = update.domain.add.as_ref().and_then(|add| add.ns.as_ref()) {
+            policy.check(name, ns)?;
+        }
+
+        match update
+            .domain
+            .remove
+            .as_ref()
+            .and_then(|rem| rem.ns.as_ref())
+        {
+            Some(ns) => policy.check_model(ns),
+            None => Ok(()),
+        }
+    }
+
+    /// Check the addresses of a host object about to be created
+    ///
+    /// A host is internal to the registry if its name falls under one of the TLDs given with
+    /// [`internal_tld()`](Self::internal_tld), and external otherwise. Internal hosts need
+    /// addresses, external ones may only have addresses if the policy of their TLD accepts
+    /// foreign glue.
+    pub fn check_host_create(&self, create: &HostCreate<'_>) -> Result<(), NameserverError> {
+        let name = create.name();
+        let internal = self.serves(name);
+        let glue = create.addresses().unwrap_or_default();
+        self.for_name(name).check_glue(name, internal, glue)
+    }
+
+    fn serves(&self, name: &str) -> bool {
+        let name = normalize_tld(name);
+        let mut suffix = name.as_str();
+        while let Some((_, parent)) = suffix.split_once('.') {
+            if self.internal.iter().any(|tld| tld == parent) {
+                return true;
+            }
+            suffix = parent;
+        }
+
+        false
+    }
+}
+
+// Whether `host` is `domain` or a subordinate name of it
+fn in_bailiwick(host: &str, domain: &str) -> bool {
+    let host = normalize_tld(host);
+    let domain = normalize_tld(domain);
+    host == domain
+        || host
+            .strip_suffix(&domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+/// Name servers rejected by a [`NameserverPolicy`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum NameserverError {
+    /// A name server is given in a model the TLD does not support
+    WrongModel {
+        host: String,
+        expected: NameserverModel,
+    },
+    /// An in-bailiwick name server has no glue addresses
+    MissingGlue { host: String },
+    /// A name server outside the delegated domain has glue addresses the TLD does not accept
+    UnexpectedGlue { host: String },
+}
+
+impl fmt::Display for NameserverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::WrongModel { host, expected } => {
+                let expected = match expected {
+                    NameserverModel::HostObj => "hostObj",
+                    NameserverModel::HostAttr => "hostAttr",
+                };
+                write!(f, "name server {host} must be given as {expected}")
+            }
+            Self::MissingGlue { host } => write!(f, "name server {host} needs glue addresses"),
+            Self::UnexpectedGlue { host } => {
+                write!(
+                    f,
+                    "name server {host} is out of bailiwick and may not have glue"
+                )
+            }
+        }
+    }
+}
+
+impl StdError for NameserverError {}
+
+impl From<NameserverError> for crate::Error {
+    fn from(err: NameserverError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+    use super::{
+        Nameserver, NameserverError, NameserverList, NameserverModel, NameserverPolicies,
+        NameserverPolicy,
+    };
+    use crate::domain::{
+        DomainContact, DomainCreate, DomainInfo, DomainUpdate, HostAttr, NameServers, ONE_YEAR,
+    };
+    use crate::host::HostCreate;
+    use crate::tests::{assert_serialized, response_from_file};
+
+    #[test]
+    fn create() {
+        let ns = NameserverList::HostAttributes(vec![
+            Nameserver::new("ns1.eppdev-1.com"),
+            Nameserver::with_glue(
+                "ns2.eppdev-1.com",
+                [
+                    IpAddr::from([177, 232, 12, 58]),
+                    IpAddr::from([0x2404, 0x6800, 0x4001, 0x801, 0, 0, 0, 0x200e]),
+                ],
+            ),
+        ]);
+        assert_eq!(ns.model(), NameserverModel::HostAttr);
+
+        let contacts = &[
+            DomainContact {
+                contact_type: "admin".into(),
+                id: "eppdev-contact-3".into(),
+            },
+            DomainContact {
+                contact_type: "tech".into(),
+                id: "eppdev-contact-3".into(),
+            },
+            DomainContact {
+                contact_type: "billing".into(),
+                id: "eppdev-contact-3".into(),
+            },
+        ];
+        let mut object = DomainCreate::new(
+            "eppdev-2.com",
+            ONE_YEAR,
+            None,
+            Some("eppdev-contact-3"),
+            "epP4uthd#v",
+            Some(contacts),
+        );
+        object.nameservers(&ns);
+        assert_serialized("request/domain/create_with_host_attr.xml", &object);
+
+        let policies = NameserverPolicies::default();
+        assert_eq!(
+            policies.check_create(&object),
+            Err(NameserverError::WrongModel {
+                host: "ns1.eppdev-1.com".into(),
+                expected: NameserverModel::HostObj,
+            })
+        );
+
+        // The name servers are outside eppdev-2.com, so ns2 must not have glue
+        let policy = NameserverPolicy::new(NameserverModel::HostAttr);
+        let policies = policies.with_tld("com", policy);
+        assert_eq!(
+            policies.check_create(&object),
+            Err(NameserverError::UnexpectedGlue {
+                host: "ns2.eppdev-1.com".into(),
+            })
+        );
+
+        let policy = NameserverPolicy {
+            foreign_glue: true,
+            ..policy
+        };
+        let policies = policies.with_tld("com", policy);
+        assert_eq!(policies.check_create(&object), Ok(()));
+
+        let ns = NameserverList::HostAttributes(vec![Nameserver::new("NS1.eppdev-2.com.")]);
+        object.nameservers(&ns);
+        assert_eq!(
+            policies.check_create(&object),
+            Err(NameserverError::MissingGlue {
+                host: "NS1.eppdev-2.com.".into(),
+            })
+        );
+    }
+
+    #[test]
+    fn update() {
+        let policies = NameserverPolicies::default()
+            .with_tld("com", NameserverPolicy::new(NameserverModel::HostAttr));
+
+        // Name servers being removed are identified by name, so they don't need glue
+        let internal = NameserverList::HostAttributes(vec![Nameserver::new("ns1.eppdev.com")]);
+        let mut update = DomainUpdate::new("eppdev.com");
+        update.remove_nameservers(&internal);
+        assert_eq!(policies.check_update(&update), Ok(()));
+
+        update.add_nameservers(&internal);
+        assert_eq!(
+            policies.check_update(&update),
+            Err(NameserverError::MissingGlue {
+                host: "ns1.eppdev.com".into(),
+            })
+        );
+
+        let objects = NameserverList::HostObjects(vec!["ns1.example.net".into()]);
+        let mut update = DomainUpdate::new("eppdev.com");
+        update.remove_nameservers(&objects);
+        assert_eq!(
+            policies.check_update(&update),
+            Err(NameserverError::WrongModel {
+                host: "ns1.example.net".into(),
+                expected: NameserverModel::HostAttr,
+            })
+        );
+    }
+
+    #[test]
+    fn host_create() {
+        // The registry operates .com under the default policy, and .net under its own
+        let policies = NameserverPolicies::default()
+            .internal_tld("com")
+            .internal_tld("net")
+            .with_tld("net", NameserverPolicy::new(NameserverModel::HostObj));
+        let addresses = [IpAddr::from([29, 245, 122, 14])];
+
+        let host = HostCreate::new("ns1.eppdev-1.com", None);
+        assert_eq!(
+            policies.check_host_create(&host),
+            Err(NameserverError::MissingGlue {
+                host: "ns1.eppdev-1.com".into(),
+            })
+        );
+        let host = HostCreate::new("ns1.eppdev-1.com", Some(&addresses));
+        assert_eq!(policies.check_host_create(&host), Ok(()));
+
+        let host = HostCreate::new("ns1.example.net", None);
+        assert_eq!(
+            policies.check_host_create(&host),
+            Err(NameserverError::MissingGlue {
+                host: "ns1.example.net".into(),
+            })
+        );
+
+        // A policy for a TLD the registry doesn't operate doesn't make its hosts internal
+        let policies = policies.with_tld("org", NameserverPolicy::new(NameserverModel::HostObj));
+        let host = HostCreate::new("ns1.example.org", Some(&addresses));
+        assert_eq!(
+            policies.check_host_create(&host),
+            Err(NameserverError::UnexpectedGlue {
+                host: "ns1.example.org".into(),
+            })
+        );
+        let host = HostCreate::new("ns1.example.org", None);
+        assert_eq!(policies.check_host_create(&host), Ok(()));
+    }
+
+    #[test]
+    fn info() {
+        let object = response_from_file::<DomainInfo>("response/domain/info.xml");
+        let ns = object.res_data().unwrap().nameservers().unwrap();
+        assert_eq!(
+            ns,
+            NameserverList::HostObjects(vec!["ns1.eppdev-1.com".into(), "ns2.eppdev-1.com".into()])
+        );
+
+        let xml = NameServers::from(&ns);
+        assert_eq!(NameserverList::from(&xml), ns);
+    }
+
+    #[test]
+    fn mixed_glue() {
+        let object = response_from_file::<DomainInfo>("response/domain/info_host_attr.xml");
+        let ns = object.res_data().unwrap().nameservers().unwrap();
+        let NameserverList::HostAttributes(hosts) = &ns else {
+            panic!("expected host attributes");
+        };
+        assert_eq!(
+            hosts[0].ipv4().collect::<Vec<_>>(),
+            [Ipv4Addr::from([192, 0, 2, 2])]
+        );
+        assert_eq!(
+            hosts[0].ipv6().collect::<Vec<_>>(),
+            [Ipv6Addr::from([0x1080, 0, 0, 0, 8, 0x800, 0x200c, 0x417a])]
+        );
+
+        let xml = instant_xml::to_string(&NameServers::from(&ns)).unwrap();
+        assert!(xml.contains(r#"<hostAddr ip="v6">1080::8:800:200c:417a</hostAddr>"#));
+        let parsed = instant_xml::from_str::<NameServers>(&xml).unwrap();
+        assert_eq!(NameserverList::from(&parsed), ns);
+
+        let reordered = NameserverList::HostAttributes(vec![
+            Nameserver::new("NS2.example.net."),
+            Nameserver::with_glue(
+                "ns1.eppdev-2.com",
+                [
+                    "1080::8:800:200C:417A".parse::<IpAddr>().unwrap(),
+                    IpAddr::from([192, 0, 2, 2]),
+                ],
+            ),
+        ]);
+        assert!(reordered.same_as(&ns));
+        assert!(!reordered.same_as(&NameserverList::HostAttributes(vec![hosts[0].clone()])));
+
+        let mismatched = r#"<hostAttr xmlns="urn:ietf:params:xml:ns:domain-1.0">
+            <hostName>ns1.eppdev-2.com</hostName>
+            <hostAddr ip="v4">1080::8:800:200c:417a</hostAddr>
+        </hostAttr>"#;
+        assert!(instant_xml::from_str::<HostAttr>(mismatched).is_err());
+    }
+}
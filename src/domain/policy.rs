@@ -0,0 +1,240 @@
+//! Registration period policy checks
+//!
+//! Registries reject out-of-range periods and renewals with a stale `curExpDate` with a
+//! `2306` (parameter value policy) error. [`PeriodPolicy`] describes the periods a TLD
+//! accepts, so create and renew commands can be checked before they are sent.
+
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt;
+
+use chrono::NaiveDate;
+
+use super::{DomainCreate, DomainRenew, Period};
+
+/// The registration and renewal periods accepted for a TLD
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct PeriodPolicy {
+    /// The shortest accepted period, in months
+    pub min_months: u16,
+    /// The longest accepted period, in months
+    pub max_months: u16,
+    /// Whether periods may be given in months; otherwise only years are accepted
+    pub months: bool,
+}
+
+impl PeriodPolicy {
+    /// A policy accepting `min` to `max` years
+    pub fn years(min: u8, max: u8) -> Self {
+        Self {
+            min_months: u16::from(min) * 12,
+            max_months: u16::from(max) * 12,
+            months: false,
+        }
+    }
+
+    /// A policy accepting `min` to `max` months, given in months or whole years
+    pub fn months(min: u16, max: u16) -> Self {
+        Self {
+            min_months: min,
+            max_months: max,
+            months: true,
+        }
+    }
+
+    /// Check that `period` is accepted by this policy
+    pub fn check(&self, period: Period) -> Result<(), PeriodError> {
+        let months = match period {
+            Period::Years(length) => u16::from(length.0) * 12,
+            Period::Months(_) if !self.months => return Err(PeriodError::MonthsNotAllowed),
+            Period::Months(length) => u16::from(length.0),
+        };
+
+        if months < self.min_months {
+            Err(PeriodError::TooShort { months })
+        } else if months > self.max_months {
+            Err(PeriodError::TooLong { months })
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check the period of a domain create command
+    pub fn check_create(&self, create: &DomainCreate<'_>) -> Result<(), PeriodError> {
+        self.check(create.domain.period)
+    }
+
+    /// Check the period of a domain renew command, and that its `curExpDate` is `expiry`
+    ///
+    /// `expiry` is the expiry date as last reported by the registry, usually from an info
+    /// response.
+    pub fn check_renew(
+        &self,
+        renew: &DomainRenew<'_>,
+        expiry: NaiveDate,
+    ) -> Result<(), PeriodError> {
+        let current = renew.domain.current_expiry_date;
+        if current != expiry {
+            return Err(PeriodError::ExpiryMismatch { current, expiry });
+        }
+
+        self.check(renew.domain.period)
+    }
+}
+
+impl Default for PeriodPolicy {
+    /// 1 to 10 years, the policy of most gTLDs
+    fn default() -> Self {
+        Self::years(1, 10)
+    }
+}
+
+/// Period policies for a set of TLDs
+#[derive(Clone, Debug, Default)]
+pub struct PeriodPolicies {
+    /// The policy for TLDs without a specific policy
+    pub default: PeriodPolicy,
+    tlds: HashMap<String, PeriodPolicy>,
+}
+
+impl PeriodPolicies {
+    /// Use `policy` for names under `tld`
+    ///
+    /// `tld` may have multiple labels (like `co.uk`), in which case it takes precedence over
+    /// a policy for its parent.
+    pub fn with_tld(mut self, tld: &str, policy: PeriodPolicy) -> Self {
+        self.tlds.insert(normalize_tld(tld), policy);
+        self
+    }
+
+    /// The policy applying to the domain `name`
+    pub fn for_name(&self, name: &str) -> &PeriodPolicy {
+        let name = normalize_tld(name);
+        let mut suffix = name.as_str();
+        loop {
+            if let Some(policy) = self.tlds.get(suffix) {
+                return policy;
+            }
+
+            match suffix.split_once('.') {
+                Some((_, parent)) => suffix = parent,
+                None => return &self.default,
+            }
+        }
+    }
+
+    /// Check the period of a domain create command against the policy for its TLD
+    pub fn check_create(&self, create: &DomainCreate<'_>) -> Result<(), PeriodError> {
+        self.for_name(create.domain.name).check_create(create)
+    }
+
+    /// Check a domain renew command against the policy for its TLD
+    pub fn check_renew(
+        &self,
+        renew: &DomainRenew<'_>,
+        expiry: NaiveDate,
+    ) -> Result<(), PeriodError> {
+        self.for_name(renew.domain.name).check_renew(renew, expiry)
+    }
+}
+
+pub(super) fn normalize_tld(tld: &str) -> String {
+    tld.trim_matches('.').to_ascii_lowercase()
+}
+
+/// A period or renewal rejected by a [`PeriodPolicy`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PeriodError {
+    /// The period is shorter than the minimum
+    TooShort { months: u16 },
+    /// The period is longer than the maximum
+    TooLong { months: u16 },
+    /// The period is given in months, but the TLD only accepts years
+    MonthsNotAllowed,
+    /// The `curExpDate` of a renewal does not match the known expiry date
+    ExpiryMismatch {
+        current: NaiveDate,
+        expiry: NaiveDate,
+    },
+}
+
+impl fmt::Display for PeriodError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooShort { months } => write!(f, "period of {months} months is too short"),
+            Self::TooLong { months } => write!(f, "period of {months} months is too long"),
+            Self::MonthsNotAllowed => write!(f, "periods must be given in years"),
+            Self::ExpiryMismatch { current, expiry } => write!(
+                f,
+                "current expiry date {current} does not match known expiry date {expiry}"
+            ),
+        }
+    }
+}
+
+impl StdError for PeriodError {}
+
+impl From<PeriodError> for crate::Error {
+    fn from(err: PeriodError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{PeriodError, PeriodPolicies, PeriodPolicy};
+    use crate::domain::{DomainRenew, ONE_MONTH, ONE_YEAR, SIX_MONTHS, THREE_YEARS};
+
+    #[test]
+    fn periods() {
+        let policy = PeriodPolicy::years(1, 2);
+        assert_eq!(policy.check(ONE_YEAR), Ok(()));
+        assert_eq!(
+            policy.check(THREE_YEARS),
+            Err(PeriodError::TooLong { months: 36 })
+        );
+        assert_eq!(policy.check(ONE_MONTH), Err(PeriodError::MonthsNotAllowed));
+
+        let policy = PeriodPolicy::months(3, 24);
+        assert_eq!(policy.check(SIX_MONTHS), Ok(()));
+        assert_eq!(policy.check(ONE_YEAR), Ok(()));
+        assert_eq!(
+            policy.check(ONE_MONTH),
+            Err(PeriodError::TooShort { months: 1 })
+        );
+    }
+
+    #[test]
+    fn renew() {
+        let policies = PeriodPolicies::default()
+            .with_tld("uk", PeriodPolicy::years(1, 10))
+            .with_tld(".CO.UK", PeriodPolicy::years(1, 2));
+        assert_eq!(
+            policies.for_name("example.co.uk"),
+            &PeriodPolicy::years(1, 2)
+        );
+        assert_eq!(policies.for_name("example.uk"), &PeriodPolicy::years(1, 10));
+        assert_eq!(policies.for_name("example.com"), &PeriodPolicy::default());
+
+        let expiry = NaiveDate::from_ymd_opt(2025, 7, 23).unwrap();
+        let renew = DomainRenew::new("eppdev.co.uk", expiry, THREE_YEARS);
+        assert_eq!(
+            policies.check_renew(&renew, expiry),
+            Err(PeriodError::TooLong { months: 36 })
+        );
+
+        let renew = DomainRenew::new("eppdev.co.uk", expiry, ONE_YEAR);
+        assert_eq!(policies.check_renew(&renew, expiry), Ok(()));
+
+        let known = NaiveDate::from_ymd_opt(2026, 7, 23).unwrap();
+        assert_eq!(
+            policies.check_renew(&renew, known),
+            Err(PeriodError::ExpiryMismatch {
+                current: expiry,
+                expiry: known
+            })
+        );
+    }
+}
@@ -1,10 +1,11 @@
 //! Types for EPP domain renew request
 
-use chrono::{DateTime, NaiveDate, Utc};
+use chrono::{DateTime, FixedOffset, Months, NaiveDate, Utc};
 use instant_xml::{FromXml, ToXml};
 
-use super::{Period, XMLNS};
+use super::{InfoData, Period, XMLNS};
 use crate::common::{NoExtension, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for DomainRenew<'_> {}
@@ -12,6 +13,10 @@ impl Transaction<NoExtension> for DomainRenew<'_> {}
 impl Command for DomainRenew<'_> {
     type Response = RenewData;
     const COMMAND: &'static str = "renew";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::DomainRenew(self)
+    }
 }
 
 impl<'a> DomainRenew<'a> {
@@ -24,6 +29,62 @@ impl<'a> DomainRenew<'a> {
             },
         }
     }
+
+    /// Renew a domain for `period`, with the `curExpDate` taken from its known `expiry`
+    pub fn from_expiry(name: &'a str, expiry: &Expiry, period: Period) -> Self {
+        Self::new(name, expiry.date(), period)
+    }
+}
+
+/// A domain expiry as reported by a registry, and the time zone the registry works in
+///
+/// Responses carry the expiry as a `dateTime` in `<exDate>`, while a renew command takes only
+/// a date in `<curExpDate>`, which the registry compares with the expiry date in its own time
+/// zone. A domain expiring at `2024-07-22T23:30:00Z` at a registry in UTC+1 has to be renewed
+/// with `2024-07-23` as its current expiry date.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Expiry {
+    /// The moment the domain expires
+    pub at: DateTime<Utc>,
+    /// The UTC offset of the registry's local time
+    pub offset: FixedOffset,
+}
+
+impl Expiry {
+    pub fn new(at: DateTime<Utc>, offset: FixedOffset) -> Self {
+        Self { at, offset }
+    }
+
+    /// The expiry date in the registry's local time, as expected in `<curExpDate>`
+    pub fn date(&self) -> NaiveDate {
+        self.at.with_timezone(&self.offset).date_naive()
+    }
+
+    /// The expiry after a renewal for `period`
+    ///
+    /// Dates that don't exist in the target month are clamped to its last day, so a domain
+    /// expiring on February 29 expires on February 28 after a one-year renewal.
+    pub fn after(&self, period: Period) -> Option<Self> {
+        let months = match period {
+            Period::Years(length) => u32::from(length.0) * 12,
+            Period::Months(length) => u32::from(length.0),
+        };
+
+        let local = self.at.with_timezone(&self.offset);
+        Some(Self {
+            at: local
+                .checked_add_months(Months::new(months))?
+                .with_timezone(&Utc),
+            offset: self.offset,
+        })
+    }
+}
+
+impl InfoData {
+    /// The expiry of the domain, for a registry in the time zone `offset`
+    pub fn expiry(&self, offset: FixedOffset) -> Option<Expiry> {
+        Some(Expiry::new(self.expiring_at?, offset))
+    }
 }
 
 // Request
@@ -66,14 +127,21 @@ pub struct RenewData {
     pub expiring_at: Option<DateTime<Utc>>,
 }
 
+impl RenewData {
+    /// The new expiry of the domain, for a registry in the time zone `offset`
+    pub fn expiry(&self, offset: FixedOffset) -> Option<Expiry> {
+        Some(Expiry::new(self.expiring_at?, offset))
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{DomainRenew, Period};
-    use crate::domain::PeriodLength;
+    use super::{DomainRenew, Expiry, Period};
+    use crate::domain::{DomainInfo, PeriodLength, ONE_YEAR};
     use crate::response::ResultCode;
     use crate::tests::{assert_serialized, response_from_file, CLTRID, SUCCESS_MSG, SVTRID};
 
-    use chrono::{NaiveDate, TimeZone, Utc};
+    use chrono::{FixedOffset, NaiveDate, TimeZone, Utc};
 
     #[test]
     fn command() {
@@ -102,4 +170,33 @@ mod tests {
         assert_eq!(object.tr_ids.client_tr_id.unwrap(), CLTRID);
         assert_eq!(object.tr_ids.server_tr_id, SVTRID);
     }
+
+    #[test]
+    fn expiry() {
+        let info = response_from_file::<DomainInfo>("response/domain/info.xml");
+        let info = info.res_data().unwrap();
+
+        // 2023-07-23T15:31:20Z is already the next day in UTC+9
+        let tokyo = FixedOffset::east_opt(9 * 3600).unwrap();
+        let expiry = info.expiry(tokyo).unwrap();
+        assert_eq!(expiry.date(), NaiveDate::from_ymd_opt(2023, 7, 24).unwrap());
+
+        let utc = FixedOffset::east_opt(0).unwrap();
+        let expiry = info.expiry(utc).unwrap();
+        let renew = DomainRenew::from_expiry(&info.name, &expiry, ONE_YEAR);
+        assert_eq!(
+            renew.domain.current_expiry_date,
+            NaiveDate::from_ymd_opt(2023, 7, 23).unwrap()
+        );
+
+        let renewed = response_from_file::<DomainRenew>("response/domain/renew.xml");
+        let renewed = renewed.res_data().unwrap().expiry(utc);
+        assert_eq!(expiry.after(ONE_YEAR), renewed);
+
+        let leap = Expiry::new(Utc.with_ymd_and_hms(2024, 2, 29, 12, 0, 0).unwrap(), utc);
+        assert_eq!(
+            leap.after(ONE_YEAR).unwrap().date(),
+            NaiveDate::from_ymd_opt(2025, 2, 28).unwrap()
+        );
+    }
 }
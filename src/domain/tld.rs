@@ -0,0 +1,593 @@
+//! Per-TLD registration policy
+//!
+//! Registries differ in the periods they accept, the name server model they implement, the
+//! contacts they require and the launch phases they run. A [`TldPolicy`] collects these in
+//! one place. It implements serde's traits, so policies can be kept in configuration files
+//! (TOML, JSON, ...) next to the registry credentials instead of in code:
+//!
+//! ```toml
+//! tld = "example"
+//! max_period = { years = 5 }
+//! nameserver_model = "hostAttr"
+//! required_contacts = ["admin", "tech"]
+//! fee_namespace = "urn:ietf:params:xml:ns:epp:fee-1.0"
+//!
+//! [[launch_phases]]
+//! phase = "sunrise"
+//! starts_at = "2025-01-01T00:00:00Z"
+//! ends_at = "2025-03-01T00:00:00Z"
+//! ```
+//!
+//! The narrower policies used for validation are derived from it, see
+//! [`TldPolicies::period_policies()`] and [`TldPolicies::nameserver_policies()`].
+//!
+//! The launch phase dates tell which phase a TLD is in, so the launch extension for checks
+//! and creates can be built from the calendar with [`TldPolicy::launch_check()`] and
+//! [`TldPolicy::launch_create()`] rather than by hand.
+
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::policy::normalize_tld;
+use super::{
+    DomainContact, DomainCreate, NameserverError, NameserverModel, NameserverPolicies,
+    NameserverPolicy, Period, PeriodError, PeriodPolicies, PeriodPolicy,
+};
+use crate::dto::PeriodDto;
+use crate::extensions::launch::{self, Phase, PhaseType};
+use crate::price_class::{PriceClass, PriceClasses};
+
+/// The registration policy of a TLD
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default)]
+pub struct TldPolicy {
+    /// The TLD, possibly with multiple labels (like `co.uk`)
+    pub tld: String,
+    /// The longest registration or renewal period
+    pub max_period: PeriodDto,
+    /// The periods accepted, if the registry doesn't accept every period up to the maximum
+    pub allowed_periods: Vec<PeriodDto>,
+    pub nameserver_model: NameserverModel,
+    /// The contact types every domain needs, like `admin` and `tech`
+    pub required_contacts: Vec<String>,
+    /// Whether domains need a registrant
+    pub registrant_required: bool,
+    /// Whether each contact type may be given only once
+    pub unique_contacts: bool,
+    /// The contact types the registry rejects, like `billing` at some ccTLDs
+    pub forbidden_contacts: Vec<String>,
+    /// The IDN tables (usually language tags) the registry accepts
+    pub idn_tables: Vec<String>,
+    /// The namespace of the fee extension version the registry implements
+    pub fee_namespace: Option<String>,
+    pub launch_phases: Vec<LaunchPhase>,
+    /// Price class labels specific to the registry, like `PIR-BBBB`
+    pub price_classes: PriceClasses,
+}
+
+impl TldPolicy {
+    /// A policy for `tld` with the defaults of most gTLDs
+    pub fn new(tld: &str) -> Self {
+        Self {
+            tld: normalize_tld(tld),
+            ..Self::default()
+        }
+    }
+
+    /// The period policy derived from this policy
+    ///
+    /// This only covers the maximum period; [`TldPolicy::check_period()`] also checks the
+    /// list of allowed periods.
+    pub fn period_policy(&self) -> PeriodPolicy {
+        let allowed = self.allowed_periods.iter().copied();
+        let months = allowed
+            .clone()
+            .any(|period| matches!(period, PeriodDto::Months(_)));
+        PeriodPolicy {
+            min_months: allowed.map(period_months).min().unwrap_or(12),
+            max_months: period_months(self.max_period),
+            months: months || matches!(self.max_period, PeriodDto::Months(_)),
+        }
+    }
+
+    /// The name server policy derived from this policy
+    pub fn nameserver_policy(&self) -> NameserverPolicy {
+        NameserverPolicy::new(self.nameserver_model)
+    }
+
+    /// Check that `period` is accepted for the TLD
+    pub fn check_period(&self, period: Period) -> Result<(), TldPolicyError> {
+        self.period_policy().check(period)?;
+        let months = match period {
+            Period::Years(length) => u16::from(length.0) * 12,
+            Period::Months(length) => u16::from(length.0),
+        };
+
+        let mut allowed = self.allowed_periods.iter().copied().map(period_months);
+        match self.allowed_periods.is_empty() || allowed.any(|m| m == months) {
+            true => Ok(()),
+            false => Err(TldPolicyError::PeriodNotOffered { months }),
+        }
+    }
+
+    /// Check a domain create command against the policy
+    ///
+    /// This covers the period, the name server model and the contacts.
+    pub fn check_create(&self, create: &DomainCreate<'_>) -> Result<(), TldPolicyError> {
+        let domain = &create.domain;
+        self.check_period(domain.period)?;
+        if let Some(ns) = &domain.ns {
+            self.nameserver_policy().check(domain.name, ns)?;
+        }
+
+        self.check_contacts(domain.registrant, domain.contacts.unwrap_or_default())
+    }
+
+    /// Check the registrant and contacts of a domain against the policy
+    ///
+    /// The registrant is given separately from the contacts in EPP, so a contact of type
+    /// `registrant` is always rejected.
+    pub fn check_contacts(
+        &self,
+        registrant: Option<&str>,
+        contacts: &[DomainContact<'_>],
+    ) -> Result<(), TldPolicyError> {
+        if self.registrant_required && registrant.is_none() {
+            return Err(TldPolicyError::MissingRegistrant);
+        }
+
+        for (i, contact) in contacts.iter().enumerate() {
+            let contact_type = &*contact.contact_type;
+            if contact_type == "registrant" {
+                return Err(TldPolicyError::RegistrantContact);
+            } else if self.forbidden_contacts.iter().any(|t| t == contact_type) {
+                return Err(TldPolicyError::ForbiddenContact(contact_type.to_owned()));
+            }
+
+            let earlier = &contacts[..i];
+            if self.unique_contacts && earlier.iter().any(|c| c.contact_type == contact_type) {
+                return Err(TldPolicyError::DuplicateContact(contact_type.to_owned()));
+            }
+        }
+
+        for required in &self.required_contacts {
+            if !contacts.iter().any(|c| c.contact_type == *required) {
+                return Err(TldPolicyError::MissingContact(required.clone()));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The typed class of a fee class or charge category label
+    pub fn price_class(&self, label: &str) -> PriceClass {
+        self.price_classes.classify(label)
+    }
+
+    /// The launch phase active at `at`, if any
+    ///
+    /// If phases overlap (like a custom sub-phase within sunrise), the one that started last
+    /// is returned.
+    pub fn active_phase(&self, at: DateTime<Utc>) -> Option<&LaunchPhase> {
+        self.launch_phases
+            .iter()
+            .filter(|phase| phase.is_active(at))
+            .max_by_key(|phase| phase.starts_at)
+    }
+
+    /// The launch extension for a check in the phase active at `at`
+    ///
+    /// Without an active phase, the check has no phase and the registry uses its current one.
+    pub fn launch_check(&self, at: DateTime<Utc>) -> launch::Check<'_> {
+        launch::Check::new(self.active_phase(at).map(LaunchPhase::phase))
+    }
+
+    /// The launch extension for a create in the phase active at `at`
+    ///
+    /// Fails if no phase is active, since the registry would reject the create.
+    pub fn launch_create(&self, at: DateTime<Utc>) -> Result<launch::Create<'_>, TldPolicyError> {
+        match self.active_phase(at) {
+            Some(phase) => Ok(launch::Create::new(phase.phase())),
+            None => Err(TldPolicyError::NoLaunchPhase),
+        }
+    }
+
+    /// Check that `create` is for the phase active at `at`
+    ///
+    /// TLDs without launch phases in their policy accept any phase.
+    pub fn check_launch_create(
+        &self,
+        create: &launch::Create<'_>,
+        at: DateTime<Utc>,
+    ) -> Result<(), TldPolicyError> {
+        if self.launch_phases.is_empty() {
+            return Ok(());
+        }
+
+        let Some(active) = self.active_phase(at) else {
+            return Err(TldPolicyError::NoLaunchPhase);
+        };
+
+        let phase = &create.phase;
+        match phase.value == active.phase && phase.name == active.name.as_deref() {
+            true => Ok(()),
+            false => Err(TldPolicyError::OutsideLaunchPhase {
+                phase: phase.name.unwrap_or(phase.value.as_str()).to_owned(),
+            }),
+        }
+    }
+}
+
+impl Default for TldPolicy {
+    /// Periods of 1 to 10 years, host objects, and no required contacts or launch phases
+    fn default() -> Self {
+        Self {
+            tld: String::new(),
+            max_period: PeriodDto::Years(10),
+            allowed_periods: Vec::new(),
+            nameserver_model: NameserverModel::default(),
+            required_contacts: Vec::new(),
+            registrant_required: false,
+            unique_contacts: false,
+            forbidden_contacts: Vec::new(),
+            idn_tables: Vec::new(),
+            fee_namespace: None,
+            launch_phases: Vec::new(),
+            price_classes: PriceClasses::default(),
+        }
+    }
+}
+
+/// A launch phase of a TLD
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct LaunchPhase {
+    pub phase: PhaseType,
+    /// The name of a custom phase or sub-phase, sent as the `name` attribute
+    pub name: Option<String>,
+    pub starts_at: DateTime<Utc>,
+    /// The end of the phase, if it is planned
+    pub ends_at: Option<DateTime<Utc>>,
+}
+
+impl LaunchPhase {
+    /// Whether the phase runs at `at`, including its start but not its end
+    pub fn is_active(&self, at: DateTime<Utc>) -> bool {
+        self.starts_at <= at && self.ends_at.is_none_or(|end| at < end)
+    }
+
+    /// The `<launch:phase>` element for this phase
+    pub fn phase(&self) -> Phase<'_> {
+        Phase::new(self.phase, self.name.as_deref())
+    }
+}
+
+/// Policies for a set of TLDs
+#[derive(Clone, Debug, Default)]
+pub struct TldPolicies {
+    tlds: HashMap<String, TldPolicy>,
+}
+
+impl TldPolicies {
+    /// Add `policy`, replacing any previous policy for its TLD
+    pub fn with(mut self, policy: TldPolicy) -> Self {
+        self.tlds.insert(normalize_tld(&policy.tld), policy);
+        self
+    }
+
+    /// The policy applying to the domain `name`, if any
+    ///
+    /// As with [`PeriodPolicies`], a policy for a multi-label TLD takes precedence over one
+    /// for its parent.
+    pub fn for_name(&self, name: &str) -> Option<&TldPolicy> {
+        let name = normalize_tld(name);
+        let mut suffix = name.as_str();
+        loop {
+            if let Some(policy) = self.tlds.get(suffix) {
+                return Some(policy);
+            }
+
+            suffix = suffix.split_once('.')?.1;
+        }
+    }
+
+    /// Check a domain create command against the policy for its TLD, if there is one
+    pub fn check_create(&self, create: &DomainCreate<'_>) -> Result<(), TldPolicyError> {
+        match self.for_name(create.domain.name) {
+            Some(policy) => policy.check_create(create),
+            None => Ok(()),
+        }
+    }
+
+    /// The period policies of all TLDs
+    pub fn period_policies(&self) -> PeriodPolicies {
+        self.tlds
+            .iter()
+            .fold(PeriodPolicies::default(), |policies, (tld, policy)| {
+                policies.with_tld(tld, policy.period_policy())
+            })
+    }
+
+    /// The name server policies of all TLDs
+    ///
+    /// These may span several registries, so no TLD is marked as internal; add those of the
+    /// registry with [`NameserverPolicies::internal_tld()`] before checking host creates.
+    pub fn nameserver_policies(&self) -> NameserverPolicies {
+        self.tlds
+            .iter()
+            .fold(NameserverPolicies::default(), |policies, (tld, policy)| {
+                policies.with_tld(tld, policy.nameserver_policy())
+            })
+    }
+}
+
+impl FromIterator<TldPolicy> for TldPolicies {
+    fn from_iter<T: IntoIterator<Item = TldPolicy>>(iter: T) -> Self {
+        iter.into_iter().fold(Self::default(), Self::with)
+    }
+}
+
+fn period_months(period: PeriodDto) -> u16 {
+    match period {
+        PeriodDto::Years(years) => u16::from(years) * 12,
+        PeriodDto::Months(months) => u16::from(months),
+    }
+}
+
+/// A command rejected by a [`TldPolicy`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TldPolicyError {
+    Period(PeriodError),
+    /// The period is in range, but not one of the periods offered
+    PeriodNotOffered {
+        months: u16,
+    },
+    Nameservers(NameserverError),
+    MissingRegistrant,
+    /// A required contact type is missing
+    MissingContact(String),
+    /// A contact type is given more than once, where the policy allows only one
+    DuplicateContact(String),
+    /// A contact type the registry doesn't accept
+    ForbiddenContact(String),
+    /// A contact of type `registrant`, rather than the registrant of the domain
+    RegistrantContact,
+    /// No launch phase is active
+    NoLaunchPhase,
+    /// A launch create is for a phase (or custom phase name) that is not active
+    OutsideLaunchPhase {
+        phase: String,
+    },
+}
+
+impl fmt::Display for TldPolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Period(err) => err.fmt(f),
+            Self::PeriodNotOffered { months } => {
+                write!(f, "period of {months} months is not offered")
+            }
+            Self::Nameservers(err) => err.fmt(f),
+            Self::MissingRegistrant => write!(f, "a registrant is required"),
+            Self::MissingContact(contact_type) => {
+                write!(f, "a {contact_type} contact is required")
+            }
+            Self::DuplicateContact(contact_type) => {
+                write!(f, "only one {contact_type} contact is allowed")
+            }
+            Self::ForbiddenContact(contact_type) => {
+                write!(f, "{contact_type} contacts are not accepted")
+            }
+            Self::RegistrantContact => {
+                write!(
+                    f,
+                    "the registrant must be given as registrant, not as contact"
+                )
+            }
+            Self::NoLaunchPhase => write!(f, "no launch phase is active"),
+            Self::OutsideLaunchPhase { phase } => {
+                write!(f, "launch phase {phase} is not active")
+            }
+        }
+    }
+}
+
+impl StdError for TldPolicyError {}
+
+impl From<PeriodError> for TldPolicyError {
+    fn from(err: PeriodError) -> Self {
+        Self::Period(err)
+    }
+}
+
+impl From<NameserverError> for TldPolicyError {
+    fn from(err: NameserverError) -> Self {
+        Self::Nameservers(err)
+    }
+}
+
+impl From<TldPolicyError> for crate::Error {
+    fn from(err: TldPolicyError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{LaunchPhase, TldPolicies, TldPolicy, TldPolicyError};
+    use crate::domain::{
+        DomainContact, DomainCreate, NameserverList, NameserverModel, PeriodError, ONE_YEAR,
+        THREE_YEARS, TWO_YEARS,
+    };
+    use crate::dto::PeriodDto;
+    use crate::extensions::launch::{Create, Phase, PhaseType};
+
+    #[test]
+    fn create() {
+        let policies = [
+            TldPolicy {
+                max_period: PeriodDto::Years(2),
+                required_contacts: vec!["admin".into(), "tech".into()],
+                ..TldPolicy::new("example")
+            },
+            TldPolicy {
+                allowed_periods: vec![PeriodDto::Years(1), PeriodDto::Years(3)],
+                nameserver_model: NameserverModel::HostAttr,
+                ..TldPolicy::new("co.example")
+            },
+        ]
+        .into_iter()
+        .collect::<TldPolicies>();
+        assert!(policies.for_name("eppdev.com").is_none());
+        assert_eq!(
+            policies.for_name("eppdev.co.example").unwrap().tld,
+            "co.example"
+        );
+
+        let contacts = [DomainContact {
+            contact_type: "admin".into(),
+            id: "eppdev-contact-3".into(),
+        }];
+        let create = |name, period| {
+            DomainCreate::new(name, period, None, None, "epP4uthd#v", Some(&contacts))
+        };
+        assert_eq!(
+            policies.check_create(&create("eppdev.example", THREE_YEARS)),
+            Err(TldPolicyError::Period(PeriodError::TooLong { months: 36 }))
+        );
+        assert_eq!(
+            policies.check_create(&create("eppdev.example", ONE_YEAR)),
+            Err(TldPolicyError::MissingContact("tech".into()))
+        );
+        assert_eq!(
+            policies.check_create(&create("eppdev.co.example", TWO_YEARS)),
+            Err(TldPolicyError::PeriodNotOffered { months: 24 })
+        );
+        assert_eq!(
+            policies.check_create(&create("eppdev.co.example", THREE_YEARS)),
+            Ok(())
+        );
+
+        let ns = NameserverList::HostObjects(vec!["ns1.eppdev.com".into()]);
+        let mut create = create("eppdev.co.example", ONE_YEAR);
+        create.nameservers(&ns);
+        assert!(matches!(
+            policies.check_create(&create),
+            Err(TldPolicyError::Nameservers(_))
+        ));
+
+        let periods = policies.period_policies();
+        assert_eq!(periods.for_name("eppdev.example").max_months, 24);
+        assert_eq!(periods.for_name("eppdev.co.example").max_months, 120);
+    }
+
+    #[test]
+    fn contacts() {
+        let policy = TldPolicy {
+            registrant_required: true,
+            unique_contacts: true,
+            forbidden_contacts: vec!["billing".into()],
+            required_contacts: vec!["admin".into()],
+            ..TldPolicy::new("example")
+        };
+        let contact = |contact_type: &'static str| DomainContact {
+            contact_type: contact_type.into(),
+            id: "eppdev-contact-3".into(),
+        };
+        let create = |registrant: Option<&str>, contacts: &[DomainContact<'_>]| {
+            DomainCreate::new(
+                "eppdev.example",
+                ONE_YEAR,
+                None,
+                registrant,
+                "epP4uthd#v",
+                Some(contacts),
+            )
+            .validated(&policy)
+            .map(|_| ())
+        };
+
+        let contacts = [contact("admin"), contact("tech")];
+        assert_eq!(
+            create(None, &contacts),
+            Err(TldPolicyError::MissingRegistrant)
+        );
+        assert_eq!(create(Some("eppdev-contact-1"), &contacts), Ok(()));
+        assert_eq!(
+            create(
+                Some("eppdev-contact-1"),
+                &[contact("admin"), contact("admin")]
+            ),
+            Err(TldPolicyError::DuplicateContact("admin".into()))
+        );
+        assert_eq!(
+            create(
+                Some("eppdev-contact-1"),
+                &[contact("admin"), contact("billing")]
+            ),
+            Err(TldPolicyError::ForbiddenContact("billing".into()))
+        );
+        assert_eq!(
+            create(
+                Some("eppdev-contact-1"),
+                &[contact("registrant"), contact("admin")]
+            ),
+            Err(TldPolicyError::RegistrantContact)
+        );
+        assert_eq!(
+            create(Some("eppdev-contact-1"), &[contact("tech")]),
+            Err(TldPolicyError::MissingContact("admin".into()))
+        );
+    }
+
+    #[test]
+    fn launch_phases() {
+        let date = |month, day| Utc.with_ymd_and_hms(2025, month, day, 0, 0, 0).unwrap();
+        let policy = TldPolicy {
+            launch_phases: vec![
+                LaunchPhase {
+                    phase: PhaseType::Sunrise,
+                    name: None,
+                    starts_at: date(1, 1),
+                    ends_at: Some(date(3, 1)),
+                },
+                LaunchPhase {
+                    phase: PhaseType::Custom,
+                    name: Some("sunrise-limited".into()),
+                    starts_at: date(2, 1),
+                    ends_at: Some(date(3, 1)),
+                },
+            ],
+            ..TldPolicy::new("example")
+        };
+
+        assert!(policy.active_phase(date(12, 1)).is_none());
+        assert!(policy.launch_check(date(12, 1)).phase.is_none());
+        assert_eq!(
+            policy.launch_create(date(3, 1)).unwrap_err(),
+            TldPolicyError::NoLaunchPhase
+        );
+
+        let create = policy.launch_create(date(1, 15)).unwrap();
+        assert_eq!(create.phase.value, PhaseType::Sunrise);
+        assert_eq!(create.phase.name, None);
+
+        let create = policy.launch_create(date(2, 15)).unwrap();
+        assert_eq!(create.phase.value, PhaseType::Custom);
+        assert_eq!(create.phase.name, Some("sunrise-limited"));
+        assert_eq!(policy.check_launch_create(&create, date(2, 15)), Ok(()));
+
+        let sunrise = Create::new(Phase::new(PhaseType::Sunrise, None));
+        assert_eq!(
+            policy.check_launch_create(&sunrise, date(2, 15)),
+            Err(TldPolicyError::OutsideLaunchPhase {
+                phase: "sunrise".into()
+            })
+        );
+    }
+}
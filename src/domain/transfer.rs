@@ -1,10 +1,11 @@
 //! Types for EPP domain transfer request
 
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, Utc};
 use instant_xml::{FromXml, ToXml};
 
-use super::{DomainAuthInfo, Period, XMLNS};
-use crate::common::{NoExtension, EPP_XMLNS};
+use super::{Period, RequestAuthInfo, XMLNS};
+use crate::common::{NoExtension, Roid, Text, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for DomainTransfer<'_> {}
@@ -12,6 +13,10 @@ impl Transaction<NoExtension> for DomainTransfer<'_> {}
 impl Command for DomainTransfer<'_> {
     type Response = TransferData;
     const COMMAND: &'static str = "transfer";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::DomainTransfer(self)
+    }
 }
 
 impl<'a> DomainTransfer<'a> {
@@ -20,7 +25,7 @@ impl<'a> DomainTransfer<'a> {
             "request",
             name,
             period,
-            Some(DomainAuthInfo::new(auth_password)),
+            Some(RequestAuthInfo::new(auth_password, None)),
         )
     }
 
@@ -29,7 +34,23 @@ impl<'a> DomainTransfer<'a> {
             "query",
             name,
             None,
-            Some(DomainAuthInfo::new(auth_password)),
+            Some(RequestAuthInfo::new(auth_password, None)),
+        )
+    }
+
+    /// Request a transfer with the password of one of the domain's contacts, identified by
+    /// its ROID
+    pub fn with_contact_auth(
+        name: &'a str,
+        period: Option<Period>,
+        auth_password: &'a str,
+        roid: &'a Roid,
+    ) -> Self {
+        Self::build(
+            "request",
+            name,
+            period,
+            Some(RequestAuthInfo::new(auth_password, Some(roid))),
         )
     }
 
@@ -49,7 +70,7 @@ impl<'a> DomainTransfer<'a> {
         operation: &'a str,
         name: &'a str,
         period: Option<Period>,
-        auth_info: Option<DomainAuthInfo<'a>>,
+        auth_info: Option<RequestAuthInfo<'a>>,
     ) -> Self {
         Self {
             operation,
@@ -60,6 +81,16 @@ impl<'a> DomainTransfer<'a> {
             },
         }
     }
+
+    /// The name of the domain being transferred
+    pub fn name(&self) -> &'a str {
+        self.domain.name
+    }
+
+    /// The period the registration is extended by, for transfer requests giving one
+    pub fn period(&self) -> Option<Period> {
+        self.domain.period
+    }
 }
 
 // Request
@@ -76,7 +107,7 @@ pub struct DomainTransferReqData<'a> {
     /// The authInfo for the domain under transfer
     /// Only applicable to domain transfer and domain transfer query requests
     #[xml(rename = "authInfo")]
-    auth_info: Option<DomainAuthInfo<'a>>,
+    auth_info: Option<RequestAuthInfo<'a>>,
 }
 
 #[derive(Debug, ToXml)]
@@ -128,6 +159,149 @@ pub struct TransferData {
     pub expiring_at: Option<DateTime<Utc>>,
 }
 
+impl TransferData {
+    /// The transfer status, or `None` if the registry uses a value not defined in RFC 5731
+    pub fn status(&self) -> Option<TransferStatus> {
+        TransferStatus::parse(&self.transfer_status)
+    }
+
+    /// Time left at `now` until the `acDate`, negative once it has passed
+    ///
+    /// For a pending transfer, this is the deadline for the losing registrar to approve or
+    /// reject it; for a completed one, the date it was acted upon.
+    pub fn time_to_ack(&self, now: DateTime<Utc>) -> Duration {
+        self.ack_by - now
+    }
+
+    /// Time elapsed at `now` since the transfer was requested (`reDate`)
+    pub fn since_request(&self, now: DateTime<Utc>) -> Duration {
+        now - self.requested_at
+    }
+
+    /// How urgently a decision on this transfer is needed at `now`
+    ///
+    /// Pending transfers whose `acDate` is less than `lead` away count as imminent: unless the
+    /// losing registrar acts, the registry will approve them (`serverApproved`).
+    pub fn urgency(&self, now: DateTime<Utc>, lead: Duration) -> TransferUrgency {
+        if self.status() != Some(TransferStatus::Pending) {
+            return TransferUrgency::Settled;
+        }
+
+        let left = self.time_to_ack(now);
+        if left <= Duration::zero() {
+            TransferUrgency::Overdue
+        } else if left <= lead {
+            TransferUrgency::Imminent
+        } else {
+            TransferUrgency::Pending
+        }
+    }
+}
+
+/// A `<trnData>` element borrowing from the response frame, like in a transfer poll message
+#[derive(Debug, FromXml)]
+#[xml(rename = "trnData", ns(XMLNS))]
+pub struct TransferDataRef<'a> {
+    #[xml(borrow)]
+    pub name: Text<'a>,
+    #[xml(rename = "trStatus", borrow)]
+    pub transfer_status: Text<'a>,
+    #[xml(rename = "reID", borrow)]
+    pub requester_id: Text<'a>,
+    #[xml(
+        rename = "reDate",
+        deserialize_with = "crate::datetime::deserialize_datetime_utc"
+    )]
+    pub requested_at: DateTime<Utc>,
+    #[xml(rename = "acID", borrow)]
+    pub ack_id: Text<'a>,
+    #[xml(
+        rename = "acDate",
+        deserialize_with = "crate::datetime::deserialize_datetime_utc"
+    )]
+    pub ack_by: DateTime<Utc>,
+    #[xml(
+        rename = "exDate",
+        deserialize_with = "crate::datetime::deserialize_datetime_utc_option"
+    )]
+    pub expiring_at: Option<DateTime<Utc>>,
+}
+
+impl TransferDataRef<'_> {
+    /// The transfer status, or `None` if the registry uses a value not defined in RFC 5731
+    pub fn status(&self) -> Option<TransferStatus> {
+        TransferStatus::parse(&self.transfer_status)
+    }
+
+    pub fn into_owned(self) -> TransferData {
+        TransferData {
+            name: self.name.into_owned(),
+            transfer_status: self.transfer_status.into_owned(),
+            requester_id: self.requester_id.into_owned(),
+            requested_at: self.requested_at,
+            ack_id: self.ack_id.into_owned(),
+            ack_by: self.ack_by,
+            expiring_at: self.expiring_at,
+        }
+    }
+}
+
+/// Classification of a transfer by [`TransferData::urgency()`], most urgent last
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum TransferUrgency {
+    /// The transfer is no longer pending
+    Settled,
+    /// The transfer is pending, with more time left than the lead time
+    Pending,
+    /// Auto-approval by the registry is due within the lead time
+    Imminent,
+    /// The `acDate` has passed; the registry approves the transfer any moment now
+    Overdue,
+}
+
+/// The state of a domain transfer, as reported in `<trStatus>`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TransferStatus {
+    ClientApproved,
+    ClientCancelled,
+    ClientRejected,
+    Pending,
+    ServerApproved,
+    ServerCancelled,
+}
+
+impl TransferStatus {
+    pub fn as_str(&self) -> &'static str {
+        use TransferStatus::*;
+        match self {
+            ClientApproved => "clientApproved",
+            ClientCancelled => "clientCancelled",
+            ClientRejected => "clientRejected",
+            Pending => "pending",
+            ServerApproved => "serverApproved",
+            ServerCancelled => "serverCancelled",
+        }
+    }
+
+    /// Whether the domain moved to the requesting registrar
+    pub fn is_approved(&self) -> bool {
+        matches!(self, Self::ClientApproved | Self::ServerApproved)
+    }
+
+    fn parse(s: &str) -> Option<Self> {
+        use TransferStatus::*;
+        Some(match s {
+            "clientApproved" => ClientApproved,
+            "clientCancelled" => ClientCancelled,
+            "clientRejected" => ClientRejected,
+            "pending" => Pending,
+            "serverApproved" => ServerApproved,
+            "serverCancelled" => ServerCancelled,
+            _ => return None,
+        })
+    }
+}
+
 /// Type that represents the `<panData>` tag for domain pending action poll messages
 #[derive(Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "panData", ns(XMLNS))]
@@ -171,9 +345,9 @@ pub struct PendingActionTRID {
 
 #[cfg(test)]
 mod tests {
-    use chrono::{TimeZone, Utc};
+    use chrono::{Duration, TimeZone, Utc};
 
-    use super::{DomainTransfer, Period};
+    use super::{DomainTransfer, Period, TransferUrgency};
     use crate::domain::PeriodLength;
     use crate::response::ResultCode;
     use crate::tests::{assert_serialized, response_from_file, CLTRID, SUCCESS_MSG, SVTRID};
@@ -303,4 +477,24 @@ mod tests {
         assert_eq!(object.tr_ids.client_tr_id.unwrap(), CLTRID);
         assert_eq!(object.tr_ids.server_tr_id, SVTRID);
     }
+
+    #[test]
+    fn urgency() {
+        let object = response_from_file::<DomainTransfer>("response/domain/transfer_request.xml");
+        let mut result = object.into_data().unwrap();
+
+        let now = Utc.with_ymd_and_hms(2021, 7, 27, 15, 31, 21).unwrap();
+        assert_eq!(result.time_to_ack(now), Duration::days(1));
+        assert_eq!(result.since_request(now), Duration::days(4));
+
+        let lead = Duration::hours(12);
+        assert_eq!(result.urgency(now, lead), TransferUrgency::Pending);
+        let later = now + Duration::hours(18);
+        assert_eq!(result.urgency(later, lead), TransferUrgency::Imminent);
+        let later = now + Duration::days(2);
+        assert_eq!(result.urgency(later, lead), TransferUrgency::Overdue);
+
+        result.transfer_status = "clientRejected".into();
+        assert_eq!(result.urgency(now, lead), TransferUrgency::Settled);
+    }
 }
@@ -2,9 +2,10 @@
 
 use instant_xml::ToXml;
 
-use super::{DomainAuthInfo, DomainContact, NameServers, Status, XMLNS};
+use super::{DomainAuthInfo, DomainContact, NameServers, NameserverList, Status, XMLNS};
 use crate::{
     common::{NoExtension, EPP_XMLNS},
+    middleware::CommandView,
     request::{Command, Transaction},
 };
 
@@ -13,6 +14,10 @@ impl Transaction<NoExtension> for DomainUpdate<'_> {}
 impl Command for DomainUpdate<'_> {
     type Response = ();
     const COMMAND: &'static str = "update";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::DomainUpdate(self.into())
+    }
 }
 
 impl<'a> DomainUpdate<'a> {
@@ -41,6 +46,26 @@ impl<'a> DomainUpdate<'a> {
     pub fn remove(&mut self, remove: DomainRemove<'a>) {
         self.domain.remove = Some(remove);
     }
+
+    /// Adds the name servers `ns`, in either model, keeping the rest of the `<add>` tag
+    pub fn add_nameservers(&mut self, ns: &'a NameserverList) {
+        let add = self.domain.add.get_or_insert(DomainAdd {
+            ns: None,
+            contacts: None,
+            statuses: None,
+        });
+        add.ns = Some(ns.into());
+    }
+
+    /// Removes the name servers `ns`, in either model, keeping the rest of the `<rem>` tag
+    pub fn remove_nameservers(&mut self, ns: &'a NameserverList) {
+        let remove = self.domain.remove.get_or_insert(DomainRemove {
+            ns: None,
+            contacts: None,
+            statuses: None,
+        });
+        remove.ns = Some(ns.into());
+    }
 }
 
 /// Type for elements under the `<chg>` tag for domain update
@@ -105,10 +130,12 @@ pub struct DomainUpdate<'a> {
 
 #[cfg(test)]
 mod tests {
+    use std::net::IpAddr;
+
     use super::{
         DomainAdd, DomainAuthInfo, DomainChangeInfo, DomainContact, DomainRemove, DomainUpdate,
     };
-    use crate::domain::Status;
+    use crate::domain::{Nameserver, NameserverList, Status};
     use crate::response::ResultCode;
     use crate::tests::{assert_serialized, response_from_file, CLTRID, SUCCESS_MSG, SVTRID};
 
@@ -144,6 +171,24 @@ mod tests {
         assert_serialized("request/domain/update.xml", &object);
     }
 
+    #[test]
+    fn nameservers() {
+        let mut object = DomainUpdate::new("eppdev.com");
+        object.add(DomainAdd {
+            ns: None,
+            contacts: None,
+            statuses: Some(&[Status::ClientDeleteProhibited]),
+        });
+
+        let glue = [IpAddr::from([192, 0, 2, 1])];
+        let add =
+            NameserverList::HostAttributes(vec![Nameserver::with_glue("ns1.eppdev.com", glue)]);
+        let remove = NameserverList::HostAttributes(vec![Nameserver::new("ns2.example.net")]);
+        object.add_nameservers(&add);
+        object.remove_nameservers(&remove);
+        assert_serialized("request/domain/update_nameservers.xml", &object);
+    }
+
     #[test]
     fn response() {
         let object = response_from_file::<DomainUpdate>("response/domain/update.xml");
@@ -0,0 +1,379 @@
+//! Owned, serde-friendly descriptions of commands
+//!
+//! Commands borrow their data, which suits building them in code but not a provisioning
+//! system taking jobs from a message queue: those arrive as JSON or YAML payloads, owned by
+//! the job. The structs in this module can be deserialized from such payloads, and a command
+//! borrowing from them is made with `TryFrom`, for example
+//! `DomainCreate::try_from(&dto)?`. The conversion checks what can be checked without asking
+//! the registry (name and ID syntax, period lengths, country codes), so that a malformed job
+//! fails before a command is sent.
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::net::IpAddr;
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::contact::{validate_id, Address, ContactCreate, Country, InfoType, PostalInfo, Voice};
+use crate::domain::{
+    validate_name, DomainContact, DomainCreate, DomainDelete, DomainInfo, DomainRenew,
+    DomainTransfer, NameserverList, Period, PeriodLength,
+};
+use crate::host::HostCreate;
+use crate::Error;
+
+/// A registration period, like `{"years": 2}`
+#[derive(Clone, Copy, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum PeriodDto {
+    Years(u8),
+    Months(u8),
+}
+
+impl Default for PeriodDto {
+    fn default() -> Self {
+        Self::Years(1)
+    }
+}
+
+impl TryFrom<PeriodDto> for Period {
+    type Error = Error;
+
+    fn try_from(dto: PeriodDto) -> Result<Self, Error> {
+        Ok(match dto {
+            PeriodDto::Years(length) => Self::Years(PeriodLength::new(length)?),
+            PeriodDto::Months(length) => Self::Months(PeriodLength::new(length)?),
+        })
+    }
+}
+
+/// The names for a [`DomainCheck`](crate::domain::DomainCheck)
+///
+/// A check borrows a slice of `&str`, so there is no `TryFrom` conversion; use
+/// [`DomainCheckDto::validate()`] and collect the names.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DomainCheckDto {
+    pub names: Vec<String>,
+}
+
+impl DomainCheckDto {
+    /// Check the syntax of the names
+    pub fn validate(&self) -> Result<(), Error> {
+        if self.names.is_empty() {
+            return Err(InvalidDto::new("names", "must not be empty").into());
+        }
+        for name in &self.names {
+            validate_name(name)?;
+        }
+        Ok(())
+    }
+}
+
+/// The data for a [`DomainInfo`]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DomainInfoDto {
+    pub name: String,
+    /// The domain password, to get the full data of a domain sponsored by another registrar
+    pub auth_info: Option<String>,
+}
+
+impl<'a> TryFrom<&'a DomainInfoDto> for DomainInfo<'a> {
+    type Error = Error;
+
+    fn try_from(dto: &'a DomainInfoDto) -> Result<Self, Error> {
+        validate_name(&dto.name)?;
+        Ok(DomainInfo::new(&dto.name, dto.auth_info.as_deref()))
+    }
+}
+
+/// The data for a [`DomainCreate`]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DomainCreateDto {
+    pub name: String,
+    /// One year if not given
+    #[serde(default)]
+    pub period: PeriodDto,
+    pub nameservers: Option<NameserverList>,
+    pub registrant: Option<String>,
+    #[serde(default)]
+    pub contacts: Vec<DomainContact<'static>>,
+    pub auth_info: String,
+}
+
+impl<'a> TryFrom<&'a DomainCreateDto> for DomainCreate<'a> {
+    type Error = Error;
+
+    fn try_from(dto: &'a DomainCreateDto) -> Result<Self, Error> {
+        validate_name(&dto.name)?;
+        if let Some(nameservers) = &dto.nameservers {
+            for name in nameservers.names() {
+                validate_name(name)?;
+            }
+        }
+        if let Some(registrant) = &dto.registrant {
+            validate_id(registrant)?;
+        }
+        for contact in &dto.contacts {
+            validate_id(&contact.id)?;
+        }
+        if dto.auth_info.is_empty() {
+            return Err(InvalidDto::new("auth_info", "must not be empty").into());
+        }
+
+        let contacts = (!dto.contacts.is_empty()).then_some(dto.contacts.as_slice());
+        let mut create = DomainCreate::new(
+            &dto.name,
+            dto.period.try_into()?,
+            None,
+            dto.registrant.as_deref(),
+            &dto.auth_info,
+            contacts,
+        );
+        if let Some(nameservers) = &dto.nameservers {
+            create.nameservers(nameservers);
+        }
+        Ok(create)
+    }
+}
+
+/// The data for a [`DomainRenew`]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DomainRenewDto {
+    pub name: String,
+    /// The current expiry date, in the registry's time zone
+    pub current_expiry: NaiveDate,
+    #[serde(default)]
+    pub period: PeriodDto,
+}
+
+impl<'a> TryFrom<&'a DomainRenewDto> for DomainRenew<'a> {
+    type Error = Error;
+
+    fn try_from(dto: &'a DomainRenewDto) -> Result<Self, Error> {
+        validate_name(&dto.name)?;
+        Ok(DomainRenew::new(
+            &dto.name,
+            dto.current_expiry,
+            dto.period.try_into()?,
+        ))
+    }
+}
+
+/// The data for a [`DomainTransfer`] request
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DomainTransferDto {
+    pub name: String,
+    /// The period to extend the registration by, if the registry allows choosing it
+    pub period: Option<PeriodDto>,
+    pub auth_info: String,
+}
+
+impl<'a> TryFrom<&'a DomainTransferDto> for DomainTransfer<'a> {
+    type Error = Error;
+
+    fn try_from(dto: &'a DomainTransferDto) -> Result<Self, Error> {
+        validate_name(&dto.name)?;
+        if dto.auth_info.is_empty() {
+            return Err(InvalidDto::new("auth_info", "must not be empty").into());
+        }
+
+        let period = dto.period.map(Period::try_from).transpose()?;
+        Ok(DomainTransfer::new(&dto.name, period, &dto.auth_info))
+    }
+}
+
+/// The data for a [`DomainDelete`]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DomainDeleteDto {
+    pub name: String,
+}
+
+impl<'a> TryFrom<&'a DomainDeleteDto> for DomainDelete<'a> {
+    type Error = Error;
+
+    fn try_from(dto: &'a DomainDeleteDto) -> Result<Self, Error> {
+        validate_name(&dto.name)?;
+        Ok(DomainDelete::new(&dto.name))
+    }
+}
+
+/// The data for a [`HostCreate`]
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct HostCreateDto {
+    pub name: String,
+    /// Glue addresses, only allowed for hosts within a domain of the registry
+    #[serde(default)]
+    pub addresses: Vec<IpAddr>,
+}
+
+impl<'a> TryFrom<&'a HostCreateDto> for HostCreate<'a> {
+    type Error = Error;
+
+    fn try_from(dto: &'a HostCreateDto) -> Result<Self, Error> {
+        validate_name(&dto.name)?;
+        let addresses = (!dto.addresses.is_empty()).then_some(dto.addresses.as_slice());
+        Ok(HostCreate::new(&dto.name, addresses))
+    }
+}
+
+/// The data for a [`ContactCreate`], with an internationalized (ASCII) postal address
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct ContactCreateDto {
+    pub id: String,
+    pub email: String,
+    pub name: String,
+    pub organization: Option<String>,
+    #[serde(default)]
+    pub street: Vec<String>,
+    pub city: String,
+    pub province: Option<String>,
+    pub postal_code: Option<String>,
+    /// The two-letter country code
+    pub country: String,
+    pub voice: Option<String>,
+    pub auth_info: String,
+}
+
+impl<'a> TryFrom<&'a ContactCreateDto> for ContactCreate<'a> {
+    type Error = Error;
+
+    fn try_from(dto: &'a ContactCreateDto) -> Result<Self, Error> {
+        validate_id(&dto.id)?;
+        if !dto.email.contains('@') {
+            return Err(InvalidDto::new("email", "not an email address").into());
+        }
+        let country = dto
+            .country
+            .parse::<Country>()
+            .map_err(|_| InvalidDto::new("country", "unknown country code"))?;
+        if dto.auth_info.is_empty() {
+            return Err(InvalidDto::new("auth_info", "must not be empty").into());
+        }
+
+        let street = dto.street.iter().map(String::as_str).collect::<Vec<_>>();
+        let address = Address::new(
+            &street,
+            &dto.city,
+            dto.province.as_deref(),
+            dto.postal_code.as_deref(),
+            country,
+        );
+        let postal_info = PostalInfo::new(
+            InfoType::International,
+            &dto.name,
+            dto.organization.as_deref(),
+            address,
+        );
+        Ok(ContactCreate::new(
+            &dto.id,
+            &dto.email,
+            postal_info,
+            dto.voice.as_deref().map(Voice::new),
+            &dto.auth_info,
+        ))
+    }
+}
+
+/// A DTO field with a value no command can be made from
+///
+/// Names and IDs with invalid syntax are reported with their own error types instead.
+#[derive(Debug)]
+pub struct InvalidDto {
+    pub field: &'static str,
+    pub reason: &'static str,
+}
+
+impl InvalidDto {
+    fn new(field: &'static str, reason: &'static str) -> Self {
+        Self { field, reason }
+    }
+}
+
+impl fmt::Display for InvalidDto {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid {}: {}", self.field, self.reason)
+    }
+}
+
+impl StdError for InvalidDto {}
+
+impl From<InvalidDto> for crate::Error {
+    fn from(err: InvalidDto) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContactCreateDto, DomainCreateDto, DomainRenewDto, PeriodDto};
+    use crate::contact::ContactCreate;
+    use crate::domain::{DomainContact, DomainCreate, DomainRenew};
+    use crate::tests::assert_serialized;
+
+    #[test]
+    fn domain() {
+        let contact = |contact_type: &str| DomainContact {
+            contact_type: contact_type.to_owned().into(),
+            id: "eppdev-contact-3".into(),
+        };
+        let mut dto = DomainCreateDto {
+            name: "eppdev-1.com".into(),
+            period: PeriodDto::default(),
+            nameservers: None,
+            registrant: Some("eppdev-contact-3".into()),
+            contacts: vec![contact("admin"), contact("tech"), contact("billing")],
+            auth_info: "epP4uthd#v".into(),
+        };
+        let object = DomainCreate::try_from(&dto).unwrap();
+        assert_serialized("request/domain/create.xml", &object);
+
+        dto.name = "-eppdev.com".into();
+        assert!(DomainCreate::try_from(&dto).is_err());
+        dto.name = "eppdev-1.com".into();
+        dto.period = PeriodDto::Years(100);
+        assert!(DomainCreate::try_from(&dto).is_err());
+
+        let dto = DomainRenewDto {
+            name: "eppdev.com".into(),
+            current_expiry: chrono::NaiveDate::from_ymd_opt(2022, 7, 23).unwrap(),
+            period: PeriodDto::Years(1),
+        };
+        let object = DomainRenew::try_from(&dto).unwrap();
+        assert_serialized("request/domain/renew.xml", &object);
+    }
+
+    #[test]
+    fn contact() {
+        let mut dto = ContactCreateDto {
+            id: "eppdev-contact-3".into(),
+            email: "contact@eppdev.net".into(),
+            name: "John Doe".into(),
+            organization: None,
+            street: Vec::new(),
+            city: "Paris".into(),
+            province: None,
+            postal_code: None,
+            country: "FR".into(),
+            voice: None,
+            auth_info: "eppdev-387323".into(),
+        };
+        let object = ContactCreate::try_from(&dto).unwrap();
+        assert_serialized("request/contact/create_minimal.xml", &object);
+
+        dto.country = "XX".into();
+        assert_eq!(
+            ContactCreate::try_from(&dto).unwrap_err().to_string(),
+            "error: invalid country: unknown country code"
+        );
+    }
+}
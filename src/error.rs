@@ -8,18 +8,47 @@ use std::num::TryFromIntError;
 use std::str::Utf8Error;
 use std::string::FromUtf8Error;
 
-use crate::response::ResponseStatus;
+use crate::response::{EppResult, ResponseStatus};
+use crate::retry::RetryHint;
 
 /// Error enum holding the possible error types
 #[derive(Debug)]
 pub enum Error {
     Command(Box<ResponseStatus>),
+    /// The response was successful but did not contain the expected `<resData>`
+    MissingResData,
+    /// The server ended the session or closed the connection
+    ///
+    /// Holds the server's parting result, if it sent one.
+    Closed(Option<Box<EppResult>>),
     Io(std::io::Error),
     Timeout,
     Xml(Box<dyn StdError + Send + Sync>),
     Other(Box<dyn StdError + Send + Sync>),
 }
 
+impl Error {
+    /// The primary result of the error response, if the server rejected the command
+    pub fn result(&self) -> Option<&EppResult> {
+        match self {
+            Self::Command(status) => Some(status.result.primary()),
+            _ => None,
+        }
+    }
+
+    /// When the registry suggests retrying the command, if it rejected it with a hint
+    ///
+    /// The first hint among the results is returned; see [`RetryHint`] for the forms
+    /// recognized.
+    pub fn retry_hint(&self) -> Option<RetryHint> {
+        match self {
+            Self::Command(status) => status.result.iter().find_map(RetryHint::from_result),
+            Self::Closed(Some(result)) => RetryHint::from_result(result),
+            _ => None,
+        }
+    }
+}
+
 impl StdError for Error {}
 
 impl Display for Error {
@@ -28,6 +57,9 @@ impl Display for Error {
             Self::Command(e) => {
                 write!(f, "command error: {}", e.result.message)
             }
+            Self::MissingResData => write!(f, "response did not contain expected resData"),
+            Self::Closed(Some(result)) => write!(f, "session closed: {}", result.message),
+            Self::Closed(None) => write!(f, "session closed"),
             Self::Io(e) => write!(f, "I/O error: {e}"),
             Self::Timeout => write!(f, "timeout"),
             Self::Xml(e) => write!(f, "(de)serialization error: {e}"),
@@ -77,3 +109,16 @@ impl From<TryFromSliceError> for Error {
         Self::Other(e.into())
     }
 }
+
+/// Whether a failed result concerns the fee of the command
+///
+/// Registries report fee mismatches as range or policy errors without a dedicated code, so this
+/// looks for a mention of the fee in the message or reason.
+pub(crate) fn is_fee_result(result: &EppResult) -> bool {
+    let mentions_fee = |text: &str| text.to_ascii_lowercase().contains("fee");
+    mentions_fee(&result.message)
+        || result
+            .ext_value
+            .as_ref()
+            .is_some_and(|ext| mentions_fee(&ext.reason))
+}
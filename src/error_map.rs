@@ -0,0 +1,223 @@
+//! Typed errors from registry-specific result messages
+//!
+//! Result codes are coarse, and registries put the details in free text: a Verisign 2304
+//! response says "Domain status does not allow for operation", another registry rejects a
+//! premium name with 2306 and a `<reason>` mentioning the price. An [`ErrorMapper`] turns such
+//! results into a stable [`RegistryError`], so applications don't have to parse registry
+//! prose. [`PatternMapper`] matches rules against the message and reason text, and comes with
+//! rules for several large registries.
+
+use crate::response::{EppResult, ResultCode};
+use crate::Error;
+
+/// Maps registry results to a [`RegistryError`]
+pub trait ErrorMapper: Send + Sync {
+    /// The kind of error `result` reports, if recognized
+    fn map(&self, result: &EppResult) -> Option<RegistryError>;
+
+    /// The kind of error a command failed with, if the registry rejected it
+    fn classify(&self, err: &Error) -> Option<RegistryError> {
+        self.map(err.result()?)
+    }
+}
+
+/// Registry-independent reasons for a command to be rejected
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum RegistryError {
+    /// The object exists already, for a domain usually because it is registered
+    ObjectExists,
+    ObjectNotFound,
+    /// A status on the object, like `clientUpdateProhibited`, prohibits the operation
+    StatusProhibits,
+    /// The object is referred to by other objects, like a host used as a name server
+    ObjectLinked,
+    /// The authorization information is wrong
+    InvalidAuthInfo,
+    /// The account balance or credit limit does not cover the operation
+    InsufficientFunds,
+    /// The name is reserved or blocked by the registry
+    NameReserved,
+    /// The name is premium and the fee was not acknowledged
+    PremiumName,
+    /// The name is not valid for the TLD
+    InvalidName,
+    /// A transfer is pending for the object
+    PendingTransfer,
+    /// The domain was registered or transferred too recently to be transferred
+    TransferTooSoon,
+    /// Too many commands or sessions
+    RateLimited,
+}
+
+impl RegistryError {
+    /// The error implied by the result code alone, for codes with a single meaning
+    pub fn from_code(code: ResultCode) -> Option<Self> {
+        use ResultCode::*;
+        Some(match code {
+            ObjectExists => Self::ObjectExists,
+            ObjectDoesNotExist => Self::ObjectNotFound,
+            ObjectStatusProhibitsOperation => Self::StatusProhibits,
+            ObjectAssociationProhibitsOperation => Self::ObjectLinked,
+            InvalidAuthorizationInformation => Self::InvalidAuthInfo,
+            BillingFailure => Self::InsufficientFunds,
+            ObjectPendingTransfer => Self::PendingTransfer,
+            SessionLimitExceededServerClosingConnection => Self::RateLimited,
+            _ => return None,
+        })
+    }
+}
+
+/// An [`ErrorMapper`] matching text in the result message and reason
+///
+/// Rules are tried in the order they were added. A rule matches if its pattern occurs in the
+/// `<msg>` or the `<reason>` of the result, compared case-insensitively, and the result has
+/// the rule's code (if it has one). If no rule matches, [`RegistryError::from_code()`] is used.
+#[derive(Clone, Debug, Default)]
+pub struct PatternMapper {
+    rules: Vec<Rule>,
+}
+
+#[derive(Clone, Debug)]
+struct Rule {
+    code: Option<ResultCode>,
+    pattern: String,
+    error: RegistryError,
+}
+
+impl PatternMapper {
+    /// A mapper without rules, only using the result code
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map results containing `pattern` (with the result code `code`, if given) to `error`
+    pub fn rule(mut self, code: Option<ResultCode>, pattern: &str, error: RegistryError) -> Self {
+        self.rules.push(Rule {
+            code,
+            pattern: pattern.to_lowercase(),
+            error,
+        });
+        self
+    }
+
+    /// Add the rules of `other` after the rules of this mapper
+    pub fn extend(mut self, other: Self) -> Self {
+        self.rules.extend(other.rules);
+        self
+    }
+
+    /// Rules for Verisign (.com, .net and others)
+    pub fn verisign() -> Self {
+        use RegistryError::*;
+        Self::new()
+            .rule(None, "status does not allow", StatusProhibits)
+            .rule(None, "within 60 days", TransferTooSoon)
+            .rule(None, "low balance", InsufficientFunds)
+            .rule(None, "reserved", NameReserved)
+            .rule(None, "rate limit", RateLimited)
+    }
+
+    /// Rules for Identity Digital (formerly Donuts and Afilias)
+    pub fn identity_digital() -> Self {
+        use RegistryError::*;
+        Self::new()
+            .rule(None, "premium", PremiumName)
+            .rule(None, "insufficient funds", InsufficientFunds)
+            .rule(None, "blocked", NameReserved)
+            .rule(None, "reserved", NameReserved)
+            .rule(None, "transfer period", TransferTooSoon)
+    }
+
+    /// Rules for CentralNic
+    pub fn centralnic() -> Self {
+        use RegistryError::*;
+        Self::new()
+            .rule(None, "premium", PremiumName)
+            .rule(None, "not available for registration", NameReserved)
+            .rule(None, "insufficient credit", InsufficientFunds)
+            .rule(None, "invalid domain name", InvalidName)
+    }
+
+    /// The rules for all registries above, and generic ones
+    pub fn builtin() -> Self {
+        use RegistryError::*;
+        Self::verisign()
+            .extend(Self::identity_digital())
+            .extend(Self::centralnic())
+            .rule(None, "too many", RateLimited)
+            .rule(
+                Some(ResultCode::ParameterValueSyntaxError),
+                "name",
+                InvalidName,
+            )
+    }
+}
+
+impl ErrorMapper for PatternMapper {
+    fn map(&self, result: &EppResult) -> Option<RegistryError> {
+        let message = result.message.to_lowercase();
+        let reason = result
+            .ext_value
+            .as_ref()
+            .map(|ext| ext.reason.to_lowercase())
+            .unwrap_or_default();
+
+        self.rules
+            .iter()
+            .find(|rule| {
+                rule.code.is_none_or(|code| code == result.code)
+                    && (message.contains(&rule.pattern) || reason.contains(&rule.pattern))
+            })
+            .map(|rule| rule.error)
+            .or_else(|| RegistryError::from_code(result.code))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ErrorMapper, PatternMapper, RegistryError};
+    use crate::response::{EppResult, ExtValue, ResultCode, ResultValue};
+
+    #[test]
+    fn builtin() {
+        let result = |code, message: &str, reason: Option<&str>| EppResult {
+            code,
+            message: message.into(),
+            ext_value: reason.map(|reason| ExtValue {
+                value: ResultValue,
+                reason: reason.into(),
+            }),
+        };
+
+        let mapper = PatternMapper::builtin();
+        assert_eq!(
+            mapper.map(&result(
+                ResultCode::ObjectStatusProhibitsOperation,
+                "Domain status does not allow for operation",
+                None
+            )),
+            Some(RegistryError::StatusProhibits)
+        );
+        assert_eq!(
+            mapper.map(&result(
+                ResultCode::ParameterValuePolicyError,
+                "Parameter value policy error",
+                Some("Premium domain name requires fee extension")
+            )),
+            Some(RegistryError::PremiumName)
+        );
+        assert_eq!(
+            mapper.map(&result(
+                ResultCode::ObjectDoesNotExist,
+                "Object does not exist",
+                None
+            )),
+            Some(RegistryError::ObjectNotFound)
+        );
+        assert_eq!(
+            mapper.map(&result(ResultCode::CommandFailed, "Command failed", None)),
+            None
+        );
+    }
+}
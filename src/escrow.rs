@@ -0,0 +1,423 @@
+//! Registrar data escrow deposits from info responses
+//!
+//! ICANN-accredited registrars deposit their registration data with an escrow agent every
+//! week. The formats follow registry data escrow ([RFC 9022]): either CSV files with one
+//! record per object, or XML `<rdeDomain:domain>` and `<rdeContact:contact>` objects inside an
+//! `<rde:deposit>`. This module renders both from the [`domain::InfoData`] and
+//! [`contact::info::InfoData`] returned by `<info>` commands, so the deposit holds exactly
+//! what the registry has on record.
+//!
+//! The XML objects declare the namespaces they use, so they can be placed in the
+//! `<rde:contents>` of a deposit as they are. Authorization information is never exported.
+//!
+//! [RFC 9022]: https://tools.ietf.org/html/rfc9022
+
+use std::fmt::Write;
+
+use chrono::{DateTime, SecondsFormat, Utc};
+use instant_xml::ToXml;
+
+use crate::contact::{self, InfoType, XMLNS as CONTACT_XMLNS};
+use crate::domain::{self, XMLNS as DOMAIN_XMLNS};
+use crate::Error;
+
+const RDE_DOMAIN_XMLNS: &str = "urn:ietf:params:xml:ns:rdeDomain-1.0";
+const RDE_CONTACT_XMLNS: &str = "urn:ietf:params:xml:ns:rdeContact-1.0";
+
+/// The columns of the records returned by [`domain_csv()`]
+///
+/// Multiple statuses and nameservers are separated by spaces; contacts are listed as
+/// `type:id`, like `admin:sh8013`.
+pub const DOMAIN_CSV_HEADER: &[&str] = &[
+    "name",
+    "roid",
+    "status",
+    "registrant",
+    "contacts",
+    "nameservers",
+    "clID",
+    "crRr",
+    "crDate",
+    "exDate",
+    "upRr",
+    "upDate",
+    "trDate",
+];
+
+/// The columns of the records returned by [`contact_csv()`]
+///
+/// The internationalized postal info is used if the contact has one. Street lines are
+/// separated by newlines.
+pub const CONTACT_CSV_HEADER: &[&str] = &[
+    "id", "roid", "status", "name", "org", "street", "city", "sp", "pc", "cc", "voice", "fax",
+    "email", "clID", "crRr", "crDate", "upRr", "upDate", "trDate",
+];
+
+/// The CSV header line for `columns`, like [`DOMAIN_CSV_HEADER`]
+pub fn csv_header(columns: &[&str]) -> String {
+    csv_record(columns.iter().copied())
+}
+
+/// The CSV record for a domain, with the columns in [`DOMAIN_CSV_HEADER`]
+pub fn domain_csv(data: &domain::InfoData) -> String {
+    let statuses = data.statuses.iter().flatten().map(|s| s.as_str());
+    let contacts = data.contacts.iter().flatten();
+    let contacts = contacts.map(|c| format!("{}:{}", c.contact_type, c.id));
+    let nameservers = data.name_servers().collect::<Vec<_>>();
+    csv_record([
+        data.name.as_str(),
+        data.roid.as_str(),
+        &statuses.collect::<Vec<_>>().join(" "),
+        data.registrant.as_deref().unwrap_or_default(),
+        &contacts.collect::<Vec<_>>().join(" "),
+        &nameservers.join(" "),
+        &data.client_id,
+        data.creator_id.as_deref().unwrap_or_default(),
+        &timestamp(data.created_at),
+        &timestamp(data.expiring_at),
+        data.updater_id.as_deref().unwrap_or_default(),
+        &timestamp(data.updated_at),
+        &timestamp(data.transferred_at),
+    ])
+}
+
+/// The CSV record for a contact, with the columns in [`CONTACT_CSV_HEADER`]
+pub fn contact_csv(data: &contact::info::InfoData) -> String {
+    let statuses = data.statuses.iter().map(|s| s.as_str());
+    let postal_info = data
+        .postal_info_by_type(InfoType::International)
+        .or(data.postal_info.first());
+    let address = postal_info.map(|info| &info.address);
+    let street = address.map(|addr| addr.street.join("\n"));
+    let voice = data.voice.as_ref();
+    let fax = data.fax.as_ref();
+    csv_record([
+        data.id.as_str(),
+        data.roid.as_str(),
+        &statuses.collect::<Vec<_>>().join(" "),
+        postal_info.map_or("", |info| &*info.name),
+        postal_info
+            .and_then(|info| info.organization.as_deref())
+            .unwrap_or_default(),
+        street.as_deref().unwrap_or_default(),
+        address.map_or("", |addr| &*addr.city),
+        address
+            .and_then(|addr| addr.province.as_deref())
+            .unwrap_or_default(),
+        address
+            .and_then(|addr| addr.postal_code.as_deref())
+            .unwrap_or_default(),
+        address.map_or("", |addr| addr.country.alpha2),
+        &phone(voice.map(|v| (&*v.number, v.extension.as_deref()))),
+        &phone(fax.map(|f| (&*f.number, f.extension.as_deref()))),
+        data.email.as_deref().unwrap_or_default(),
+        &data.client_id,
+        &data.creator_id,
+        &timestamp(Some(data.created_at)),
+        data.updater_id.as_deref().unwrap_or_default(),
+        &timestamp(data.updated_at),
+        &timestamp(data.transferred_at),
+    ])
+}
+
+/// The `<rdeDomain:domain>` object for a domain
+pub fn domain_xml(data: &domain::InfoData) -> Result<String, Error> {
+    let hosts = data.name_servers().collect::<Vec<_>>();
+    let domain = RdeDomain {
+        name: &data.name,
+        roid: data.roid.as_str(),
+        statuses: (data.statuses.iter().flatten())
+            .map(|status| DomainStatus { s: status.as_str() })
+            .collect(),
+        registrant: data.registrant.as_deref(),
+        contacts: (data.contacts.iter().flatten())
+            .map(|contact| DomainContact {
+                contact_type: &contact.contact_type,
+                id: &contact.id,
+            })
+            .collect(),
+        ns: (!hosts.is_empty()).then_some(DomainNs { hosts }),
+        client_id: &data.client_id,
+        creator_id: data.creator_id.as_deref(),
+        created_at: data.created_at.map(timestamp_str),
+        expiring_at: data.expiring_at.map(timestamp_str),
+        updater_id: data.updater_id.as_deref(),
+        updated_at: data.updated_at.map(timestamp_str),
+        transferred_at: data.transferred_at.map(timestamp_str),
+    };
+
+    instant_xml::to_string(&domain).map_err(|e| Error::Xml(e.into()))
+}
+
+/// The `<rdeContact:contact>` object for a contact
+pub fn contact_xml(data: &contact::info::InfoData) -> Result<String, Error> {
+    let contact = RdeContact {
+        id: &data.id,
+        roid: data.roid.as_str(),
+        statuses: (data.statuses.iter())
+            .map(|status| ContactStatus { s: status.as_str() })
+            .collect(),
+        postal_info: (data.postal_info.iter())
+            .map(|info| ContactPostalInfo {
+                info_type: match info.info_type {
+                    InfoType::Local => "loc",
+                    InfoType::International => "int",
+                },
+                name: &info.name,
+                organization: info.organization.as_deref(),
+                address: ContactAddress {
+                    street: info.address.street.iter().map(|s| &**s).collect(),
+                    city: &info.address.city,
+                    province: info.address.province.as_deref(),
+                    postal_code: info.address.postal_code.as_deref(),
+                    country: info.address.country.alpha2,
+                },
+            })
+            .collect(),
+        voice: data.voice.as_ref().map(|voice| ContactVoice {
+            extension: voice.extension.as_deref(),
+            number: &voice.number,
+        }),
+        fax: data.fax.as_ref().map(|fax| ContactFax {
+            extension: fax.extension.as_deref(),
+            number: &fax.number,
+        }),
+        email: data.email.as_deref(),
+        client_id: &data.client_id,
+        creator_id: &data.creator_id,
+        created_at: timestamp_str(data.created_at),
+        updater_id: data.updater_id.as_deref(),
+        updated_at: data.updated_at.map(timestamp_str),
+        transferred_at: data.transferred_at.map(timestamp_str),
+    };
+
+    instant_xml::to_string(&contact).map_err(|e| Error::Xml(e.into()))
+}
+
+#[derive(ToXml)]
+#[xml(rename = "domain", ns(RDE_DOMAIN_XMLNS, domain = DOMAIN_XMLNS))]
+struct RdeDomain<'a> {
+    name: &'a str,
+    roid: &'a str,
+    statuses: Vec<DomainStatus<'a>>,
+    registrant: Option<&'a str>,
+    contacts: Vec<DomainContact<'a>>,
+    ns: Option<DomainNs<'a>>,
+    #[xml(rename = "clID")]
+    client_id: &'a str,
+    #[xml(rename = "crRr")]
+    creator_id: Option<&'a str>,
+    #[xml(rename = "crDate")]
+    created_at: Option<String>,
+    #[xml(rename = "exDate")]
+    expiring_at: Option<String>,
+    #[xml(rename = "upRr")]
+    updater_id: Option<&'a str>,
+    #[xml(rename = "upDate")]
+    updated_at: Option<String>,
+    #[xml(rename = "trDate")]
+    transferred_at: Option<String>,
+}
+
+#[derive(ToXml)]
+#[xml(rename = "status", ns(RDE_DOMAIN_XMLNS))]
+struct DomainStatus<'a> {
+    #[xml(attribute)]
+    s: &'a str,
+}
+
+#[derive(ToXml)]
+#[xml(rename = "contact", ns(RDE_DOMAIN_XMLNS))]
+struct DomainContact<'a> {
+    #[xml(attribute, rename = "type")]
+    contact_type: &'a str,
+    #[xml(direct)]
+    id: &'a str,
+}
+
+#[derive(ToXml)]
+#[xml(rename = "ns", ns(RDE_DOMAIN_XMLNS, domain = DOMAIN_XMLNS))]
+struct DomainNs<'a> {
+    #[xml(rename = "hostObj", ns(DOMAIN_XMLNS))]
+    hosts: Vec<&'a str>,
+}
+
+#[derive(ToXml)]
+#[xml(rename = "contact", ns(RDE_CONTACT_XMLNS))]
+struct RdeContact<'a> {
+    id: &'a str,
+    roid: &'a str,
+    statuses: Vec<ContactStatus<'a>>,
+    postal_info: Vec<ContactPostalInfo<'a>>,
+    voice: Option<ContactVoice<'a>>,
+    fax: Option<ContactFax<'a>>,
+    email: Option<&'a str>,
+    #[xml(rename = "clID")]
+    client_id: &'a str,
+    #[xml(rename = "crRr")]
+    creator_id: &'a str,
+    #[xml(rename = "crDate")]
+    created_at: String,
+    #[xml(rename = "upRr")]
+    updater_id: Option<&'a str>,
+    #[xml(rename = "upDate")]
+    updated_at: Option<String>,
+    #[xml(rename = "trDate")]
+    transferred_at: Option<String>,
+}
+
+#[derive(ToXml)]
+#[xml(rename = "status", ns(RDE_CONTACT_XMLNS))]
+struct ContactStatus<'a> {
+    #[xml(attribute)]
+    s: &'a str,
+}
+
+#[derive(ToXml)]
+#[xml(rename = "postalInfo", ns(RDE_CONTACT_XMLNS))]
+struct ContactPostalInfo<'a> {
+    #[xml(attribute, rename = "type")]
+    info_type: &'a str,
+    #[xml(ns(CONTACT_XMLNS))]
+    name: &'a str,
+    #[xml(rename = "org", ns(CONTACT_XMLNS))]
+    organization: Option<&'a str>,
+    address: ContactAddress<'a>,
+}
+
+#[derive(ToXml)]
+#[xml(rename = "addr", ns(CONTACT_XMLNS))]
+struct ContactAddress<'a> {
+    street: Vec<&'a str>,
+    city: &'a str,
+    #[xml(rename = "sp")]
+    province: Option<&'a str>,
+    #[xml(rename = "pc")]
+    postal_code: Option<&'a str>,
+    #[xml(rename = "cc")]
+    country: &'a str,
+}
+
+#[derive(ToXml)]
+#[xml(rename = "voice", ns(RDE_CONTACT_XMLNS))]
+struct ContactVoice<'a> {
+    #[xml(attribute, rename = "x")]
+    extension: Option<&'a str>,
+    #[xml(direct)]
+    number: &'a str,
+}
+
+#[derive(ToXml)]
+#[xml(rename = "fax", ns(RDE_CONTACT_XMLNS))]
+struct ContactFax<'a> {
+    #[xml(attribute, rename = "x")]
+    extension: Option<&'a str>,
+    #[xml(direct)]
+    number: &'a str,
+}
+
+// A CSV line (terminated by CRLF, as in RFC 4180) with the given fields
+fn csv_record<'a>(fields: impl IntoIterator<Item = &'a str>) -> String {
+    let mut record = String::new();
+    for (i, field) in fields.into_iter().enumerate() {
+        if i > 0 {
+            record.push(',');
+        }
+
+        match field.contains([',', '"', '\r', '\n']) {
+            true => {
+                let _ = write!(record, "\"{}\"", field.replace('"', "\"\""));
+            }
+            false => record.push_str(field),
+        }
+    }
+    record.push_str("\r\n");
+    record
+}
+
+fn phone(phone: Option<(&str, Option<&str>)>) -> String {
+    match phone {
+        Some((number, Some(extension))) => format!("{number}x{extension}"),
+        Some((number, None)) => number.to_owned(),
+        None => String::new(),
+    }
+}
+
+fn timestamp(dt: Option<DateTime<Utc>>) -> String {
+    dt.map(timestamp_str).unwrap_or_default()
+}
+
+fn timestamp_str(dt: DateTime<Utc>) -> String {
+    dt.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{contact_csv, contact_xml, csv_header, domain_csv, domain_xml, DOMAIN_CSV_HEADER};
+    use crate::contact::ContactInfo;
+    use crate::domain::DomainInfo;
+    use crate::tests::response_from_file;
+
+    #[test]
+    fn domain() {
+        let rsp = response_from_file::<DomainInfo>("response/domain/info.xml");
+        let data = rsp.res_data().unwrap();
+
+        assert_eq!(
+            csv_header(DOMAIN_CSV_HEADER),
+            "name,roid,status,registrant,contacts,nameservers,clID,crRr,crDate,exDate,upRr,upDate,trDate\r\n"
+        );
+        assert_eq!(
+            domain_csv(data),
+            "eppdev-1.com,125899511_DOMAIN_COM-VRSN,ok clientTransferProhibited,eppdev-contact-2,\
+             admin:eppdev-contact-2 tech:eppdev-contact-2 billing:eppdev-contact-2,\
+             ns1.eppdev-1.com ns2.eppdev-1.com,eppdev,SYSTEM,2021-07-23T15:31:20Z,\
+             2023-07-23T15:31:20Z,SYSTEM,2021-07-23T15:31:21Z,\r\n"
+        );
+        assert_eq!(
+            domain_xml(data).unwrap(),
+            "<domain xmlns=\"urn:ietf:params:xml:ns:rdeDomain-1.0\" \
+             xmlns:domain=\"urn:ietf:params:xml:ns:domain-1.0\">\
+             <name>eppdev-1.com</name><roid>125899511_DOMAIN_COM-VRSN</roid>\
+             <status s=\"ok\"></status><status s=\"clientTransferProhibited\"></status>\
+             <registrant>eppdev-contact-2</registrant>\
+             <contact type=\"admin\">eppdev-contact-2</contact>\
+             <contact type=\"tech\">eppdev-contact-2</contact>\
+             <contact type=\"billing\">eppdev-contact-2</contact>\
+             <ns><domain:hostObj>ns1.eppdev-1.com</domain:hostObj>\
+             <domain:hostObj>ns2.eppdev-1.com</domain:hostObj></ns>\
+             <clID>eppdev</clID><crRr>SYSTEM</crRr><crDate>2021-07-23T15:31:20Z</crDate>\
+             <exDate>2023-07-23T15:31:20Z</exDate><upRr>SYSTEM</upRr>\
+             <upDate>2021-07-23T15:31:21Z</upDate></domain>"
+        );
+    }
+
+    #[test]
+    fn contact() {
+        let rsp = response_from_file::<ContactInfo>("response/contact/info.xml");
+        let data = rsp.res_data().unwrap();
+
+        assert_eq!(
+            contact_csv(data),
+            "eppdev-contact-3,UNDEF-ROID,ok,John Doe,Acme Widgets,\"58\nOrchid Road\",Paris,\
+             Paris,392374,FR,+33.47237942x123,+33.86698799x243,contact@eppdev.net,eppdev,\
+             SYSTEM,2021-07-23T13:09:09Z,SYSTEM,2021-07-23T13:09:09Z,\r\n"
+        );
+
+        assert_eq!(
+            contact_xml(data).unwrap(),
+            "<contact xmlns=\"urn:ietf:params:xml:ns:rdeContact-1.0\">\
+             <id>eppdev-contact-3</id><roid>UNDEF-ROID</roid><status s=\"ok\"></status>\
+             <postalInfo type=\"loc\">\
+             <name xmlns=\"urn:ietf:params:xml:ns:contact-1.0\">John Doe</name>\
+             <org xmlns=\"urn:ietf:params:xml:ns:contact-1.0\">Acme Widgets</org>\
+             <addr xmlns=\"urn:ietf:params:xml:ns:contact-1.0\">\
+             <street>58</street><street>Orchid Road</street><city>Paris</city><sp>Paris</sp>\
+             <pc>392374</pc><cc>FR</cc></addr></postalInfo>\
+             <voice x=\"123\">+33.47237942</voice><fax x=\"243\">+33.86698799</fax>\
+             <email>contact@eppdev.net</email><clID>eppdev</clID><crRr>SYSTEM</crRr>\
+             <crDate>2021-07-23T13:09:09Z</crDate><upRr>SYSTEM</upRr>\
+             <upDate>2021-07-23T13:09:09Z</upDate></contact>"
+        );
+    }
+}
@@ -1,5 +1,8 @@
-use crate::common::NoExtension;
-use crate::domain::{DomainCheck, DomainCreate, DomainRenew, DomainTransfer, DomainUpdate};
+use crate::common::{Amount, NoExtension};
+use crate::domain::{
+    DomainCheck, DomainCreate, DomainInfo, DomainRenew, DomainTransfer, DomainUpdate,
+};
+use crate::price_class::{PriceClass, PriceClasses};
 use crate::request::{Extension, Transaction};
 use instant_xml::{Deserializer, Error, FromXml, Id, Kind, Serializer, ToXml};
 use std::fmt::Write;
@@ -31,6 +34,30 @@ impl Extension for ChargeExtension {
 
 impl<'a> Transaction<ChargeExtension> for DomainCheck<'a> {}
 
+/// Response-only extension reading the charges of a domain from `<charge:infData>`
+#[derive(Debug, Eq, PartialEq)]
+pub struct ChargeInfoExtension;
+
+crate::impl_extension! {
+    #[response_only]
+    impl Extension for ChargeInfoExtension {
+        type Response = InfoData;
+        commands: DomainInfo<'_>;
+    }
+}
+
+/// Response-only extension reading the charges of a transfer from `<charge:trnData>`
+#[derive(Debug, Eq, PartialEq)]
+pub struct ChargeTransferExtension;
+
+crate::impl_extension! {
+    #[response_only]
+    impl Extension for ChargeTransferExtension {
+        type Response = TransferData;
+        commands: DomainTransfer<'_>;
+    }
+}
+
 #[derive(Debug, FromXml)]
 #[xml(rename = "chkData", ns(XMLNS))]
 pub struct CheckData {
@@ -49,6 +76,24 @@ pub struct ChargeCd {
     pub set: ChargeSet,
 }
 
+/// The `<charge:infData>` on a domain info response, for premium domains
+#[derive(Debug, FromXml)]
+#[xml(rename = "infData", ns(XMLNS))]
+pub struct InfoData {
+    /// One set per charge category and type
+    #[xml(rename = "set")]
+    pub sets: Vec<ChargeSet>,
+}
+
+/// The `<charge:trnData>` on a domain transfer response, for premium domains
+#[derive(Debug, FromXml)]
+#[xml(rename = "trnData", ns(XMLNS))]
+pub struct TransferData {
+    /// One set per charge category and type
+    #[xml(rename = "set")]
+    pub sets: Vec<ChargeSet>,
+}
+
 #[derive(Debug, Clone, FromXml)]
 #[xml(rename = "set", ns(XMLNS))]
 pub struct ChargeSet {
@@ -75,6 +120,19 @@ pub struct ChargeCategory {
     pub value: String,
 }
 
+impl ChargeCategory {
+    /// The typed [`PriceClass`] of the category
+    ///
+    /// A mapping for the registry's category `name` (like `PIR-BBBB`) takes precedence over
+    /// the generic `premium` value.
+    pub fn price_class(&self, classes: &PriceClasses) -> PriceClass {
+        match classes.classify(&self.name) {
+            PriceClass::Custom(_) => classes.classify(&self.value),
+            class => class,
+        }
+    }
+}
+
 #[derive(Debug, Clone, FromXml)]
 #[xml(rename = "amount", ns(XMLNS))]
 pub struct ChargeAmount {
@@ -184,7 +242,7 @@ pub struct AgreementAmount {
 
     /// Numeric amount inside <charge:amount>.
     #[xml(direct)]
-    pub amount: f64,
+    pub amount: Amount,
 }
 
 impl Agreement {
@@ -195,7 +253,7 @@ impl Agreement {
         category_name: Option<String>,
         category_value: String, // "premium" / "standard"
         charge_type: String,    // e.g. "price"
-        amount: f64,
+        amount: Amount,
     ) -> Self {
         Self {
             set: AgreementSet {
@@ -217,7 +275,7 @@ impl Agreement {
         category_name: Option<String>,
         category_value: String,
         charge_type: String,
-        amount: f64,
+        amount: impl Into<Amount>,
     ) -> Self {
         Self::new_with_command(
             "create",
@@ -225,7 +283,7 @@ impl Agreement {
             category_name,
             category_value,
             charge_type,
-            amount,
+            amount.into(),
         )
     }
 
@@ -233,7 +291,7 @@ impl Agreement {
         category_name: Option<String>,
         category_value: String,
         charge_type: String,
-        amount: f64,
+        amount: impl Into<Amount>,
     ) -> Self {
         Self::new_with_command(
             "renew",
@@ -241,7 +299,7 @@ impl Agreement {
             category_name,
             category_value,
             charge_type,
-            amount,
+            amount.into(),
         )
     }
 
@@ -249,7 +307,7 @@ impl Agreement {
         category_name: Option<String>,
         category_value: String,
         charge_type: String,
-        amount: f64,
+        amount: impl Into<Amount>,
     ) -> Self {
         Self::new_with_command(
             "transfer",
@@ -257,7 +315,7 @@ impl Agreement {
             category_name,
             category_value,
             charge_type,
-            amount,
+            amount.into(),
         )
     }
 
@@ -265,7 +323,7 @@ impl Agreement {
         category_name: Option<String>,
         category_value: String,
         charge_type: String,
-        amount: f64,
+        amount: impl Into<Amount>,
     ) -> Self {
         Self::new_with_command(
             "update",
@@ -273,7 +331,7 @@ impl Agreement {
             category_name,
             category_value,
             charge_type,
-            amount,
+            amount.into(),
         )
     }
 }
@@ -281,6 +339,10 @@ impl Agreement {
 impl Extension for Agreement {
     // Ignore the charge extension response
     type Response = NoExtension;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
 }
 
 impl<'a> Transaction<Agreement> for DomainCreate<'a> {}
@@ -290,13 +352,14 @@ impl<'a> Transaction<Agreement> for DomainUpdate<'a> {}
 
 #[cfg(test)]
 mod tests {
-    use super::{Agreement, XMLNS};
-    use crate::client::RequestData;
+    use super::{Agreement, ChargeCommand, ChargeInfoExtension, ChargeTransferExtension, XMLNS};
     use crate::domain::update::{DomainChangeInfo, DomainUpdate};
+    use crate::domain::{DomainInfo, DomainTransfer};
     use crate::extensions::composite::CompositeExtWithFirstResponse;
     use crate::extensions::rgp::request::{RgpRestoreRequest, Update as RgpUpdate};
-    use crate::request::{Command, CommandWrapper, Extension, Transaction};
-    use crate::tests::CLTRID;
+    use crate::price_class::{PremiumTier, PriceClass, PriceClasses};
+    use crate::request::{Command, CommandWrapper, Extension, RequestData, Transaction};
+    use crate::tests::{response_from_file_with_ext, CLTRID};
     use crate::xml;
 
     fn serialize_request<'c, 'e, Cmd, Ext>(req: impl Into<RequestData<'c, 'e, Cmd, Ext>>) -> String
@@ -333,6 +396,7 @@ mod tests {
         assert!(xml.contains(r#"command="update" name="restore""#));
         assert!(xml.contains(">premium</category>"));
         assert!(xml.contains(">price</type>"));
+        assert!(xml.contains(">80.00</amount>"));
     }
 
     #[test]
@@ -359,4 +423,44 @@ mod tests {
         assert!(xml.contains(r#"op="request""#));
         assert!(xml.contains(r#"command="update" name="restore""#));
     }
+
+    #[test]
+    fn info_response() {
+        let object = response_from_file_with_ext::<DomainInfo, ChargeInfoExtension>(
+            "response/extensions/charge_info.xml",
+        );
+        let data = object.extension().unwrap();
+
+        assert_eq!(data.sets.len(), 1);
+        let set = &data.sets[0];
+        assert_eq!(set.category.name, "PIR-BBBB");
+        assert_eq!(set.category.value, "premium");
+        let classes = PriceClasses::default();
+        assert_eq!(
+            set.category.price_class(&classes),
+            PriceClass::Premium(None)
+        );
+        let classes = classes.with_label("PIR-BBBB", PriceClass::Premium(Some(PremiumTier::B)));
+        assert_eq!(
+            set.category.price_class(&classes),
+            PriceClass::Premium(Some(PremiumTier::B))
+        );
+        assert_eq!(set.charge_type, "price");
+        assert_eq!(set.amounts.len(), 3);
+        assert_eq!(set.amounts[0].command, ChargeCommand::Create);
+        assert_eq!(set.amounts[0].amount, 100.0);
+        assert_eq!(set.amounts[2].name.as_deref(), Some("restore"));
+    }
+
+    #[test]
+    fn transfer_response() {
+        let object = response_from_file_with_ext::<DomainTransfer, ChargeTransferExtension>(
+            "response/extensions/charge_transfer.xml",
+        );
+        let data = object.extension().unwrap();
+
+        assert_eq!(data.sets.len(), 1);
+        assert_eq!(data.sets[0].amounts[0].command, ChargeCommand::Transfer);
+        assert_eq!(data.sets[0].amounts[0].amount, 50.0);
+    }
 }
@@ -1,10 +1,35 @@
-use instant_xml::{Serializer, ToXml};
+use instant_xml::{Accumulate, Deserializer, FromXml, Id, Kind, Serializer, ToXml};
 use std::fmt::Debug;
 
+use crate::balance::Balance;
 use crate::common::NoExtension;
-use crate::domain::{DomainCheck, DomainCreate, DomainUpdate};
+use crate::contact::{ContactCreate, ContactDelete, ContactUpdate};
+use crate::domain::{
+    DomainCheck, DomainCreate, DomainDelete, DomainInfo, DomainRenew, DomainTransfer, DomainUpdate,
+};
+use crate::host::{HostCreate, HostDelete, HostUpdate};
 use crate::request::{Extension, Transaction};
 
+// Implement `Transaction` for every command composite extensions can be attached to: the
+// domain queries and all transform commands
+macro_rules! transactions {
+    (<$($param:ident),+> $ext:ty) => {
+        impl<$($param: Extension),+> Transaction<$ext> for DomainCheck<'_> {}
+        impl<$($param: Extension),+> Transaction<$ext> for DomainInfo<'_> {}
+        impl<$($param: Extension),+> Transaction<$ext> for DomainCreate<'_> {}
+        impl<$($param: Extension),+> Transaction<$ext> for DomainDelete<'_> {}
+        impl<$($param: Extension),+> Transaction<$ext> for DomainRenew<'_> {}
+        impl<$($param: Extension),+> Transaction<$ext> for DomainTransfer<'_> {}
+        impl<$($param: Extension),+> Transaction<$ext> for DomainUpdate<'_> {}
+        impl<$($param: Extension),+> Transaction<$ext> for ContactCreate<'_> {}
+        impl<$($param: Extension),+> Transaction<$ext> for ContactDelete<'_> {}
+        impl<$($param: Extension),+> Transaction<$ext> for ContactUpdate<'_> {}
+        impl<$($param: Extension),+> Transaction<$ext> for HostCreate<'_> {}
+        impl<$($param: Extension),+> Transaction<$ext> for HostDelete<'_> {}
+        impl<$($param: Extension),+> Transaction<$ext> for HostUpdate<'_> {}
+    };
+}
+
 /// A composite payload for <extension> that renders multiple child extensions.
 #[derive(Debug)]
 pub struct CompositeExt<E1: Extension, E2: Extension> {
@@ -32,10 +57,20 @@ impl<E1: Extension, E2: Extension> Extension for CompositeExt<E1, E2> {
     const DO_SEND: bool = true;
 
     type Response = NoExtension;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        let mut namespaces = Vec::new();
+        if self.first.do_send() {
+            namespaces.extend(self.first.namespaces()?);
+        }
+        if self.second.do_send() {
+            namespaces.extend(self.second.namespaces()?);
+        }
+        Some(namespaces)
+    }
 }
 
-impl<E1: Extension, E2: Extension> Transaction<CompositeExt<E1, E2>> for DomainUpdate<'_> {}
-impl<'a, E1: Extension, E2: Extension> Transaction<CompositeExt<E1, E2>> for DomainCreate<'a> {}
+transactions!(<E1, E2> CompositeExt<E1, E2>);
 
 /// Composite extension that renders two child extensions but uses the second
 /// extension's response type.
@@ -65,13 +100,25 @@ impl<E1: Extension, E2: Extension> Extension for CompositeExtWithSecondResponse<
     const DO_SEND: bool = true;
 
     type Response = E2::Response;
-}
 
-impl<'a, E1: Extension, E2: Extension> Transaction<CompositeExtWithSecondResponse<E1, E2>>
-    for DomainCheck<'a>
-{
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        let mut namespaces = Vec::new();
+        if self.first.do_send() {
+            namespaces.extend(self.first.namespaces()?);
+        }
+        if self.second.do_send() {
+            namespaces.extend(self.second.namespaces()?);
+        }
+        Some(namespaces)
+    }
+
+    fn balance(response: &E2::Response) -> Option<Balance> {
+        E2::balance(response)
+    }
 }
 
+transactions!(<E1, E2> CompositeExtWithSecondResponse<E1, E2>);
+
 /// Composite extension that renders two child extensions but uses the first
 /// extension's response type.
 #[derive(Debug)]
@@ -100,19 +147,212 @@ impl<E1: Extension, E2: Extension> Extension for CompositeExtWithFirstResponse<E
     const DO_SEND: bool = true;
 
     type Response = E1::Response;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        let mut namespaces = Vec::new();
+        if self.first.do_send() {
+            namespaces.extend(self.first.namespaces()?);
+        }
+        if self.second.do_send() {
+            namespaces.extend(self.second.namespaces()?);
+        }
+        Some(namespaces)
+    }
+
+    fn balance(response: &E1::Response) -> Option<Balance> {
+        E1::balance(response)
+    }
+}
+
+transactions!(<E1, E2> CompositeExtWithFirstResponse<E1, E2>);
+
+/// Composite extension rendering any number of child extensions, given as a tuple
+///
+/// The extensions are serialized in tuple order. The response holds the response of each
+/// child extension in the same position, if the server sent one:
+///
+/// ```ignore
+/// let ext = Composite((launch, fee, idn));
+/// let response = client.transact((&create, &ext), "create-1").await?;
+/// let CompositeResponse((_, fee, _)) = response.extension().unwrap();
+/// ```
+///
+/// Tuples of two to six extensions are supported.
+#[derive(Debug)]
+pub struct Composite<T>(pub T);
+
+/// The responses to the extensions of a [`Composite`], as a tuple of options
+#[derive(Debug)]
+pub struct CompositeResponse<T>(pub T);
+
+// Whether `R` matches any element, like `NoExtension` does. Catch-all responses only take
+// elements that no other response in the composite claims.
+fn is_catch_all<'xml, R: FromXml<'xml>>() -> bool {
+    R::matches(Id { ns: "", name: "" }, None)
 }
 
-impl<'a, E1: Extension, E2: Extension> Transaction<CompositeExtWithFirstResponse<E1, E2>>
-    for DomainCheck<'a>
-{
+fn deserialize_into<'xml, R: FromXml<'xml>>(
+    into: &mut Option<R>,
+    field: &'static str,
+    deserializer: &mut Deserializer<'_, 'xml>,
+) -> Result<(), instant_xml::Error> {
+    if into.is_some() {
+        return Err(instant_xml::Error::DuplicateValue(field));
+    }
+
+    let mut acc = R::Accumulator::default();
+    R::deserialize(&mut acc, field, deserializer)?;
+    *into = Some(acc.try_done(field)?);
+    Ok(())
 }
 
-impl<'a, E1: Extension, E2: Extension> Transaction<CompositeExtWithFirstResponse<E1, E2>>
-    for DomainCreate<'a>
-{
+macro_rules! composite {
+    ($($ext:ident $idx:tt),+) => {
+        impl<$($ext: Extension),+> ToXml for Composite<($($ext,)+)> {
+            fn serialize<W: std::fmt::Write + ?Sized>(
+                &self,
+                _id: Option<Id<'_>>,
+                serializer: &mut Serializer<W>,
+            ) -> Result<(), instant_xml::Error> {
+                $(
+                    if self.0.$idx.do_send() {
+                        self.0.$idx.serialize(None, serializer)?;
+                    }
+                )+
+                Ok(())
+            }
+        }
+
+        impl<$($ext: Extension),+> Extension for Composite<($($ext,)+)> {
+            type Response = CompositeResponse<($(Option<$ext::Response>,)+)>;
+
+            fn namespaces(&self) -> Option<Vec<&str>> {
+                let mut namespaces = Vec::new();
+                $(
+                    if self.0.$idx.do_send() {
+                        namespaces.extend(self.0.$idx.namespaces()?);
+                    }
+                )+
+                Some(namespaces)
+            }
+
+            // The first extension whose response reports a balance
+            fn balance(response: &Self::Response) -> Option<Balance> {
+                None$(.or_else(|| response.0.$idx.as_ref().and_then($ext::balance)))+
+            }
+        }
+
+        transactions!(<$($ext),+> Composite<($($ext,)+)>);
+
+        impl<'xml, $($ext: FromXml<'xml>),+> FromXml<'xml> for CompositeResponse<($(Option<$ext>,)+)> {
+            fn matches(id: Id<'_>, _field: Option<Id<'_>>) -> bool {
+                $($ext::matches(id, None))||+
+            }
+
+            fn deserialize<'cx>(
+                into: &mut Self::Accumulator,
+                field: &'static str,
+                deserializer: &mut Deserializer<'cx, 'xml>,
+            ) -> Result<(), instant_xml::Error> {
+                let id = deserializer.parent();
+                let responses = &mut into
+                    .get_or_insert_with(|| CompositeResponse(($(None::<$ext>,)+)))
+                    .0;
+
+                $(
+                    if !is_catch_all::<$ext>() && $ext::matches(id, None) {
+                        return deserialize_into(&mut responses.$idx, field, deserializer);
+                    }
+                )+
+                $(
+                    if $ext::matches(id, None) {
+                        return deserialize_into(&mut responses.$idx, field, deserializer);
+                    }
+                )+
+
+                deserializer.ignore()
+            }
+
+            type Accumulator = Option<Self>;
+            const KIND: Kind = Kind::Element;
+        }
+    };
 }
 
-impl<E1: Extension, E2: Extension> Transaction<CompositeExtWithFirstResponse<E1, E2>>
-    for DomainUpdate<'_>
-{
+composite!(E1 0, E2 1);
+composite!(E1 0, E2 1, E3 2);
+composite!(E1 0, E2 1, E3 2, E4 3);
+composite!(E1 0, E2 1, E3 2, E4 3, E5 4);
+composite!(E1 0, E2 1, E3 2, E4 3, E5 4, E6 5);
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{Composite, CompositeExt, CompositeResponse};
+    use crate::domain::{DomainCreate, DomainRenew, Period, PeriodLength, ONE_YEAR};
+    use crate::extensions::{charge, fee, idn_variant, launch};
+    use crate::request::CommandWrapper;
+    use crate::tests::{assert_serialized, response_from_file_with_ext, CLTRID};
+    use crate::xml;
+
+    #[test]
+    fn create() {
+        let variants = [idn_variant::Variant::new(
+            "xn--fsqu00a.example",
+            Some(idn_variant::VariantState::Active),
+        )];
+        let object = DomainCreate::new(
+            "xn--fsq270a.example",
+            Period::Years(PeriodLength::new(1).unwrap()),
+            None,
+            None,
+            "2fooBAR",
+            None,
+        );
+
+        let ext = Composite((
+            launch::Create::new(launch::Phase::new(launch::PhaseType::Sunrise, None)),
+            fee::Create::new(Some("USD"), 100.0),
+            idn_variant::Create::new(Some("zh-cn"), &variants),
+        ));
+
+        assert_serialized("request/extensions/composite_create.xml", (&object, &ext));
+    }
+
+    #[test]
+    fn create_response() {
+        type Ext<'a> = Composite<(launch::Create<'a>, fee::Create<'a>)>;
+        let object =
+            response_from_file_with_ext::<DomainCreate, Ext>("response/extensions/fee_create.xml");
+
+        let CompositeResponse((launch, fee)) = object.extension().unwrap();
+        assert!(launch.is_none());
+        let fee = fee.as_ref().unwrap();
+        assert_eq!(fee.currency, "USD");
+        assert_eq!(fee.fees[0].amount, 100.0);
+    }
+
+    #[test]
+    fn renew() {
+        let object = DomainRenew::new(
+            "eppdev.com",
+            NaiveDate::from_ymd_opt(2025, 7, 23).unwrap(),
+            ONE_YEAR,
+        );
+        let ext = CompositeExt {
+            first: fee::Renew::new(Some("USD"), 100.0),
+            second: charge::Agreement::renew(
+                Some("PIR-BBBB".to_owned()),
+                "premium".to_owned(),
+                "price".to_owned(),
+                100.0,
+            ),
+        };
+
+        let xml = xml::serialize(CommandWrapper::new(&object, Some(&ext), CLTRID)).unwrap();
+        let fee = xml.find(fee::XMLNS).unwrap();
+        let charge = xml.find(charge::XMLNS).unwrap();
+        assert!(fee < charge);
+    }
 }
@@ -11,7 +11,7 @@ use crate::common::NoExtension;
 use crate::domain::update::DomainUpdate;
 use crate::request::{Extension, Transaction};
 
-use super::namestore::NameStore;
+use super::namestore::{self, NameStore};
 
 pub const XMLNS: &str = "http://www.verisign.com/epp/sync-1.0";
 
@@ -19,15 +19,23 @@ impl Transaction<Update> for DomainUpdate<'_> {}
 
 impl Extension for Update {
     type Response = NoExtension;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
 }
 
 impl Transaction<UpdateWithNameStore<'_>> for DomainUpdate<'_> {}
 
 impl Extension for UpdateWithNameStore<'_> {
     type Response = NameStore<'static>;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS, namestore::XMLNS])
+    }
 }
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub struct GMonthDay {
     pub month: u8,
     pub day: u8,
@@ -0,0 +1,84 @@
+//! Mapping for the DK Hostmaster extension, covering changes of registrant
+//!
+//! DK Hostmaster (.dk) changes the registrant of a domain with a domain `<update>`, but the new
+//! registrant has to accept the change and its terms first. The update carries the
+//! [`OwnerChange`] extension with the order confirmation token the new registrant got for
+//! accepting, and the registry answers with a tracking number for the pending change. The
+//! outcome is reported with a pending action poll message.
+
+use instant_xml::{FromXml, ToXml};
+
+use crate::domain::DomainUpdate;
+use crate::request::{Extension, Transaction};
+
+pub const XMLNS: &str = "urn:dkhm:params:xml:ns:dkhm-4.3";
+
+impl<'a> Transaction<OwnerChange<'a>> for DomainUpdate<'a> {}
+
+impl Extension for OwnerChange<'_> {
+    type Response = TrackingNumber;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+}
+
+// Request
+
+/// Extension confirming a change of registrant with a domain `<update>`
+#[derive(Debug, ToXml)]
+#[xml(rename = "orderconfirmationToken", ns(XMLNS))]
+pub struct OwnerChange<'a> {
+    #[xml(direct)]
+    token: &'a str,
+}
+
+impl<'a> OwnerChange<'a> {
+    /// Confirm the change with the order confirmation `token` of the new registrant
+    pub fn new(token: &'a str) -> Self {
+        Self { token }
+    }
+}
+
+// Response
+
+/// The `<dkhm:trackingNo>` of a pending change of registrant
+#[derive(Debug, FromXml)]
+#[xml(rename = "trackingNo", ns(XMLNS))]
+pub struct TrackingNumber {
+    #[xml(direct)]
+    pub value: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OwnerChange;
+    use crate::domain::update::DomainChangeInfo;
+    use crate::domain::DomainUpdate;
+    use crate::response::ResultCode;
+    use crate::tests::{assert_serialized, response_from_file_with_ext};
+
+    #[test]
+    fn owner_change() {
+        let mut object = DomainUpdate::new("eppdev.dk");
+        object.info(DomainChangeInfo {
+            registrant: Some("DKHM1-DK"),
+            auth_info: None,
+        });
+        let ext = OwnerChange::new("ae2b1c9c2dc6b6f7e5b5d7f1a8c74f53");
+        assert_serialized("request/extensions/dkhm_owner_change.xml", (&object, &ext));
+    }
+
+    #[test]
+    fn owner_change_pending() {
+        let object = response_from_file_with_ext::<DomainUpdate, OwnerChange>(
+            "response/extensions/dkhm_owner_change.xml",
+        );
+
+        assert_eq!(
+            object.result.code,
+            ResultCode::CommandCompletedSuccessfullyActionPending
+        );
+        assert_eq!(object.extension().unwrap().value, "1-1234567");
+    }
+}
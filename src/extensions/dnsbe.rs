@@ -0,0 +1,188 @@
+//! Mapping for the DNS Belgium extension, covering the quarantine lifecycle
+//!
+//! DNS Belgium (.be) runs the same quarantine lifecycle as EURid (see
+//! [`eurid`](super::eurid)) under its own `dnsbe` namespace. A quarantined domain is brought
+//! back with a domain `<update>` carrying the [`Reactivate`] extension, and the domain info
+//! response reports whether a domain is quarantined.
+
+use chrono::{DateTime, Utc};
+use instant_xml::{FromXml, ToXml};
+
+use crate::common::NoExtension;
+use crate::domain::{DomainInfo, DomainUpdate};
+use crate::request::{Extension, Transaction};
+
+pub const XMLNS: &str = "http://www.dns.be/xml/epp/dnsbe-1.0";
+
+impl Transaction<Reactivate> for DomainUpdate<'_> {}
+
+impl Extension for Reactivate {
+    type Response = NoExtension;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+}
+
+impl Transaction<QuarantineInfo> for DomainInfo<'_> {}
+
+impl Extension for QuarantineInfo {
+    const DO_SEND: bool = false;
+    type Response = InfoData;
+}
+
+// Request
+
+/// Extension to reactivate a domain from quarantine
+///
+/// Combine with a domain `<update>` that doesn't change anything else.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct Reactivate;
+
+impl ToXml for Reactivate {
+    fn serialize<W: std::fmt::Write + ?Sized>(
+        &self,
+        _field: Option<instant_xml::Id<'_>>,
+        serializer: &mut instant_xml::Serializer<W>,
+    ) -> Result<(), instant_xml::Error> {
+        Ext {
+            update: Update {
+                domain: UpdateDomain {
+                    reactivate: ReactivateMarker,
+                },
+            },
+        }
+        .serialize(None, serializer)
+    }
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "ext", ns(XMLNS))]
+struct Ext {
+    update: Update,
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "update", ns(XMLNS))]
+struct Update {
+    domain: UpdateDomain,
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "domain", ns(XMLNS))]
+struct UpdateDomain {
+    reactivate: ReactivateMarker,
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "reactivate", ns(XMLNS))]
+struct ReactivateMarker;
+
+/// Response-only extension to read the DNS Belgium data from a domain info response
+#[derive(Debug, Eq, PartialEq)]
+pub struct QuarantineInfo;
+
+impl ToXml for QuarantineInfo {
+    fn serialize<W: std::fmt::Write + ?Sized>(
+        &self,
+        _field: Option<instant_xml::Id<'_>>,
+        _serializer: &mut instant_xml::Serializer<W>,
+    ) -> Result<(), instant_xml::Error> {
+        Ok(())
+    }
+
+    fn present(&self) -> bool {
+        false
+    }
+}
+
+// Response
+
+/// Type that represents the `<dnsbe:ext>` tag for domain info responses
+#[derive(Debug, FromXml)]
+#[xml(rename = "ext", ns(XMLNS))]
+pub struct InfoData {
+    #[xml(rename = "infData")]
+    info: InfoDomain,
+}
+
+impl InfoData {
+    /// The quarantine data of the domain
+    pub fn domain(&self) -> &DomainData {
+        &self.info.domain
+    }
+}
+
+#[derive(Debug, FromXml)]
+#[xml(rename = "infData", ns(XMLNS))]
+struct InfoDomain {
+    domain: DomainData,
+}
+
+/// Type that represents the `<dnsbe:domain>` tag in a domain info response
+#[derive(Debug, FromXml)]
+#[xml(rename = "domain", ns(XMLNS))]
+pub struct DomainData {
+    /// Whether the domain is on hold, so it can't be changed or deleted
+    #[xml(
+        rename = "onhold",
+        deserialize_with = "crate::scalar::deserialize_bool"
+    )]
+    pub on_hold: bool,
+    /// Whether the domain is in quarantine
+    #[xml(deserialize_with = "crate::scalar::deserialize_bool")]
+    pub quarantined: bool,
+    /// When a quarantined domain becomes available for registration by anyone
+    #[xml(
+        rename = "availableDate",
+        deserialize_with = "crate::datetime::deserialize_datetime_utc_option"
+    )]
+    pub available_date: Option<DateTime<Utc>>,
+    /// When the domain was, or is scheduled to be, deleted
+    #[xml(
+        rename = "deletionDate",
+        deserialize_with = "crate::datetime::deserialize_datetime_utc_option"
+    )]
+    pub deletion_date: Option<DateTime<Utc>>,
+}
+
+impl DomainData {
+    /// Whether the domain can still be reactivated at the given time
+    pub fn reactivatable(&self, now: DateTime<Utc>) -> bool {
+        self.quarantined && self.available_date.is_none_or(|date| now < date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::tests::{assert_serialized, response_from_file_with_ext};
+
+    #[test]
+    fn reactivate() {
+        let object = DomainUpdate::new("dnsbe.be");
+        assert_serialized(
+            "request/extensions/dnsbe_reactivate.xml",
+            (&object, &Reactivate),
+        );
+    }
+
+    #[test]
+    fn info_quarantined() {
+        let object = response_from_file_with_ext::<DomainInfo, QuarantineInfo>(
+            "response/extensions/dnsbe_info_quarantined.xml",
+        );
+        let domain = object.extension().unwrap().domain();
+
+        assert!(!domain.on_hold);
+        assert!(domain.quarantined);
+        assert_eq!(
+            domain.available_date,
+            Some(Utc.with_ymd_and_hms(2024, 6, 10, 22, 0, 0).unwrap())
+        );
+        assert_eq!(domain.deletion_date, None);
+        assert!(domain.reactivatable(Utc.with_ymd_and_hms(2024, 5, 20, 0, 0, 0).unwrap()));
+    }
+}
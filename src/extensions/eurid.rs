@@ -0,0 +1,231 @@
+//! Mapping for the EURid domain extension, covering the quarantine lifecycle and trades
+//!
+//! EURid doesn't implement RGP. Deleted .eu domains enter a 40-day quarantine instead, during
+//! which only the last registrant can get them back, by having their registrar send a domain
+//! `<update>` with the [`Reactivate`] extension. The domain info response reports whether a
+//! domain is quarantined and when it becomes available to the public again.
+//!
+//! A change of registrant ("trade") is a domain `<transfer>` request naming the new registrant
+//! and contacts in the [`Trade`] extension, sent by the registrar taking over the domain or the
+//! current one.
+//!
+//! DNS Belgium runs the same quarantine lifecycle for .be under its own namespace, see
+//! [`dnsbe`](super::dnsbe).
+
+use chrono::{DateTime, Utc};
+use instant_xml::{FromXml, ToXml};
+
+use crate::common::NoExtension;
+use crate::domain::{DomainInfo, DomainTransfer, DomainUpdate};
+use crate::request::{Extension, Transaction};
+
+pub const XMLNS: &str = "http://www.eurid.eu/xml/epp/domain-ext-2.4";
+
+impl Transaction<Reactivate> for DomainUpdate<'_> {}
+
+impl Extension for Reactivate {
+    type Response = NoExtension;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+}
+
+impl<'a> Transaction<Trade<'a>> for DomainTransfer<'a> {}
+
+impl Extension for Trade<'_> {
+    type Response = NoExtension;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+}
+
+impl Transaction<QuarantineInfo> for DomainInfo<'_> {}
+
+impl Extension for QuarantineInfo {
+    const DO_SEND: bool = false;
+    type Response = InfoData;
+}
+
+// Request
+
+/// Extension to reactivate a domain from quarantine
+///
+/// Combine with a domain `<update>` that doesn't change anything else.
+#[derive(Debug, Default, Eq, PartialEq)]
+pub struct Reactivate;
+
+impl ToXml for Reactivate {
+    fn serialize<W: std::fmt::Write + ?Sized>(
+        &self,
+        _field: Option<instant_xml::Id<'_>>,
+        serializer: &mut instant_xml::Serializer<W>,
+    ) -> Result<(), instant_xml::Error> {
+        UpdateData {
+            request: Request {
+                reactivate: ReactivateMarker,
+            },
+        }
+        .serialize(None, serializer)
+    }
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "update", ns(XMLNS))]
+struct UpdateData {
+    request: Request,
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "request", ns(XMLNS))]
+struct Request {
+    reactivate: ReactivateMarker,
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "reactivate", ns(XMLNS))]
+struct ReactivateMarker;
+
+/// Extension to change the registrant of a domain with a transfer request
+///
+/// Combine with [`DomainTransfer::new()`], using the authorization code of the domain.
+#[derive(Debug, ToXml)]
+#[xml(rename = "transfer", ns(XMLNS))]
+pub struct Trade<'a> {
+    request: TradeRequest<'a>,
+}
+
+impl<'a> Trade<'a> {
+    pub fn new(registrant: &'a str) -> Self {
+        Self {
+            request: TradeRequest {
+                registrant,
+                contacts: Vec::new(),
+            },
+        }
+    }
+
+    /// Set the contact of type `contact_type` (like `billing` or `tech`) to `id`
+    pub fn contact(mut self, contact_type: &'a str, id: &'a str) -> Self {
+        self.request.contacts.push(Contact { contact_type, id });
+        self
+    }
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "request", ns(XMLNS))]
+struct TradeRequest<'a> {
+    registrant: &'a str,
+    contacts: Vec<Contact<'a>>,
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "contact", ns(XMLNS))]
+struct Contact<'a> {
+    #[xml(attribute, rename = "type")]
+    contact_type: &'a str,
+    #[xml(direct)]
+    id: &'a str,
+}
+
+/// Response-only extension to read the EURid data from a domain info response
+#[derive(Debug, Eq, PartialEq)]
+pub struct QuarantineInfo;
+
+impl ToXml for QuarantineInfo {
+    fn serialize<W: std::fmt::Write + ?Sized>(
+        &self,
+        _field: Option<instant_xml::Id<'_>>,
+        _serializer: &mut instant_xml::Serializer<W>,
+    ) -> Result<(), instant_xml::Error> {
+        Ok(())
+    }
+
+    fn present(&self) -> bool {
+        false
+    }
+}
+
+// Response
+
+/// Type that represents the `<domain-ext:infData>` tag for domain info responses
+#[derive(Debug, FromXml)]
+#[xml(rename = "infData", ns(XMLNS))]
+pub struct InfoData {
+    /// Whether the domain is on hold, so it can't be changed or deleted
+    #[xml(
+        rename = "onHold",
+        deserialize_with = "crate::scalar::deserialize_bool"
+    )]
+    pub on_hold: bool,
+    /// Whether the domain is in quarantine
+    #[xml(deserialize_with = "crate::scalar::deserialize_bool")]
+    pub quarantined: bool,
+    /// When a quarantined domain becomes available for registration by anyone
+    #[xml(
+        rename = "availableDate",
+        deserialize_with = "crate::datetime::deserialize_datetime_utc_option"
+    )]
+    pub available_date: Option<DateTime<Utc>>,
+    /// When the domain was, or is scheduled to be, deleted
+    #[xml(
+        rename = "deletionDate",
+        deserialize_with = "crate::datetime::deserialize_datetime_utc_option"
+    )]
+    pub deletion_date: Option<DateTime<Utc>>,
+}
+
+impl InfoData {
+    /// Whether the domain can still be reactivated at the given time
+    pub fn reactivatable(&self, now: DateTime<Utc>) -> bool {
+        self.quarantined && self.available_date.is_none_or(|date| now < date)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::tests::{assert_serialized, response_from_file_with_ext};
+
+    #[test]
+    fn reactivate() {
+        let object = DomainUpdate::new("eurid.eu");
+        assert_serialized(
+            "request/extensions/eurid_reactivate.xml",
+            (&object, &Reactivate),
+        );
+    }
+
+    #[test]
+    fn trade() {
+        let object = DomainTransfer::new("eurid.eu", None, "XXXX-2MRN-MRAP-MXVR");
+        let trade = Trade::new("c160")
+            .contact("billing", "c10")
+            .contact("tech", "c159");
+        assert_serialized("request/extensions/eurid_trade.xml", (&object, &trade));
+    }
+
+    #[test]
+    fn info_quarantined() {
+        let object = response_from_file_with_ext::<DomainInfo, QuarantineInfo>(
+            "response/extensions/eurid_info_quarantined.xml",
+        );
+        let ext = object.extension().unwrap();
+
+        assert!(!ext.on_hold);
+        assert!(ext.quarantined);
+        assert_eq!(
+            ext.available_date,
+            Some(Utc.with_ymd_and_hms(2024, 6, 10, 22, 0, 0).unwrap())
+        );
+        assert_eq!(
+            ext.deletion_date,
+            Some(Utc.with_ymd_and_hms(2024, 5, 1, 22, 0, 0).unwrap())
+        );
+        assert!(ext.reactivatable(Utc.with_ymd_and_hms(2024, 5, 20, 0, 0, 0).unwrap()));
+        assert!(!ext.reactivatable(Utc.with_ymd_and_hms(2024, 6, 11, 0, 0, 0).unwrap()));
+    }
+}
@@ -1,6 +1,13 @@
+use std::collections::HashMap;
+use std::fmt;
+
 use instant_xml::{FromXml, ToXml};
 
-use crate::domain::{DomainCheck, DomainCreate, DomainRenew, DomainTransfer, DomainUpdate};
+use crate::common::Amount;
+use crate::domain::{
+    normalize_name, DomainCheck, DomainCreate, DomainRenew, DomainTransfer, DomainUpdate,
+};
+use crate::price_class::PriceClass;
 use crate::request::{Extension, Transaction};
 
 /// RFC 8748 namespace
@@ -24,6 +31,10 @@ pub struct Check<'a> {
 
 impl<'a> Extension for Check<'a> {
     type Response = CheckData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
 }
 
 // Tie this extension to <domain:check> so (&DomainCheck, &Check) works.
@@ -68,6 +79,15 @@ pub enum PeriodUnit {
     Months,
 }
 
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.unit {
+            PeriodUnit::Years => write!(f, "{}y", self.value),
+            PeriodUnit::Months => write!(f, "{}m", self.value),
+        }
+    }
+}
+
 impl Period {
     pub fn years(v: u16) -> Self {
         Self {
@@ -149,6 +169,74 @@ pub struct CheckDomainData {
     pub commands: Vec<CommandResp>,
 }
 
+impl CheckDomainData {
+    /// The typed [`PriceClass`] of the `<fee:class>`, if given
+    ///
+    /// Use [`PriceClasses::classify()`](crate::price_class::PriceClasses::classify) for
+    /// registry-specific labels.
+    pub fn price_class(&self) -> Option<PriceClass> {
+        self.class.as_deref().map(PriceClass::from_label)
+    }
+}
+
+impl CheckData {
+    /// The fees for `name`, compared after [normalization](crate::domain::normalize_name)
+    pub fn get(&self, name: &str) -> Option<&CheckDomainData> {
+        let name = normalize_name(name);
+        self.list
+            .iter()
+            .find(|cd| normalize_name(&cd.obj_id) == name)
+    }
+
+    /// The names of the objects the server marked as available
+    pub fn available_names(&self) -> impl Iterator<Item = &str> {
+        self.list
+            .iter()
+            .filter(|cd| cd.avail == Some(true))
+            .map(|cd| cd.obj_id.as_str())
+    }
+
+    /// The fees per object, keyed by their [normalized](crate::domain::normalize_name) names
+    pub fn into_map(self) -> HashMap<String, CheckDomainData> {
+        self.list
+            .into_iter()
+            .map(|cd| (normalize_name(&cd.obj_id), cd))
+            .collect()
+    }
+}
+
+/// One line per object, listing the price of each command, like
+/// `example.com [premium]: create 1y 100.00 USD, renew 1y 100.00 USD`
+impl fmt::Display for CheckData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, cd) in self.list.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            write!(f, "{}", cd.obj_id)?;
+            if let Some(class) = &cd.class {
+                write!(f, " [{class}]")?;
+            }
+            if cd.avail == Some(false) {
+                write!(f, " (unavailable)")?;
+            }
+
+            for (j, command) in cd.commands.iter().enumerate() {
+                write!(f, "{}{}", if j == 0 { ": " } else { ", " }, command.name)?;
+                if let Some(period) = &command.period {
+                    write!(f, " {period}")?;
+                }
+                match &command.reason {
+                    Some(reason) if command.fees.is_empty() => write!(f, " ({reason})")?,
+                    _ => write!(f, " {:.2} {}", command.total(), self.currency)?,
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
 /// <fee:command name="create" standard="1">…</fee:command>
 #[derive(Debug, FromXml)]
 #[xml(rename = "command", ns(XMLNS))]
@@ -183,6 +271,15 @@ pub struct CommandResp {
     pub reason: Option<String>,
 }
 
+impl CommandResp {
+    /// The sum of all fees and credits for the command
+    pub fn total(&self) -> f64 {
+        let fees = self.fees.iter().map(|fee| fee.amount).sum::<f64>();
+        let credits = self.credits.iter().map(|credit| credit.amount).sum::<f64>();
+        fees + credits
+    }
+}
+
 #[derive(Debug, FromXml)]
 #[xml(rename = "fee", ns(XMLNS))]
 pub struct Fee {
@@ -209,6 +306,18 @@ pub struct Credit {
     pub amount: f64,
 }
 
+/// Request-side <fee:credit> for create, renew, transfer and update.
+#[derive(Debug, ToXml)]
+#[xml(rename = "credit", ns(XMLNS))]
+pub struct CreditReq<'a> {
+    #[xml(attribute)]
+    pub description: Option<&'a str>,
+
+    /// The credit amount, which is negative
+    #[xml(direct)]
+    pub amount: Amount,
+}
+
 //
 // REQUEST SIDE: <extension><fee:create>…</fee:create></extension>
 //
@@ -220,9 +329,13 @@ pub struct Create<'a> {
     #[xml(rename = "currency")]
     pub currency: Option<&'a str>,
 
-    /// Single <fee:fee> with the expected amount
+    /// One or more <fee:fee> elements with the expected amounts
     #[xml(rename = "fee")]
-    pub fee: CreateFee<'a>,
+    pub fees: Vec<CreateFee<'a>>,
+
+    /// Optional <fee:credit> elements acknowledging expected credits
+    #[xml(rename = "credit")]
+    pub credits: Vec<CreditReq<'a>>,
 }
 
 /// Request-side <fee:fee> for create.
@@ -240,22 +353,32 @@ pub struct CreateFee<'a> {
     pub grace_period: Option<&'a str>, // ISO 8601 duration
 
     #[xml(direct)]
-    pub amount: f64,
+    pub amount: Amount,
 }
 
 impl<'a> Create<'a> {
     /// Helper: "currency + period + price" for premium create.
-    pub fn new(currency: Option<&'a str>, amount: f64) -> Self {
+    pub fn new(currency: Option<&'a str>, amount: impl Into<Amount>) -> Self {
         Create {
             currency,
-            fee: CreateFee {
+            fees: vec![CreateFee {
                 description: None,
                 refundable: None,
                 grace_period: None,
-                amount,
-            },
+                amount: amount.into(),
+            }],
+            credits: Vec::new(),
         }
     }
+
+    /// Acknowledge an expected credit, given as a negative amount
+    pub fn with_credit(mut self, amount: impl Into<Amount>) -> Self {
+        self.credits.push(CreditReq {
+            description: None,
+            amount: amount.into(),
+        });
+        self
+    }
 }
 
 #[derive(Debug, FromXml)]
@@ -272,6 +395,10 @@ pub struct CreateData {
 
 impl<'a> Extension for Create<'a> {
     type Response = CreateData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
 }
 
 // Tie this extension to <domain:create> so (&DomainCreate, &Create) works.
@@ -288,9 +415,13 @@ pub struct Renew<'a> {
     #[xml(rename = "currency")]
     pub currency: Option<&'a str>,
 
-    /// Single <fee:fee> with the expected amount
+    /// One or more <fee:fee> elements with the expected amounts
     #[xml(rename = "fee")]
-    pub fee: RenewFee<'a>,
+    pub fees: Vec<RenewFee<'a>>,
+
+    /// Optional <fee:credit> elements acknowledging expected credits
+    #[xml(rename = "credit")]
+    pub credits: Vec<CreditReq<'a>>,
 }
 
 /// Request-side <fee:fee> for create.
@@ -308,22 +439,32 @@ pub struct RenewFee<'a> {
     pub grace_period: Option<&'a str>, // ISO 8601 duration
 
     #[xml(direct)]
-    pub amount: f64,
+    pub amount: Amount,
 }
 
 impl<'a> Renew<'a> {
     /// Helper: "currency + period + price" for premium create.
-    pub fn new(currency: Option<&'a str>, amount: f64) -> Self {
+    pub fn new(currency: Option<&'a str>, amount: impl Into<Amount>) -> Self {
         Renew {
             currency,
-            fee: RenewFee {
+            fees: vec![RenewFee {
                 description: None,
                 refundable: None,
                 grace_period: None,
-                amount,
-            },
+                amount: amount.into(),
+            }],
+            credits: Vec::new(),
         }
     }
+
+    /// Acknowledge an expected credit, given as a negative amount
+    pub fn with_credit(mut self, amount: impl Into<Amount>) -> Self {
+        self.credits.push(CreditReq {
+            description: None,
+            amount: amount.into(),
+        });
+        self
+    }
 }
 
 #[derive(Debug, FromXml)]
@@ -340,6 +481,10 @@ pub struct RenewData {
 
 impl<'a> Extension for Renew<'a> {
     type Response = RenewData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
 }
 
 impl<'a> Transaction<Renew<'a>> for DomainRenew<'a> {}
@@ -355,9 +500,13 @@ pub struct Transfer<'a> {
     #[xml(rename = "currency")]
     pub currency: Option<&'a str>,
 
-    /// Single <fee:fee> with the expected amount
+    /// One or more <fee:fee> elements with the expected amounts
     #[xml(rename = "fee")]
-    pub fee: TransferFee<'a>,
+    pub fees: Vec<TransferFee<'a>>,
+
+    /// Optional <fee:credit> elements acknowledging expected credits
+    #[xml(rename = "credit")]
+    pub credits: Vec<CreditReq<'a>>,
 }
 
 /// Request-side <fee:fee> for transfer.
@@ -375,21 +524,31 @@ pub struct TransferFee<'a> {
     pub grace_period: Option<&'a str>,
 
     #[xml(direct)]
-    pub amount: f64,
+    pub amount: Amount,
 }
 
 impl<'a> Transfer<'a> {
-    pub fn new(currency: Option<&'a str>, amount: f64) -> Self {
+    pub fn new(currency: Option<&'a str>, amount: impl Into<Amount>) -> Self {
         Transfer {
             currency,
-            fee: TransferFee {
+            fees: vec![TransferFee {
                 description: None,
                 refundable: None,
                 grace_period: None,
-                amount,
-            },
+                amount: amount.into(),
+            }],
+            credits: Vec::new(),
         }
     }
+
+    /// Acknowledge an expected credit, given as a negative amount
+    pub fn with_credit(mut self, amount: impl Into<Amount>) -> Self {
+        self.credits.push(CreditReq {
+            description: None,
+            amount: amount.into(),
+        });
+        self
+    }
 }
 
 #[derive(Debug, FromXml)]
@@ -406,6 +565,10 @@ pub struct TransferData {
 
 impl<'a> Extension for Transfer<'a> {
     type Response = TransferData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
 }
 
 impl<'a> Transaction<Transfer<'a>> for DomainTransfer<'a> {}
@@ -421,9 +584,13 @@ pub struct Update<'a> {
     #[xml(rename = "currency")]
     pub currency: Option<&'a str>,
 
-    /// Single <fee:fee> with the expected amount
+    /// One or more <fee:fee> elements with the expected amounts
     #[xml(rename = "fee")]
-    pub fee: UpdateFee<'a>,
+    pub fees: Vec<UpdateFee<'a>>,
+
+    /// Optional <fee:credit> elements acknowledging expected credits
+    #[xml(rename = "credit")]
+    pub credits: Vec<CreditReq<'a>>,
 }
 
 /// Request-side <fee:fee> for update.
@@ -440,23 +607,33 @@ pub struct UpdateFee<'a> {
     pub grace_period: Option<&'a str>,
 
     #[xml(direct)]
-    pub amount: f64,
+    pub amount: Amount,
 }
 
 impl<'a> Update<'a> {
-    pub fn new(currency: Option<&'a str>, amount: f64) -> Self {
+    pub fn new(currency: Option<&'a str>, amount: impl Into<Amount>) -> Self {
         Self {
             currency,
-            fee: UpdateFee {
+            fees: vec![UpdateFee {
                 description: None,
                 refundable: None,
                 grace_period: None,
-                amount,
-            },
+                amount: amount.into(),
+            }],
+            credits: Vec::new(),
         }
     }
 
-    pub fn restore(currency: Option<&'a str>, amount: f64) -> Self {
+    /// Acknowledge an expected credit, given as a negative amount
+    pub fn with_credit(mut self, amount: impl Into<Amount>) -> Self {
+        self.credits.push(CreditReq {
+            description: None,
+            amount: amount.into(),
+        });
+        self
+    }
+
+    pub fn restore(currency: Option<&'a str>, amount: impl Into<Amount>) -> Self {
         Self::new(currency, amount)
     }
 }
@@ -475,19 +652,22 @@ pub struct UpdateData {
 
 impl<'a> Extension for Update<'a> {
     type Response = UpdateData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
 }
 
 impl<'a> Transaction<Update<'a>> for DomainUpdate<'a> {}
 
 #[cfg(test)]
 mod tests {
-    use super::{Check, Update, XMLNS};
-    use crate::client::RequestData;
+    use super::{Check, Transfer, Update, XMLNS};
     use crate::domain::update::{DomainChangeInfo, DomainUpdate};
-    use crate::domain::DomainCheck;
+    use crate::domain::{DomainCheck, DomainTransfer};
     use crate::extensions::composite::CompositeExtWithFirstResponse;
     use crate::extensions::rgp::request::{RgpRestoreRequest, Update as RgpUpdate};
-    use crate::request::{Command, CommandWrapper, Extension, Transaction};
+    use crate::request::{Command, CommandWrapper, Extension, RequestData, Transaction};
     use crate::response::ResultCode;
     use crate::tests::{response_from_file_with_ext, CLTRID, SUCCESS_MSG, SVTRID};
     use crate::xml;
@@ -532,7 +712,17 @@ mod tests {
 
         assert!(xml.contains(&format!(r#"<update xmlns="{}">"#, XMLNS)));
         assert!(xml.contains("<currency>USD</currency>"));
-        assert!(xml.contains("<fee>80</fee>") || xml.contains("<fee>80.0</fee>"));
+        assert!(xml.contains("<fee>80.00</fee>"));
+    }
+
+    #[test]
+    fn transfer_serializes_fees_and_credits() {
+        let object = DomainTransfer::new("eppdev.com", None, "epP4uthd#v");
+        let ext = Transfer::new(Some("USD"), 10.0).with_credit(-5.0);
+
+        let xml = serialize_request((&object, &ext));
+
+        assert!(xml.contains("<fee>10.00</fee><credit>-5.00</credit>"));
     }
 
     #[test]
@@ -552,7 +742,7 @@ mod tests {
 
         assert!(fee_idx < rgp_idx);
         assert!(xml.contains(r#"op="request""#));
-        assert!(xml.contains("<fee>80</fee>") || xml.contains("<fee>80.0</fee>"));
+        assert!(xml.contains("<fee>80.00</fee>"));
     }
 
     #[test]
@@ -570,4 +760,20 @@ mod tests {
         assert_eq!(object.tr_ids.client_tr_id.unwrap(), CLTRID);
         assert_eq!(object.tr_ids.server_tr_id, SVTRID);
     }
+
+    #[test]
+    fn check_response_display() {
+        let object =
+            response_from_file_with_ext::<DomainCheck, Check>("response/extensions/fee_check.xml");
+
+        assert_eq!(
+            object.res_data().unwrap().to_string(),
+            "example.com: available\nexample.net: unavailable (In use)"
+        );
+        assert_eq!(
+            object.extension().unwrap().to_string(),
+            "example.com [premium]: create 1y 100.00 USD, renew 1y 80.00 USD\n\
+             example.net (unavailable): create (Domain is not available)"
+        );
+    }
 }
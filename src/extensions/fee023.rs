@@ -5,9 +5,12 @@
 
 use instant_xml::{FromXml, Id, ToXml};
 
+use crate::balance::{Balance, ReportsBalance};
+use crate::common::Amount;
 use crate::domain::{
     DomainCheck, DomainCreate, DomainDelete, DomainRenew, DomainTransfer, DomainUpdate,
 };
+use crate::price_class::PriceClass;
 use crate::request::{Extension, Transaction};
 
 /// fee-0.23 namespace (pre-RFC8748)
@@ -113,6 +116,10 @@ pub struct Check<'a> {
 
 impl<'a> Extension for Check<'a> {
     type Response = CheckData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
 }
 
 impl<'a> Transaction<Check<'a>> for DomainCheck<'a> {}
@@ -240,6 +247,16 @@ pub struct CommandResp {
     pub reason: Option<Reason>,
 }
 
+impl CommandResp {
+    /// The typed [`PriceClass`] of the `<fee:class>`, if given
+    ///
+    /// Use [`PriceClasses::classify()`](crate::price_class::PriceClasses::classify) for
+    /// registry-specific labels.
+    pub fn price_class(&self) -> Option<PriceClass> {
+        self.class.as_deref().map(PriceClass::from_label)
+    }
+}
+
 // -------------------------------------------------------------------------------------------
 // REQUEST SIDE: <extension><fee:create|renew|transfer|update>…</fee:...></extension>
 // -------------------------------------------------------------------------------------------
@@ -314,7 +331,7 @@ pub struct FeeReq<'a> {
     pub applied: Option<&'a str>,
 
     #[xml(direct)]
-    pub amount: f64,
+    pub amount: Amount,
 }
 
 #[derive(Debug, ToXml)]
@@ -324,11 +341,11 @@ pub struct CreditReq<'a> {
     pub description: Option<&'a str>,
 
     #[xml(direct)]
-    pub amount: f64,
+    pub amount: Amount,
 }
 
 impl<'a> Create<'a> {
-    pub fn new(currency: Option<&'a str>, amount: f64) -> Self {
+    pub fn new(currency: Option<&'a str>, amount: impl Into<Amount>) -> Self {
         Self {
             currency,
             fees: vec![FeeReq {
@@ -336,7 +353,7 @@ impl<'a> Create<'a> {
                 refundable: None,
                 grace_period: None,
                 applied: None,
-                amount,
+                amount: amount.into(),
             }],
             credits: vec![],
         }
@@ -344,7 +361,7 @@ impl<'a> Create<'a> {
 }
 
 impl<'a> Renew<'a> {
-    pub fn new(currency: Option<&'a str>, amount: f64) -> Self {
+    pub fn new(currency: Option<&'a str>, amount: impl Into<Amount>) -> Self {
         Self {
             currency,
             fees: vec![FeeReq {
@@ -352,7 +369,7 @@ impl<'a> Renew<'a> {
                 refundable: None,
                 grace_period: None,
                 applied: None,
-                amount,
+                amount: amount.into(),
             }],
             credits: vec![],
         }
@@ -360,7 +377,7 @@ impl<'a> Renew<'a> {
 }
 
 impl<'a> Transfer<'a> {
-    pub fn new(currency: Option<&'a str>, amount: f64) -> Self {
+    pub fn new(currency: Option<&'a str>, amount: impl Into<Amount>) -> Self {
         Self {
             currency,
             fees: vec![FeeReq {
@@ -368,7 +385,7 @@ impl<'a> Transfer<'a> {
                 refundable: None,
                 grace_period: None,
                 applied: None,
-                amount,
+                amount: amount.into(),
             }],
             credits: vec![],
         }
@@ -376,7 +393,7 @@ impl<'a> Transfer<'a> {
 }
 
 impl<'a> Update<'a> {
-    pub fn new(currency: Option<&'a str>, amount: f64) -> Self {
+    pub fn new(currency: Option<&'a str>, amount: impl Into<Amount>) -> Self {
         Self {
             currency,
             fees: vec![FeeReq {
@@ -384,13 +401,13 @@ impl<'a> Update<'a> {
                 refundable: None,
                 grace_period: None,
                 applied: None,
-                amount,
+                amount: amount.into(),
             }],
             credits: vec![],
         }
     }
 
-    pub fn restore(currency: Option<&'a str>, amount: f64) -> Self {
+    pub fn restore(currency: Option<&'a str>, amount: impl Into<Amount>) -> Self {
         Self::new(currency, amount)
     }
 }
@@ -414,10 +431,16 @@ pub struct CreateData {
     #[xml(rename = "credit")]
     pub credits: Vec<Credit>,
 
-    #[xml(rename = "balance")]
+    #[xml(
+        rename = "balance",
+        deserialize_with = "crate::scalar::deserialize_number_option"
+    )]
     pub balance: Option<f64>,
 
-    #[xml(rename = "creditLimit")]
+    #[xml(
+        rename = "creditLimit",
+        deserialize_with = "crate::scalar::deserialize_number_option"
+    )]
     pub credit_limit: Option<f64>,
 }
 
@@ -436,10 +459,16 @@ pub struct RenewData {
     #[xml(rename = "credit")]
     pub credits: Vec<Credit>,
 
-    #[xml(rename = "balance")]
+    #[xml(
+        rename = "balance",
+        deserialize_with = "crate::scalar::deserialize_number_option"
+    )]
     pub balance: Option<f64>,
 
-    #[xml(rename = "creditLimit")]
+    #[xml(
+        rename = "creditLimit",
+        deserialize_with = "crate::scalar::deserialize_number_option"
+    )]
     pub credit_limit: Option<f64>,
 }
 
@@ -458,10 +487,16 @@ pub struct TransferData {
     #[xml(rename = "credit")]
     pub credits: Vec<Credit>,
 
-    #[xml(rename = "balance")]
+    #[xml(
+        rename = "balance",
+        deserialize_with = "crate::scalar::deserialize_number_option"
+    )]
     pub balance: Option<f64>,
 
-    #[xml(rename = "creditLimit")]
+    #[xml(
+        rename = "creditLimit",
+        deserialize_with = "crate::scalar::deserialize_number_option"
+    )]
     pub credit_limit: Option<f64>,
 }
 
@@ -480,10 +515,16 @@ pub struct UpdateData {
     #[xml(rename = "credit")]
     pub credits: Vec<Credit>,
 
-    #[xml(rename = "balance")]
+    #[xml(
+        rename = "balance",
+        deserialize_with = "crate::scalar::deserialize_number_option"
+    )]
     pub balance: Option<f64>,
 
-    #[xml(rename = "creditLimit")]
+    #[xml(
+        rename = "creditLimit",
+        deserialize_with = "crate::scalar::deserialize_number_option"
+    )]
     pub credit_limit: Option<f64>,
 }
 
@@ -502,24 +543,62 @@ pub struct DeleteData {
     #[xml(rename = "credit")]
     pub credits: Vec<Credit>,
 
-    #[xml(rename = "balance")]
+    #[xml(
+        rename = "balance",
+        deserialize_with = "crate::scalar::deserialize_number_option"
+    )]
     pub balance: Option<f64>,
 
-    #[xml(rename = "creditLimit")]
+    #[xml(
+        rename = "creditLimit",
+        deserialize_with = "crate::scalar::deserialize_number_option"
+    )]
     pub credit_limit: Option<f64>,
 }
 
 impl<'a> Extension for Create<'a> {
     type Response = CreateData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+
+    fn balance(response: &CreateData) -> Option<Balance> {
+        response.balance()
+    }
 }
 impl<'a> Extension for Renew<'a> {
     type Response = RenewData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+
+    fn balance(response: &RenewData) -> Option<Balance> {
+        response.balance()
+    }
 }
 impl<'a> Extension for Transfer<'a> {
     type Response = TransferData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+
+    fn balance(response: &TransferData) -> Option<Balance> {
+        response.balance()
+    }
 }
 impl<'a> Extension for Update<'a> {
     type Response = UpdateData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+
+    fn balance(response: &UpdateData) -> Option<Balance> {
+        response.balance()
+    }
 }
 
 impl<'a> Transaction<Create<'a>> for DomainCreate<'a> {}
@@ -551,6 +630,10 @@ impl ToXml for DeleteExtension {
 impl Extension for DeleteExtension {
     const DO_SEND: bool = false;
     type Response = DeleteData;
+
+    fn balance(response: &DeleteData) -> Option<Balance> {
+        response.balance()
+    }
 }
 
 impl<'a> Transaction<DeleteExtension> for DomainDelete<'a> {}
@@ -558,10 +641,9 @@ impl<'a> Transaction<DeleteExtension> for DomainDelete<'a> {}
 #[cfg(test)]
 mod tests {
     use super::{Check, Update, XMLNS};
-    use crate::client::RequestData;
     use crate::domain::update::{DomainChangeInfo, DomainUpdate};
     use crate::domain::DomainCheck;
-    use crate::request::{Command, CommandWrapper, Extension, Transaction};
+    use crate::request::{Command, CommandWrapper, Extension, RequestData, Transaction};
     use crate::tests::CLTRID;
     use crate::xml;
 
@@ -605,6 +687,6 @@ mod tests {
 
         assert!(xml.contains(&format!(r#"<update xmlns="{}">"#, XMLNS)));
         assert!(xml.contains("<currency>USD</currency>"));
-        assert!(xml.contains("<fee>80</fee>") || xml.contains("<fee>80.0</fee>"));
+        assert!(xml.contains("<fee>80.00</fee>"));
     }
 }
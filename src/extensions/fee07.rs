@@ -3,9 +3,14 @@
 // Namespace: urn:ietf:params:xml:ns:fee-0.7
 // ===============================================================================================
 
-use instant_xml::{FromXml, ToXml};
-
-use crate::domain::{DomainCheck, DomainCreate, DomainRenew, DomainTransfer, DomainUpdate};
+use instant_xml::{FromXml, Id, ToXml};
+
+use crate::balance::{Balance, ReportsBalance};
+use crate::common::Amount;
+use crate::domain::{
+    DomainCheck, DomainCreate, DomainDelete, DomainRenew, DomainTransfer, DomainUpdate,
+};
+use crate::price_class::PriceClass;
 use crate::request::{Extension, Transaction};
 
 /// fee-0.7 namespace (pre-RFC8748)
@@ -121,6 +126,10 @@ pub struct Check<'a> {
 
 impl<'a> Extension for Check<'a> {
     type Response = CheckData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
 }
 
 impl<'a> Transaction<Check<'a>> for DomainCheck<'a> {}
@@ -210,6 +219,16 @@ pub struct CheckDomainData {
     pub class: Option<String>,
 }
 
+impl CheckDomainData {
+    /// The typed [`PriceClass`] of the `<fee:class>`, if given
+    ///
+    /// Use [`PriceClasses::classify()`](crate::price_class::PriceClasses::classify) for
+    /// registry-specific labels.
+    pub fn price_class(&self) -> Option<PriceClass> {
+        self.class.as_deref().map(PriceClass::from_label)
+    }
+}
+
 // -------------------------------------------------------------------------------------------
 // REQUEST SIDE: <extension><fee:create|renew|transfer|update>…</fee:...></extension>
 // -------------------------------------------------------------------------------------------
@@ -284,7 +303,7 @@ pub struct FeeReq<'a> {
     pub applied: Option<&'a str>,
 
     #[xml(direct)]
-    pub amount: f64,
+    pub amount: Amount,
 }
 
 #[derive(Debug, ToXml)]
@@ -294,11 +313,11 @@ pub struct CreditReq<'a> {
     pub description: Option<&'a str>,
 
     #[xml(direct)]
-    pub amount: f64,
+    pub amount: Amount,
 }
 
 impl<'a> Create<'a> {
-    pub fn new(currency: Option<&'a str>, amount: f64) -> Self {
+    pub fn new(currency: Option<&'a str>, amount: impl Into<Amount>) -> Self {
         Self {
             currency,
             fees: vec![FeeReq {
@@ -306,7 +325,7 @@ impl<'a> Create<'a> {
                 refundable: None,
                 grace_period: None,
                 applied: None,
-                amount,
+                amount: amount.into(),
             }],
             credits: vec![],
         }
@@ -314,7 +333,7 @@ impl<'a> Create<'a> {
 }
 
 impl<'a> Renew<'a> {
-    pub fn new(currency: Option<&'a str>, amount: f64) -> Self {
+    pub fn new(currency: Option<&'a str>, amount: impl Into<Amount>) -> Self {
         Self {
             currency,
             fees: vec![FeeReq {
@@ -322,7 +341,7 @@ impl<'a> Renew<'a> {
                 refundable: None,
                 grace_period: None,
                 applied: None,
-                amount,
+                amount: amount.into(),
             }],
             credits: vec![],
         }
@@ -330,7 +349,7 @@ impl<'a> Renew<'a> {
 }
 
 impl<'a> Transfer<'a> {
-    pub fn new(currency: Option<&'a str>, amount: f64) -> Self {
+    pub fn new(currency: Option<&'a str>, amount: impl Into<Amount>) -> Self {
         Self {
             currency,
             fees: vec![FeeReq {
@@ -338,7 +357,7 @@ impl<'a> Transfer<'a> {
                 refundable: None,
                 grace_period: None,
                 applied: None,
-                amount,
+                amount: amount.into(),
             }],
             credits: vec![],
         }
@@ -346,7 +365,7 @@ impl<'a> Transfer<'a> {
 }
 
 impl<'a> Update<'a> {
-    pub fn new(currency: Option<&'a str>, amount: f64) -> Self {
+    pub fn new(currency: Option<&'a str>, amount: impl Into<Amount>) -> Self {
         Self {
             currency,
             fees: vec![FeeReq {
@@ -354,19 +373,19 @@ impl<'a> Update<'a> {
                 refundable: None,
                 grace_period: None,
                 applied: None,
-                amount,
+                amount: amount.into(),
             }],
             credits: vec![],
         }
     }
 
-    pub fn restore(currency: Option<&'a str>, amount: f64) -> Self {
+    pub fn restore(currency: Option<&'a str>, amount: impl Into<Amount>) -> Self {
         Self::new(currency, amount)
     }
 }
 
 // -------------------------------------------------------------------------------------------
-// RESPONSE SIDE: <fee:creData>, <fee:renData>, <fee:trnData>, <fee:upData>
+// RESPONSE SIDE: <fee:creData>, <fee:renData>, <fee:trnData>, <fee:upData>, <fee:delData>
 // -------------------------------------------------------------------------------------------
 
 /// fee-0.7 "transform result" type (create/renew/update) includes balance/creditLimit optionally.  [oai_citation:6‡IETF Datatracker](https://datatracker.ietf.org/doc/draft-brown-epp-fees/04/)
@@ -450,17 +469,69 @@ pub struct UpdateData {
     pub credit_limit: Option<f64>,
 }
 
+/// fee-0.7 delete result, reporting credits for deletions within a grace period
+#[derive(Debug, FromXml)]
+#[xml(rename = "delData", ns(XMLNS))]
+pub struct DeleteData {
+    #[xml(rename = "currency")]
+    pub currency: String,
+
+    #[xml(rename = "fee")]
+    pub fees: Vec<Fee>,
+
+    #[xml(rename = "credit")]
+    pub credits: Vec<Credit>,
+
+    #[xml(rename = "balance")]
+    pub balance: Option<f64>,
+
+    #[xml(rename = "creditLimit")]
+    pub credit_limit: Option<f64>,
+}
+
 impl<'a> Extension for Create<'a> {
     type Response = CreateData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+
+    fn balance(response: &CreateData) -> Option<Balance> {
+        response.balance()
+    }
 }
 impl<'a> Extension for Renew<'a> {
     type Response = RenewData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+
+    fn balance(response: &RenewData) -> Option<Balance> {
+        response.balance()
+    }
 }
 impl<'a> Extension for Transfer<'a> {
     type Response = TransferData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+
+    fn balance(response: &TransferData) -> Option<Balance> {
+        response.balance()
+    }
 }
 impl<'a> Extension for Update<'a> {
     type Response = UpdateData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+
+    fn balance(response: &UpdateData) -> Option<Balance> {
+        response.balance()
+    }
 }
 
 impl<'a> Transaction<Create<'a>> for DomainCreate<'a> {}
@@ -468,13 +539,45 @@ impl<'a> Transaction<Renew<'a>> for DomainRenew<'a> {}
 impl<'a> Transaction<Transfer<'a>> for DomainTransfer<'a> {}
 impl<'a> Transaction<Update<'a>> for DomainUpdate<'a> {}
 
+// -------------------------------------------------------------------------------------------
+// RESPONSE-ONLY DELETE EXTENSION (fee:delData)
+// -------------------------------------------------------------------------------------------
+
+#[derive(Debug, Eq, PartialEq)]
+pub struct DeleteExtension;
+
+impl ToXml for DeleteExtension {
+    fn serialize<W: std::fmt::Write + ?Sized>(
+        &self,
+        _field: Option<Id<'_>>,
+        _serializer: &mut instant_xml::Serializer<W>,
+    ) -> Result<(), instant_xml::Error> {
+        Ok(())
+    }
+
+    fn present(&self) -> bool {
+        false
+    }
+}
+
+impl Extension for DeleteExtension {
+    const DO_SEND: bool = false;
+    type Response = DeleteData;
+
+    fn balance(response: &DeleteData) -> Option<Balance> {
+        response.balance()
+    }
+}
+
+impl<'a> Transaction<DeleteExtension> for DomainDelete<'a> {}
+
 #[cfg(test)]
 mod tests {
-    use super::{Check, Update, XMLNS};
-    use crate::client::RequestData;
+    use super::{Check, DeleteExtension, Update, XMLNS};
+    use crate::common::Amount;
     use crate::domain::update::{DomainChangeInfo, DomainUpdate};
-    use crate::domain::DomainCheck;
-    use crate::request::{Command, CommandWrapper, Extension, Transaction};
+    use crate::domain::{DomainCheck, DomainDelete};
+    use crate::request::{Command, CommandWrapper, Extension, RequestData, Transaction};
     use crate::response::ResultCode;
     use crate::tests::{response_from_file_with_ext, CLTRID, SUCCESS_MSG, SVTRID};
     use crate::xml;
@@ -519,7 +622,11 @@ mod tests {
 
         assert!(xml.contains(&format!(r#"<update xmlns="{}">"#, XMLNS)));
         assert!(xml.contains("<currency>USD</currency>"));
-        assert!(xml.contains("<fee>80</fee>") || xml.contains("<fee>80.0</fee>"));
+        assert!(xml.contains("<fee>80.00</fee>"));
+
+        let ext = Update::restore(Some("JPY"), Amount::new(8000.0).with_decimals(0));
+        let xml = serialize_request((&object, &ext));
+        assert!(xml.contains("<fee>8000</fee>"));
     }
 
     #[test]
@@ -537,4 +644,22 @@ mod tests {
         assert_eq!(object.tr_ids.client_tr_id.unwrap(), CLTRID);
         assert_eq!(object.tr_ids.server_tr_id, SVTRID);
     }
+
+    #[test]
+    fn delete_response() {
+        let object = response_from_file_with_ext::<DomainDelete, DeleteExtension>(
+            "response/extensions/fee07_delete.xml",
+        );
+        let ext = object.extension.unwrap();
+
+        assert_eq!(ext.data.currency, "USD");
+        assert!(ext.data.fees.is_empty());
+        assert_eq!(ext.data.credits.len(), 1);
+        assert_eq!(ext.data.credits[0].amount, -5.0);
+        assert_eq!(
+            ext.data.credits[0].description.as_deref(),
+            Some("AGP Credit")
+        );
+        assert_eq!(ext.data.balance, Some(1005.0));
+    }
 }
@@ -0,0 +1,113 @@
+//! Fee checks tolerating a different fee version in the response
+//!
+//! During a migration between versions of the fee extension, some registries answer a fee
+//! check in another version than the one it was sent in. Decoding such a response with the
+//! module of the requested version fails, as the `<fee:chkData>` element is in an unexpected
+//! namespace. [`Check`] sends the check in one version, and its [`CheckData`] response is
+//! decoded with the module matching the namespace the registry actually answered in.
+
+use instant_xml::{FromXml, ToXml};
+
+use crate::domain::DomainCheck;
+use crate::extensions::{fee, fee023, fee07};
+use crate::request::{Extension, Transaction};
+
+/// A fee check in one of the supported versions of the fee extension
+#[derive(Debug, ToXml)]
+#[xml(forward)]
+pub enum Check<'a> {
+    Fee(fee::Check<'a>),
+    Fee023(fee023::Check<'a>),
+    Fee07(fee07::Check<'a>),
+}
+
+impl Check<'_> {
+    /// The namespace the check is sent in
+    pub fn xmlns(&self) -> &'static str {
+        match self {
+            Self::Fee(_) => fee::XMLNS,
+            Self::Fee023(_) => fee023::XMLNS,
+            Self::Fee07(_) => fee07::XMLNS,
+        }
+    }
+}
+
+impl<'a> From<fee::Check<'a>> for Check<'a> {
+    fn from(check: fee::Check<'a>) -> Self {
+        Self::Fee(check)
+    }
+}
+
+impl<'a> From<fee023::Check<'a>> for Check<'a> {
+    fn from(check: fee023::Check<'a>) -> Self {
+        Self::Fee023(check)
+    }
+}
+
+impl<'a> From<fee07::Check<'a>> for Check<'a> {
+    fn from(check: fee07::Check<'a>) -> Self {
+        Self::Fee07(check)
+    }
+}
+
+impl Extension for Check<'_> {
+    type Response = CheckData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![self.xmlns()])
+    }
+}
+
+impl<'a> Transaction<Check<'a>> for DomainCheck<'a> {}
+
+/// A fee check response, in the version the registry answered in
+#[derive(Debug, FromXml)]
+#[xml(forward)]
+pub enum CheckData {
+    /// Data under the `<fee:chkData>` tag of fee-1.0
+    Fee(fee::CheckData),
+    /// Data under the `<fee:chkData>` tag of fee-0.23
+    Fee023(fee023::CheckData),
+    /// Data under the `<fee:chkData>` tag of fee-0.7
+    Fee07(fee07::CheckData),
+}
+
+impl CheckData {
+    /// The namespace the registry answered in
+    pub fn xmlns(&self) -> &'static str {
+        match self {
+            Self::Fee(_) => fee::XMLNS,
+            Self::Fee023(_) => fee023::XMLNS,
+            Self::Fee07(_) => fee07::XMLNS,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Check, CheckData};
+    use crate::domain::DomainCheck;
+    use crate::extensions::{fee, fee07};
+    use crate::tests::response_from_file_with_ext;
+
+    #[test]
+    fn other_version() {
+        let rsp =
+            response_from_file_with_ext::<DomainCheck, Check>("response/extensions/fee_check.xml");
+        assert_eq!(rsp.extension().unwrap().xmlns(), fee::XMLNS);
+
+        let rsp = response_from_file_with_ext::<DomainCheck, Check>(
+            "response/extensions/fee07_check.xml",
+        );
+        let Some(CheckData::Fee07(data)) = rsp.extension() else {
+            panic!("expected fee-0.7 data");
+        };
+        assert_eq!(data.list.len(), 2);
+        assert_eq!(data.list[0].fees[0].amount, 10.0);
+
+        let check = Check::from(fee::Check::new(None, Some(1)));
+        assert_eq!(check.xmlns(), fee::XMLNS);
+        let check = Check::from(fee07::Check::new(["example.com"], None, Some(1)));
+        assert_eq!(check.xmlns(), fee07::XMLNS);
+    }
+}
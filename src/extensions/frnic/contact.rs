@@ -10,6 +10,10 @@ impl<'a> Transaction<Ext<Create<ContactCreate<'a>>>> for crate::contact::create:
 
 impl Extension for Ext<Create<ContactCreate<'_>>> {
     type Response = ();
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
 }
 
 /// For french TLDs, a contact is either an individual (PP) or a legal
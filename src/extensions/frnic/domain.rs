@@ -0,0 +1,112 @@
+//! Types for EPP FRNIC domain requests
+use instant_xml::ToXml;
+
+use crate::domain::{DomainTransfer, DomainUpdate};
+use crate::request::{Extension, Transaction};
+
+use super::{Ext, XMLNS};
+
+impl<'a> Transaction<Ext<Trade<'a>>> for DomainUpdate<'a> {}
+
+impl Extension for Ext<Trade<'_>> {
+    type Response = ();
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+}
+
+impl<'a> Transaction<Ext<Recover<'a>>> for DomainTransfer<'a> {}
+
+impl Extension for Ext<Recover<'_>> {
+    type Response = ();
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+}
+
+/// A change of registrant, sent with a domain `<update>`
+///
+/// The new registrant and contacts replace the current ones once the registry completed the
+/// trade; the update itself shouldn't change anything else.
+#[derive(Debug, ToXml)]
+#[xml(rename = "trade", ns(XMLNS))]
+pub struct Trade<'a> {
+    domain: Domain<'a>,
+}
+
+impl<'a> Trade<'a> {
+    pub fn new(registrant: &'a str) -> Self {
+        Self {
+            domain: Domain::new(registrant),
+        }
+    }
+
+    /// Set the contact of type `contact_type` (`admin` or `tech`) to `id`
+    pub fn contact(mut self, contact_type: &'a str, id: &'a str) -> Self {
+        self.domain.contacts.push(Contact { contact_type, id });
+        self
+    }
+}
+
+impl<'a> From<Trade<'a>> for Ext<Trade<'a>> {
+    fn from(data: Trade<'a>) -> Self {
+        Ext { data }
+    }
+}
+
+/// The recovery of a domain by its registrant, sent with a domain `<transfer>` request
+///
+/// The registrant gets the domain back from a registrar that doesn't act on their behalf
+/// anymore, with the authorization code of the domain.
+#[derive(Debug, ToXml)]
+#[xml(rename = "recover", ns(XMLNS))]
+pub struct Recover<'a> {
+    domain: Domain<'a>,
+}
+
+impl<'a> Recover<'a> {
+    pub fn new(registrant: &'a str) -> Self {
+        Self {
+            domain: Domain::new(registrant),
+        }
+    }
+
+    /// Set the contact of type `contact_type` (`admin` or `tech`) to `id`
+    pub fn contact(mut self, contact_type: &'a str, id: &'a str) -> Self {
+        self.domain.contacts.push(Contact { contact_type, id });
+        self
+    }
+}
+
+impl<'a> From<Recover<'a>> for Ext<Recover<'a>> {
+    fn from(data: Recover<'a>) -> Self {
+        Ext { data }
+    }
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "domain", ns(XMLNS))]
+struct Domain<'a> {
+    registrant: &'a str,
+    contacts: Vec<Contact<'a>>,
+}
+
+impl<'a> Domain<'a> {
+    fn new(registrant: &'a str) -> Self {
+        Self {
+            registrant,
+            contacts: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "contact", ns(XMLNS))]
+struct Contact<'a> {
+    #[xml(attribute, rename = "type")]
+    contact_type: &'a str,
+    #[xml(direct)]
+    id: &'a str,
+}
@@ -3,8 +3,10 @@
 use instant_xml::{FromXml, ToXml};
 
 pub mod contact;
+pub mod domain;
 
 pub use contact::ContactCreate;
+pub use domain::{Recover, Trade};
 
 pub const XMLNS: &str = "http://www.afnic.fr/xml/epp/frnic-2.0";
 
@@ -24,6 +26,7 @@ pub struct Create<T> {
 mod tests {
     use crate::contact::{Address, PostalInfo, Voice};
     use crate::contact::{ContactCreate, InfoType};
+    use crate::domain::{DomainTransfer, DomainUpdate};
     use crate::extensions::frnic;
     use crate::tests::assert_serialized;
     use frnic::{contact, Ext};
@@ -155,4 +158,22 @@ mod tests {
             (&object, &frnic_contact),
         );
     }
+
+    #[test]
+    fn test_domain_trade() {
+        let object = DomainUpdate::new("afnic.fr");
+        let trade = Ext::from(
+            frnic::Trade::new("TEST-AFNIC")
+                .contact("admin", "TEST-ADMIN")
+                .contact("tech", "TEST-TECH"),
+        );
+        assert_serialized("request/extensions/frnic_trade.xml", (&object, &trade));
+    }
+
+    #[test]
+    fn test_domain_recover() {
+        let object = DomainTransfer::new("afnic.fr", None, "WarlordZ666");
+        let recover = Ext::from(frnic::Recover::new("TEST-AFNIC").contact("admin", "TEST-ADMIN"));
+        assert_serialized("request/extensions/frnic_recover.xml", (&object, &recover));
+    }
 }
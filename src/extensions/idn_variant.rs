@@ -0,0 +1,248 @@
+//! Mapping for IDN variant management
+//!
+//! Registries operating IDN TLDs with variant rules bundle variant labels with a primary
+//! domain. Variants are declared on create, activated, blocked or removed on update, and
+//! listed in the domain info response. Each variant carries a state: active variants
+//! resolve like the primary domain, blocked variants are reserved for the registrant but
+//! not delegated.
+
+use std::borrow::Cow;
+
+use instant_xml::{FromXml, ToXml};
+
+use crate::common::NoExtension;
+use crate::domain::{DomainCreate, DomainInfo, DomainUpdate};
+use crate::request::{Extension, Transaction};
+
+pub const XMLNS: &str = "urn:ietf:params:xml:ns:epp:idnVariant-1.0";
+
+impl<'a> Transaction<Create<'a>> for DomainCreate<'a> {}
+
+impl Extension for Create<'_> {
+    type Response = NoExtension;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+}
+
+impl<'a> Transaction<Update<'a>> for DomainUpdate<'a> {}
+
+impl Extension for Update<'_> {
+    type Response = NoExtension;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+}
+
+impl Transaction<VariantInfo> for DomainInfo<'_> {}
+
+impl Extension for VariantInfo {
+    const DO_SEND: bool = false;
+    type Response = InfoData;
+}
+
+/// The state of a variant label
+#[derive(Clone, Copy, Debug, Eq, FromXml, PartialEq, ToXml)]
+#[xml(scalar, rename_all = "lowercase")]
+pub enum VariantState {
+    /// The variant is delegated alongside the primary domain
+    Active,
+    /// The variant is reserved for the registrant of the primary domain, but not delegated
+    Blocked,
+}
+
+/// Type corresponding to the `<variant>` tag
+#[derive(Clone, Debug, Eq, FromXml, PartialEq, ToXml)]
+#[xml(rename = "variant", ns(XMLNS))]
+pub struct Variant<'a> {
+    /// The state of the variant; the registry's default state applies if omitted
+    #[xml(attribute)]
+    pub state: Option<VariantState>,
+    /// The variant label, as an A-label
+    #[xml(direct)]
+    pub name: Cow<'a, str>,
+}
+
+impl<'a> Variant<'a> {
+    pub fn new(name: impl Into<Cow<'a, str>>, state: Option<VariantState>) -> Self {
+        Self {
+            state,
+            name: name.into(),
+        }
+    }
+}
+
+// Request
+
+/// Type for the `<create>` extension, declaring variants bundled with a new domain
+#[derive(Debug, ToXml)]
+#[xml(rename = "create", ns(XMLNS))]
+pub struct Create<'a> {
+    /// The IDN table the primary domain and its variants are validated against
+    pub table: Option<&'a str>,
+    /// The variants to bundle with the domain
+    #[xml(rename = "variant")]
+    pub variants: &'a [Variant<'a>],
+}
+
+impl<'a> Create<'a> {
+    pub fn new(table: Option<&'a str>, variants: &'a [Variant<'a>]) -> Self {
+        Self { table, variants }
+    }
+}
+
+/// Type for the `<update>` extension, changing the variants bundled with a domain
+#[derive(Debug, Default, ToXml)]
+#[xml(rename = "update", ns(XMLNS))]
+pub struct Update<'a> {
+    /// Variants to add to the bundle
+    pub add: Option<Add<'a>>,
+    /// Variants to remove from the bundle
+    pub rem: Option<Rem<'a>>,
+    /// Variants to change the state of
+    pub chg: Option<Chg<'a>>,
+}
+
+impl<'a> Update<'a> {
+    /// Add variants to the bundle
+    pub fn add_variants(mut self, variants: &'a [Variant<'a>]) -> Self {
+        self.add = Some(Add { variants });
+        self
+    }
+
+    /// Remove variants from the bundle
+    pub fn remove_variants(mut self, variants: &'a [Variant<'a>]) -> Self {
+        self.rem = Some(Rem { variants });
+        self
+    }
+
+    /// Change the state of variants already in the bundle, to activate or block them
+    pub fn change_variants(mut self, variants: &'a [Variant<'a>]) -> Self {
+        self.chg = Some(Chg { variants });
+        self
+    }
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "add", ns(XMLNS))]
+pub struct Add<'a> {
+    #[xml(rename = "variant")]
+    pub variants: &'a [Variant<'a>],
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "rem", ns(XMLNS))]
+pub struct Rem<'a> {
+    #[xml(rename = "variant")]
+    pub variants: &'a [Variant<'a>],
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "chg", ns(XMLNS))]
+pub struct Chg<'a> {
+    #[xml(rename = "variant")]
+    pub variants: &'a [Variant<'a>],
+}
+
+/// Response-only extension to read the variants from a domain info response
+#[derive(Debug, Eq, PartialEq)]
+pub struct VariantInfo;
+
+impl ToXml for VariantInfo {
+    fn serialize<W: std::fmt::Write + ?Sized>(
+        &self,
+        _field: Option<instant_xml::Id<'_>>,
+        _serializer: &mut instant_xml::Serializer<W>,
+    ) -> Result<(), instant_xml::Error> {
+        Ok(())
+    }
+
+    fn present(&self) -> bool {
+        false
+    }
+}
+
+// Response
+
+/// Type that represents the `<infData>` tag for the variants of a domain
+#[derive(Debug, FromXml)]
+#[xml(rename = "infData", ns(XMLNS))]
+pub struct InfoData {
+    /// The IDN table used for the domain
+    pub table: Option<String>,
+    /// The variants bundled with the domain
+    #[xml(rename = "variant")]
+    pub variants: Vec<Variant<'static>>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::update::DomainChangeInfo;
+    use crate::domain::{Period, PeriodLength};
+    use crate::tests::{assert_serialized, response_from_file_with_ext};
+
+    #[test]
+    fn create() {
+        let variants = [
+            Variant::new("xn--fsqu00a.example", Some(VariantState::Active)),
+            Variant::new("xn--fsqu00b.example", Some(VariantState::Blocked)),
+        ];
+        let extension = Create::new(Some("zh-cn"), &variants);
+        let object = DomainCreate::new(
+            "xn--fsq270a.example",
+            Period::Years(PeriodLength::new(1).unwrap()),
+            None,
+            None,
+            "2fooBAR",
+            None,
+        );
+
+        assert_serialized(
+            "request/extensions/idn_variant_create.xml",
+            (&object, &extension),
+        );
+    }
+
+    #[test]
+    fn update() {
+        let add = [Variant::new("xn--fsqu00c.example", None)];
+        let change = [Variant::new(
+            "xn--fsqu00b.example",
+            Some(VariantState::Active),
+        )];
+        let extension = Update::default()
+            .add_variants(&add)
+            .change_variants(&change);
+
+        let mut object = DomainUpdate::new("xn--fsq270a.example");
+        object.info(DomainChangeInfo {
+            registrant: None,
+            auth_info: None,
+        });
+
+        assert_serialized(
+            "request/extensions/idn_variant_update.xml",
+            (&object, &extension),
+        );
+    }
+
+    #[test]
+    fn info_response() {
+        let object = response_from_file_with_ext::<DomainInfo, VariantInfo>(
+            "response/extensions/idn_variant_info.xml",
+        );
+        let data = object.extension().unwrap();
+
+        assert_eq!(data.table.as_deref(), Some("zh-cn"));
+        assert_eq!(
+            data.variants,
+            [
+                Variant::new("xn--fsqu00a.example", Some(VariantState::Active)),
+                Variant::new("xn--fsqu00b.example", Some(VariantState::Blocked)),
+            ]
+        );
+    }
+}
@@ -1,10 +1,14 @@
 //! Types for the EPP launch phase extension
 
+use std::collections::HashMap;
+use std::fmt;
+
 use chrono::{DateTime, Utc};
-use instant_xml::{FromXml, ToXml};
+use instant_xml::{FromXml, Id, Serializer, ToXml};
+use serde::{Deserialize, Serialize};
 
-use crate::common::NoExtension;
-use crate::domain::{DomainCheck, DomainCreate};
+use crate::common::{NoExtension, Timestamp};
+use crate::domain::{normalize_name, DomainCheck, DomainCreate};
 use crate::request::{Extension, Transaction};
 
 /// Launch Phase Mapping namespace
@@ -18,14 +22,23 @@ impl<'a> Transaction<Create<'a>> for DomainCreate<'a> {}
 
 impl Extension for Check<'_> {
     type Response = CheckData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
 }
 
 impl Extension for Create<'_> {
     type Response = NoExtension;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
 }
 
-#[derive(Clone, Copy, Debug, FromXml, ToXml)]
+#[derive(Clone, Copy, Debug, Deserialize, Eq, FromXml, PartialEq, Serialize, ToXml)]
 #[xml(scalar)]
+#[serde(rename_all = "lowercase")]
 pub enum PhaseType {
     #[xml(rename = "sunrise")]
     Sunrise,
@@ -39,6 +52,18 @@ pub enum PhaseType {
     Custom,
 }
 
+impl PhaseType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sunrise => "sunrise",
+            Self::Landrush => "landrush",
+            Self::Claims => "claims",
+            Self::Open => "open",
+            Self::Custom => "custom",
+        }
+    }
+}
+
 /// <launch:phase name="custom-name">sunrise</launch:phase>
 #[derive(Debug, ToXml)]
 #[xml(rename = "phase", ns(XMLNS))]
@@ -176,6 +201,33 @@ pub struct CheckData {
     pub list: Vec<CheckDomainData>,
 }
 
+impl CheckData {
+    /// The claims result for `name`, compared after
+    /// [normalization](crate::domain::normalize_name)
+    pub fn get(&self, name: &str) -> Option<&CheckDomainData> {
+        let name = normalize_name(name);
+        self.list
+            .iter()
+            .find(|cd| normalize_name(&cd.name.value) == name)
+    }
+
+    /// The names of the domains for which no trademark claims exist
+    pub fn unclaimed_names(&self) -> impl Iterator<Item = &str> {
+        self.list
+            .iter()
+            .filter(|cd| cd.name.exists == Some(false))
+            .map(|cd| cd.name.value.as_str())
+    }
+
+    /// The claims results, keyed by their [normalized](crate::domain::normalize_name) names
+    pub fn into_map(self) -> HashMap<String, CheckDomainData> {
+        self.list
+            .into_iter()
+            .map(|cd| (normalize_name(&cd.name.value), cd))
+            .collect()
+    }
+}
+
 #[derive(Debug, FromXml)]
 #[xml(rename = "cd", ns(XMLNS))]
 pub struct CheckDomainData {
@@ -201,8 +253,30 @@ pub struct CheckName {
 pub struct Notice<'a> {
     #[xml(rename = "noticeID")]
     pub notice_id: &'a str,
-    #[xml(rename = "notAfter")]
+    #[xml(serialize_with = "serialize_not_after")]
     pub not_after: DateTime<Utc>,
-    #[xml(rename = "acceptedDate")]
+    #[xml(serialize_with = "serialize_accepted_date")]
     pub accepted_date: DateTime<Utc>,
 }
+
+fn serialize_not_after<W: fmt::Write + ?Sized>(
+    value: &DateTime<Utc>,
+    serializer: &mut Serializer<W>,
+) -> Result<(), instant_xml::Error> {
+    let id = Id {
+        ns: XMLNS,
+        name: "notAfter",
+    };
+    Timestamp(*value).serialize(Some(id), serializer)
+}
+
+fn serialize_accepted_date<W: fmt::Write + ?Sized>(
+    value: &DateTime<Utc>,
+    serializer: &mut Serializer<W>,
+) -> Result<(), instant_xml::Error> {
+    let id = Id {
+        ns: XMLNS,
+        name: "acceptedDate",
+    };
+    Timestamp(*value).serialize(Some(id), serializer)
+}
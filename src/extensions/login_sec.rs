@@ -0,0 +1,195 @@
+//! Mapping for the [Login Security
+//! extension](https://www.rfc-editor.org/rfc/rfc8807.html)
+//!
+//! The `<pw>` of the core `<login>` command is limited to 16 characters. This extension carries
+//! longer passwords (and a user agent) in the login's `<extension>`, with the core password set
+//! to [`PASSWORD_PLACEHOLDER`]. The login response may report security events, like a password
+//! or certificate about to expire.
+
+use std::borrow::Cow;
+
+use chrono::{DateTime, Utc};
+use instant_xml::{FromXml, ToXml};
+
+use crate::login::Login;
+use crate::request::{Extension, Transaction};
+
+pub const XMLNS: &str = "urn:ietf:params:xml:ns:epp:loginSec-1.0";
+
+/// The core `<pw>` (or `<newPW>`) to send when the password is carried by the extension
+pub const PASSWORD_PLACEHOLDER: &str = "[LOGIN-SECURITY]";
+
+/// The longest password the core `<login>` command accepts
+pub const MAX_CORE_PASSWORD_LEN: usize = 16;
+
+impl Transaction<LoginSecurity<'_>> for Login<'_> {}
+
+impl Extension for LoginSecurity<'_> {
+    type Response = LoginSecData;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+}
+
+// Request
+
+/// Software identification sent with the login
+#[derive(Debug, Default, Eq, PartialEq, ToXml)]
+#[xml(rename = "userAgent", ns(XMLNS))]
+pub struct UserAgent<'a> {
+    /// The client application, like `registrar-sync 2.1.0`
+    pub app: Option<Cow<'a, str>>,
+    /// The technology the client is built with, like `instant-epp 0.5`
+    pub tech: Option<Cow<'a, str>>,
+    /// The operating system of the client
+    pub os: Option<Cow<'a, str>>,
+}
+
+/// The `<loginSec>` extension to the `<login>` command
+#[derive(Debug, Default, Eq, PartialEq, ToXml)]
+#[xml(rename = "loginSec", ns(XMLNS))]
+pub struct LoginSecurity<'a> {
+    #[xml(rename = "userAgent")]
+    pub user_agent: Option<UserAgent<'a>>,
+    /// The password, if the core `<pw>` is [`PASSWORD_PLACEHOLDER`]
+    pub pw: Option<&'a str>,
+    /// The new password, if the core `<newPW>` is [`PASSWORD_PLACEHOLDER`]
+    #[xml(rename = "newPW")]
+    pub new_pw: Option<&'a str>,
+}
+
+impl<'a> LoginSecurity<'a> {
+    /// A `<login>` and its extension, moving passwords too long for the core command into the
+    /// extension
+    ///
+    /// The login security namespace is added to `ext_uris` if missing.
+    pub fn login(
+        username: &'a str,
+        password: &'a str,
+        new_password: Option<&'a str>,
+        ext_uris: Option<&'_ [&'a str]>,
+        user_agent: Option<UserAgent<'a>>,
+    ) -> (Login<'a>, Self) {
+        let split = |password: &'a str| match password.chars().count() {
+            len if len > MAX_CORE_PASSWORD_LEN => (PASSWORD_PLACEHOLDER, Some(password)),
+            _ => (password, None),
+        };
+
+        let (core_pw, pw) = split(password);
+        let (core_new_pw, new_pw) = match new_password.map(split) {
+            Some((core, ext)) => (Some(core), ext),
+            None => (None, None),
+        };
+
+        let mut uris = ext_uris.unwrap_or_default().to_vec();
+        if !uris.contains(&XMLNS) {
+            uris.push(XMLNS);
+        }
+
+        let login = Login::new(username, core_pw, core_new_pw, Some(uris.as_slice()));
+        let security = Self {
+            user_agent,
+            pw,
+            new_pw,
+        };
+        (login, security)
+    }
+}
+
+// Response
+
+/// The `<loginSecData>` in a login response
+#[derive(Debug, FromXml)]
+#[xml(rename = "loginSecData", ns(XMLNS))]
+pub struct LoginSecData {
+    #[xml(rename = "event")]
+    pub events: Vec<Event>,
+}
+
+/// A security event reported by the server
+#[derive(Debug, FromXml)]
+#[xml(rename = "event", ns(XMLNS))]
+pub struct Event {
+    /// The kind of event: `password`, `certificate`, `cipher`, `tlsProtocol`, `newPW`, `stat`
+    /// or `custom`
+    #[xml(attribute, rename = "type")]
+    pub event_type: String,
+    /// The name of a `stat` or `custom` event
+    #[xml(attribute)]
+    pub name: Option<String>,
+    /// `warning` or `error`
+    #[xml(attribute)]
+    pub level: String,
+    /// When the password or certificate expires
+    #[xml(attribute, rename = "exDate")]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// The value that triggered the event, like the cipher or the number of failed logins
+    #[xml(attribute)]
+    pub value: Option<String>,
+    /// The period a statistic applies to, as an XML duration
+    #[xml(attribute)]
+    pub duration: Option<String>,
+    #[xml(direct)]
+    pub description: Option<String>,
+}
+
+impl Event {
+    /// Whether the server refuses the login because of this event
+    pub fn is_error(&self) -> bool {
+        self.level == "error"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{LoginSecurity, UserAgent};
+    use crate::login::Login;
+    use crate::response::ResultCode;
+    use crate::tests::{assert_serialized, response_from_file_with_ext};
+
+    #[test]
+    fn command() {
+        let user_agent = UserAgent {
+            app: Some("EPP SDK 1.0.0".into()),
+            tech: Some("Vendor Rust 1.85".into()),
+            os: None,
+        };
+        let (login, security) = LoginSecurity::login(
+            "username",
+            "this is a long password",
+            Some("new password that is still long"),
+            None,
+            Some(user_agent),
+        );
+        assert_serialized("request/extensions/login_sec.xml", (&login, &security));
+
+        let (_, security) = LoginSecurity::login("username", "short", None, None, None);
+        assert_eq!(security.pw, None);
+    }
+
+    #[test]
+    fn response() {
+        let object = response_from_file_with_ext::<Login, LoginSecurity>(
+            "response/extensions/login_sec.xml",
+        );
+        assert_eq!(object.result.code, ResultCode::CommandCompletedSuccessfully);
+
+        let events = &object.extension().unwrap().events;
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].event_type, "password");
+        assert_eq!(
+            events[0].expires_at,
+            Utc.with_ymd_and_hms(2020, 3, 25, 0, 0, 0).single()
+        );
+        assert_eq!(
+            events[0].description.as_deref(),
+            Some("Password expiring in a week")
+        );
+        assert!(!events[0].is_error());
+        assert_eq!(events[1].name.as_deref(), Some("failedLogins"));
+        assert_eq!(events[1].value.as_deref(), Some("100"));
+    }
+}
@@ -0,0 +1,242 @@
+//! Registry Maintenance Notifications for the Extensible Provisioning Protocol (EPP)
+//!
+//! As described in [RFC 9167](https://tools.ietf.org/html/rfc9167). Registries send a poll
+//! message whenever a maintenance item is created, updated or deleted, and when it ends.
+
+use chrono::{DateTime, Utc};
+use instant_xml::FromXml;
+
+pub const XMLNS: &str = "urn:ietf:params:xml:ns:epp:maintenance-1.0";
+
+/// Type that represents the `<maint:infData>` tag for a maintenance item
+#[derive(Debug, FromXml)]
+#[xml(rename = "infData", ns(XMLNS))]
+pub struct InfoData {
+    /// The maintenance item
+    pub item: MaintenanceItem,
+}
+
+/// Type corresponding to the `<maint:item>` tag
+#[derive(Debug, FromXml)]
+#[xml(rename = "item", ns(XMLNS))]
+pub struct MaintenanceItem {
+    /// The maintenance identifier
+    pub id: MaintenanceId,
+    /// The type of maintenance, like "Routine Maintenance"
+    #[xml(rename = "type")]
+    pub kind: Vec<MaintenanceType>,
+    /// What happened to the item, for poll messages
+    #[xml(rename = "pollType")]
+    pub poll_type: Option<PollType>,
+    /// The systems affected by the maintenance
+    pub systems: Systems,
+    /// The environment affected by the maintenance
+    pub environment: Environment,
+    /// The start of the maintenance window
+    #[xml(deserialize_with = "crate::datetime::deserialize_datetime_utc")]
+    pub start: DateTime<Utc>,
+    /// The end of the maintenance window
+    #[xml(deserialize_with = "crate::datetime::deserialize_datetime_utc")]
+    pub end: DateTime<Utc>,
+    /// Whether the maintenance is planned or an emergency
+    pub reason: Reason,
+    /// URL with details about the maintenance
+    pub detail: Option<String>,
+    /// Free-form descriptions of the maintenance
+    #[xml(rename = "description")]
+    pub descriptions: Vec<Description>,
+    /// The TLDs affected by the maintenance
+    pub tlds: Option<Tlds>,
+    /// Interventions required from the registrar
+    pub intervention: Option<Intervention>,
+    /// When the maintenance item was created
+    #[xml(
+        rename = "crDate",
+        deserialize_with = "crate::datetime::deserialize_datetime_utc"
+    )]
+    pub created_at: DateTime<Utc>,
+    /// When the maintenance item was last updated
+    #[xml(
+        rename = "upDate",
+        deserialize_with = "crate::datetime::deserialize_datetime_utc_option"
+    )]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// Type corresponding to the `<maint:id>` tag
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
+#[xml(rename = "id", ns(XMLNS))]
+pub struct MaintenanceId {
+    /// Human-readable name of the maintenance
+    #[xml(attribute)]
+    pub name: Option<String>,
+    /// URL with details about the maintenance
+    #[xml(attribute)]
+    pub url: Option<String>,
+    /// The server-assigned identifier
+    #[xml(direct)]
+    pub id: String,
+}
+
+/// Type corresponding to the `<maint:type>` tag
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
+#[xml(rename = "type", ns(XMLNS))]
+pub struct MaintenanceType {
+    #[xml(attribute)]
+    pub lang: Option<String>,
+    #[xml(direct)]
+    pub text: String,
+}
+
+/// Type corresponding to the `<maint:description>` tag
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
+#[xml(rename = "description", ns(XMLNS))]
+pub struct Description {
+    #[xml(attribute)]
+    pub lang: Option<String>,
+    #[xml(direct)]
+    pub text: String,
+}
+
+/// The change a maintenance poll message reports
+#[derive(Clone, Copy, Debug, Eq, FromXml, PartialEq)]
+#[xml(scalar, rename_all = "lowercase", ns(XMLNS))]
+pub enum PollType {
+    Create,
+    Update,
+    Delete,
+    Courtesy,
+    End,
+}
+
+/// Type corresponding to the `<maint:systems>` tag
+#[derive(Debug, FromXml)]
+#[xml(rename = "systems", ns(XMLNS))]
+pub struct Systems {
+    #[xml(rename = "system")]
+    pub list: Vec<System>,
+}
+
+/// Type corresponding to the `<maint:system>` tag
+#[derive(Debug, FromXml)]
+#[xml(rename = "system", ns(XMLNS))]
+pub struct System {
+    /// The name of the affected system, like "EPP" or "WHOIS"
+    pub name: String,
+    /// The host name of the affected system
+    pub host: Option<String>,
+    /// The impact of the maintenance on the system
+    pub impact: Impact,
+}
+
+/// The impact of a maintenance on a system
+#[derive(Clone, Copy, Debug, Eq, FromXml, PartialEq)]
+#[xml(scalar, rename_all = "lowercase", ns(XMLNS))]
+pub enum Impact {
+    Full,
+    Partial,
+    None,
+}
+
+/// Type corresponding to the `<maint:environment>` tag
+#[derive(Debug, FromXml)]
+#[xml(rename = "environment", ns(XMLNS))]
+pub struct Environment {
+    /// The type of environment: production, ote, staging, dev or custom
+    #[xml(attribute, rename = "type")]
+    pub kind: String,
+    /// The name of a custom environment
+    #[xml(attribute)]
+    pub name: Option<String>,
+}
+
+/// The reason for a maintenance
+#[derive(Clone, Copy, Debug, Eq, FromXml, PartialEq)]
+#[xml(scalar, rename_all = "lowercase", ns(XMLNS))]
+pub enum Reason {
+    Planned,
+    Emergency,
+}
+
+/// Type corresponding to the `<maint:tlds>` tag
+#[derive(Debug, FromXml)]
+#[xml(rename = "tlds", ns(XMLNS))]
+pub struct Tlds {
+    #[xml(rename = "tld")]
+    pub list: Vec<String>,
+}
+
+/// Type corresponding to the `<maint:intervention>` tag
+#[derive(Debug, FromXml)]
+#[xml(rename = "intervention", ns(XMLNS))]
+pub struct Intervention {
+    /// Whether registrars need to reconnect after the maintenance
+    #[xml(deserialize_with = "crate::scalar::deserialize_bool")]
+    pub connection: bool,
+    /// Whether registrars need to change their implementation
+    #[xml(deserialize_with = "crate::scalar::deserialize_bool")]
+    pub implementation: bool,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::*;
+    use crate::poll::{Poll, PollData};
+    use crate::response::ResultCode;
+    use crate::tests::{response_from_file, CLTRID, SVTRID};
+
+    #[test]
+    fn maintenance_poll() {
+        let object = response_from_file::<Poll>("response/poll/poll_maintenance.xml");
+
+        let item = match object.res_data() {
+            Some(PollData::Maintenance(data)) => &data.item,
+            _ => panic!("Unexpected message data"),
+        };
+
+        assert_eq!(item.id.id, "2e6df9b0-4092-4491-bcc8-9fb2166dcee6");
+        assert_eq!(item.id.name.as_deref(), Some("Routine Maintenance"));
+        assert_eq!(item.kind[0].text, "Routine Maintenance");
+        assert_eq!(item.poll_type, Some(PollType::Create));
+        assert_eq!(item.systems.list.len(), 1);
+        assert_eq!(item.systems.list[0].name, "EPP");
+        assert_eq!(
+            item.systems.list[0].host.as_deref(),
+            Some("epp.registry.example")
+        );
+        assert_eq!(item.systems.list[0].impact, Impact::Full);
+        assert_eq!(item.environment.kind, "production");
+        assert_eq!(
+            item.start,
+            Utc.with_ymd_and_hms(2021, 12, 30, 6, 0, 0).unwrap()
+        );
+        assert_eq!(
+            item.end,
+            Utc.with_ymd_and_hms(2021, 12, 30, 7, 0, 0).unwrap()
+        );
+        assert_eq!(item.reason, Reason::Planned);
+        assert_eq!(
+            item.detail.as_deref(),
+            Some("https://www.registry.example/notice?123")
+        );
+        assert_eq!(item.descriptions.len(), 2);
+        assert_eq!(item.tlds.as_ref().unwrap().list, ["example", "test"]);
+        let intervention = item.intervention.as_ref().unwrap();
+        assert!(!intervention.connection);
+        assert!(intervention.implementation);
+        assert_eq!(
+            item.created_at,
+            Utc.with_ymd_and_hms(2021, 11, 8, 22, 10, 0).unwrap()
+        );
+        assert_eq!(item.updated_at, None);
+
+        assert_eq!(
+            object.result.code,
+            ResultCode::CommandCompletedSuccessfullyAckToDequeue
+        );
+        assert_eq!(object.tr_ids.client_tr_id.unwrap(), CLTRID);
+        assert_eq!(object.tr_ids.server_tr_id, SVTRID);
+    }
+}
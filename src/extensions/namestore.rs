@@ -19,6 +19,7 @@ use crate::{
         check::HostCheck, create::HostCreate, delete::HostDelete, info::HostInfo,
         update::HostUpdate,
     },
+    login::Login,
     request::{Extension, Transaction},
 };
 
@@ -50,6 +51,10 @@ impl Transaction<NameStore<'_>> for HostDelete<'_> {}
 impl Transaction<NameStore<'_>> for HostInfo<'_> {}
 impl Transaction<NameStore<'_>> for HostUpdate<'_> {}
 
+// Session
+
+impl Transaction<NameStore<'_>> for Login<'_> {}
+
 impl<'a> NameStore<'a> {
     pub fn new(subproduct: &'a str) -> Self {
         NameStore {
@@ -60,6 +65,10 @@ impl<'a> NameStore<'a> {
 
 impl Extension for NameStore<'_> {
     type Response = NameStore<'static>;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
 }
 
 #[derive(Debug, ToXml)]
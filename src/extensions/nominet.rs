@@ -0,0 +1,118 @@
+//! Mapping for the Nominet list command
+//!
+//! Nominet (.uk) lets a registrar list the domains on its tag that were registered or expire
+//! in a given month, using an `<info>` command carrying a `<list>` element of its std-list-1.0
+//! schema. Only the names are returned; use a domain `<info>` for the details.
+
+use chrono::{Datelike, NaiveDate};
+use instant_xml::{FromXml, ToXml};
+
+use crate::common::{NoExtension, EPP_XMLNS};
+use crate::request::{Command, Transaction};
+
+pub const XMLNS: &str = "http://www.nominet.org.uk/epp/xml/std-list-1.0";
+
+impl Transaction<NoExtension> for DomainList {}
+
+impl Command for DomainList {
+    type Response = ListData;
+    const COMMAND: &'static str = "info";
+}
+
+// Request
+
+/// The month a [`DomainList`] selects domains by
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ListBy {
+    /// Domains registered in the month
+    Registration { year: i32, month: u32 },
+    /// Domains expiring in the month
+    Expiry { year: i32, month: u32 },
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "list", ns(XMLNS))]
+struct ListCriteria {
+    month: Option<String>,
+    expiry: Option<String>,
+}
+
+/// Nominet command listing the registrar's domains for a month
+#[derive(Debug, ToXml)]
+#[xml(rename = "info", ns(EPP_XMLNS))]
+pub struct DomainList {
+    list: ListCriteria,
+}
+
+impl DomainList {
+    pub fn new(by: ListBy) -> Self {
+        let list = match by {
+            ListBy::Registration { year, month } => ListCriteria {
+                month: Some(format!("{year:04}-{month:02}")),
+                expiry: None,
+            },
+            ListBy::Expiry { year, month } => ListCriteria {
+                month: None,
+                expiry: Some(format!("{year:04}-{month:02}")),
+            },
+        };
+        Self { list }
+    }
+
+    /// List the domains expiring in the month of `date`
+    pub fn expiring(date: NaiveDate) -> Self {
+        Self::new(ListBy::Expiry {
+            year: date.year(),
+            month: date.month(),
+        })
+    }
+}
+
+// Response
+
+/// The `<listData>` of a Nominet list response
+#[derive(Debug, FromXml)]
+#[xml(rename = "listData", ns(XMLNS))]
+pub struct ListData {
+    /// The number of domains listed
+    #[xml(attribute, rename = "noDomains")]
+    pub count: u32,
+    #[xml(rename = "domainName")]
+    pub domains: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{DomainList, ListBy};
+    use crate::response::ResultCode;
+    use crate::tests::{assert_serialized, response_from_file, CLTRID, SVTRID};
+
+    #[test]
+    fn command() {
+        let object = DomainList::new(ListBy::Expiry {
+            year: 2025,
+            month: 3,
+        });
+        assert_serialized("request/extensions/nominet_list.xml", &object);
+
+        let date = NaiveDate::from_ymd_opt(2025, 3, 17).unwrap();
+        assert_serialized(
+            "request/extensions/nominet_list.xml",
+            &DomainList::expiring(date),
+        );
+    }
+
+    #[test]
+    fn response() {
+        let object = response_from_file::<DomainList>("response/extensions/nominet_list.xml");
+        let result = object.res_data().unwrap();
+
+        assert_eq!(object.result.code, ResultCode::CommandCompletedSuccessfully);
+        assert_eq!(result.count, 2);
+        assert_eq!(result.domains, ["example1.co.uk", "example2.co.uk"]);
+        assert_eq!(object.tr_ids.client_tr_id.unwrap(), CLTRID);
+        assert_eq!(object.tr_ids.server_tr_id, SVTRID);
+    }
+}
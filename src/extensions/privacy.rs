@@ -0,0 +1,148 @@
+//! Mapping for registry-operated WHOIS privacy services
+//!
+//! Registries offering a privacy or proxy service let the registrar toggle it per domain. The
+//! flag is set on create, changed on update and reported in the info response, along with the
+//! proxy contact the registry publishes instead of the registrant when privacy is enabled.
+
+use std::fmt;
+
+use instant_xml::{FromXml, Id, Serializer, ToXml};
+
+use crate::common::{Boolean, NoExtension};
+use crate::domain::{DomainCreate, DomainInfo, DomainUpdate};
+
+pub const XMLNS: &str = "urn:ietf:params:xml:ns:epp:privacy-1.0";
+
+crate::impl_extension! {
+    impl Extension for Create {
+        type Response = NoExtension;
+        namespaces: XMLNS;
+        commands: DomainCreate<'_>;
+    }
+}
+
+crate::impl_extension! {
+    impl Extension for Update {
+        type Response = NoExtension;
+        namespaces: XMLNS;
+        commands: DomainUpdate<'_>;
+    }
+}
+
+crate::impl_extension! {
+    #[response_only]
+    impl Extension for PrivacyInfo {
+        type Response = InfoData;
+        commands: DomainInfo<'_>;
+    }
+}
+
+// Request
+
+/// Type for the `<create>` extension, setting the privacy flag of a new domain
+#[derive(Debug, ToXml)]
+#[xml(rename = "create", ns(XMLNS))]
+pub struct Create {
+    /// Whether the privacy service is enabled
+    #[xml(serialize_with = "serialize_enabled")]
+    pub enabled: bool,
+}
+
+impl Create {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+}
+
+/// Type for the `<update>` extension, enabling or disabling the privacy service
+#[derive(Debug, ToXml)]
+#[xml(rename = "update", ns(XMLNS))]
+pub struct Update {
+    /// Whether the privacy service is enabled
+    #[xml(serialize_with = "serialize_enabled")]
+    pub enabled: bool,
+}
+
+impl Update {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+}
+
+fn serialize_enabled<W: fmt::Write + ?Sized>(
+    value: &bool,
+    serializer: &mut Serializer<W>,
+) -> Result<(), instant_xml::Error> {
+    let id = Id {
+        ns: XMLNS,
+        name: "enabled",
+    };
+    Boolean(*value).serialize(Some(id), serializer)
+}
+
+/// Response-only extension to read the privacy settings from a domain info response
+#[derive(Debug, Eq, PartialEq)]
+pub struct PrivacyInfo;
+
+// Response
+
+/// Type that represents the `<infData>` tag for the privacy settings of a domain
+#[derive(Debug, FromXml)]
+#[xml(rename = "infData", ns(XMLNS))]
+pub struct InfoData {
+    /// Whether the privacy service is enabled
+    #[xml(deserialize_with = "crate::scalar::deserialize_bool")]
+    pub enabled: bool,
+    /// The ID of the proxy contact published in place of the registrant
+    pub proxy: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::update::DomainChangeInfo;
+    use crate::domain::{Period, PeriodLength};
+    use crate::tests::{assert_serialized, response_from_file_with_ext};
+
+    #[test]
+    fn create() {
+        let object = DomainCreate::new(
+            "eppdev-1.com",
+            Period::Years(PeriodLength::new(1).unwrap()),
+            None,
+            Some("eppdev-contact-3"),
+            "epP4uthd#v",
+            None,
+        );
+
+        assert_serialized(
+            "request/extensions/privacy_create.xml",
+            (&object, &Create::new(true)),
+        );
+    }
+
+    #[test]
+    fn update() {
+        let mut object = DomainUpdate::new("eppdev-1.com");
+        object.info(DomainChangeInfo {
+            registrant: None,
+            auth_info: None,
+        });
+
+        assert_serialized(
+            "request/extensions/privacy_update.xml",
+            (&object, &Update::new(false)),
+        );
+    }
+
+    #[test]
+    fn info_response() {
+        let object = response_from_file_with_ext::<DomainInfo, PrivacyInfo>(
+            "response/extensions/privacy_info.xml",
+        );
+        let data = object.extension().unwrap();
+
+        assert!(data.enabled);
+        assert_eq!(data.proxy.as_deref(), Some("privacy-proxy-1"));
+    }
+}
@@ -0,0 +1,146 @@
+//! Extensions given as raw XML
+//!
+//! Registries occasionally require an extension this crate has no type for, or a field a
+//! typed extension doesn't cover yet. [`RawExtension`] sends caller-provided XML in the
+//! `<extension>` element, alone or as part of a [`Composite`](super::composite::Composite),
+//! until typed support is available.
+
+use std::fmt::Write;
+
+use instant_xml::{Error, Id, Serializer, ToXml};
+
+use crate::common::NoExtension;
+use crate::contact::{ContactCreate, ContactDelete, ContactUpdate};
+use crate::domain::{
+    DomainCheck, DomainCreate, DomainDelete, DomainInfo, DomainRenew, DomainTransfer, DomainUpdate,
+};
+use crate::host::{HostCreate, HostDelete, HostUpdate};
+use crate::request::Extension;
+use crate::xml::{self, Token};
+
+/// One or more extension elements in the namespace `ns`, given as XML
+///
+/// `body` holds complete elements, like `<update><expMonthDay>--05-31</expMonthDay></update>`
+/// or the same with a prefix. Top-level elements that don't declare their namespace are
+/// declared in `ns`. The XML is not validated, and sent as is otherwise.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RawExtension {
+    pub ns: String,
+    pub body: String,
+}
+
+impl RawExtension {
+    pub fn new(ns: &str, body: &str) -> Self {
+        Self {
+            ns: ns.to_owned(),
+            body: body.to_owned(),
+        }
+    }
+}
+
+impl ToXml for RawExtension {
+    fn serialize<W: Write + ?Sized>(
+        &self,
+        _field: Option<Id<'_>>,
+        serializer: &mut Serializer<W>,
+    ) -> Result<(), Error> {
+        serializer.write_str(&declare_ns(&self.body, &self.ns))
+    }
+}
+
+impl Extension for RawExtension {
+    type Response = NoExtension;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![&self.ns])
+    }
+}
+
+crate::impl_extension! {
+    @transactions [] RawExtension;
+    DomainCheck<'_>, DomainInfo<'_>, DomainCreate<'_>, DomainDelete<'_>, DomainRenew<'_>,
+    DomainTransfer<'_>, DomainUpdate<'_>, ContactCreate<'_>, ContactDelete<'_>,
+    ContactUpdate<'_>, HostCreate<'_>, HostDelete<'_>, HostUpdate<'_>
+}
+
+// Add an `xmlns` (or `xmlns:prefix`) attribute for `ns` to the top-level start tags of `body`
+// that don't have one
+fn declare_ns(body: &str, ns: &str) -> String {
+    let ns = ns
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('"', "&quot;");
+    let mut out = String::with_capacity(body.len() + ns.len() + 16);
+    let mut depth = 0usize;
+    for token in xml::tokens(body) {
+        let tag = match token {
+            Token::Start(tag) => tag,
+            Token::End(_) => {
+                depth = depth.saturating_sub(1);
+                out.push_str(token.raw());
+                continue;
+            }
+            _ => {
+                out.push_str(token.raw());
+                continue;
+            }
+        };
+
+        let name = tag.name();
+        let attr = match name.split_once(':') {
+            Some((prefix, _)) => format!("xmlns:{prefix}"),
+            None => "xmlns".to_owned(),
+        };
+
+        if depth == 0 && !tag.attributes().any(|found| found.name == attr) {
+            let name_end = 1 + name.len();
+            out.push_str(&tag.0[..name_end]);
+            write!(out, " {attr}=\"{ns}\"").unwrap();
+            out.push_str(&tag.0[name_end..]);
+        } else {
+            out.push_str(tag.0);
+        }
+
+        if !tag.self_closing() {
+            depth += 1;
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{declare_ns, RawExtension};
+    use crate::domain::update::{DomainChangeInfo, DomainUpdate};
+    use crate::tests::assert_serialized;
+
+    #[test]
+    fn command() {
+        let ext = RawExtension::new(
+            "http://www.verisign.com/epp/sync-1.0",
+            "<update><expMonthDay>--05-31</expMonthDay></update>",
+        );
+
+        let mut object = DomainUpdate::new("eppdev.com");
+        object.info(DomainChangeInfo {
+            registrant: None,
+            auth_info: None,
+        });
+
+        assert_serialized("request/extensions/consolidate.xml", (&object, &ext));
+    }
+
+    #[test]
+    fn namespaces() {
+        let ns = "urn:example:acme-1.0";
+        assert_eq!(
+            declare_ns("<acme:create><acme:ref a='>'/></acme:create>", ns),
+            r#"<acme:create xmlns:acme="urn:example:acme-1.0"><acme:ref a='>'/></acme:create>"#
+        );
+        assert_eq!(
+            declare_ns(r#"<!-- note --><flag/><info xmlns="urn:other"/>"#, ns),
+            r#"<!-- note --><flag xmlns="urn:example:acme-1.0"/><info xmlns="urn:other"/>"#
+        );
+    }
+}
@@ -8,7 +8,7 @@ pub mod poll; // Technically a separate extension (different namespace, RFC)
 pub mod report;
 pub mod request;
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum RgpStatus {
     AddPeriod,
     AutoRenewPeriod,
@@ -1,9 +1,9 @@
 //! Types for EPP RGP restore report
 
-use chrono::{DateTime, SecondsFormat, Utc};
+use chrono::{DateTime, Utc};
 use instant_xml::ToXml;
 
-use crate::common::NoExtension;
+use crate::common::{NoExtension, Timestamp};
 use crate::domain::update::DomainUpdate;
 use crate::request::{Extension, Transaction};
 
@@ -27,8 +27,8 @@ impl<'a> RgpRestoreReport<'a> {
             report: RgpRestoreReportSectionData {
                 pre_data,
                 post_data,
-                deleted_at: deleted_at.to_rfc3339_opts(SecondsFormat::AutoSi, true),
-                restored_at: restored_at.to_rfc3339_opts(SecondsFormat::AutoSi, true),
+                deleted_at: deleted_at.into(),
+                restored_at: restored_at.into(),
                 restore_reason,
                 statements,
                 other,
@@ -39,6 +39,10 @@ impl<'a> RgpRestoreReport<'a> {
 
 impl Extension for Update<RgpRestoreReport<'_>> {
     type Response = NoExtension;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
 }
 
 #[derive(Debug, ToXml)]
@@ -59,10 +63,10 @@ pub struct RgpRestoreReportSectionData<'a> {
     post_data: &'a str,
     /// The domain deletion date
     #[xml(rename = "delTime")]
-    deleted_at: String,
+    deleted_at: Timestamp,
     /// The domain restore request date
     #[xml(rename = "resTime")]
-    restored_at: String,
+    restored_at: Timestamp,
     /// The reason for domain restoration
     #[xml(rename = "resReason")]
     restore_reason: &'a str,
@@ -15,6 +15,38 @@ impl<'a> Transaction<Update<RgpRestoreRequest<'a>>> for DomainInfo<'a> {}
 
 impl Extension for Update<RgpRestoreRequest<'_>> {
     type Response = RgpRequestResponse;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+}
+
+impl Transaction<RgpInfoExtension> for DomainInfo<'_> {}
+
+/// Response-only extension to read the `<rgp:infData>` from a domain info response
+///
+/// Nothing is sent to the server; registries that support RGP include the grace period
+/// statuses in every domain info response.
+#[derive(Debug, Eq, PartialEq)]
+pub struct RgpInfoExtension;
+
+impl ToXml for RgpInfoExtension {
+    fn serialize<W: std::fmt::Write + ?Sized>(
+        &self,
+        _field: Option<instant_xml::Id<'_>>,
+        _serializer: &mut instant_xml::Serializer<W>,
+    ) -> Result<(), instant_xml::Error> {
+        Ok(())
+    }
+
+    fn present(&self) -> bool {
+        false
+    }
+}
+
+impl Extension for RgpInfoExtension {
+    const DO_SEND: bool = false;
+    type Response = RgpRequestInfoResponse;
 }
 
 // Request
@@ -68,7 +100,7 @@ pub enum RgpRequestResponse {
 
 #[cfg(test)]
 mod tests {
-    use super::{RgpRestoreRequest, Update};
+    use super::{RgpInfoExtension, RgpRestoreRequest, Update};
     use crate::domain::info::DomainInfo;
     use crate::domain::update::{DomainChangeInfo, DomainUpdate};
     use crate::extensions::rgp::request::RgpRequestResponse;
@@ -131,4 +163,19 @@ mod tests {
         assert_eq!(data.rgp_status[0], RgpStatus::AddPeriod);
         assert_eq!(data.rgp_status[1], RgpStatus::RenewPeriod);
     }
+
+    #[test]
+    fn domain_info_extension() {
+        let object = DomainInfo::new("eppdev.com", Some("2fooBAR"));
+        assert_serialized("request/domain/info.xml", (&object, &RgpInfoExtension));
+
+        let object = response_from_file_with_ext::<DomainInfo, RgpInfoExtension>(
+            "response/extensions/domain_info_rgp.xml",
+        );
+        let data = object.extension().unwrap();
+        assert_eq!(
+            data.rgp_status,
+            [RgpStatus::AddPeriod, RgpStatus::RenewPeriod]
+        );
+    }
 }
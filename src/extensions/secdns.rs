@@ -1,13 +1,14 @@
 //! DNS security extensions mapping
 //!
-//! As described in [RFC 5910](https://www.rfc-editor.org/rfc/rfc5910)
-use instant_xml::{Error, Id, Serializer, ToXml};
+//! As described in [RFC 5910](https://www.rfc-editor.org/rfc/rfc5910). For registries that only
+//! support the older `secDNS-1.0` namespace, see the [`secdns10`](super::secdns10) module.
+use instant_xml::{Error, FromXml, Id, Serializer, ToXml};
 use std::borrow::Cow;
 use std::fmt::Write;
 use std::time::Duration;
 
-use crate::common::NoExtension;
-use crate::domain::DomainUpdate;
+use crate::common::{Boolean, NoExtension};
+use crate::domain::{DomainInfo, DomainUpdate};
 use crate::request::{Extension, Transaction};
 
 pub const XMLNS: &str = "urn:ietf:params:xml:ns:secDNS-1.1";
@@ -16,6 +17,10 @@ impl<'a> Transaction<CreateData<'a>> for crate::domain::create::DomainCreate<'a>
 
 impl Extension for CreateData<'_> {
     type Response = NoExtension;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
 }
 
 #[derive(Debug, ToXml)]
@@ -109,14 +114,14 @@ pub enum DsOrKeyData<'a> {
 #[xml(rename = "dsData", ns(XMLNS))]
 pub struct DsDataType<'a> {
     #[xml(rename = "keyTag")]
-    key_tag: u16,
+    pub(crate) key_tag: u16,
     #[xml(rename = "alg")]
-    algorithm: Algorithm,
+    pub(crate) algorithm: Algorithm,
     #[xml(rename = "digestType")]
-    digest_type: DigestAlgorithm,
-    digest: Cow<'a, str>,
+    pub(crate) digest_type: DigestAlgorithm,
+    pub(crate) digest: Cow<'a, str>,
     #[xml(rename = "keyData")]
-    key_data: Option<KeyDataType<'a>>,
+    pub(crate) key_data: Option<KeyDataType<'a>>,
 }
 
 impl<'a> DsDataType<'a> {
@@ -165,6 +170,19 @@ impl From<DigestAlgorithm> for u8 {
     }
 }
 
+impl From<u8> for DigestAlgorithm {
+    fn from(n: u8) -> Self {
+        match n {
+            1 => Self::Sha1,
+            2 => Self::Sha256,
+            3 => Self::Gost,
+            4 => Self::Sha384,
+            6 => Self::Sm3,
+            n => Self::Other(n),
+        }
+    }
+}
+
 impl ToXml for DigestAlgorithm {
     fn serialize<W: Write + ?Sized>(
         &self,
@@ -254,6 +272,34 @@ impl From<Algorithm> for u8 {
     }
 }
 
+impl From<u8> for Algorithm {
+    fn from(n: u8) -> Self {
+        match n {
+            0 => Self::Delete,
+            1 => Self::RsaMd5,
+            2 => Self::Dh,
+            3 => Self::Dsa,
+            4 => Self::Ecc,
+            5 => Self::RsaSha1,
+            6 => Self::DsaNsec3Sha1,
+            7 => Self::RsaSha1Nsec3Sha1,
+            8 => Self::RsaSha256,
+            10 => Self::RsaSha512,
+            12 => Self::EccGost,
+            13 => Self::EcdsaP256Sha256,
+            14 => Self::EcdsaP384Sha384,
+            15 => Self::Ed25519,
+            16 => Self::Ed448,
+            17 => Self::Sm2Sm3,
+            23 => Self::EccGost12,
+            252 => Self::Indirect,
+            253 => Self::PrivateDns,
+            254 => Self::PrivateOid,
+            n => Self::Other(n),
+        }
+    }
+}
+
 impl ToXml for Algorithm {
     fn serialize<W: Write + ?Sized>(
         &self,
@@ -267,12 +313,12 @@ impl ToXml for Algorithm {
 #[derive(Debug, ToXml)]
 #[xml(rename = "keyData", ns(XMLNS))]
 pub struct KeyDataType<'a> {
-    flags: Flags,
-    protocol: Protocol,
+    pub(crate) flags: Flags,
+    pub(crate) protocol: Protocol,
     #[xml(rename = "alg")]
-    algorithm: Algorithm,
+    pub(crate) algorithm: Algorithm,
     #[xml(rename = "pubKey")]
-    public_key: Cow<'a, str>,
+    pub(crate) public_key: Cow<'a, str>,
 }
 
 impl<'a> KeyDataType<'a> {
@@ -380,8 +426,9 @@ impl ToXml for Protocol {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::domain::update::DomainChangeInfo;
     use crate::domain::{self, Period, PeriodLength};
-    use crate::tests::assert_serialized;
+    use crate::tests::{assert_serialized, response_from_file_with_ext};
 
     #[test]
     fn create_ds_data_interface() {
@@ -513,16 +560,133 @@ mod tests {
             (&object, &extension),
         );
     }
+
+    #[test]
+    fn update_replace_all() {
+        let ds_data = [DsDataType::new(
+            12346,
+            Algorithm::Dsa,
+            DigestAlgorithm::Sha1,
+            "38EC35D5B3A34B44C39B",
+            None,
+        )];
+        let extension = UpdateData::builder()
+            .remove_all()
+            .add_ds(&ds_data)
+            .max_sig_life(Duration::from_secs(605900))
+            .urgent()
+            .build()
+            .unwrap();
+
+        let mut object = DomainUpdate::new("example.com");
+        object.info(DomainChangeInfo {
+            registrant: None,
+            auth_info: None,
+        });
+
+        assert_serialized(
+            "request/extensions/secdns_update_replace_all.xml",
+            (&object, &extension),
+        );
+    }
+
+    #[test]
+    fn update_invalid() {
+        let ds_data = [DsDataType::new(
+            12346,
+            Algorithm::Dsa,
+            DigestAlgorithm::Sha1,
+            "38EC35D5B3A34B44C39B",
+            None,
+        )];
+        let key_data = [KeyDataType::new(
+            FLAGS_DNS_ZONE_KEY_SEP,
+            Protocol::Dnssec,
+            Algorithm::RsaMd5,
+            "AQPJ////4Q==",
+        )];
+
+        assert!(UpdateData::builder().build().is_err());
+        assert!(UpdateData::builder()
+            .remove_all()
+            .remove_ds(&ds_data)
+            .build()
+            .is_err());
+        assert!(UpdateData::builder()
+            .remove_ds(&ds_data)
+            .add_keys(&key_data)
+            .build()
+            .is_err());
+        assert!(UpdateData::builder()
+            .remove_all()
+            .add_keys(&key_data)
+            .build()
+            .is_ok());
+    }
+
+    #[test]
+    fn info_ds_data() {
+        let object = response_from_file_with_ext::<DomainInfo, SecDnsInfoExtension>(
+            "response/extensions/domain_info_secdns.xml",
+        );
+        let data = object.extension().unwrap();
+        assert_eq!(data.ds_data.len(), 2);
+        assert_eq!(
+            data.ds_data[0],
+            DsRecord {
+                key_tag: 12345,
+                algorithm: 3,
+                digest_type: 1,
+                digest: "49FD46E6C4B45C55D4AC".into(),
+            }
+        );
+        assert!(data.ds_data[1].matches(&DsRecord {
+            key_tag: 12346,
+            algorithm: 13,
+            digest_type: 2,
+            digest: "E2D3C916F6DEEAC73294E8268FB5885044A833FC5459588F4A9184CFC41A5766".into(),
+        }));
+    }
 }
 
-/// secDNS <update> extension for DS management
-#[derive(Debug, ToXml)]
-#[xml(rename = "update", ns(XMLNS))]
+/// secDNS `<update>` extension
+///
+/// Use [`UpdateData::builder()`] to combine removals, additions and a new maximum signature
+/// lifetime in one command.
+#[derive(Debug)]
 pub struct UpdateData<'a> {
-    #[xml(rename = "add")]
-    pub add: Option<UpdateAdd<'a>>,
-    #[xml(rename = "rem")]
+    /// Whether the registry should process the update with high priority
+    pub urgent: Option<bool>,
     pub rem: Option<UpdateRem<'a>>,
+    pub add: Option<UpdateAdd<'a>>,
+    pub chg: Option<UpdateChg>,
+}
+
+impl ToXml for UpdateData<'_> {
+    fn serialize<W: Write + ?Sized>(
+        &self,
+        _: Option<Id<'_>>,
+        serializer: &mut Serializer<W>,
+    ) -> Result<(), Error> {
+        SerializeUpdate {
+            urgent: self.urgent.map(Boolean),
+            rem: self.rem.as_ref(),
+            add: self.add.as_ref(),
+            chg: self.chg.as_ref(),
+        }
+        .serialize(None, serializer)
+    }
+}
+
+// The `<update>` element as sent, with `urgent` in the configured boolean format
+#[derive(ToXml)]
+#[xml(rename = "update", ns(XMLNS))]
+struct SerializeUpdate<'a, 'b> {
+    #[xml(attribute)]
+    urgent: Option<Boolean>,
+    rem: Option<&'b UpdateRem<'a>>,
+    add: Option<&'b UpdateAdd<'a>>,
+    chg: Option<&'b UpdateChg>,
 }
 
 #[derive(Debug, ToXml)]
@@ -530,6 +694,9 @@ pub struct UpdateData<'a> {
 pub struct UpdateAdd<'a> {
     #[xml(rename = "dsData")]
     pub ds_data: &'a [DsDataType<'a>],
+    /// Key data to add, for registries using the key data interface
+    #[xml(rename = "keyData")]
+    pub key_data: &'a [KeyDataType<'a>],
 }
 
 #[derive(Debug, ToXml)]
@@ -537,36 +704,196 @@ pub struct UpdateAdd<'a> {
 pub struct UpdateRem<'a> {
     #[xml(rename = "dsData")]
     pub ds_data: Option<&'a [DsDataType<'a>]>,
+    /// Key data to remove, for registries using the key data interface
+    #[xml(rename = "keyData")]
+    pub key_data: Option<&'a [KeyDataType<'a>]>,
     /// When `all` is `Some(true)`, all DS records should be removed
+    #[xml(serialize_with = "serialize_all")]
     pub all: Option<bool>,
 }
 
+fn serialize_all<W: Write + ?Sized>(
+    value: &Option<bool>,
+    serializer: &mut Serializer<W>,
+) -> Result<(), Error> {
+    let id = Id {
+        ns: XMLNS,
+        name: "all",
+    };
+    value.map(Boolean).serialize(Some(id), serializer)
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "chg", ns(XMLNS))]
+pub struct UpdateChg {
+    /// The new maximum signature lifetime, in seconds
+    #[xml(rename = "maxSigLife")]
+    pub maximum_signature_lifetime: u64,
+}
+
 impl<'a> UpdateData<'a> {
+    pub fn builder() -> UpdateBuilder<'a> {
+        UpdateBuilder::default()
+    }
+
     pub fn add_ds(ds: &'a [DsDataType<'a>]) -> Self {
         Self {
-            add: Some(UpdateAdd { ds_data: ds }),
+            urgent: None,
             rem: None,
+            add: Some(UpdateAdd {
+                ds_data: ds,
+                key_data: &[],
+            }),
+            chg: None,
         }
     }
 
     pub fn rem_ds(ds: &'a [DsDataType<'a>]) -> Self {
         Self {
-            add: None,
+            urgent: None,
             rem: Some(UpdateRem {
                 ds_data: Some(ds),
+                key_data: None,
                 all: None,
             }),
+            add: None,
+            chg: None,
         }
     }
 
     pub fn rem_all() -> Self {
         Self {
-            add: None,
+            urgent: None,
             rem: Some(UpdateRem {
                 ds_data: None,
+                key_data: None,
                 all: Some(true),
             }),
+            add: None,
+            chg: None,
+        }
+    }
+}
+
+/// Builder for [`UpdateData`] that rejects combinations RFC 5910 does not allow
+///
+/// Removals are sent before additions, so `remove_all()` combined with `add_ds()` or
+/// `add_keys()` replaces the full set of records in a single command. The DS data and
+/// key data interfaces can't be mixed within one update.
+#[derive(Debug, Default)]
+pub struct UpdateBuilder<'a> {
+    urgent: bool,
+    rem_all: bool,
+    rem: Option<DsOrKeyData<'a>>,
+    add: Option<DsOrKeyData<'a>>,
+    maximum_signature_lifetime: Option<Duration>,
+}
+
+impl<'a> UpdateBuilder<'a> {
+    /// Remove all existing DS or key data
+    pub fn remove_all(mut self) -> Self {
+        self.rem_all = true;
+        self
+    }
+
+    /// Remove the given DS records
+    pub fn remove_ds(mut self, ds: &'a [DsDataType<'a>]) -> Self {
+        self.rem = Some(DsOrKeyData::DsData(ds));
+        self
+    }
+
+    /// Remove the given key data
+    pub fn remove_keys(mut self, keys: &'a [KeyDataType<'a>]) -> Self {
+        self.rem = Some(DsOrKeyData::KeyData(keys));
+        self
+    }
+
+    /// Add the given DS records
+    pub fn add_ds(mut self, ds: &'a [DsDataType<'a>]) -> Self {
+        self.add = Some(DsOrKeyData::DsData(ds));
+        self
+    }
+
+    /// Add the given key data
+    pub fn add_keys(mut self, keys: &'a [KeyDataType<'a>]) -> Self {
+        self.add = Some(DsOrKeyData::KeyData(keys));
+        self
+    }
+
+    /// Change the maximum signature lifetime, if the registry permits it
+    pub fn max_sig_life(mut self, maximum_signature_lifetime: Duration) -> Self {
+        self.maximum_signature_lifetime = Some(maximum_signature_lifetime);
+        self
+    }
+
+    /// Request high priority processing of the update
+    pub fn urgent(mut self) -> Self {
+        self.urgent = true;
+        self
+    }
+
+    pub fn build(self) -> Result<UpdateData<'a>, crate::Error> {
+        if let (Some(rem), Some(add)) = (&self.rem, &self.add) {
+            if rem.is_ds() != add.is_ds() {
+                return Err(crate::Error::Other(
+                    "cannot mix the DS data and key data interfaces in one update".into(),
+                ));
+            }
+        }
+
+        let rem = match (self.rem_all, self.rem) {
+            (true, Some(_)) => {
+                return Err(crate::Error::Other(
+                    "cannot remove all records and specific records in one update".into(),
+                ))
+            }
+            (true, None) => Some(UpdateRem {
+                ds_data: None,
+                key_data: None,
+                all: Some(true),
+            }),
+            (false, Some(DsOrKeyData::DsData(ds))) => Some(UpdateRem {
+                ds_data: Some(ds),
+                key_data: None,
+                all: None,
+            }),
+            (false, Some(DsOrKeyData::KeyData(keys))) => Some(UpdateRem {
+                ds_data: None,
+                key_data: Some(keys),
+                all: None,
+            }),
+            (false, None) => None,
+        };
+
+        let chg = self.maximum_signature_lifetime.map(|lifetime| UpdateChg {
+            maximum_signature_lifetime: lifetime.as_secs(),
+        });
+
+        if rem.is_none() && self.add.is_none() && chg.is_none() {
+            return Err(crate::Error::Other("empty secDNS update".into()));
         }
+
+        Ok(UpdateData {
+            urgent: self.urgent.then_some(true),
+            rem,
+            add: self.add.map(|data| match data {
+                DsOrKeyData::DsData(ds) => UpdateAdd {
+                    ds_data: ds,
+                    key_data: &[],
+                },
+                DsOrKeyData::KeyData(keys) => UpdateAdd {
+                    ds_data: &[],
+                    key_data: keys,
+                },
+            }),
+            chg,
+        })
+    }
+}
+
+impl DsOrKeyData<'_> {
+    fn is_ds(&self) -> bool {
+        matches!(self, Self::DsData(_))
     }
 }
 
@@ -574,4 +901,78 @@ impl<'a> Transaction<UpdateData<'a>> for DomainUpdate<'a> {}
 
 impl Extension for UpdateData<'_> {
     type Response = NoExtension;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+}
+
+impl Transaction<SecDnsInfoExtension> for DomainInfo<'_> {}
+
+/// Response-only extension to read the `<secDNS:infData>` from a domain info response
+///
+/// Nothing is sent to the server. Only DS data is read; registries using the key data
+/// interface return an empty list.
+#[derive(Debug, Eq, PartialEq)]
+pub struct SecDnsInfoExtension;
+
+impl ToXml for SecDnsInfoExtension {
+    fn serialize<W: Write + ?Sized>(
+        &self,
+        _field: Option<Id<'_>>,
+        _serializer: &mut Serializer<'_, W>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn present(&self) -> bool {
+        false
+    }
+}
+
+impl Extension for SecDnsInfoExtension {
+    const DO_SEND: bool = false;
+    type Response = InfoData;
+}
+
+/// The `<secDNS:infData>` of a domain info response
+#[derive(Debug, FromXml)]
+#[xml(rename = "infData", ns(XMLNS))]
+pub struct InfoData {
+    pub ds_data: Vec<DsRecord>,
+}
+
+/// A DS record of a domain, in an owned form that can be read from info responses
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
+#[xml(rename = "dsData", ns(XMLNS))]
+pub struct DsRecord {
+    #[xml(rename = "keyTag")]
+    pub key_tag: u16,
+    #[xml(rename = "alg")]
+    pub algorithm: u8,
+    #[xml(rename = "digestType")]
+    pub digest_type: u8,
+    /// The digest, in hexadecimal
+    pub digest: String,
+}
+
+impl DsRecord {
+    /// Whether both describe the same record, ignoring the case of the digest
+    pub fn matches(&self, other: &Self) -> bool {
+        self.key_tag == other.key_tag
+            && self.algorithm == other.algorithm
+            && self.digest_type == other.digest_type
+            && self.digest.eq_ignore_ascii_case(&other.digest)
+    }
+
+    /// The record in the form used for create and update commands
+    pub fn to_ds_data(&self) -> DsDataType<'_> {
+        DsDataType::new(
+            self.key_tag,
+            Algorithm::from(self.algorithm),
+            DigestAlgorithm::from(self.digest_type),
+            self.digest.as_str(),
+            None,
+        )
+    }
 }
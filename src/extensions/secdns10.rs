@@ -0,0 +1,291 @@
+//! DNS security extensions mapping, version 1.0
+//!
+//! As described in [RFC 4310](https://www.rfc-editor.org/rfc/rfc4310). Some registries still only
+//! accept this older namespace. The types here are built from the same [`DsDataType`] and
+//! [`KeyDataType`] values as the [`secdns`](super::secdns) extension, so call sites only need
+//! to pick the extension type matching the registry's greeting.
+//!
+//! Compared to secDNS-1.1, version 1.0 only supports the DS data interface, carries the maximum
+//! signature lifetime inside each `<dsData>` element, removes DS records by key tag and
+//! replaces the full set of DS records with `<chg>` instead of offering `<rem><all>`.
+
+use std::time::Duration;
+
+use instant_xml::ToXml;
+
+use super::secdns::{Algorithm, DigestAlgorithm, DsDataType, Flags, KeyDataType, Protocol};
+use crate::common::{Boolean, NoExtension};
+use crate::domain::{DomainCreate, DomainUpdate};
+use crate::request::{Extension, Transaction};
+
+pub const XMLNS: &str = "urn:ietf:params:xml:ns:secDNS-1.0";
+
+impl<'a> Transaction<CreateData<'a>> for DomainCreate<'a> {}
+
+impl Extension for CreateData<'_> {
+    type Response = NoExtension;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+}
+
+impl<'a> Transaction<UpdateData<'a>> for DomainUpdate<'a> {}
+
+impl Extension for UpdateData<'_> {
+    type Response = NoExtension;
+
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        Some(vec![XMLNS])
+    }
+}
+
+/// secDNS-1.0 `<create>` extension
+#[derive(Debug, ToXml)]
+#[xml(rename = "create", ns(XMLNS))]
+pub struct CreateData<'a> {
+    #[xml(rename = "dsData")]
+    ds_data: Vec<DsData<'a>>,
+}
+
+impl<'a> From<&'a [DsDataType<'a>]> for CreateData<'a> {
+    fn from(ds_data: &'a [DsDataType<'a>]) -> Self {
+        Self {
+            ds_data: DsData::list(ds_data, None),
+        }
+    }
+}
+
+impl<'a> From<(Duration, &'a [DsDataType<'a>])> for CreateData<'a> {
+    fn from((maximum_signature_lifetime, ds_data): (Duration, &'a [DsDataType<'a>])) -> Self {
+        Self {
+            ds_data: DsData::list(ds_data, Some(maximum_signature_lifetime)),
+        }
+    }
+}
+
+/// secDNS-1.0 `<update>` extension
+///
+/// Unlike secDNS-1.1, each update performs exactly one of adding, removing or replacing
+/// DS records.
+#[derive(Debug, ToXml)]
+#[xml(rename = "update", ns(XMLNS))]
+pub struct UpdateData<'a> {
+    /// Whether the registry should process the update with high priority
+    #[xml(attribute)]
+    urgent: Option<Boolean>,
+    change: UpdateChange<'a>,
+}
+
+impl<'a> UpdateData<'a> {
+    /// Add the given DS records
+    pub fn add_ds(ds_data: &'a [DsDataType<'a>]) -> Self {
+        Self {
+            urgent: None,
+            change: UpdateChange::Add(Add {
+                ds_data: DsData::list(ds_data, None),
+            }),
+        }
+    }
+
+    /// Remove the DS records with the given key tags
+    pub fn rem_ds(key_tags: &'a [u16]) -> Self {
+        Self {
+            urgent: None,
+            change: UpdateChange::Rem(Rem { key_tags }),
+        }
+    }
+
+    /// Replace all DS records with the given ones
+    pub fn chg_ds(ds_data: &'a [DsDataType<'a>]) -> Self {
+        Self {
+            urgent: None,
+            change: UpdateChange::Chg(Chg {
+                ds_data: DsData::list(ds_data, None),
+            }),
+        }
+    }
+
+    /// Request high priority processing of the update
+    pub fn urgent(mut self) -> Self {
+        self.urgent = Some(Boolean(true));
+        self
+    }
+}
+
+#[derive(Debug, ToXml)]
+#[xml(forward)]
+enum UpdateChange<'a> {
+    Add(Add<'a>),
+    Chg(Chg<'a>),
+    Rem(Rem<'a>),
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "add", ns(XMLNS))]
+struct Add<'a> {
+    #[xml(rename = "dsData")]
+    ds_data: Vec<DsData<'a>>,
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "chg", ns(XMLNS))]
+struct Chg<'a> {
+    #[xml(rename = "dsData")]
+    ds_data: Vec<DsData<'a>>,
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "rem", ns(XMLNS))]
+struct Rem<'a> {
+    #[xml(rename = "keyTag")]
+    key_tags: &'a [u16],
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "dsData", ns(XMLNS))]
+struct DsData<'a> {
+    #[xml(rename = "keyTag")]
+    key_tag: u16,
+    #[xml(rename = "alg")]
+    algorithm: Algorithm,
+    #[xml(rename = "digestType")]
+    digest_type: DigestAlgorithm,
+    digest: &'a str,
+    #[xml(rename = "maxSigLife")]
+    maximum_signature_lifetime: Option<u64>,
+    #[xml(rename = "keyData")]
+    key_data: Option<KeyData<'a>>,
+}
+
+impl<'a> DsData<'a> {
+    fn list(ds_data: &'a [DsDataType<'a>], max_sig_life: Option<Duration>) -> Vec<Self> {
+        ds_data
+            .iter()
+            .map(|ds| Self {
+                key_tag: ds.key_tag,
+                algorithm: ds.algorithm,
+                digest_type: ds.digest_type,
+                digest: &ds.digest,
+                maximum_signature_lifetime: max_sig_life.map(|d| d.as_secs()),
+                key_data: ds.key_data.as_ref().map(KeyData::from),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, ToXml)]
+#[xml(rename = "keyData", ns(XMLNS))]
+struct KeyData<'a> {
+    flags: Flags,
+    protocol: Protocol,
+    #[xml(rename = "alg")]
+    algorithm: Algorithm,
+    #[xml(rename = "pubKey")]
+    public_key: &'a str,
+}
+
+impl<'a> From<&'a KeyDataType<'a>> for KeyData<'a> {
+    fn from(key: &'a KeyDataType<'a>) -> Self {
+        Self {
+            flags: key.flags,
+            protocol: key.protocol,
+            algorithm: key.algorithm,
+            public_key: &key.public_key,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::update::DomainChangeInfo;
+    use crate::domain::{self, Period, PeriodLength};
+    use crate::extensions::secdns::FLAGS_DNS_ZONE_KEY_SEP;
+    use crate::tests::assert_serialized;
+
+    #[test]
+    fn create() {
+        let key_data = KeyDataType::new(
+            FLAGS_DNS_ZONE_KEY_SEP,
+            Protocol::Dnssec,
+            Algorithm::Dsa,
+            "AQPJ////4Q==",
+        );
+        let ds_data = [DsDataType::new(
+            12345,
+            Algorithm::Dsa,
+            DigestAlgorithm::Sha1,
+            "49FD46E6C4B45C55D4AC",
+            Some(key_data),
+        )];
+        let extension = CreateData::from((Duration::from_secs(604800), ds_data.as_ref()));
+        let ns = [
+            domain::HostInfo::Obj(domain::HostObj {
+                name: "ns1.example.com".into(),
+            }),
+            domain::HostInfo::Obj(domain::HostObj {
+                name: "ns2.example.com".into(),
+            }),
+        ];
+        let contact = [
+            domain::DomainContact {
+                contact_type: "admin".into(),
+                id: "sh8013".into(),
+            },
+            domain::DomainContact {
+                contact_type: "tech".into(),
+                id: "sh8013".into(),
+            },
+        ];
+        let object = domain::DomainCreate::new(
+            "example.com",
+            Period::Years(PeriodLength::new(2).unwrap()),
+            Some(&ns),
+            Some("jd1234"),
+            "2fooBAR",
+            Some(&contact),
+        );
+        assert_serialized(
+            "request/extensions/secdns10_create.xml",
+            (&object, &extension),
+        );
+    }
+
+    #[test]
+    fn update_rem() {
+        let mut object = DomainUpdate::new("example.com");
+        object.info(DomainChangeInfo {
+            registrant: None,
+            auth_info: None,
+        });
+
+        let extension = UpdateData::rem_ds(&[12345, 12346]).urgent();
+        assert_serialized(
+            "request/extensions/secdns10_update_rem.xml",
+            (&object, &extension),
+        );
+    }
+
+    #[test]
+    fn update_chg() {
+        let mut object = DomainUpdate::new("example.com");
+        object.info(DomainChangeInfo {
+            registrant: None,
+            auth_info: None,
+        });
+
+        let ds_data = [DsDataType::new(
+            12346,
+            Algorithm::Dsa,
+            DigestAlgorithm::Sha1,
+            "38EC35D5B3A34B44C39B",
+            None,
+        )];
+        let extension = UpdateData::chg_ds(&ds_data);
+        assert_serialized(
+            "request/extensions/secdns10_update_chg.xml",
+            (&object, &extension),
+        );
+    }
+}
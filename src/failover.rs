@@ -0,0 +1,249 @@
+//! Failover between registry endpoints
+//!
+//! Several registries publish a secondary EPP host to use when the primary one is
+//! unavailable, with the expectation that clients return to the primary once it recovers.
+//! [`FailoverConnector`] wraps one [`Connector`] per endpoint: it keeps using the active
+//! endpoint until connecting to it has failed a number of times in a row, then moves on to the
+//! next one in the list. Failures are counted per endpoint, so moving on to an endpoint that
+//! has already failed that often moves on again after a single failed attempt. While connected
+//! to a fallback, the primary is tried again on reconnect once the probe interval has passed.
+
+use std::sync::Mutex;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use tokio::time::Instant;
+use tracing::{info, warn};
+
+use crate::connection::Connector;
+use crate::error::Error;
+
+/// A [`Connector`] failing over between an ordered list of endpoints
+pub struct FailoverConnector<C> {
+    endpoints: Vec<C>,
+    max_failures: u32,
+    probe_interval: Duration,
+    state: Mutex<FailoverState>,
+}
+
+impl<C: Connector> FailoverConnector<C> {
+    /// Create a connector using `primary` until it fails
+    ///
+    /// By default, the connector fails over after 3 consecutive failures and probes the
+    /// primary endpoint every 5 minutes while it is not active.
+    pub fn new(primary: C) -> Self {
+        Self {
+            endpoints: vec![primary],
+            max_failures: 3,
+            probe_interval: Duration::from_secs(300),
+            state: Mutex::new(FailoverState {
+                active: 0,
+                failures: vec![0],
+                last_probe: None,
+            }),
+        }
+    }
+
+    /// Add an endpoint to fail over to, after the ones added before
+    pub fn with_fallback(mut self, connector: C) -> Self {
+        self.endpoints.push(connector);
+        self.state.get_mut().unwrap().failures.push(0);
+        self
+    }
+
+    /// Fail over after `max_failures` consecutive connection failures (at least 1)
+    pub fn with_max_failures(mut self, max_failures: u32) -> Self {
+        self.max_failures = max_failures.max(1);
+        self
+    }
+
+    /// Try to return to the primary endpoint at most once per `interval`
+    pub fn with_probe_interval(mut self, interval: Duration) -> Self {
+        self.probe_interval = interval;
+        self
+    }
+
+    /// The index of the endpoint currently in use, where 0 is the primary
+    pub fn active(&self) -> usize {
+        self.state.lock().unwrap().active
+    }
+
+    /// The connector for the endpoint currently in use
+    pub fn active_endpoint(&self) -> &C {
+        &self.endpoints[self.active()]
+    }
+
+    // Decide whether the primary endpoint is due to be probed, and if so, record the attempt
+    fn probe_due(&self) -> bool {
+        let mut state = self.state.lock().unwrap();
+        if state.active == 0 {
+            return false;
+        }
+
+        let now = Instant::now();
+        match state.last_probe {
+            Some(last) if now.duration_since(last) < self.probe_interval => false,
+            _ => {
+                state.last_probe = Some(now);
+                true
+            }
+        }
+    }
+
+    // Record a failure of the endpoint at `index`, returning the next endpoint to try (if any)
+    fn record_failure(&self, index: usize, tried: usize) -> Option<usize> {
+        let mut state = self.state.lock().unwrap();
+        state.failures[index] += 1;
+        if state.failures[index] < self.max_failures || tried >= self.endpoints.len() {
+            return None;
+        }
+
+        state.active = (index + 1) % self.endpoints.len();
+        state.last_probe = Some(Instant::now());
+        warn!("failing over to endpoint {}", state.active);
+        Some(state.active)
+    }
+}
+
+#[async_trait]
+impl<C> Connector for FailoverConnector<C>
+where
+    C: Connector + Send + Sync,
+    C::Connection: Send,
+{
+    type Connection = C::Connection;
+
+    async fn connect(&self, timeout: Duration) -> Result<Self::Connection, Error> {
+        if self.probe_due() {
+            match self.endpoints[0].connect(timeout).await {
+                Ok(connection) => {
+                    info!("primary endpoint recovered");
+                    let mut state = self.state.lock().unwrap();
+                    state.active = 0;
+                    state.failures[0] = 0;
+                    state.last_probe = None;
+                    return Ok(connection);
+                }
+                Err(err) => {
+                    warn!("primary endpoint still unavailable: {err}");
+                    self.state.lock().unwrap().failures[0] += 1;
+                }
+            }
+        }
+
+        let mut index = self.active();
+        let mut tried = 1;
+        loop {
+            let err = match self.endpoints[index].connect(timeout).await {
+                Ok(connection) => {
+                    self.state.lock().unwrap().failures[index] = 0;
+                    return Ok(connection);
+                }
+                Err(err) => err,
+            };
+
+            warn!("failed to connect to endpoint {index}: {err}");
+            match self.record_failure(index, tried) {
+                Some(next) => {
+                    index = next;
+                    tried += 1;
+                }
+                None => return Err(err),
+            }
+        }
+    }
+}
+
+struct FailoverState {
+    // The index of the endpoint in use
+    active: usize,
+    // Consecutive connection failures of each endpoint
+    failures: Vec<u32>,
+    // When the primary endpoint was last tried while a fallback was active
+    last_probe: Option<Instant>,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use async_trait::async_trait;
+    use tokio_test::io::{Builder, Mock};
+
+    use super::FailoverConnector;
+    use crate::connection::Connector;
+    use crate::Error;
+
+    struct FakeConnector {
+        up: Arc<AtomicBool>,
+    }
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            if self.up.load(Ordering::SeqCst) {
+                Ok(Builder::new().build())
+            } else {
+                Err(io::Error::from(io::ErrorKind::ConnectionRefused).into())
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn fail_over_and_recover() {
+        let primary = Arc::new(AtomicBool::new(false));
+        let connector = FailoverConnector::new(FakeConnector {
+            up: primary.clone(),
+        })
+        .with_fallback(FakeConnector {
+            up: Arc::new(AtomicBool::new(true)),
+        })
+        .with_max_failures(2)
+        .with_probe_interval(Duration::ZERO);
+
+        let timeout = Duration::from_secs(1);
+        assert!(connector.connect(timeout).await.is_err());
+        assert_eq!(connector.active(), 0);
+
+        // The second failure in a row switches to the fallback within the same attempt
+        connector.connect(timeout).await.unwrap();
+        assert_eq!(connector.active(), 1);
+
+        // The primary is probed on the next connection attempt and used once it is back
+        primary.store(true, Ordering::SeqCst);
+        connector.connect(timeout).await.unwrap();
+        assert_eq!(connector.active(), 0);
+    }
+
+    #[tokio::test]
+    async fn failures_per_endpoint() {
+        let up = [false, false, true].map(|up| Arc::new(AtomicBool::new(up)));
+        let connector = FailoverConnector::new(FakeConnector { up: up[0].clone() })
+            .with_fallback(FakeConnector { up: up[1].clone() })
+            .with_fallback(FakeConnector { up: up[2].clone() })
+            .with_max_failures(2)
+            .with_probe_interval(Duration::from_secs(3600));
+
+        let timeout = Duration::from_secs(1);
+        assert!(connector.connect(timeout).await.is_err());
+        assert!(connector.connect(timeout).await.is_err());
+        assert_eq!(connector.active(), 1);
+        connector.connect(timeout).await.unwrap();
+        assert_eq!(connector.active(), 2);
+
+        // The last endpoint goes down and the second one comes back
+        up[2].store(false, Ordering::SeqCst);
+        up[1].store(true, Ordering::SeqCst);
+        assert!(connector.connect(timeout).await.is_err());
+        assert_eq!(connector.active(), 2);
+
+        // Wrapping around, the primary has already failed twice, so a single failure moves on
+        connector.connect(timeout).await.unwrap();
+        assert_eq!(connector.active(), 1);
+    }
+}
@@ -0,0 +1,259 @@
+//! The EPP frame format over TCP
+//!
+//! [RFC 5734](https://tools.ietf.org/html/rfc5734#section-4) prefixes each EPP document with
+//! its total length (including the 4-byte prefix itself) as a 32-bit big-endian integer. The
+//! client does this internally; this module exposes the encoding for gateways, proxies and
+//! tools reading captured sessions, without needing a connection or an async runtime.
+//!
+//! The codec works on plain byte buffers, so it can back any framing abstraction. With the
+//! `codec` feature, [`FrameCodec`] implements the `tokio_util::codec` traits on top of it, for
+//! use with `Framed` on any `AsyncRead + AsyncWrite` stream.
+//!
+//! ```
+//! use instant_epp::frame::{encode, FrameDecoder};
+//!
+//! let mut buf = Vec::new();
+//! encode("<epp/>", &mut buf).unwrap();
+//! encode("<epp></epp>", &mut buf).unwrap();
+//! buf.truncate(buf.len() - 1);
+//!
+//! let mut decoder = FrameDecoder::new();
+//! assert_eq!(decoder.decode(&mut buf).unwrap().as_deref(), Some("<epp/>"));
+//! // The second frame is incomplete
+//! assert_eq!(decoder.decode(&mut buf).unwrap(), None);
+//! ```
+
+use std::error::Error as StdError;
+use std::fmt;
+
+#[cfg(feature = "codec")]
+use bytes::BytesMut;
+#[cfg(feature = "codec")]
+use tokio_util::codec::{Decoder, Encoder};
+
+/// The length of the frame header
+pub const HEADER_LEN: usize = 4;
+
+/// The largest frame [`FrameDecoder::new()`] accepts, 16 MiB
+///
+/// Even large poll messages and domain lists are far below this; a bigger length usually
+/// means the stream is not EPP (for example TLS records read as plain text).
+pub const DEFAULT_MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+/// Append the frame for the document `xml` to `dst`
+pub fn encode(xml: &str, dst: &mut Vec<u8>) -> Result<(), FrameError> {
+    dst.reserve(xml.len() + HEADER_LEN);
+    dst.extend_from_slice(&header(xml)?);
+    dst.extend_from_slice(xml.as_bytes());
+    Ok(())
+}
+
+fn header(xml: &str) -> Result<[u8; HEADER_LEN], FrameError> {
+    let len = xml.len() + HEADER_LEN;
+    let header = u32::try_from(len).map_err(|_| FrameError::TooLong(len))?;
+    Ok(header.to_be_bytes())
+}
+
+/// Splits a byte stream into EPP documents
+#[derive(Clone, Debug)]
+pub struct FrameDecoder {
+    max_len: usize,
+}
+
+impl FrameDecoder {
+    /// A decoder accepting frames up to [`DEFAULT_MAX_FRAME_LEN`]
+    pub fn new() -> Self {
+        Self {
+            max_len: DEFAULT_MAX_FRAME_LEN,
+        }
+    }
+
+    /// Reject frames longer than `max_len` bytes, including the header
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Remove the first complete frame from `src` and return its document
+    ///
+    /// Returns `None` (leaving `src` as it is) if `src` doesn't hold a complete frame yet. After
+    /// an error the stream can't be resynchronized, since the frame boundaries are unknown.
+    pub fn decode(&mut self, src: &mut Vec<u8>) -> Result<Option<String>, FrameError> {
+        let Some(len) = self.frame_len(src)? else {
+            return Ok(None);
+        };
+
+        if src.len() < len {
+            return Ok(None);
+        }
+
+        let frame = src.drain(..len).skip(HEADER_LEN).collect::<Vec<_>>();
+        document(frame).map(Some)
+    }
+
+    /// The length of the frame at the start of `src`, if its header is complete
+    pub fn frame_len(&self, src: &[u8]) -> Result<Option<usize>, FrameError> {
+        let Some(header) = src.get(..HEADER_LEN) else {
+            return Ok(None);
+        };
+
+        let len = u32::from_be_bytes(header.try_into().unwrap()) as usize;
+        match len {
+            _ if len < HEADER_LEN => Err(FrameError::TooShort(len)),
+            _ if len > self.max_len => Err(FrameError::TooLong(len)),
+            _ => Ok(Some(len)),
+        }
+    }
+}
+
+impl Default for FrameDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn document(frame: Vec<u8>) -> Result<String, FrameError> {
+    String::from_utf8(frame).map_err(|_| FrameError::InvalidUtf8)
+}
+
+/// A `tokio_util` codec for EPP frames, built on [`FrameDecoder`] and [`encode()`]
+///
+/// Requires the `codec` feature.
+#[cfg(feature = "codec")]
+#[derive(Clone, Debug, Default)]
+pub struct FrameCodec {
+    decoder: FrameDecoder,
+}
+
+#[cfg(feature = "codec")]
+impl FrameCodec {
+    /// A codec accepting frames up to [`DEFAULT_MAX_FRAME_LEN`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reject frames longer than `max_len` bytes, including the header
+    pub fn max_len(mut self, max_len: usize) -> Self {
+        self.decoder = self.decoder.max_len(max_len);
+        self
+    }
+}
+
+#[cfg(feature = "codec")]
+impl Decoder for FrameCodec {
+    type Item = String;
+    type Error = crate::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<String>, Self::Error> {
+        let Some(len) = self.decoder.frame_len(src)? else {
+            return Ok(None);
+        };
+
+        if src.len() < len {
+            src.reserve(len - src.len());
+            return Ok(None);
+        }
+
+        let frame = src.split_to(len);
+        Ok(Some(document(frame[HEADER_LEN..].to_vec())?))
+    }
+}
+
+#[cfg(feature = "codec")]
+impl Encoder<&str> for FrameCodec {
+    type Error = crate::Error;
+
+    fn encode(&mut self, xml: &str, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        dst.reserve(xml.len() + HEADER_LEN);
+        dst.extend_from_slice(&header(xml)?);
+        dst.extend_from_slice(xml.as_bytes());
+        Ok(())
+    }
+}
+
+/// A frame that can't be encoded or decoded
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FrameError {
+    /// The length in the header is less than the length of the header itself
+    TooShort(usize),
+    /// The frame is longer than allowed (or than a 32-bit length can express)
+    TooLong(usize),
+    /// The document is not valid UTF-8
+    InvalidUtf8,
+}
+
+impl fmt::Display for FrameError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TooShort(len) => write!(f, "invalid frame length {len}"),
+            Self::TooLong(len) => write!(f, "frame of {len} bytes is too long"),
+            Self::InvalidUtf8 => write!(f, "frame is not valid UTF-8"),
+        }
+    }
+}
+
+impl StdError for FrameError {}
+
+impl From<FrameError> for crate::Error {
+    fn from(err: FrameError) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode, FrameDecoder, FrameError};
+
+    #[test]
+    fn decode() {
+        let mut buf = Vec::new();
+        encode("<epp/>", &mut buf).unwrap();
+        assert_eq!(buf[..4], [0, 0, 0, 10]);
+
+        let mut decoder = FrameDecoder::new();
+        let mut partial = buf[..3].to_vec();
+        assert_eq!(decoder.decode(&mut partial), Ok(None));
+        assert_eq!(partial.len(), 3);
+
+        assert_eq!(decoder.decode(&mut buf), Ok(Some("<epp/>".to_owned())));
+        assert!(buf.is_empty());
+
+        let mut short = vec![0, 0, 0, 2];
+        assert_eq!(decoder.decode(&mut short), Err(FrameError::TooShort(2)));
+
+        let mut decoder = decoder.max_len(8);
+        encode("<epp/>", &mut buf).unwrap();
+        assert_eq!(decoder.decode(&mut buf), Err(FrameError::TooLong(10)));
+    }
+
+    #[cfg(feature = "codec")]
+    #[test]
+    fn codec() {
+        use bytes::BytesMut;
+        use tokio_util::codec::{Decoder, Encoder};
+
+        use super::FrameCodec;
+
+        let mut codec = FrameCodec::new();
+        let mut buf = BytesMut::new();
+        codec.encode("<epp/>", &mut buf).unwrap();
+        codec.encode("<epp></epp>", &mut buf).unwrap();
+        assert_eq!(buf[..4], [0, 0, 0, 10]);
+
+        let mut partial = buf.split_to(3);
+        assert_eq!(codec.decode(&mut partial).unwrap(), None);
+        partial.unsplit(buf);
+        let mut buf = partial;
+
+        assert_eq!(codec.decode(&mut buf).unwrap().as_deref(), Some("<epp/>"));
+        assert_eq!(
+            codec.decode(&mut buf).unwrap().as_deref(),
+            Some("<epp></epp>")
+        );
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+
+        let mut codec = codec.max_len(8);
+        codec.encode("<epp/>", &mut buf).unwrap();
+        assert!(codec.decode(&mut buf).is_err());
+    }
+}
@@ -0,0 +1,522 @@
+//! Building blocks for JSON gateways in front of a registry
+//!
+//! A REST or message-queue gateway receives commands as JSON and answers with JSON. Rather
+//! than re-modeling EPP, it can deserialize a [`GatewayRequest`] envelope, pass it to
+//! [`execute()`] with any [`Transport`] (including an [`EppClient`]) and serialize the
+//! [`GatewayResponse`]. The payloads are the [DTOs](crate::dto), so commands are validated
+//! before anything is sent.
+//!
+//! The envelopes look like this:
+//!
+//! ```json
+//! {"id": "job-42", "command": "domain_renew",
+//!  "data": {"name": "example.com", "current_expiry": "2025-03-01", "period": {"years": 1}}}
+//!
+//! {"id": "job-42", "server_tr_id": "54322-XYZ", "code": 1000,
+//!  "message": "Command completed successfully",
+//!  "data": {"renewed": {"name": "example.com", "expires_at": "2026-03-01T00:00:00Z"}}}
+//! ```
+//!
+//! Commands the registry rejects are answered with their result code and no data; only
+//! invalid payloads and transport failures are returned as errors.
+//!
+//! With the `schemars` feature, the envelopes and DTOs implement `JsonSchema`, and
+//! [`request_schema()`] and [`response_schema()`] return the JSON schemas of the envelopes, to
+//! validate input or document the gateway's API.
+//!
+//! [`EppClient`]: crate::EppClient
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::common::Availability;
+use crate::contact::ContactCreate;
+use crate::domain::{
+    DomainCheck, DomainContact, DomainCreate, DomainDelete, DomainInfo, DomainRenew,
+    DomainTransfer, InfoData,
+};
+use crate::dto::{
+    ContactCreateDto, DomainCheckDto, DomainCreateDto, DomainDeleteDto, DomainInfoDto,
+    DomainRenewDto, DomainTransferDto, HostCreateDto,
+};
+use crate::host::HostCreate;
+use crate::response::{EppResults, Response, ResponseTRID};
+use crate::transport::{transact, Transport};
+use crate::Error;
+
+/// A command received by a gateway
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct GatewayRequest {
+    /// The client transaction ID to send the command with
+    pub id: String,
+    #[serde(flatten)]
+    pub command: GatewayCommand,
+}
+
+/// The commands a gateway can send, tagged by `command` with the DTO in `data`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(tag = "command", content = "data", rename_all = "snake_case")]
+pub enum GatewayCommand {
+    DomainCheck(DomainCheckDto),
+    DomainInfo(DomainInfoDto),
+    DomainCreate(DomainCreateDto),
+    DomainRenew(DomainRenewDto),
+    DomainTransfer(DomainTransferDto),
+    DomainDelete(DomainDeleteDto),
+    HostCreate(HostCreateDto),
+    ContactCreate(ContactCreateDto),
+}
+
+/// The answer to a [`GatewayRequest`]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct GatewayResponse {
+    /// The client transaction ID echoed by the registry
+    pub id: Option<String>,
+    pub server_tr_id: String,
+    /// The primary result code
+    pub code: u16,
+    pub message: String,
+    /// The response data, for successful commands returning any
+    pub data: Option<GatewayData>,
+}
+
+/// The data of a [`GatewayResponse`], by the kind of command
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[serde(rename_all = "snake_case")]
+pub enum GatewayData {
+    Checked(Vec<CheckedName>),
+    Domain(DomainDetails),
+    /// A domain, host or contact was created
+    Created {
+        /// The domain or host name, or the contact ID
+        name: String,
+        created_at: DateTime<Utc>,
+        expires_at: Option<DateTime<Utc>>,
+    },
+    Renewed {
+        name: String,
+        expires_at: Option<DateTime<Utc>>,
+    },
+    Transfer {
+        name: String,
+        status: String,
+        requester_id: String,
+        requested_at: DateTime<Utc>,
+    },
+}
+
+/// The availability of a name, in [`GatewayData::Checked`]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct CheckedName {
+    pub name: String,
+    pub available: bool,
+    pub reason: Option<String>,
+}
+
+/// The data of a domain, in [`GatewayData::Domain`]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct DomainDetails {
+    pub name: String,
+    pub roid: String,
+    pub statuses: Vec<String>,
+    pub registrant: Option<String>,
+    pub contacts: Vec<DomainContact<'static>>,
+    pub nameservers: Vec<String>,
+    pub client_id: String,
+    pub created_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+/// The JSON schema of a [`GatewayRequest`]
+#[cfg(feature = "schemars")]
+pub fn request_schema() -> schemars::Schema {
+    schemars::schema_for!(GatewayRequest)
+}
+
+/// The JSON schema of a [`GatewayResponse`]
+#[cfg(feature = "schemars")]
+pub fn response_schema() -> schemars::Schema {
+    schemars::schema_for!(GatewayResponse)
+}
+
+/// Validate and send the command of `request` over `transport`
+pub async fn execute<T: Transport + ?Sized>(
+    transport: &mut T,
+    request: &GatewayRequest,
+) -> Result<GatewayResponse, Error> {
+    let id = request.id.as_str();
+    match &request.command {
+        GatewayCommand::DomainCheck(dto) => {
+            dto.validate()?;
+            let names = dto.names.iter().map(String::as_str).collect::<Vec<_>>();
+            let check = DomainCheck { domains: &names };
+            respond(transact(transport, &check, id).await, |data| {
+                let checked = data.list.iter().map(|checked| CheckedName {
+                    name: checked.inner.id.clone(),
+                    available: checked.inner.available,
+                    reason: match checked.availability() {
+                        Availability::Available => None,
+                        Availability::Unavailable { reason } => reason.map(|r| r.text.to_owned()),
+                        Availability::Invalid { reason } => Some(reason.text.to_owned()),
+                    },
+                });
+                Some(GatewayData::Checked(checked.collect()))
+            })
+        }
+        GatewayCommand::DomainInfo(dto) => {
+            let info = DomainInfo::try_from(dto)?;
+            respond(transact(transport, &info, id).await, |data| {
+                Some(domain_details(data))
+            })
+        }
+        GatewayCommand::DomainCreate(dto) => {
+            let create = DomainCreate::try_from(dto)?;
+            respond(transact(transport, &create, id).await, |data| {
+                Some(GatewayData::Created {
+                    name: data.name.clone(),
+                    created_at: data.created_at,
+                    expires_at: data.expiring_at,
+                })
+            })
+        }
+        GatewayCommand::DomainRenew(dto) => {
+            let renew = DomainRenew::try_from(dto)?;
+            respond(transact(transport, &renew, id).await, |data| {
+                Some(GatewayData::Renewed {
+                    name: data.name.clone(),
+                    expires_at: data.expiring_at,
+                })
+            })
+        }
+        GatewayCommand::DomainTransfer(dto) => {
+            let transfer = DomainTransfer::try_from(dto)?;
+            respond(transact(transport, &transfer, id).await, |data| {
+                Some(GatewayData::Transfer {
+                    name: data.name.clone(),
+                    status: data.transfer_status.clone(),
+                    requester_id: data.requester_id.clone(),
+                    requested_at: data.requested_at,
+                })
+            })
+        }
+        GatewayCommand::DomainDelete(dto) => {
+            let delete = DomainDelete::try_from(dto)?;
+            respond(transact(transport, &delete, id).await, |_| None)
+        }
+        GatewayCommand::HostCreate(dto) => {
+            let create = HostCreate::try_from(dto)?;
+            respond(transact(transport, &create, id).await, |data| {
+                Some(GatewayData::Created {
+                    name: data.name.clone(),
+                    created_at: data.created_at,
+                    expires_at: None,
+                })
+            })
+        }
+        GatewayCommand::ContactCreate(dto) => {
+            let create = ContactCreate::try_from(dto)?;
+            respond(transact(transport, &create, id).await, |data| {
+                Some(GatewayData::Created {
+                    name: data.id.clone(),
+                    created_at: data.created_at,
+                    expires_at: None,
+                })
+            })
+        }
+    }
+}
+
+fn domain_details(data: &InfoData) -> GatewayData {
+    GatewayData::Domain(DomainDetails {
+        name: data.name.clone(),
+        roid: data.roid.to_string(),
+        statuses: data
+            .statuses
+            .iter()
+            .flatten()
+            .map(|status| status.as_str().to_owned())
+            .collect(),
+        registrant: data.registrant.clone(),
+        contacts: data.contacts.clone().unwrap_or_default(),
+        nameservers: data.name_servers().map(ToOwned::to_owned).collect(),
+        client_id: data.client_id.clone(),
+        created_at: data.created_at,
+        expires_at: data.expiring_at,
+        updated_at: data.updated_at,
+    })
+}
+
+// The gateway response for the outcome of a command, with its data mapped by `map`
+fn respond<D, E>(
+    result: Result<Response<D, E>, Error>,
+    map: impl FnOnce(&D) -> Option<GatewayData>,
+) -> Result<GatewayResponse, Error> {
+    match result {
+        Ok(rsp) => Ok(status(
+            &rsp.result,
+            &rsp.tr_ids,
+            rsp.res_data().and_then(map),
+        )),
+        Err(Error::Command(rsp)) => Ok(status(&rsp.result, &rsp.tr_ids, None)),
+        Err(err) => Err(err),
+    }
+}
+
+fn status(
+    results: &EppResults,
+    tr_ids: &ResponseTRID,
+    data: Option<GatewayData>,
+) -> GatewayResponse {
+    let result = results.primary();
+    GatewayResponse {
+        id: tr_ids.client_tr_id.clone(),
+        server_tr_id: tr_ids.server_tr_id.clone(),
+        code: result.code as u16,
+        message: result.message.clone(),
+        data,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::IpAddr;
+
+    use async_trait::async_trait;
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    use super::{execute, GatewayCommand, GatewayData, GatewayRequest, GatewayResponse};
+    use crate::domain::NameserverList;
+    use crate::dto::{
+        ContactCreateDto, DomainCheckDto, DomainCreateDto, DomainDeleteDto, DomainInfoDto,
+        DomainRenewDto, DomainTransferDto, HostCreateDto, PeriodDto,
+    };
+    use crate::tests::{get_xml, CLTRID, SVTRID};
+    use crate::transport::Transport;
+    use crate::Error;
+
+    struct Registry(Vec<String>);
+
+    #[async_trait(?Send)]
+    impl Transport for Registry {
+        async fn exchange(&mut self, _: &str) -> Result<String, Error> {
+            Ok(self.0.remove(0))
+        }
+    }
+
+    #[test]
+    fn execute_commands() {
+        let mut registry = Registry(vec![
+            get_xml("response/domain/check.xml").unwrap(),
+            get_xml("response/error.xml").unwrap(),
+        ]);
+
+        let request = GatewayRequest {
+            id: CLTRID.into(),
+            command: GatewayCommand::DomainCheck(DomainCheckDto {
+                names: vec!["eppdev.com".into(), "eppdev.net".into()],
+            }),
+        };
+        let response = tokio_test::block_on(execute(&mut registry, &request)).unwrap();
+        assert_eq!(response.code, 1000);
+        assert_eq!(response.server_tr_id, SVTRID);
+        let Some(GatewayData::Checked(checked)) = response.data else {
+            panic!("expected check data: {:?}", response.data);
+        };
+        assert_eq!(checked.len(), 2);
+        assert_eq!(checked[0].name, "eppdev.com");
+
+        let request = GatewayRequest {
+            id: CLTRID.into(),
+            command: GatewayCommand::DomainDelete(DomainDeleteDto {
+                name: "eppdev.com".into(),
+            }),
+        };
+        let response = tokio_test::block_on(execute(&mut registry, &request)).unwrap();
+        assert_eq!(response.code, 2303);
+        assert_eq!(response.data, None);
+
+        let request = GatewayRequest {
+            id: CLTRID.into(),
+            command: GatewayCommand::DomainDelete(DomainDeleteDto {
+                name: "not a name".into(),
+            }),
+        };
+        assert!(tokio_test::block_on(execute(&mut registry, &request)).is_err());
+    }
+
+    fn send(response: &str, code: u16, command: GatewayCommand) -> GatewayResponse {
+        let mut registry = Registry(vec![get_xml(response).unwrap()]);
+        let request = GatewayRequest {
+            id: CLTRID.into(),
+            command,
+        };
+        let response = tokio_test::block_on(execute(&mut registry, &request)).unwrap();
+        assert_eq!(response.code, code);
+        assert_eq!(response.id.as_deref(), Some(CLTRID));
+        response
+    }
+
+    #[test]
+    fn execute_every_command() {
+        let response = send(
+            "response/domain/info.xml",
+            1000,
+            GatewayCommand::DomainInfo(DomainInfoDto {
+                name: "eppdev-1.com".into(),
+                auth_info: None,
+            }),
+        );
+        let Some(GatewayData::Domain(domain)) = response.data else {
+            panic!("expected domain data: {:?}", response.data);
+        };
+        assert_eq!(domain.name, "eppdev-1.com");
+        assert!(!domain.statuses.is_empty());
+        assert_eq!(
+            domain.expires_at,
+            Some(Utc.with_ymd_and_hms(2023, 7, 23, 15, 31, 20).unwrap())
+        );
+
+        let response = send(
+            "response/domain/create.xml",
+            1000,
+            GatewayCommand::DomainCreate(DomainCreateDto {
+                name: "eppdev-2.com".into(),
+                period: PeriodDto::Years(1),
+                nameservers: Some(NameserverList::HostObjects(vec!["ns1.eppdev-1.com".into()])),
+                registrant: Some("eppdev-contact-3".into()),
+                contacts: Vec::new(),
+                auth_info: "epP4uthd#v".into(),
+            }),
+        );
+        let Some(GatewayData::Created {
+            name, expires_at, ..
+        }) = response.data
+        else {
+            panic!("expected create data: {:?}", response.data);
+        };
+        assert_eq!(name, "eppdev-2.com");
+        assert_eq!(
+            expires_at,
+            Some(Utc.with_ymd_and_hms(2022, 7, 25, 18, 11, 34).unwrap())
+        );
+
+        let response = send(
+            "response/domain/renew.xml",
+            1000,
+            GatewayCommand::DomainRenew(DomainRenewDto {
+                name: "eppdev-1.com".into(),
+                current_expiry: NaiveDate::from_ymd_opt(2023, 7, 23).unwrap(),
+                period: PeriodDto::Years(1),
+            }),
+        );
+        let Some(GatewayData::Renewed { name, expires_at }) = response.data else {
+            panic!("expected renew data: {:?}", response.data);
+        };
+        assert_eq!(name, "eppdev-1.com");
+        assert_eq!(
+            expires_at,
+            Some(Utc.with_ymd_and_hms(2024, 7, 23, 15, 31, 20).unwrap())
+        );
+
+        let response = send(
+            "response/domain/transfer_request.xml",
+            1001,
+            GatewayCommand::DomainTransfer(DomainTransferDto {
+                name: "eppdev-transfer.com".into(),
+                period: None,
+                auth_info: "epP4uthd#v".into(),
+            }),
+        );
+        let Some(GatewayData::Transfer {
+            name,
+            status,
+            requester_id,
+            ..
+        }) = response.data
+        else {
+            panic!("expected transfer data: {:?}", response.data);
+        };
+        assert_eq!(name, "eppdev-transfer.com");
+        assert_eq!(status, "pending");
+        assert_eq!(requester_id, "eppdev");
+
+        let response = send(
+            "response/host/create.xml",
+            1000,
+            GatewayCommand::HostCreate(HostCreateDto {
+                name: "host2.eppdev-1.com".into(),
+                addresses: vec!["29.245.122.14".parse::<IpAddr>().unwrap()],
+            }),
+        );
+        let Some(GatewayData::Created {
+            name, expires_at, ..
+        }) = response.data
+        else {
+            panic!("expected create data: {:?}", response.data);
+        };
+        assert_eq!(name, "host2.eppdev-1.com");
+        assert_eq!(expires_at, None);
+
+        let response = send(
+            "response/contact/create.xml",
+            1000,
+            GatewayCommand::ContactCreate(ContactCreateDto {
+                id: "eppdev-contact-4".into(),
+                email: "contact@eppdev.net".into(),
+                name: "John Doe".into(),
+                organization: None,
+                street: vec!["58".into()],
+                city: "Paris".into(),
+                province: None,
+                postal_code: Some("392374".into()),
+                country: "FR".into(),
+                voice: Some("+33.47237942".into()),
+                auth_info: "eppdev-387323".into(),
+            }),
+        );
+        let Some(GatewayData::Created {
+            name, created_at, ..
+        }) = response.data
+        else {
+            panic!("expected create data: {:?}", response.data);
+        };
+        assert_eq!(name, "eppdev-contact-4");
+        assert_eq!(
+            created_at,
+            Utc.with_ymd_and_hms(2021, 7, 25, 16, 5, 32).unwrap()
+        );
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn schemas() {
+        let request = super::request_schema();
+        let commands = request.as_value().to_string();
+        for command in [
+            "domain_check",
+            "domain_info",
+            "domain_create",
+            "domain_renew",
+            "domain_transfer",
+            "domain_delete",
+            "host_create",
+            "contact_create",
+        ] {
+            assert!(commands.contains(&format!("\"{command}\"")), "{command}");
+        }
+        assert!(commands.contains("\"current_expiry\""));
+
+        let response = super::response_schema();
+        let data = response.as_value().to_string();
+        for kind in ["checked", "domain", "created", "renewed", "transfer"] {
+            assert!(data.contains(&format!("\"{kind}\"")), "{kind}");
+        }
+    }
+}
@@ -1,12 +1,20 @@
-use std::fmt::Debug;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::error::Error as StdError;
+use std::fmt::{self, Debug};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 use chrono::{DateTime, Utc};
-use instant_xml::{Deserializer, FromXml, ToXml};
+#[cfg(any(feature = "client", test))]
+use instant_xml::ToXml;
+use instant_xml::{Deserializer, FromXml};
 
 use crate::common::{Options, ServiceExtension, Services, EPP_XMLNS};
 
 // Request
 
+#[cfg(any(feature = "client", test))]
 #[derive(Debug, PartialEq, ToXml)]
 #[xml(rename = "hello", ns(EPP_XMLNS))]
 pub(crate) struct Hello;
@@ -14,7 +22,7 @@ pub(crate) struct Hello;
 // Response
 
 /// Type for data within the `<svcMenu>` section of an EPP greeting
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ServiceMenu {
     pub options: Options<'static>,
     pub services: Services<'static>,
@@ -76,37 +84,37 @@ impl<'xml> FromXml<'xml> for ServiceMenu {
 }
 
 /// Type corresponding to `<all>` in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "all", ns(EPP_XMLNS))]
 pub struct All;
 
-/// Type corresponding to `<none>` in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
-#[xml(rename = "noAccess", ns(EPP_XMLNS))]
+/// Type corresponding to `<none>` under `<access>` in the EPP greeting XML
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
+#[xml(rename = "none", ns(EPP_XMLNS))]
 pub struct NoAccess;
 
 /// Type corresponding to `<null>` in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "null", ns(EPP_XMLNS))]
 pub struct Null;
 
 /// Type corresponding to `<personal>` in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "personal", ns(EPP_XMLNS))]
 pub struct Personal;
 
 /// Type corresponding to `<personalAndOther>` in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "personalAndOther", ns(EPP_XMLNS))]
 pub struct PersonalAndOther;
 
 /// Type corresponding to `<other>` in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "other", ns(EPP_XMLNS))]
 pub struct Other;
 
-/// Type corresponding to possible `<retention>` type values
-#[derive(Debug, Eq, FromXml, PartialEq)]
+/// Type corresponding to possible `<access>` type values
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(forward)]
 pub enum AccessType {
     /// Data for the `<all>` tag
@@ -123,14 +131,17 @@ pub enum AccessType {
     Other(Other),
 }
 
-#[derive(Debug, Eq, FromXml, PartialEq)]
+/// Type corresponding to `<access>` in the EPP greeting XML
+///
+/// Describes which of the collected data the server grants access to.
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "access", ns(EPP_XMLNS))]
 pub struct Access {
-    inner: AccessType,
+    pub inner: AccessType,
 }
 
 /// Type corresponding to possible `<purpose>` type values
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(forward)]
 pub enum PurposeType {
     /// Data for the `<admin>` tag
@@ -143,31 +154,35 @@ pub enum PurposeType {
     OtherPurpose(OtherPurpose),
 }
 
-#[derive(Debug, Eq, FromXml, PartialEq)]
+/// Type corresponding to `<admin>` under `<purpose>` in the EPP greeting XML
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "admin", ns(EPP_XMLNS))]
 pub struct Admin;
 
-#[derive(Debug, Eq, FromXml, PartialEq)]
+/// Type corresponding to `<contact>` under `<purpose>` in the EPP greeting XML
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "contact", ns(EPP_XMLNS))]
 pub struct Contact;
 
-#[derive(Debug, Eq, FromXml, PartialEq)]
+/// Type corresponding to `<prov>` under `<purpose>` in the EPP greeting XML
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "prov", ns(EPP_XMLNS))]
 pub struct Prov;
 
-#[derive(Debug, Eq, FromXml, PartialEq)]
-#[xml(rename = "otherPurpose", ns(EPP_XMLNS))]
+/// Type corresponding to `<other>` under `<purpose>` in the EPP greeting XML
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
+#[xml(rename = "other", ns(EPP_XMLNS))]
 pub struct OtherPurpose;
 
 /// Type corresponding to `<purpose>` in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "purpose", ns(EPP_XMLNS))]
 pub struct Purpose {
     pub purpose: Vec<PurposeType>,
 }
 
-/// Type corresponding to possible `<purpose>` type values
-#[derive(Debug, Eq, FromXml, PartialEq)]
+/// Type corresponding to possible `<recipient>` type values
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(forward)]
 pub enum RecipientType {
     /// Data for the `<other>` tag
@@ -182,56 +197,64 @@ pub enum RecipientType {
     Unrelated(Unrelated),
 }
 
-#[derive(Debug, Eq, FromXml, PartialEq)]
+/// Type corresponding to `<ours>` under `<recipient>` in the EPP greeting XML
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "ours", ns(EPP_XMLNS))]
-pub struct Ours;
+pub struct Ours {
+    /// Description of the recipient, from the `<recDesc>` tag
+    #[xml(rename = "recDesc")]
+    pub description: Option<String>,
+}
 
-#[derive(Debug, Eq, FromXml, PartialEq)]
+/// Type corresponding to `<public>` under `<recipient>` in the EPP greeting XML
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "public", ns(EPP_XMLNS))]
 pub struct Public;
 
-#[derive(Debug, Eq, FromXml, PartialEq)]
+/// Type corresponding to `<unrelated>` under `<recipient>` in the EPP greeting XML
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "unrelated", ns(EPP_XMLNS))]
 pub struct Unrelated;
 
-#[derive(Debug, Eq, FromXml, PartialEq)]
+/// Type corresponding to `<same>` under `<recipient>` in the EPP greeting XML
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "same", ns(EPP_XMLNS))]
 pub struct Same;
 
-/// Type corresponding to `<recipeint>` in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+/// Type corresponding to `<recipient>` in the EPP greeting XML
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "recipient", ns(EPP_XMLNS))]
 pub struct Recipient {
     pub recipient: Vec<RecipientType>,
 }
 
 /// Type corresponding to `<business>` in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "business", ns(EPP_XMLNS))]
 pub struct Business;
 
 /// Type corresponding to `<indefinite>` in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "indefinite", ns(EPP_XMLNS))]
 pub struct Indefinite;
 
 /// Type corresponding to `<legal>` in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "legal", ns(EPP_XMLNS))]
 pub struct Legal;
 
 /// Type corresponding to `<none>` in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "none", ns(EPP_XMLNS))]
 pub struct No;
 
 /// Type corresponding to `<stated>` in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "stated", ns(EPP_XMLNS))]
 pub struct Stated;
 
 /// Type corresponding to possible `<retention>` type values
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(forward, rename = "retention", ns(EPP_XMLNS))]
 pub enum RetentionType {
     /// Data for the `<business>` tag
@@ -246,14 +269,15 @@ pub enum RetentionType {
     Stated(Stated),
 }
 
-#[derive(Debug, Eq, FromXml, PartialEq)]
+/// Type corresponding to `<retention>` in the EPP greeting XML
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "retention", ns(EPP_XMLNS))]
 pub struct Retention {
-    inner: RetentionType,
+    pub inner: RetentionType,
 }
 
-/// Type corresponding to `<statement>` in the EPP greeting XML (pending more compliant implementation)
-#[derive(Debug, Eq, FromXml, PartialEq)]
+/// Type corresponding to `<statement>` in the EPP greeting XML
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "statement", ns(EPP_XMLNS))]
 pub struct Statement {
     /// Data for the `<purpose>` tag
@@ -265,17 +289,17 @@ pub struct Statement {
 }
 
 /// Type corresponding to `<absolute>` value in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "absolute", ns(EPP_XMLNS))]
-pub struct Absolute(String);
+pub struct Absolute(pub String);
 
 /// Type corresponding to `<relative>` value in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "relative", ns(EPP_XMLNS))]
-pub struct Relative(String);
+pub struct Relative(pub String);
 
 /// Type corresponding to possible `<expiry>` type values
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(forward)]
 pub enum ExpiryType {
     /// Data for the `<absolute>` tag
@@ -284,15 +308,18 @@ pub enum ExpiryType {
     Relative(Relative),
 }
 
-/// Type corresponding to possible `<expiry>` type values
-#[derive(Debug, Eq, FromXml, PartialEq)]
+/// Type corresponding to `<expiry>` in the EPP greeting XML
+///
+/// Describes the lifetime of the policy, either as an absolute date or as a duration relative
+/// to the time the greeting was received.
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "expiry", ns(EPP_XMLNS))]
 pub struct Expiry {
-    inner: ExpiryType,
+    pub inner: ExpiryType,
 }
 
 /// Type corresponding to `<dcp>` in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "dcp", ns(EPP_XMLNS))]
 pub struct Dcp {
     /// Data for the `<access>` tag
@@ -304,7 +331,7 @@ pub struct Dcp {
 }
 
 /// Type corresponding to the `<greeting>` tag in the EPP greeting XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(ns(EPP_XMLNS), rename = "greeting", rename_all = "lowercase")]
 pub struct Greeting {
     /// The service ID
@@ -322,11 +349,329 @@ pub struct Greeting {
     pub dcp: Dcp,
 }
 
+/// What the greeting of the intended server should look like
+///
+/// Checked by [`EppClient::set_greeting_expectations()`] before logging in, so that a
+/// configuration mix-up (like production credentials pointed at an OT&E endpoint, or the
+/// other way around) fails before any credentials are sent. The protocol version must always
+/// be `1.0`.
+///
+/// [`EppClient::set_greeting_expectations()`]: crate::EppClient::set_greeting_expectations
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GreetingExpectations {
+    /// Text the `<svID>` must contain, compared case-insensitively
+    pub service_id: Option<String>,
+    /// Object URIs the server must announce
+    pub obj_uris: Vec<String>,
+    /// Extension URIs the server must announce
+    pub ext_uris: Vec<String>,
+}
+
+impl GreetingExpectations {
+    /// Check `greeting` against the expectations
+    pub fn check(&self, greeting: &Greeting) -> Result<(), GreetingMismatch> {
+        let version = &greeting.svc_menu.options.version;
+        if version != "1.0" {
+            return Err(GreetingMismatch::Version(version.to_string()));
+        }
+
+        if let Some(expected) = &self.service_id {
+            let found = greeting.service_id.to_lowercase();
+            if !found.contains(&expected.to_lowercase()) {
+                return Err(GreetingMismatch::ServiceId {
+                    expected: expected.clone(),
+                    found: greeting.service_id.clone(),
+                });
+            }
+        }
+
+        let services = &greeting.svc_menu.services;
+        if let Some(uri) = self
+            .obj_uris
+            .iter()
+            .find(|uri| !services.obj_uris.iter().any(|found| found == *uri))
+        {
+            return Err(GreetingMismatch::MissingObjUri(uri.clone()));
+        }
+
+        let ext_uris = services
+            .svc_ext
+            .as_ref()
+            .map(|ext| ext.ext_uris.as_slice())
+            .unwrap_or_default();
+        if let Some(uri) = self
+            .ext_uris
+            .iter()
+            .find(|uri| !ext_uris.iter().any(|found| found == *uri))
+        {
+            return Err(GreetingMismatch::MissingExtUri(uri.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+/// A greeting that does not match the [`GreetingExpectations`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GreetingMismatch {
+    /// The server speaks a protocol version other than 1.0
+    Version(String),
+    /// The `<svID>` does not contain the expected text
+    ServiceId { expected: String, found: String },
+    /// A required object URI is not announced
+    MissingObjUri(String),
+    /// A required extension URI is not announced
+    MissingExtUri(String),
+}
+
+impl fmt::Display for GreetingMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Version(version) => write!(f, "unsupported EPP version {version}"),
+            Self::ServiceId { expected, found } => {
+                write!(f, "server ID {found:?} does not contain {expected:?}")
+            }
+            Self::MissingObjUri(uri) => write!(f, "server does not support object {uri}"),
+            Self::MissingExtUri(uri) => write!(f, "server does not support extension {uri}"),
+        }
+    }
+}
+
+impl StdError for GreetingMismatch {}
+
+impl From<GreetingMismatch> for crate::Error {
+    fn from(err: GreetingMismatch) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+type OnChange = Box<dyn Fn(&GreetingChange) + Send + Sync>;
+
+/// Keeps the last greeting of an endpoint and reports changes in later ones
+///
+/// Registries announce new extensions (and the retirement of old ones) by changing the
+/// service menu of their greeting, and a server with a broken clock shows it in the
+/// `<svDate>`. Set a watch with [`EppClient::set_greeting_watch()`] to compare the greeting
+/// received on each reconnect (and each `<hello>`) with the previous one. Share a single
+/// watch between the clients connected to the same endpoint.
+///
+/// [`EppClient::set_greeting_watch()`]: crate::EppClient::set_greeting_watch
+pub struct GreetingWatch {
+    max_skew: Duration,
+    on_change: Option<OnChange>,
+    last: Mutex<Option<Arc<Greeting>>>,
+}
+
+impl GreetingWatch {
+    /// Report a `<svDate>` more than `max_skew` away from the local clock
+    pub fn new(max_skew: Duration) -> Self {
+        Self {
+            max_skew,
+            on_change: None,
+            last: Mutex::new(None),
+        }
+    }
+
+    /// Call `on_change` for every change observed
+    pub fn on_change(
+        mut self,
+        on_change: impl Fn(&GreetingChange) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_change = Some(Box::new(on_change));
+        self
+    }
+
+    /// The last greeting observed
+    pub fn latest(&self) -> Option<Arc<Greeting>> {
+        self.last
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clone()
+    }
+
+    /// Compare `greeting`, received at `received`, with the previous one and store it
+    ///
+    /// The service menu is only compared if a greeting was observed before; the clock is
+    /// checked on every greeting.
+    pub fn observe(&self, greeting: Greeting, received: DateTime<Utc>) -> Vec<GreetingChange> {
+        let mut changes = Vec::new();
+        let greeting = Arc::new(greeting);
+        let previous = self
+            .last
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .replace(greeting.clone());
+
+        if let Some(previous) = previous {
+            let (old, new) = (&previous.svc_menu.services, &greeting.svc_menu.services);
+            let (added, removed) = diff(&old.obj_uris, &new.obj_uris);
+            if !added.is_empty() || !removed.is_empty() {
+                changes.push(GreetingChange::ObjUris { added, removed });
+            }
+
+            let (added, removed) = diff(ext_uris(old), ext_uris(new));
+            if !added.is_empty() || !removed.is_empty() {
+                changes.push(GreetingChange::ExtUris { added, removed });
+            }
+        }
+
+        let skew = greeting.service_date - received;
+        if u128::from(skew.num_milliseconds().unsigned_abs()) > self.max_skew.as_millis() {
+            changes.push(GreetingChange::ClockSkew(skew));
+        }
+
+        if let Some(on_change) = &self.on_change {
+            for change in &changes {
+                on_change(change);
+            }
+        }
+
+        changes
+    }
+}
+
+impl Debug for GreetingWatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GreetingWatch")
+            .field("max_skew", &self.max_skew)
+            .field("last", &self.latest())
+            .finish_non_exhaustive()
+    }
+}
+
+fn ext_uris<'a>(services: &'a Services<'static>) -> &'a [Cow<'static, str>] {
+    services
+        .svc_ext
+        .as_ref()
+        .map(|ext| ext.ext_uris.as_slice())
+        .unwrap_or_default()
+}
+
+// The URIs added in and removed from `new` compared to `old`
+fn diff<T: AsRef<str>>(old: &[T], new: &[T]) -> (Vec<String>, Vec<String>) {
+    let missing = |from: &[T], to: &[T]| {
+        from.iter()
+            .map(AsRef::as_ref)
+            .filter(|uri| !to.iter().any(|other| other.as_ref() == *uri))
+            .map(str::to_owned)
+            .collect::<Vec<_>>()
+    };
+    (missing(new, old), missing(old, new))
+}
+
+/// A difference between a greeting and the previous one, reported by [`GreetingWatch`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum GreetingChange {
+    /// Object URIs were added to or removed from the service menu
+    ObjUris {
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+    /// Extension URIs were added to or removed from the service menu
+    ExtUris {
+        added: Vec<String>,
+        removed: Vec<String>,
+    },
+    /// The `<svDate>` minus the local time, beyond the allowed skew
+    ClockSkew(chrono::Duration),
+}
+
+/// A `<hello>` round trip, recorded by [`EppClient::hello()`]
+///
+/// [`EppClient::hello()`]: crate::EppClient::hello
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct HelloSample {
+    /// When the `<hello>` was sent, by the local clock
+    pub sent_at: DateTime<Utc>,
+    /// The time until the greeting arrived
+    pub rtt: Duration,
+    /// The `<svDate>` of the greeting
+    pub service_date: DateTime<Utc>,
+}
+
+impl HelloSample {
+    /// The server's clock minus the local clock
+    ///
+    /// Assumes the server generated the greeting halfway through the round trip, so the
+    /// error is at most half the round trip time.
+    pub fn skew(&self) -> chrono::Duration {
+        let half =
+            chrono::Duration::from_std(self.rtt / 2).unwrap_or_else(|_| chrono::Duration::zero());
+        self.service_date - (self.sent_at + half)
+    }
+}
+
+/// The most recent `<hello>` round trips of a connection, see [`EppClient::hello_history()`]
+///
+/// [`EppClient::hello_history()`]: crate::EppClient::hello_history
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct HelloHistory {
+    samples: VecDeque<HelloSample>,
+}
+
+impl HelloHistory {
+    /// The number of samples kept
+    pub const CAPACITY: usize = 16;
+
+    /// The samples, oldest first
+    pub fn samples(&self) -> impl Iterator<Item = &HelloSample> {
+        self.samples.iter()
+    }
+
+    pub fn latest(&self) -> Option<&HelloSample> {
+        self.samples.back()
+    }
+
+    /// The median clock skew of the samples
+    pub fn skew(&self) -> Option<chrono::Duration> {
+        let mut skews = self
+            .samples
+            .iter()
+            .map(HelloSample::skew)
+            .collect::<Vec<_>>();
+        skews.sort_unstable();
+        skews.get(skews.len().checked_sub(1)? / 2).copied()
+    }
+
+    /// How much the clock skew changed from the oldest to the latest sample
+    pub fn drift(&self) -> Option<chrono::Duration> {
+        let (first, last) = (self.samples.front()?, self.samples.back()?);
+        Some(last.skew() - first.skew())
+    }
+
+    /// The mean difference between consecutive round trip times
+    pub fn jitter(&self) -> Option<Duration> {
+        let diffs = self
+            .samples
+            .iter()
+            .zip(self.samples.iter().skip(1))
+            .map(|(a, b)| a.rtt.abs_diff(b.rtt))
+            .collect::<Vec<_>>();
+        let count = u32::try_from(diffs.len()).ok().filter(|&count| count > 0)?;
+        Some(diffs.iter().sum::<Duration>() / count)
+    }
+
+    #[cfg(any(feature = "client", test))]
+    pub(crate) fn record(&mut self, sample: HelloSample) {
+        if self.samples.len() == Self::CAPACITY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use std::time::Duration;
+
     use chrono::{TimeZone, Utc};
 
-    use super::{ExpiryType, Greeting, Hello, Relative};
+    use super::{
+        Absolute, AccessType, Admin, All, Contact, ExpiryType, Greeting, GreetingChange,
+        GreetingExpectations, GreetingMismatch, GreetingWatch, Hello, HelloHistory, HelloSample,
+        Legal, No, NoAccess, OtherPurpose, Ours, Prov, Public, PurposeType, RecipientType,
+        Relative, RetentionType, Same, Stated, Unrelated,
+    };
     use crate::tests::get_xml;
     use crate::xml;
 
@@ -338,6 +683,38 @@ mod tests {
         assert_eq!(xml, serialized);
     }
 
+    #[test]
+    fn expectations() {
+        let xml = get_xml("response/greeting.xml").unwrap();
+        let greeting = xml::deserialize::<Greeting>(xml.as_str()).unwrap();
+
+        let mut expected = GreetingExpectations {
+            service_id: Some("ispapi".into()),
+            obj_uris: vec!["urn:ietf:params:xml:ns:domain-1.0".into()],
+            ext_uris: vec!["urn:ietf:params:xml:ns:rgp-1.0".into()],
+        };
+        assert_eq!(expected.check(&greeting), Ok(()));
+
+        expected
+            .ext_uris
+            .push("urn:ietf:params:xml:ns:fee-1.0".into());
+        assert_eq!(
+            expected.check(&greeting),
+            Err(GreetingMismatch::MissingExtUri(
+                "urn:ietf:params:xml:ns:fee-1.0".into()
+            ))
+        );
+
+        expected.service_id = Some("OT&E".into());
+        assert_eq!(
+            expected.check(&greeting),
+            Err(GreetingMismatch::ServiceId {
+                expected: "OT&E".into(),
+                found: "ISPAPI EPP Server".into(),
+            })
+        );
+    }
+
     #[test]
     fn greeting() {
         let xml = get_xml("response/greeting.xml").unwrap();
@@ -352,10 +729,116 @@ mod tests {
         assert_eq!(object.svc_menu.options.lang, "en");
         assert_eq!(object.svc_menu.services.obj_uris.len(), 4);
         assert_eq!(object.svc_menu.services.svc_ext.unwrap().ext_uris.len(), 5);
+        assert_eq!(object.dcp.access.inner, AccessType::All(All));
         assert_eq!(object.dcp.statement.len(), 2);
+
+        let statement = &object.dcp.statement[0];
+        assert_eq!(
+            statement.purpose.purpose,
+            [PurposeType::Admin(Admin), PurposeType::Prov(Prov)]
+        );
+        assert_eq!(
+            statement.recipient.recipient,
+            [
+                RecipientType::Ours(Ours { description: None }),
+                RecipientType::Public(Public)
+            ]
+        );
+        assert_eq!(statement.retention.inner, RetentionType::Stated(Stated));
+
+        let statement = &object.dcp.statement[1];
+        assert_eq!(
+            statement.purpose.purpose,
+            [PurposeType::OtherPurpose(OtherPurpose)]
+        );
+        assert_eq!(
+            statement.recipient.recipient,
+            [RecipientType::Unrelated(Unrelated)]
+        );
+        assert_eq!(statement.retention.inner, RetentionType::None(No));
+
         assert_eq!(
             object.dcp.expiry.unwrap().inner,
             ExpiryType::Relative(Relative("P1M".into()))
         );
     }
+
+    #[test]
+    fn greeting_dcp() {
+        let xml = get_xml("response/greeting_dcp.xml").unwrap();
+        let object = xml::deserialize::<Greeting>(xml.as_str()).unwrap();
+
+        assert_eq!(object.dcp.access.inner, AccessType::NoAccess(NoAccess));
+        assert_eq!(object.dcp.statement.len(), 1);
+
+        let statement = &object.dcp.statement[0];
+        assert_eq!(statement.purpose.purpose, [PurposeType::Contact(Contact)]);
+        assert_eq!(
+            statement.recipient.recipient,
+            [
+                RecipientType::Ours(Ours {
+                    description: Some("Accredited registrars".into())
+                }),
+                RecipientType::Same(Same)
+            ]
+        );
+        assert_eq!(statement.retention.inner, RetentionType::Legal(Legal));
+        assert_eq!(
+            object.dcp.expiry.unwrap().inner,
+            ExpiryType::Absolute(Absolute("2025-12-31T23:59:59.0Z".into()))
+        );
+    }
+
+    #[test]
+    fn watch() {
+        let xml = get_xml("response/greeting.xml").unwrap();
+        let greeting = xml::deserialize::<Greeting>(xml.as_str()).unwrap();
+        let sent = greeting.service_date;
+
+        let watch = GreetingWatch::new(Duration::from_secs(5));
+        let changes = watch.observe(greeting, sent + chrono::Duration::seconds(2));
+        assert_eq!(changes, []);
+
+        let xml = xml.replace("fee-0.7", "fee-1.0");
+        let greeting = xml::deserialize::<Greeting>(xml.as_str()).unwrap();
+        let changes = watch.observe(greeting, sent + chrono::Duration::seconds(60));
+        assert_eq!(
+            changes,
+            [
+                GreetingChange::ExtUris {
+                    added: vec!["urn:ietf:params:xml:ns:fee-1.0".into()],
+                    removed: vec!["urn:ietf:params:xml:ns:fee-0.7".into()],
+                },
+                GreetingChange::ClockSkew(chrono::Duration::seconds(-60)),
+            ]
+        );
+        assert!(watch.latest().is_some());
+    }
+
+    #[test]
+    fn hello_history() {
+        let sent_at = Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap();
+        let sample = |rtt_ms: u64, skew_ms: i64| HelloSample {
+            sent_at,
+            rtt: Duration::from_millis(rtt_ms),
+            service_date: sent_at + chrono::Duration::milliseconds(rtt_ms as i64 / 2 + skew_ms),
+        };
+
+        let mut history = HelloHistory::default();
+        assert_eq!(history.skew(), None);
+        assert_eq!(history.jitter(), None);
+
+        history.record(sample(100, 500));
+        history.record(sample(140, 700));
+        history.record(sample(120, 900));
+        assert_eq!(history.skew(), Some(chrono::Duration::milliseconds(700)));
+        assert_eq!(history.drift(), Some(chrono::Duration::milliseconds(400)));
+        assert_eq!(history.jitter(), Some(Duration::from_millis(30)));
+
+        for _ in 0..HelloHistory::CAPACITY {
+            history.record(sample(100, 0));
+        }
+        assert_eq!(history.samples().count(), HelloHistory::CAPACITY);
+        assert_eq!(history.drift(), Some(chrono::Duration::zero()));
+    }
 }
@@ -1,11 +1,13 @@
 //! Types for EPP host check request
 
+use std::collections::HashMap;
 use std::fmt::{self, Debug};
 
 use instant_xml::{FromXml, Serializer, ToXml};
 
 use super::XMLNS;
-use crate::common::{NoExtension, EPP_XMLNS};
+use crate::common::{Availability, CheckReason, NoExtension, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for HostCheck<'_> {}
@@ -13,6 +15,10 @@ impl Transaction<NoExtension> for HostCheck<'_> {}
 impl Command for HostCheck<'_> {
     type Response = CheckData;
     const COMMAND: &'static str = "check";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::HostCheck(self)
+    }
 }
 
 // Request
@@ -41,6 +47,20 @@ pub struct HostCheck<'a> {
     pub hosts: &'a [&'a str],
 }
 
+impl<'a> HostCheck<'a> {
+    /// Split `hosts` into several check commands of at most `size` hosts each
+    ///
+    /// Like [`DomainCheck::chunked()`](crate::domain::DomainCheck::chunked), for batching
+    /// large lists.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is 0.
+    pub fn chunked(hosts: &'a [&'a str], size: usize) -> impl Iterator<Item = Self> + 'a {
+        hosts.chunks(size).map(|hosts| Self { hosts })
+    }
+}
+
 // Response
 
 #[derive(Debug, FromXml)]
@@ -60,6 +80,29 @@ pub struct CheckedHost {
     /// Data under the `<cd>` tag
     #[xml(rename = "cd")]
     pub inner: Checked,
+    /// Why the object is unavailable, if given by the server
+    pub reason: Option<Reason>,
+}
+
+impl CheckedHost {
+    /// The availability of the object, together with the reason given by the server
+    pub fn availability(&self) -> Availability<'_> {
+        let reason = self.reason.as_ref().map(|reason| CheckReason {
+            lang: reason.lang.as_deref(),
+            text: &reason.text,
+        });
+        Availability::new(self.inner.available, reason, self.inner.reason.as_deref())
+    }
+}
+
+/// Type that represents the `<reason>` tag in a `<cd>` element
+#[derive(Debug, FromXml)]
+#[xml(rename = "reason", ns(XMLNS))]
+pub struct Reason {
+    #[xml(attribute)]
+    pub lang: Option<String>,
+    #[xml(direct)]
+    pub text: String,
 }
 
 /// Type that represents the `<chkData>` tag for host check response
@@ -69,9 +112,39 @@ pub struct CheckData {
     pub list: Vec<CheckedHost>,
 }
 
+impl fmt::Display for CheckedHost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.inner.id, self.availability())
+    }
+}
+
+impl CheckData {
+    /// The checked hosts, keyed by their names
+    pub fn into_map(self) -> HashMap<String, CheckedHost> {
+        self.list
+            .into_iter()
+            .map(|checked| (checked.inner.id.clone(), checked))
+            .collect()
+    }
+}
+
+/// One line per checked object, like `example.com: unavailable (In use)`
+impl fmt::Display for CheckData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, checked) in self.list.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{checked}")?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::HostCheck;
+    use crate::common::{Availability, CheckReason};
     use crate::response::ResultCode;
     use crate::tests::{assert_serialized, response_from_file, CLTRID, SUCCESS_MSG, SVTRID};
 
@@ -97,4 +170,51 @@ mod tests {
         assert_eq!(object.tr_ids.client_tr_id.unwrap(), CLTRID);
         assert_eq!(object.tr_ids.server_tr_id, SVTRID);
     }
+
+    #[test]
+    fn response_reason() {
+        let object = response_from_file::<HostCheck>("response/host/check_reason.xml");
+        let result = object.res_data().unwrap();
+
+        assert_eq!(result.list[0].availability(), Availability::Available);
+        assert_eq!(
+            result.list[1].availability(),
+            Availability::Unavailable {
+                reason: Some(CheckReason {
+                    lang: None,
+                    text: "In use"
+                })
+            }
+        );
+        assert_eq!(
+            result.list[2].availability(),
+            Availability::Unavailable {
+                reason: Some(CheckReason {
+                    lang: None,
+                    text: "Reserved"
+                })
+            }
+        );
+    }
+
+    #[test]
+    fn chunked() {
+        let chunks = HostCheck::chunked(
+            &["ns1.eppdev-1.com", "host1.eppdev-1.com", "ns2.eppdev-1.com"],
+            2,
+        )
+        .collect::<Vec<_>>();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].hosts.len(), 2);
+        assert_eq!(chunks[1].hosts.len(), 1);
+    }
+
+    #[test]
+    fn response_map() {
+        let object = response_from_file::<HostCheck>("response/host/check.xml");
+        let map = object.into_data().unwrap().into_map();
+
+        assert_eq!(map.len(), 2);
+        assert!(!map["ns1.testing.com"].availability().is_available());
+    }
 }
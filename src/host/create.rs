@@ -7,6 +7,7 @@ use instant_xml::{FromXml, ToXml};
 
 use super::{serialize_host_addrs_option, XMLNS};
 use crate::common::{NoExtension, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for HostCreate<'_> {}
@@ -14,6 +15,10 @@ impl Transaction<NoExtension> for HostCreate<'_> {}
 impl Command for HostCreate<'_> {
     type Response = CreateData;
     const COMMAND: &'static str = "create";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::HostCreate(self)
+    }
 }
 
 impl<'a> HostCreate<'a> {
@@ -22,6 +27,16 @@ impl<'a> HostCreate<'a> {
             host: HostCreateRequest { name, addresses },
         }
     }
+
+    /// The name of the host to create
+    pub fn name(&self) -> &'a str {
+        self.host.name
+    }
+
+    /// The addresses of the host to create
+    pub fn addresses(&self) -> Option<&'a [IpAddr]> {
+        self.host.addresses
+    }
 }
 
 // Request
@@ -4,6 +4,7 @@ use instant_xml::ToXml;
 
 use super::XMLNS;
 use crate::common::{NoExtension, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for HostDelete<'_> {}
@@ -11,6 +12,10 @@ impl Transaction<NoExtension> for HostDelete<'_> {}
 impl Command for HostDelete<'_> {
     type Response = ();
     const COMMAND: &'static str = "delete";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::HostDelete(self)
+    }
 }
 
 impl<'a> HostDelete<'a> {
@@ -19,6 +24,11 @@ impl<'a> HostDelete<'a> {
             host: HostDeleteRequest { name },
         }
     }
+
+    /// The name of the host to delete
+    pub fn name(&self) -> &'a str {
+        self.host.name
+    }
 }
 
 /// Type for data under the host `<delete>` tag
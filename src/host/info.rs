@@ -7,7 +7,8 @@ use chrono::{DateTime, Utc};
 use instant_xml::{FromXml, ToXml};
 
 use super::{HostAddr, Status, XMLNS};
-use crate::common::{NoExtension, EPP_XMLNS};
+use crate::common::{NoExtension, Roid, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for HostInfo<'_> {}
@@ -15,6 +16,10 @@ impl Transaction<NoExtension> for HostInfo<'_> {}
 impl Command for HostInfo<'_> {
     type Response = InfoData;
     const COMMAND: &'static str = "info";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::HostInfo(self)
+    }
 }
 
 impl<'a> HostInfo<'a> {
@@ -23,6 +28,11 @@ impl<'a> HostInfo<'a> {
             info: HostInfoRequestData { name },
         }
     }
+
+    /// The name of the host to query
+    pub fn name(&self) -> &'a str {
+        self.info.name
+    }
 }
 
 // Request
@@ -53,7 +63,7 @@ pub struct InfoData {
     /// The host name
     pub name: String,
     /// The host ROID
-    pub roid: String,
+    pub roid: Roid,
     /// The list of host statuses
     #[xml(rename = "status")]
     pub statuses: Vec<Status>,
@@ -89,6 +99,30 @@ pub struct InfoData {
     pub transferred_at: Option<DateTime<Utc>>,
 }
 
+impl InfoData {
+    /// Whether the host has the given status
+    pub fn has_status(&self, status: Status) -> bool {
+        self.statuses.contains(&status)
+    }
+
+    /// Whether the host is referenced by any domain
+    ///
+    /// RFC 5732 doesn't report how many domains link to a host, only the `linked` status.
+    pub fn is_linked(&self) -> bool {
+        self.has_status(Status::Linked)
+    }
+
+    /// The IPv4 addresses of the host
+    pub fn ipv4_addresses(&self) -> impl Iterator<Item = &IpAddr> {
+        self.addresses.iter().filter(|addr| addr.is_ipv4())
+    }
+
+    /// The IPv6 addresses of the host
+    pub fn ipv6_addresses(&self) -> impl Iterator<Item = &IpAddr> {
+        self.addresses.iter().filter(|addr| addr.is_ipv6())
+    }
+}
+
 fn deserialize_host_addrs(
     into: &mut Vec<IpAddr>,
     field: &'static str,
@@ -167,4 +201,24 @@ mod tests {
         assert_eq!(object.tr_ids.client_tr_id.unwrap(), CLTRID);
         assert_eq!(object.tr_ids.server_tr_id, SVTRID);
     }
+
+    #[test]
+    fn response_full() {
+        let object = response_from_file::<HostInfo>("response/host/info_full.xml");
+        let result = object.res_data().unwrap();
+
+        assert_eq!(result.roid, "NS1_EXAMPLE1-REP");
+        assert_eq!(
+            result.statuses,
+            [Status::Linked, Status::ClientUpdateProhibited]
+        );
+        assert!(result.is_linked());
+        assert!(result.has_status(Status::ClientUpdateProhibited));
+        assert_eq!(result.ipv4_addresses().count(), 2);
+        assert_eq!(result.ipv6_addresses().count(), 1);
+        assert_eq!(
+            result.transferred_at,
+            Utc.with_ymd_and_hms(2000, 4, 8, 9, 0, 0).single()
+        );
+    }
 }
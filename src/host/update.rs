@@ -6,6 +6,7 @@ use instant_xml::ToXml;
 
 use super::{serialize_host_addrs_option, Status, XMLNS};
 use crate::common::{NoExtension, EPP_XMLNS};
+use crate::middleware::CommandView;
 use crate::request::{Command, Transaction};
 
 impl Transaction<NoExtension> for HostUpdate<'_> {}
@@ -13,6 +14,10 @@ impl Transaction<NoExtension> for HostUpdate<'_> {}
 impl Command for HostUpdate<'_> {
     type Response = ();
     const COMMAND: &'static str = "update";
+
+    fn view(&self) -> CommandView<'_> {
+        CommandView::HostUpdate(self)
+    }
 }
 
 impl<'a> HostUpdate<'a> {
@@ -41,6 +46,11 @@ impl<'a> HostUpdate<'a> {
     pub fn remove(&mut self, remove: HostRemove<'a>) {
         self.host.remove = Some(remove);
     }
+
+    /// The name of the host to update
+    pub fn name(&self) -> &'a str {
+        self.host.name
+    }
 }
 
 /// Type for data under the `<chg>` tag
@@ -0,0 +1,210 @@
+//! Safety interlocks for destructive commands
+//!
+//! Deleting a flagship domain, or approving its transfer to another registrar, takes one
+//! command and is hard or impossible to undo. An [`Interlock`] is a [`Middleware`] that refuses
+//! such commands for protected names outright, and can require every destructive command to be
+//! confirmed by a callback, for example one asking a second operator.
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use crate::domain::normalize_name;
+use crate::middleware::{CommandView, Middleware, Request};
+use crate::Error;
+
+type Confirm = Box<dyn Fn(&Request<'_>) -> bool + Send + Sync>;
+
+/// A [`Middleware`] guarding against destructive commands
+///
+/// Domain deletes and transfer approvals (transfers away from this registrar) are refused for
+/// protected domains. If a confirmation callback is set, it is called for these commands and
+/// for host and contact deletes, and the command is only sent if it returns `true`.
+#[derive(Default)]
+pub struct Interlock {
+    protected: Vec<String>,
+    confirm: Option<Confirm>,
+}
+
+impl Interlock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Protect domains matching `pattern` from deletion and transfer
+    ///
+    /// `*` in the pattern matches any sequence of characters, so `*.example.com` protects
+    /// all subdomains of `example.com` (but not `example.com` itself) and `example.*` protects
+    /// `example` under every TLD. Names are compared after
+    /// [normalization](crate::domain::normalize_name).
+    pub fn protect(mut self, pattern: &str) -> Self {
+        self.protected.push(normalize_name(pattern));
+        self
+    }
+
+    /// Call `confirm` for every destructive command, sending it only if it returns `true`
+    pub fn confirm_with(
+        mut self,
+        confirm: impl Fn(&Request<'_>) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.confirm = Some(Box::new(confirm));
+        self
+    }
+
+    /// Whether the domain `name` is protected
+    pub fn is_protected(&self, name: &str) -> bool {
+        let name = normalize_name(name);
+        self.protected
+            .iter()
+            .any(|pattern| wildcard_match(pattern, &name))
+    }
+}
+
+impl Middleware for Interlock {
+    fn request(&self, request: &mut Request<'_>) -> Result<(), Error> {
+        let (object, domain) = match request.command {
+            CommandView::DomainDelete(delete) => (delete.name(), true),
+            CommandView::DomainTransfer(transfer) if transfer.operation == "approve" => {
+                (transfer.name(), true)
+            }
+            CommandView::HostDelete(delete) => (delete.name(), false),
+            CommandView::ContactDelete(delete) => (delete.id(), false),
+            _ => return Ok(()),
+        };
+
+        let refused = |reason| Interlocked {
+            command: request.name,
+            object: object.to_owned(),
+            reason,
+        };
+
+        if domain && self.is_protected(object) {
+            return Err(refused(InterlockReason::Protected).into());
+        }
+
+        match &self.confirm {
+            Some(confirm) if !confirm(request) => {
+                Err(refused(InterlockReason::NotConfirmed).into())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+// Match `name` against `pattern`, where `*` matches any (possibly empty) sequence
+fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+    let mut rest = match name.strip_prefix(first) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let mut parts = parts.peekable();
+    if parts.peek().is_none() {
+        return rest.is_empty();
+    }
+
+    while let Some(part) = parts.next() {
+        if parts.peek().is_none() {
+            return rest.ends_with(part);
+        }
+
+        match rest.find(part) {
+            Some(at) => rest = &rest[at + part.len()..],
+            None => return false,
+        }
+    }
+
+    true
+}
+
+/// A command refused by an [`Interlock`]
+///
+/// Returned as [`Error::Other`]; nothing was sent to the registry.
+#[derive(Debug)]
+pub struct Interlocked {
+    /// The command verb, like `delete`
+    pub command: &'static str,
+    /// The name or ID of the object the command acts on
+    pub object: String,
+    pub reason: InterlockReason,
+}
+
+/// Why an [`Interlock`] refused a command
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum InterlockReason {
+    /// The domain is protected
+    Protected,
+    /// The confirmation callback did not confirm the command
+    NotConfirmed,
+}
+
+impl fmt::Display for Interlocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (command, object) = (self.command, &self.object);
+        match self.reason {
+            InterlockReason::Protected => write!(f, "{command} of {object} refused: protected"),
+            InterlockReason::NotConfirmed => {
+                write!(f, "{command} of {object} refused: not confirmed")
+            }
+        }
+    }
+}
+
+impl StdError for Interlocked {}
+
+impl From<Interlocked> for crate::Error {
+    fn from(err: Interlocked) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{wildcard_match, Interlock};
+    use crate::domain::{DomainDelete, DomainTransfer};
+    use crate::host::HostDelete;
+    use crate::middleware::{Middleware, Request};
+    use crate::request::Command;
+
+    #[test]
+    fn wildcards() {
+        assert!(wildcard_match("example.com", "example.com"));
+        assert!(!wildcard_match("example.com", "example.co"));
+        assert!(wildcard_match("*.example.com", "www.example.com"));
+        assert!(!wildcard_match("*.example.com", "example.com"));
+        assert!(wildcard_match("example.*", "example.co.uk"));
+        assert!(wildcard_match("ex*le.*", "example.net"));
+        assert!(!wildcard_match("ex*le.*", "exam.net"));
+    }
+
+    #[test]
+    fn refuses() {
+        let interlock = Interlock::new()
+            .protect("Example.com")
+            .confirm_with(|request| request.id.ends_with("confirmed"));
+
+        let delete = DomainDelete::new("example.com");
+        let mut req = Request::new(delete.view(), DomainDelete::COMMAND, "confirmed");
+        let err = interlock.request(&mut req).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "error: delete of example.com refused: protected"
+        );
+
+        let transfer = DomainTransfer::approve("example.com");
+        let mut req = Request::new(transfer.view(), DomainTransfer::COMMAND, "confirmed");
+        assert!(interlock.request(&mut req).is_err());
+
+        let delete = HostDelete::new("ns1.example.com");
+        let mut req = Request::new(delete.view(), HostDelete::COMMAND, "abc");
+        let err = interlock.request(&mut req).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "error: delete of ns1.example.com refused: not confirmed"
+        );
+
+        req.id = "abc-confirmed".into();
+        assert!(interlock.request(&mut req).is_ok());
+    }
+}
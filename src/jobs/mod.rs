@@ -0,0 +1,3 @@
+//! Bulk jobs running over connection pools
+
+pub mod renewals;
@@ -0,0 +1,384 @@
+//! Renewing a batch of domains
+//!
+//! A renewal run starts from the domains coming due, each with its current expiry date and
+//! the number of years to add. A [`RenewalJob`] routes them to the [`EppPool`] of the
+//! registry operating their TLD, checks the renewal price first at registries that charge
+//! premium rates for some names, and sends the renewals no faster than each registry allows.
+//! The [`RenewalReport`] lists the domains renewed with their new expiry, those that failed
+//! with the reason, and the premium names left for a person to decide on.
+
+use std::time::Duration;
+
+use chrono::{DateTime, NaiveDate, Utc};
+use tokio::time::Instant;
+
+use crate::client::Connector;
+use crate::domain::{normalize_name, DomainCheck, DomainRenew, Period, PeriodLength};
+use crate::extensions::{fee, fee023, fee07, fee_compat};
+use crate::pool::{join_all, EppPool};
+use crate::quote::from_fees;
+use crate::Error;
+
+/// A domain to renew
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Renewal {
+    pub name: String,
+    /// The current expiry date, as sent in `<curExpDate>`
+    pub expiry: NaiveDate,
+    pub years: u8,
+}
+
+impl Renewal {
+    pub fn new(name: &str, expiry: NaiveDate, years: u8) -> Self {
+        Self {
+            name: name.to_owned(),
+            expiry,
+            years,
+        }
+    }
+}
+
+/// A registry taking part in a [`RenewalJob`]
+pub struct Registry<'a, C: Connector> {
+    name: String,
+    pool: &'a EppPool<C>,
+    tlds: Vec<String>,
+    fee_check: Option<&'static str>,
+    interval: Duration,
+}
+
+impl<'a, C: Connector> Registry<'a, C> {
+    /// A registry called `name` (for the report), reached through `pool`
+    pub fn new(name: &str, pool: &'a EppPool<C>) -> Self {
+        Self {
+            name: name.to_owned(),
+            pool,
+            tlds: Vec::new(),
+            fee_check: None,
+            interval: Duration::ZERO,
+        }
+    }
+
+    /// Renew names under `tld` at this registry
+    ///
+    /// A multi-label `tld` (like `co.uk`) takes precedence over its parent.
+    pub fn tld(mut self, tld: &str) -> Self {
+        self.tlds.push(tld.trim_matches('.').to_ascii_lowercase());
+        self
+    }
+
+    /// Check the renewal price of each name with the fee extension in `xmlns` first
+    ///
+    /// `xmlns` is the namespace of fee-1.0, fee-0.23 or fee-0.7, as supported by the registry.
+    /// Names the registry reports as premium are skipped.
+    pub fn fee_check(mut self, xmlns: &'static str) -> Self {
+        self.fee_check = Some(xmlns);
+        self
+    }
+
+    /// Start a renewal at most once per `interval`
+    ///
+    /// This spaces out the renewals sent to the registry; the number of commands in flight is
+    /// limited by the pool.
+    pub fn interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    async fn renew(&self, renewal: &Renewal, id: &str) -> RenewalOutcome {
+        let name = renewal.name.clone();
+        match self.try_renew(renewal, id).await {
+            Ok(Priced::Standard(expiry)) => RenewalOutcome::Renewed {
+                name,
+                registry: self.name.clone(),
+                expiry,
+            },
+            Ok(Priced::Premium { price, currency }) => RenewalOutcome::SkippedPremium {
+                name,
+                registry: self.name.clone(),
+                price,
+                currency,
+            },
+            Err(error) => RenewalOutcome::Failed {
+                name,
+                registry: Some(self.name.clone()),
+                error,
+            },
+        }
+    }
+
+    async fn try_renew(&self, renewal: &Renewal, id: &str) -> Result<Priced, Error> {
+        let period = Period::Years(PeriodLength::new(renewal.years)?);
+        if let Some(xmlns) = self.fee_check {
+            let names = [renewal.name.as_str()];
+            let check = DomainCheck { domains: &names };
+            let years = Some(u16::from(renewal.years));
+            let ext = match xmlns {
+                fee::XMLNS => fee_compat::Check::from(fee::Check::new(None, years)),
+                fee023::XMLNS => fee023::Check::new(None, years).into(),
+                fee07::XMLNS => fee07::Check::new(names, None, years).into(),
+                _ => {
+                    return Err(Error::Other(
+                        format!("unsupported fee version {xmlns}").into(),
+                    ))
+                }
+            };
+
+            let rsp = self
+                .pool
+                .transact((&check, &ext), &format!("{id}:fee"))
+                .await?;
+            let quote = from_fees(&renewal.name, rsp.res_data(), rsp.extension());
+            if quote.premium {
+                return Ok(Priced::Premium {
+                    price: quote.renew_price,
+                    currency: quote.currency,
+                });
+            }
+        }
+
+        let renew = DomainRenew::new(&renewal.name, renewal.expiry, period);
+        let rsp = self.pool.transact(&renew, id).await?;
+        Ok(Priced::Standard(
+            rsp.res_data().and_then(|data| data.expiring_at),
+        ))
+    }
+}
+
+enum Priced {
+    Standard(Option<DateTime<Utc>>),
+    Premium {
+        price: Option<f64>,
+        currency: Option<String>,
+    },
+}
+
+/// Renews batches of domains over the pools of one or more registries
+pub struct RenewalJob<'a, C: Connector> {
+    registries: Vec<Registry<'a, C>>,
+}
+
+impl<'a, C: Connector> RenewalJob<'a, C> {
+    pub fn new() -> Self {
+        Self {
+            registries: Vec::new(),
+        }
+    }
+
+    pub fn registry(mut self, registry: Registry<'a, C>) -> Self {
+        self.registries.push(registry);
+        self
+    }
+
+    /// Renew `renewals`, concurrently across registries
+    ///
+    /// At each registry, domains are renewed in the order of their expiry date, earliest
+    /// first. The client transaction IDs are `{id}:{seq}`, numbered in the order of the
+    /// report, with `:fee` appended for fee checks. Names under a TLD no registry was set up
+    /// for fail without sending a command.
+    pub async fn run(
+        &self,
+        renewals: impl IntoIterator<Item = Renewal>,
+        id: &str,
+    ) -> RenewalReport {
+        let tlds = self
+            .registries
+            .iter()
+            .map(|registry| registry.tlds.as_slice())
+            .collect::<Vec<_>>();
+        let (planned, unrouted) = plan(&tlds, renewals);
+
+        let start = Instant::now();
+        let ids = (1..=planned.len())
+            .map(|seq| format!("{id}:{seq}"))
+            .collect::<Vec<_>>();
+        let pending = planned.iter().zip(&ids).map(|(planned, id)| {
+            let registry = &self.registries[planned.index];
+            let renewal = &planned.renewal;
+            let at = start + registry.interval * planned.slot;
+            async move {
+                tokio::time::sleep_until(at).await;
+                registry.renew(renewal, id).await
+            }
+        });
+
+        let mut outcomes = join_all(pending).await;
+        outcomes.extend(unrouted.into_iter().map(|renewal| RenewalOutcome::Failed {
+            error: Error::Other(format!("no registry for {}", renewal.name).into()),
+            name: renewal.name,
+            registry: None,
+        }));
+        RenewalReport { outcomes }
+    }
+}
+
+impl<C: Connector> Default for RenewalJob<'_, C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The outcome of a [`RenewalJob`] run
+#[derive(Debug)]
+pub struct RenewalReport {
+    /// One outcome per domain, grouped by registry
+    pub outcomes: Vec<RenewalOutcome>,
+}
+
+impl RenewalReport {
+    pub fn renewed(&self) -> impl Iterator<Item = &RenewalOutcome> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| matches!(outcome, RenewalOutcome::Renewed { .. }))
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &RenewalOutcome> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| matches!(outcome, RenewalOutcome::Failed { .. }))
+    }
+
+    pub fn skipped(&self) -> impl Iterator<Item = &RenewalOutcome> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| matches!(outcome, RenewalOutcome::SkippedPremium { .. }))
+    }
+}
+
+/// What happened to a single domain in a [`RenewalJob`] run
+#[derive(Debug)]
+pub enum RenewalOutcome {
+    Renewed {
+        name: String,
+        registry: String,
+        /// The new expiry, if the registry returned one
+        expiry: Option<DateTime<Utc>>,
+    },
+    Failed {
+        name: String,
+        /// `None` if no registry was set up for the name's TLD
+        registry: Option<String>,
+        error: Error,
+    },
+    /// The registry reported the name as premium, so it was not renewed
+    SkippedPremium {
+        name: String,
+        registry: String,
+        /// The quoted price of the renewal, if the registry returned one
+        price: Option<f64>,
+        currency: Option<String>,
+    },
+}
+
+impl RenewalOutcome {
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Renewed { name, .. }
+            | Self::Failed { name, .. }
+            | Self::SkippedPremium { name, .. } => name,
+        }
+    }
+}
+
+// A renewal routed to the registry at `index`, as the `slot`-th renewal sent there
+#[derive(Debug)]
+struct Planned {
+    index: usize,
+    slot: u32,
+    renewal: Renewal,
+}
+
+// Order `renewals` by registry, then by expiry date; renewals no registry takes are returned
+// separately
+fn plan(
+    tlds: &[&[String]],
+    renewals: impl IntoIterator<Item = Renewal>,
+) -> (Vec<Planned>, Vec<Renewal>) {
+    let mut routed = Vec::new();
+    let mut unrouted = Vec::new();
+    for renewal in renewals {
+        match route(tlds, &renewal.name) {
+            Some(index) => routed.push((index, renewal)),
+            None => unrouted.push(renewal),
+        }
+    }
+
+    routed.sort_by(|(a, x), (b, y)| (a, x.expiry, &x.name).cmp(&(b, y.expiry, &y.name)));
+    let mut planned: Vec<Planned> = Vec::with_capacity(routed.len());
+    let mut slot = 0;
+    for (index, renewal) in routed {
+        match planned.last() {
+            Some(last) if last.index == index => slot += 1,
+            _ => slot = 0,
+        }
+        planned.push(Planned {
+            index,
+            slot,
+            renewal,
+        });
+    }
+
+    (planned, unrouted)
+}
+
+// The registry for the longest TLD `name` falls under
+fn route(tlds: &[&[String]], name: &str) -> Option<usize> {
+    let name = normalize_name(name);
+    let mut suffix = name.as_str();
+    while let Some((_, parent)) = suffix.split_once('.') {
+        let found = tlds
+            .iter()
+            .position(|tlds| tlds.iter().any(|tld| tld == parent));
+        if found.is_some() {
+            return found;
+        }
+        suffix = parent;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{plan, Renewal};
+
+    #[test]
+    fn planning() {
+        let date = |month| NaiveDate::from_ymd_opt(2025, month, 1).unwrap();
+        let registries = [
+            vec!["com".to_owned(), "net".to_owned()],
+            vec!["uk".to_owned()],
+            vec!["co.uk".to_owned()],
+        ];
+        let tlds = registries.iter().map(Vec::as_slice).collect::<Vec<_>>();
+
+        let (planned, unrouted) = plan(
+            &tlds,
+            [
+                Renewal::new("example.co.uk", date(3), 1),
+                Renewal::new("example.net", date(5), 1),
+                Renewal::new("example.org", date(1), 1),
+                Renewal::new("Example.COM.", date(2), 2),
+                Renewal::new("example.uk", date(4), 1),
+                Renewal::new("eppdev.com", date(5), 1),
+            ],
+        );
+
+        let order = planned
+            .iter()
+            .map(|planned| (planned.index, planned.slot, planned.renewal.name.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            order,
+            [
+                (0, 0, "Example.COM."),
+                (0, 1, "eppdev.com"),
+                (0, 2, "example.net"),
+                (1, 0, "example.uk"),
+                (2, 0, "example.co.uk"),
+            ]
+        );
+        assert_eq!(unrouted, [Renewal::new("example.org", date(1), 1)]);
+    }
+}
@@ -0,0 +1,182 @@
+//! Durable record of the transforms sent to a registry
+//!
+//! If the process dies between writing a create to the connection and reading the response,
+//! nothing in memory tells it afterwards whether the domain now exists. A
+//! [`TransactionJournal`] attached with [`EppClient::set_journal()`] is written before every
+//! transform is sent and again when its response arrives, so on restart
+//! [`TransactionJournal::in_doubt()`] lists the transforms that need to be checked with the
+//! registry. Once checked, [`EppClient::resolve_transaction()`] takes them off that list.
+//! [`FileJournal`] is a simple append-only implementation.
+//!
+//! [`EppClient::set_journal()`]: crate::EppClient::set_journal
+//! [`EppClient::resolve_transaction()`]: crate::EppClient::resolve_transaction
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::response::ResultCode;
+use crate::Error;
+
+/// Durable storage for the transforms sent by a client
+pub trait TransactionJournal: Send + Sync {
+    /// Record a transform that is about to be sent
+    ///
+    /// The command is not sent if this returns an error.
+    fn begin(&self, entry: &JournalEntry) -> Result<(), Error>;
+
+    /// Record the response to the transform sent with `client_tr_id`
+    fn complete(&self, client_tr_id: &str, outcome: &JournalOutcome) -> Result<(), Error>;
+
+    /// Record that the transform sent with `client_tr_id` is no longer in doubt
+    ///
+    /// Used for transforms that were never written to the connection, and for those whose
+    /// outcome was established some other way, like by looking up the object at the registry.
+    fn resolve(&self, client_tr_id: &str) -> Result<(), Error>;
+
+    /// The transforms that were started but never completed
+    fn in_doubt(&self) -> Result<Vec<JournalEntry>, Error>;
+}
+
+/// A transform as recorded before it is sent
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JournalEntry {
+    /// The name of the registry, as passed to the client
+    pub registry: String,
+    /// The command verb, like `create`
+    pub command: String,
+    /// The name or ID of the object the command acts on, if known
+    pub object: Option<String>,
+    /// The client transaction ID the command was sent with
+    pub client_tr_id: String,
+}
+
+/// The response to a transform
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct JournalOutcome {
+    pub code: ResultCode,
+    pub server_tr_id: String,
+}
+
+/// A [`TransactionJournal`] appending to a text file
+///
+/// Each record is written as a tab-separated line and synced to disk before the call
+/// returns. The file grows without bound; rotate it while the client is idle.
+#[derive(Debug)]
+pub struct FileJournal {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl FileJournal {
+    /// Open the journal at `path`, creating it if it does not exist
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_owned();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(file),
+        })
+    }
+
+    fn append(&self, fields: &[&str]) -> Result<(), Error> {
+        let mut line = fields
+            .iter()
+            .map(|field| field.replace(['\t', '\n', '\r'], " "))
+            .collect::<Vec<_>>()
+            .join("\t");
+        line.push('\n');
+
+        let mut file = self.file.lock().unwrap_or_else(|err| err.into_inner());
+        file.write_all(line.as_bytes())?;
+        file.sync_data()?;
+        Ok(())
+    }
+}
+
+impl TransactionJournal for FileJournal {
+    fn begin(&self, entry: &JournalEntry) -> Result<(), Error> {
+        self.append(&[
+            "begin",
+            &entry.client_tr_id,
+            &entry.registry,
+            &entry.command,
+            entry.object.as_deref().unwrap_or(""),
+        ])
+    }
+
+    fn complete(&self, client_tr_id: &str, outcome: &JournalOutcome) -> Result<(), Error> {
+        self.append(&[
+            "complete",
+            client_tr_id,
+            &(outcome.code as u16).to_string(),
+            &outcome.server_tr_id,
+        ])
+    }
+
+    fn resolve(&self, client_tr_id: &str) -> Result<(), Error> {
+        self.append(&["resolve", client_tr_id])
+    }
+
+    fn in_doubt(&self) -> Result<Vec<JournalEntry>, Error> {
+        let contents = fs::read_to_string(&self.path)?;
+        let mut pending = Vec::<JournalEntry>::new();
+        for line in contents.lines() {
+            let mut fields = line.split('\t');
+            match (fields.next(), fields.next()) {
+                (Some("begin"), Some(client_tr_id)) => {
+                    let mut field = || fields.next().unwrap_or("").to_owned();
+                    let (registry, command, object) = (field(), field(), field());
+                    pending.push(JournalEntry {
+                        registry,
+                        command,
+                        object: Some(object).filter(|object| !object.is_empty()),
+                        client_tr_id: client_tr_id.to_owned(),
+                    });
+                }
+                (Some("complete" | "resolve"), Some(client_tr_id)) => {
+                    pending.retain(|entry| entry.client_tr_id != client_tr_id);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FileJournal, JournalEntry, JournalOutcome, TransactionJournal};
+    use crate::response::ResultCode;
+
+    #[test]
+    fn in_doubt() {
+        let path = std::env::temp_dir().join(format!("instant-epp-{}.journal", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let journal = FileJournal::open(&path).unwrap();
+
+        let entry = |id: &str, object: &str| JournalEntry {
+            registry: "test".into(),
+            command: "create".into(),
+            object: Some(object.into()),
+            client_tr_id: id.into(),
+        };
+
+        journal.begin(&entry("abc-1", "eppdev.com")).unwrap();
+        journal.begin(&entry("abc-2", "eppdev.net")).unwrap();
+        journal.begin(&entry("abc-3", "eppdev.org")).unwrap();
+        journal.resolve("abc-3").unwrap();
+        let outcome = JournalOutcome {
+            code: ResultCode::CommandCompletedSuccessfully,
+            server_tr_id: "RO-1".into(),
+        };
+        journal.complete("abc-1", &outcome).unwrap();
+
+        // Reopening sees the same records
+        let journal = FileJournal::open(&path).unwrap();
+        assert_eq!(journal.in_doubt().unwrap(), [entry("abc-2", "eppdev.net")]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
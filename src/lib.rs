@@ -11,6 +11,9 @@
 //! - [RFC 5733](https://tools.ietf.org/html/rfc5733) - Extensible Provisioning Protocol (EPP) Contact Mapping
 //! - [RFC 5734](https://tools.ietf.org/html/rfc5734) - Extensible Provisioning Protocol (EPP) Transport over TCP
 //! - [RFC 3915](https://tools.ietf.org/html/rfc3915) - Domain Registry Grace Period Mapping
+//! - [RFC 9167](https://tools.ietf.org/html/rfc9167) - Registry Maintenance Notifications for EPP
+//! - [RFC 4310](https://tools.ietf.org/html/rfc4310) - Domain Name System (DNS) Security Extensions Mapping for EPP
+//! - [RFC 8807](https://tools.ietf.org/html/rfc8807) - Login Security Extension for EPP
 //! - [ConsoliDate mapping](https://www.verisign.com/assets/consolidate-mapping.txt)
 //! - [Namestore Extension Mapping](https://www.verisign.com/assets/epp-sdk/verisign_epp-extension_namestoreext_v01.html)
 //! - [Low Balance Mapping](https://www.verisign.com/assets/epp-sdk/verisign_epp-extension_low-balance_v01.html)
@@ -35,22 +38,75 @@
 #![warn(unreachable_pub)]
 #![warn(clippy::use_self)]
 
+pub mod analyzer;
 #[cfg(feature = "transaction-audit")]
 pub mod audit;
+pub mod balance;
+pub mod budget;
+#[cfg(feature = "client")]
+pub mod cache;
+#[cfg(feature = "client")]
+pub mod certification;
+#[cfg(feature = "client")]
 pub mod client;
+#[cfg(feature = "client")]
+pub mod cltrid;
 pub mod common;
+#[cfg(feature = "compression")]
+pub mod compression;
+#[cfg(feature = "client")]
 pub mod connection;
 pub mod contact;
 mod datetime;
 pub mod domain;
+pub mod dto;
 mod error;
+pub mod error_map;
+pub mod escrow;
+#[cfg(feature = "client")]
+pub mod failover;
+pub mod frame;
+pub mod gateway;
 pub mod hello;
 pub mod host;
+pub mod interlock;
+#[cfg(feature = "pool")]
+pub mod jobs;
+pub mod journal;
 pub mod login;
 pub mod logout;
+pub mod middleware;
+#[cfg(feature = "otel")]
+mod otel;
+#[cfg(feature = "client")]
+pub mod pending;
 pub mod poll;
+#[cfg(feature = "client")]
+pub mod poll_store;
+#[cfg(feature = "pool")]
+pub mod pool;
+#[cfg(feature = "client")]
+pub mod portfolio;
+pub mod price_class;
+pub mod profile;
+mod punycode;
+pub mod quirks;
+#[cfg(feature = "client")]
+pub mod quote;
+#[cfg(feature = "client")]
+pub mod registrar;
 pub mod request;
 pub mod response;
+pub mod retry;
+mod scalar;
+#[cfg(feature = "tower")]
+pub mod service;
+pub mod slo;
+#[cfg(feature = "time")]
+pub mod time_compat;
+#[cfg(feature = "client")]
+pub mod trade;
+pub mod transport;
 pub mod xml;
 
 pub mod extensions {
@@ -58,17 +114,29 @@ pub mod extensions {
     pub mod charge;
     pub mod composite;
     pub mod consolidate;
+    pub mod dkhm;
+    pub mod dnsbe;
+    pub mod eurid;
     pub mod fee;
     pub mod fee023;
     pub mod fee07;
+    pub mod fee_compat;
     pub mod frnic;
+    pub mod idn_variant;
     pub mod launch;
+    pub mod login_sec;
     pub mod low_balance;
+    pub mod maintenance;
     pub mod namestore;
+    pub mod nominet;
+    pub mod privacy;
+    pub mod raw;
     pub mod rgp;
     pub mod secdns;
+    pub mod secdns10;
 }
 
+#[cfg(feature = "client")]
 pub use client::EppClient;
 pub use error::Error;
 
@@ -0,0 +1,335 @@
+//! Typed hooks around the commands sent by the client
+//!
+//! [`Quirks`](crate::quirks::Quirks) rewrite the XML exchanged with a registry, which is the
+//! wrong level for rules like "never delete domains under `example.com`". A [`Middleware`]
+//! attached to the client with [`EppClient::add_middleware()`] sees every command before it is
+//! serialized, as a typed [`CommandView`], and can reject it, replace it with another command or
+//! change its client transaction ID. After the response has been parsed, it sees the typed
+//! response (or the error).
+//!
+//! [`EppClient::add_middleware()`]: crate::EppClient::add_middleware
+
+use std::any::Any;
+use std::mem;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use instant_xml::ser::Context;
+use instant_xml::{Serializer, ToXml};
+
+use crate::balance::Balance;
+use crate::common::EPP_XMLNS;
+use crate::contact::{ContactCheck, ContactCreate, ContactDelete, ContactInfo, ContactUpdate};
+use crate::domain::update::{DomainAdd, DomainChangeInfo, DomainRemove};
+use crate::domain::{
+    self, DomainAuthInfo, DomainCheck, DomainContact, DomainCreate, DomainDelete, DomainInfo,
+    DomainRenew, DomainTransfer, DomainUpdate, NameServers, Period,
+};
+use crate::host::{HostCheck, HostCreate, HostDelete, HostInfo, HostUpdate};
+use crate::request::Command;
+use crate::Error;
+
+/// Inspects, adjusts or rejects commands and observes their responses
+///
+/// Request hooks run in the order the middleware was added to the client; response hooks run
+/// in reverse order, so the first middleware added sees the response last.
+pub trait Middleware: Send + Sync {
+    /// Called before `request` is serialized
+    ///
+    /// Returning an error aborts the transaction without sending anything; the error is
+    /// returned from [`EppClient::transact()`](crate::EppClient::transact) as-is.
+    fn request(&self, request: &mut Request<'_>) -> Result<(), Error> {
+        let _ = request;
+        Ok(())
+    }
+
+    /// Called with the outcome of a command
+    ///
+    /// Also called, with the error, when a middleware added later rejected the command, so
+    /// state kept for the request (see [`Request::key()`]) can be released. A successful
+    /// `response` is the [`Response`](crate::response::Response) returned from
+    /// [`EppClient::transact()`](crate::EppClient::transact); downcast it to the
+    /// `Response<Cmd::Response, Ext::Response>` for the commands of interest. Commands sent with
+    /// [`EppClient::transact_frame()`](crate::EppClient::transact_frame) pass the
+    /// [`RawResponse`](crate::response::RawResponse) instead.
+    fn response(&self, request: &Request<'_>, response: Result<&dyn Any, &Error>) {
+        let _ = (request, response);
+    }
+}
+
+/// A command about to be sent, as seen by a [`Middleware`]
+#[derive(Debug)]
+pub struct Request<'a> {
+    /// The command, for the command types known to this crate
+    pub command: CommandView<'a>,
+    /// The command verb, like `create` or `delete`
+    pub name: &'static str,
+    /// The client transaction ID the command is sent with
+    pub id: String,
+    // The serialized command sent instead, see `replace()`
+    replacement: Option<String>,
+    key: u64,
+    // The balance reported in the response, see `balance()`
+    balance: Option<Balance>,
+}
+
+impl<'a> Request<'a> {
+    pub fn new(command: CommandView<'a>, name: &'static str, id: &str) -> Self {
+        Self {
+            command,
+            name,
+            id: id.to_owned(),
+            replacement: None,
+            key: NEXT_KEY.fetch_add(1, Ordering::Relaxed),
+            balance: None,
+        }
+    }
+
+    /// A key identifying this request, unique within the process
+    ///
+    /// Unlike the client transaction ID, which middleware may change and which callers may
+    /// reuse, the key stays the same from [`Middleware::request()`] to
+    /// [`Middleware::response()`].
+    pub fn key(&self) -> u64 {
+        self.key
+    }
+
+    /// Send `command` instead of the command the transaction was started with
+    ///
+    /// `command` has to be the same kind of command, like another [`DomainCreate`] for a
+    /// domain create, since its response is parsed as the response to the original command.
+    /// It is serialized right away, so it can borrow from the middleware.
+    ///
+    /// Middleware running later still sees the original command in [`Request::command`], so
+    /// add middleware rejecting commands before middleware replacing them.
+    pub fn replace<C: Command>(&mut self, command: &C) -> Result<(), Error> {
+        if C::COMMAND != self.name
+            || mem::discriminant(&command.view()) != mem::discriminant(&self.command)
+        {
+            return Err(Error::Other(
+                format!("cannot replace {} command with {}", self.name, C::COMMAND).into(),
+            ));
+        }
+
+        let xml = serialize_command(command).map_err(|err| Error::Xml(err.into()))?;
+        self.replacement = Some(xml);
+        Ok(())
+    }
+
+    /// Whether a middleware replaced the command
+    pub fn replaced(&self) -> bool {
+        self.replacement.is_some()
+    }
+
+    #[cfg(feature = "client")]
+    pub(crate) fn replacement(&self) -> Option<&str> {
+        self.replacement.as_deref()
+    }
+
+    /// The account balance reported in the response to this request, if any
+    ///
+    /// Read from the response's extension through
+    /// [`Extension::balance()`](crate::request::Extension::balance), so it is only set when
+    /// [`Middleware::response()`] is called.
+    pub fn balance(&self) -> Option<&Balance> {
+        self.balance.as_ref()
+    }
+
+    #[cfg(any(feature = "client", test))]
+    pub(crate) fn set_balance(&mut self, balance: Option<Balance>) {
+        self.balance = balance;
+    }
+
+    #[cfg(feature = "client")]
+    // This request without the borrowed command, for handing the response to middleware after
+    // the command is gone; the command is seen as `CommandView::Other`
+    pub(crate) fn detach(self) -> Request<'static> {
+        Request {
+            command: CommandView::Other(self.name),
+            name: self.name,
+            id: self.id,
+            replacement: self.replacement,
+            key: self.key,
+            balance: self.balance,
+        }
+    }
+}
+
+static NEXT_KEY: AtomicU64 = AtomicU64::new(0);
+
+/// Typed access to a command passed through a [`Middleware`]
+#[derive(Clone, Copy, Debug)]
+#[non_exhaustive]
+pub enum CommandView<'a> {
+    DomainCheck(&'a DomainCheck<'a>),
+    DomainInfo(&'a DomainInfo<'a>),
+    DomainCreate(DomainCreateView<'a>),
+    DomainDelete(&'a DomainDelete<'a>),
+    DomainRenew(&'a DomainRenew<'a>),
+    DomainTransfer(&'a DomainTransfer<'a>),
+    DomainUpdate(DomainUpdateView<'a>),
+    HostCheck(&'a HostCheck<'a>),
+    HostInfo(&'a HostInfo<'a>),
+    HostCreate(&'a HostCreate<'a>),
+    HostDelete(&'a HostDelete<'a>),
+    HostUpdate(&'a HostUpdate<'a>),
+    ContactCheck(&'a ContactCheck<'a>),
+    ContactInfo(&'a ContactInfo<'a>),
+    ContactCreate(&'a ContactCreate<'a>),
+    ContactDelete(&'a ContactDelete<'a>),
+    ContactUpdate(&'a ContactUpdate<'a>),
+    /// Any other command, like `login` or `poll`, identified by its verb
+    Other(&'static str),
+}
+
+impl<'a> CommandView<'a> {
+    /// The name or ID of the object the command acts on
+    ///
+    /// Returns `None` for check commands, which act on several objects, and for commands that
+    /// do not act on an object.
+    pub fn object(&self) -> Option<&'a str> {
+        Some(match *self {
+            Self::DomainInfo(cmd) => cmd.name(),
+            Self::DomainCreate(cmd) => cmd.name,
+            Self::DomainDelete(cmd) => cmd.name(),
+            Self::DomainRenew(cmd) => cmd.domain.name,
+            Self::DomainTransfer(cmd) => cmd.name(),
+            Self::DomainUpdate(cmd) => cmd.name,
+            Self::HostInfo(cmd) => cmd.name(),
+            Self::HostCreate(cmd) => cmd.name(),
+            Self::HostDelete(cmd) => cmd.name(),
+            Self::HostUpdate(cmd) => cmd.name(),
+            Self::ContactInfo(cmd) => cmd.id(),
+            Self::ContactCreate(cmd) => cmd.id(),
+            Self::ContactDelete(cmd) => cmd.id(),
+            Self::ContactUpdate(cmd) => cmd.id(),
+            Self::DomainCheck(_) | Self::HostCheck(_) | Self::ContactCheck(_) | Self::Other(_) => {
+                return None
+            }
+        })
+    }
+
+    /// The type of object the command acts on, like `domain`
+    ///
+    /// Returns `None` for [`CommandView::Other`].
+    pub fn object_type(&self) -> Option<&'static str> {
+        Some(match self {
+            Self::DomainCheck(_)
+            | Self::DomainInfo(_)
+            | Self::DomainCreate(_)
+            | Self::DomainDelete(_)
+            | Self::DomainRenew(_)
+            | Self::DomainTransfer(_)
+            | Self::DomainUpdate(_) => "domain",
+            Self::HostCheck(_)
+            | Self::HostInfo(_)
+            | Self::HostCreate(_)
+            | Self::HostDelete(_)
+            | Self::HostUpdate(_) => "host",
+            Self::ContactCheck(_)
+            | Self::ContactInfo(_)
+            | Self::ContactCreate(_)
+            | Self::ContactDelete(_)
+            | Self::ContactUpdate(_) => "contact",
+            Self::Other(_) => return None,
+        })
+    }
+}
+
+// Serialize `command` as it is written inside the `<command>` element, where the EPP namespace
+// is already the default namespace
+fn serialize_command(command: &impl ToXml) -> Result<String, instant_xml::Error> {
+    let mut xml = String::new();
+    let mut serializer = Serializer::new(&mut xml);
+    serializer.write_start("command", EPP_XMLNS)?;
+    let context = Context::<0> {
+        default_ns: EPP_XMLNS,
+        prefixes: [],
+    };
+    serializer.push(context)?;
+    serializer.end_start()?;
+    command.serialize(None, &mut serializer)?;
+
+    let start = xml.find('>').map_or(0, |end| end + 1);
+    Ok(xml.split_off(start))
+}
+
+// `DomainCreate` and `DomainUpdate` can't be shortened to the lifetime of a borrow of them, since
+// `NameServers` holds its hosts in a `Cow`. Their views hold the name servers as a slice.
+
+/// A [`DomainCreate`] as seen by a [`Middleware`]
+#[derive(Clone, Copy, Debug)]
+pub struct DomainCreateView<'a> {
+    pub name: &'a str,
+    pub period: Period,
+    pub ns: Option<&'a [domain::HostInfo<'a>]>,
+    pub registrant: Option<&'a str>,
+    pub contacts: Option<&'a [DomainContact<'a>]>,
+    pub auth_info: &'a DomainAuthInfo<'a>,
+}
+
+impl<'a> From<&'a DomainCreate<'_>> for DomainCreateView<'a> {
+    fn from(cmd: &'a DomainCreate<'_>) -> Self {
+        let data = &cmd.domain;
+        Self {
+            name: data.name,
+            period: data.period,
+            ns: hosts(&data.ns),
+            registrant: data.registrant,
+            contacts: data.contacts,
+            auth_info: &data.auth_info,
+        }
+    }
+}
+
+/// A [`DomainUpdate`] as seen by a [`Middleware`]
+#[derive(Clone, Copy, Debug)]
+pub struct DomainUpdateView<'a> {
+    pub name: &'a str,
+    pub add: Option<DomainChangesView<'a>>,
+    pub remove: Option<DomainChangesView<'a>>,
+    pub change_info: Option<&'a DomainChangeInfo<'a>>,
+}
+
+impl<'a> From<&'a DomainUpdate<'_>> for DomainUpdateView<'a> {
+    fn from(cmd: &'a DomainUpdate<'_>) -> Self {
+        let data = &cmd.domain;
+        Self {
+            name: data.name,
+            add: data.add.as_ref().map(DomainChangesView::from),
+            remove: data.remove.as_ref().map(DomainChangesView::from),
+            change_info: data.change_info.as_ref(),
+        }
+    }
+}
+
+/// The data added to or removed from a domain in a [`DomainUpdateView`]
+#[derive(Clone, Copy, Debug)]
+pub struct DomainChangesView<'a> {
+    pub ns: Option<&'a [domain::HostInfo<'a>]>,
+    pub contacts: Option<&'a [DomainContact<'a>]>,
+    pub statuses: Option<&'a [domain::Status]>,
+}
+
+impl<'a> From<&'a DomainAdd<'_>> for DomainChangesView<'a> {
+    fn from(add: &'a DomainAdd<'_>) -> Self {
+        Self {
+            ns: hosts(&add.ns),
+            contacts: add.contacts,
+            statuses: add.statuses,
+        }
+    }
+}
+
+impl<'a> From<&'a DomainRemove<'_>> for DomainChangesView<'a> {
+    fn from(remove: &'a DomainRemove<'_>) -> Self {
+        Self {
+            ns: hosts(&remove.ns),
+            contacts: remove.contacts,
+            statuses: remove.statuses,
+        }
+    }
+}
+
+fn hosts<'a>(ns: &'a Option<NameServers<'_>>) -> Option<&'a [domain::HostInfo<'a>]> {
+    ns.as_ref().map(|ns| &*ns.ns)
+}
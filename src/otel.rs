@@ -0,0 +1,130 @@
+//! Tracing spans for EPP commands, correlated with OpenTelemetry traces
+//!
+//! With the `otel` feature, every command sent through [`EppClient::transact()`] runs in an
+//! `epp` span recording the registry, the command verb, the object type and the result code.
+//! Export these with a `tracing-opentelemetry` layer, which names them after the object type and
+//! verb (like `domain create`) and makes them children of the span the command was sent from.
+//! The client can also append the trace ID to the client transaction ID (see
+//! [`EppClient::set_trace_cltrid()`]), so that registry-side logs quoting the clTRID can be
+//! matched with the trace.
+//!
+//! [`EppClient::transact()`]: crate::EppClient::transact
+//! [`EppClient::set_trace_cltrid()`]: crate::EppClient::set_trace_cltrid
+
+use std::borrow::Cow;
+
+use opentelemetry::trace::TraceContextExt;
+use opentelemetry::Context;
+use tracing::{field, info_span, Span};
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use crate::middleware::CommandView;
+use crate::response::ResultCode;
+use crate::Error;
+
+/// The maximum length of a client transaction ID, as defined by RFC 5730
+const MAX_CLTRID_LEN: usize = 64;
+
+pub(crate) fn command_span(registry: &str, view: &CommandView<'_>, command: &'static str) -> Span {
+    let object = view.object_type();
+    let name = match object {
+        Some(object) => format!("{object} {command}"),
+        None => command.to_owned(),
+    };
+
+    info_span!(
+        "epp",
+        otel.name = %name,
+        otel.kind = "client",
+        epp.registry = registry,
+        epp.command = command,
+        epp.object = object,
+        epp.result_code = field::Empty,
+    )
+}
+
+pub(crate) fn record_result<T>(
+    span: &Span,
+    result: &Result<T, Error>,
+    code: impl Fn(&T) -> ResultCode,
+) {
+    let code = match result {
+        Ok(response) => code(response),
+        Err(Error::Command(status)) => status.result.code,
+        Err(_) => return,
+    };
+
+    span.record("epp.result_code", code as u16);
+}
+
+/// Append the ID of the trace `span` belongs to to `id`
+///
+/// The trace is taken from the OpenTelemetry context of `span`, which follows its parent
+/// `tracing` spans, or from the current OpenTelemetry context if no `tracing-opentelemetry`
+/// layer is installed. The ID is returned unchanged if there is no valid trace context, or if
+/// the result would exceed the maximum clTRID length.
+pub(crate) fn traced_id<'a>(span: &Span, id: &'a str) -> Cow<'a, str> {
+    let mut span_context = span.context().span().span_context().clone();
+    if !span_context.is_valid() {
+        span_context = Context::current().span().span_context().clone();
+    }
+    if !span_context.is_valid() {
+        return Cow::Borrowed(id);
+    }
+
+    let traced = format!("{id}:{}", span_context.trace_id());
+    if traced.len() <= MAX_CLTRID_LEN {
+        Cow::Owned(traced)
+    } else {
+        Cow::Borrowed(id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use opentelemetry::trace::noop::NoopTracer;
+    use opentelemetry::trace::{SpanContext, SpanId, TraceContextExt, TraceFlags, TraceId};
+    use opentelemetry::Context;
+    use tracing::info_span;
+    use tracing_opentelemetry::OpenTelemetrySpanExt;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    use super::{command_span, traced_id};
+    use crate::domain::DomainInfo;
+    use crate::middleware::CommandView;
+
+    #[test]
+    fn parent_trace() {
+        let subscriber = tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(NoopTracer::new()));
+        let trace_id = TraceId::from_hex("4bf92f3577b34da6a3ce929d0e0e4736").unwrap();
+        let remote = Context::new().with_remote_span_context(SpanContext::new(
+            trace_id,
+            SpanId::from_hex("00f067aa0ba902b7").unwrap(),
+            TraceFlags::SAMPLED,
+            true,
+            Default::default(),
+        ));
+
+        tracing::subscriber::with_default(subscriber, || {
+            let parent = info_span!("request");
+            parent.set_parent(remote);
+            let _guard = parent.enter();
+
+            let info = DomainInfo::new("eppdev.com", None);
+            let view = CommandView::DomainInfo(&info);
+            assert_eq!(view.object_type(), Some("domain"));
+            let span = command_span("registry", &view, "info");
+            assert_eq!(
+                traced_id(&span, "cltrid:1626454866"),
+                "cltrid:1626454866:4bf92f3577b34da6a3ce929d0e0e4736"
+            );
+
+            let long = "x".repeat(40);
+            assert_eq!(traced_id(&span, &long), long);
+        });
+
+        let span = info_span!("untraced");
+        assert_eq!(traced_id(&span, "cltrid:1626454866"), "cltrid:1626454866");
+    }
+}
@@ -0,0 +1,239 @@
+//! Submitting commands and collecting their responses later
+//!
+//! Queue-based applications record a command's client transaction ID when they submit it and
+//! handle the response somewhere else, possibly after the connection was lost and
+//! re-established. [`EppClient::submit()`] writes a command to the connection and returns a
+//! [`PendingTransaction`] carrying the clTRID, to be awaited for the response. The handle does
+//! not borrow the client, so it can be sent to another task; the client reads the response
+//! before it sends its next command, or on [`EppClient::flush()`]. For a transform whose handle was lost,
+//! [`EppClient::transaction_state()`] tells from the [`ClTridCache`] and the
+//! [`TransactionJournal`] whether a response arrived, and [`EppClient::completed()`] returns it.
+//!
+//! The most dangerous case is a create or delete written to the connection just before it
+//! broke. With [`EppClient::set_lost_response_check()`], the client reconnects, logs in again
+//! and looks the object up before reporting such a failure, returning a [`LostResponse`] that
+//! says whether the command took effect.
+//!
+//! [`ClTridCache`]: crate::cltrid::ClTridCache
+//! [`TransactionJournal`]: crate::journal::TransactionJournal
+
+use std::error::Error as StdError;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tokio::sync::oneshot;
+
+use crate::client::{Connector, EppClient, RequestData};
+use crate::request::{Command, Extension, Transaction};
+use crate::response::Response;
+use crate::Error;
+
+/// A submitted command, resolving to its response when awaited
+///
+/// The command was written to the connection by [`EppClient::submit()`]; its response is read
+/// by the client before it sends its next command, or on [`EppClient::flush()`]. Awaiting the
+/// handle before then waits until the client gets there. Should the client be dropped first,
+/// the handle resolves to [`Error::Closed`].
+pub struct PendingTransaction<R> {
+    id: String,
+    receiver: oneshot::Receiver<Result<R, Error>>,
+}
+
+impl<R> PendingTransaction<R> {
+    /// The client transaction ID the command was sent with
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+}
+
+impl<R> Future for PendingTransaction<R> {
+    type Output = Result<R, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.receiver)
+            .poll(cx)
+            .map(|result| result.unwrap_or(Err(Error::Closed(None))))
+    }
+}
+
+impl<R> fmt::Debug for PendingTransaction<R> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PendingTransaction")
+            .field("id", &self.id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// What the client knows about a transform, see [`EppClient::transaction_state()`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TransactionState {
+    /// Neither the clTRID cache nor the journal knows the client transaction ID
+    Unknown,
+    /// The command was sent, but no response was received
+    InDoubt,
+    /// A response was received
+    Completed,
+}
+
+/// How to look up objects for [`EppClient::set_lost_response_check()`]
+///
+/// The client doesn't keep the credentials for the session; `relogin` logs in on the new
+/// connection, with the client and the client transaction ID to use, and can fetch the password
+/// when it is needed:
+///
+/// ```no_run
+/// use instant_epp::client::Connector;
+/// use instant_epp::login::Login;
+/// use instant_epp::pending::LostResponseCheck;
+/// use instant_epp::Error;
+///
+/// fn check<C>() -> LostResponseCheck<C>
+/// where
+///     C: Connector + Send + Sync,
+///     C::Connection: Send,
+/// {
+///     LostResponseCheck::new("registrar", |client, id| {
+///         Box::pin(async move {
+///             let password = std::env::var("EPP_PASSWORD").map_err(|e| Error::Other(e.into()))?;
+///             let login = Login::new("registrar", &password, None, None);
+///             client.transact(&login, id).await?;
+///             Ok(())
+///         })
+///     })
+/// }
+/// ```
+pub struct LostResponseCheck<C: Connector> {
+    client_id: String,
+    relogin: Arc<Relogin<C>>,
+}
+
+type Relogin<C> = dyn for<'a> Fn(
+        &'a mut EppClient<C>,
+        &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>>
+    + Send
+    + Sync;
+
+impl<C: Connector> LostResponseCheck<C> {
+    /// Look up objects as the registrar `client_id`, logging in with `relogin`
+    pub fn new(
+        client_id: &str,
+        relogin: impl for<'a> Fn(
+                &'a mut EppClient<C>,
+                &'a str,
+            ) -> Pin<Box<dyn Future<Output = Result<(), Error>> + Send + 'a>>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        Self {
+            client_id: client_id.to_owned(),
+            relogin: Arc::new(relogin),
+        }
+    }
+
+    /// The client ID of the registrar, as seen in the `<clID>` of the objects it sponsors
+    pub fn client_id(&self) -> &str {
+        &self.client_id
+    }
+
+    pub(crate) async fn relogin(&self, client: &mut EppClient<C>, id: &str) -> Result<(), Error> {
+        (self.relogin)(client, id).await
+    }
+}
+
+impl<C: Connector> Clone for LostResponseCheck<C> {
+    fn clone(&self) -> Self {
+        Self {
+            client_id: self.client_id.clone(),
+            relogin: self.relogin.clone(),
+        }
+    }
+}
+
+impl<C: Connector> fmt::Debug for LostResponseCheck<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LostResponseCheck")
+            .field("client_id", &self.client_id)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Whether a transform whose response was lost took effect, judging by the object's state
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Verified {
+    Applied,
+    NotApplied,
+    /// The object could not be looked up, or it doesn't tell
+    Unknown,
+}
+
+/// The connection broke after a create or delete was sent, and the object was looked up
+///
+/// Returned as [`Error::Other`] in place of the I/O error or timeout.
+#[derive(Debug)]
+pub struct LostResponse {
+    /// The client transaction ID of the command
+    pub id: String,
+    /// The command verb, `create` or `delete`
+    pub command: &'static str,
+    /// The domain name or contact ID
+    pub object: String,
+    pub verified: Verified,
+    /// The error the command originally failed with
+    pub cause: Error,
+}
+
+impl fmt::Display for LostResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let verified = match self.verified {
+            Verified::Applied => "took effect",
+            Verified::NotApplied => "did not take effect",
+            Verified::Unknown => "may have taken effect",
+        };
+        write!(
+            f,
+            "response to {} of {} ({}) was lost, command {verified}: {}",
+            self.command, self.object, self.id, self.cause
+        )
+    }
+}
+
+impl StdError for LostResponse {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        Some(&self.cause)
+    }
+}
+
+impl From<LostResponse> for Error {
+    fn from(err: LostResponse) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+impl<C: Connector> EppClient<C> {
+    /// Write a command to the connection, returning a handle that resolves to its response
+    ///
+    /// Errors that keep the command from being sent, like a rejection by [`Middleware`] or a
+    /// reused clTRID, are returned right away; errors sending it resolve the handle.
+    ///
+    /// [`Middleware`]: crate::middleware::Middleware
+    pub async fn submit<'c, 'e, Cmd, Ext>(
+        &mut self,
+        data: impl Into<RequestData<'c, 'e, Cmd, Ext>>,
+        id: &str,
+    ) -> Result<PendingTransaction<Response<Cmd::Response, Ext::Response>>, Error>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+        Cmd::Response: Send,
+        Ext::Response: Send,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let id = self.write_submitted(data.into(), id, sender).await?;
+        Ok(PendingTransaction { id, receiver })
+    }
+}
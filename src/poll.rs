@@ -1,10 +1,12 @@
-use instant_xml::{FromXml, ToXml};
+use chrono::{DateTime, Utc};
+use instant_xml::{Deserializer, FromXml, Id, Kind, ToXml};
 
 use crate::common::{NoExtension, EPP_XMLNS};
 use crate::contact::info::InfoData as ContactInfoData;
 use crate::domain;
-use crate::domain::transfer::{PendingActionData, TransferData};
+use crate::domain::transfer::{PendingActionData, TransferData, TransferDataRef, TransferStatus};
 use crate::extensions::low_balance::LowBalance;
+use crate::extensions::maintenance;
 use crate::extensions::rgp::poll::RgpPollData;
 use crate::host;
 use crate::request::{Command, Transaction};
@@ -81,14 +83,123 @@ pub enum PollData {
     LowBalance(LowBalance),
     /// Data under the `<rgp-poll:pollData>` tag
     RgpPoll(RgpPollData),
+    /// Data under the `<maint:infData>` tag
+    Maintenance(maintenance::InfoData),
+}
+
+impl PollData {
+    /// The domain transfer this message is about, as seen by the registrar `client_id`
+    ///
+    /// Registries send these messages to both sides of a transfer: to the losing registrar
+    /// when a transfer is requested, and to both when it is approved, rejected or cancelled.
+    pub fn domain_transfer(&self, client_id: &str) -> Option<TransferNotice<'_>> {
+        match self {
+            Self::DomainTransfer(data) => Some(TransferNotice {
+                data,
+                status: data.status(),
+                losing: data.ack_id == client_id,
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// The `<resData>` of a poll message, borrowing from the response frame
+///
+/// Only the messages polled most often are covered: parsing any other message with
+/// [`RawResponse::parse()`] fails, so parse the same frame into [`PollData`] then.
+///
+/// [`RawResponse::parse()`]: crate::response::RawResponse::parse
+#[derive(Debug)]
+pub enum PollDataRef<'a> {
+    /// Data under the `<domain:trnData>` tag
+    DomainTransfer(TransferDataRef<'a>),
+    /// Data under the `<domain:infData>` tag
+    DomainInfo(domain::InfoDataRef<'a>),
+}
+
+// Derived forward enums don't borrow, so this mirrors the derive by hand
+impl<'xml: 'a, 'a> FromXml<'xml> for PollDataRef<'a> {
+    fn matches(id: Id<'_>, field: Option<Id<'_>>) -> bool {
+        TransferDataRef::matches(id, field) || domain::InfoDataRef::matches(id, field)
+    }
+
+    fn deserialize<'cx>(
+        into: &mut Self::Accumulator,
+        field: &'static str,
+        deserializer: &mut Deserializer<'cx, 'xml>,
+    ) -> Result<(), instant_xml::Error> {
+        if into.is_some() {
+            return Err(instant_xml::Error::DuplicateValue(field));
+        }
+
+        let id = deserializer.parent();
+        if TransferDataRef::matches(id, None) {
+            let mut value = None;
+            TransferDataRef::deserialize(&mut value, field, deserializer)?;
+            *into = value.map(Self::DomainTransfer);
+        } else if domain::InfoDataRef::matches(id, None) {
+            let mut value = None;
+            domain::InfoDataRef::deserialize(&mut value, field, deserializer)?;
+            *into = value.map(Self::DomainInfo);
+        } else {
+            return Err(instant_xml::Error::UnexpectedTag(format!("{id:?}")));
+        }
+
+        if deserializer.next().is_some() {
+            return Err(instant_xml::Error::UnexpectedState(
+                "unexpected node after wrapped enum variant",
+            ));
+        }
+
+        Ok(())
+    }
+
+    type Accumulator = Option<Self>;
+    const KIND: Kind = Kind::Element;
+}
+
+/// A domain transfer poll message, from the perspective of one registrar
+#[derive(Clone, Copy, Debug)]
+pub struct TransferNotice<'a> {
+    pub data: &'a TransferData,
+    /// The transfer status, `None` for values not defined in RFC 5731
+    pub status: Option<TransferStatus>,
+    /// Whether the registrar is the current sponsor, which is asked to act on the transfer
+    pub losing: bool,
+}
+
+impl TransferNotice<'_> {
+    /// Whether the registrar should approve or reject the transfer
+    ///
+    /// If it does neither before [`decide_by()`](Self::decide_by), the registry usually
+    /// approves the transfer itself.
+    pub fn awaiting_decision(&self) -> bool {
+        self.losing && self.status == Some(TransferStatus::Pending)
+    }
+
+    /// The ID of the registrar that requested the transfer
+    pub fn requester(&self) -> &str {
+        &self.data.requester_id
+    }
+
+    /// The date by which the losing registrar has to act on a pending transfer
+    pub fn decide_by(&self) -> DateTime<Utc> {
+        self.data.ack_by
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::{Ack, Poll, PollData};
+    use super::{Ack, Poll, PollData, PollDataRef};
+    use crate::common::NoExtension;
+    use crate::domain::TransferStatus;
     use crate::host::Status;
-    use crate::response::ResultCode;
-    use crate::tests::{assert_serialized, response_from_file, CLTRID, SUCCESS_MSG, SVTRID};
+    use crate::response::{Response, ResultCode};
+    use crate::tests::{
+        assert_serialized, get_xml, response_from_file, CLTRID, SUCCESS_MSG, SVTRID,
+    };
+    use crate::xml;
 
     use chrono::{TimeZone, Utc};
     use std::net::IpAddr;
@@ -166,6 +277,36 @@ mod tests {
         assert_eq!(object.tr_ids.server_tr_id, SVTRID);
     }
 
+    #[test]
+    fn domain_transfer_notice() {
+        let object = response_from_file::<Poll>("response/poll/poll_domain_transfer.xml");
+        let result = object.res_data().unwrap();
+
+        let notice = result.domain_transfer("ClientY").unwrap();
+        assert_eq!(notice.status, Some(TransferStatus::Pending));
+        assert!(notice.awaiting_decision());
+        assert_eq!(notice.requester(), "eppdev");
+        assert_eq!(
+            notice.decide_by(),
+            Utc.with_ymd_and_hms(2021, 7, 28, 15, 31, 21).unwrap()
+        );
+
+        // The gaining registrar has nothing to decide
+        let notice = result.domain_transfer("eppdev").unwrap();
+        assert!(!notice.losing);
+        assert!(!notice.awaiting_decision());
+
+        let object = response_from_file::<Poll>("response/poll/poll_domain_transfer_approved.xml");
+        let notice = object
+            .res_data()
+            .unwrap()
+            .domain_transfer("ClientY")
+            .unwrap();
+        assert_eq!(notice.status, Some(TransferStatus::ClientApproved));
+        assert!(notice.status.unwrap().is_approved());
+        assert!(!notice.awaiting_decision());
+    }
+
     #[test]
     fn domain_pending_action_response() {
         let object = response_from_file::<Poll>("response/poll/poll_domain_pan.xml");
@@ -324,6 +465,10 @@ mod tests {
             Utc.with_ymd_and_hms(2000, 6, 8, 22, 10, 0).single()
         );
         assert_eq!(msg.message.as_ref().unwrap().text, "Credit balance low.");
+        assert_eq!(
+            msg.to_string(),
+            "message 12346 (4 queued) 2000-06-08T22:10:00Z: Credit balance low."
+        );
 
         assert_eq!(object.tr_ids.client_tr_id.unwrap(), CLTRID);
         assert_eq!(object.tr_ids.server_tr_id, SVTRID);
@@ -345,4 +490,36 @@ mod tests {
         assert_eq!(object.tr_ids.client_tr_id.unwrap(), CLTRID);
         assert_eq!(object.tr_ids.server_tr_id, SVTRID);
     }
+
+    #[test]
+    fn borrowed() {
+        let xml = get_xml("response/poll/poll_domain_transfer.xml").unwrap();
+        let object =
+            xml::deserialize_borrowed::<Response<PollDataRef<'_>, NoExtension>>(&xml).unwrap();
+        let PollDataRef::DomainTransfer(transfer) = object.res_data().unwrap() else {
+            panic!("Wrong type");
+        };
+        assert_eq!(transfer.name, "eppdev-transfer.com");
+        assert!(transfer.name.is_borrowed());
+        assert_eq!(transfer.status(), Some(TransferStatus::Pending));
+        assert_eq!(object.message_queue().unwrap().id, "12345");
+
+        let owned = object.res_data.unwrap().into_inner();
+        let PollDataRef::DomainTransfer(transfer) = owned else {
+            panic!("Wrong type");
+        };
+        assert_eq!(transfer.into_owned().ack_id, "ClientY");
+
+        let xml = get_xml("response/poll/poll_domain_info_offset.xml").unwrap();
+        let object =
+            xml::deserialize_borrowed::<Response<PollDataRef<'_>, NoExtension>>(&xml).unwrap();
+        let PollDataRef::DomainInfo(info) = object.res_data().unwrap() else {
+            panic!("Wrong type");
+        };
+        assert_eq!(info.client_id, "clid-nom-cy-9999");
+
+        // Other messages have to be parsed into `PollData`
+        let xml = get_xml("response/poll/poll_low_balance.xml").unwrap();
+        assert!(xml::deserialize_borrowed::<Response<PollDataRef<'_>, NoExtension>>(&xml).is_err());
+    }
 }
@@ -0,0 +1,176 @@
+//! Exactly-once handling of poll messages
+//!
+//! A poll message disappears from the registry's queue once it is acknowledged. Acknowledging
+//! it right after retrieval loses the message if the application crashes before handling it;
+//! acknowledging it only after handling makes the registry deliver it again if the crash
+//! happens in between, so a billing notification may be processed twice. With a
+//! [`PollStore`], [`EppClient::next_message()`] records each message durably before
+//! acknowledging it, recognizes messages delivered again, and hands recorded messages that
+//! were never marked processed back to the application first.
+
+use std::collections::HashSet;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Utc};
+
+use crate::client::{Connector, EppClient};
+use crate::common::NoExtension;
+use crate::poll::{Ack, Poll, PollData};
+use crate::response::{Response, ResultCode};
+use crate::{xml, Error};
+
+/// Storage for the poll messages retrieved by a client
+pub trait PollStore: Send + Sync {
+    /// Record a message retrieved from the registry, before it is acknowledged
+    ///
+    /// Returns `false` if a message with the same ID was recorded before. The message is not
+    /// acknowledged if this returns an error.
+    fn record(&self, message: &PolledMessage) -> Result<bool, Error>;
+
+    /// Mark the message with the given ID as handled by the application
+    fn processed(&self, id: &str) -> Result<(), Error>;
+
+    /// The recorded messages not yet marked processed, oldest first
+    fn unprocessed(&self) -> Result<Vec<PolledMessage>, Error>;
+}
+
+/// A poll message as retrieved from the registry
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PolledMessage {
+    /// The message ID assigned by the registry
+    pub id: String,
+    /// The date the message was enqueued
+    pub date: Option<DateTime<Utc>>,
+    /// The human-readable text of the message
+    pub text: Option<String>,
+    /// The complete `<poll>` response
+    pub raw: String,
+}
+
+impl PolledMessage {
+    /// Parse the response the message was delivered in
+    pub fn response(&self) -> Result<Response<PollData, NoExtension>, Error> {
+        xml::deserialize(&self.raw)
+    }
+}
+
+/// A [`PollStore`] keeping messages in memory
+///
+/// This only protects against messages delivered twice within the lifetime of the process;
+/// to survive crashes, implement [`PollStore`] on top of durable storage, ideally the one
+/// the application writes the outcome of processing to.
+#[derive(Debug, Default)]
+pub struct MemoryPollStore {
+    inner: Mutex<MemoryInner>,
+}
+
+#[derive(Debug, Default)]
+struct MemoryInner {
+    seen: HashSet<String>,
+    pending: Vec<PolledMessage>,
+}
+
+impl PollStore for MemoryPollStore {
+    fn record(&self, message: &PolledMessage) -> Result<bool, Error> {
+        let mut inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        if !inner.seen.insert(message.id.clone()) {
+            return Ok(false);
+        }
+
+        inner.pending.push(message.clone());
+        Ok(true)
+    }
+
+    fn processed(&self, id: &str) -> Result<(), Error> {
+        let mut inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        inner.pending.retain(|message| message.id != id);
+        Ok(())
+    }
+
+    fn unprocessed(&self) -> Result<Vec<PolledMessage>, Error> {
+        let inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        Ok(inner.pending.clone())
+    }
+}
+
+impl<C: Connector> EppClient<C> {
+    /// The next poll message to handle, or `None` if the queue is empty
+    ///
+    /// Messages recorded in `store` but not marked processed are returned first, without
+    /// contacting the registry. Otherwise a `<poll>` command is sent; a new message is
+    /// recorded in `store` and then acknowledged, a message already recorded is acknowledged
+    /// and skipped. The commands are sent with `id` suffixed by `:` and a sequence number as
+    /// their client transaction IDs.
+    ///
+    /// Call [`PollStore::processed()`] once the application has handled the message.
+    pub async fn next_message(
+        &mut self,
+        store: &dyn PollStore,
+        id: &str,
+    ) -> Result<Option<PolledMessage>, Error> {
+        if let Some(message) = store.unprocessed()?.into_iter().next() {
+            return Ok(Some(message));
+        }
+
+        let mut seq = 0;
+        loop {
+            seq += 1;
+            let rsp = self
+                .transact_retaining_raw(&Poll, &format!("{id}:{seq}"))
+                .await?;
+
+            if rsp.result.code != ResultCode::CommandCompletedSuccessfullyAckToDequeue {
+                return Ok(None);
+            }
+
+            let queue = match rsp.message_queue() {
+                Some(queue) => queue,
+                None => return Ok(None),
+            };
+
+            let message = PolledMessage {
+                id: queue.id.clone(),
+                date: queue.date,
+                text: queue.message.as_ref().map(|msg| msg.text.clone()),
+                raw: rsp.raw_xml().unwrap_or_default().to_owned(),
+            };
+            let new = store.record(&message)?;
+
+            seq += 1;
+            let ack = Ack {
+                message_id: &message.id,
+            };
+            self.transact(&ack, &format!("{id}:{seq}")).await?;
+
+            if new {
+                return Ok(Some(message));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MemoryPollStore, PollStore, PolledMessage};
+
+    #[test]
+    fn memory_store() {
+        let store = MemoryPollStore::default();
+        let message = |id: &str| PolledMessage {
+            id: id.into(),
+            date: None,
+            text: None,
+            raw: String::new(),
+        };
+
+        assert!(store.record(&message("1")).unwrap());
+        assert!(store.record(&message("2")).unwrap());
+        assert!(!store.record(&message("1")).unwrap());
+
+        store.processed("1").unwrap();
+        assert_eq!(store.unprocessed().unwrap(), [message("2")]);
+
+        // A processed message delivered again is still recognized
+        assert!(!store.record(&message("1")).unwrap());
+    }
+}
@@ -0,0 +1,438 @@
+//! Running commands concurrently over several sessions
+//!
+//! An EPP session handles one command at a time, so bulk jobs (like renewing a portfolio)
+//! open several sessions to the same registry. Spreading commands round-robin over those
+//! works until one session degrades: every n-th command then waits for the slow one. An
+//! [`EppPool`] instead sends each command to the session with the least expected wait, based
+//! on the number of commands in flight on it, its recent latency and how often its commands
+//! failed lately, and caps the number of commands queued per session.
+//!
+//! Registries often meter queries and transform commands separately, and a burst of renewals
+//! should not hold up the availability checks behind a storefront. With
+//! [`EppPool::limit_class()`], each [`CommandClass`] gets its own ceiling on the number of
+//! commands in flight across the pool.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex as SyncMutex;
+use std::time::{Duration, Instant};
+
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use tokio::sync::{Mutex, Notify};
+
+use crate::client::{Connector, EppClient, RequestData};
+use crate::request::{is_transform, is_transform_verb, Command, Extension, Transaction};
+use crate::response::{Response, ResultCode};
+use crate::Error;
+
+// The scale of the error rate of a session: a rate of `ERROR_SCALE` means every command failed
+const ERROR_SCALE: u64 = 1024;
+
+/// A set of sessions to the same registry, balancing commands between them
+pub struct EppPool<C: Connector> {
+    sessions: Vec<Session<C>>,
+    max_in_flight: usize,
+    // Commands in flight and their ceiling per class, indexed by `CommandClass::index()`
+    classes: [ClassLoad; 2],
+    // Serializes picking a session, so two callers don't both take the last free slot
+    pick: SyncMutex<()>,
+    released: Notify,
+}
+
+struct Session<C: Connector> {
+    client: Mutex<EppClient<C>>,
+    in_flight: AtomicUsize,
+    // Moving average of the time taken by a command, in microseconds
+    latency: AtomicU64,
+    // Moving average of the share of commands that failed, out of `ERROR_SCALE`
+    errors: AtomicU64,
+}
+
+impl<C: Connector> EppPool<C> {
+    /// Create a pool from logged-in clients, allowing `max_in_flight` commands per session
+    ///
+    /// Commands beyond the limit wait for a slot on any session.
+    pub fn new(clients: Vec<EppClient<C>>, max_in_flight: usize) -> Self {
+        Self {
+            sessions: clients
+                .into_iter()
+                .map(|client| Session {
+                    client: Mutex::new(client),
+                    in_flight: AtomicUsize::new(0),
+                    latency: AtomicU64::new(0),
+                    errors: AtomicU64::new(0),
+                })
+                .collect(),
+            max_in_flight: max_in_flight.max(1),
+            classes: [ClassLoad::default(), ClassLoad::default()],
+            pick: SyncMutex::new(()),
+            released: Notify::new(),
+        }
+    }
+
+    /// Allow at most `max_in_flight` commands of `class` in flight across all sessions
+    ///
+    /// Commands beyond the limit wait until a command of the same class completes, even if
+    /// sessions are free. Without a limit, a class can use all sessions.
+    pub fn limit_class(mut self, class: CommandClass, max_in_flight: usize) -> Self {
+        self.classes[class.index()].max = Some(max_in_flight.max(1));
+        self
+    }
+
+    /// Send a command on the least loaded session
+    pub async fn transact<'c, 'e, Cmd, Ext>(
+        &self,
+        data: impl Into<RequestData<'c, 'e, Cmd, Ext>>,
+        id: &str,
+    ) -> Result<Response<Cmd::Response, Ext::Response>, Error>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+    {
+        if self.sessions.is_empty() {
+            return Err(Error::Other("connection pool has no sessions".into()));
+        }
+
+        let data = data.into();
+        let class = match is_transform(Cmd::COMMAND, &data.command.view()) {
+            true => CommandClass::Transform,
+            false => CommandClass::Query,
+        };
+        let index = loop {
+            let released = self.released.notified();
+            if let Some(index) = self.reserve(class) {
+                break index;
+            }
+            released.await;
+        };
+
+        let session = &self.sessions[index];
+        let _slot = Slot {
+            pool: self,
+            index,
+            class,
+        };
+        let mut client = session.client.lock().await;
+        let start = Instant::now();
+        let result = client.transact(data, id).await;
+        let failed = result.as_ref().is_err_and(is_session_failure);
+        let mut elapsed = start.elapsed();
+        if failed {
+            // A session failing fast should not look fast
+            let slowest = self
+                .sessions
+                .iter()
+                .map(|session| session.latency.load(Ordering::Relaxed))
+                .max()
+                .unwrap_or(0);
+            elapsed = elapsed.max(Duration::from_micros(slowest));
+        }
+        session.record(elapsed, failed);
+        result
+    }
+
+    /// Send a batch of commands concurrently over the sessions in the pool
+    ///
+    /// Client transaction IDs and failure handling work like for
+    /// [`EppClient::transact_batch()`]; the results are in the order of `batch`.
+    pub async fn transact_batch<'c, 'e, Cmd, Ext, D>(
+        &self,
+        batch: impl IntoIterator<Item = D>,
+        id: &str,
+    ) -> Vec<Result<Response<Cmd::Response, Ext::Response>, Error>>
+    where
+        Cmd: Transaction<Ext> + Command + 'c,
+        Ext: Extension + 'e,
+        D: Into<RequestData<'c, 'e, Cmd, Ext>>,
+    {
+        let batch = batch.into_iter().map(Into::into).collect::<Vec<_>>();
+        let ids = (1..=batch.len())
+            .map(|seq| format!("{id}:{seq}"))
+            .collect::<Vec<_>>();
+
+        let pending = batch
+            .into_iter()
+            .zip(&ids)
+            .map(|(data, id)| self.transact(data, id));
+        join_all(pending).await
+    }
+
+    /// The number of sessions in the pool
+    pub fn len(&self) -> usize {
+        self.sessions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sessions.is_empty()
+    }
+
+    /// The number of commands of `class` in flight across all sessions
+    pub fn class_in_flight(&self, class: CommandClass) -> usize {
+        self.classes[class.index()]
+            .in_flight
+            .load(Ordering::Relaxed)
+    }
+
+    /// The current load of each session, in the order the clients were passed in
+    pub fn stats(&self) -> Vec<SessionStats> {
+        self.sessions
+            .iter()
+            .map(|session| SessionStats {
+                in_flight: session.in_flight.load(Ordering::Relaxed),
+                latency: Duration::from_micros(session.latency.load(Ordering::Relaxed)),
+                error_rate: session.errors.load(Ordering::Relaxed) as f64 / ERROR_SCALE as f64,
+            })
+            .collect()
+    }
+
+    fn reserve(&self, class: CommandClass) -> Option<usize> {
+        let _pick = self.pick.lock().unwrap_or_else(|err| err.into_inner());
+        let load = &self.classes[class.index()];
+        if let Some(max) = load.max {
+            if load.in_flight.load(Ordering::Relaxed) >= max {
+                return None;
+            }
+        }
+
+        let loads = self.sessions.iter().map(|session| Load {
+            in_flight: session.in_flight.load(Ordering::Relaxed),
+            latency: session.latency.load(Ordering::Relaxed),
+            errors: session.errors.load(Ordering::Relaxed),
+        });
+
+        let index = pick(loads, self.max_in_flight)?;
+        self.sessions[index]
+            .in_flight
+            .fetch_add(1, Ordering::Relaxed);
+        load.in_flight.fetch_add(1, Ordering::Relaxed);
+        Some(index)
+    }
+}
+
+impl<C: Connector> Session<C> {
+    fn record(&self, elapsed: Duration, failed: bool) {
+        let _ = self
+            .errors
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old| {
+                let sample = if failed { ERROR_SCALE } else { 0 };
+                Some((old * 7 + sample) / 8)
+            });
+
+        let sample = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        let _ = self
+            .latency
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |old| {
+                Some(match old {
+                    0 => sample,
+                    old => old.saturating_mul(7).saturating_add(sample) / 8,
+                })
+            });
+    }
+}
+
+// Whether `error` points at a problem with the session, rather than with the command
+//
+// The registry rejecting a command says nothing about the session, unless it failed the
+// command on its side or is closing the connection.
+fn is_session_failure(error: &Error) -> bool {
+    match error {
+        Error::Command(status) => matches!(
+            status.result.code,
+            ResultCode::CommandFailed
+                | ResultCode::CommandFailedServerClosingConnection
+                | ResultCode::AuthenticationErrorServerClosingConnection
+                | ResultCode::SessionLimitExceededServerClosingConnection
+        ),
+        Error::MissingResData => false,
+        Error::Closed(_) | Error::Io(_) | Error::Timeout | Error::Xml(_) | Error::Other(_) => true,
+    }
+}
+
+/// The classes of commands an [`EppPool`] can limit separately
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CommandClass {
+    /// Commands that only read from the registry, like `check`, `info` and `poll`
+    Query,
+    /// Commands that change objects: `create`, `delete`, `renew`, `transfer` and `update`
+    ///
+    /// Transfer queries are not, as they only read the transfer status.
+    Transform,
+}
+
+impl CommandClass {
+    /// The class of the command verb `command`
+    ///
+    /// The verb doesn't tell a transfer query apart from the other transfer operations, so
+    /// `transfer` is always a [`CommandClass::Transform`] here.
+    pub fn of(command: &str) -> Self {
+        if is_transform_verb(command) {
+            Self::Transform
+        } else {
+            Self::Query
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Self::Query => 0,
+            Self::Transform => 1,
+        }
+    }
+}
+
+#[derive(Default)]
+struct ClassLoad {
+    in_flight: AtomicUsize,
+    max: Option<usize>,
+}
+
+/// The load of a session in an [`EppPool`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SessionStats {
+    /// Commands sent or waiting to be sent on this session
+    pub in_flight: usize,
+    /// Moving average of the time taken by a command, zero before the first command
+    ///
+    /// A failed command counts as taking at least as long as the slowest session in the pool.
+    pub latency: Duration,
+    /// Moving average of the share of commands that failed, from 0 to 1
+    ///
+    /// Failures are transport errors and results showing a problem on the registry side, like
+    /// `2400` or the server closing the connection; the registry rejecting a command does not
+    /// count.
+    pub error_rate: f64,
+}
+
+// Releases a session slot, also when the command future is dropped
+struct Slot<'a, C: Connector> {
+    pool: &'a EppPool<C>,
+    index: usize,
+    class: CommandClass,
+}
+
+impl<C: Connector> Drop for Slot<'_, C> {
+    fn drop(&mut self) {
+        self.pool.sessions[self.index]
+            .in_flight
+            .fetch_sub(1, Ordering::Relaxed);
+        self.pool.classes[self.class.index()]
+            .in_flight
+            .fetch_sub(1, Ordering::Relaxed);
+        // Wake all waiters: the first one may be waiting for a slot of the other class
+        self.pool.released.notify_waiters();
+    }
+}
+
+// The state of a session, as considered when picking one
+#[derive(Clone, Copy, Debug)]
+struct Load {
+    in_flight: usize,
+    // Microseconds
+    latency: u64,
+    // Out of `ERROR_SCALE`
+    errors: u64,
+}
+
+// Pick the session with the lowest expected wait
+//
+// The expected wait is the latency times the number of commands the new one queues behind,
+// plus one. Sessions without latency samples count as fast, so that they get tried. With an
+// error rate of `f`, a command takes `1 / (1 - f)` attempts on average, so the wait is scaled
+// by that; a session failing every command still gets one now and then, to notice it recover.
+fn pick(loads: impl Iterator<Item = Load>, max_in_flight: usize) -> Option<usize> {
+    loads
+        .enumerate()
+        .filter(|(_, load)| load.in_flight < max_in_flight)
+        .min_by_key(|(_, load)| {
+            let wait = (load.in_flight as u64 + 1).saturating_mul(load.latency.max(1));
+            let success = ERROR_SCALE - load.errors.min(ERROR_SCALE - ERROR_SCALE / 64);
+            (u128::from(wait) * u128::from(ERROR_SCALE)) / u128::from(success)
+        })
+        .map(|(index, _)| index)
+}
+
+// Run `futures` concurrently, returning their outputs in order
+//
+// Only the futures that were woken are polled again, so this stays cheap for large batches.
+pub(crate) async fn join_all<F: Future>(futures: impl IntoIterator<Item = F>) -> Vec<F::Output> {
+    let mut pending = futures
+        .into_iter()
+        .enumerate()
+        .map(|(index, future)| async move { (index, future.await) })
+        .collect::<FuturesUnordered<_>>();
+
+    let mut outputs = (0..pending.len()).map(|_| None).collect::<Vec<_>>();
+    while let Some((index, output)) = pending.next().await {
+        outputs[index] = Some(output);
+    }
+    outputs.into_iter().flatten().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::{is_session_failure, pick, CommandClass, Load, ERROR_SCALE};
+    use crate::Error;
+
+    fn loads(loads: &[(usize, u64, u64)]) -> impl Iterator<Item = Load> + '_ {
+        loads.iter().map(|&(in_flight, latency, errors)| Load {
+            in_flight,
+            latency,
+            errors,
+        })
+    }
+
+    #[test]
+    fn picks_least_expected_wait() {
+        // Idle sessions first, preferring the faster one
+        let idle = [(0, 900, 0), (0, 300, 0), (1, 200, 0)];
+        assert_eq!(pick(loads(&idle), 4), Some(1));
+        // A degraded session gets fewer commands than round-robin would send it
+        let degraded = [(1, 5_000, 0), (3, 1_000, 0)];
+        assert_eq!(pick(loads(&degraded), 4), Some(1));
+        // Full sessions are skipped
+        assert_eq!(pick(loads(&[(2, 100, 0), (1, 5_000, 0)]), 2), Some(1));
+        assert_eq!(pick(loads(&[(2, 100, 0), (2, 100, 0)]), 2), None);
+        // Sessions without samples get tried
+        assert_eq!(pick(loads(&[(0, 300, 0), (0, 0, 0)]), 4), Some(1));
+    }
+
+    #[test]
+    fn penalizes_errors() {
+        // A fast session failing half its commands loses to a slightly slower one
+        let failing = [(0, 100, ERROR_SCALE / 2), (0, 150, 0)];
+        assert_eq!(pick(loads(&failing), 4), Some(1));
+        // But not to a much slower one
+        let slow = [(0, 100, ERROR_SCALE / 2), (0, 1_000, 0)];
+        assert_eq!(pick(loads(&slow), 4), Some(0));
+        // A session failing every command only gets one if the others are far behind
+        let dead = [(0, 100, ERROR_SCALE), (3, 2_000, 0)];
+        assert_eq!(pick(loads(&dead), 4), Some(0));
+        let dead = [(0, 100, ERROR_SCALE), (1, 1_000, 0)];
+        assert_eq!(pick(loads(&dead), 4), Some(1));
+        // Full sessions are skipped even if healthy
+        assert_eq!(
+            pick(loads(&[(2, 100, 0), (0, 100, ERROR_SCALE)]), 2),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn session_failures() {
+        assert!(is_session_failure(&Error::Timeout));
+        assert!(is_session_failure(&Error::Closed(None)));
+        assert!(is_session_failure(&Error::Io(io::Error::from(
+            io::ErrorKind::ConnectionReset
+        ))));
+        assert!(!is_session_failure(&Error::MissingResData));
+    }
+
+    #[test]
+    fn command_classes() {
+        assert_eq!(CommandClass::of("check"), CommandClass::Query);
+        assert_eq!(CommandClass::of("poll"), CommandClass::Query);
+        assert_eq!(CommandClass::of("renew"), CommandClass::Transform);
+        assert_eq!(CommandClass::of("transfer"), CommandClass::Transform);
+    }
+}
@@ -0,0 +1,427 @@
+//! Utilities for managing sets of domains
+//!
+//! Portfolio managers often want all domains of a customer to expire on the same date. The
+//! functions in this module plan and execute the renewals (or [ConsoliDate] syncs) needed
+//! to get there.
+//!
+//! The domains can be passed in directly, or come from a [`PortfolioSource`]: either an
+//! [`Inventory`] kept by the registrar, or a listing command of the registry like
+//! [`NominetList`], so that no external inventory is needed.
+//!
+//! [ConsoliDate]: crate::extensions::consolidate
+
+use async_trait::async_trait;
+use chrono::{DateTime, Datelike, Months, NaiveDate, Utc};
+
+use crate::client::{Connector, EppClient};
+use crate::domain::update::DomainChangeInfo;
+use crate::domain::{DomainInfo, DomainRenew, DomainUpdate, Period, PeriodLength};
+use crate::extensions::consolidate::{self, GMonthDay};
+use crate::extensions::nominet::{DomainList, ListBy};
+use crate::Error;
+
+/// A domain in a portfolio
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct PortfolioDomain {
+    pub name: String,
+    /// The expiry date, if the source knows it
+    pub expiry: Option<DateTime<Utc>>,
+}
+
+/// Where the domains of a portfolio come from
+///
+/// Implement this for registry-specific commands listing a registrar's (or registrant's)
+/// objects.
+#[async_trait(?Send)]
+pub trait PortfolioSource<C: Connector> {
+    /// The domains in the portfolio
+    ///
+    /// Commands sent to list them use `id` suffixed by `:list:` and a sequence number as their
+    /// client transaction IDs.
+    async fn domains(
+        &self,
+        client: &mut EppClient<C>,
+        id: &str,
+    ) -> Result<Vec<PortfolioDomain>, Error>;
+}
+
+/// A fixed list of domains, for example from the registrar's database
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Inventory(pub Vec<PortfolioDomain>);
+
+#[async_trait(?Send)]
+impl<C: Connector> PortfolioSource<C> for Inventory {
+    async fn domains(&self, _: &mut EppClient<C>, _: &str) -> Result<Vec<PortfolioDomain>, Error> {
+        Ok(self.0.clone())
+    }
+}
+
+/// The domains listed by the Nominet (.uk) list command, for one or more months
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct NominetList {
+    pub months: Vec<ListBy>,
+}
+
+impl NominetList {
+    /// The domains expiring in the months from `start` up to and including `end`
+    pub fn expiring_between(start: NaiveDate, end: NaiveDate) -> Self {
+        let mut months = Vec::new();
+        let (mut year, mut month) = (start.year(), start.month());
+        while (year, month) <= (end.year(), end.month()) {
+            months.push(ListBy::Expiry { year, month });
+            (year, month) = match month {
+                12 => (year + 1, 1),
+                _ => (year, month + 1),
+            };
+        }
+        Self { months }
+    }
+}
+
+#[async_trait(?Send)]
+impl<C: Connector> PortfolioSource<C> for NominetList {
+    async fn domains(
+        &self,
+        client: &mut EppClient<C>,
+        id: &str,
+    ) -> Result<Vec<PortfolioDomain>, Error> {
+        let mut domains = Vec::new();
+        for (i, &by) in self.months.iter().enumerate() {
+            let list = client
+                .transact(&DomainList::new(by), &format!("{id}:list:{}", i + 1))
+                .await?
+                .into_data()?;
+
+            for name in list.domains {
+                if !domains
+                    .iter()
+                    .any(|domain: &PortfolioDomain| domain.name == name)
+                {
+                    domains.push(PortfolioDomain { name, expiry: None });
+                }
+            }
+        }
+
+        Ok(domains)
+    }
+}
+
+/// Options for planning expiry alignment
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct AlignOptions {
+    /// The maximum number of years a registration can extend into the future
+    ///
+    /// Most registries use 10 years, as mandated for gTLDs.
+    pub max_validity_years: u8,
+    /// Whether the registry supports the ConsoliDate sync extension
+    ///
+    /// Without it, domains can only be aligned to targets on the same month and day as their
+    /// current expiry date.
+    pub sync: bool,
+}
+
+impl Default for AlignOptions {
+    fn default() -> Self {
+        Self {
+            max_validity_years: 10,
+            sync: false,
+        }
+    }
+}
+
+/// The commands needed to move a domain's expiry date to a target date
+#[derive(Debug, Eq, PartialEq)]
+pub enum AlignmentPlan {
+    /// The domain already expires on the target date
+    Aligned,
+    /// Renew the domain by the given number of years
+    Renew(u8),
+    /// Sync the expiry date to the target month and day, then renew if `renew` is set
+    ///
+    /// The sync moves the expiry date to the next occurrence of the month and day after the
+    /// current expiry date.
+    Sync { exp: GMonthDay, renew: Option<u8> },
+    /// The target date can't be reached
+    ///
+    /// Either the target date lies before the current expiry date, it is beyond the maximum
+    /// validity period, or reaching it requires a sync the registry doesn't support.
+    Unreachable,
+}
+
+/// Plan the commands needed to move `expiry` to `target`
+///
+/// `today` is used to enforce [`AlignOptions::max_validity_years`].
+pub fn plan_alignment(
+    expiry: NaiveDate,
+    target: NaiveDate,
+    today: NaiveDate,
+    options: &AlignOptions,
+) -> AlignmentPlan {
+    if expiry == target {
+        return AlignmentPlan::Aligned;
+    }
+
+    // On February 29, the limit falls on February 28 of a common year
+    let max = today.checked_add_months(Months::new(12 * u32::from(options.max_validity_years)));
+    if target < expiry || max.is_some_and(|max| target > max) {
+        return AlignmentPlan::Unreachable;
+    }
+
+    if (expiry.month(), expiry.day()) == (target.month(), target.day()) {
+        return match u8::try_from(target.year() - expiry.year()) {
+            Ok(years) => AlignmentPlan::Renew(years),
+            Err(_) => AlignmentPlan::Unreachable,
+        };
+    }
+
+    if !options.sync {
+        return AlignmentPlan::Unreachable;
+    }
+
+    let exp = match GMonthDay::new(target.month() as u8, target.day() as u8, None) {
+        Ok(exp) => exp,
+        Err(_) => return AlignmentPlan::Unreachable,
+    };
+
+    let synced = match next_occurrence(expiry, &exp) {
+        Some(synced) if synced <= target => synced,
+        _ => return AlignmentPlan::Unreachable,
+    };
+
+    match u8::try_from(target.year() - synced.year()) {
+        Ok(0) => AlignmentPlan::Sync { exp, renew: None },
+        Ok(years) => AlignmentPlan::Sync {
+            exp,
+            renew: Some(years),
+        },
+        Err(_) => AlignmentPlan::Unreachable,
+    }
+}
+
+// The first date after `date` that falls on the given month and day
+fn next_occurrence(date: NaiveDate, exp: &GMonthDay) -> Option<NaiveDate> {
+    let (month, day) = (u32::from(exp.month), u32::from(exp.day));
+    match NaiveDate::from_ymd_opt(date.year(), month, day) {
+        Some(next) if next > date => Some(next),
+        _ => NaiveDate::from_ymd_opt(date.year() + 1, month, day),
+    }
+}
+
+/// Align the expiry dates of `domains` to `target`
+///
+/// Each domain is given as its name and current expiry date. The commands for each domain
+/// are planned with [`plan_alignment()`] and sent in order, using `id` suffixed by `:` and a
+/// sequence number as the client transaction IDs. Returns the plan executed for each domain,
+/// in the same order; a failure for one domain does not prevent the others from being aligned.
+pub async fn align_expiry<C: Connector>(
+    client: &mut EppClient<C>,
+    domains: &[(&str, DateTime<Utc>)],
+    target: NaiveDate,
+    options: &AlignOptions,
+    id: &str,
+) -> Vec<Result<AlignmentPlan, Error>> {
+    let today = Utc::now().date_naive();
+    let mut seq = 0;
+    let mut results = Vec::with_capacity(domains.len());
+    for &(name, expiry) in domains {
+        let expiry = expiry.date_naive();
+        let plan = plan_alignment(expiry, target, today, options);
+        let result = execute(client, name, expiry, &plan, id, &mut seq).await;
+        results.push(result.map(|()| plan));
+    }
+
+    results
+}
+
+/// Align the expiry dates of all domains from `source` to `target`
+///
+/// Domains for which `source` doesn't provide an expiry date are looked up with a domain
+/// `<info>` first, using `id` suffixed by `:info:` and a sequence number as the client
+/// transaction ID. The alignment itself works like [`align_expiry()`]. Returns the name of
+/// each domain with its outcome, or an error if the domains couldn't be listed.
+pub async fn align_portfolio<C: Connector>(
+    client: &mut EppClient<C>,
+    source: &impl PortfolioSource<C>,
+    target: NaiveDate,
+    options: &AlignOptions,
+    id: &str,
+) -> Result<Vec<(String, Result<AlignmentPlan, Error>)>, Error> {
+    let domains = source.domains(client, id).await?;
+
+    let mut seq = 0;
+    let mut expiries = Vec::with_capacity(domains.len());
+    for domain in &domains {
+        let expiry = match domain.expiry {
+            Some(expiry) => Ok(expiry),
+            None => {
+                seq += 1;
+                expiry(client, &domain.name, &format!("{id}:info:{seq}")).await
+            }
+        };
+        expiries.push(expiry);
+    }
+
+    let known = domains
+        .iter()
+        .zip(&expiries)
+        .filter_map(|(domain, expiry)| Some((domain.name.as_str(), *expiry.as_ref().ok()?)))
+        .collect::<Vec<_>>();
+    let mut aligned = align_expiry(client, &known, target, options, id)
+        .await
+        .into_iter();
+
+    Ok(domains
+        .into_iter()
+        .zip(expiries)
+        .map(|(domain, expiry)| {
+            let result = match expiry {
+                // One result per domain with a known expiry date, in the same order
+                Ok(_) => aligned.next().unwrap_or(Ok(AlignmentPlan::Unreachable)),
+                Err(err) => Err(err),
+            };
+            (domain.name, result)
+        })
+        .collect())
+}
+
+async fn expiry<C: Connector>(
+    client: &mut EppClient<C>,
+    name: &str,
+    id: &str,
+) -> Result<DateTime<Utc>, Error> {
+    let info = client
+        .transact(&DomainInfo::new(name, None), id)
+        .await?
+        .into_data()?;
+    info.expiring_at
+        .ok_or_else(|| Error::Other(format!("no expiry date for {name}").into()))
+}
+
+async fn execute<C: Connector>(
+    client: &mut EppClient<C>,
+    name: &str,
+    expiry: NaiveDate,
+    plan: &AlignmentPlan,
+    id: &str,
+    seq: &mut usize,
+) -> Result<(), Error> {
+    let (expiry, years) = match plan {
+        AlignmentPlan::Aligned => return Ok(()),
+        AlignmentPlan::Unreachable => {
+            return Err(Error::Other(
+                format!("unable to align expiry date of {name}").into(),
+            ))
+        }
+        AlignmentPlan::Renew(years) => (expiry, Some(*years)),
+        AlignmentPlan::Sync { exp, renew } => {
+            let mut update = DomainUpdate::new(name);
+            update.info(DomainChangeInfo {
+                registrant: None,
+                auth_info: None,
+            });
+
+            let sync = consolidate::Update::new(*exp);
+            *seq += 1;
+            client
+                .transact((&update, &sync), &format!("{id}:{seq}"))
+                .await?;
+
+            // The sync moved the expiry date, which the renew command has to match
+            let expiry = next_occurrence(expiry, exp)
+                .ok_or_else(|| Error::Other(format!("invalid sync date for {name}").into()))?;
+            (expiry, *renew)
+        }
+    };
+
+    if let Some(years) = years {
+        let renew = DomainRenew::new(name, expiry, Period::Years(PeriodLength::new(years)?));
+        *seq += 1;
+        client.transact(&renew, &format!("{id}:{seq}")).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{plan_alignment, AlignOptions, AlignmentPlan, NominetList};
+    use crate::extensions::consolidate::GMonthDay;
+    use crate::extensions::nominet::ListBy;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn alignment() {
+        let today = date(2024, 1, 15);
+        let options = AlignOptions::default();
+
+        let plan = plan_alignment(date(2025, 3, 1), date(2025, 3, 1), today, &options);
+        assert_eq!(plan, AlignmentPlan::Aligned);
+
+        let plan = plan_alignment(date(2025, 3, 1), date(2028, 3, 1), today, &options);
+        assert_eq!(plan, AlignmentPlan::Renew(3));
+
+        // Beyond the maximum validity period
+        let plan = plan_alignment(date(2025, 3, 1), date(2035, 3, 1), today, &options);
+        assert_eq!(plan, AlignmentPlan::Unreachable);
+        let leap_day = date(2024, 2, 29);
+        let plan = plan_alignment(date(2025, 2, 28), date(2034, 2, 28), leap_day, &options);
+        assert_eq!(plan, AlignmentPlan::Renew(9));
+        let plan = plan_alignment(date(2025, 2, 28), date(2035, 2, 28), leap_day, &options);
+        assert_eq!(plan, AlignmentPlan::Unreachable);
+
+        // Before the current expiry date
+        let plan = plan_alignment(date(2025, 3, 1), date(2024, 3, 1), today, &options);
+        assert_eq!(plan, AlignmentPlan::Unreachable);
+
+        // Different month and day requires sync support
+        let plan = plan_alignment(date(2025, 3, 1), date(2027, 6, 30), today, &options);
+        assert_eq!(plan, AlignmentPlan::Unreachable);
+    }
+
+    #[test]
+    fn alignment_with_sync() {
+        let today = date(2024, 1, 15);
+        let options = AlignOptions {
+            sync: true,
+            ..AlignOptions::default()
+        };
+
+        let plan = plan_alignment(date(2025, 3, 1), date(2025, 6, 30), today, &options);
+        assert_eq!(
+            plan,
+            AlignmentPlan::Sync {
+                exp: GMonthDay::new(6, 30, None).unwrap(),
+                renew: None,
+            }
+        );
+
+        let plan = plan_alignment(date(2025, 8, 1), date(2027, 6, 30), today, &options);
+        assert_eq!(
+            plan,
+            AlignmentPlan::Sync {
+                exp: GMonthDay::new(6, 30, None).unwrap(),
+                renew: Some(1),
+            }
+        );
+    }
+
+    #[test]
+    fn nominet_months() {
+        let list = NominetList::expiring_between(date(2024, 11, 20), date(2025, 2, 1));
+        let expiry = |year, month| ListBy::Expiry { year, month };
+        assert_eq!(
+            list.months,
+            [
+                expiry(2024, 11),
+                expiry(2024, 12),
+                expiry(2025, 1),
+                expiry(2025, 2)
+            ]
+        );
+    }
+}
@@ -0,0 +1,182 @@
+//! Typed price classes from fee and charge responses
+//!
+//! The fee extension's `<fee:class>` and the charge extension's `<charge:category>` carry
+//! free-form labels: `standard`, `Premium`, `premium-tier-b`, or registry codes like
+//! `PIR-BBBB`. [`PriceClass`] turns the common spellings into well-known tiers, so pricing
+//! code can match on an enum. Registry-specific codes are mapped with [`PriceClasses`],
+//! usually kept in the [`TldPolicy`](crate::domain::TldPolicy) of the TLD.
+
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// The price class of a name
+#[derive(Clone, Debug, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(from = "String", into = "String")]
+pub enum PriceClass {
+    /// The regular price of the TLD
+    Standard,
+    /// A premium price, in a lettered tier if the registry uses them
+    Premium(Option<PremiumTier>),
+    /// Any other label, as given
+    Custom(String),
+}
+
+impl PriceClass {
+    /// Classify `label`, ignoring case and separators
+    ///
+    /// Recognizes `standard` (also `std`, `regular` and `default`), `premium` and premium
+    /// tiers like `premium-a`, `Premium B` or `premiumTierC`. Anything else is
+    /// [`PriceClass::Custom`].
+    pub fn from_label(label: &str) -> Self {
+        let normalized = label
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+
+        match normalized.as_str() {
+            "standard" | "std" | "regular" | "default" => return Self::Standard,
+            "premium" => return Self::Premium(None),
+            _ => {}
+        }
+
+        let tier = normalized
+            .strip_prefix("premium")
+            .map(|rest| rest.strip_prefix("tier").unwrap_or(rest))
+            .and_then(PremiumTier::from_letter);
+        match tier {
+            Some(tier) => Self::Premium(Some(tier)),
+            None => Self::Custom(label.to_owned()),
+        }
+    }
+
+    pub fn is_premium(&self) -> bool {
+        matches!(self, Self::Premium(_))
+    }
+}
+
+impl FromStr for PriceClass {
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self::from_label(s))
+    }
+}
+
+impl From<String> for PriceClass {
+    fn from(label: String) -> Self {
+        Self::from_label(&label)
+    }
+}
+
+impl From<PriceClass> for String {
+    fn from(class: PriceClass) -> Self {
+        class.to_string()
+    }
+}
+
+/// The canonical label, like `standard` or `premium-b`, or a custom label as given
+impl fmt::Display for PriceClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Standard => write!(f, "standard"),
+            Self::Premium(None) => write!(f, "premium"),
+            Self::Premium(Some(tier)) => write!(f, "premium-{}", tier.as_str()),
+            Self::Custom(label) => write!(f, "{label}"),
+        }
+    }
+}
+
+/// A lettered premium tier, `A` being the most expensive at most registries
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub enum PremiumTier {
+    A,
+    B,
+    C,
+    D,
+    E,
+}
+
+impl PremiumTier {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::A => "a",
+            Self::B => "b",
+            Self::C => "c",
+            Self::D => "d",
+            Self::E => "e",
+        }
+    }
+
+    fn from_letter(letter: &str) -> Option<Self> {
+        Some(match letter {
+            "a" => Self::A,
+            "b" => Self::B,
+            "c" => Self::C,
+            "d" => Self::D,
+            "e" => Self::E,
+            _ => return None,
+        })
+    }
+}
+
+/// Registry-specific labels mapped to price classes
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(transparent)]
+pub struct PriceClasses {
+    labels: HashMap<String, PriceClass>,
+}
+
+impl PriceClasses {
+    /// Classify `label` (compared case-insensitively) as `class`
+    pub fn with_label(mut self, label: &str, class: PriceClass) -> Self {
+        self.labels.insert(label.to_ascii_lowercase(), class);
+        self
+    }
+
+    /// The class for `label`, from the mapping or else by [`PriceClass::from_label()`]
+    pub fn classify(&self, label: &str) -> PriceClass {
+        match self.labels.get(&label.to_ascii_lowercase()) {
+            Some(class) => class.clone(),
+            None => PriceClass::from_label(label),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PremiumTier, PriceClass, PriceClasses};
+
+    #[test]
+    fn labels() {
+        assert_eq!(PriceClass::from_label("Standard"), PriceClass::Standard);
+        assert_eq!(PriceClass::from_label("PREMIUM"), PriceClass::Premium(None));
+        for label in ["premium-b", "Premium B", "premiumTierB", "premium_tier_b"] {
+            assert_eq!(
+                PriceClass::from_label(label),
+                PriceClass::Premium(Some(PremiumTier::B)),
+                "{label}"
+            );
+        }
+        assert_eq!(
+            PriceClass::from_label("PIR-BBBB"),
+            PriceClass::Custom("PIR-BBBB".into())
+        );
+        assert_eq!(
+            PriceClass::Premium(Some(PremiumTier::C)).to_string(),
+            "premium-c"
+        );
+
+        let classes = PriceClasses::default()
+            .with_label("PIR-BBBB", PriceClass::Premium(Some(PremiumTier::B)));
+        assert_eq!(
+            classes.classify("pir-bbbb"),
+            PriceClass::Premium(Some(PremiumTier::B))
+        );
+        assert_eq!(classes.classify("standard"), PriceClass::Standard);
+    }
+}
@@ -0,0 +1,203 @@
+//! Limiting the extensions a session negotiates and uses
+//!
+//! A service that only registers domains has no business sending DNSSEC or launch phase
+//! extensions. A [`SessionProfile`] names the extension URIs a service needs: it provides the
+//! `<svcExtension>` list for the [`Login`] command, and once set with
+//! [`EppClient::set_profile()`] the client refuses to send commands carrying any other
+//! extension.
+//!
+//! [`EppClient::set_profile()`]: crate::EppClient::set_profile
+
+use std::error::Error as StdError;
+use std::fmt;
+
+use crate::extensions::{launch, rgp, secdns};
+use crate::hello::Greeting;
+use crate::login::Login;
+#[cfg(any(feature = "client", test))]
+use crate::request::Extension;
+
+/// A named set of extension URIs a session may use
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct SessionProfile {
+    pub name: String,
+    pub ext_uris: Vec<String>,
+}
+
+impl SessionProfile {
+    /// A profile without any extensions
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            ext_uris: Vec::new(),
+        }
+    }
+
+    /// Allow the extension with namespace `uri`
+    pub fn extension(mut self, uri: &str) -> Self {
+        if !self.allows(uri) {
+            self.ext_uris.push(uri.to_owned());
+        }
+        self
+    }
+
+    /// Plain domain, host and contact management, without extensions
+    pub fn domains_only() -> Self {
+        Self::new("domains-only")
+    }
+
+    /// DNSSEC, launch phases and grace period restores
+    pub fn dnssec_launch() -> Self {
+        Self::new("full-dnssec-launch")
+            .extension(secdns::XMLNS)
+            .extension(launch::XMLNS)
+            .extension(rgp::XMLNS)
+    }
+
+    /// Whether the extension with namespace `uri` is part of the profile
+    pub fn allows(&self, uri: &str) -> bool {
+        self.ext_uris.iter().any(|allowed| allowed == uri)
+    }
+
+    /// The extension URIs of the profile that the server announces in `greeting`
+    pub fn negotiate(&self, greeting: &Greeting) -> Vec<&str> {
+        let announced = greeting
+            .svc_menu
+            .services
+            .svc_ext
+            .as_ref()
+            .map(|ext| ext.ext_uris.as_slice())
+            .unwrap_or_default();
+        self.ext_uris
+            .iter()
+            .filter(|uri| announced.iter().any(|found| found == *uri))
+            .map(String::as_str)
+            .collect()
+    }
+
+    /// A [`Login`] negotiating the extensions of the profile the server announces
+    pub fn login<'a>(
+        &'a self,
+        greeting: &Greeting,
+        username: &'a str,
+        password: &'a str,
+        new_password: Option<&'a str>,
+    ) -> Login<'a> {
+        let ext_uris = self.negotiate(greeting);
+        Login::new(username, password, new_password, Some(ext_uris.as_slice()))
+    }
+
+    #[cfg(any(feature = "client", test))]
+    /// Check that `extension` only sends elements in namespaces of the profile
+    pub(crate) fn check<E: Extension>(&self, extension: &E) -> Result<(), ProfileViolation> {
+        if !extension.do_send() {
+            return Ok(());
+        }
+
+        let violation = |uri: Option<&str>| ProfileViolation {
+            profile: self.name.clone(),
+            uri: uri.map(str::to_owned),
+        };
+        let namespaces = extension.namespaces().ok_or_else(|| violation(None))?;
+        match namespaces.into_iter().find(|uri| !self.allows(uri)) {
+            Some(uri) => Err(violation(Some(uri))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// A command carried an extension outside the client's [`SessionProfile`]
+///
+/// Returned as [`Error::Other`](crate::Error::Other); nothing was sent to the registry.
+#[derive(Debug)]
+pub struct ProfileViolation {
+    /// The name of the profile
+    pub profile: String,
+    /// The namespace of the extension, `None` if the extension doesn't declare its namespaces
+    pub uri: Option<String>,
+}
+
+impl fmt::Display for ProfileViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.uri {
+            Some(uri) => write!(
+                f,
+                "extension {uri} is not part of session profile {}",
+                self.profile
+            ),
+            None => write!(
+                f,
+                "extension without declared namespaces refused by session profile {}",
+                self.profile
+            ),
+        }
+    }
+}
+
+impl StdError for ProfileViolation {}
+
+impl From<ProfileViolation> for crate::Error {
+    fn from(err: ProfileViolation) -> Self {
+        Self::Other(Box::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use instant_xml::ToXml;
+
+    use super::SessionProfile;
+    use crate::common::NoExtension;
+    use crate::extensions::raw::RawExtension;
+    use crate::extensions::{consolidate, namestore};
+    use crate::hello::Greeting;
+    use crate::request::Extension;
+    use crate::tests::get_xml;
+    use crate::xml;
+
+    #[test]
+    fn check() {
+        let exp = consolidate::GMonthDay::new(5, 31, None).unwrap();
+        let extension = consolidate::UpdateWithNameStore::new(exp, "com");
+        let profile = SessionProfile::domains_only().extension(consolidate::XMLNS);
+        assert_eq!(
+            profile.check(&extension).unwrap_err().to_string(),
+            "extension http://www.verisign-grs.com/epp/namestoreExt-1.1 is not part of \
+             session profile domains-only"
+        );
+
+        let profile = profile.extension(namestore::XMLNS);
+        assert!(profile.check(&extension).is_ok());
+
+        let raw = RawExtension::new("urn:example:acme-1.0", "<acme/>");
+        let err = SessionProfile::domains_only().check(&raw).unwrap_err();
+        assert_eq!(err.uri.as_deref(), Some("urn:example:acme-1.0"));
+
+        #[derive(Debug, ToXml)]
+        #[xml(rename = "acme", ns("urn:example:acme-1.0"))]
+        struct Undeclared;
+
+        impl Extension for Undeclared {
+            type Response = NoExtension;
+        }
+
+        let err = SessionProfile::domains_only()
+            .check(&Undeclared)
+            .unwrap_err();
+        assert_eq!(err.uri, None);
+    }
+
+    #[test]
+    fn negotiate() {
+        let xml = get_xml("response/greeting.xml").unwrap();
+        let greeting = xml::deserialize::<Greeting>(&xml).unwrap();
+        let profile = SessionProfile::dnssec_launch();
+        assert_eq!(
+            profile.negotiate(&greeting),
+            [
+                "urn:ietf:params:xml:ns:secDNS-1.1",
+                "urn:ietf:params:xml:ns:rgp-1.0"
+            ]
+        );
+    }
+}
@@ -0,0 +1,137 @@
+//! Adjustments for registries deviating from the usual EPP serialization
+//!
+//! The typed models in this crate produce schema-valid XML, but some registries are stricter
+//! (or looser) than the schemas in ways that are hard to model per type: they insist on
+//! namespace prefixes on extension elements, reject empty elements, can't parse fractional
+//! seconds or only accept `1` and `0` as booleans. A [`Quirks`] implementation attached to the
+//! client with [`EppClient::set_quirks()`] chooses how requests are serialized and responses
+//! parsed, and can rewrite either. [`RegistryQuirks`] covers the common deviations.
+//!
+//! Responses are parsed strictly by default: a single `avail="TRUE"` fails the whole
+//! response. The [`ParseOptions`] returned by [`Quirks::parsing()`] let the fields known to
+//! hold booleans and numbers accept the variants registries actually send, and every value
+//! accepted this way is returned with the response, see [`Response::scalar_warnings()`].
+//!
+//! [`Response::scalar_warnings()`]: crate::response::Response::scalar_warnings
+//! [`EppClient::set_quirks()`]: crate::EppClient::set_quirks
+
+use std::borrow::Cow;
+
+use crate::xml::{self, ParseOptions, SerializeOptions, Token};
+
+/// Adjusts the XML exchanged with a registry
+pub trait Quirks: Send + Sync {
+    /// The options to serialize requests with
+    fn serialization(&self) -> Option<&SerializeOptions> {
+        None
+    }
+
+    /// The options to parse responses with
+    fn parsing(&self) -> Option<&ParseOptions> {
+        None
+    }
+
+    /// Adjust a serialized request before it is sent
+    fn request<'a>(&self, xml: Cow<'a, str>) -> Cow<'a, str> {
+        xml
+    }
+
+    /// Adjust a raw response before it is parsed
+    fn response<'a>(&self, xml: Cow<'a, str>) -> Cow<'a, str> {
+        xml
+    }
+}
+
+/// Configurable set of common registry deviations
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RegistryQuirks {
+    /// How requests are serialized, like the namespace prefixes of extension elements or
+    /// booleans as `1` and `0`
+    pub serialization: SerializeOptions,
+    /// Leave out elements that have neither attributes nor content, like an empty `<chg>`
+    pub omit_empty_elements: bool,
+    /// How booleans and numbers in responses are parsed
+    pub parsing: ParseOptions,
+}
+
+impl RegistryQuirks {
+    /// Use `prefix` for elements in the extension namespace `namespace`
+    pub fn with_prefix(mut self, namespace: impl Into<String>, prefix: impl Into<String>) -> Self {
+        self.serialization = self.serialization.with_prefix(namespace, prefix);
+        self
+    }
+}
+
+impl Quirks for RegistryQuirks {
+    fn serialization(&self) -> Option<&SerializeOptions> {
+        Some(&self.serialization)
+    }
+
+    fn parsing(&self) -> Option<&ParseOptions> {
+        Some(&self.parsing)
+    }
+
+    fn request<'a>(&self, xml: Cow<'a, str>) -> Cow<'a, str> {
+        match self.omit_empty_elements {
+            true => Cow::Owned(omit_empty_elements(&xml)),
+            false => xml,
+        }
+    }
+}
+
+// Remove elements without attributes or content, including elements only containing such
+// elements
+fn omit_empty_elements(xml: &str) -> String {
+    let mut out = String::with_capacity(xml.len());
+    // For each open element, where its start tag was written and whether it can be removed
+    let mut open = Vec::<(usize, bool)>::new();
+    for token in xml::tokens(xml) {
+        match token {
+            Token::Start(tag) if !tag.self_closing() => {
+                // Only a bare `<name>` can go
+                open.push((out.len(), tag.0.len() == tag.name().len() + 2));
+            }
+            Token::End(_) => match open.pop() {
+                Some((start, true)) => {
+                    out.truncate(start);
+                    continue;
+                }
+                _ => {
+                    if let Some((_, parent)) = open.last_mut() {
+                        *parent = false;
+                    }
+                }
+            },
+            _ => {
+                if let Some((_, parent)) = open.last_mut() {
+                    *parent = false;
+                }
+            }
+        }
+
+        out.push_str(token.raw());
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use std::borrow::Cow;
+
+    use super::{Quirks, RegistryQuirks};
+
+    #[test]
+    fn empty_elements() {
+        let quirks = RegistryQuirks {
+            omit_empty_elements: true,
+            ..RegistryQuirks::default()
+        };
+
+        let xml = r#"<update><name>example.com</name><chg></chg><flag avail="true">false</flag><logout /></update>"#;
+        assert_eq!(
+            quirks.request(Cow::Borrowed(xml)),
+            r#"<update><name>example.com</name><flag avail="true">false</flag><logout /></update>"#
+        );
+    }
+}
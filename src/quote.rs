@@ -0,0 +1,282 @@
+//! Availability and price of a domain in a single call
+//!
+//! Registries report prices through different check extensions: the fee extension in one of
+//! its versions, or the charge extension for premium names. [`EppClient::quote()`] picks the
+//! extension the registry announces in its greeting, sends one domain check with it and
+//! reduces the response to a [`Quote`].
+
+use crate::client::{Connector, EppClient};
+use crate::domain::check::CheckData as DomainCheckData;
+use crate::domain::{normalize_name, DomainCheck};
+use crate::extensions::{charge, fee, fee023, fee07, fee_compat};
+use crate::price_class::PriceClass;
+use crate::Error;
+
+/// Whether a domain can be registered, and at what price
+#[derive(Clone, Debug, PartialEq)]
+pub struct Quote {
+    pub available: bool,
+    /// Whether the registry prices the domain above its standard rate
+    pub premium: bool,
+    /// The currency of the prices, if the registry stated one
+    pub currency: Option<String>,
+    /// The price of registering the domain for the requested period
+    ///
+    /// `None` if the registry did not return a price, for example because the domain is not
+    /// available or it supports none of the price extensions.
+    pub create_price: Option<f64>,
+    /// The price of renewing the domain for the requested period
+    pub renew_price: Option<f64>,
+}
+
+impl<C: Connector> EppClient<C> {
+    /// Check the availability and price of `name` for a period of `years`
+    ///
+    /// Uses the first of fee-1.0, fee-0.23, fee-0.7 and charge-1.0 listed in the server's
+    /// greeting, or a plain domain check if there is none. A fee response in another of these
+    /// fee versions than the one requested is accepted as well.
+    pub async fn quote(&mut self, name: &str, years: u16, id: &str) -> Result<Quote, Error> {
+        let greeting = self.greeting()?;
+        let supports = |xmlns: &str| {
+            greeting
+                .svc_menu
+                .services
+                .svc_ext
+                .as_ref()
+                .is_some_and(|ext| ext.ext_uris.iter().any(|uri| uri == xmlns))
+        };
+
+        let names = [name];
+        let check = DomainCheck { domains: &names };
+        let fee_check = if supports(fee::XMLNS) {
+            Some(fee_compat::Check::from(fee::Check::new(None, Some(years))))
+        } else if supports(fee023::XMLNS) {
+            Some(fee023::Check::new(None, Some(years)).into())
+        } else if supports(fee07::XMLNS) {
+            Some(fee07::Check::new(names, None, Some(years)).into())
+        } else {
+            None
+        };
+
+        if let Some(ext) = fee_check {
+            let rsp = self.transact((&check, &ext), id).await?;
+            Ok(from_fees(name, rsp.res_data(), rsp.extension()))
+        } else if supports(charge::XMLNS) {
+            let rsp = self
+                .transact((&check, &charge::ChargeExtension), id)
+                .await?;
+            Ok(from_charge(name, rsp.res_data(), rsp.extension(), years))
+        } else {
+            let rsp = self.transact(&check, id).await?;
+            Ok(Quote {
+                available: available(name, rsp.res_data()),
+                premium: false,
+                currency: None,
+                create_price: None,
+                renew_price: None,
+            })
+        }
+    }
+}
+
+fn available(name: &str, data: Option<&DomainCheckData>) -> bool {
+    let name = normalize_name(name);
+    data.and_then(|data| {
+        data.list
+            .iter()
+            .find(|checked| normalize_name(&checked.inner.id) == name)
+    })
+    .is_some_and(|checked| checked.inner.available)
+}
+
+// Fee classes like `premium-a` or `Premium Tier B` count as premium, see `PriceClass`
+fn is_premium(class: Option<&str>) -> bool {
+    class.is_some_and(|class| PriceClass::from_label(class).is_premium())
+}
+
+// The response may be in another fee version than the check, see `fee_compat`
+pub(crate) fn from_fees(
+    name: &str,
+    data: Option<&DomainCheckData>,
+    fees: Option<&fee_compat::CheckData>,
+) -> Quote {
+    match fees {
+        Some(fee_compat::CheckData::Fee(fees)) => from_fee(name, data, Some(fees)),
+        Some(fee_compat::CheckData::Fee023(fees)) => from_fee023(name, data, Some(fees)),
+        Some(fee_compat::CheckData::Fee07(fees)) => from_fee07(name, data, Some(fees)),
+        None => from_fee(name, data, None),
+    }
+}
+
+fn from_fee(name: &str, data: Option<&DomainCheckData>, fees: Option<&fee::CheckData>) -> Quote {
+    let cd = fees.and_then(|fees| fees.get(name));
+    let price = |command: &str| {
+        let command = cd?.commands.iter().find(|cmd| cmd.name == command)?;
+        (!command.fees.is_empty()).then(|| command.total())
+    };
+
+    Quote {
+        available: available(name, data) && cd.and_then(|cd| cd.avail) != Some(false),
+        premium: cd.is_some_and(|cd| {
+            is_premium(cd.class.as_deref())
+                || cd.commands.iter().any(|cmd| cmd.standard == Some(false))
+        }),
+        currency: fees.map(|fees| fees.currency.clone()),
+        create_price: price("create"),
+        renew_price: price("renew"),
+    }
+}
+
+fn from_fee023(
+    name: &str,
+    data: Option<&DomainCheckData>,
+    fees: Option<&fee023::CheckData>,
+) -> Quote {
+    let normalized = normalize_name(name);
+    let cd = fees.and_then(|fees| {
+        fees.list
+            .iter()
+            .find(|cd| normalize_name(&cd.obj_id.value) == normalized)
+    });
+    let price = |name: &str| {
+        let command = cd?.commands.iter().find(|cmd| cmd.name == name)?;
+        if command.fees.is_empty() {
+            return None;
+        }
+
+        let fees = command.fees.iter().map(|fee| fee.amount);
+        let credits = command.credits.iter().map(|credit| credit.amount);
+        Some(fees.chain(credits).sum())
+    };
+
+    Quote {
+        available: available(name, data) && cd.and_then(|cd| cd.avail) != Some(false),
+        premium: cd.is_some_and(|cd| {
+            cd.commands
+                .iter()
+                .any(|cmd| is_premium(cmd.class.as_deref()))
+        }),
+        currency: fees.map(|fees| fees.currency.clone()),
+        create_price: price("create"),
+        renew_price: price("renew"),
+    }
+}
+
+fn from_fee07(
+    name: &str,
+    data: Option<&DomainCheckData>,
+    fees: Option<&fee07::CheckData>,
+) -> Quote {
+    let normalized = normalize_name(name);
+    let entries = fees
+        .map(|fees| fees.list.as_slice())
+        .unwrap_or_default()
+        .iter()
+        .filter(|cd| normalize_name(&cd.name) == normalized)
+        .collect::<Vec<_>>();
+    let price = |command: &str| {
+        let cd = entries.iter().find(|cd| cd.command.value == command)?;
+        if cd.fees.is_empty() {
+            return None;
+        }
+
+        let fees = cd.fees.iter().map(|fee| fee.amount);
+        let credits = cd.credits.iter().map(|credit| credit.amount);
+        Some(fees.chain(credits).sum())
+    };
+
+    Quote {
+        available: available(name, data),
+        premium: entries.iter().any(|cd| is_premium(cd.class.as_deref())),
+        currency: entries.first().map(|cd| cd.currency.clone()),
+        create_price: price("create"),
+        renew_price: price("renew"),
+    }
+}
+
+// The charge extension only lists premium domains, with prices for a single year
+fn from_charge(
+    name: &str,
+    data: Option<&DomainCheckData>,
+    charges: Option<&charge::CheckData>,
+    years: u16,
+) -> Quote {
+    let normalized = normalize_name(name);
+    let cd = charges.and_then(|charges| {
+        charges
+            .list
+            .iter()
+            .find(|cd| normalize_name(&cd.name) == normalized)
+    });
+    let price = |command: charge::ChargeCommand| {
+        let amount = cd?
+            .set
+            .amounts
+            .iter()
+            .find(|amount| amount.command == command && amount.name.is_none())?;
+        Some(amount.amount * f64::from(years))
+    };
+
+    Quote {
+        available: available(name, data),
+        premium: cd.is_some(),
+        currency: None,
+        create_price: price(charge::ChargeCommand::Create),
+        renew_price: price(charge::ChargeCommand::Renew),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{from_fee, from_fees, Quote};
+    use crate::domain::DomainCheck;
+    use crate::extensions::{fee, fee_compat};
+    use crate::tests::response_from_file_with_ext;
+
+    #[test]
+    fn fee_check() {
+        let rsp = response_from_file_with_ext::<DomainCheck, fee::Check>(
+            "response/extensions/fee_check.xml",
+        );
+
+        assert_eq!(
+            from_fee("example.com", rsp.res_data(), rsp.extension()),
+            Quote {
+                available: true,
+                premium: true,
+                currency: Some("USD".into()),
+                create_price: Some(100.0),
+                renew_price: Some(80.0),
+            }
+        );
+
+        assert_eq!(
+            from_fee("example.net", rsp.res_data(), rsp.extension()),
+            Quote {
+                available: false,
+                premium: false,
+                currency: Some("USD".into()),
+                create_price: None,
+                renew_price: None,
+            }
+        );
+    }
+
+    #[test]
+    fn other_fee_version() {
+        let rsp = response_from_file_with_ext::<DomainCheck, fee_compat::Check>(
+            "response/extensions/fee07_check.xml",
+        );
+
+        assert_eq!(
+            from_fees("example.com", rsp.res_data(), rsp.extension()),
+            Quote {
+                available: true,
+                premium: false,
+                currency: Some("USD".into()),
+                create_price: Some(10.0),
+                renew_price: Some(8.0),
+            }
+        );
+    }
+}
@@ -0,0 +1,270 @@
+//! Moving domains from one set of name servers to another
+//!
+//! When a DNS provider changes, every domain delegated to the old name servers has to be
+//! re-delegated. EPP has no standard way to search for the domains using a host, so
+//! [`migrate_nameservers()`] takes the candidate domains from the caller (for example from the
+//! registrar's database or a [portfolio listing](crate::portfolio)), reads the delegation of
+//! each and only updates those actually using the old hosts. If too many updates fail, the
+//! ones already made are reverted, so that a bad name server set does not end up half rolled
+//! out.
+
+use std::fmt;
+
+use crate::client::{Connector, EppClient};
+use crate::domain::update::{DomainAdd, DomainRemove};
+use crate::domain::{DomainInfo, DomainUpdate, HostInfo, HostObj, NameServers};
+use crate::Error;
+
+/// The name servers to move domains between
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Migration {
+    /// Host object names of the name servers to move away from
+    pub old: Vec<String>,
+    /// Host object names of the name servers to move to; these must exist at the registry
+    pub new: Vec<String>,
+    /// The number of failed updates tolerated before the migration is rolled back
+    pub max_failures: usize,
+}
+
+/// What happened to a single domain during a migration
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum MigrationOutcome {
+    /// The domain was re-delegated
+    Updated,
+    /// The domain does not use any of the old name servers
+    Skipped,
+    /// Reading or updating the domain failed
+    Failed(String),
+    /// The domain was re-delegated, and moved back when the migration was rolled back
+    RolledBack,
+    /// The domain was re-delegated, but moving it back failed
+    RollbackFailed(String),
+}
+
+/// Passed to the progress callback of [`migrate_nameservers()`] after each domain
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct MigrationProgress<'a> {
+    pub domain: &'a str,
+    pub outcome: &'a MigrationOutcome,
+    /// The number of domains processed so far, including this one
+    pub done: usize,
+    pub total: usize,
+}
+
+/// The outcome of [`migrate_nameservers()`], per domain in the order given
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct MigrationReport {
+    pub domains: Vec<(String, MigrationOutcome)>,
+    /// Whether the failure threshold was exceeded and the updates were reverted
+    pub rolled_back: bool,
+}
+
+/// One line per domain, like `example.com: updated`
+impl fmt::Display for MigrationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, (domain, outcome)) in self.domains.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{domain}: ")?;
+            match outcome {
+                MigrationOutcome::Updated => write!(f, "updated")?,
+                MigrationOutcome::Skipped => write!(f, "skipped")?,
+                MigrationOutcome::Failed(err) => write!(f, "failed: {err}")?,
+                MigrationOutcome::RolledBack => write!(f, "rolled back")?,
+                MigrationOutcome::RollbackFailed(err) => write!(f, "rollback failed: {err}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+// The name servers added to and removed from a domain, to revert them
+struct Delegation {
+    index: usize,
+    add: Vec<String>,
+    remove: Vec<String>,
+}
+
+/// Re-delegate the `domains` using any of the old name servers of `migration` to the new ones
+///
+/// For each domain, an `<info>` command is sent (with `id` suffixed by `:`, the position of
+/// the domain starting at 1 and `:info` as its client transaction ID), followed by an
+/// `<update>` replacing the old name servers it uses with the new ones (`id`, `:` and the
+/// position). Other name servers of the domain are left alone. `progress` is called after each
+/// domain.
+///
+/// Once more than `max_failures` domains failed, no further domains are processed and the
+/// domains updated so far are moved back, with `:rollback` appended to the client transaction
+/// ID of their update.
+pub async fn migrate_nameservers<C: Connector>(
+    client: &mut EppClient<C>,
+    migration: &Migration,
+    domains: &[&str],
+    mut progress: impl FnMut(MigrationProgress<'_>),
+    id: &str,
+) -> Result<MigrationReport, Error> {
+    let mut report = MigrationReport::default();
+    let mut updated = Vec::new();
+    let mut failures = 0;
+
+    for (index, &name) in domains.iter().enumerate() {
+        let seq = index + 1;
+        let outcome = match migrate(client, migration, name, &format!("{id}:{seq}")).await {
+            Ok(Some((add, remove))) => {
+                updated.push(Delegation { index, add, remove });
+                MigrationOutcome::Updated
+            }
+            Ok(None) => MigrationOutcome::Skipped,
+            Err(err) => {
+                failures += 1;
+                MigrationOutcome::Failed(err.to_string())
+            }
+        };
+
+        progress(MigrationProgress {
+            domain: name,
+            outcome: &outcome,
+            done: seq,
+            total: domains.len(),
+        });
+        report.domains.push((name.to_owned(), outcome));
+
+        if failures > migration.max_failures {
+            report.rolled_back = true;
+            break;
+        }
+    }
+
+    if !report.rolled_back {
+        return Ok(report);
+    }
+
+    for delegation in updated.iter().rev() {
+        let (name, outcome) = &mut report.domains[delegation.index];
+        // Moving back is the same change with the roles of the name servers swapped
+        let update = update(name, &delegation.remove, &delegation.add);
+        let rollback_id = format!("{id}:{}:rollback", delegation.index + 1);
+        *outcome = match client.transact(&update, &rollback_id).await {
+            Ok(_) => MigrationOutcome::RolledBack,
+            Err(err) => MigrationOutcome::RollbackFailed(err.to_string()),
+        };
+    }
+
+    Ok(report)
+}
+
+// Re-delegate a single domain, returning the name servers added and removed
+async fn migrate<C: Connector>(
+    client: &mut EppClient<C>,
+    migration: &Migration,
+    name: &str,
+    id: &str,
+) -> Result<Option<(Vec<String>, Vec<String>)>, Error> {
+    let info = client
+        .transact(&DomainInfo::new(name, None), &format!("{id}:info"))
+        .await?
+        .into_data()?;
+
+    let current = info.name_servers().collect::<Vec<_>>();
+    let (add, remove) = match changes(&current, migration) {
+        Some(changes) => changes,
+        None => return Ok(None),
+    };
+
+    client.transact(&update(name, &add, &remove), id).await?;
+    Ok(Some((add, remove)))
+}
+
+// The name servers to add and remove, or `None` if the domain uses none of the old ones
+fn changes(current: &[&str], migration: &Migration) -> Option<(Vec<String>, Vec<String>)> {
+    let listed =
+        |names: &[String], name: &str| names.iter().any(|listed| listed.eq_ignore_ascii_case(name));
+
+    if !current.iter().any(|name| listed(&migration.old, name)) {
+        return None;
+    }
+
+    let remove = current
+        .iter()
+        .filter(|name| listed(&migration.old, name) && !listed(&migration.new, name))
+        .map(|name| name.to_string())
+        .collect();
+
+    let add = migration
+        .new
+        .iter()
+        .filter(|new| !current.iter().any(|name| name.eq_ignore_ascii_case(new)))
+        .cloned()
+        .collect();
+    Some((add, remove))
+}
+
+fn update<'a>(name: &'a str, add: &'a [String], remove: &'a [String]) -> DomainUpdate<'a> {
+    let mut update = DomainUpdate::new(name);
+    if !add.is_empty() {
+        update.add(DomainAdd {
+            ns: Some(host_objects(add)),
+            contacts: None,
+            statuses: None,
+        });
+    }
+    if !remove.is_empty() {
+        update.remove(DomainRemove {
+            ns: Some(host_objects(remove)),
+            contacts: None,
+            statuses: None,
+        });
+    }
+    update
+}
+
+fn host_objects(names: &[String]) -> NameServers<'_> {
+    let hosts = names
+        .iter()
+        .map(|name| {
+            HostInfo::Obj(HostObj {
+                name: name.as_str().into(),
+            })
+        })
+        .collect::<Vec<_>>();
+    NameServers { ns: hosts.into() }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{changes, Migration, MigrationOutcome, MigrationReport};
+
+    #[test]
+    fn delegation_changes() {
+        let migration = Migration {
+            old: vec!["ns1.old.example".into(), "ns2.old.example".into()],
+            new: vec!["ns1.new.example".into(), "ns2.new.example".into()],
+            max_failures: 0,
+        };
+
+        assert_eq!(changes(&["ns1.other.example"], &migration), None);
+        assert_eq!(
+            changes(
+                &["NS1.old.example", "ns1.new.example", "ns3.other.example"],
+                &migration
+            ),
+            Some((
+                vec!["ns2.new.example".to_owned()],
+                vec!["NS1.old.example".to_owned()]
+            ))
+        );
+
+        let report = MigrationReport {
+            domains: vec![
+                ("a.example".into(), MigrationOutcome::RolledBack),
+                ("b.example".into(), MigrationOutcome::Failed("2304".into())),
+            ],
+            rolled_back: true,
+        };
+        assert_eq!(
+            report.to_string(),
+            "a.example: rolled back\nb.example: failed: 2304"
+        );
+    }
+}
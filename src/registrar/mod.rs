@@ -0,0 +1,583 @@
+//! Higher-level helpers for common registrar workflows
+//!
+//! These combine several EPP commands to encode registry lifecycle rules that would otherwise
+//! have to be handled at every call site. They are built on top of [`EppClient::transact()`] and
+//! can be mixed freely with plain commands on the same client.
+
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use chrono::{DateTime, Duration, Months, Utc};
+use tracing::warn;
+
+use crate::client::{Connector, EppClient};
+use crate::contact::check::CheckedContact;
+use crate::contact::{
+    self, ContactCheck, ContactCreate, ContactDelete, ContactInfo, ContactUpdate, PostalInfo, Voice,
+};
+use crate::domain::check::CheckedDomain;
+use crate::domain::update::DomainChangeInfo;
+use crate::domain::{DomainCheck, DomainDelete, DomainInfo, DomainUpdate};
+use crate::extensions::rgp::report::{self, RgpRestoreReport};
+use crate::extensions::rgp::request::{
+    self, RgpInfoExtension, RgpRequestResponse, RgpRestoreRequest,
+};
+use crate::extensions::rgp::RgpStatus;
+use crate::host::check::CheckedHost;
+use crate::host::update::{HostAdd, HostRemove};
+use crate::host::{self, HostCheck, HostCreate, HostDelete, HostInfo, HostUpdate};
+use crate::response::ResultCode;
+use crate::Error;
+
+pub mod migrate;
+pub mod reconcile;
+pub use reconcile::{reconcile, Change, DomainSpec, Plan};
+
+/// Check the availability of any number of domains
+///
+/// `names` is split into check commands of at most `chunk_size` domains (see
+/// [`DomainCheck::chunked()`]), sent with `id` suffixed by `:` and a sequence number as their
+/// client transaction IDs. The results of all commands are keyed by normalized domain name
+/// (see [`normalize_name()`](crate::domain::normalize_name)).
+pub async fn check_domains<C: Connector>(
+    client: &mut EppClient<C>,
+    names: &[&str],
+    chunk_size: usize,
+    id: &str,
+) -> Result<HashMap<String, CheckedDomain>, Error> {
+    let mut checked = HashMap::with_capacity(names.len());
+    for (seq, check) in DomainCheck::chunked(names, chunk_size).enumerate() {
+        let data = client
+            .transact(&check, &format!("{id}:{}", seq + 1))
+            .await?
+            .into_data()?;
+        checked.extend(data.into_map());
+    }
+
+    Ok(checked)
+}
+
+/// Check the availability of any number of contact IDs
+///
+/// Useful to detect ID collisions before creating contacts in bulk. Chunking and client
+/// transaction IDs work like for [`check_domains()`].
+pub async fn check_contacts<C: Connector>(
+    client: &mut EppClient<C>,
+    contact_ids: &[&str],
+    chunk_size: usize,
+    id: &str,
+) -> Result<HashMap<String, CheckedContact>, Error> {
+    let mut checked = HashMap::with_capacity(contact_ids.len());
+    for (seq, check) in ContactCheck::chunked(contact_ids, chunk_size).enumerate() {
+        let data = client
+            .transact(&check, &format!("{id}:{}", seq + 1))
+            .await?
+            .into_data()?;
+        checked.extend(data.into_map());
+    }
+
+    Ok(checked)
+}
+
+/// Find a contact ID starting with `prefix` that is not in use at the registry
+///
+/// Generates `batch_size` random IDs at a time (see [`contact::generate_id()`]) and checks them
+/// in a single command, up to `max_batches` times. The check commands are sent with `id`
+/// suffixed by `:` and a sequence number as their client transaction IDs.
+pub async fn find_free_contact_id<C: Connector>(
+    client: &mut EppClient<C>,
+    prefix: &str,
+    batch_size: usize,
+    max_batches: usize,
+    id: &str,
+) -> Result<String, Error> {
+    for seq in 1..=max_batches {
+        let candidates = (0..batch_size.max(1))
+            .map(|_| contact::generate_id(prefix))
+            .collect::<Result<Vec<_>, _>>()?;
+        let ids = candidates.iter().map(String::as_str).collect::<Vec<_>>();
+
+        let check = ContactCheck { contact_ids: &ids };
+        let data = client
+            .transact(&check, &format!("{id}:{seq}"))
+            .await?
+            .into_data()?;
+        if let Some(free) = data.list.into_iter().find(|c| c.inner.available) {
+            return Ok(free.inner.id);
+        }
+    }
+
+    Err(Error::Other(
+        format!("no free contact ID found after {max_batches} checks").into(),
+    ))
+}
+
+/// Check the availability of any number of hosts
+///
+/// Chunking and client transaction IDs work like for [`check_domains()`].
+pub async fn check_hosts<C: Connector>(
+    client: &mut EppClient<C>,
+    names: &[&str],
+    chunk_size: usize,
+    id: &str,
+) -> Result<HashMap<String, CheckedHost>, Error> {
+    let mut checked = HashMap::with_capacity(names.len());
+    for (seq, check) in HostCheck::chunked(names, chunk_size).enumerate() {
+        let data = client
+            .transact(&check, &format!("{id}:{}", seq + 1))
+            .await?
+            .into_data()?;
+        checked.extend(data.into_map());
+    }
+
+    Ok(checked)
+}
+
+/// What to do when an object that is about to be deleted carries the `linked` status
+///
+/// Linked objects are still referenced by another object (usually a domain), so the registry
+/// will reject the delete with 2305 "Object association prohibits operation".
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LinkedPolicy {
+    /// Do not send the delete command
+    Refuse,
+    /// Log a warning and send the delete command anyway
+    Warn,
+}
+
+/// The outcome of a delete guarded by a [`LinkedPolicy`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GuardedDelete {
+    /// The delete command was sent and succeeded
+    ///
+    /// `linked` is set if the object was linked according to its info response, which
+    /// can only happen with [`LinkedPolicy::Warn`].
+    Deleted { linked: bool },
+    /// The object was linked, so the delete command was not sent ([`LinkedPolicy::Refuse`])
+    Refused,
+}
+
+/// Delete a host object, checking whether it is linked first
+///
+/// Sends a host `<info>` command (with `id` suffixed by `:info` as its client transaction ID),
+/// then applies `policy` if the host has the `linked` status before sending the `<delete>`.
+pub async fn delete_host<C: Connector>(
+    client: &mut EppClient<C>,
+    name: &str,
+    policy: LinkedPolicy,
+    id: &str,
+) -> Result<GuardedDelete, Error> {
+    let info = client
+        .transact(&HostInfo::new(name), &format!("{id}:info"))
+        .await?
+        .into_data()?;
+
+    let linked = info.statuses.contains(&host::Status::Linked);
+    if linked && !proceed(policy, "host", name) {
+        return Ok(GuardedDelete::Refused);
+    }
+
+    client.transact(&HostDelete::new(name), id).await?;
+    Ok(GuardedDelete::Deleted { linked })
+}
+
+/// Delete a contact object, checking whether it is linked first
+///
+/// Sends a contact `<info>` command (with `id` suffixed by `:info` as its client transaction ID),
+/// then applies `policy` if the contact has the `linked` status before sending the `<delete>`.
+pub async fn delete_contact<C: Connector>(
+    client: &mut EppClient<C>,
+    contact_id: &str,
+    auth_password: &str,
+    policy: LinkedPolicy,
+    id: &str,
+) -> Result<GuardedDelete, Error> {
+    let info = client
+        .transact(
+            &ContactInfo::new(contact_id, auth_password),
+            &format!("{id}:info"),
+        )
+        .await?
+        .into_data()?;
+
+    let linked = info.statuses.contains(&contact::Status::Linked);
+    if linked && !proceed(policy, "contact", contact_id) {
+        return Ok(GuardedDelete::Refused);
+    }
+
+    client.transact(&ContactDelete::new(contact_id), id).await?;
+    Ok(GuardedDelete::Deleted { linked })
+}
+
+/// Lengths of the RGP grace periods, used to estimate when a refund window closes
+///
+/// The defaults are the ones used by most gTLD registries. ccTLD registries vary, so check
+/// the registry's policy documentation.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GracePeriods {
+    /// Length of the add grace period, starting at the creation date
+    pub add: Duration,
+    /// Length of the auto-renew grace period, starting at the previous expiry date
+    pub auto_renew: Duration,
+    /// Length of the transfer grace period, starting at the transfer date
+    pub transfer: Duration,
+}
+
+impl Default for GracePeriods {
+    fn default() -> Self {
+        Self {
+            add: Duration::days(5),
+            auto_renew: Duration::days(45),
+            transfer: Duration::days(5),
+        }
+    }
+}
+
+/// A grace period credit expected for a domain delete
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct GraceCredit {
+    /// The grace period the domain was in when it was deleted
+    pub period: RgpStatus,
+    /// The estimated end of the grace period, if it can be derived from the domain's dates
+    ///
+    /// This is not known for [`RgpStatus::RenewPeriod`], since the domain info response does
+    /// not record when the last explicit renewal happened.
+    pub until: Option<DateTime<Utc>>,
+}
+
+/// The outcome of [`delete_domain()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DomainDeletion {
+    /// Grace period credits the registry is expected to issue for this delete
+    ///
+    /// Empty if the domain was not in a grace period that yields a credit.
+    pub credits: Vec<GraceCredit>,
+    /// Whether the delete is pending (result code 1001)
+    ///
+    /// Domains deleted outside of the add grace period usually enter the redemption period
+    /// instead of being purged immediately.
+    pub pending: bool,
+}
+
+/// Delete a domain, predicting grace period credits from its RGP status
+///
+/// Sends a domain `<info>` command (with `id` suffixed by `:info` as its client transaction ID)
+/// to read the RGP statuses, then sends the `<delete>`. Registries that don't support RGP
+/// are handled gracefully: no credits are predicted.
+pub async fn delete_domain<C: Connector>(
+    client: &mut EppClient<C>,
+    name: &str,
+    grace: &GracePeriods,
+    id: &str,
+) -> Result<DomainDeletion, Error> {
+    let info = DomainInfo::new(name, None);
+    let response = client
+        .transact((&info, &RgpInfoExtension), &format!("{id}:info"))
+        .await?;
+
+    let data = response.data()?;
+    let statuses = match response.extension() {
+        Some(ext) => ext.rgp_status.as_slice(),
+        None => &[],
+    };
+
+    let mut credits = Vec::new();
+    for &period in statuses {
+        let until = match period {
+            RgpStatus::AddPeriod => data.created_at.map(|at| at + grace.add),
+            RgpStatus::AutoRenewPeriod => data
+                .expiring_at
+                .and_then(|at| at.checked_sub_months(Months::new(12)))
+                .map(|at| at + grace.auto_renew),
+            RgpStatus::TransferPeriod => data.transferred_at.map(|at| at + grace.transfer),
+            RgpStatus::RenewPeriod => None,
+            RgpStatus::RedemptionPeriod | RgpStatus::PendingRestore | RgpStatus::PendingDelete => {
+                continue
+            }
+        };
+
+        credits.push(GraceCredit { period, until });
+    }
+
+    let response = client.transact(&DomainDelete::new(name), id).await?;
+    Ok(DomainDeletion {
+        credits,
+        pending: response.result.code == ResultCode::CommandCompletedSuccessfullyActionPending,
+    })
+}
+
+/// Whether a registry needs a restore report to complete a domain restore
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum RestoreReportPolicy {
+    /// The domain stays in `pendingRestore` until a report is submitted, as RFC 3915 specifies
+    #[default]
+    Required,
+    /// The restore request alone restores the domain
+    ///
+    /// Some registries (among them several gTLD back-ends) skip the report phase; waiting for
+    /// it would never end.
+    NotRequired,
+}
+
+/// The state of a domain restore after [`restore_domain()`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RestoreState {
+    /// The restore was requested, the registry waits for a report
+    ///
+    /// Send the report with a domain `<update>` carrying an [`RgpRestoreReport`].
+    AwaitingReport,
+    /// The report was submitted, the registry restores the domain once it accepts the report
+    ReportSubmitted,
+    /// The domain was restored without a report
+    Restored,
+}
+
+/// Restore a domain in the redemption period
+///
+/// Sends the restore request (with `id` as its client transaction ID), followed by `report`
+/// (with `id` suffixed by `:report`) if the registry requires one. Under
+/// [`RestoreReportPolicy::Required`], the domain also counts as restored if the registry's
+/// response no longer lists the `pendingRestore` status, and `report` is not sent.
+pub async fn restore_domain<'a, C: Connector>(
+    client: &mut EppClient<C>,
+    name: &'a str,
+    report: Option<RgpRestoreReport<'a>>,
+    policy: RestoreReportPolicy,
+    id: &str,
+) -> Result<RestoreState, Error> {
+    let mut update = DomainUpdate::new(name);
+    update.info(DomainChangeInfo {
+        registrant: None,
+        auth_info: None,
+    });
+
+    let restore = request::Update {
+        data: RgpRestoreRequest::default(),
+    };
+    let response = client.transact((&update, &restore), id).await?;
+
+    let pending = match response.extension() {
+        Some(RgpRequestResponse::Update(data)) => {
+            data.rgp_status.contains(&RgpStatus::PendingRestore)
+        }
+        Some(RgpRequestResponse::Info(data)) => {
+            data.rgp_status.contains(&RgpStatus::PendingRestore)
+        }
+        // Without RGP status in the response, go by the policy alone
+        None => true,
+    };
+
+    if policy == RestoreReportPolicy::NotRequired || !pending {
+        return Ok(RestoreState::Restored);
+    }
+
+    let report = match report {
+        Some(report) => report::Update { data: report },
+        None => return Ok(RestoreState::AwaitingReport),
+    };
+
+    client
+        .transact((&update, &report), &format!("{id}:report"))
+        .await?;
+    Ok(RestoreState::ReportSubmitted)
+}
+
+/// The outcome of [`ensure_host()`] and [`ensure_contact()`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Ensured {
+    /// The ID to refer to the object by in domain commands; the name for hosts
+    pub id: String,
+    pub action: EnsureAction,
+}
+
+/// What [`ensure_host()`] or [`ensure_contact()`] had to do
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EnsureAction {
+    Created,
+    Updated,
+    /// The object already matched
+    Unchanged,
+}
+
+/// Make sure a host exists with exactly the given addresses
+///
+/// Sends a host `<check>` (with `id` suffixed by `:check` as its client transaction ID). An
+/// available host is created; otherwise the host is read with an `<info>` command (`:info`)
+/// and its addresses are updated if they differ. The create or update is sent with `id`.
+/// Calling this again with the same arguments sends no further changes.
+pub async fn ensure_host<C: Connector>(
+    client: &mut EppClient<C>,
+    name: &str,
+    addresses: &[IpAddr],
+    id: &str,
+) -> Result<Ensured, Error> {
+    let ensured = |action| Ensured {
+        id: name.to_owned(),
+        action,
+    };
+
+    let check = HostCheck { hosts: &[name] };
+    let data = client
+        .transact(&check, &format!("{id}:check"))
+        .await?
+        .into_data()?;
+    let available = data
+        .list
+        .iter()
+        .find(|checked| checked.inner.id.eq_ignore_ascii_case(name))
+        .map(|checked| checked.inner.available)
+        .ok_or_else(|| Error::Other(format!("no check result for host {name}").into()))?;
+
+    if available {
+        let addresses = Some(addresses).filter(|addrs| !addrs.is_empty());
+        client
+            .transact(&HostCreate::new(name, addresses), id)
+            .await?;
+        return Ok(ensured(EnsureAction::Created));
+    }
+
+    let info = client
+        .transact(&HostInfo::new(name), &format!("{id}:info"))
+        .await?
+        .into_data()?;
+
+    let (add, remove) = address_changes(&info.addresses, addresses);
+    if add.is_empty() && remove.is_empty() {
+        return Ok(ensured(EnsureAction::Unchanged));
+    }
+
+    client
+        .transact(&address_update(name, &add, &remove), id)
+        .await?;
+    Ok(ensured(EnsureAction::Updated))
+}
+
+// The addresses to add and to remove to get from `current` to `desired`
+fn address_changes(current: &[IpAddr], desired: &[IpAddr]) -> (Vec<IpAddr>, Vec<IpAddr>) {
+    let add = desired
+        .iter()
+        .filter(|addr| !current.contains(addr))
+        .copied()
+        .collect();
+    let remove = current
+        .iter()
+        .filter(|addr| !desired.contains(addr))
+        .copied()
+        .collect();
+    (add, remove)
+}
+
+fn address_update<'a>(name: &'a str, add: &'a [IpAddr], remove: &'a [IpAddr]) -> HostUpdate<'a> {
+    let mut update = HostUpdate::new(name);
+    if !add.is_empty() {
+        update.add(HostAdd {
+            addresses: Some(add),
+            statuses: None,
+        });
+    }
+    if !remove.is_empty() {
+        update.remove(HostRemove {
+            addresses: Some(remove),
+            statuses: None,
+        });
+    }
+    update
+}
+
+/// The desired state of a contact, for [`ensure_contact()`]
+#[derive(Clone, Debug)]
+pub struct ContactSpec<'a> {
+    pub id: &'a str,
+    /// The postal info of one type; postal info of the other type is left alone
+    pub postal_info: PostalInfo<'a>,
+    /// The voice telephone number; an existing number is kept if this is `None`
+    pub voice: Option<Voice<'a>>,
+    pub email: &'a str,
+    /// The password set on a new contact, also used to read an existing contact
+    pub auth_password: &'a str,
+}
+
+/// Make sure a contact exists and matches `spec`
+///
+/// Works like [`ensure_host()`]: sends a contact `<check>` (`:check`), then creates the
+/// contact or reads it (`:info`) and updates the postal info, voice number and email that
+/// differ from `spec`. The authorization password of an existing contact is not changed.
+pub async fn ensure_contact<C: Connector>(
+    client: &mut EppClient<C>,
+    spec: &ContactSpec<'_>,
+    id: &str,
+) -> Result<Ensured, Error> {
+    let ensured = |action| Ensured {
+        id: spec.id.to_owned(),
+        action,
+    };
+
+    let check = ContactCheck {
+        contact_ids: &[spec.id],
+    };
+    let data = client
+        .transact(&check, &format!("{id}:check"))
+        .await?
+        .into_data()?;
+    let available = data
+        .list
+        .iter()
+        .find(|checked| checked.inner.id == spec.id)
+        .map(|checked| checked.inner.available)
+        .ok_or_else(|| Error::Other(format!("no check result for contact {}", spec.id).into()))?;
+
+    if available {
+        let create = ContactCreate::new(
+            spec.id,
+            spec.email,
+            spec.postal_info.clone(),
+            spec.voice.clone(),
+            spec.auth_password,
+        );
+        client.transact(&create, id).await?;
+        return Ok(ensured(EnsureAction::Created));
+    }
+
+    let info = client
+        .transact(
+            &ContactInfo::new(spec.id, spec.auth_password),
+            &format!("{id}:info"),
+        )
+        .await?
+        .into_data()?;
+
+    let mut update = ContactUpdate::builder(spec.id);
+    let mut changed = false;
+    if info.postal_info_by_type(spec.postal_info.info_type) != Some(&spec.postal_info) {
+        update = update.postal_info(spec.postal_info.clone());
+        changed = true;
+    }
+    if let Some(voice) = &spec.voice {
+        if info.voice.as_ref() != Some(voice) {
+            update = update.voice(voice.clone());
+            changed = true;
+        }
+    }
+    if info.email.as_deref() != Some(spec.email) {
+        update = update.email(spec.email);
+        changed = true;
+    }
+
+    if !changed {
+        return Ok(ensured(EnsureAction::Unchanged));
+    }
+
+    client.transact(&update.build()?, id).await?;
+    Ok(ensured(EnsureAction::Updated))
+}
+
+// Returns whether a delete of a linked object should be sent anyway
+fn proceed(policy: LinkedPolicy, kind: &str, name: &str) -> bool {
+    match policy {
+        LinkedPolicy::Refuse => false,
+        LinkedPolicy::Warn => {
+            warn!("deleting linked {kind} {name}, registry is likely to reject this");
+            true
+        }
+    }
+}
@@ -0,0 +1,753 @@
+//! Converging a domain to a declared state
+//!
+//! Instead of issuing updates by hand, describe the desired state of a domain in a
+//! [`DomainSpec`]. [`plan()`] reads the domain, its DS records and the hosts listed in the
+//! spec, and returns the differences as a [`Plan`] that can be reviewed; [`apply()`] sends the
+//! commands to carry it out. [`reconcile()`] does both. To review the exact commands before
+//! anything is changed, [`dry_run()`] renders them without sending them.
+
+use std::fmt;
+use std::net::IpAddr;
+
+use super::{address_changes, address_update};
+use crate::client::{Connector, EppClient, RenderedCommand};
+use crate::domain::update::{DomainAdd, DomainChangeInfo, DomainRemove};
+use crate::domain::{
+    DomainContact, DomainInfo, DomainUpdate, InfoData, NameServers, Nameserver, NameserverList,
+    NameserverModel, Status,
+};
+use crate::extensions::secdns::{DsDataType, DsRecord, SecDnsInfoExtension, UpdateData};
+use crate::host::{HostCreate, HostInfo, HostUpdate};
+use crate::response::ResultCode;
+use crate::Error;
+
+/// The desired state of a domain
+///
+/// Fields set to `None` are left as they are at the registry.
+#[derive(Clone, Debug, Default)]
+pub struct DomainSpec {
+    pub name: String,
+    pub registrant: Option<String>,
+    /// Contacts as `(type, id)` pairs, like `("admin", "ABC-123")`
+    pub contacts: Option<Vec<(String, String)>>,
+    /// Names of the name servers
+    ///
+    /// In the host attribute model, their glue addresses are taken from [`hosts`](Self::hosts).
+    pub nameservers: Option<Vec<String>>,
+    /// The way the registry expects name servers to be given
+    ///
+    /// If not set, the model of the domain's current name servers is used, or host objects for
+    /// a domain without any.
+    pub nameserver_model: Option<NameserverModel>,
+    /// Client statuses, like [`Status::ClientTransferProhibited`]
+    ///
+    /// Server statuses are set by the registry; they are ignored here and never removed.
+    pub statuses: Option<Vec<Status>>,
+    /// DS records; an empty list removes all of them
+    pub ds: Option<Vec<DsRecord>>,
+    /// Hosts that must exist with the given addresses, usually in-bailiwick name servers
+    ///
+    /// In the host attribute model, these are the glue addresses of the name servers instead,
+    /// and no host objects are created or updated.
+    pub hosts: Vec<(String, Vec<IpAddr>)>,
+}
+
+/// A difference between a domain and its [`DomainSpec`]
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Change {
+    CreateHost {
+        name: String,
+        addresses: Vec<IpAddr>,
+    },
+    UpdateHost {
+        name: String,
+        add: Vec<IpAddr>,
+        remove: Vec<IpAddr>,
+    },
+    SetRegistrant(String),
+    AddContact {
+        contact_type: String,
+        id: String,
+    },
+    RemoveContact {
+        contact_type: String,
+        id: String,
+    },
+    /// A name server, with its glue in the host attribute model
+    AddNameserver(Nameserver),
+    RemoveNameserver(String),
+    AddStatus(Status),
+    RemoveStatus(Status),
+    AddDs(DsRecord),
+    RemoveDs(DsRecord),
+}
+
+/// One line, like `+ ns ns1.example.com` or `- status clientHold`
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let addresses = |addresses: &[IpAddr]| {
+            addresses
+                .iter()
+                .map(IpAddr::to_string)
+                .collect::<Vec<_>>()
+                .join(" ")
+        };
+
+        match self {
+            Self::CreateHost { name, addresses: a } => {
+                write!(f, "+ host {name} {}", addresses(a))
+            }
+            Self::UpdateHost { name, add, remove } => {
+                write!(f, "~ host {name}")?;
+                if !add.is_empty() {
+                    write!(f, " +{}", addresses(add))?;
+                }
+                if !remove.is_empty() {
+                    write!(f, " -{}", addresses(remove))?;
+                }
+                Ok(())
+            }
+            Self::SetRegistrant(id) => write!(f, "~ registrant {id}"),
+            Self::AddContact { contact_type, id } => write!(f, "+ contact {contact_type} {id}"),
+            Self::RemoveContact { contact_type, id } => {
+                write!(f, "- contact {contact_type} {id}")
+            }
+            Self::AddNameserver(ns) => {
+                write!(f, "+ ns {}", ns.name)?;
+                if !ns.glue.is_empty() {
+                    write!(f, " {}", addresses(&ns.glue))?;
+                }
+                Ok(())
+            }
+            Self::RemoveNameserver(name) => write!(f, "- ns {name}"),
+            Self::AddStatus(status) => write!(f, "+ status {}", status.as_str()),
+            Self::RemoveStatus(status) => write!(f, "- status {}", status.as_str()),
+            Self::AddDs(ds) => write!(f, "+ ds {}", DsLine(ds)),
+            Self::RemoveDs(ds) => write!(f, "- ds {}", DsLine(ds)),
+        }
+    }
+}
+
+struct DsLine<'a>(&'a DsRecord);
+
+impl fmt::Display for DsLine<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ds = self.0;
+        write!(
+            f,
+            "{} {} {} {}",
+            ds.key_tag, ds.algorithm, ds.digest_type, ds.digest
+        )
+    }
+}
+
+/// The changes needed to converge a domain to its [`DomainSpec`]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Plan {
+    /// The name of the domain
+    pub name: String,
+    pub changes: Vec<Change>,
+    /// The model name server changes are sent in
+    pub nameserver_model: NameserverModel,
+    /// Whether the domain had `clientUpdateProhibited` set when the plan was made
+    pub locked: bool,
+}
+
+impl Plan {
+    /// Whether the domain already matches its spec
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// One line per change
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, change) in self.changes.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{change}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Read the current state of the domain in `spec` and compute the changes to converge it
+///
+/// Sends a domain `<info>` command (with `id` suffixed by `:info` as its client transaction
+/// ID) and, in the host object model, a host `<info>` command for each host in the spec
+/// (`:host:` and a sequence number). Nothing is changed at the registry.
+pub async fn plan<C: Connector>(
+    client: &mut EppClient<C>,
+    spec: &DomainSpec,
+    id: &str,
+) -> Result<Plan, Error> {
+    let info = DomainInfo::new(&spec.name, None);
+    let response = client
+        .transact((&info, &SecDnsInfoExtension), &format!("{id}:info"))
+        .await?;
+    let data = response.data()?;
+    let ds = match response.extension() {
+        Some(ext) => ext.ds_data.as_slice(),
+        None => &[],
+    };
+
+    let current = data.nameservers().filter(|ns| !ns.is_empty());
+    let model = spec
+        .nameserver_model
+        .or(current.map(|ns| ns.model()))
+        .unwrap_or_default();
+
+    let mut changes = Vec::new();
+    if model == NameserverModel::HostObj {
+        for (seq, (name, addresses)) in spec.hosts.iter().enumerate() {
+            let info = HostInfo::new(name);
+            match client
+                .transact(&info, &format!("{id}:host:{}", seq + 1))
+                .await
+            {
+                Ok(response) => {
+                    let (add, remove) = address_changes(&response.data()?.addresses, addresses);
+                    if !add.is_empty() || !remove.is_empty() {
+                        changes.push(Change::UpdateHost {
+                            name: name.clone(),
+                            add,
+                            remove,
+                        });
+                    }
+                }
+                Err(Error::Command(status))
+                    if status.result.code == ResultCode::ObjectDoesNotExist =>
+                {
+                    changes.push(Change::CreateHost {
+                        name: name.clone(),
+                        addresses: addresses.clone(),
+                    });
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    changes.extend(diff(spec, data, ds, model));
+    Ok(Plan {
+        name: spec.name.clone(),
+        changes,
+        nameserver_model: model,
+        locked: data.has_status(Status::ClientUpdateProhibited),
+    })
+}
+
+/// Send the commands carrying out `plan`
+///
+/// Hosts are created or updated first, so that new name servers exist before the domain
+/// refers to them. The domain changes go into a single `<update>` command. Registries reject
+/// any other change while `clientUpdateProhibited` is set, so for a [locked](Plan::locked)
+/// domain it is removed in a command of its own first and set again in one after the changes,
+/// unless the plan removes it for good; a plan adding it sets it last. The commands are sent
+/// with `id` suffixed by `:` and a sequence number as their client transaction IDs.
+pub async fn apply<C: Connector>(
+    client: &mut EppClient<C>,
+    plan: &Plan,
+    id: &str,
+) -> Result<(), Error> {
+    for (seq, step) in steps(plan).iter().enumerate() {
+        let id = format!("{id}:{}", seq + 1);
+        match step {
+            Step::CreateHost(create) => {
+                client.transact(create, &id).await?;
+            }
+            Step::UpdateHost(update) => {
+                client.transact(update, &id).await?;
+            }
+            Step::Domain(changes) => {
+                let update = changes.update();
+                match changes.secdns()? {
+                    Some(secdns) => client.transact((&update, &secdns), &id).await?,
+                    None => client.transact(&update, &id).await?,
+                };
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Render the commands [`apply()`] would send for `plan`, without sending them
+///
+/// The commands come in the order and with the client transaction IDs `apply()` uses for the
+/// same `id`, each with the XML it would be sent as (see [`EppClient::render()`]).
+pub fn dry_run<C: Connector>(
+    client: &EppClient<C>,
+    plan: &Plan,
+    id: &str,
+) -> Result<Vec<RenderedCommand>, Error> {
+    let mut rendered = Vec::new();
+    for (seq, step) in steps(plan).iter().enumerate() {
+        let id = format!("{id}:{}", seq + 1);
+        rendered.push(match step {
+            Step::CreateHost(create) => client.render(create, &id)?,
+            Step::UpdateHost(update) => client.render(update, &id)?,
+            Step::Domain(changes) => {
+                let update = changes.update();
+                match changes.secdns()? {
+                    Some(secdns) => client.render((&update, &secdns), &id)?,
+                    None => client.render(&update, &id)?,
+                }
+            }
+        });
+    }
+
+    Ok(rendered)
+}
+
+// One command carrying out part of a plan, in the order `apply()` sends them
+enum Step<'a> {
+    CreateHost(HostCreate<'a>),
+    UpdateHost(HostUpdate<'a>),
+    Domain(DomainChanges<'a>),
+}
+
+fn steps(plan: &Plan) -> Vec<Step<'_>> {
+    let mut steps = Vec::new();
+    for change in &plan.changes {
+        match change {
+            Change::CreateHost { name, addresses } => {
+                let addresses = Some(addresses.as_slice()).filter(|addrs| !addrs.is_empty());
+                steps.push(Step::CreateHost(HostCreate::new(name, addresses)));
+            }
+            Change::UpdateHost { name, add, remove } => {
+                steps.push(Step::UpdateHost(address_update(name, add, remove)));
+            }
+            _ => {}
+        }
+    }
+
+    for group in domain_updates(plan) {
+        steps.push(Step::Domain(DomainChanges::new(
+            &plan.name,
+            &group,
+            plan.nameserver_model,
+        )));
+    }
+
+    steps
+}
+
+/// Compute the changes for the domain in `spec`, then send them
+///
+/// Combines [`plan()`] and [`apply()`], returning the plan that was carried out. The info
+/// commands are sent with `id` suffixed by `:plan`, the changes with `id` suffixed by
+/// `:apply`.
+pub async fn reconcile<C: Connector>(
+    client: &mut EppClient<C>,
+    spec: &DomainSpec,
+    id: &str,
+) -> Result<Plan, Error> {
+    let plan = plan(client, spec, &format!("{id}:plan")).await?;
+    if !plan.is_empty() {
+        apply(client, &plan, &format!("{id}:apply")).await?;
+    }
+
+    Ok(plan)
+}
+
+// The domain changes between `spec` and the current state in `info` and `ds`, with name
+// servers given in `model`
+fn diff(
+    spec: &DomainSpec,
+    info: &InfoData,
+    ds: &[DsRecord],
+    model: NameserverModel,
+) -> Vec<Change> {
+    let mut changes = Vec::new();
+
+    if let Some(registrant) = &spec.registrant {
+        if info.registrant.as_ref() != Some(registrant) {
+            changes.push(Change::SetRegistrant(registrant.clone()));
+        }
+    }
+
+    if let Some(contacts) = &spec.contacts {
+        let current = info
+            .contacts
+            .iter()
+            .flatten()
+            .map(|contact| (contact.contact_type.to_string(), contact.id.to_string()))
+            .collect::<Vec<_>>();
+        for (contact_type, id) in current.iter().filter(|c| !contacts.contains(c)) {
+            changes.push(Change::RemoveContact {
+                contact_type: contact_type.clone(),
+                id: id.clone(),
+            });
+        }
+        for (contact_type, id) in contacts.iter().filter(|c| !current.contains(c)) {
+            changes.push(Change::AddContact {
+                contact_type: contact_type.clone(),
+                id: id.clone(),
+            });
+        }
+    }
+
+    if let Some(nameservers) = &spec.nameservers {
+        let current = match info.nameservers() {
+            Some(NameserverList::HostObjects(names)) => {
+                names.iter().map(|name| Nameserver::new(name)).collect()
+            }
+            Some(NameserverList::HostAttributes(hosts)) => hosts,
+            None => Vec::new(),
+        };
+        let desired = nameservers
+            .iter()
+            .map(|name| match model {
+                NameserverModel::HostObj => Nameserver::new(name),
+                NameserverModel::HostAttr => Nameserver::with_glue(name, glue(spec, name)),
+            })
+            .collect::<Vec<_>>();
+
+        // A name server whose glue changes is removed and added again
+        let listed = |list: &[Nameserver], ns: &Nameserver| {
+            let ns = ns.normalized();
+            list.iter().any(|listed| listed.normalized() == ns)
+        };
+        for ns in current.iter().filter(|ns| !listed(&desired, ns)) {
+            changes.push(Change::RemoveNameserver(ns.name.clone()));
+        }
+        for ns in desired.iter().filter(|ns| !listed(&current, ns)) {
+            changes.push(Change::AddNameserver(ns.clone()));
+        }
+    }
+
+    if let Some(statuses) = &spec.statuses {
+        let current = info
+            .statuses
+            .iter()
+            .flatten()
+            .filter(|status| is_client(status))
+            .collect::<Vec<_>>();
+        for &status in current.iter().filter(|status| !statuses.contains(status)) {
+            changes.push(Change::RemoveStatus(*status));
+        }
+        for status in statuses.iter().filter(|status| is_client(status)) {
+            if !current.contains(&status) {
+                changes.push(Change::AddStatus(*status));
+            }
+        }
+    }
+
+    if let Some(desired) = &spec.ds {
+        for record in ds.iter().filter(|r| !desired.iter().any(|d| d.matches(r))) {
+            changes.push(Change::RemoveDs(record.clone()));
+        }
+        for record in desired.iter().filter(|d| !ds.iter().any(|r| r.matches(d))) {
+            changes.push(Change::AddDs(record.clone()));
+        }
+    }
+
+    changes
+}
+
+fn is_client(status: &Status) -> bool {
+    status.as_str().starts_with("client")
+}
+
+// The glue addresses for the name server `name`, from the hosts in `spec`
+fn glue(spec: &DomainSpec, name: &str) -> Vec<IpAddr> {
+    spec.hosts
+        .iter()
+        .find(|(host, _)| host.eq_ignore_ascii_case(name))
+        .map(|(_, addresses)| addresses.clone())
+        .unwrap_or_default()
+}
+
+static LOCK: Change = Change::AddStatus(Status::ClientUpdateProhibited);
+static UNLOCK: Change = Change::RemoveStatus(Status::ClientUpdateProhibited);
+
+// Group the domain changes into update commands, see `apply()`
+fn domain_updates(plan: &Plan) -> Vec<Vec<&Change>> {
+    let rest = plan
+        .changes
+        .iter()
+        .filter(|change| {
+            !matches!(
+                change,
+                Change::CreateHost { .. } | Change::UpdateHost { .. }
+            ) && **change != LOCK
+                && **change != UNLOCK
+        })
+        .collect::<Vec<_>>();
+
+    let unlock = plan.changes.contains(&UNLOCK);
+    let relock = plan.locked && !unlock && !rest.is_empty();
+    let mut groups = Vec::new();
+    if unlock || relock {
+        groups.push(vec![&UNLOCK]);
+    }
+    if !rest.is_empty() {
+        groups.push(rest);
+    }
+    if relock || plan.changes.contains(&LOCK) {
+        groups.push(vec![&LOCK]);
+    }
+    groups
+}
+
+// The parts of a domain `<update>` command, owned so the command can borrow them
+struct DomainChanges<'a> {
+    name: &'a str,
+    registrant: Option<&'a str>,
+    add_ns: NameserverList,
+    remove_ns: NameserverList,
+    add_contacts: Vec<DomainContact<'a>>,
+    remove_contacts: Vec<DomainContact<'a>>,
+    add_statuses: Vec<Status>,
+    remove_statuses: Vec<Status>,
+    add_ds: Vec<DsDataType<'a>>,
+    remove_ds: Vec<DsDataType<'a>>,
+}
+
+impl<'a> DomainChanges<'a> {
+    fn new(name: &'a str, changes: &[&'a Change], model: NameserverModel) -> Self {
+        let (mut add_ns, mut remove_ns) = (Vec::new(), Vec::new());
+        let (mut add_contacts, mut remove_contacts) = (Vec::new(), Vec::new());
+        let (mut add_statuses, mut remove_statuses) = (Vec::new(), Vec::new());
+        let (mut add_ds, mut remove_ds) = (Vec::new(), Vec::new());
+        let mut registrant = None;
+
+        for change in changes {
+            match change {
+                Change::SetRegistrant(id) => registrant = Some(id.as_str()),
+                Change::AddContact { contact_type, id } => add_contacts.push(DomainContact {
+                    contact_type: contact_type.as_str().into(),
+                    id: id.as_str().into(),
+                }),
+                Change::RemoveContact { contact_type, id } => remove_contacts.push(DomainContact {
+                    contact_type: contact_type.as_str().into(),
+                    id: id.as_str().into(),
+                }),
+                Change::AddNameserver(ns) => add_ns.push(ns.clone()),
+                Change::RemoveNameserver(name) => remove_ns.push(Nameserver::new(name)),
+                Change::AddStatus(status) => add_statuses.push(*status),
+                Change::RemoveStatus(status) => remove_statuses.push(*status),
+                Change::AddDs(ds) => add_ds.push(ds.to_ds_data()),
+                Change::RemoveDs(ds) => remove_ds.push(ds.to_ds_data()),
+                Change::CreateHost { .. } | Change::UpdateHost { .. } => {}
+            }
+        }
+
+        let list = |hosts: Vec<Nameserver>| match model {
+            NameserverModel::HostObj => {
+                NameserverList::HostObjects(hosts.into_iter().map(|ns| ns.name).collect())
+            }
+            NameserverModel::HostAttr => NameserverList::HostAttributes(hosts),
+        };
+
+        Self {
+            name,
+            registrant,
+            add_ns: list(add_ns),
+            remove_ns: list(remove_ns),
+            add_contacts,
+            remove_contacts,
+            add_statuses,
+            remove_statuses,
+            add_ds,
+            remove_ds,
+        }
+    }
+
+    fn update(&self) -> DomainUpdate<'_> {
+        let mut update = DomainUpdate::new(self.name);
+        let (add_ns, remove_ns) = (&self.add_ns, &self.remove_ns);
+        if !add_ns.is_empty() || !self.add_contacts.is_empty() || !self.add_statuses.is_empty() {
+            update.add(DomainAdd {
+                ns: Some(NameServers::from(add_ns)).filter(|_| !add_ns.is_empty()),
+                contacts: Some(self.add_contacts.as_slice()).filter(|c| !c.is_empty()),
+                statuses: Some(self.add_statuses.as_slice()).filter(|s| !s.is_empty()),
+            });
+        }
+        if !remove_ns.is_empty()
+            || !self.remove_contacts.is_empty()
+            || !self.remove_statuses.is_empty()
+        {
+            update.remove(DomainRemove {
+                ns: Some(NameServers::from(remove_ns)).filter(|_| !remove_ns.is_empty()),
+                contacts: Some(self.remove_contacts.as_slice()).filter(|c| !c.is_empty()),
+                statuses: Some(self.remove_statuses.as_slice()).filter(|s| !s.is_empty()),
+            });
+        }
+        if self.registrant.is_some() {
+            update.info(DomainChangeInfo {
+                registrant: self.registrant,
+                auth_info: None,
+            });
+        }
+        update
+    }
+
+    // The secDNS extension for the DS changes, if there are any
+    fn secdns(&self) -> Result<Option<UpdateData<'_>>, Error> {
+        if self.add_ds.is_empty() && self.remove_ds.is_empty() {
+            return Ok(None);
+        }
+
+        let mut secdns = UpdateData::builder();
+        if !self.remove_ds.is_empty() {
+            secdns = secdns.remove_ds(&self.remove_ds);
+        }
+        if !self.add_ds.is_empty() {
+            secdns = secdns.add_ds(&self.add_ds);
+        }
+        secdns.build().map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, domain_updates, steps, Change, DomainSpec, Plan, Step};
+    use crate::domain::{DomainInfo, Nameserver, NameserverModel, Status};
+    use crate::extensions::secdns::{DsRecord, SecDnsInfoExtension};
+    use crate::tests::{response_from_file, response_from_file_with_ext};
+
+    #[test]
+    fn plan() {
+        let object = response_from_file_with_ext::<DomainInfo, SecDnsInfoExtension>(
+            "response/extensions/domain_info_secdns.xml",
+        );
+        let info = object.res_data().unwrap();
+        let current = &object.extension().unwrap().ds_data;
+
+        // The current state yields no changes
+        let spec = DomainSpec {
+            name: "eppdev-1.com".into(),
+            registrant: Some("eppdev-contact-2".into()),
+            nameservers: Some(vec!["NS1.eppdev-1.com".into(), "ns2.eppdev-1.com".into()]),
+            statuses: Some(vec![Status::ClientTransferProhibited]),
+            ds: Some(current.clone()),
+            ..DomainSpec::default()
+        };
+        assert!(diff(&spec, info, current, NameserverModel::HostObj).is_empty());
+
+        let ds = DsRecord {
+            key_tag: 4242,
+            algorithm: 13,
+            digest_type: 2,
+            digest: "ABCDEF".into(),
+        };
+        let spec = DomainSpec {
+            name: "eppdev-1.com".into(),
+            contacts: Some(vec![
+                ("admin".into(), "eppdev-contact-2".into()),
+                ("tech".into(), "eppdev-contact-3".into()),
+                ("billing".into(), "eppdev-contact-2".into()),
+            ]),
+            nameservers: Some(vec!["ns1.eppdev-1.com".into(), "ns3.eppdev-1.com".into()]),
+            statuses: Some(vec![Status::ClientUpdateProhibited, Status::ServerHold]),
+            ds: Some(vec![current[1].clone(), ds.clone()]),
+            ..DomainSpec::default()
+        };
+
+        let plan = Plan {
+            name: spec.name.clone(),
+            changes: diff(&spec, info, current, NameserverModel::HostObj),
+            ..Plan::default()
+        };
+        assert_eq!(
+            plan.to_string(),
+            "- contact tech eppdev-contact-2\n\
+             + contact tech eppdev-contact-3\n\
+             - ns ns2.eppdev-1.com\n\
+             + ns ns3.eppdev-1.com\n\
+             - status clientTransferProhibited\n\
+             + status clientUpdateProhibited\n\
+             - ds 12345 3 1 49FD46E6C4B45C55D4AC\n\
+             + ds 4242 13 2 ABCDEF"
+        );
+
+        // Locking the domain comes after the other changes
+        let groups = domain_updates(&plan);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].len(), 7);
+        assert_eq!(
+            groups[1],
+            [&Change::AddStatus(Status::ClientUpdateProhibited)]
+        );
+    }
+
+    #[test]
+    fn locked() {
+        let lock = Change::AddStatus(Status::ClientUpdateProhibited);
+        let unlock = Change::RemoveStatus(Status::ClientUpdateProhibited);
+        let registrant = Change::SetRegistrant("eppdev-contact-3".into());
+        let mut plan = Plan {
+            name: "eppdev.com".into(),
+            changes: vec![registrant.clone()],
+            locked: true,
+            ..Plan::default()
+        };
+
+        // A locked domain is unlocked for the changes and locked again
+        assert_eq!(
+            domain_updates(&plan),
+            [vec![&unlock], vec![&registrant], vec![&lock]]
+        );
+
+        // Unless the spec unlocks it
+        plan.changes.push(unlock.clone());
+        assert_eq!(domain_updates(&plan), [vec![&unlock], vec![&registrant]]);
+
+        // Nothing to do for a locked domain without changes
+        plan.changes.clear();
+        assert!(domain_updates(&plan).is_empty());
+    }
+
+    #[test]
+    fn host_attributes() {
+        let object = response_from_file::<DomainInfo>("response/domain/info_host_attr.xml");
+        let info = object.res_data().unwrap();
+        let ns1 = Nameserver::with_glue(
+            "ns1.eppdev-2.com",
+            [
+                "1080::8:800:200c:417a".parse().unwrap(),
+                "192.0.2.2".parse().unwrap(),
+            ],
+        );
+
+        // The same glue in another order and notation is no change
+        let mut spec = DomainSpec {
+            name: "eppdev-2.com".into(),
+            nameservers: Some(vec!["ns1.eppdev-2.com".into(), "ns3.example.net".into()]),
+            hosts: vec![(ns1.name.clone(), ns1.glue.clone())],
+            ..DomainSpec::default()
+        };
+        let plan = Plan {
+            name: spec.name.clone(),
+            changes: diff(&spec, info, &[], NameserverModel::HostAttr),
+            nameserver_model: NameserverModel::HostAttr,
+            locked: false,
+        };
+        assert_eq!(
+            plan.to_string(),
+            "- ns ns2.example.net\n\
+             + ns ns3.example.net"
+        );
+
+        let steps = steps(&plan);
+        let [Step::Domain(changes)] = steps.as_slice() else {
+            panic!("expected a single domain update");
+        };
+        let update = changes.update();
+        let add = update.domain.add.as_ref().unwrap().ns.as_ref().unwrap();
+        assert!(matches!(add.ns[0], crate::domain::HostInfo::Attr(_)));
+
+        // Changed glue replaces the name server
+        spec.hosts[0].1.pop();
+        let changes = diff(&spec, info, &[], NameserverModel::HostAttr);
+        assert_eq!(changes[0], Change::RemoveNameserver(ns1.name.clone()));
+        assert_eq!(
+            changes[2].to_string(),
+            "+ ns ns1.eppdev-2.com 1080::8:800:200c:417a"
+        );
+    }
+}
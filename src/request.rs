@@ -4,7 +4,10 @@ use std::fmt::Debug;
 
 use instant_xml::{FromXmlOwned, ToXml};
 
-use crate::common::EPP_XMLNS;
+use crate::balance::Balance;
+use crate::common::{NoExtension, EPP_XMLNS};
+use crate::middleware::CommandView;
+use crate::xml;
 
 pub const EPP_VERSION: &str = "1.0";
 pub const EPP_LANG: &str = "en";
@@ -13,18 +16,206 @@ pub const EPP_LANG: &str = "en";
 pub trait Transaction<Ext: Extension>: Command + Sized {}
 
 pub trait Command: ToXml + Debug {
-    type Response: FromXmlOwned + Debug;
+    /// The type of the `<resData>` returned for this command
+    ///
+    /// Commands that do not return `<resData>` (like delete, update or logout) use `()`.
+    type Response: FromXmlOwned + Debug + 'static;
     const COMMAND: &'static str;
+
+    /// Typed access to this command for [`Middleware`](crate::middleware::Middleware)
+    fn view(&self) -> CommandView<'_> {
+        CommandView::Other(Self::COMMAND)
+    }
+}
+
+#[cfg(feature = "client")]
+/// Whether the command `view`, with the verb `command`, changes objects at the registry
+///
+/// Transfer queries share the verb of the other transfer operations, but only read the
+/// transfer status.
+pub(crate) fn is_transform(command: &str, view: &CommandView<'_>) -> bool {
+    match view {
+        CommandView::DomainTransfer(transfer) => transfer.operation != "query",
+        _ => is_transform_verb(command),
+    }
+}
+
+#[cfg(feature = "client")]
+/// Whether the command verb `command` changes objects at the registry
+pub(crate) fn is_transform_verb(command: &str) -> bool {
+    matches!(
+        command,
+        "create" | "delete" | "renew" | "transfer" | "update"
+    )
 }
 
 pub trait Extension: ToXml + Debug {
     const DO_SEND: bool = true;
-    type Response: FromXmlOwned + Debug;
+    type Response: FromXmlOwned + Debug + 'static;
     fn do_send(&self) -> bool {
         Self::DO_SEND
     }
+
+    /// The namespaces of the elements this extension sends
+    ///
+    /// A [`SessionProfile`](crate::profile::SessionProfile) checks these before a command is
+    /// sent, and refuses extensions returning `None`.
+    fn namespaces(&self) -> Option<Vec<&str>> {
+        None
+    }
+
+    /// The account balance reported in `response`
+    ///
+    /// Extensions whose response implements [`ReportsBalance`] return it from here, so
+    /// [`AccountBalance`] sees the balance whatever extensions the response is combined with.
+    ///
+    /// [`ReportsBalance`]: crate::balance::ReportsBalance
+    /// [`AccountBalance`]: crate::balance::AccountBalance
+    fn balance(response: &Self::Response) -> Option<Balance> {
+        let _ = response;
+        None
+    }
 }
 
+/// Implement [`Extension`] for a type, and [`Transaction`] for the commands it applies to
+///
+/// The generic parameters of the `impl` are available in the command types:
+///
+/// ```
+/// use instant_epp::common::NoExtension;
+/// use instant_epp::domain::{DomainCreate, DomainUpdate};
+/// use instant_xml::ToXml;
+///
+/// const XMLNS: &str = "urn:example:params:xml:ns:acme-1.0";
+///
+/// #[derive(Debug, ToXml)]
+/// #[xml(rename = "create", ns(XMLNS))]
+/// pub struct Create<'a> {
+///     pub reference: &'a str,
+/// }
+///
+/// instant_epp::impl_extension! {
+///     impl<'a> Extension for Create<'a> {
+///         type Response = NoExtension;
+///         namespaces: XMLNS;
+///         commands: DomainCreate<'a>, DomainUpdate<'a>;
+///     }
+/// }
+/// ```
+///
+/// Extensions that only read data from responses are marked with `#[response_only]`. This
+/// also implements `ToXml` to serialize nothing, so the type must not derive it; the crate
+/// using the macro needs a dependency on `instant-xml` for this.
+///
+/// ```
+/// # use instant_epp::common::NoExtension;
+/// use instant_epp::domain::DomainInfo;
+///
+/// #[derive(Debug)]
+/// pub struct AcmeInfo;
+///
+/// instant_epp::impl_extension! {
+///     #[response_only]
+///     impl Extension for AcmeInfo {
+///         type Response = NoExtension;
+///         commands: DomainInfo<'_>;
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! impl_extension {
+    (
+        #[response_only]
+        impl Extension for $ext:ty {
+            type Response = $resp:ty;
+            commands: $($cmd:ty),+ $(,)?;
+        }
+    ) => {
+        impl ::instant_xml::ToXml for $ext {
+            fn serialize<W: ::std::fmt::Write + ?Sized>(
+                &self,
+                _field: ::std::option::Option<::instant_xml::Id<'_>>,
+                _serializer: &mut ::instant_xml::Serializer<W>,
+            ) -> ::std::result::Result<(), ::instant_xml::Error> {
+                Ok(())
+            }
+
+            fn present(&self) -> bool {
+                false
+            }
+        }
+
+        impl $crate::request::Extension for $ext {
+            const DO_SEND: bool = false;
+            type Response = $resp;
+        }
+
+        $crate::impl_extension!(@transactions [] $ext; $($cmd),+);
+    };
+    (
+        impl $(<$($lt:lifetime),+>)? Extension for $ext:ty {
+            type Response = $resp:ty;
+            $(namespaces: $($ns:expr),+ $(,)?;)?
+            commands: $($cmd:ty),+ $(,)?;
+        }
+    ) => {
+        impl $(<$($lt),+>)? $crate::request::Extension for $ext {
+            type Response = $resp;
+
+            $(
+                fn namespaces(&self) -> ::std::option::Option<::std::vec::Vec<&str>> {
+                    ::std::option::Option::Some(::std::vec![$($ns),+])
+                }
+            )?
+        }
+
+        $crate::impl_extension!(@transactions [$($($lt),+)?] $ext; $($cmd),+);
+    };
+    (@transactions [$($generics:tt)*] $ext:ty;) => {};
+    (@transactions [$($generics:tt)*] $ext:ty; $cmd:ty $(, $rest:ty)*) => {
+        impl<$($generics)*> $crate::request::Transaction<$ext> for $cmd {}
+        $crate::impl_extension!(@transactions [$($generics)*] $ext; $($rest),*);
+    };
+}
+
+#[derive(Debug)]
+pub struct RequestData<'c, 'e, C, E> {
+    pub(crate) command: &'c C,
+    pub(crate) extension: Option<&'e E>,
+    // The serialized command a middleware replaced `command` with
+    pub(crate) replacement: Option<&'c str>,
+}
+
+impl<'c, C: Command> From<&'c C> for RequestData<'c, 'static, C, NoExtension> {
+    fn from(command: &'c C) -> Self {
+        Self {
+            command,
+            extension: None,
+            replacement: None,
+        }
+    }
+}
+
+impl<'c, 'e, C: Command, E: Extension> From<(&'c C, &'e E)> for RequestData<'c, 'e, C, E> {
+    fn from((command, extension): (&'c C, &'e E)) -> Self {
+        Self {
+            command,
+            extension: Some(extension),
+            replacement: None,
+        }
+    }
+}
+
+// Manual impl because this does not depend on whether `C` and `E` are `Clone`
+impl<C, E> Clone for RequestData<'_, '_, C, E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+// Manual impl because this does not depend on whether `C` and `E` are `Copy`
+impl<C, E> Copy for RequestData<'_, '_, C, E> {}
+
 #[derive(Debug, PartialEq)]
 /// Type corresponding to the `<command>` tag in an EPP XML request
 /// with an `<extension>` tag
@@ -48,6 +239,37 @@ impl<'a, E: Extension, D: Transaction<E>> CommandWrapper<'a, D, E> {
     }
 }
 
+impl<'a, E: Extension> CommandWrapper<'a, Serialized<'a>, E> {
+    /// Wrap a command serialized before, for the command verb `command`
+    pub(crate) fn serialized(
+        command: &'static str,
+        data: &'a Serialized<'a>,
+        extension: Option<&'a E>,
+        client_tr_id: &'a str,
+    ) -> Self {
+        Self {
+            command,
+            data,
+            extension,
+            client_tr_id: client_tr_id.into(),
+        }
+    }
+}
+
+/// A command serialized before, written out as is
+#[derive(Debug, PartialEq)]
+pub(crate) struct Serialized<'a>(pub(crate) &'a str);
+
+impl ToXml for Serialized<'_> {
+    fn serialize<W: std::fmt::Write + ?Sized>(
+        &self,
+        _: Option<instant_xml::Id<'_>>,
+        serializer: &mut instant_xml::Serializer<W>,
+    ) -> Result<(), instant_xml::Error> {
+        serializer.write_str(self.0)
+    }
+}
+
 impl<D: ToXml, E: ToXml + Extension> ToXml for CommandWrapper<'_, D, E> {
     fn serialize<W: std::fmt::Write + ?Sized>(
         &self,
@@ -59,7 +281,10 @@ impl<D: ToXml, E: ToXml + Extension> ToXml for CommandWrapper<'_, D, E> {
         self.data.serialize(None, serializer)?;
         if let Some(extension) = self.extension {
             if extension.do_send() {
-                Ext { inner: extension }.serialize(None, serializer)?;
+                let ext_prefix = serializer.write_start("extension", EPP_XMLNS)?;
+                serializer.end_start()?;
+                xml::serialize_extension(extension, serializer)?;
+                serializer.write_close(ext_prefix, "extension")?;
             }
         }
 
@@ -72,9 +297,3 @@ impl<D: ToXml, E: ToXml + Extension> ToXml for CommandWrapper<'_, D, E> {
         Ok(())
     }
 }
-
-#[derive(Debug, ToXml)]
-#[xml(rename = "extension", ns(EPP_XMLNS))]
-struct Ext<E> {
-    inner: E,
-}
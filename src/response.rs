@@ -1,11 +1,18 @@
 //! Types for EPP responses
 
-use std::fmt::Debug;
+#[cfg(feature = "client")]
+use std::any::Any;
+use std::fmt::{self, Debug};
+use std::ops::Deref;
 
 use chrono::{DateTime, Utc};
-use instant_xml::{Deserializer, Error, FromXml, Id, Kind};
+#[cfg(feature = "client")]
+use instant_xml::FromXmlOwned;
+use instant_xml::{Accumulate, Deserializer, Error, FromXml, Id, Kind};
 
 use crate::common::EPP_XMLNS;
+use crate::error::Error as EppError;
+use crate::xml::ScalarWarning;
 
 /// Type corresponding to the `<undef>` tag an EPP response XML
 #[derive(Debug, Eq, FromXml, PartialEq)]
@@ -13,7 +20,7 @@ use crate::common::EPP_XMLNS;
 pub struct Undef;
 
 /// Type corresponding to the `<value>` tag under `<extValue>` in an EPP response XML
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub struct ResultValue;
 
 impl<'xml> FromXml<'xml> for ResultValue {
@@ -45,7 +52,7 @@ impl<'xml> FromXml<'xml> for ResultValue {
 }
 
 /// Type corresponding to the `<extValue>` tag in an EPP response XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "extValue", ns(EPP_XMLNS))]
 pub struct ExtValue {
     /// Data under the `<value>` tag
@@ -55,7 +62,7 @@ pub struct ExtValue {
 }
 
 /// Type corresponding to the `<result>` tag in an EPP response XML
-#[derive(Debug, Eq, FromXml, PartialEq)]
+#[derive(Clone, Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "result", ns(EPP_XMLNS))]
 pub struct EppResult {
     /// The result code
@@ -68,6 +75,124 @@ pub struct EppResult {
     pub ext_value: Option<ExtValue>,
 }
 
+/// Formats as the code followed by the message, like `2303 Object does not exist`
+impl fmt::Display for EppResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.code as u16, self.message)?;
+        if let Some(ext_value) = &self.ext_value {
+            write!(f, " ({})", ext_value.reason)?;
+        }
+        Ok(())
+    }
+}
+
+/// The non-empty list of `<result>` tags in an EPP response XML
+///
+/// RFC 5730 allows a response to carry more than one `<result>`; the first one is treated as the
+/// primary result, and this type dereferences to it.
+#[derive(Debug, Eq, PartialEq)]
+pub struct EppResults {
+    primary: EppResult,
+    additional: Vec<EppResult>,
+}
+
+impl EppResults {
+    /// Returns the first `<result>` in the response
+    pub fn primary(&self) -> &EppResult {
+        &self.primary
+    }
+
+    /// Returns any `<result>` tags following the primary one
+    pub fn additional(&self) -> &[EppResult] {
+        &self.additional
+    }
+
+    /// Iterates over all results, starting with the primary one
+    pub fn iter(&self) -> impl Iterator<Item = &EppResult> {
+        std::iter::once(&self.primary).chain(self.additional.iter())
+    }
+
+    /// The number of `<result>` tags in the response (always at least one)
+    pub fn len(&self) -> usize {
+        1 + self.additional.len()
+    }
+
+    /// Always `false`, as a response carries at least one `<result>`
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+}
+
+/// All results on one line, separated by semicolons
+impl fmt::Display for EppResults {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, result) in self.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{result}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Deref for EppResults {
+    type Target = EppResult;
+
+    fn deref(&self) -> &Self::Target {
+        &self.primary
+    }
+}
+
+impl From<EppResult> for EppResults {
+    fn from(primary: EppResult) -> Self {
+        Self {
+            primary,
+            additional: Vec::new(),
+        }
+    }
+}
+
+impl<'xml> FromXml<'xml> for EppResults {
+    fn matches(id: Id<'_>, field: Option<Id<'_>>) -> bool {
+        EppResult::matches(id, field)
+    }
+
+    fn deserialize<'cx>(
+        into: &mut Self::Accumulator,
+        field: &'static str,
+        deserializer: &mut Deserializer<'cx, 'xml>,
+    ) -> Result<(), Error> {
+        let mut value = None;
+        EppResult::deserialize(&mut value, field, deserializer)?;
+        if let Some(value) = value {
+            into.0.push(value);
+        }
+
+        Ok(())
+    }
+
+    type Accumulator = EppResultsAccumulator;
+    const KIND: Kind = EppResult::KIND;
+}
+
+/// Accumulator collecting `<result>` tags into [`EppResults`]
+#[derive(Debug, Default)]
+pub struct EppResultsAccumulator(Vec<EppResult>);
+
+impl Accumulate<EppResults> for EppResultsAccumulator {
+    fn try_done(self, field: &'static str) -> Result<EppResults, Error> {
+        let mut results = self.0.into_iter();
+        match results.next() {
+            Some(primary) => Ok(EppResults {
+                primary,
+                additional: results.collect(),
+            }),
+            None => Err(Error::MissingValue(field)),
+        }
+    }
+}
+
 /// Response codes as enumerated in section 3 of RFC 5730
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum ResultCode {
@@ -199,8 +324,17 @@ impl<'xml> FromXml<'xml> for ResultCode {
         field: &'static str,
         deserializer: &mut instant_xml::Deserializer<'cx, 'xml>,
     ) -> Result<(), instant_xml::Error> {
-        let mut value = None;
-        u16::deserialize(&mut value, field, deserializer)?;
+        let value = match deserializer.take_str()? {
+            Some(value) => match crate::scalar::parse_number::<u16>(field, &value) {
+                Some(value) => Some(value),
+                None => {
+                    return Err(instant_xml::Error::UnexpectedValue(format!(
+                        "unable to parse result code from '{value}'"
+                    )))
+                }
+            },
+            None => None,
+        };
         if let Some(value) = value {
             *into = match Self::from_u16(value) {
                 Some(value) => Some(value),
@@ -252,6 +386,21 @@ pub struct MessageQueue {
     pub message: Option<Message>,
 }
 
+/// Formats like `message 12345 (4 queued) 2000-06-08T22:10:00Z: Credit balance low.`
+impl fmt::Display for MessageQueue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "message {} ({} queued)", self.id, self.count)?;
+        if let Some(date) = &self.date {
+            write!(f, " {}", date.format("%Y-%m-%dT%H:%M:%SZ"))?;
+        }
+        if let Some(message) = &self.message {
+            // Messages may contain structured data, keep only the text on the first line
+            write!(f, ": {}", message.text.lines().next().unwrap_or("").trim())?;
+        }
+        Ok(())
+    }
+}
+
 #[derive(Debug, Eq, FromXml, PartialEq)]
 #[xml(rename = "msg", ns(EPP_XMLNS))]
 pub struct Message {
@@ -266,8 +415,8 @@ pub struct Message {
 /// containing an `<extension>` tag
 #[xml(rename = "response", ns(EPP_XMLNS))]
 pub struct Response<D, E> {
-    /// Data under the `<result>` tag
-    pub result: EppResult,
+    /// Data under the `<result>` tags
+    pub result: EppResults,
     /// Data under the `<msgQ>` tag
     #[xml(rename = "msgQ")]
     pub message_queue: Option<MessageQueue>,
@@ -277,6 +426,62 @@ pub struct Response<D, E> {
     pub extension: Option<Extension<E>>,
     /// Data under the `<trID>` tag
     pub tr_ids: ResponseTRID,
+    /// Data under the `<svDate>` tag, which some servers include in responses
+    #[xml(
+        rename = "svDate",
+        deserialize_with = "crate::datetime::deserialize_datetime_utc_option"
+    )]
+    pub server_date: Option<DateTime<Utc>>,
+    /// The response frame as received, if the client was asked to retain it
+    raw: Option<RawXml>,
+    /// The values only accepted because of lenient parse options
+    warnings: Option<ScalarWarnings>,
+}
+
+/// The raw XML of a response, retained alongside the typed data
+///
+/// This never matches any element during deserialization; the client fills it in afterwards.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RawXml(String);
+
+impl<'xml> FromXml<'xml> for RawXml {
+    fn matches(_: Id<'_>, _: Option<Id<'_>>) -> bool {
+        false
+    }
+
+    fn deserialize<'cx>(
+        _: &mut Self::Accumulator,
+        _: &'static str,
+        _: &mut Deserializer<'cx, 'xml>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    type Accumulator = Option<Self>;
+    const KIND: Kind = Kind::Element;
+}
+
+/// The values of a response only accepted because of lenient parse options
+///
+/// Like [`RawXml`], this never matches any element; the client fills it in after parsing.
+#[derive(Clone, Debug, Eq, PartialEq)]
+struct ScalarWarnings(Vec<ScalarWarning>);
+
+impl<'xml> FromXml<'xml> for ScalarWarnings {
+    fn matches(_: Id<'_>, _: Option<Id<'_>>) -> bool {
+        false
+    }
+
+    fn deserialize<'cx>(
+        _: &mut Self::Accumulator,
+        _: &'static str,
+        _: &mut Deserializer<'cx, 'xml>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    type Accumulator = Option<Self>;
+    const KIND: Kind = Kind::Element;
 }
 
 #[derive(Debug, Eq, FromXml, PartialEq)]
@@ -296,11 +501,230 @@ impl<D> ResponseData<D> {
 /// without `<msgQ>` or `<resData>` sections. Generally used for error handling
 #[xml(rename = "response", ns(EPP_XMLNS))]
 pub struct ResponseStatus {
-    /// Data under the `<result>` tag
-    pub result: EppResult,
+    /// Data under the `<result>` tags
+    pub result: EppResults,
     #[xml(rename = "trID")]
     /// Data under the `<trID>` tag
     pub tr_ids: ResponseTRID,
+    /// Data under the `<svDate>` tag, which some servers include in responses
+    #[xml(
+        rename = "svDate",
+        deserialize_with = "crate::datetime::deserialize_datetime_utc_option"
+    )]
+    pub server_date: Option<DateTime<Utc>>,
+    /// The response frame as received, if the client was asked to retain it
+    raw: Option<RawXml>,
+    /// The values only accepted because of lenient parse options
+    warnings: Option<ScalarWarnings>,
+}
+
+impl ResponseStatus {
+    /// Returns the transaction metadata for this response
+    pub fn meta(&self) -> ResponseMeta<'_> {
+        ResponseMeta::new(&self.result, &self.tr_ids, self.server_date)
+    }
+
+    /// Returns the response frame exactly as received from the server
+    ///
+    /// Like [`Response::raw_xml()`], only available if the client retained it.
+    pub fn raw_xml(&self) -> Option<&str> {
+        self.raw.as_ref().map(|raw| raw.0.as_str())
+    }
+
+    /// The values only accepted because of lenient parse options, see
+    /// [`Response::scalar_warnings()`]
+    pub fn scalar_warnings(&self) -> &[ScalarWarning] {
+        scalar_warnings(&self.warnings)
+    }
+}
+
+/// A response to a raw command, as returned by [`EppClient::transact_raw()`] or
+/// [`EppClient::transact_frame()`]
+///
+/// [`EppClient::transact_raw()`]: crate::EppClient::transact_raw
+/// [`EppClient::transact_frame()`]: crate::EppClient::transact_frame
+#[derive(Debug, PartialEq)]
+pub struct RawResponse {
+    /// The response frame as received from the server
+    pub xml: String,
+    /// The result codes and transaction IDs parsed from the response
+    pub status: ResponseStatus,
+}
+
+impl RawResponse {
+    /// The code of the primary result
+    pub fn code(&self) -> ResultCode {
+        self.status.result.code
+    }
+
+    /// Returns the transaction metadata for this response
+    pub fn meta(&self) -> ResponseMeta<'_> {
+        self.status.meta()
+    }
+
+    /// Parse the response into types that borrow from the frame
+    ///
+    /// Response types with [`Text`](crate::common::Text) fields, like
+    /// [`CheckDataRef`](crate::domain::check::CheckDataRef) or
+    /// [`PollDataRef`](crate::poll::PollDataRef), point into the frame instead of copying each
+    /// value into a `String` of its own.
+    pub fn parse<'a, D, E>(&'a self) -> Result<Response<D, E>, EppError>
+    where
+        D: FromXml<'a>,
+        E: FromXml<'a>,
+    {
+        crate::xml::deserialize_borrowed(&self.xml)
+    }
+
+    /// Returns the data under the `<msgQ>` of the response, if any
+    pub fn message_queue(&self) -> Option<MessageQueue> {
+        crate::xml::deserialize::<QueueStatus>(&self.xml)
+            .ok()?
+            .message_queue
+    }
+}
+
+// The message queue of a response, skipping everything else
+#[derive(FromXml)]
+#[xml(rename = "response", ns(EPP_XMLNS))]
+struct QueueStatus {
+    #[xml(rename = "msgQ")]
+    message_queue: Option<MessageQueue>,
+}
+
+#[cfg(feature = "client")]
+/// A response as returned by the client: parsed into types, or a frame to be parsed later
+pub(crate) trait Reply: Any + Sized {
+    /// Parse `xml`, with the response quirks applied
+    fn parse(xml: &str) -> Result<Self, EppError>;
+    fn results(&self) -> &EppResults;
+    fn tr_ids(&self) -> &ResponseTRID;
+    fn message_id(&self) -> Option<String>;
+    /// The data under `<extension>`, if any
+    fn extension(&self) -> Option<&dyn Any>;
+    fn retain_raw(&mut self, xml: String);
+    fn set_scalar_warnings(&mut self, warnings: Vec<ScalarWarning>);
+    fn into_status(self) -> ResponseStatus;
+}
+
+#[cfg(feature = "client")]
+impl<D, E> Reply for Response<D, E>
+where
+    D: FromXmlOwned + 'static,
+    E: FromXmlOwned + 'static,
+{
+    fn parse(xml: &str) -> Result<Self, EppError> {
+        crate::xml::deserialize(xml)
+    }
+
+    fn results(&self) -> &EppResults {
+        &self.result
+    }
+
+    fn tr_ids(&self) -> &ResponseTRID {
+        &self.tr_ids
+    }
+
+    fn message_id(&self) -> Option<String> {
+        self.message_queue().map(|queue| queue.id.clone())
+    }
+
+    fn extension(&self) -> Option<&dyn Any> {
+        Self::extension(self).map(|ext| ext as &dyn Any)
+    }
+
+    fn retain_raw(&mut self, xml: String) {
+        Self::retain_raw(self, xml);
+    }
+
+    fn set_scalar_warnings(&mut self, warnings: Vec<ScalarWarning>) {
+        self.warnings = (!warnings.is_empty()).then_some(ScalarWarnings(warnings));
+    }
+
+    fn into_status(self) -> ResponseStatus {
+        Self::into_status(self)
+    }
+}
+
+#[cfg(feature = "client")]
+impl Reply for RawResponse {
+    fn parse(xml: &str) -> Result<Self, EppError> {
+        // Repair the frame here, as borrowing parsers can't
+        let xml = crate::xml::normalize_fee023_empty_prefix(xml).into_owned();
+        let status = crate::xml::deserialize(&xml)?;
+        Ok(Self { xml, status })
+    }
+
+    fn results(&self) -> &EppResults {
+        &self.status.result
+    }
+
+    fn tr_ids(&self) -> &ResponseTRID {
+        &self.status.tr_ids
+    }
+
+    fn message_id(&self) -> Option<String> {
+        self.message_queue().map(|queue| queue.id)
+    }
+
+    fn extension(&self) -> Option<&dyn Any> {
+        None
+    }
+
+    fn retain_raw(&mut self, xml: String) {
+        self.status.raw = Some(RawXml(xml));
+    }
+
+    fn set_scalar_warnings(&mut self, warnings: Vec<ScalarWarning>) {
+        self.status.warnings = (!warnings.is_empty()).then_some(ScalarWarnings(warnings));
+    }
+
+    fn into_status(self) -> ResponseStatus {
+        self.status
+    }
+}
+
+fn scalar_warnings(warnings: &Option<ScalarWarnings>) -> &[ScalarWarning] {
+    match warnings {
+        Some(warnings) => &warnings.0,
+        None => &[],
+    }
+}
+
+/// Transaction metadata common to all EPP responses
+///
+/// Collects the transaction IDs, result codes and server date in one place, so they can be
+/// logged the same way regardless of the command that was sent.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ResponseMeta<'a> {
+    /// The client transaction ID, if the server echoed one
+    pub client_tr_id: Option<&'a str>,
+    /// The server transaction ID
+    pub server_tr_id: &'a str,
+    /// The result codes, starting with the primary result
+    pub codes: Vec<ResultCode>,
+    /// The server date, if the response contained one
+    pub server_date: Option<DateTime<Utc>>,
+}
+
+impl<'a> ResponseMeta<'a> {
+    fn new(
+        results: &EppResults,
+        tr_ids: &'a ResponseTRID,
+        server_date: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            client_tr_id: tr_ids.client_tr_id.as_deref(),
+            server_tr_id: &tr_ids.server_tr_id,
+            codes: results.iter().map(|result| result.code).collect(),
+            server_date,
+        }
+    }
+
+    /// The code of the primary result
+    pub fn code(&self) -> ResultCode {
+        self.codes[0]
+    }
 }
 
 impl<T, E> Response<T, E> {
@@ -312,6 +736,27 @@ impl<T, E> Response<T, E> {
         }
     }
 
+    /// Returns the data under `<resData>`, or an error if the response did not contain any
+    ///
+    /// Use this for commands that are expected to return `<resData>`, to tell a legitimately empty
+    /// response apart from one that is missing data.
+    pub fn data(&self) -> Result<&T, EppError> {
+        self.res_data().ok_or(EppError::MissingResData)
+    }
+
+    /// Consumes the response, returning the data under `<resData>` if present
+    pub fn into_data(self) -> Result<T, EppError> {
+        match self.res_data {
+            Some(res_data) => Ok(res_data.data),
+            None => Err(EppError::MissingResData),
+        }
+    }
+
+    /// Returns the transaction metadata for this response
+    pub fn meta(&self) -> ResponseMeta<'_> {
+        ResponseMeta::new(&self.result, &self.tr_ids, self.server_date)
+    }
+
     pub fn extension(&self) -> Option<&E> {
         match &self.extension {
             Some(extension) => Some(&extension.data),
@@ -319,6 +764,42 @@ impl<T, E> Response<T, E> {
         }
     }
 
+    /// Returns the response frame exactly as received from the server
+    ///
+    /// Only available if the client retained it, see [`EppClient::set_retain_raw()`] and
+    /// [`EppClient::transact_retaining_raw()`].
+    ///
+    /// [`EppClient::set_retain_raw()`]: crate::EppClient::set_retain_raw
+    /// [`EppClient::transact_retaining_raw()`]: crate::EppClient::transact_retaining_raw
+    pub fn raw_xml(&self) -> Option<&str> {
+        self.raw.as_ref().map(|raw| raw.0.as_str())
+    }
+
+    /// The values only accepted because of lenient parse options
+    ///
+    /// Empty unless the client's [`Quirks`](crate::quirks::Quirks) set
+    /// [`ParseOptions`](crate::xml::ParseOptions) that let a registry's deviations through,
+    /// like `avail="TRUE"`.
+    pub fn scalar_warnings(&self) -> &[ScalarWarning] {
+        scalar_warnings(&self.warnings)
+    }
+
+    #[cfg(feature = "client")]
+    pub(crate) fn retain_raw(&mut self, xml: String) {
+        self.raw = Some(RawXml(xml));
+    }
+
+    /// The status of the response, without its data
+    pub(crate) fn into_status(self) -> ResponseStatus {
+        ResponseStatus {
+            result: self.result,
+            tr_ids: self.tr_ids,
+            server_date: self.server_date,
+            raw: self.raw,
+            warnings: self.warnings,
+        }
+    }
+
     /// Returns the data under the corresponding `<msgQ>` from the EPP XML
     pub fn message_queue(&self) -> Option<&MessageQueue> {
         match &self.message_queue {
@@ -336,9 +817,13 @@ pub struct Extension<E> {
 
 #[cfg(test)]
 mod tests {
+    use chrono::{TimeZone, Utc};
+
     use super::{ResponseStatus, ResultCode};
-    use crate::tests::{get_xml, CLTRID, SVTRID};
-    use crate::xml;
+    use crate::domain::{DomainCheck, DomainDelete};
+    use crate::logout::Logout;
+    use crate::tests::{get_xml, response_from_file, CLTRID, SVTRID};
+    use crate::{xml, Error};
 
     #[test]
     fn error() {
@@ -348,10 +833,67 @@ mod tests {
         assert_eq!(object.result.code, ResultCode::ObjectDoesNotExist);
         assert_eq!(object.result.message, "Object does not exist");
         assert_eq!(
-            object.result.ext_value.unwrap().reason,
+            object.result.ext_value.as_ref().unwrap().reason,
             "545 Object not found"
         );
+        assert_eq!(
+            object.result.to_string(),
+            "2303 Object does not exist (545 Object not found)"
+        );
+        assert_eq!(object.result.len(), 1);
         assert_eq!(object.tr_ids.client_tr_id.unwrap(), CLTRID);
         assert_eq!(object.tr_ids.server_tr_id, SVTRID);
     }
+
+    #[test]
+    fn missing_res_data() {
+        let object = response_from_file::<DomainDelete>("response/domain/delete.xml");
+        assert_eq!(object.res_data(), None);
+
+        let object = response_from_file::<DomainCheck>("response/domain/delete.xml");
+        assert!(matches!(object.data(), Err(Error::MissingResData)));
+        assert!(matches!(object.into_data(), Err(Error::MissingResData)));
+    }
+
+    #[test]
+    fn multiple_results() {
+        let xml = get_xml("response/multiple_results.xml").unwrap();
+        let object = xml::deserialize::<ResponseStatus>(xml.as_str()).unwrap();
+
+        assert_eq!(object.result.len(), 2);
+        assert_eq!(object.result.code, ResultCode::ParameterValuePolicyError);
+        assert_eq!(
+            object.result.additional()[0].code,
+            ResultCode::ObjectStatusProhibitsOperation
+        );
+        assert_eq!(
+            object.result.iter().map(|r| r.code).collect::<Vec<_>>(),
+            [
+                ResultCode::ParameterValuePolicyError,
+                ResultCode::ObjectStatusProhibitsOperation
+            ]
+        );
+        assert_eq!(object.tr_ids.server_tr_id, SVTRID);
+
+        let meta = object.meta();
+        assert_eq!(meta.code(), ResultCode::ParameterValuePolicyError);
+        assert_eq!(meta.codes.len(), 2);
+        assert_eq!(meta.client_tr_id, Some(CLTRID));
+        assert_eq!(meta.server_tr_id, SVTRID);
+        assert_eq!(meta.server_date, None);
+    }
+
+    #[test]
+    fn server_date() {
+        let object = response_from_file::<Logout>("response/server_date.xml");
+
+        let meta = object.meta();
+        assert_eq!(meta.codes, [ResultCode::CommandCompletedSuccessfully]);
+        assert_eq!(meta.client_tr_id, Some(CLTRID));
+        assert_eq!(meta.server_tr_id, SVTRID);
+        assert_eq!(
+            meta.server_date,
+            Some(Utc.with_ymd_and_hms(2021, 7, 25, 14, 51, 17).unwrap())
+        );
+    }
 }
@@ -0,0 +1,254 @@
+//! Retry hints in registry error results
+//!
+//! During maintenance, some registries reject commands with `2308` (data management policy
+//! violation), `2400` or `2502` and say in the `<reason>` when to come back: `retry-after=300`,
+//! `Retry after 2025-03-01T06:00:00Z`, or a maintenance window `until` a time, sometimes
+//! with the identifier of the maintenance item. [`RetryHint`] collects these, so a scheduler
+//! can back off until the registry is available rather than retrying right away.
+//!
+//! Get the hint of a failed command with [`Error::retry_hint()`](crate::Error::retry_hint).
+
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::response::{EppResult, ResultCode};
+
+/// When the registry suggests sending a command again
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct RetryHint {
+    /// How long to wait, for a hint like `retry-after=300` or `retry after PT5M`
+    pub after: Option<Duration>,
+    /// When to retry, for a hint like `retry after 2025-03-01T06:00:00Z` or `until ...`
+    pub at: Option<DateTime<Utc>>,
+    /// The identifier of the maintenance item the registry referred to
+    ///
+    /// Details are available from the maintenance extension, see
+    /// [`maintenance`](crate::extensions::maintenance).
+    pub maintenance: Option<String>,
+}
+
+impl RetryHint {
+    /// The hint in the message and reason of `result`, if it has one
+    ///
+    /// Only results with the codes registries use for maintenance are considered: `2308`,
+    /// `2400` and `2502`.
+    pub fn from_result(result: &EppResult) -> Option<Self> {
+        if !matches!(
+            result.code,
+            ResultCode::DataManagementPolicyViolation
+                | ResultCode::CommandFailed
+                | ResultCode::SessionLimitExceededServerClosingConnection
+        ) {
+            return None;
+        }
+
+        let reason = result.ext_value.as_ref().map(|ext| ext.reason.as_str());
+        let hint = [Some(result.message.as_str()), reason]
+            .into_iter()
+            .flatten()
+            .map(Self::parse)
+            .fold(Self::default(), |hint, other| Self {
+                after: hint.after.or(other.after),
+                at: hint.at.or(other.at),
+                maintenance: hint.maintenance.or(other.maintenance),
+            });
+
+        (hint != Self::default()).then_some(hint)
+    }
+
+    /// The earliest time to retry at, given the response was received at `now`
+    ///
+    /// `None` if the hint only names a maintenance item, or waits longer than a date can represent.
+    pub fn retry_at(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let after = self
+            .after
+            .and_then(|after| chrono::Duration::from_std(after).ok())
+            .and_then(|after| now.checked_add_signed(after));
+        after.max(self.at)
+    }
+
+    // Registries word hints differently, so this looks for a few keywords and the value after
+    fn parse(text: &str) -> Self {
+        let mut hint = Self::default();
+        let retry_after = ["retry-after", "retry_after", "retry after", "retryafter"]
+            .into_iter()
+            .find_map(|key| value_after(text, key));
+        if let Some((value, rest)) = retry_after {
+            match DateTime::parse_from_rfc3339(value) {
+                Ok(at) => hint.at = Some(at.with_timezone(&Utc)),
+                Err(_) => hint.after = parse_duration(value, rest),
+            }
+        }
+
+        if hint.at.is_none() {
+            hint.at = value_after(text, "until")
+                .and_then(|(value, _)| DateTime::parse_from_rfc3339(value).ok())
+                .map(|at| at.with_timezone(&Utc));
+        }
+
+        hint.maintenance = [
+            "maintenance-id",
+            "maintenance_id",
+            "maintenance id",
+            "maintenance",
+        ]
+        .into_iter()
+        .find_map(|key| assigned(text, key))
+        .map(str::to_owned);
+        hint
+    }
+}
+
+// The token following `key` and an optional `=` or `:` separator, and the text after it
+fn value_after<'a>(text: &'a str, key: &str) -> Option<(&'a str, &'a str)> {
+    // ASCII lowercasing keeps byte offsets intact
+    let start = text.to_ascii_lowercase().find(key)? + key.len();
+    let rest = text[start..].trim_start();
+    let rest = rest.strip_prefix(['=', ':']).unwrap_or(rest);
+    token(rest.trim_start())
+}
+
+// The token assigned to `key` with `=` or `:`, rather than the next word of a sentence
+fn assigned<'a>(text: &'a str, key: &str) -> Option<&'a str> {
+    let lower = text.to_ascii_lowercase();
+    lower.match_indices(key).find_map(|(start, _)| {
+        let rest = text[start + key.len()..].trim_start();
+        let rest = rest.strip_prefix(['=', ':'])?;
+        token(rest.trim_start()).map(|(token, _)| token)
+    })
+}
+
+fn token(text: &str) -> Option<(&str, &str)> {
+    let end = text
+        .find(|c: char| c.is_whitespace() || matches!(c, ',' | ';' | ')' | '"' | '\''))
+        .unwrap_or(text.len());
+    let token = text[..end].trim_end_matches('.');
+    (!token.is_empty()).then_some((token, &text[end..]))
+}
+
+// Seconds (optionally followed by a unit), or an ISO 8601 duration like `PT5M`
+fn parse_duration(value: &str, rest: &str) -> Option<Duration> {
+    if let Some(iso) = value.strip_prefix(['P', 'p']) {
+        return parse_iso_duration(iso);
+    }
+
+    let digits = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let amount = value[..digits].parse::<u64>().ok()?;
+    let unit = match &value[digits..] {
+        // The unit may be the next word, as in `retry after 5 minutes`
+        "" => token(rest.trim_start()).map_or("", |(unit, _)| unit),
+        unit => unit,
+    };
+
+    let seconds = match unit.to_ascii_lowercase().as_str() {
+        "m" | "min" | "mins" | "minute" | "minutes" => amount.checked_mul(60)?,
+        "h" | "hour" | "hours" => amount.checked_mul(3600)?,
+        _ => amount,
+    };
+    Some(Duration::from_secs(seconds))
+}
+
+// The part of an ISO 8601 duration after the `P`, limited to days, hours, minutes and seconds
+fn parse_iso_duration(value: &str) -> Option<Duration> {
+    let mut seconds = 0u64;
+    let mut time = false;
+    let mut amount = String::new();
+    for c in value.chars() {
+        let unit = match c.to_ascii_uppercase() {
+            'T' => {
+                time = true;
+                continue;
+            }
+            c if c.is_ascii_digit() => {
+                amount.push(c);
+                continue;
+            }
+            'D' if !time => 86_400,
+            'H' if time => 3600,
+            'M' if time => 60,
+            'S' if time => 1,
+            _ => return None,
+        };
+
+        seconds = amount
+            .parse::<u64>()
+            .ok()?
+            .checked_mul(unit)
+            .and_then(|amount| seconds.checked_add(amount))?;
+        amount.clear();
+    }
+
+    amount.is_empty().then(|| Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::RetryHint;
+    use crate::response::{EppResult, ExtValue, ResultCode, ResultValue};
+    use crate::tests::get_xml;
+    use crate::xml;
+    use crate::Error;
+
+    #[test]
+    fn hints() {
+        let hint = RetryHint::parse;
+        assert_eq!(
+            hint("retry-after=300").after,
+            Some(Duration::from_secs(300))
+        );
+        assert_eq!(
+            hint("Please retry after 5 minutes.").after,
+            Some(Duration::from_secs(300))
+        );
+        assert_eq!(
+            hint("Retry-After: PT1H30M").after,
+            Some(Duration::from_secs(5400))
+        );
+        let at = Utc.with_ymd_and_hms(2025, 3, 1, 6, 0, 0).unwrap();
+        assert_eq!(hint("Retry after 2025-03-01T06:00:00Z").at, Some(at));
+        assert_eq!(
+            hint("Read-only maintenance until 2025-03-01T06:00:00Z.").at,
+            Some(at)
+        );
+        assert_eq!(
+            hint("maintenance-id: 2e6df9b0-4092-4491-bcc8-9fb2166dcee6").maintenance,
+            Some("2e6df9b0-4092-4491-bcc8-9fb2166dcee6".into())
+        );
+        assert_eq!(hint("Registry in maintenance mode"), RetryHint::default());
+        assert_eq!(hint("retry after 18446744073709551615 minutes").after, None);
+        assert_eq!(hint("retry-after=99999999999999999h").after, None);
+        assert_eq!(hint("retry-after=P999999999999999D").after, None);
+        let far = hint("retry-after=10000000000000");
+        assert_eq!(far.after, Some(Duration::from_secs(10_000_000_000_000)));
+        assert_eq!(far.retry_at(Utc::now()), None);
+
+        let result = EppResult {
+            code: ResultCode::DataManagementPolicyViolation,
+            message: "Data management policy violation".into(),
+            ext_value: Some(ExtValue {
+                value: ResultValue,
+                reason: "Registry maintenance (maintenance=MW-42), retry-after=600".into(),
+            }),
+        };
+        let hint = RetryHint::from_result(&result).unwrap();
+        assert_eq!(hint.maintenance.as_deref(), Some("MW-42"));
+        assert_eq!(hint.retry_at(at), Some(at + chrono::Duration::minutes(10)));
+
+        let result = EppResult {
+            code: ResultCode::ObjectDoesNotExist,
+            ..result
+        };
+        assert_eq!(RetryHint::from_result(&result), None);
+
+        let xml = get_xml("response/error.xml").unwrap();
+        let status = xml::deserialize::<crate::response::ResponseStatus>(&xml).unwrap();
+        assert_eq!(Error::Command(Box::new(status)).retry_hint(), None);
+    }
+}
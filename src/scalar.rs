@@ -0,0 +1,248 @@
+use std::borrow::Cow;
+use std::fmt::Display;
+use std::str::FromStr;
+
+use instant_xml::{de::Deserializer, Error, OptionAccumulator};
+
+use crate::xml::{self, ParseOptions, ScalarWarning, Token};
+
+/// Parse a boolean element from a registry response
+///
+/// Accepts `true`, `false`, `1` and `0`, as XML Schema does. With
+/// [`ParseOptions::lenient_booleans`], these are accepted in any case and with surrounding
+/// whitespace, as are `Y`, `N`, `yes` and `no`.
+pub(crate) fn deserialize_bool<'xml>(
+    into: &mut Option<bool>,
+    field: &'static str,
+    deserializer: &mut Deserializer<'_, 'xml>,
+) -> Result<(), Error> {
+    if into.is_some() {
+        return Err(Error::DuplicateValue(field));
+    }
+
+    let value = match deserializer.take_str()? {
+        Some(value) => value,
+        None => {
+            deserializer.ignore()?;
+            return Ok(());
+        }
+    };
+
+    let parsed = parse_bool(field, &value).ok_or_else(|| {
+        Error::UnexpectedValue(format!("unable to parse bool from '{value}' for {field}"))
+    })?;
+
+    *into = Some(parsed);
+    deserializer.ignore()?;
+    Ok(())
+}
+
+/// Parse a number element from a registry response
+///
+/// With [`ParseOptions::lenient_numbers`], the number may be surrounded by whitespace.
+pub(crate) fn deserialize_number<'xml, T: Display + FromStr>(
+    into: &mut Option<T>,
+    field: &'static str,
+    deserializer: &mut Deserializer<'_, 'xml>,
+) -> Result<(), Error> {
+    if into.is_some() {
+        return Err(Error::DuplicateValue(field));
+    }
+
+    let value = match deserializer.take_str()? {
+        Some(value) => value,
+        None => {
+            deserializer.ignore()?;
+            return Ok(());
+        }
+    };
+
+    let parsed = parse_number(field, &value).ok_or_else(|| {
+        Error::UnexpectedValue(format!("unable to parse number from '{value}' for {field}"))
+    })?;
+
+    *into = Some(parsed);
+    deserializer.ignore()?;
+    Ok(())
+}
+
+pub(crate) fn deserialize_number_option<'xml, T: Display + FromStr>(
+    into: &mut OptionAccumulator<T, Option<T>>,
+    field: &'static str,
+    deserializer: &mut Deserializer<'_, 'xml>,
+) -> Result<(), Error> {
+    deserialize_number(into.get_mut(), field, deserializer)
+}
+
+// instant-xml can't call a `deserialize_with` function for attributes, so the attributes
+// known to hold booleans or numbers are normalized before parsing instead. Other attributes
+// and element contents are left as sent.
+const BOOLEAN_ATTRIBUTES: [&str; 3] = ["avail", "refundable", "standard"];
+const NUMBER_ATTRIBUTES: [&str; 1] = ["count"];
+
+/// Rewrite the values of known boolean and number attributes in `xml` that are only accepted
+/// because of the parse options in effect
+pub(crate) fn normalize_attributes(xml: &str) -> Cow<'_, str> {
+    let options = xml::parse_options();
+    if !options.lenient_booleans && !options.lenient_numbers {
+        return Cow::Borrowed(xml);
+    }
+
+    let mut out = String::new();
+    let (mut pos, mut written) = (0, 0);
+    for token in xml::tokens(xml) {
+        let tag_offset = pos;
+        pos += token.raw().len();
+        let Token::Start(tag) = token else {
+            continue;
+        };
+
+        for attr in tag.attributes() {
+            let name = attr.name.rsplit(':').next().unwrap_or(attr.name);
+            let normalized = if let Some(field) = BOOLEAN_ATTRIBUTES.iter().find(|n| **n == name) {
+                match attr.value {
+                    "true" | "false" | "1" | "0" => None,
+                    value => parse_bool(field, value).map(|value| value.to_string()),
+                }
+            } else if let Some(field) = NUMBER_ATTRIBUTES.iter().find(|n| **n == name) {
+                match attr.value.parse::<u64>() {
+                    Ok(_) => None,
+                    Err(_) => parse_number::<u64>(field, attr.value).map(|value| value.to_string()),
+                }
+            } else {
+                None
+            };
+
+            if let Some(normalized) = normalized {
+                let offset = tag_offset + attr.offset;
+                out.push_str(&xml[written..offset]);
+                out.push_str(&normalized);
+                written = offset + attr.value.len();
+            }
+        }
+    }
+
+    match written {
+        0 => Cow::Borrowed(xml),
+        _ => {
+            out.push_str(&xml[written..]);
+            Cow::Owned(out)
+        }
+    }
+}
+
+// Parse `value` as a boolean, leniently if the options in effect allow it
+fn parse_bool(field: &'static str, value: &str) -> Option<bool> {
+    match value {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => lenient(field, value, |options| {
+            if !options.lenient_booleans {
+                return None;
+            }
+
+            match value.trim().to_ascii_lowercase().as_str() {
+                "true" | "1" | "y" | "yes" => Some(true),
+                "false" | "0" | "n" | "no" => Some(false),
+                _ => None,
+            }
+        }),
+    }
+}
+
+/// Parse `value` as a number, leniently if the options in effect allow it
+pub(crate) fn parse_number<T: Display + FromStr>(field: &'static str, value: &str) -> Option<T> {
+    match value.parse() {
+        Ok(parsed) => Some(parsed),
+        Err(_) => lenient(field, value, |options| match options.lenient_numbers {
+            true => value.trim().parse().ok(),
+            false => None,
+        }),
+    }
+}
+
+// Parse `original` with `parse` if the options in effect allow it, recording a warning
+fn lenient<T: Display>(
+    field: &'static str,
+    original: &str,
+    parse: impl FnOnce(ParseOptions) -> Option<T>,
+) -> Option<T> {
+    let parsed = parse(xml::parse_options())?;
+    xml::record_scalar_warning(ScalarWarning {
+        field,
+        original: original.to_owned(),
+        normalized: parsed.to_string(),
+    });
+    Some(parsed)
+}
+
+#[cfg(test)]
+mod tests {
+    use instant_xml::{from_str, FromXml};
+
+    use super::normalize_attributes;
+    use crate::xml::{with_parse_options, ParseOptions, ScalarWarning};
+
+    const LENIENT: ParseOptions = ParseOptions {
+        lenient_booleans: true,
+        lenient_numbers: true,
+    };
+
+    #[derive(Debug, FromXml, PartialEq)]
+    #[xml(rename = "test")]
+    struct Test {
+        #[xml(deserialize_with = "crate::scalar::deserialize_bool")]
+        flag: bool,
+        #[xml(deserialize_with = "crate::scalar::deserialize_number")]
+        count: u32,
+    }
+
+    #[test]
+    fn elements() {
+        let xml = "<test><flag>Yes</flag><count> 5 </count></test>";
+        assert!(from_str::<Test>(xml).is_err());
+
+        let (test, warnings) = with_parse_options(LENIENT, || from_str::<Test>(xml));
+        assert_eq!(
+            test.unwrap(),
+            Test {
+                flag: true,
+                count: 5
+            }
+        );
+        assert_eq!(
+            warnings,
+            [
+                ScalarWarning {
+                    field: "Test::flag",
+                    original: "Yes".into(),
+                    normalized: "true".into(),
+                },
+                ScalarWarning {
+                    field: "Test::count",
+                    original: " 5 ".into(),
+                    normalized: "5".into(),
+                },
+            ]
+        );
+
+        let strict = ParseOptions::default();
+        let (test, warnings) = with_parse_options(strict, || from_str::<Test>(xml));
+        assert!(test.is_err());
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn attributes() {
+        // Only the attributes known to hold booleans or numbers are rewritten
+        let xml = r#"<epp><msgQ count=" 5" id=" 12"/><cd><name avail="no">example.no</name><cc>NO</cc></cd><fee refundable="Y"> 5.00 </fee><pc> 12345 </pc><flag value="no"/></epp>"#;
+        let (normalized, warnings) = with_parse_options(LENIENT, || normalize_attributes(xml));
+        assert_eq!(
+            normalized,
+            r#"<epp><msgQ count="5" id=" 12"/><cd><name avail="false">example.no</name><cc>NO</cc></cd><fee refundable="true"> 5.00 </fee><pc> 12345 </pc><flag value="no"/></epp>"#
+        );
+        assert_eq!(warnings.len(), 3);
+
+        assert_eq!(normalize_attributes(xml), xml);
+    }
+}
@@ -0,0 +1,114 @@
+//! [`tower::Service`] integration
+//!
+//! [`EppService`] shares an [`EppClient`] between callers and implements [`Service`] for
+//! [`EppRequest`], so EPP commands can be wrapped in the same timeout, retry, rate limiting
+//! and metrics middleware as other RPCs. Commands are still sent one at a time over the
+//! underlying session; concurrent calls wait for their turn.
+//!
+//! [`tower::Service`]: tower_service::Service
+
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use tokio::sync::Mutex;
+use tower_service::Service;
+
+use crate::client::{Connector, EppClient, RequestData};
+use crate::common::NoExtension;
+use crate::request::{Command, Extension, Transaction};
+use crate::response::Response;
+use crate::Error;
+
+/// A command to send through an [`EppService`]
+#[derive(Clone, Debug)]
+pub struct EppRequest<Cmd, Ext = NoExtension> {
+    /// The command to send
+    pub command: Cmd,
+    /// The extension to send along with the command, if any
+    pub extension: Option<Ext>,
+    /// The client transaction ID
+    pub id: String,
+}
+
+impl<Cmd> EppRequest<Cmd> {
+    pub fn new(command: Cmd, id: impl Into<String>) -> Self {
+        Self {
+            command,
+            extension: None,
+            id: id.into(),
+        }
+    }
+}
+
+impl<Cmd, Ext> EppRequest<Cmd, Ext> {
+    pub fn with_extension(command: Cmd, extension: Ext, id: impl Into<String>) -> Self {
+        Self {
+            command,
+            extension: Some(extension),
+            id: id.into(),
+        }
+    }
+}
+
+/// A [`Service`] sending [`EppRequest`]s over a shared [`EppClient`]
+///
+/// Cloning the service is cheap; all clones use the same client.
+pub struct EppService<C: Connector> {
+    client: Arc<Mutex<EppClient<C>>>,
+}
+
+impl<C: Connector> EppService<C> {
+    pub fn new(client: EppClient<C>) -> Self {
+        Self {
+            client: Arc::new(Mutex::new(client)),
+        }
+    }
+
+    /// The client shared by this service, for commands sent outside of the service stack
+    pub fn client(&self) -> &Arc<Mutex<EppClient<C>>> {
+        &self.client
+    }
+}
+
+impl<C: Connector> Clone for EppService<C> {
+    fn clone(&self) -> Self {
+        Self {
+            client: self.client.clone(),
+        }
+    }
+}
+
+impl<C, Cmd, Ext> Service<EppRequest<Cmd, Ext>> for EppService<C>
+where
+    C: Connector + Send + Sync + 'static,
+    C::Connection: Send,
+    Cmd: Transaction<Ext> + Command + Send + Sync + 'static,
+    Cmd::Response: Send,
+    Ext: Extension + Send + Sync + 'static,
+    Ext::Response: Send,
+{
+    type Response = Response<Cmd::Response, Ext::Response>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // Requests queue on the client lock instead of applying backpressure here
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: EppRequest<Cmd, Ext>) -> Self::Future {
+        let client = self.client.clone();
+        Box::pin(async move {
+            let mut client = client.lock().await;
+            let data = RequestData {
+                command: &request.command,
+                extension: request.extension.as_ref(),
+                replacement: None,
+            };
+
+            client.transact(data, &request.id).await
+        })
+    }
+}
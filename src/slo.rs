@@ -0,0 +1,326 @@
+//! Tracking latency and error rate objectives for a registry
+//!
+//! A registry that degrades usually gets slow before it fails outright. An [`SloMonitor`] is a
+//! [`Middleware`] keeping the latency and outcome of the most recent commands, and calls a
+//! callback when a latency percentile or the error rate crosses its threshold, so that
+//! traffic can be shifted to another endpoint or an alert raised. Use one monitor per registry
+//! endpoint, shared between the clients connected to it.
+
+use std::any::Any;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::middleware::{Middleware, Request};
+use crate::response::ResultCode;
+use crate::Error;
+
+type OnBreach = Box<dyn Fn(&SloBreach) + Send + Sync>;
+
+/// A [`Middleware`] evaluating the recent commands against service level objectives
+///
+/// Only failures pointing at the registry count as errors: I/O errors, timeouts, closed
+/// sessions and the 2400 and 25xx result codes. A command the registry rejects for its
+/// contents, like a create for a domain that exists, counts as a success.
+pub struct SloMonitor {
+    window: usize,
+    min_samples: usize,
+    latency: Vec<(f64, Duration)>,
+    error_rate: Option<f64>,
+    on_breach: Option<OnBreach>,
+    inner: Mutex<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    // Send times of the commands in flight, by request key
+    started: HashMap<u64, Instant>,
+    samples: VecDeque<Sample>,
+    // The objectives currently breached, so the callback is only called on a change
+    breached: Vec<Objective>,
+}
+
+#[derive(Clone, Copy)]
+struct Sample {
+    latency: Duration,
+    failed: bool,
+}
+
+impl SloMonitor {
+    /// Evaluate the objectives over the last `window` commands
+    pub fn new(window: usize) -> Self {
+        let window = window.max(1);
+        Self {
+            window,
+            min_samples: window.min(10),
+            latency: Vec::new(),
+            error_rate: None,
+            on_breach: None,
+            inner: Mutex::default(),
+        }
+    }
+
+    /// Require the `percentile` latency (like `0.99`) to stay at or below `max`
+    pub fn latency(mut self, percentile: f64, max: Duration) -> Self {
+        self.latency.push((percentile.clamp(0.0, 1.0), max));
+        self
+    }
+
+    /// Require the fraction of failed commands to stay at or below `max`
+    pub fn error_rate(mut self, max: f64) -> Self {
+        self.error_rate = Some(max);
+        self
+    }
+
+    /// Only evaluate the objectives once `min_samples` commands were seen (default 10)
+    pub fn min_samples(mut self, min_samples: usize) -> Self {
+        self.min_samples = min_samples.clamp(1, self.window);
+        self
+    }
+
+    /// Call `on_breach` when an objective starts being breached
+    ///
+    /// The callback is called once per objective when it is first breached, and again only
+    /// after it was met in between. It runs inline after a response, so it should not block.
+    pub fn on_slo_breach(mut self, on_breach: impl Fn(&SloBreach) + Send + Sync + 'static) -> Self {
+        self.on_breach = Some(Box::new(on_breach));
+        self
+    }
+
+    /// Statistics over the commands in the current window
+    pub fn stats(&self) -> SloStats {
+        let inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        SloStats::new(&inner.samples)
+    }
+
+    /// The objectives breached as of the last command
+    pub fn breached(&self) -> Vec<Objective> {
+        let inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        inner.breached.clone()
+    }
+
+    fn record(&self, latency: Duration, failed: bool) {
+        let breaches = {
+            let mut inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+            if inner.samples.len() == self.window {
+                inner.samples.pop_front();
+            }
+            inner.samples.push_back(Sample { latency, failed });
+            if inner.samples.len() < self.min_samples {
+                return;
+            }
+
+            let stats = SloStats::new(&inner.samples);
+            let mut breaches = Vec::new();
+            for &(percentile, max) in &self.latency {
+                let observed = stats.percentile(percentile);
+                if observed > max {
+                    breaches.push(SloBreach {
+                        objective: Objective::Latency { percentile, max },
+                        observed: Observed::Latency(observed),
+                        stats: stats.clone(),
+                    });
+                }
+            }
+            if let Some(max) = self.error_rate {
+                if stats.error_rate > max {
+                    breaches.push(SloBreach {
+                        objective: Objective::ErrorRate(max),
+                        observed: Observed::ErrorRate(stats.error_rate),
+                        stats: stats.clone(),
+                    });
+                }
+            }
+
+            let previous = std::mem::replace(
+                &mut inner.breached,
+                breaches.iter().map(|breach| breach.objective).collect(),
+            );
+            breaches.retain(|breach| !previous.contains(&breach.objective));
+            breaches
+        };
+
+        if let Some(on_breach) = &self.on_breach {
+            for breach in &breaches {
+                on_breach(breach);
+            }
+        }
+    }
+}
+
+impl Middleware for SloMonitor {
+    fn request(&self, request: &mut Request<'_>) -> Result<(), Error> {
+        let mut inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+        inner.started.insert(request.key(), Instant::now());
+        Ok(())
+    }
+
+    fn response(&self, request: &Request<'_>, response: Result<&dyn Any, &Error>) {
+        let started = {
+            let mut inner = self.inner.lock().unwrap_or_else(|err| err.into_inner());
+            inner.started.remove(&request.key())
+        };
+
+        let failed = match response {
+            Ok(_) => false,
+            Err(Error::Io(_) | Error::Timeout | Error::Closed(_)) => true,
+            Err(Error::Command(status)) => matches!(
+                status.result.code,
+                ResultCode::CommandFailed
+                    | ResultCode::CommandFailedServerClosingConnection
+                    | ResultCode::AuthenticationErrorServerClosingConnection
+                    | ResultCode::SessionLimitExceededServerClosingConnection
+            ),
+            // Not sent, or not the registry's fault
+            Err(_) => return,
+        };
+
+        if let Some(started) = started {
+            self.record(started.elapsed(), failed);
+        }
+    }
+}
+
+/// Latency and error statistics over the commands in an [`SloMonitor`]'s window
+#[derive(Clone, Debug, PartialEq)]
+pub struct SloStats {
+    pub samples: usize,
+    /// The fraction of commands that failed, from 0 to 1
+    pub error_rate: f64,
+    // Sorted latencies, for percentiles
+    latencies: Vec<Duration>,
+}
+
+impl SloStats {
+    fn new(samples: &VecDeque<Sample>) -> Self {
+        let mut latencies = samples
+            .iter()
+            .map(|sample| sample.latency)
+            .collect::<Vec<_>>();
+        latencies.sort_unstable();
+        let failed = samples.iter().filter(|sample| sample.failed).count();
+        Self {
+            samples: samples.len(),
+            error_rate: match samples.len() {
+                0 => 0.0,
+                n => failed as f64 / n as f64,
+            },
+            latencies,
+        }
+    }
+
+    /// The latency below which the fraction `percentile` of commands completed
+    ///
+    /// Uses the nearest-rank method; zero if no commands were seen.
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        if self.latencies.is_empty() {
+            return Duration::ZERO;
+        }
+
+        let rank = (percentile.clamp(0.0, 1.0) * self.latencies.len() as f64).ceil() as usize;
+        self.latencies[rank.clamp(1, self.latencies.len()) - 1]
+    }
+}
+
+/// An objective tracked by an [`SloMonitor`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Objective {
+    /// The `percentile` latency stays at or below `max`
+    Latency { percentile: f64, max: Duration },
+    /// The error rate stays at or below this fraction
+    ErrorRate(f64),
+}
+
+/// The value that breached an [`Objective`]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Observed {
+    Latency(Duration),
+    ErrorRate(f64),
+}
+
+/// Passed to the [`SloMonitor::on_slo_breach()`] callback
+#[derive(Clone, Debug, PartialEq)]
+pub struct SloBreach {
+    pub objective: Objective,
+    pub observed: Observed,
+    /// The statistics the objective was evaluated on
+    pub stats: SloStats,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+
+    use super::{Objective, Observed, SloMonitor};
+    use crate::domain::DomainInfo;
+    use crate::middleware::{CommandView, Middleware, Request};
+
+    #[test]
+    fn breaches() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let monitor = SloMonitor::new(4)
+            .min_samples(2)
+            .latency(0.5, Duration::from_millis(100))
+            .error_rate(0.25)
+            .on_slo_breach({
+                let seen = seen.clone();
+                move |breach| seen.lock().unwrap().push(breach.observed)
+            });
+
+        let ms = Duration::from_millis;
+        monitor.record(ms(300), false);
+        assert!(seen.lock().unwrap().is_empty());
+
+        // The median of two samples is the lower one
+        monitor.record(ms(200), false);
+        assert_eq!(*seen.lock().unwrap(), [Observed::Latency(ms(200))]);
+
+        // Still breached, so not reported again
+        monitor.record(ms(40), false);
+        assert_eq!(seen.lock().unwrap().len(), 1);
+
+        monitor.record(ms(30), false);
+        assert!(monitor.breached().is_empty());
+
+        // 300ms drops out of the window
+        monitor.record(ms(500), true);
+        monitor.record(ms(600), true);
+        assert_eq!(monitor.stats().error_rate, 0.5);
+        assert_eq!(seen.lock().unwrap()[1], Observed::ErrorRate(0.5));
+
+        monitor.record(ms(700), false);
+        assert_eq!(monitor.stats().percentile(0.5), ms(500));
+        assert_eq!(seen.lock().unwrap().len(), 3);
+        assert_eq!(
+            monitor.breached(),
+            [
+                Objective::Latency {
+                    percentile: 0.5,
+                    max: ms(100)
+                },
+                Objective::ErrorRate(0.25)
+            ]
+        );
+    }
+
+    #[test]
+    fn concurrent_requests() {
+        let monitor = SloMonitor::new(4);
+        let info = DomainInfo::new("eppdev.com", None);
+
+        // Callers may send several commands with the same client transaction ID
+        let mut first = Request::new(CommandView::DomainInfo(&info), "info", "cltrid");
+        let mut second = Request::new(CommandView::DomainInfo(&info), "info", "cltrid");
+        monitor.request(&mut second).unwrap();
+        std::thread::sleep(Duration::from_millis(20));
+        monitor.request(&mut first).unwrap();
+
+        monitor.response(&first, Ok(&()));
+        monitor.response(&second, Ok(&()));
+        let stats = monitor.stats();
+        assert_eq!(stats.samples, 2);
+        assert!(stats.percentile(1.0) >= Duration::from_millis(20));
+        assert!(stats.percentile(0.5) < Duration::from_millis(20));
+    }
+}
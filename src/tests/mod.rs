@@ -6,11 +6,10 @@ use regex::Regex;
 use similar_asserts::assert_eq;
 
 use crate::{
-    client::RequestData,
     common::NoExtension,
-    request::{Command, CommandWrapper, Extension, Transaction},
+    request::{Command, CommandWrapper, Extension, RequestData, Transaction},
     response::Response,
-    xml,
+    xml::{self, SerializeOptions},
 };
 
 pub(crate) const RESOURCES_DIR: &str = "./tests/resources";
@@ -51,6 +50,22 @@ pub(crate) fn assert_serialized<'c, 'e, Cmd, Ext>(
     assert_eq!(expected, xml::serialize(document).unwrap());
 }
 
+/// Like [`assert_serialized()`], serializing with `options`
+pub(crate) fn assert_serialized_with<'c, 'e, Cmd, Ext>(
+    path: &str,
+    options: &SerializeOptions,
+    req: impl Into<RequestData<'c, 'e, Cmd, Ext>>,
+) where
+    Cmd: Transaction<Ext> + Command + 'c,
+    Ext: Extension + 'e,
+{
+    let expected = get_xml(path).unwrap();
+    let req = req.into();
+    let document = CommandWrapper::new(req.command, req.extension, CLTRID);
+    let xml = xml::with_options(Some(options), || xml::serialize(document)).unwrap();
+    assert_eq!(expected, xml);
+}
+
 pub(crate) fn response_from_file<'c, Cmd>(
     path: &str,
 ) -> Response<Cmd::Response, <NoExtension as Extension>::Response>
@@ -0,0 +1,43 @@
+//! Conversions between `chrono` and `time` timestamps
+//!
+//! All timestamps in this crate's API are [`chrono::DateTime<Utc>`]. With the `time` feature,
+//! these helpers convert them to and from [`time::OffsetDateTime`] at the boundary, for
+//! applications that use `time` everywhere else.
+
+use chrono::{DateTime, Utc};
+use time::OffsetDateTime;
+
+/// Convert a timestamp from a request or response to a [`time::OffsetDateTime`] in UTC
+///
+/// Returns `None` if the timestamp is outside of the range supported by `time` (the years
+/// -9999 to 9999 unless its `large-dates` feature is enabled).
+pub fn to_time(dt: DateTime<Utc>) -> Option<OffsetDateTime> {
+    let nanos =
+        i128::from(dt.timestamp()) * 1_000_000_000 + i128::from(dt.timestamp_subsec_nanos());
+    OffsetDateTime::from_unix_timestamp_nanos(nanos).ok()
+}
+
+/// Convert a [`time::OffsetDateTime`] to the timestamp type used in requests
+pub fn from_time(dt: OffsetDateTime) -> DateTime<Utc> {
+    // `time` supports a narrower range of years than `chrono`, so this can't fail
+    DateTime::from_timestamp(dt.unix_timestamp(), dt.nanosecond())
+        .expect("OffsetDateTime out of range for chrono")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use time::macros::datetime;
+
+    use super::{from_time, to_time};
+
+    #[test]
+    fn round_trip() {
+        let chrono = Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap();
+        let time = datetime!(2024-01-02 03:04:05 UTC);
+
+        assert_eq!(to_time(chrono), Some(time));
+        assert_eq!(from_time(time), chrono);
+        assert_eq!(from_time(datetime!(2024-01-02 04:04:05 +01:00)), chrono);
+    }
+}
@@ -0,0 +1,147 @@
+//! Registrant changes ("trades")
+//!
+//! Many registries treat a change of registrant as a separate, billable operation rather than
+//! a plain domain update: it may need a registry-specific extension, and it often completes
+//! asynchronously once the old and/or new registrant have approved it. The registry reports
+//! the outcome with a pending action poll message.
+//!
+//! The command carrying the trade differs between registries. Most accept a domain `<update>`
+//! changing the registrant (see [`registrant_change()`]), optionally combined with an
+//! extension; others model it as a transfer with a new registrant. [`request()`] accepts any
+//! command and extension, and classifies the result. The registry-specific payloads are:
+//!
+//! - EURid (.eu): a domain `<transfer>` request with [`eurid::Trade`]
+//! - AFNIC (.fr): a domain `<update>` with [`frnic::Trade`], or a domain `<transfer>` request
+//!   with [`frnic::Recover`] for a registrant taking their domain back
+//! - DK Hostmaster (.dk): a [`registrant_change()`] with [`dkhm::OwnerChange`]
+//!
+//! [`eurid::Trade`]: crate::extensions::eurid::Trade
+//! [`frnic::Trade`]: crate::extensions::frnic::Trade
+//! [`frnic::Recover`]: crate::extensions::frnic::Recover
+//! [`dkhm::OwnerChange`]: crate::extensions::dkhm::OwnerChange
+
+use chrono::{DateTime, Utc};
+
+use crate::client::{Connector, EppClient, RequestData};
+use crate::domain::update::DomainChangeInfo;
+use crate::domain::{DomainAuthInfo, DomainUpdate};
+use crate::poll::PollData;
+use crate::request::{Command, Extension, Transaction};
+use crate::response::ResultCode;
+use crate::Error;
+
+/// The state of a trade after the registry accepted the request
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum TradeStatus {
+    /// The registrant was changed immediately
+    Completed,
+    /// The registry accepted the request, but the change awaits approval
+    ///
+    /// The outcome is reported later with a poll message, see [`TradeResolution`].
+    Pending,
+}
+
+/// Build a domain `<update>` command changing the registrant of `name`
+///
+/// `auth_info` sets a new authorization code in the same command, which registries commonly
+/// require when the registrant changes.
+pub fn registrant_change<'a>(
+    name: &'a str,
+    registrant: &'a str,
+    auth_info: Option<&'a str>,
+) -> DomainUpdate<'a> {
+    let mut update = DomainUpdate::new(name);
+    update.info(DomainChangeInfo {
+        registrant: Some(registrant),
+        auth_info: auth_info.map(DomainAuthInfo::new),
+    });
+    update
+}
+
+/// Send a trade request and report whether it completed immediately
+///
+/// `data` is the command, optionally combined with a registry-specific extension, like for
+/// [`EppClient::transact()`].
+pub async fn request<'c, 'e, C, Cmd, Ext>(
+    client: &mut EppClient<C>,
+    data: impl Into<RequestData<'c, 'e, Cmd, Ext>>,
+    id: &str,
+) -> Result<TradeStatus, Error>
+where
+    C: Connector,
+    Cmd: Transaction<Ext> + Command + 'c,
+    Ext: Extension + 'e,
+{
+    let response = client.transact(data, id).await?;
+    Ok(match response.result.code {
+        ResultCode::CommandCompletedSuccessfullyActionPending => TradeStatus::Pending,
+        _ => TradeStatus::Completed,
+    })
+}
+
+/// The outcome of a pending trade, as reported in a poll message
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TradeResolution {
+    /// The traded domain
+    pub name: String,
+    /// Whether the trade was approved and the registrant changed
+    pub approved: bool,
+    /// The client transaction ID of the trade request, to correlate with [`request()`]
+    pub client_tr_id: Option<String>,
+    /// The server transaction ID of the trade request
+    pub server_tr_id: String,
+    /// When the trade was resolved
+    pub date: DateTime<Utc>,
+}
+
+impl TradeResolution {
+    /// Extract the outcome of a pending action from a poll message
+    ///
+    /// Pending action messages don't say which operation they resolve, so match
+    /// [`TradeResolution::client_tr_id`] against the ID used for the trade request.
+    pub fn from_poll(data: &PollData) -> Option<Self> {
+        let PollData::DomainPendingAction(data) = data else {
+            return None;
+        };
+
+        Some(Self {
+            name: data.name.name.clone(),
+            approved: data.name.result,
+            client_tr_id: data.transaction_ids.client_tr_id.clone(),
+            server_tr_id: data.transaction_ids.server_tr_id.clone(),
+            date: data.date,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::{registrant_change, TradeResolution};
+    use crate::poll::Poll;
+    use crate::tests::{assert_serialized, response_from_file};
+
+    #[test]
+    fn command() {
+        let object = registrant_change("eppdev.com", "eppdev-contact-5", Some("2fooBAR"));
+        assert_serialized("request/domain/update_registrant.xml", &object);
+    }
+
+    #[test]
+    fn resolution() {
+        let object = response_from_file::<Poll>("response/poll/poll_domain_pan.xml");
+        let resolution = TradeResolution::from_poll(object.res_data().unwrap()).unwrap();
+
+        assert_eq!(resolution.name, "early.space");
+        assert!(resolution.approved);
+        assert_eq!(
+            resolution.client_tr_id.as_deref(),
+            Some("7664b1f7-02c6-4a1c-8d8d-3059e02ef3ac")
+        );
+        assert_eq!(
+            resolution.date,
+            Utc.with_ymd_and_hms(2026, 3, 10, 0, 16, 57).unwrap()
+        );
+    }
+}
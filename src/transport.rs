@@ -0,0 +1,186 @@
+//! Exchanging EPP documents without the built-in client
+//!
+//! [`EppClient`] holds a TCP connection (usually with TLS) through tokio, which is not
+//! available on every target. Browser and edge runtimes (`wasm32-unknown-unknown`) can only
+//! reach a registry through a gateway, over a WebSocket or HTTPS. With the default `client`
+//! feature disabled, this crate builds without tokio and TLS, leaving the command and response
+//! types and this module.
+//!
+//! A [`Transport`] moves one request document to the registry and returns the response
+//! document; [`transact()`] renders a typed command, exchanges it and parses the response.
+//! Unlike [`EppClient::transact()`], no middleware, quirks, journaling or session management
+//! is applied: the gateway is expected to hold the session. Transports over HTTPS can compress
+//! their bodies with the helpers in [`compression`](crate::compression) (with the
+//! `compression` feature).
+//!
+//! [`EppClient`]: crate::EppClient
+//! [`EppClient::transact()`]: crate::EppClient::transact
+
+use async_trait::async_trait;
+
+#[cfg(feature = "client")]
+use crate::client::{Connector, EppClient};
+use crate::request::{Command, CommandWrapper, Extension, RequestData, Serialized, Transaction};
+use crate::response::Response;
+use crate::xml::{self, XmlLimits};
+use crate::Error;
+
+/// Carries EPP documents to a registry and back
+#[async_trait(?Send)]
+pub trait Transport {
+    /// Send the `<epp>` document `xml` and return the response document
+    async fn exchange(&mut self, xml: &str) -> Result<String, Error>;
+}
+
+/// Send documents through the client's session, as with [`EppClient::transact_raw()`]
+///
+/// The documents bypass the client's middleware, clTRID cache and journal, so transforms are
+/// refused while any of these is set, unless allowed with
+/// [`EppClient::set_unchecked_raw()`].
+///
+/// [`EppClient::transact_raw()`]: crate::EppClient::transact_raw
+/// [`EppClient::set_unchecked_raw()`]: crate::EppClient::set_unchecked_raw
+#[cfg(feature = "client")]
+#[async_trait(?Send)]
+impl<C: Connector> Transport for EppClient<C> {
+    async fn exchange(&mut self, xml: &str) -> Result<String, Error> {
+        Ok(self.transact_raw(xml).await?.xml)
+    }
+}
+
+/// Render the `<epp>` document for a command with the client transaction ID `id`
+pub fn serialize_command<'c, 'e, Cmd, Ext>(
+    data: impl Into<RequestData<'c, 'e, Cmd, Ext>>,
+    id: &str,
+) -> Result<String, Error>
+where
+    Cmd: Transaction<Ext> + Command + 'c,
+    Ext: Extension + 'e,
+{
+    let data = data.into();
+    match data.replacement {
+        Some(replacement) => {
+            let command = Serialized(replacement);
+            xml::serialize(CommandWrapper::serialized(
+                Cmd::COMMAND,
+                &command,
+                data.extension,
+                id,
+            ))
+        }
+        None => xml::serialize(CommandWrapper::new(data.command, data.extension, id)),
+    }
+}
+
+/// Parse the response document `xml` to a command of type `Cmd` with extension `Ext`
+///
+/// Like [`EppClient::transact()`](crate::EppClient::transact), a response with an error
+/// result code is returned as [`Error::Command`]. The document is checked against the default
+/// [`XmlLimits`] before it is parsed; use [`parse_response_within()`] for other limits.
+pub fn parse_response<Cmd, Ext>(xml: &str) -> Result<Response<Cmd::Response, Ext::Response>, Error>
+where
+    Cmd: Transaction<Ext> + Command,
+    Ext: Extension,
+{
+    parse_response_within::<Cmd, Ext>(xml, Some(&XmlLimits::default()))
+}
+
+/// Parse the response document `xml` like [`parse_response()`], if it stays within `limits`
+///
+/// `None` disables the checks. A document exceeding the limits is returned as [`Error::Xml`].
+pub fn parse_response_within<Cmd, Ext>(
+    xml: &str,
+    limits: Option<&XmlLimits>,
+) -> Result<Response<Cmd::Response, Ext::Response>, Error>
+where
+    Cmd: Transaction<Ext> + Command,
+    Ext: Extension,
+{
+    let rsp = xml::deserialize_within::<Response<Cmd::Response, Ext::Response>>(xml, limits)?;
+    if rsp.result.code.is_success() {
+        return Ok(rsp);
+    }
+
+    Err(Error::Command(Box::new(rsp.into_status())))
+}
+
+/// Send a command over `transport` and parse the response
+pub async fn transact<'c, 'e, T, Cmd, Ext>(
+    transport: &mut T,
+    data: impl Into<RequestData<'c, 'e, Cmd, Ext>>,
+    id: &str,
+) -> Result<Response<Cmd::Response, Ext::Response>, Error>
+where
+    T: Transport + ?Sized,
+    Cmd: Transaction<Ext> + Command + 'c,
+    Ext: Extension + 'e,
+{
+    let request = serialize_command(data, id)?;
+    let response = transport.exchange(&request).await?;
+    parse_response::<Cmd, Ext>(&response)
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+
+    use super::{parse_response, parse_response_within, transact, Transport};
+    use crate::common::NoExtension;
+    use crate::domain::DomainCheck;
+    use crate::response::ResultCode;
+    use crate::tests::{get_xml, CLTRID};
+    use crate::xml::XmlLimits;
+    use crate::Error;
+
+    struct Gateway {
+        expected: String,
+        response: String,
+    }
+
+    #[async_trait(?Send)]
+    impl Transport for Gateway {
+        async fn exchange(&mut self, xml: &str) -> Result<String, Error> {
+            assert_eq!(xml, self.expected);
+            Ok(self.response.clone())
+        }
+    }
+
+    #[test]
+    fn gateway() {
+        let mut gateway = Gateway {
+            expected: get_xml("request/domain/check.xml").unwrap(),
+            response: get_xml("response/domain/check.xml").unwrap(),
+        };
+        let check = DomainCheck {
+            domains: &["eppdev.com", "eppdev.net"],
+        };
+
+        let rsp = tokio_test::block_on(transact(&mut gateway, &check, CLTRID)).unwrap();
+        assert_eq!(rsp.result.code, ResultCode::CommandCompletedSuccessfully);
+        assert_eq!(rsp.res_data().unwrap().list.len(), 2);
+
+        gateway.response = get_xml("response/error.xml").unwrap();
+        let err = tokio_test::block_on(transact(&mut gateway, &check, CLTRID)).unwrap_err();
+        assert!(matches!(err, Error::Command(_)));
+    }
+
+    #[test]
+    fn limits() {
+        let xml = get_xml("response/domain/check.xml").unwrap();
+        assert!(parse_response::<DomainCheck<'_>, NoExtension>(&xml).is_ok());
+
+        let limits = XmlLimits {
+            max_depth: 4,
+            ..XmlLimits::default()
+        };
+        let err = parse_response_within::<DomainCheck<'_>, NoExtension>(&xml, Some(&limits));
+        assert!(matches!(err, Err(Error::Xml(_))));
+
+        let bomb = xml.replacen("<epp", "<!DOCTYPE epp [<!ENTITY a \"a\">]><epp", 1);
+        let err = parse_response::<DomainCheck<'_>, NoExtension>(&bomb).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "(de)serialization error: document type declarations are not allowed"
+        );
+    }
+}
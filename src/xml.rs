@@ -1,5 +1,11 @@
 //! Types to use in serialization to and deserialization from EPP XML
 
+use std::borrow::Cow;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::error::Error as StdError;
+use std::fmt;
+
 use instant_xml::{FromXml, FromXmlOwned, ToXml};
 
 use crate::common::EPP_XMLNS;
@@ -17,29 +23,876 @@ pub(crate) fn serialize(data: impl ToXml) -> Result<String, Error> {
 
 pub(crate) fn deserialize<T: FromXmlOwned>(xml: &str) -> Result<T, Error> {
     let xml = normalize_fee023_empty_prefix(xml);
+    let xml = crate::scalar::normalize_attributes(&xml);
     match instant_xml::from_str::<Epp<T>>(&xml) {
         Ok(Epp { data }) => Ok(data),
         Err(e) => Err(Error::Xml(e.into())),
     }
 }
 
+/// Deserialize a response borrowing from `xml`
+///
+/// Unlike [`deserialize()`], this does not repair responses with an empty fee-0.23 namespace
+/// prefix, since that requires a modified copy of the input.
+pub(crate) fn deserialize_borrowed<'xml, T: FromXml<'xml>>(xml: &'xml str) -> Result<T, Error> {
+    match instant_xml::from_str::<Epp<T>>(xml) {
+        Ok(Epp { data }) => Ok(data),
+        Err(e) => Err(Error::Xml(e.into())),
+    }
+}
+
 #[derive(FromXml, ToXml)]
 #[xml(rename = "epp", ns(EPP_XMLNS))]
 pub(crate) struct Epp<T> {
     pub(crate) data: T,
 }
 
-fn normalize_fee023_empty_prefix(xml: &str) -> String {
+/// Serialization choices for registries deviating from the usual EPP serialization
+///
+/// These apply to the requests a client serializes while they are set with
+/// [`Quirks::serialization()`](crate::quirks::Quirks::serialization). The types concerned
+/// read them as they write their elements: the `<extension>` wrapper for the namespace
+/// prefixes, [`Timestamp`](crate::common::Timestamp) for the fractional seconds and
+/// [`Boolean`](crate::common::Boolean) for the boolean format.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct SerializeOptions {
+    /// Use a namespace prefix instead of a default namespace for extension elements
+    ///
+    /// The prefix is derived from the namespace URI, like `secDNS` for
+    /// `urn:ietf:params:xml:ns:secDNS-1.1`, unless set in [`SerializeOptions::prefixes`].
+    pub prefix_extensions: bool,
+    /// Prefixes to use for specific extension namespaces, keyed by namespace URI
+    ///
+    /// These apply even if [`SerializeOptions::prefix_extensions`] is not set, so only the
+    /// listed namespaces get a prefix.
+    pub prefixes: HashMap<String, String>,
+    /// Write timestamps without fractional seconds
+    pub whole_seconds: bool,
+    /// Write booleans as `1` and `0` instead of `true` and `false`
+    pub numeric_booleans: bool,
+}
+
+impl SerializeOptions {
+    /// Use `prefix` for elements in the extension namespace `namespace`
+    pub fn with_prefix(mut self, namespace: impl Into<String>, prefix: impl Into<String>) -> Self {
+        self.prefixes.insert(namespace.into(), prefix.into());
+        self
+    }
+
+    // The prefix to use for the extension namespace `namespace`, if any
+    fn prefix(&self, namespace: &str) -> Option<String> {
+        match self.prefixes.get(namespace) {
+            Some(prefix) => Some(prefix.clone()),
+            None if self.prefix_extensions => Some(namespace_prefix(namespace)),
+            None => None,
+        }
+    }
+
+    fn prefixes_extensions(&self) -> bool {
+        self.prefix_extensions || !self.prefixes.is_empty()
+    }
+}
+
+/// Parsing choices for registries deviating from the usual EPP serialization
+///
+/// These apply to the responses a client parses while they are set with
+/// [`Quirks::parsing()`](crate::quirks::Quirks::parsing). Only fields known to hold booleans
+/// or numbers read them, like the `avail` attribute of check results, result codes, message
+/// counts and fee balances; any other value, like a postal code, is parsed as sent.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct ParseOptions {
+    /// Accept booleans in any case, like `True` or `FALSE`, and as `Y`, `N`, `yes` and `no`
+    pub lenient_booleans: bool,
+    /// Accept numbers surrounded by whitespace, like `code=" 1000"`
+    pub lenient_numbers: bool,
+}
+
+/// A response value only accepted because of the [`ParseOptions`] in effect
+///
+/// Returned by [`Response::scalar_warnings()`](crate::response::Response::scalar_warnings),
+/// so the deviation can be reported to the registry.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ScalarWarning {
+    /// The field the value was parsed into, like `EppResult::code`, or the name of the
+    /// attribute, like `avail`
+    pub field: &'static str,
+    /// The value as sent by the registry
+    pub original: String,
+    /// The value that was parsed instead
+    pub normalized: String,
+}
+
+thread_local! {
+    // The options for the request being serialized on this thread
+    static OPTIONS: RefCell<Option<SerializeOptions>> = const { RefCell::new(None) };
+}
+
+#[cfg(any(feature = "client", test))]
+/// Run `f`, serializing requests with `options`
+///
+/// `ToXml` has no way to pass options down to the types being serialized, so they are set for
+/// the current thread while `f` runs. Serialization is synchronous, so this can't leak into
+/// other requests.
+pub(crate) fn with_options<T>(options: Option<&SerializeOptions>, f: impl FnOnce() -> T) -> T {
+    struct Restore(Option<SerializeOptions>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            OPTIONS.with(|cell| *cell.borrow_mut() = previous);
+        }
+    }
+
+    let _restore = Restore(OPTIONS.with(|cell| cell.replace(options.cloned())));
+    f()
+}
+
+/// Call `f` with the options in effect for the request being serialized
+pub(crate) fn options<T>(f: impl FnOnce(Option<&SerializeOptions>) -> T) -> T {
+    OPTIONS.with(|cell| f(cell.borrow().as_ref()))
+}
+
+thread_local! {
+    // The options for the response being parsed on this thread, and the values they accepted
+    static PARSING: RefCell<Option<(ParseOptions, Vec<ScalarWarning>)>> =
+        const { RefCell::new(None) };
+}
+
+#[cfg(any(feature = "client", test))]
+/// Run `f`, parsing responses with `options`, and return the values they accepted
+///
+/// Like [`with_options()`], the options are set for the current thread while `f` runs, since
+/// `FromXml` can't pass them down either.
+pub(crate) fn with_parse_options<T>(
+    options: ParseOptions,
+    f: impl FnOnce() -> T,
+) -> (T, Vec<ScalarWarning>) {
+    struct Restore(Option<(ParseOptions, Vec<ScalarWarning>)>);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            let previous = self.0.take();
+            PARSING.with(|cell| *cell.borrow_mut() = previous);
+        }
+    }
+
+    let _restore = Restore(PARSING.with(|cell| cell.replace(Some((options, Vec::new())))));
+    let value = f();
+    let warnings = PARSING.with(|cell| match &mut *cell.borrow_mut() {
+        Some((_, warnings)) => std::mem::take(warnings),
+        None => Vec::new(),
+    });
+    (value, warnings)
+}
+
+/// The options in effect for the response being parsed
+pub(crate) fn parse_options() -> ParseOptions {
+    PARSING
+        .with(|cell| cell.borrow().as_ref().map(|(options, _)| *options))
+        .unwrap_or_default()
+}
+
+/// Record a value accepted because of the options in effect
+pub(crate) fn record_scalar_warning(warning: ScalarWarning) {
+    PARSING.with(|cell| {
+        if let Some((_, warnings)) = &mut *cell.borrow_mut() {
+            warnings.push(warning);
+        }
+    });
+}
+
+/// Serialize the `<extension>` content `data`, with the prefixes from the options in effect
+pub(crate) fn serialize_extension<T: ToXml + ?Sized, W: fmt::Write + ?Sized>(
+    data: &T,
+    serializer: &mut instant_xml::Serializer<W>,
+) -> Result<(), instant_xml::Error> {
+    let prefixed = options(|options| {
+        let options = options.filter(|options| options.prefixes_extensions())?;
+        let mut writer = PrefixWriter::new(options);
+        let result = data.serialize(None, &mut instant_xml::Serializer::new(&mut writer));
+        Some(result.map(|()| writer.out))
+    });
+
+    match prefixed {
+        Some(xml) => serializer.write_str(&xml?),
+        None => data.serialize(None, serializer),
+    }
+}
+
+// Moves the default namespace declarations of the elements written through it to prefixes
+//
+// This sits between an instant-xml `Serializer` and its output. The serializer writes
+// `<name xmlns="..."` for an element in a namespace other than the default one, and `<name`
+// for elements in the default namespace, so the writer keeps track of the prefix in scope for
+// each open element. Text and attribute values are escaped by the serializer, so a `<` or `>`
+// is always markup.
+struct PrefixWriter<'a> {
+    options: &'a SerializeOptions,
+    out: String,
+    // Markup not complete yet
+    pending: String,
+    // The prefix in scope for each open element, if its default namespace was replaced
+    scopes: Vec<Option<String>>,
+}
+
+impl<'a> PrefixWriter<'a> {
+    fn new(options: &'a SerializeOptions) -> Self {
+        Self {
+            options,
+            out: String::new(),
+            pending: String::new(),
+            scopes: Vec::new(),
+        }
+    }
+
+    fn tag(&mut self, tag: &str) {
+        if let Some(name) = tag.strip_prefix('/') {
+            match self.scopes.pop().flatten() {
+                Some(prefix) if !name.contains(':') => {
+                    self.out.push_str(&format!("</{prefix}:{name}>"))
+                }
+                _ => self.out.push_str(&format!("</{name}>")),
+            }
+            return;
+        }
+
+        let (tag, self_closing) = match tag.strip_suffix('/') {
+            Some(tag) => (tag, true),
+            None => (tag, false),
+        };
+        let name_end = tag.find(char::is_whitespace).unwrap_or(tag.len());
+        let (name, attrs) = tag.split_at(name_end);
+
+        const XMLNS: &str = " xmlns=\"";
+        let inherited = self.scopes.last().cloned().flatten();
+        let (scope, attrs) = match attrs.find(XMLNS) {
+            Some(pos) if !name.contains(':') => {
+                let start = pos + XMLNS.len();
+                let end = start + attrs[start..].find('"').unwrap_or(0);
+                match self.options.prefix(&attrs[start..end]) {
+                    Some(prefix) => {
+                        let attrs =
+                            format!("{} xmlns:{prefix}{}", &attrs[..pos], &attrs[pos + 6..]);
+                        (Some(prefix), Cow::Owned(attrs))
+                    }
+                    // A default namespace that is kept resets the prefix in scope
+                    None => (None, Cow::Borrowed(attrs)),
+                }
+            }
+            _ => (inherited, Cow::Borrowed(attrs)),
+        };
+
+        let close = if self_closing { "/" } else { "" };
+        match (&scope, name.contains(':')) {
+            (Some(prefix), false) => self
+                .out
+                .push_str(&format!("<{prefix}:{name}{attrs}{close}>")),
+            _ => self.out.push_str(&format!("<{name}{attrs}{close}>")),
+        }
+
+        if !self_closing {
+            self.scopes.push(scope);
+        }
+    }
+}
+
+impl fmt::Write for PrefixWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.pending.push_str(s);
+        loop {
+            if self.pending.starts_with('<') {
+                let Some(end) = self.pending.find('>') else {
+                    return Ok(());
+                };
+                let tag = self.pending[1..end].to_owned();
+                self.pending.drain(..=end);
+                self.tag(&tag);
+            } else {
+                let end = self.pending.find('<').unwrap_or(self.pending.len());
+                if end == 0 {
+                    return Ok(());
+                }
+                self.out.extend(self.pending.drain(..end));
+            }
+        }
+    }
+}
+
+// Derive a prefix like `secDNS` from a namespace like `urn:ietf:params:xml:ns:secDNS-1.1`
+fn namespace_prefix(namespace: &str) -> String {
+    let last = namespace
+        .rsplit([':', '/'])
+        .find(|s| !s.is_empty())
+        .unwrap_or("ext");
+
+    match last.rsplit_once('-') {
+        Some((name, version)) if version.starts_with(|c: char| c.is_ascii_digit()) => {
+            name.to_owned()
+        }
+        _ => last.to_owned(),
+    }
+}
+
+pub(crate) fn normalize_fee023_empty_prefix(xml: &str) -> Cow<'_, str> {
     const BAD_XMLNS: &str = "xmlns:=\"urn:ietf:params:xml:ns:fee-0.23\"";
     const GOOD_XMLNS: &str = "xmlns:fee=\"urn:ietf:params:xml:ns:fee-0.23\"";
 
     if !xml.contains(BAD_XMLNS) {
-        return xml.to_string();
+        return Cow::Borrowed(xml);
     }
 
     // Some registries emit an empty namespace prefix (e.g. "<:chkData xmlns:="...">"),
     // which is invalid XML. Normalize to a real prefix so the parser can proceed.
-    xml.replace(BAD_XMLNS, GOOD_XMLNS)
-        .replace("<:", "<fee:")
-        .replace("</:", "</fee:")
+    Cow::Owned(
+        xml.replace(BAD_XMLNS, GOOD_XMLNS)
+            .replace("<:", "<fee:")
+            .replace("</:", "</fee:"),
+    )
+}
+
+/// Limits a received document has to stay within before it is parsed
+///
+/// Registries are only semi-trusted, and a parser accepting anything is open to documents
+/// built to exhaust memory or stack, like the "billion laughs" entity expansion. The defaults
+/// reject document type declarations (which EPP never uses) and leave plenty of room for the
+/// nesting, attributes and text of real responses.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct XmlLimits {
+    /// Accept `<!DOCTYPE>` (and so entity) declarations
+    pub allow_doctype: bool,
+    /// The deepest nesting of elements allowed
+    pub max_depth: usize,
+    /// The most attributes (including namespace declarations) allowed on one element
+    pub max_attributes: usize,
+    /// The longest text or CDATA section allowed, in bytes
+    pub max_text_len: usize,
+}
+
+impl Default for XmlLimits {
+    fn default() -> Self {
+        Self {
+            allow_doctype: false,
+            max_depth: 32,
+            max_attributes: 32,
+            max_text_len: 1024 * 1024,
+        }
+    }
+}
+
+impl XmlLimits {
+    /// Check that `xml` stays within the limits
+    ///
+    /// This scans the markup without validating it; malformed documents are left for the
+    /// parser to reject.
+    pub fn check(&self, xml: &str) -> Result<(), XmlLimitError> {
+        let mut depth = 0usize;
+        for token in tokens(xml) {
+            match token {
+                Token::Text(text) | Token::Cdata { text, .. } => self.text(text)?,
+                Token::Doctype(_) if !self.allow_doctype => return Err(XmlLimitError::Doctype),
+                Token::Start(tag) => {
+                    let attributes = tag.attributes().count();
+                    if attributes > self.max_attributes {
+                        return Err(XmlLimitError::TooManyAttributes(attributes));
+                    }
+                    if !tag.self_closing() {
+                        depth += 1;
+                        if depth > self.max_depth {
+                            return Err(XmlLimitError::TooDeep(depth));
+                        }
+                    }
+                }
+                Token::End(_) => depth = depth.saturating_sub(1),
+                Token::Doctype(_) | Token::Other(_) => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    fn text(&self, text: &str) -> Result<(), XmlLimitError> {
+        match text.len() > self.max_text_len {
+            true => Err(XmlLimitError::TextTooLong(text.len())),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Deserialize the received document `xml`, if it stays within `limits`
+pub(crate) fn deserialize_within<T: FromXmlOwned>(
+    xml: &str,
+    limits: Option<&XmlLimits>,
+) -> Result<T, Error> {
+    if let Some(limits) = limits {
+        limits.check(xml)?;
+    }
+
+    deserialize(xml)
+}
+
+#[cfg(any(feature = "client", test))]
+/// The verb of the command in the EPP request `xml`, or `hello`
+///
+/// Returns `None` if `xml` is not an `<epp>` document with a command or hello.
+pub(crate) fn command_verb(xml: &str) -> Option<&'static str> {
+    const VERBS: [&str; 10] = [
+        "check", "create", "delete", "info", "login", "logout", "poll", "renew", "transfer",
+        "update",
+    ];
+
+    let mut tokens = tokens(xml);
+    if tokens.next_child()?.local_name() != "epp" {
+        return None;
+    }
+
+    match tokens.next_child()?.local_name() {
+        "hello" => Some("hello"),
+        "command" => {
+            let verb = tokens.next_child()?.local_name();
+            VERBS.into_iter().find(|known| *known == verb)
+        }
+        _ => None,
+    }
+}
+
+/// Split `xml` into markup and text, see [`Token`]
+///
+/// This is the one place the crate scans XML without parsing it: to check [`XmlLimits`]
+/// before a document reaches the parser, and to inspect or patch serialized documents that
+/// are never parsed. The scanner doesn't validate anything, and the tokens cover `xml` without
+/// gaps, so their [`raw()`](Token::raw) markup concatenates back to the input.
+pub(crate) fn tokens(xml: &str) -> Tokens<'_> {
+    Tokens { rest: xml }
+}
+
+/// The tokens of a document, see [`tokens()`]
+#[derive(Clone, Debug)]
+pub(crate) struct Tokens<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Tokens<'a> {
+    #[cfg(any(feature = "client", test))]
+    /// The next start tag, unless an end tag comes first
+    pub(crate) fn next_child(&mut self) -> Option<Tag<'a>> {
+        self.find_map(|token| match token {
+            Token::Start(tag) => Some(Some(tag)),
+            Token::End(_) => Some(None),
+            _ => None,
+        })?
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let rest = self.rest;
+        if rest.is_empty() {
+            return None;
+        }
+
+        let through = |end: &str| rest.find(end).map_or(rest.len(), |i| i + end.len());
+        let token = if !rest.starts_with('<') {
+            Token::Text(&rest[..rest.find('<').unwrap_or(rest.len())])
+        } else if rest.starts_with("<!--") || rest.starts_with("<?") {
+            let end = if rest.starts_with("<?") { "?>" } else { "-->" };
+            Token::Other(&rest[..through(end)])
+        } else if let Some(cdata) = rest.strip_prefix("<![CDATA[") {
+            Token::Cdata {
+                markup: &rest[..through("]]>")],
+                text: &cdata[..cdata.find("]]>").unwrap_or(cdata.len())],
+            }
+        } else if rest.starts_with("<!") {
+            // Include the internal subset with its declarations, if there is one
+            let end = match (rest.find('['), rest.find('>')) {
+                (Some(open), Some(close)) if open < close => "]>",
+                _ => ">",
+            };
+            Token::Doctype(&rest[..through(end)])
+        } else {
+            let tag = &rest[..tag_end(rest)];
+            match tag.starts_with("</") {
+                true => Token::End(tag),
+                false => Token::Start(Tag(tag)),
+            }
+        };
+
+        self.rest = &rest[token.raw().len()..];
+        Some(token)
+    }
+}
+
+/// A piece of a document, with the markup it was read from
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Token<'a> {
+    /// A start tag or empty-element tag
+    Start(Tag<'a>),
+    /// An end tag, like `</domain:name>`
+    End(&'a str),
+    /// Character data between tags, with any entity references
+    Text(&'a str),
+    /// A CDATA section, and the text inside it
+    Cdata { markup: &'a str, text: &'a str },
+    /// A document type declaration, with its internal subset
+    Doctype(&'a str),
+    /// A comment or processing instruction
+    Other(&'a str),
+}
+
+impl<'a> Token<'a> {
+    /// The markup or text of the token, as it appears in the document
+    pub(crate) fn raw(&self) -> &'a str {
+        match *self {
+            Self::Start(tag) => tag.0,
+            Self::End(raw) | Self::Text(raw) | Self::Doctype(raw) | Self::Other(raw) => raw,
+            Self::Cdata { markup, .. } => markup,
+        }
+    }
+}
+
+/// A start tag like `<domain:name>` or `<contact:postalInfo type="loc"/>`
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Tag<'a>(pub(crate) &'a str);
+
+impl<'a> Tag<'a> {
+    /// The qualified name of the element, with any prefix
+    pub(crate) fn name(&self) -> &'a str {
+        let name = &self.0[1..];
+        &name[..name
+            .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+            .unwrap_or(name.len())]
+    }
+
+    #[cfg(any(feature = "client", test))]
+    /// The name of the element without its prefix
+    pub(crate) fn local_name(&self) -> &'a str {
+        let name = self.name();
+        name.rsplit(':').next().unwrap_or(name)
+    }
+
+    /// Whether this is an empty-element tag, like `<name/>`
+    pub(crate) fn self_closing(&self) -> bool {
+        self.0.ends_with("/>")
+    }
+
+    /// The attributes of the tag, in order
+    pub(crate) fn attributes(&self) -> Attributes<'a> {
+        let start = 1 + self.name().len();
+        Attributes {
+            tag: self.0,
+            pos: start,
+        }
+    }
+}
+
+/// The attributes of a [`Tag`]
+pub(crate) struct Attributes<'a> {
+    tag: &'a str,
+    pos: usize,
+}
+
+impl<'a> Iterator for Attributes<'a> {
+    type Item = Attribute<'a>;
+
+    fn next(&mut self) -> Option<Attribute<'a>> {
+        let rest = &self.tag[self.pos..];
+        let name_start = rest.find(|c: char| !c.is_whitespace())?;
+        let (name, after) = rest[name_start..].split_once('=')?;
+        let after_eq = after.trim_start();
+        let quote = after_eq
+            .chars()
+            .next()
+            .filter(|c| *c == '"' || *c == '\'')?;
+        let value_start = self.tag.len() - after_eq.len() + 1;
+        let value_len = self.tag[value_start..].find(quote)?;
+
+        self.pos = value_start + value_len + 1;
+        Some(Attribute {
+            name: name.trim_end(),
+            value: &self.tag[value_start..value_start + value_len],
+            offset: value_start,
+        })
+    }
+}
+
+/// An attribute of a [`Tag`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct Attribute<'a> {
+    pub(crate) name: &'a str,
+    /// The value between the quotes, with any entity references
+    pub(crate) value: &'a str,
+    /// The position of `value` in the tag
+    pub(crate) offset: usize,
+}
+
+// The length of the tag at the start of `xml`, up to and including its `>` (ignoring any in
+// attribute values)
+fn tag_end(xml: &str) -> usize {
+    let mut quote = None;
+    for (i, c) in xml.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if open == c => quote = None,
+            (None, '>') => return i + 1,
+            _ => {}
+        }
+    }
+    xml.len()
+}
+
+/// A received document exceeding the [`XmlLimits`]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum XmlLimitError {
+    /// The document contains a `<!DOCTYPE>` declaration
+    Doctype,
+    /// Elements are nested this deep
+    TooDeep(usize),
+    /// An element has this many attributes
+    TooManyAttributes(usize),
+    /// A text node or CDATA section is this many bytes long
+    TextTooLong(usize),
+}
+
+impl fmt::Display for XmlLimitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Doctype => write!(f, "document type declarations are not allowed"),
+            Self::TooDeep(depth) => write!(f, "elements nested {depth} deep"),
+            Self::TooManyAttributes(count) => write!(f, "element with {count} attributes"),
+            Self::TextTooLong(len) => write!(f, "text of {len} bytes"),
+        }
+    }
+}
+
+impl StdError for XmlLimitError {}
+
+impl From<XmlLimitError> for Error {
+    fn from(err: XmlLimitError) -> Self {
+        Self::Xml(Box::new(err))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::{command_verb, with_options, SerializeOptions, Token, XmlLimitError, XmlLimits};
+    use crate::common::Timestamp;
+    use crate::contact::{Disclose, DiscloseField};
+    use crate::domain::update::DomainChangeInfo;
+    use crate::domain::{DomainCheck, DomainUpdate};
+    use crate::extensions::{fee, privacy, secdns};
+    use crate::tests::{assert_serialized_with, get_xml};
+
+    #[test]
+    fn prefix_extensions() {
+        let options = SerializeOptions {
+            prefix_extensions: true,
+            ..SerializeOptions::default()
+        };
+
+        let ds_data = [secdns::DsDataType::new(
+            12346,
+            secdns::Algorithm::Dsa,
+            secdns::DigestAlgorithm::Sha1,
+            "38EC35D5B3A34B44C39B",
+            None,
+        )];
+        let extension = secdns::UpdateData::builder()
+            .remove_all()
+            .add_ds(&ds_data)
+            .max_sig_life(Duration::from_secs(605900))
+            .urgent()
+            .build()
+            .unwrap();
+        let mut object = DomainUpdate::new("example.com");
+        object.info(DomainChangeInfo {
+            registrant: None,
+            auth_info: None,
+        });
+
+        assert_serialized_with(
+            "request/extensions/secdns_update_prefixed.xml",
+            &options,
+            (&object, &extension),
+        );
+    }
+
+    #[test]
+    fn explicit_prefixes() {
+        let options = SerializeOptions::default().with_prefix(fee::XMLNS, "fee");
+        let object = DomainCheck {
+            domains: &["eppdev.com"],
+        };
+        let extension = fee::Check::new(Some("USD"), Some(1));
+
+        assert_serialized_with(
+            "request/extensions/fee_check_prefixed.xml",
+            &options,
+            (&object, &extension),
+        );
+    }
+
+    #[test]
+    fn whole_seconds() {
+        let timestamp = Timestamp(
+            Utc.with_ymd_and_hms(2024, 1, 2, 3, 4, 5).unwrap()
+                + chrono::Duration::milliseconds(678),
+        );
+        assert_eq!(
+            instant_xml::to_string(&timestamp).unwrap(),
+            "2024-01-02T03:04:05.678Z"
+        );
+
+        let options = SerializeOptions {
+            whole_seconds: true,
+            ..SerializeOptions::default()
+        };
+        let xml = with_options(Some(&options), || instant_xml::to_string(&timestamp));
+        assert_eq!(xml.unwrap(), "2024-01-02T03:04:05Z");
+    }
+
+    #[test]
+    fn numeric_booleans() {
+        let options = SerializeOptions {
+            numeric_booleans: true,
+            ..SerializeOptions::default()
+        };
+
+        let ds_data = [secdns::DsDataType::new(
+            12346,
+            secdns::Algorithm::Dsa,
+            secdns::DigestAlgorithm::Sha1,
+            "38EC35D5B3A34B44C39B",
+            None,
+        )];
+        let extension = secdns::UpdateData::builder()
+            .remove_all()
+            .add_ds(&ds_data)
+            .max_sig_life(Duration::from_secs(605900))
+            .urgent()
+            .build()
+            .unwrap();
+        let mut object = DomainUpdate::new("example.com");
+        object.info(DomainChangeInfo {
+            registrant: None,
+            auth_info: None,
+        });
+
+        assert_serialized_with(
+            "request/extensions/secdns_update_numeric.xml",
+            &options,
+            (&object, &extension),
+        );
+
+        let privacy = privacy::Update::new(false);
+        let xml = with_options(Some(&options), || instant_xml::to_string(&privacy));
+        assert_eq!(
+            xml.unwrap(),
+            r#"<update xmlns="urn:ietf:params:xml:ns:epp:privacy-1.0"><enabled>0</enabled></update>"#
+        );
+
+        let disclose = Disclose::new(true, &[DiscloseField::Email]);
+        let xml = with_options(Some(&options), || instant_xml::to_string(&disclose));
+        assert_eq!(
+            xml.unwrap(),
+            r#"<disclose xmlns="urn:ietf:params:xml:ns:contact-1.0" flag="1"><email /></disclose>"#
+        );
+        assert!(instant_xml::to_string(&disclose)
+            .unwrap()
+            .contains(r#"flag="true""#));
+    }
+
+    #[test]
+    fn limits() {
+        let limits = XmlLimits::default();
+        for path in ["response/greeting.xml", "response/domain/info.xml"] {
+            assert_eq!(limits.check(&get_xml(path).unwrap()), Ok(()));
+        }
+
+        let bomb = r#"<?xml version="1.0"?>
+<!DOCTYPE lolz [<!ENTITY lol "lol"><!ENTITY lol2 "&lol;&lol;&lol;">]>
+<epp xmlns="urn:ietf:params:xml:ns:epp-1.0">&lol2;</epp>"#;
+        assert_eq!(limits.check(bomb), Err(XmlLimitError::Doctype));
+        let relaxed = XmlLimits {
+            allow_doctype: true,
+            ..limits
+        };
+        assert_eq!(relaxed.check(bomb), Ok(()));
+
+        let deep = "<a>".repeat(33);
+        assert_eq!(limits.check(&deep), Err(XmlLimitError::TooDeep(33)));
+        assert_eq!(limits.check(&"<a/>".repeat(100)), Ok(()));
+
+        let attributes = (0..40).map(|i| format!(" a{i}='>'")).collect::<String>();
+        assert_eq!(
+            limits.check(&format!("<epp{attributes}/>")),
+            Err(XmlLimitError::TooManyAttributes(40))
+        );
+
+        let limits = XmlLimits {
+            max_text_len: 4,
+            ..limits
+        };
+        assert_eq!(
+            limits.check("<a><![CDATA[12345]]></a>"),
+            Err(XmlLimitError::TextTooLong(5))
+        );
+    }
+
+    #[test]
+    fn tokens() {
+        let xml = get_xml("response/domain/info.xml").unwrap();
+        let raw = super::tokens(&xml)
+            .map(|token| token.raw())
+            .collect::<String>();
+        assert_eq!(raw, xml);
+
+        let xml =
+            r#"<?xml version="1.0"?><!-- a > b --><a:b x='>' y = "2"/>text<![CDATA[<c>]]></a:b>"#;
+        let tokens = super::tokens(xml).collect::<Vec<_>>();
+        assert!(matches!(tokens[0], Token::Other(_)));
+        assert_eq!(tokens[1], Token::Other("<!-- a > b -->"));
+        let Token::Start(tag) = tokens[2] else {
+            panic!("expected a start tag, got {:?}", tokens[2]);
+        };
+        assert_eq!((tag.name(), tag.local_name()), ("a:b", "b"));
+        assert!(tag.self_closing());
+        let attributes = tag
+            .attributes()
+            .map(|attr| (attr.name, attr.value))
+            .collect::<Vec<_>>();
+        assert_eq!(attributes, [("x", ">"), ("y", "2")]);
+        assert_eq!(tokens[3], Token::Text("text"));
+        assert!(matches!(tokens[4], Token::Cdata { text: "<c>", .. }));
+        assert_eq!(tokens[5], Token::End("</a:b>"));
+    }
+
+    #[test]
+    fn command_verbs() {
+        assert_eq!(
+            command_verb(&get_xml("request/login.xml").unwrap()),
+            Some("login")
+        );
+        assert_eq!(
+            command_verb(&get_xml("request/hello.xml").unwrap()),
+            Some("hello")
+        );
+        let check = get_xml("request/domain/check.xml").unwrap();
+        assert_eq!(command_verb(&check), Some("check"));
+
+        let prefixed = r#"<?xml version="1.0"?>
+<!-- a <command> in a comment -->
+<e:epp xmlns:e="urn:ietf:params:xml:ns:epp-1.0"><e:command><e:logout/></e:command></e:epp>"#;
+        assert_eq!(command_verb(prefixed), Some("logout"));
+        assert_eq!(command_verb("<epp><command></command></epp>"), None);
+        assert_eq!(
+            command_verb("<epp><command><frobnicate/></command></epp>"),
+            None
+        );
+        assert_eq!(command_verb("<login/>"), None);
+    }
 }
@@ -1,87 +1,1610 @@
+#![cfg(feature = "client")]
+
+use std::any::Any;
 use std::fs::File;
 use std::io::{self, Read, Write};
+use std::net::IpAddr;
 use std::str;
-use std::time::Duration;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
+use chrono::{TimeZone, Utc};
 use regex::Regex;
 use tokio::time::timeout;
 use tokio_test::io::Builder;
 
-use instant_epp::client::{Connector, EppClient};
-use instant_epp::domain::{DomainCheck, DomainContact, DomainCreate, Period, PeriodLength};
+use instant_epp::balance::{AccountBalance, Balance};
+use instant_epp::client::{Connector, EppClient, SessionState, UncheckedCommand};
+use instant_epp::cltrid::{ClTridCache, ClTridConflict};
+use instant_epp::common::NoExtension;
+use instant_epp::domain::check::{CheckData, CheckDataRef};
+use instant_epp::domain::{
+    DomainCheck, DomainContact, DomainCreate, DomainDelete, DomainTransfer, Period, PeriodLength,
+    Status,
+};
+use instant_epp::extensions::fee07;
+use instant_epp::extensions::rgp::report::RgpRestoreReport;
+use instant_epp::extensions::rgp::RgpStatus;
+use instant_epp::host::{HostCheck, HostCreate, HostDelete};
+use instant_epp::interlock::Interlock;
+use instant_epp::journal::{FileJournal, JournalEntry, TransactionJournal};
 use instant_epp::login::Login;
-use instant_epp::response::ResultCode;
+use instant_epp::logout::Logout;
+use instant_epp::middleware::{CommandView, Middleware, Request};
+use instant_epp::pending::{LostResponse, LostResponseCheck, TransactionState, Verified};
+use instant_epp::poll::Poll;
+use instant_epp::poll_store::{MemoryPollStore, PollStore};
+#[cfg(feature = "pool")]
+use instant_epp::pool::EppPool;
+use instant_epp::quirks::RegistryQuirks;
+use instant_epp::registrar::reconcile::{self, Change, Plan};
+use instant_epp::registrar::{
+    self, GraceCredit, GracePeriods, GuardedDelete, LinkedPolicy, RestoreReportPolicy, RestoreState,
+};
+use instant_epp::response::{RawResponse, Response, ResultCode};
+use instant_epp::xml::{ParseOptions, ScalarWarning};
 use instant_epp::Error;
 
 const CLTRID: &str = "cltrid:1626454866";
-#[cfg(feature = "transaction-audit")]
 const SVTRID: &str = "RO-6879-1627224678242975";
 
-struct TestWriter;
+struct TestWriter;
+
+impl Write for TestWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        print!(
+            "{}",
+            str::from_utf8(buf).expect("tried to log invalid UTF-8")
+        );
+        Ok(buf.len())
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        io::stdout().flush()
+    }
+}
+
+fn log_to_stdout() -> tracing::subscriber::DefaultGuard {
+    let sub = tracing_subscriber::FmtSubscriber::builder()
+        .with_max_level(tracing::Level::TRACE)
+        .with_writer(|| TestWriter)
+        .finish();
+    tracing::subscriber::set_default(sub)
+}
+
+fn len_bytes(bytes: &str) -> [u8; 4] {
+    ((bytes.len() as u32) + 4).to_be_bytes()
+}
+
+fn xml(path: &str) -> String {
+    let ws_regex = Regex::new(r"[\s]{2,}").unwrap();
+    let end_regex = Regex::new(r"\?>").unwrap();
+
+    let mut f = File::open(format!("tests/resources/{path}")).unwrap();
+    let mut buf = String::new();
+    f.read_to_string(&mut buf).unwrap();
+
+    if !buf.is_empty() {
+        let mat = end_regex.find(buf.as_str()).unwrap();
+        let start = mat.end();
+        buf = format!(
+            "{}\r\n{}",
+            &buf[..start],
+            ws_regex.replace_all(&buf[start..], "")
+        );
+    }
+
+    buf
+}
+
+fn build_stream(units: &[&str]) -> Builder {
+    let mut builder = Builder::new();
+    for (i, path) in units.iter().enumerate() {
+        let buf = xml(path);
+        match i % 2 {
+            0 => builder.read(&len_bytes(&buf)).read(buf.as_bytes()),
+            1 => builder.write(&len_bytes(&buf)).write(buf.as_bytes()),
+            _ => unreachable!(),
+        };
+    }
+
+    builder
+}
+
+// Logs in with the command in `request/login.xml`, answered by `response/login.xml`
+async fn login<C: Connector>(client: &mut EppClient<C>) {
+    let ext_uris = ["http://schema.ispapi.net/epp/xml/keyvalue-1.0"];
+    let login = Login::new(
+        "username",
+        "password",
+        Some("new-password"),
+        Some(&ext_uris),
+    );
+    client.transact(&login, CLTRID).await.unwrap();
+}
+
+#[tokio::test]
+async fn client() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/domain/check.xml",
+                "response/domain/check.xml",
+            ])
+            .build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    assert_eq!(client.xml_greeting(), xml("response/greeting.xml"));
+
+    // Commands other than hello, login and logout are refused before logging in
+    let check = DomainCheck {
+        domains: &["eppdev.com", "eppdev.net"],
+    };
+    let err = client.transact(&check, CLTRID).await.unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "error: cannot send check in session state Greeted"
+    );
+
+    let rsp = client
+        .transact(
+            &Login::new(
+                "username",
+                "password",
+                Some("new-password"),
+                Some(&["http://schema.ispapi.net/epp/xml/keyvalue-1.0"]),
+            ),
+            CLTRID,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(rsp.result.code, ResultCode::CommandCompletedSuccessfully);
+    assert_eq!(client.session_state(), &SessionState::LoggedIn);
+
+    // Logging in twice is refused without touching the stream
+    let err = client
+        .transact(&Login::new("username", "password", None, None), CLTRID)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "error: cannot send login in session state LoggedIn"
+    );
+
+    let rsp = client
+        .transact(
+            &DomainCheck {
+                domains: &["eppdev.com", "eppdev.net"],
+            },
+            CLTRID,
+        )
+        .await
+        .unwrap();
+    assert_eq!(rsp.result.code, ResultCode::CommandCompletedSuccessfully);
+
+    let result = rsp.res_data().unwrap();
+    assert_eq!(result.list[0].inner.id, "eppdev.com");
+}
+
+#[tokio::test]
+async fn dropped() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            let mut builder = Builder::new();
+
+            let buf = xml("response/greeting.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            let buf = xml("request/login.xml");
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+
+            let buf = xml("response/login.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            let buf = xml("request/domain/check.xml");
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+
+            // We add a wait here. We're going to timeout below as a way of dropping the future.
+            builder.wait(Duration::from_millis(100));
+
+            let buf = xml("response/domain/check.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            let buf = xml("request/domain/create.xml");
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+
+            let buf = xml("response/domain/create.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            Ok(builder.build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    assert_eq!(client.xml_greeting(), xml("response/greeting.xml"));
+    let rsp = client
+        .transact(
+            &Login::new(
+                "username",
+                "password",
+                Some("new-password"),
+                Some(&["http://schema.ispapi.net/epp/xml/keyvalue-1.0"]),
+            ),
+            CLTRID,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(rsp.result.code, ResultCode::CommandCompletedSuccessfully);
+
+    // Here, we add a 10ms timeout on the entire transaction. The mock stream
+    // specifies that the caller will have to wait for 100ms after sending
+    // the request before the response is returned. When `timeout()` returns
+    // `Err(Elapsed)`, the `RequestFuture` inside the `Timeout` future is dropped,
+    // leaving a half-finished request in the `EppConnection`.
+    timeout(
+        Duration::from_millis(10),
+        client.transact(
+            &DomainCheck {
+                domains: &["eppdev.com", "eppdev.net"],
+            },
+            CLTRID,
+        ),
+    )
+    .await
+    .unwrap_err();
+
+    let contacts = &[
+        DomainContact {
+            contact_type: "admin".into(),
+            id: "eppdev-contact-3".into(),
+        },
+        DomainContact {
+            contact_type: "tech".into(),
+            id: "eppdev-contact-3".into(),
+        },
+        DomainContact {
+            contact_type: "billing".into(),
+            id: "eppdev-contact-3".into(),
+        },
+    ];
+
+    // Then, we start another request (of a different type). This should push through the
+    // remainder of the in-flight request before starting the new one, and succeed.
+    let create = DomainCreate::new(
+        "eppdev-1.com",
+        Period::Years(PeriodLength::new(1).unwrap()),
+        None,
+        Some("eppdev-contact-3"),
+        "epP4uthd#v",
+        Some(contacts),
+    );
+
+    let rsp = client.transact(&create, CLTRID).await.unwrap();
+    assert_eq!(rsp.result.code, ResultCode::CommandCompletedSuccessfully);
+}
+
+#[tokio::test]
+async fn logout() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/logout.xml",
+                "response/logout.xml",
+            ])
+            .build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    assert_eq!(client.session_state(), &SessionState::Greeted);
+
+    let rsp = client.transact(&Logout, CLTRID).await.unwrap();
+    assert_eq!(
+        rsp.result.code,
+        ResultCode::CommandCompletedSuccessfullyEndingSession
+    );
+    assert_eq!(client.session_state(), &SessionState::Ended);
+
+    // The session is over, so further commands are refused without touching the stream
+    let err = client.transact(&Logout, CLTRID).await.unwrap_err();
+    assert!(matches!(err, Error::Closed(None)));
+
+    client.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+async fn shutdown_gracefully() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/poll/poll.xml",
+                "response/poll/poll_message_only.xml",
+                "request/poll/ack_shutdown.xml",
+                "response/poll/ack_shutdown.xml",
+                "request/logout.xml",
+                "response/logout.xml",
+            ])
+            .build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+
+    let rsp = client.transact(&Poll, CLTRID).await.unwrap();
+    assert_eq!(
+        rsp.result.code,
+        ResultCode::CommandCompletedSuccessfullyAckToDequeue
+    );
+
+    let deadline = Instant::now() + Duration::from_secs(5);
+    let report = client
+        .shutdown_gracefully(deadline, CLTRID, |id| {
+            assert_eq!(id, "12346");
+            async { true }
+        })
+        .await
+        .unwrap();
+    assert!(!report.abandoned_in_flight);
+    assert!(report.logged_out);
+    assert_eq!(report.unacked_message, None);
+}
+
+#[tokio::test]
+async fn shutdown_deadline() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/poll/poll.xml",
+                "response/poll/poll_message_only.xml",
+            ])
+            .build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+    client.transact(&Poll, CLTRID).await.unwrap();
+
+    // The message is never handled, so neither the acknowledgement nor the logout is sent
+    let deadline = Instant::now() + Duration::from_millis(50);
+    let report = client
+        .shutdown_gracefully(deadline, CLTRID, |_| std::future::pending())
+        .await
+        .unwrap();
+    assert!(!report.abandoned_in_flight);
+    assert!(!report.logged_out);
+    assert_eq!(report.unacked_message.as_deref(), Some("12346"));
+    assert!(Instant::now() < deadline + Duration::from_secs(1));
+}
+
+#[tokio::test]
+async fn server_closed() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            let mut builder = Builder::new();
+
+            let buf = xml("response/greeting.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            // The server pushes its parting frame and hangs up before we get to write
+            builder.write_error(io::Error::new(io::ErrorKind::BrokenPipe, "broken pipe"));
+
+            let buf = xml("response/closing.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            Ok(builder.build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    let err = client.transact(&Logout, CLTRID).await.unwrap_err();
+    let Error::Command(status) = err else {
+        panic!("expected command error, got {err:?}");
+    };
+    assert_eq!(
+        status.result.code,
+        ResultCode::CommandFailedServerClosingConnection
+    );
+
+    let SessionState::Closed(Some(result)) = client.session_state() else {
+        panic!("unexpected session state {:?}", client.session_state());
+    };
+    assert_eq!(
+        result.code,
+        ResultCode::CommandFailedServerClosingConnection
+    );
+
+    let err = client.transact(&Logout, CLTRID).await.unwrap_err();
+    assert!(matches!(err, Error::Closed(Some(_))));
+}
+
+#[tokio::test]
+async fn lost_response() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector {
+        connects: AtomicUsize,
+        // The creation date of the domain, as found after reconnecting
+        created: String,
+    }
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            let mut builder = Builder::new();
+
+            let buf = xml("response/greeting.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            if self.connects.fetch_add(1, Ordering::SeqCst) == 0 {
+                let buf = xml("request/login.xml");
+                builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+                let buf = xml("response/login.xml");
+                builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+                // The connection breaks after the create was written
+                let buf = xml("request/domain/create.xml");
+                builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+                builder.read_error(io::Error::new(io::ErrorKind::ConnectionReset, "reset"));
+                return Ok(builder.build());
+            }
+
+            let buf = xml("request/login.xml")
+                .replace("<clID>username</clID>", "<clID>eppdev</clID>")
+                .replace("<newPW>new-password</newPW>", "")
+                .replace(CLTRID, &format!("{CLTRID}:login"));
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+
+            let buf = xml("response/login.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            let buf = xml("request/domain/info.xml")
+                .replace("eppdev.com", "eppdev-1.com")
+                .replace("<authInfo><pw>2fooBAR</pw></authInfo>", "")
+                .replace(CLTRID, &format!("{CLTRID}:info"));
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+
+            let buf =
+                xml("response/domain/info.xml").replace("2021-07-23T15:31:20.0Z", &self.created);
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            Ok(builder.build())
+        }
+    }
+
+    let contacts = &[
+        DomainContact {
+            contact_type: "admin".into(),
+            id: "eppdev-contact-3".into(),
+        },
+        DomainContact {
+            contact_type: "tech".into(),
+            id: "eppdev-contact-3".into(),
+        },
+        DomainContact {
+            contact_type: "billing".into(),
+            id: "eppdev-contact-3".into(),
+        },
+    ];
+
+    let create = DomainCreate::new(
+        "eppdev-1.com",
+        Period::Years(PeriodLength::new(1).unwrap()),
+        None,
+        Some("eppdev-contact-3"),
+        "epP4uthd#v",
+        Some(contacts),
+    );
+
+    // A domain we sponsor was created by the command only if it is newer than the command
+    let just_created = (Utc::now() + chrono::Duration::minutes(1)).to_rfc3339();
+    for (created, verified) in [
+        (just_created.as_str(), Verified::Applied),
+        ("2021-07-23T15:31:20.0Z", Verified::NotApplied),
+    ] {
+        let connector = FakeConnector {
+            connects: AtomicUsize::new(0),
+            created: created.to_owned(),
+        };
+        let mut client = EppClient::new(connector, "test".into(), Duration::from_secs(5))
+            .await
+            .unwrap();
+        login(&mut client).await;
+
+        // The password is only looked up when logging in again
+        client.set_lost_response_check(Some(LostResponseCheck::new("eppdev", |client, id| {
+            Box::pin(async move {
+                let ext_uris = ["http://schema.ispapi.net/epp/xml/keyvalue-1.0"];
+                let login = Login::new("eppdev", "password", None, Some(&ext_uris));
+                client.transact(&login, id).await?;
+                Ok(())
+            })
+        })));
+
+        let Error::Other(err) = client.transact(&create, CLTRID).await.unwrap_err() else {
+            panic!("expected lost response error");
+        };
+        let lost = err.downcast_ref::<LostResponse>().unwrap();
+        assert_eq!(lost.command, "create");
+        assert_eq!(lost.object, "eppdev-1.com");
+        assert_eq!(lost.verified, verified);
+        assert!(matches!(lost.cause, Error::Io(_)));
+        assert_eq!(client.session_state(), &SessionState::LoggedIn);
+    }
+}
+
+#[tokio::test]
+async fn retain_raw() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/domain/check.xml",
+                "response/domain/check.xml",
+                "request/domain/check.xml",
+                "response/domain/check.xml",
+                "request/domain/check.xml",
+                "response/error.xml",
+            ])
+            .build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+
+    let check = DomainCheck {
+        domains: &["eppdev.com", "eppdev.net"],
+    };
+
+    let rsp = client.transact(&check, CLTRID).await.unwrap();
+    assert_eq!(rsp.raw_xml(), None);
+
+    let rsp = client.transact_retaining_raw(&check, CLTRID).await.unwrap();
+    assert_eq!(
+        rsp.raw_xml(),
+        Some(xml("response/domain/check.xml").as_str())
+    );
+
+    let err = client
+        .transact_retaining_raw(&check, CLTRID)
+        .await
+        .unwrap_err();
+    let Error::Command(status) = err else {
+        panic!("expected a command error, got {err:?}");
+    };
+    assert_eq!(status.raw_xml(), Some(xml("response/error.xml").as_str()));
+}
+
+#[tokio::test]
+async fn lenient_scalars() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/domain/check.xml",
+                "response/domain/check_lenient.xml",
+                "request/domain/check.xml",
+                "response/domain/check_lenient.xml",
+            ])
+            .build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+
+    let check = DomainCheck {
+        domains: &["eppdev.com", "eppdev.net"],
+    };
+
+    // Parsed strictly by default
+    assert!(matches!(
+        client.transact(&check, CLTRID).await,
+        Err(Error::Xml(_))
+    ));
+
+    client.set_quirks(Some(Arc::new(RegistryQuirks {
+        parsing: ParseOptions {
+            lenient_booleans: true,
+            lenient_numbers: true,
+        },
+        ..RegistryQuirks::default()
+    })));
+    let rsp = client.transact(&check, CLTRID).await.unwrap();
+    assert_eq!(rsp.result.code, ResultCode::CommandCompletedSuccessfully);
+    let data = rsp.res_data().unwrap();
+    assert!(data.list[0].inner.available);
+    assert!(!data.list[1].inner.available);
+    assert_eq!(
+        rsp.scalar_warnings(),
+        [
+            ScalarWarning {
+                field: "avail",
+                original: "TRUE".into(),
+                normalized: "true".into(),
+            },
+            ScalarWarning {
+                field: "avail",
+                original: "N".into(),
+                normalized: "false".into(),
+            },
+            ScalarWarning {
+                field: "EppResult::code",
+                original: " 1000".into(),
+                normalized: "1000".into(),
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn account_balance() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/domain/delete.xml",
+                "response/extensions/fee07_delete.xml",
+            ])
+            .build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+    let tracker = Arc::new(AccountBalance::new());
+    client.add_middleware(tracker.clone());
+
+    let delete = DomainDelete::new("eppdev.com");
+    client
+        .transact((&delete, &fee07::DeleteExtension), CLTRID)
+        .await
+        .unwrap();
+    assert_eq!(
+        tracker.current(),
+        Some(Balance {
+            balance: 1005.0,
+            credit_limit: None,
+            currency: Some("USD".into()),
+        })
+    );
+}
+
+#[tokio::test]
+async fn raw_transaction() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/domain/check.xml",
+                "response/error.xml",
+            ])
+            .build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+
+    let rsp = client
+        .transact_raw(&xml("request/domain/check.xml"))
+        .await
+        .unwrap();
+    assert_eq!(rsp.xml, xml("response/error.xml"));
+    assert_eq!(rsp.code(), ResultCode::ObjectDoesNotExist);
+    assert_eq!(rsp.meta().client_tr_id, Some(CLTRID));
+}
+
+#[tokio::test]
+async fn raw_safeguards() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/domain/check.xml",
+                "response/domain/check.xml",
+                "request/host/delete.xml",
+                "response/host/delete.xml",
+            ])
+            .build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+    client.add_middleware(Arc::new(Interlock::new().protect("eppdev.com")));
+
+    // Queries can't do harm
+    let rsp = client
+        .transact_raw(&xml("request/domain/check.xml"))
+        .await
+        .unwrap();
+    assert_eq!(rsp.code(), ResultCode::CommandCompletedSuccessfully);
+
+    // Transforms would bypass the interlock
+    let delete = xml("request/host/delete.xml");
+    let err = client.transact_raw(&delete).await.unwrap_err();
+    let Error::Other(err) = err else {
+        panic!("unexpected error: {err}");
+    };
+    let err = err.downcast_ref::<UncheckedCommand>().unwrap();
+    assert_eq!(err.command, Some("delete"));
+    assert!(client.transact_xml("<epp/>").await.is_err());
+
+    client.set_unchecked_raw(true);
+    let rsp = client.transact_xml(&delete).await.unwrap();
+    assert_eq!(rsp, xml("response/host/delete.xml"));
+}
+
+#[tokio::test]
+async fn raw_session_state() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/logout.xml",
+                "response/logout.xml",
+            ])
+            .build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    // The state follows raw commands like typed ones
+    let rsp = client
+        .transact_raw(&xml("request/login.xml"))
+        .await
+        .unwrap();
+    assert_eq!(rsp.code(), ResultCode::CommandCompletedSuccessfully);
+    assert_eq!(client.session_state(), &SessionState::LoggedIn);
+
+    let err = client
+        .transact_raw(&xml("request/login.xml"))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "error: cannot send login in session state LoggedIn"
+    );
+
+    let rsp = client
+        .transact_raw(&xml("request/logout.xml"))
+        .await
+        .unwrap();
+    assert_eq!(
+        rsp.code(),
+        ResultCode::CommandCompletedSuccessfullyEndingSession
+    );
+    assert_eq!(client.session_state(), &SessionState::Ended);
+
+    let err = client
+        .transact_raw(&xml("request/logout.xml"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, Error::Closed(None)));
+}
+
+#[tokio::test]
+async fn middleware() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/domain/check.xml",
+                "response/domain/check.xml",
+            ])
+            .build())
+        }
+    }
+
+    #[derive(Default)]
+    struct Policy {
+        checked: Mutex<Vec<String>>,
+        errors: Mutex<Vec<String>>,
+    }
+
+    impl Middleware for Policy {
+        fn request(&self, request: &mut Request<'_>) -> Result<(), Error> {
+            match request.command {
+                CommandView::DomainDelete(cmd) if cmd.name().ends_with("eppdev.com") => {
+                    Err(Error::Other("protected domain".into()))
+                }
+                _ => Ok(()),
+            }
+        }
+
+        fn response(&self, _: &Request<'_>, response: Result<&dyn Any, &Error>) {
+            let rsp = match response {
+                Ok(rsp) => rsp.downcast_ref::<Response<CheckData, NoExtension>>(),
+                Err(err) => {
+                    self.errors.lock().unwrap().push(err.to_string());
+                    return;
+                }
+            };
+            let Some(rsp) = rsp else {
+                return;
+            };
+
+            let mut checked = self.checked.lock().unwrap();
+            for domain in &rsp.res_data().unwrap().list {
+                checked.push(domain.inner.id.clone());
+            }
+        }
+    }
+
+    struct Lowercase;
+
+    impl Middleware for Lowercase {
+        fn request(&self, request: &mut Request<'_>) -> Result<(), Error> {
+            let cmd = match request.command {
+                CommandView::DomainCheck(cmd) => cmd,
+                CommandView::DomainDelete(_) => return Err(Error::Other("locked domain".into())),
+                _ => return Ok(()),
+            };
+
+            let names = cmd
+                .domains
+                .iter()
+                .map(|name| name.to_lowercase())
+                .collect::<Vec<_>>();
+            let names = names.iter().map(String::as_str).collect::<Vec<_>>();
+            request.replace(&DomainCheck { domains: &names })?;
+
+            // Another kind of command can't take the place of a domain check
+            assert!(request
+                .replace(&HostCheck {
+                    hosts: &["ns1.eppdev.com"]
+                })
+                .is_err());
+            Ok(())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+
+    let policy = Arc::new(Policy::default());
+    client.add_middleware(policy.clone());
+    client.add_middleware(Arc::new(Lowercase));
+
+    let check = DomainCheck {
+        domains: &["EPPDEV.com", "eppdev.NET"],
+    };
+    client.transact(&check, CLTRID).await.unwrap();
+    assert_eq!(
+        *policy.checked.lock().unwrap(),
+        ["eppdev.com", "eppdev.net"]
+    );
+
+    // Rejected before anything is written to the (exhausted) stream
+    let err = client
+        .transact(&DomainDelete::new("www.eppdev.com"), CLTRID)
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "error: protected domain");
+
+    // Middleware that already saw a rejected command sees the rejection
+    let err = client
+        .transact(&DomainDelete::new("eppdev.net"), CLTRID)
+        .await
+        .unwrap_err();
+    assert_eq!(err.to_string(), "error: locked domain");
+    assert_eq!(*policy.errors.lock().unwrap(), ["error: locked domain"]);
+}
+
+#[tokio::test]
+async fn frame_pipeline() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/domain/check.xml",
+                "response/domain/check.xml",
+                "request/host/delete.xml",
+                "response/host/delete.xml",
+            ])
+            .build())
+        }
+    }
+
+    #[derive(Default)]
+    struct Frames(Mutex<Vec<&'static str>>);
+
+    impl Middleware for Frames {
+        fn response(&self, request: &Request<'_>, response: Result<&dyn Any, &Error>) {
+            if let Ok(Some(_)) = response.map(|rsp| rsp.downcast_ref::<RawResponse>()) {
+                self.0.lock().unwrap().push(request.name);
+            }
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+    let frames = Arc::new(Frames::default());
+    client.add_middleware(frames.clone());
+    client.set_cltrid_cache(Some(ClTridCache::new(16)));
+
+    let check = DomainCheck {
+        domains: &["eppdev.com", "eppdev.net"],
+    };
+    let frame = client.transact_frame(&check, CLTRID).await.unwrap();
+    let rsp = frame.parse::<CheckDataRef<'_>, NoExtension>().unwrap();
+    assert_eq!(
+        rsp.res_data()
+            .unwrap()
+            .available_names()
+            .collect::<Vec<_>>(),
+        ["eppdev.com"]
+    );
+
+    // Transforms sent as frames are cached like typed ones
+    let delete = HostDelete::new("ns1.eppdev-1.com");
+    for _ in 0..2 {
+        let frame = client.transact_frame(&delete, CLTRID).await.unwrap();
+        assert_eq!(frame.code(), ResultCode::CommandCompletedSuccessfully);
+    }
+    assert!(client
+        .completed::<HostDelete, NoExtension>(CLTRID)
+        .is_some_and(|rsp| rsp.is_ok()));
+    assert_eq!(*frames.0.lock().unwrap(), ["check", "delete", "delete"]);
+}
+
+#[tokio::test]
+async fn cltrid_cache() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/host/delete.xml",
+                "response/host/delete.xml",
+            ])
+            .build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+    client.set_cltrid_cache(Some(ClTridCache::new(16)));
+
+    let delete = HostDelete::new("ns1.eppdev-1.com");
+    let rsp = client.transact(&delete, CLTRID).await.unwrap();
+    assert_eq!(rsp.result.code, ResultCode::CommandCompletedSuccessfully);
+
+    // The retry is answered from the cache instead of being written to the stream
+    let rsp = client.transact(&delete, CLTRID).await.unwrap();
+    assert_eq!(rsp.result.code, ResultCode::CommandCompletedSuccessfully);
+    assert_eq!(rsp.tr_ids.server_tr_id, SVTRID);
+
+    // A different command can't be answered with the cached response
+    let create = HostCreate::new("ns1.eppdev-1.com", None);
+    let err = client.transact(&create, CLTRID).await.unwrap_err();
+    let Error::Other(err) = err else {
+        panic!("unexpected error: {err}");
+    };
+    let conflict = err.downcast_ref::<ClTridConflict>().unwrap();
+    assert_eq!((conflict.command, conflict.previous), ("create", "delete"));
+    assert!(client
+        .completed::<HostCreate, NoExtension>(CLTRID)
+        .is_some_and(|rsp| rsp.is_err()));
+}
+
+#[tokio::test]
+async fn cltrid_cache_unsent() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/domain/transfer_query.xml",
+                "response/domain/transfer_query.xml",
+                "request/domain/transfer_query.xml",
+                "response/domain/transfer_query.xml",
+                "request/logout.xml",
+                "response/logout.xml",
+            ])
+            .build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+    client.set_cltrid_cache(Some(ClTridCache::new(16)));
+
+    // Transfer queries don't change anything, so both are sent
+    let query = DomainTransfer::query("testing.com", "epP4uthd#v");
+    for _ in 0..2 {
+        client.transact(&query, CLTRID).await.unwrap();
+    }
+    assert_eq!(
+        client.transaction_state(CLTRID).unwrap(),
+        TransactionState::Unknown
+    );
+
+    // A transform that never reached the connection doesn't use up its clTRID
+    client.transact(&Logout, CLTRID).await.unwrap();
+    let delete = HostDelete::new("ns1.eppdev-1.com");
+    let err = client.transact(&delete, "cltrid:unsent").await.unwrap_err();
+    assert!(matches!(err, Error::Closed(_)));
+    assert_eq!(
+        client.transaction_state("cltrid:unsent").unwrap(),
+        TransactionState::Unknown
+    );
+}
+
+#[tokio::test]
+async fn journal() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/host/create.xml",
+                "response/host/create_bad_date.xml",
+                "request/logout.xml",
+                "response/logout.xml",
+            ])
+            .build())
+        }
+    }
+
+    let path =
+        std::env::temp_dir().join(format!("instant-epp-basic-{}.journal", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let journal = Arc::new(FileJournal::open(&path).unwrap());
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+    client.set_journal(Some(journal.clone()));
+
+    // The registry answered, so the create is not in doubt even if its response can't be parsed
+    let addresses = &[
+        IpAddr::from([29, 245, 122, 14]),
+        IpAddr::from([0x2404, 0x6800, 0x4001, 0x801, 0, 0, 0, 0x200e]),
+    ];
+    let create = HostCreate::new("host1.eppdev-1.com", Some(addresses));
+    assert!(client.transact(&create, CLTRID).await.is_err());
+    assert_eq!(journal.in_doubt().unwrap(), []);
+
+    // Nor is a transform that was never written to the connection
+    client.transact(&Logout, CLTRID).await.unwrap();
+    let delete = HostDelete::new("ns1.eppdev-1.com");
+    assert!(client.transact(&delete, "cltrid:unsent").await.is_err());
+    assert_eq!(journal.in_doubt().unwrap(), []);
+
+    // Entries left over from an earlier process are resolved once they have been checked
+    let entry = JournalEntry {
+        registry: "test".into(),
+        command: "delete".into(),
+        object: Some("ns1.eppdev-1.com".into()),
+        client_tr_id: "cltrid:crashed".into(),
+    };
+    journal.begin(&entry).unwrap();
+    assert_eq!(journal.in_doubt().unwrap(), [entry]);
+    client.resolve_transaction("cltrid:crashed").unwrap();
+    assert_eq!(journal.in_doubt().unwrap(), []);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn submit() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/host/delete.xml",
+                "response/host/delete.xml",
+                "request/hello.xml",
+                "response/greeting.xml",
+            ])
+            .build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+    client.set_cltrid_cache(Some(ClTridCache::new(16)));
+
+    // The command is written right away, and the handle can be awaited elsewhere
+    let delete = HostDelete::new("ns1.eppdev-1.com");
+    let pending = client.submit(&delete, CLTRID).await.unwrap();
+    assert_eq!(pending.id(), CLTRID);
+    assert_eq!(
+        client.transaction_state(CLTRID).unwrap(),
+        TransactionState::InDoubt
+    );
+    let waiter = tokio::spawn(pending);
+
+    // Its response is read before the next command is sent
+    client.hello().await.unwrap();
+    let rsp = waiter.await.unwrap().unwrap();
+    assert_eq!(rsp.result.code, ResultCode::CommandCompletedSuccessfully);
+
+    // Submitting the clTRID again answers from the cache without sending anything
+    let rsp = client.submit(&delete, CLTRID).await.unwrap().await.unwrap();
+    assert_eq!(rsp.tr_ids.server_tr_id, SVTRID);
+
+    // The response can be looked up again by its clTRID
+    assert_eq!(
+        client.transaction_state(CLTRID).unwrap(),
+        TransactionState::Completed
+    );
+    let rsp = client
+        .completed::<HostDelete, NoExtension>(CLTRID)
+        .unwrap()
+        .unwrap();
+    assert_eq!(rsp.tr_ids.server_tr_id, SVTRID);
+}
+
+#[tokio::test]
+async fn batch() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            let mut builder = Builder::new();
+
+            let buf = xml("response/greeting.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            let buf = xml("request/login.xml");
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+            let buf = xml("response/login.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            for (seq, response) in ["response/host/delete.xml", "response/error.xml"]
+                .into_iter()
+                .enumerate()
+            {
+                let buf = xml("request/host/delete.xml")
+                    .replace(CLTRID, &format!("{CLTRID}:{}", seq + 1));
+                builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+
+                let buf = xml(response);
+                builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+            }
+
+            Ok(builder.build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+
+    let delete = HostDelete::new("ns1.eppdev-1.com");
+    let results = client.transact_batch([&delete, &delete], CLTRID).await;
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+
+    // The failure of one command is reported in its place
+    let Err(Error::Command(status)) = &results[1] else {
+        panic!("unexpected result {:?}", results[1]);
+    };
+    assert_eq!(status.result.code, ResultCode::ObjectDoesNotExist);
+}
+
+#[tokio::test]
+async fn poll_store() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            let mut builder = Builder::new();
+
+            let buf = xml("response/greeting.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            let buf = xml("request/login.xml");
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+            let buf = xml("response/login.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            // The last poll is sent by a new call, starting the sequence over
+            let exchanges = [
+                (
+                    1,
+                    "request/poll/poll.xml",
+                    "response/poll/poll_domain_transfer.xml",
+                ),
+                (2, "request/poll/ack.xml", "response/poll/ack.xml"),
+                (
+                    1,
+                    "request/poll/poll.xml",
+                    "response/poll/poll_empty_queue.xml",
+                ),
+            ];
+            for (seq, request, response) in exchanges {
+                let buf = xml(request).replace(CLTRID, &format!("{CLTRID}:{seq}"));
+                builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+
+                let buf = xml(response);
+                builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+            }
+
+            Ok(builder.build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+
+    let store = MemoryPollStore::default();
+    let message = client.next_message(&store, CLTRID).await.unwrap().unwrap();
+    assert_eq!(message.id, "12345");
+    assert!(message.response().unwrap().res_data().is_some());
+
+    // Until it is marked processed, the message is handed out again without polling
+    let again = client.next_message(&store, CLTRID).await.unwrap();
+    assert_eq!(again.as_ref(), Some(&message));
+
+    store.processed(&message.id).unwrap();
+    assert_eq!(client.next_message(&store, CLTRID).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn linked_host_delete() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            let mut builder = Builder::new();
+
+            let buf = xml("response/greeting.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            let buf = xml("request/login.xml");
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+            let buf = xml("response/login.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            for _ in 0..2 {
+                let buf = xml("request/host/info.xml").replace(CLTRID, &format!("{CLTRID}:info"));
+                builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+
+                let buf = xml("response/host/info_linked.xml");
+                builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+            }
+
+            let buf = xml("request/host/delete.xml");
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+
+            let buf = xml("response/host/delete.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            Ok(builder.build())
+        }
+    }
+
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
+
+    let outcome = registrar::delete_host(
+        &mut client,
+        "ns1.eppdev-1.com",
+        LinkedPolicy::Refuse,
+        CLTRID,
+    )
+    .await
+    .unwrap();
+    assert_eq!(outcome, GuardedDelete::Refused);
+
+    let outcome =
+        registrar::delete_host(&mut client, "ns1.eppdev-1.com", LinkedPolicy::Warn, CLTRID)
+            .await
+            .unwrap();
+    assert_eq!(outcome, GuardedDelete::Deleted { linked: true });
+}
+
+#[tokio::test]
+async fn ensure_host() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            let mut builder = Builder::new();
+
+            let buf = xml("response/greeting.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            let buf = xml("request/login.xml");
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+            let buf = xml("response/login.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            // An available host is created
+            let buf = xml("request/host/check.xml")
+                .replace("<name>ns1.eppdev-1.com</name>", "")
+                .replace(CLTRID, &format!("{CLTRID}:check"));
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+
+            let buf = xml("response/host/check.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            let buf = xml("request/host/create.xml");
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
 
-impl Write for TestWriter {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        print!(
-            "{}",
-            str::from_utf8(buf).expect("tried to log invalid UTF-8")
-        );
-        Ok(buf.len())
-    }
-    fn flush(&mut self) -> io::Result<()> {
-        io::stdout().flush()
-    }
-}
+            let buf = xml("response/host/create.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
 
-fn log_to_stdout() -> tracing::subscriber::DefaultGuard {
-    let sub = tracing_subscriber::FmtSubscriber::builder()
-        .with_max_level(tracing::Level::TRACE)
-        .with_writer(|| TestWriter)
-        .finish();
-    tracing::subscriber::set_default(sub)
-}
+            // An existing host with the same addresses is left alone
+            let buf = xml("request/host/check.xml")
+                .replace("<name>ns1.eppdev-1.com</name>", "")
+                .replace("host1", "host2")
+                .replace(CLTRID, &format!("{CLTRID}:check"));
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
 
-fn len_bytes(bytes: &str) -> [u8; 4] {
-    ((bytes.len() as u32) + 4).to_be_bytes()
-}
+            let buf =
+                xml("response/host/check.xml").replace(r#"avail="1">host1"#, r#"avail="0">host2"#);
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
 
-fn xml(path: &str) -> String {
-    let ws_regex = Regex::new(r"[\s]{2,}").unwrap();
-    let end_regex = Regex::new(r"\?>").unwrap();
+            let buf = xml("request/host/info.xml")
+                .replace("ns1", "host2")
+                .replace(CLTRID, &format!("{CLTRID}:info"));
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
 
-    let mut f = File::open(format!("tests/resources/{path}")).unwrap();
-    let mut buf = String::new();
-    f.read_to_string(&mut buf).unwrap();
+            let buf = xml("response/host/info.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
 
-    if !buf.is_empty() {
-        let mat = end_regex.find(buf.as_str()).unwrap();
-        let start = mat.end();
-        buf = format!(
-            "{}\r\n{}",
-            &buf[..start],
-            ws_regex.replace_all(&buf[start..], "")
-        );
+            Ok(builder.build())
+        }
     }
 
-    buf
-}
+    let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+    login(&mut client).await;
 
-fn build_stream(units: &[&str]) -> Builder {
-    let mut builder = Builder::new();
-    for (i, path) in units.iter().enumerate() {
-        let buf = xml(path);
-        match i % 2 {
-            0 => builder.read(&len_bytes(&buf)).read(buf.as_bytes()),
-            1 => builder.write(&len_bytes(&buf)).write(buf.as_bytes()),
-            _ => unreachable!(),
-        };
-    }
+    let addresses = [
+        "29.245.122.14".parse().unwrap(),
+        "2404:6800:4001:801::200e".parse().unwrap(),
+    ];
 
-    builder
+    let ensured = registrar::ensure_host(&mut client, "host1.eppdev-1.com", &addresses, CLTRID)
+        .await
+        .unwrap();
+    assert_eq!(ensured.id, "host1.eppdev-1.com");
+    assert_eq!(ensured.action, registrar::EnsureAction::Created);
+
+    let ensured = registrar::ensure_host(&mut client, "host2.eppdev-1.com", &addresses, CLTRID)
+        .await
+        .unwrap();
+    assert_eq!(ensured.action, registrar::EnsureAction::Unchanged);
 }
 
 #[tokio::test]
-async fn client() {
+async fn rgp_domain_delete() {
     let _guard = log_to_stdout();
 
     struct FakeConnector;
@@ -91,54 +1614,62 @@ async fn client() {
         type Connection = tokio_test::io::Mock;
 
         async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
-            Ok(build_stream(&[
-                "response/greeting.xml",
-                "request/login.xml",
-                "response/login.xml",
-                "request/domain/check.xml",
-                "response/domain/check.xml",
-            ])
-            .build())
+            let mut builder = Builder::new();
+
+            let buf = xml("response/greeting.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            let buf = xml("request/login.xml");
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+            let buf = xml("response/login.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            let buf = xml("request/domain/info.xml")
+                .replace("<authInfo><pw>2fooBAR</pw></authInfo>", "")
+                .replace(CLTRID, &format!("{CLTRID}:info"));
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+
+            let buf = xml("response/extensions/domain_info_rgp.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            let buf = xml("request/domain/delete.xml");
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+
+            let buf = xml("response/domain/delete.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
+
+            Ok(builder.build())
         }
     }
 
     let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
         .await
         .unwrap();
+    login(&mut client).await;
 
-    assert_eq!(client.xml_greeting(), xml("response/greeting.xml"));
-    let rsp = client
-        .transact(
-            &Login::new(
-                "username",
-                "password",
-                Some("new-password"),
-                Some(&["http://schema.ispapi.net/epp/xml/keyvalue-1.0"]),
-            ),
-            CLTRID,
-        )
-        .await
-        .unwrap();
-
-    assert_eq!(rsp.result.code, ResultCode::CommandCompletedSuccessfully);
+    let deletion =
+        registrar::delete_domain(&mut client, "eppdev.com", &GracePeriods::default(), CLTRID)
+            .await
+            .unwrap();
 
-    let rsp = client
-        .transact(
-            &DomainCheck {
-                domains: &["eppdev.com", "eppdev.net"],
+    assert!(!deletion.pending);
+    assert_eq!(
+        deletion.credits,
+        [
+            GraceCredit {
+                period: RgpStatus::AddPeriod,
+                until: Some(Utc.with_ymd_and_hms(2021, 7, 28, 15, 31, 20).unwrap()),
             },
-            CLTRID,
-        )
-        .await
-        .unwrap();
-    assert_eq!(rsp.result.code, ResultCode::CommandCompletedSuccessfully);
-
-    let result = rsp.res_data().unwrap();
-    assert_eq!(result.list[0].inner.id, "eppdev.com");
+            GraceCredit {
+                period: RgpStatus::RenewPeriod,
+                until: None,
+            },
+        ]
+    );
 }
 
 #[tokio::test]
-async fn dropped() {
+async fn rgp_domain_restore() {
     let _guard = log_to_stdout();
 
     struct FakeConnector;
@@ -155,23 +1686,28 @@ async fn dropped() {
 
             let buf = xml("request/login.xml");
             builder.write(&len_bytes(&buf)).write(buf.as_bytes());
-
             let buf = xml("response/login.xml");
             builder.read(&len_bytes(&buf)).read(buf.as_bytes());
 
-            let buf = xml("request/domain/check.xml");
+            // Without a report phase, the restore request is all that is sent
+            let buf = xml("request/extensions/rgp_restore_request.xml");
             builder.write(&len_bytes(&buf)).write(buf.as_bytes());
 
-            // We add a wait here. We're going to timeout below as a way of dropping the future.
-            builder.wait(Duration::from_millis(100));
+            let buf = xml("response/extensions/rgp_restore.xml");
+            builder.read(&len_bytes(&buf)).read(buf.as_bytes());
 
-            let buf = xml("response/domain/check.xml");
+            // With a report phase, the report follows the request
+            let buf = xml("request/extensions/rgp_restore_request.xml");
+            builder.write(&len_bytes(&buf)).write(buf.as_bytes());
+
+            let buf = xml("response/extensions/rgp_restore.xml");
             builder.read(&len_bytes(&buf)).read(buf.as_bytes());
 
-            let buf = xml("request/domain/create.xml");
+            let buf = xml("request/extensions/rgp_restore_report.xml")
+                .replace(CLTRID, &format!("{CLTRID}:report"));
             builder.write(&len_bytes(&buf)).write(buf.as_bytes());
 
-            let buf = xml("response/domain/create.xml");
+            let buf = xml("response/domain/update.xml");
             builder.read(&len_bytes(&buf)).read(buf.as_bytes());
 
             Ok(builder.build())
@@ -181,68 +1717,104 @@ async fn dropped() {
     let mut client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
         .await
         .unwrap();
+    login(&mut client).await;
 
-    assert_eq!(client.xml_greeting(), xml("response/greeting.xml"));
-    let rsp = client
-        .transact(
-            &Login::new(
-                "username",
-                "password",
-                Some("new-password"),
-                Some(&["http://schema.ispapi.net/epp/xml/keyvalue-1.0"]),
-            ),
-            CLTRID,
-        )
-        .await
-        .unwrap();
+    let state = registrar::restore_domain(
+        &mut client,
+        "eppdev.com",
+        None,
+        RestoreReportPolicy::NotRequired,
+        CLTRID,
+    )
+    .await
+    .unwrap();
+    assert_eq!(state, RestoreState::Restored);
 
-    assert_eq!(rsp.result.code, ResultCode::CommandCompletedSuccessfully);
+    let statements = &[
+        "This registrar has not restored the Registered Name in order to assume the rights to use or sell the Registered Name for itself or for any third party.",
+        "The information in this report is true to best of this registrar's knowledge, and this registrar acknowledges that intentionally supplying false information in this report shall constitute an incurable material breach of the Registry-Registrar Agreement.",
+    ];
+    let report = RgpRestoreReport::new(
+        "Pre-delete registration data goes here. Both XML and free text are allowed.",
+        "Post-restore registration data goes here. Both XML and free text are allowed.",
+        Utc.with_ymd_and_hms(2021, 7, 10, 22, 0, 0).unwrap(),
+        Utc.with_ymd_and_hms(2021, 7, 20, 22, 0, 0).unwrap(),
+        "Registrant error.",
+        statements,
+        "Supporting information goes here.",
+    );
 
-    // Here, we add a 10ms timeout on the entire transaction. The mock stream
-    // specifies that the caller will have to wait for 100ms after sending
-    // the request before the response is returned. When `timeout()` returns
-    // `Err(Elapsed)`, the `RequestFuture` inside the `Timeout` future is dropped,
-    // leaving a half-finished request in the `EppConnection`.
-    timeout(
-        Duration::from_millis(10),
-        client.transact(
-            &DomainCheck {
-                domains: &["eppdev.com", "eppdev.net"],
-            },
-            CLTRID,
-        ),
+    let state = registrar::restore_domain(
+        &mut client,
+        "eppdev.com",
+        Some(report),
+        RestoreReportPolicy::Required,
+        CLTRID,
     )
     .await
-    .unwrap_err();
+    .unwrap();
+    assert_eq!(state, RestoreState::ReportSubmitted);
+}
 
-    let contacts = &[
-        DomainContact {
-            contact_type: "admin".into(),
-            id: "eppdev-contact-3".into(),
-        },
-        DomainContact {
-            contact_type: "tech".into(),
-            id: "eppdev-contact-3".into(),
-        },
-        DomainContact {
-            contact_type: "billing".into(),
-            id: "eppdev-contact-3".into(),
-        },
-    ];
+#[tokio::test]
+async fn dry_run() {
+    let _guard = log_to_stdout();
 
-    // Then, we start another request (of a different type). This should push through the
-    // remainder of the in-flight request before starting the new one, and succeed.
-    let create = DomainCreate::new(
-        "eppdev-1.com",
-        Period::Years(PeriodLength::new(1).unwrap()),
-        None,
-        Some("eppdev-contact-3"),
-        "epP4uthd#v",
-        Some(contacts),
+    struct FakeConnector;
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            // Only the greeting is read; writing anything fails the test
+            let buf = xml("response/greeting.xml");
+            Ok(Builder::new()
+                .read(&len_bytes(&buf))
+                .read(buf.as_bytes())
+                .build())
+        }
+    }
+
+    let client = EppClient::new(FakeConnector, "test".into(), Duration::from_secs(5))
+        .await
+        .unwrap();
+
+    let plan = Plan {
+        name: "eppdev.com".into(),
+        changes: vec![
+            Change::AddStatus(Status::ClientDeleteProhibited),
+            Change::CreateHost {
+                name: "host1.eppdev-1.com".into(),
+                addresses: vec![
+                    "29.245.122.14".parse().unwrap(),
+                    "2404:6800:4001:801::200e".parse().unwrap(),
+                ],
+            },
+        ],
+        ..Plan::default()
+    };
+
+    let rendered = reconcile::dry_run(&client, &plan, CLTRID).unwrap();
+    assert_eq!(rendered.len(), 2);
+    assert_eq!(
+        rendered[0].xml,
+        xml("request/host/create.xml").replace(CLTRID, &format!("{CLTRID}:1"))
     );
+    assert_eq!(rendered[1].id, format!("{CLTRID}:2"));
+    assert_eq!(rendered[1].command, "update");
+    assert_eq!(rendered[1].object.as_deref(), Some("eppdev.com"));
 
-    let rsp = client.transact(&create, CLTRID).await.unwrap();
-    assert_eq!(rsp.result.code, ResultCode::CommandCompletedSuccessfully);
+    let deletes = [
+        DomainDelete::new("eppdev.com"),
+        DomainDelete::new("eppdev.net"),
+    ];
+    let rendered = client.render_batch(&deletes, CLTRID).unwrap();
+    assert_eq!(
+        rendered[0].xml,
+        xml("request/domain/delete.xml").replace(CLTRID, &format!("{CLTRID}:1"))
+    );
+    assert_eq!(rendered[1].object.as_deref(), Some("eppdev.net"));
 }
 
 #[cfg(feature = "transaction-audit")]
@@ -290,6 +1862,8 @@ mod transaction_audit {
             FakeConnector {
                 units: vec![
                     "response/greeting.xml",
+                    "request/login.xml",
+                    "response/login.xml",
                     "request/domain/check.xml",
                     "response/domain/check.xml",
                 ],
@@ -300,6 +1874,7 @@ mod transaction_audit {
         )
         .await
         .unwrap();
+        login(&mut client).await;
 
         let rsp = client
             .transact(
@@ -314,8 +1889,8 @@ mod transaction_audit {
         assert_eq!(rsp.result.code, ResultCode::CommandCompletedSuccessfully);
 
         let events = sink.events();
-        assert_eq!(events.len(), 1);
-        let event = &events[0];
+        assert_eq!(events.len(), 2);
+        let event = &events[1];
         assert_eq!(event.registry, "test");
         assert_eq!(event.command, Some("check"));
         assert!(event.command_type.unwrap().contains("DomainCheck"));
@@ -343,6 +1918,8 @@ mod transaction_audit {
             FakeConnector {
                 units: vec![
                     "response/greeting.xml",
+                    "request/login.xml",
+                    "response/login.xml",
                     "request/domain/check.xml",
                     "response/error.xml",
                 ],
@@ -353,6 +1930,7 @@ mod transaction_audit {
         )
         .await
         .unwrap();
+        login(&mut client).await;
 
         let err = client
             .transact(
@@ -367,8 +1945,8 @@ mod transaction_audit {
         assert!(matches!(err, Error::Command(_)));
 
         let events = sink.events();
-        assert_eq!(events.len(), 1);
-        let event = &events[0];
+        assert_eq!(events.len(), 2);
+        let event = &events[1];
         assert_eq!(event.response_code, Some(2303));
         assert_eq!(event.message.as_deref(), Some("Object does not exist"));
         assert_eq!(event.client_tr_id.as_deref(), Some(CLTRID));
@@ -454,6 +2032,11 @@ mod transaction_audit {
                     .read(&len_bytes(&greeting))
                     .read(greeting.as_bytes());
 
+                let login = xml("request/login.xml");
+                builder.write(&len_bytes(&login)).write(login.as_bytes());
+                let login = xml("response/login.xml");
+                builder.read(&len_bytes(&login)).read(login.as_bytes());
+
                 let request = xml("request/domain/check.xml");
                 builder
                     .write(&len_bytes(&request))
@@ -476,6 +2059,7 @@ mod transaction_audit {
         )
         .await
         .unwrap();
+        login(&mut client).await;
 
         let err = client
             .transact(
@@ -490,8 +2074,8 @@ mod transaction_audit {
         assert!(matches!(err, Error::Xml(_)));
 
         let events = sink.events();
-        assert_eq!(events.len(), 1);
-        let event = &events[0];
+        assert_eq!(events.len(), 2);
+        let event = &events[1];
         assert_eq!(event.response_code, Some(1000));
         assert_eq!(
             event.message.as_deref(),
@@ -528,6 +2112,11 @@ mod transaction_audit {
                     .read(&len_bytes(&greeting))
                     .read(greeting.as_bytes());
 
+                let login = xml("request/login.xml");
+                builder.write(&len_bytes(&login)).write(login.as_bytes());
+                let login = xml("response/login.xml");
+                builder.read(&len_bytes(&login)).read(login.as_bytes());
+
                 let request = xml("request/domain/check.xml");
                 builder
                     .write(&len_bytes(&request))
@@ -547,6 +2136,7 @@ mod transaction_audit {
         )
         .await
         .unwrap();
+        login(&mut client).await;
 
         let err = client
             .transact(
@@ -561,8 +2151,8 @@ mod transaction_audit {
         assert!(matches!(err, Error::Io(_)));
 
         let events = sink.events();
-        assert_eq!(events.len(), 1);
-        let event = &events[0];
+        assert_eq!(events.len(), 2);
+        let event = &events[1];
         assert_eq!(event.response_code, None);
         assert_eq!(event.message, None);
         assert_eq!(event.client_tr_id, None);
@@ -573,3 +2163,59 @@ mod transaction_audit {
         assert_eq!(event.error.as_deref(), Some("I/O error: read timed out"));
     }
 }
+
+#[cfg(feature = "pool")]
+#[tokio::test]
+async fn pool_errors() {
+    let _guard = log_to_stdout();
+
+    struct FakeConnector {
+        failing: bool,
+    }
+
+    #[async_trait]
+    impl Connector for FakeConnector {
+        type Connection = tokio_test::io::Mock;
+
+        async fn connect(&self, _: Duration) -> Result<Self::Connection, Error> {
+            let response = match self.failing {
+                true => "response/closing.xml",
+                false => "response/domain/check.xml",
+            };
+            Ok(build_stream(&[
+                "response/greeting.xml",
+                "request/login.xml",
+                "response/login.xml",
+                "request/domain/check.xml",
+                response,
+            ])
+            .build())
+        }
+    }
+
+    let mut clients = Vec::new();
+    for failing in [true, false] {
+        let connector = FakeConnector { failing };
+        let mut client = EppClient::new(connector, "test".into(), Duration::from_secs(5))
+            .await
+            .unwrap();
+        login(&mut client).await;
+        clients.push(client);
+    }
+
+    let pool = EppPool::new(clients, 1);
+    let check = DomainCheck {
+        domains: &["eppdev.com", "eppdev.net"],
+    };
+
+    // Both sessions are idle and untried, so the first one gets the command
+    pool.transact(&check, CLTRID).await.unwrap_err();
+    let stats = pool.stats();
+    assert_eq!(stats[0].error_rate, 0.125);
+    assert_eq!(stats[1].error_rate, 0.0);
+
+    // The failure keeps the next command away from it
+    let rsp = pool.transact(&check, CLTRID).await.unwrap();
+    assert_eq!(rsp.result.code, ResultCode::CommandCompletedSuccessfully);
+    assert_eq!(pool.stats()[1].error_rate, 0.0);
+}